@@ -30,6 +30,8 @@ pub enum Command {
     QuietMode(Option<bool>),
     OutputFormat(Option<String>),
     Write(String),
+    Prepare(String, String),
+    ExecutePrepared(String),
 }
 
 pub enum OutputFormat {
@@ -95,6 +97,8 @@ impl Command {
                 let results = ctx.write(path).await?;
                 print_options.print_batches(&results, now)
             }
+            Self::Prepare(name, sql) => ctx.prepare(name, sql.clone()).await,
+            Self::ExecutePrepared(name) => print_options.print_prepared(ctx, name, now).await,
         }
     }
 
@@ -111,11 +115,13 @@ impl Command {
             Self::QuietMode(_) => ("\\quiet (true|false)?", "print or set quiet mode"),
             Self::OutputFormat(_) => ("\\pset [NAME [VALUE]]", "set table output option\n(format)"),
             Self::Write(_) => ("\\w path", "line protocol"),
+            Self::Prepare(_, _) => ("\\prepare name sql", "prepare statement by flight sql"),
+            Self::ExecutePrepared(_) => ("\\execute name", "execute prepared statement"),
         }
     }
 }
 
-const ALL_COMMANDS: [Command; 11] = [
+const ALL_COMMANDS: [Command; 13] = [
     Command::ConnectDatabase(String::new()),
     Command::ListTables,
     Command::DescribeTable(String::new()),
@@ -127,6 +133,8 @@ const ALL_COMMANDS: [Command; 11] = [
     Command::QuietMode(None),
     Command::OutputFormat(None),
     Command::Write(String::new()),
+    Command::Prepare(String::new(), String::new()),
+    Command::ExecutePrepared(String::new()),
 ];
 
 fn all_commands_info() -> ResultSet {
@@ -174,6 +182,11 @@ impl FromStr for Command {
             ("pset", None) => Self::OutputFormat(None),
             ("w", Some(path)) => Self::Write(path.into()),
             ("db", Some(db)) => Self::DescribeDatabase(db.to_string()),
+            ("prepare", Some(arg)) => match arg.split_once(' ') {
+                Some((name, sql)) => Self::Prepare(name.into(), sql.into()),
+                None => return Err(()),
+            },
+            ("execute", Some(name)) => Self::ExecutePrepared(name.into()),
             _ => return Err(()),
         })
     }
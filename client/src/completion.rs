@@ -0,0 +1,376 @@
+//! Candidates of the auto-completion in the REPL: keywords and the names of functions,
+//! databases, tables and columns, which are fetched lazily from `information_schema`.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use tokio::runtime::{Handle, RuntimeFlavor};
+
+use crate::ctx::SessionContext;
+
+/// Keywords of the sql dialect, including the extension statements of CnosDB,
+/// e.g. `SHOW SERIES`, `COPY INTO`, `ALTER TENANT` and `CREATE PIPE`
+#[rustfmt::skip]
+pub const KEYWORDS: &[&str] = &[
+    "ADD", "ALL", "ALTER", "AND", "ANALYZE", "APPEND", "AS", "ASC", "AUTO_INCREMENT", "BETWEEN",
+    "BIGINT", "BOOLEAN", "BY", "CASE", "CAST", "CODEC", "COLD_DURATION", "COLUMN", "COMPACT",
+    "COMPRESSION", "COPY", "COPY_OPTIONS", "CREATE", "CROSS", "DATABASE", "DATABASES", "DEFAULT",
+    "DELETE", "DELIMITER", "DESC", "DESCRIBE", "DISTINCT", "DOUBLE", "DROP", "ELSE", "END",
+    "EXCEPT", "EXISTS", "EXPLAIN", "EXTERNAL", "FALSE", "FIELD", "FILES", "FILE_FORMAT", "FORMAT",
+    "FROM", "FULL", "GRANT", "GROUP", "HAVING", "HEADER", "IF", "IN", "INHERIT", "INNER",
+    "INSERT", "INTERSECT", "INTERVAL", "INTO", "IS", "JOIN", "KILL", "LEFT", "LIKE", "LIMIT",
+    "LOCATION", "MEMBER", "NODE", "NOT", "NULL", "OFFSET", "ON", "ON_ERROR", "OR", "ORDER",
+    "OUTER", "PARTITION", "PATTERN", "PIPE", "PIPES", "PRECISION", "QUERIES", "QUERY", "READ",
+    "REMOVE", "RENAME", "REPLICA", "RESET", "REVOKE", "RIGHT", "ROLE", "SELECT", "SERIES",
+    "SESSION", "SET", "SHARD", "SHOW", "STORED", "STREAM", "STREAMS", "STRING", "TABLE", "TABLES",
+    "TAG", "TAGS", "TENANT", "THEN", "TIME", "TIMESTAMP", "TO", "TRUE", "TTL", "UNION", "UNSIGNED",
+    "UPDATE", "USE", "USER", "USING", "VALUES", "VERBOSE", "VNODE", "VNODE_DURATION", "WHEN",
+    "WHERE", "WITH", "WRITE",
+];
+
+/// Keywords after which the names of tables are expected
+const TABLE_KEYWORDS: &[&str] = &["FROM", "JOIN", "INTO", "TABLE", "UPDATE", "ON"];
+/// Keywords after which the names of databases are expected
+const DATABASE_KEYWORDS: &[&str] = &["DATABASE", "USE"];
+
+/// Timeout of fetching the names, the REPL is blocked while fetching
+const FETCH_TIMEOUT: Duration = Duration::from_secs(3);
+/// Names are not fetched for a while after a failure, the interval is doubled
+/// on each failure until it reaches the max
+const MIN_RETRY_INTERVAL: Duration = Duration::from_secs(1);
+const MAX_RETRY_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Names of the schema objects, each level is fetched when it is first completed.
+///
+/// The cache is cleared after the schema is changed by the REPL.
+pub struct SchemaCache {
+    /// `None` if the names can't be fetched, only the cached ones are completed
+    ctx: Option<SessionContext>,
+    /// Database of the REPL
+    database: String,
+    functions: Option<Vec<String>>,
+    databases: Option<Vec<String>>,
+    tables: HashMap<String, Vec<String>>,
+    columns: HashMap<(String, String), Vec<String>>,
+    /// Time before which the names are not fetched after a failure, and the interval of it
+    retry: Option<(Instant, Duration)>,
+}
+
+impl SchemaCache {
+    pub fn new(ctx: Option<SessionContext>, database: &str) -> Self {
+        Self {
+            ctx,
+            database: database.to_string(),
+            functions: None,
+            databases: None,
+            tables: HashMap::new(),
+            columns: HashMap::new(),
+            retry: None,
+        }
+    }
+
+    pub fn set_database(&mut self, database: &str) {
+        self.database = database.to_string();
+    }
+
+    pub fn invalidate(&mut self) {
+        self.databases = None;
+        self.tables.clear();
+        self.columns.clear();
+    }
+
+    /// Names of the functions, which are not changed by the REPL
+    pub fn functions(&mut self) -> Vec<String> {
+        if self.functions.is_none() {
+            let names = self.fetch("SELECT function_name FROM information_schema.functions");
+            self.functions = names;
+        }
+        self.functions.clone().unwrap_or_default()
+    }
+
+    pub fn databases(&mut self) -> Vec<String> {
+        if self.databases.is_none() {
+            let names = self.fetch("SELECT database_name FROM information_schema.databases");
+            self.databases = names;
+        }
+        self.databases.clone().unwrap_or_default()
+    }
+
+    pub fn tables(&mut self, database: &str) -> Vec<String> {
+        if !self.tables.contains_key(database) {
+            let names = self.fetch(&format!(
+                "SELECT table_name FROM information_schema.tables WHERE table_database = '{}'",
+                escape_literal(database)
+            ));
+            match names {
+                Some(names) => {
+                    self.tables.insert(database.to_string(), names);
+                }
+                None => return vec![],
+            }
+        }
+        self.tables.get(database).cloned().unwrap_or_default()
+    }
+
+    /// Names of the columns of the table, tags included
+    pub fn columns(&mut self, database: &str, table: &str) -> Vec<String> {
+        let key = (database.to_string(), table.to_string());
+        if !self.columns.contains_key(&key) {
+            let names = self.fetch(&format!(
+                "SELECT column_name FROM information_schema.columns \
+                 WHERE database_name = '{}' AND table_name = '{}'",
+                escape_literal(database),
+                escape_literal(table)
+            ));
+            match names {
+                Some(names) => {
+                    self.columns.insert(key.clone(), names);
+                }
+                None => return vec![],
+            }
+        }
+        self.columns.get(&key).cloned().unwrap_or_default()
+    }
+
+    /// Execute the query returning a column of names, `None` if it failed.
+    ///
+    /// The completion is synchronous, the query is executed by blocking the worker thread
+    /// of the REPL, which is only possible in the multi-thread runtime.
+    ///
+    /// Nothing is fetched for a while after a failure, so that the REPL is not blocked
+    /// on every completion while the server is down.
+    fn fetch(&mut self, sql: &str) -> Option<Vec<String>> {
+        let ctx = self.ctx.as_ref()?;
+        let handle = Handle::try_current().ok()?;
+        if handle.runtime_flavor() != RuntimeFlavor::MultiThread {
+            return None;
+        }
+        if matches!(self.retry, Some((retry_at, _)) if Instant::now() < retry_at) {
+            return None;
+        }
+
+        let result = tokio::task::block_in_place(|| {
+            handle.block_on(tokio::time::timeout(
+                FETCH_TIMEOUT,
+                ctx.sql_csv(sql.to_string()),
+            ))
+        });
+        let csv = match result {
+            Ok(Ok(csv)) => {
+                self.retry = None;
+                csv
+            }
+            _ => {
+                let interval = self
+                    .retry
+                    .map(|(_, interval)| (interval * 2).min(MAX_RETRY_INTERVAL))
+                    .unwrap_or(MIN_RETRY_INTERVAL);
+                self.retry = Some((Instant::now() + interval, interval));
+                return None;
+            }
+        };
+        let names = csv
+            .lines()
+            .skip(1)
+            .map(|e| e.trim().trim_matches('"').replace("\"\"", "\""))
+            .filter(|e| !e.is_empty())
+            .collect();
+        Some(names)
+    }
+
+    /// Candidates of the word being typed in the line, returns the start of the word
+    pub fn complete(&mut self, line: &str, pos: usize) -> (usize, Vec<String>) {
+        let start = word_start(line, pos);
+        let word = &line[start..pos];
+
+        let candidates = match word.rsplit_once('.') {
+            // `database.table` or `table.column`
+            Some((qualifier, prefix)) => {
+                let qualifier = unquote(qualifier);
+                let names = if self.databases().iter().any(|e| e == &qualifier) {
+                    self.tables(&qualifier)
+                } else {
+                    let database = self.database.clone();
+                    self.columns(&database, &qualifier)
+                };
+                return (
+                    pos - prefix.len(),
+                    matches(prefix, names.iter().map(String::as_str)),
+                );
+            }
+            None => {
+                let database = self.database.clone();
+                let previous = previous_word(line, start).to_ascii_uppercase();
+                if DATABASE_KEYWORDS.contains(&previous.as_str()) {
+                    matches(word, self.databases().iter().map(String::as_str))
+                } else if TABLE_KEYWORDS.contains(&previous.as_str()) {
+                    let mut names = self.tables(&database);
+                    names.extend(self.databases());
+                    matches(word, names.iter().map(String::as_str))
+                } else {
+                    let mut names = vec![];
+                    for table in referenced_tables(line) {
+                        names.extend(self.columns(&database, &table));
+                    }
+                    let mut candidates = matches(word, names.iter().map(String::as_str));
+                    candidates.extend(matches_keywords(word));
+                    candidates.extend(matches(word, self.functions().iter().map(String::as_str)));
+                    candidates
+                }
+            }
+        };
+
+        (start, dedup(candidates))
+    }
+}
+
+/// Start of the word ending at `pos`, dots and quoted identifiers are part of the word
+pub fn word_start(line: &str, pos: usize) -> usize {
+    line[..pos]
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| c.is_alphanumeric() || matches!(c, '_' | '.' | '"'))
+        .last()
+        .map(|(i, _)| i)
+        .unwrap_or(pos)
+}
+
+fn previous_word(line: &str, start: usize) -> &str {
+    line[..start]
+        .split(|c: char| c.is_whitespace() || c == ',' || c == '(')
+        .rev()
+        .find(|e| !e.is_empty())
+        .unwrap_or_default()
+}
+
+/// Tables after `FROM` or `JOIN` in the line, whose columns are completed
+fn referenced_tables(line: &str) -> Vec<String> {
+    let words = line
+        .split(|c: char| c.is_whitespace() || c == ',' || c == '(' || c == ')' || c == ';')
+        .filter(|e| !e.is_empty())
+        .collect::<Vec<_>>();
+    words
+        .windows(2)
+        .filter(|w| w[0].eq_ignore_ascii_case("FROM") || w[0].eq_ignore_ascii_case("JOIN"))
+        .map(|w| unquote(w[1]))
+        .collect()
+}
+
+/// Keywords are completed in the case of the typed word
+fn matches_keywords(word: &str) -> Vec<String> {
+    let lowercase = !word.is_empty() && word.chars().all(|c| !c.is_uppercase());
+    matches(word, KEYWORDS.iter().copied())
+        .into_iter()
+        .map(|e| if lowercase { e.to_lowercase() } else { e })
+        .collect()
+}
+
+fn matches<'a>(prefix: &str, names: impl Iterator<Item = &'a str>) -> Vec<String> {
+    let prefix = unquote(prefix).to_lowercase();
+    names
+        .filter(|e| e.to_lowercase().starts_with(&prefix))
+        .map(|e| e.to_string())
+        .collect()
+}
+
+fn dedup(candidates: Vec<String>) -> Vec<String> {
+    let mut result: Vec<String> = Vec::with_capacity(candidates.len());
+    for c in candidates {
+        if !result.contains(&c) {
+            result.push(c);
+        }
+    }
+    result
+}
+
+fn unquote(s: &str) -> String {
+    s.trim_matches('"').to_string()
+}
+
+fn escape_literal(s: &str) -> String {
+    s.replace('\'', "''")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache() -> SchemaCache {
+        let mut cache = SchemaCache::new(None, "public");
+        cache.functions = Some(
+            [
+                "avg",
+                "starts_with",
+                "stddev",
+                "stddev_pop",
+                "stddev_samp",
+                "strpos",
+            ]
+            .iter()
+            .map(|e| e.to_string())
+            .collect(),
+        );
+        cache.databases = Some(vec!["public".to_string(), "oceanic".to_string()]);
+        cache.tables.insert(
+            "public".to_string(),
+            vec!["air".to_string(), "sea".to_string()],
+        );
+        cache
+            .tables
+            .insert("oceanic".to_string(), vec!["wind".to_string()]);
+        cache.columns.insert(
+            ("public".to_string(), "air".to_string()),
+            vec![
+                "time".to_string(),
+                "station".to_string(),
+                "temperature".to_string(),
+            ],
+        );
+        cache
+    }
+
+    #[test]
+    fn test_complete() {
+        let mut cache = cache();
+
+        assert_eq!(
+            cache.complete("SELECT * FROM a", 15),
+            (14, vec!["air".to_string()])
+        );
+        assert_eq!(
+            cache.complete("select * from oceanic.w", 23),
+            (22, vec!["wind".to_string()])
+        );
+        assert_eq!(
+            cache.complete("USE oc", 6),
+            (4, vec!["oceanic".to_string()])
+        );
+
+        let (start, candidates) = cache.complete("SELECT st FROM air", 9);
+        assert_eq!(start, 7);
+        assert_eq!(
+            candidates,
+            vec![
+                "station".to_string(),
+                "stored".to_string(),
+                "stream".to_string(),
+                "streams".to_string(),
+                "string".to_string(),
+                "starts_with".to_string(),
+                "stddev".to_string(),
+                "stddev_pop".to_string(),
+                "stddev_samp".to_string(),
+                "strpos".to_string(),
+            ]
+        );
+        assert_eq!(
+            cache.complete("SELECT air.te FROM air", 13),
+            (11, vec!["temperature".to_string()])
+        );
+
+        assert_eq!(
+            cache.complete("show ser", 8),
+            (5, vec!["series".to_string()])
+        );
+    }
+}
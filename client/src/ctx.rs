@@ -1,10 +1,16 @@
+use std::collections::{BTreeMap, HashMap};
+
+use datafusion::arrow::datatypes::SchemaRef;
 use datafusion::arrow::record_batch::RecordBatch;
-use http_protocol::header::ACCEPT;
+use http_protocol::header::{ACCEPT, DB, TENANT};
 use http_protocol::http_client::HttpClient;
 use http_protocol::parameter::{SqlParam, WriteParam};
 use http_protocol::status_code::OK;
+use reqwest::Response;
+use tokio::sync::{Mutex, MutexGuard};
 
 use crate::config::ConfigOptions;
+use crate::flight::{FlightSqlClient, PreparedStatement};
 use crate::print_format::PrintFormat;
 
 pub const DEFAULT_USER: &str = "cnosdb";
@@ -14,14 +20,27 @@ pub const DEFAULT_DATABASE: &str = "public";
 pub const API_V1_SQL_PATH: &str = "/api/v1/sql";
 pub const API_V1_WRITE_PATH: &str = "/api/v1/write";
 
+/// Transport the statements are executed by
+#[derive(Debug, PartialEq, Eq, clap::ArgEnum, Clone, Copy)]
+pub enum Protocol {
+    /// The http api, results are returned as text
+    Http,
+    /// Arrow Flight SQL, results are returned as record batches
+    Flight,
+}
+
+#[derive(Clone)]
 pub struct SessionConfig {
     pub user_info: UserInfo,
     pub connection_info: ConnectionInfo,
+    pub protocol: Protocol,
     pub tenant: String,
     pub database: String,
     pub target_partitions: Option<usize>,
     pub fmt: PrintFormat,
     pub config_options: ConfigOptions,
+    // session variables set by `SET`, sent with every query
+    pub session_variables: BTreeMap<String, String>,
 }
 
 impl SessionConfig {
@@ -32,11 +51,13 @@ impl SessionConfig {
         Self {
             user_info: Default::default(),
             connection_info: Default::default(),
+            protocol: Protocol::Http,
             tenant: DEFAULT_USER.to_string(),
             database: DEFAULT_DATABASE.to_string(),
             target_partitions: None,
             config_options,
             fmt: PrintFormat::Csv,
+            session_variables: BTreeMap::new(),
         }
     }
 
@@ -81,6 +102,18 @@ impl SessionConfig {
         self
     }
 
+    pub fn with_flight_port(mut self, port: usize) -> Self {
+        self.connection_info.flight_port = port;
+
+        self
+    }
+
+    pub fn with_protocol(mut self, protocol: Protocol) -> Self {
+        self.protocol = protocol;
+
+        self
+    }
+
     pub fn with_tls(mut self, tls: TLSConfig) -> Self {
         self.connection_info.tls_config = Some(tls);
 
@@ -94,6 +127,7 @@ impl SessionConfig {
     }
 }
 
+#[derive(Clone)]
 pub struct UserInfo {
     pub user: String,
     pub password: Option<String>,
@@ -108,14 +142,17 @@ impl Default for UserInfo {
     }
 }
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct ConnectionInfo {
     pub host: String,
     pub port: usize,
+    /// Port of the Flight SQL service
+    pub flight_port: usize,
 
     pub tls_config: Option<TLSConfig>,
 }
 
+#[derive(Clone)]
 pub struct TLSConfig {
     pub client_cert_file: String,
     pub client_key_file: String,
@@ -125,6 +162,9 @@ pub struct SessionContext {
     session_config: SessionConfig,
 
     http_client: HttpClient,
+    /// Connected on the first statement executed by Flight SQL
+    flight_client: Mutex<Option<FlightSqlClient>>,
+    prepared_statements: Mutex<HashMap<String, PreparedStatement>>,
 }
 
 impl SessionContext {
@@ -135,9 +175,16 @@ impl SessionContext {
         Self {
             session_config,
             http_client,
+            flight_client: Mutex::new(None),
+            prepared_statements: Mutex::new(HashMap::new()),
         }
     }
 
+    /// A new session of the same user and tenant, e.g. to fetch the names for auto-completion
+    pub fn metadata_context(&self) -> SessionContext {
+        SessionContext::new(self.session_config.clone())
+    }
+
     pub fn set_database(&mut self, name: &str) {
         self.session_config.database = name.to_string();
     }
@@ -146,7 +193,73 @@ impl SessionContext {
         self.session_config.database.as_str()
     }
 
+    /// Remember the session variable changed by a successfully executed `SET` or `RESET` statement
+    pub fn apply_session_statement(&mut self, sql: &str) {
+        let (name, value) = match parse_session_statement(sql) {
+            Some(e) => e,
+            None => return,
+        };
+
+        let variables = &mut self.session_config.session_variables;
+        match (name.as_str(), value) {
+            ("database", Some(db)) => self.session_config.database = db,
+            ("all", None) => variables.clear(),
+            (_, Some(value)) => {
+                variables.insert(name, value);
+            }
+            (_, None) => {
+                variables.remove(&name);
+            }
+        }
+    }
+
+    fn session_variable(&self, name: &str) -> Option<String> {
+        self.session_config.session_variables.get(name).cloned()
+    }
+
+    pub fn is_flight(&self) -> bool {
+        self.session_config.protocol == Protocol::Flight
+    }
+
     pub async fn sql(&self, sql: String) -> Result<ResultSet, String> {
+        if self.is_flight() {
+            let mut client = self.flight_client().await?;
+            let client = client.as_mut().expect("connected");
+            let batches = client.execute(sql, &self.flight_headers()).await?;
+            return Ok(ResultSet::RecordBatches(batches));
+        }
+
+        let resp = self.sql_response(sql, self.session_config.fmt).await?;
+        let body = resp.bytes().await.map_err(|e| format!("{}", e))?;
+
+        Ok(ResultSet::Bytes((body.to_vec(), 0)))
+    }
+
+    /// Execute the query by Flight SQL whatever the protocol of the session, each record batch of
+    /// the result is passed to `f` once it is received, the types of the columns are kept.
+    /// Returns the schema of the result.
+    pub async fn sql_for_each(
+        &self,
+        sql: String,
+        f: impl FnMut(RecordBatch) -> Result<(), String>,
+    ) -> Result<SchemaRef, String> {
+        let mut client = self.flight_client().await?;
+        let client = client.as_mut().expect("connected");
+
+        client
+            .execute_for_each(sql, &self.flight_headers(), f)
+            .await
+    }
+
+    /// Execute the query, returns the result in csv with header
+    pub async fn sql_csv(&self, sql: String) -> Result<String, String> {
+        let resp = self.sql_response(sql, PrintFormat::Csv).await?;
+
+        resp.text().await.map_err(|e| format!("{}", e))
+    }
+
+    /// Execute the query, the body of the returned response is the result in the format `fmt`
+    pub async fn sql_response(&self, sql: String, fmt: PrintFormat) -> Result<Response, String> {
         let user_info = &self.session_config.user_info;
 
         let tenant = self.session_config.tenant.clone();
@@ -157,6 +270,18 @@ impl SessionContext {
             db: Some(db),
             chunked: None,
             target_partitions,
+            query_memory_limit: None,
+            priority: self.session_variable("priority"),
+            query_timeout_ms: self
+                .session_variable("query_timeout")
+                .and_then(|e| e.parse().ok()),
+            time_zone: self.session_variable("time_zone"),
+            precision: self.session_variable("precision"),
+            batch_size: self
+                .session_variable("batch_size")
+                .and_then(|e| e.parse().ok()),
+            consistency: self.session_variable("consistency"),
+            client_session: Some(true),
         };
 
         // let param = &[("db", &self.session_config.database)];
@@ -165,7 +290,7 @@ impl SessionContext {
             .http_client
             .post(API_V1_SQL_PATH)
             .basic_auth::<&str, &str>(&user_info.user, user_info.password.as_deref())
-            .header(ACCEPT, self.session_config.fmt.get_http_content_type())
+            .header(ACCEPT, fmt.get_http_content_type())
             .query(&param)
             .body(sql)
             .send()
@@ -173,11 +298,7 @@ impl SessionContext {
             .map_err(|e| e.to_string())?;
 
         match resp.status() {
-            OK => {
-                let body = resp.bytes().await.map_err(|e| format!("{}", e))?;
-
-                Ok(ResultSet::Bytes((body.to_vec(), 0)))
-            }
+            OK => Ok(resp),
             _ => {
                 let status = resp.status().to_string();
                 let body = resp.text().await.map_err(|e| format!("{}", e))?;
@@ -187,13 +308,99 @@ impl SessionContext {
         }
     }
 
+    /// Prepare the statement by Flight SQL, the statement replaces the one of the same name
+    pub async fn prepare(&self, name: &str, sql: String) -> Result<(), String> {
+        if self.session_config.protocol != Protocol::Flight {
+            return Err("Prepared statements require --protocol flight".to_string());
+        }
+
+        let headers = self.flight_headers();
+        let mut client = self.flight_client().await?;
+        let client = client.as_mut().expect("connected");
+        let statement = client.prepare(sql, &headers).await?;
+
+        let replaced = self
+            .prepared_statements
+            .lock()
+            .await
+            .insert(name.to_string(), statement);
+        if let Some(replaced) = replaced {
+            client.close_prepared(replaced, &headers).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Execute the prepared statement of the name, each record batch of the result is passed
+    /// to `f` once it is received, returns the schema of the result
+    pub async fn execute_prepared_for_each(
+        &self,
+        name: &str,
+        f: impl FnMut(RecordBatch) -> Result<(), String>,
+    ) -> Result<SchemaRef, String> {
+        let statement = self
+            .prepared_statements
+            .lock()
+            .await
+            .get(name)
+            .cloned()
+            .ok_or_else(|| format!("Prepared statement {} not found", name))?;
+
+        let mut client = self.flight_client().await?;
+        let client = client.as_mut().expect("connected");
+        client
+            .execute_prepared_for_each(&statement, &self.flight_headers(), f)
+            .await
+    }
+
+    /// The flight client, connected and authenticated if not yet
+    async fn flight_client(&self) -> Result<MutexGuard<'_, Option<FlightSqlClient>>, String> {
+        let mut client = self.flight_client.lock().await;
+        if client.is_none() {
+            let c = &self.session_config.connection_info;
+            let user_info = &self.session_config.user_info;
+            *client = Some(
+                FlightSqlClient::connect(
+                    &c.host,
+                    c.flight_port,
+                    &user_info.user,
+                    user_info.password.as_deref(),
+                )
+                .await?,
+            );
+        }
+
+        Ok(client)
+    }
+
+    /// Tenant, database and session variables are sent by the headers of the same name
+    fn flight_headers(&self) -> Vec<(String, String)> {
+        let mut headers = vec![
+            (TENANT.to_string(), self.session_config.tenant.clone()),
+            (DB.to_string(), self.session_config.database.clone()),
+        ];
+        headers.extend(
+            self.session_config
+                .session_variables
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone())),
+        );
+
+        headers
+    }
+
     pub async fn write(&self, path: &str) -> Result<ResultSet, String> {
         let body = tokio::fs::read(path).await.map_err(|e| e.to_string())?;
 
+        self.write_line_protocol(body).await
+    }
+
+    /// Write the lines of line protocol into the current database
+    pub async fn write_line_protocol(&self, body: Vec<u8>) -> Result<ResultSet, String> {
         let user_info = &self.session_config.user_info;
 
         let param = WriteParam {
-            tenant: None,
+            tenant: Some(self.session_config.tenant.clone()),
             db: self.session_config.database.clone(),
         };
 
@@ -224,6 +431,46 @@ impl SessionContext {
     }
 }
 
+/// Parse `SET [SESSION] <name> { = | TO } <value>` and `RESET { <name> | ALL }`,
+/// `None` value means the variable is reset to default
+fn parse_session_statement(sql: &str) -> Option<(String, Option<String>)> {
+    let sql = sql.trim().trim_end_matches(';').trim();
+    let (keyword, rest) = sql.split_once(char::is_whitespace)?;
+    let rest = rest.trim();
+
+    if keyword.eq_ignore_ascii_case("reset") {
+        return Some((rest.to_ascii_lowercase(), None));
+    }
+    if !keyword.eq_ignore_ascii_case("set") {
+        return None;
+    }
+
+    let rest = match rest.split_once(char::is_whitespace) {
+        Some((session, rest)) if session.eq_ignore_ascii_case("session") => rest.trim(),
+        _ => rest,
+    };
+    let (name, value) = match rest.split_once('=') {
+        Some(e) => e,
+        None => {
+            let (name, rest) = rest.split_once(char::is_whitespace)?;
+            let (to, value) = rest.trim().split_once(char::is_whitespace)?;
+            if !to.eq_ignore_ascii_case("to") {
+                return None;
+            }
+            (name, value)
+        }
+    };
+
+    let name = name.trim().to_ascii_lowercase();
+    let value = value.trim();
+    if value.eq_ignore_ascii_case("default") {
+        return Some((name, None));
+    }
+    let value = value.trim_matches('\'').to_string();
+
+    Some((name, Some(value)))
+}
+
 pub enum ResultSet {
     RecordBatches(Vec<RecordBatch>),
     // (data, row_number)
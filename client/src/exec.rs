@@ -1,14 +1,16 @@
 //! Execution functions
 
 use std::fs::File;
+use std::future::Future;
 use std::io::prelude::*;
 use std::io::BufReader;
 use std::time::Instant;
 
 use rustyline::error::ReadlineError;
-use rustyline::Editor;
+use rustyline::{Cmd, Config, Editor, KeyCode, KeyEvent, Modifiers};
 
 use crate::command::{Command, OutputFormat};
+use crate::completion::SchemaCache;
 use crate::ctx::SessionContext;
 use crate::helper::CliHelper;
 use crate::print_options::PrintOptions;
@@ -71,13 +73,28 @@ pub async fn exec_from_files(
 
 /// run and execute SQL statements and commands against a context with the given print options
 pub async fn exec_from_repl(ctx: &mut SessionContext, print_options: &mut PrintOptions) {
-    let mut rl = Editor::<CliHelper>::new();
-    rl.set_helper(Some(CliHelper::default()));
+    let config = Config::builder().history_ignore_dups(true).build();
+    let mut rl = Editor::<CliHelper>::with_config(config);
+    let schema = SchemaCache::new(Some(ctx.metadata_context()), ctx.get_database());
+    rl.set_helper(Some(CliHelper::new(schema)));
+    // Search the history, multi-line statements included, by the typed prefix
+    rl.bind_sequence(
+        KeyEvent(KeyCode::PageUp, Modifiers::NONE),
+        Cmd::HistorySearchBackward,
+    );
+    rl.bind_sequence(
+        KeyEvent(KeyCode::PageDown, Modifiers::NONE),
+        Cmd::HistorySearchForward,
+    );
     rl.load_history(".history").ok();
 
     let mut print_options = print_options.clone();
 
     loop {
+        if let Some(helper) = rl.helper() {
+            helper.set_database(ctx.get_database());
+        }
+
         match rl.readline(format!("{} ❯ ", ctx.get_database()).as_str()) {
             Ok(line) if line.starts_with('\\') => {
                 rl.add_history_entry(line.trim_end());
@@ -99,7 +116,7 @@ pub async fn exec_from_repl(ctx: &mut SessionContext, print_options: &mut PrintO
                             }
                         }
                         _ => {
-                            if let Err(e) = cmd.execute(ctx, &mut print_options).await {
+                            if let Err(e) = cancelable(cmd.execute(ctx, &mut print_options)).await {
                                 eprintln!("{}", e)
                             }
                         }
@@ -119,10 +136,14 @@ pub async fn exec_from_repl(ctx: &mut SessionContext, print_options: &mut PrintO
 
             Ok(line) => {
                 rl.add_history_entry(line.trim_end());
+                let changes_schema = changes_schema(&line);
                 match exec_and_print(ctx, &print_options, line).await {
                     Ok(_) => {}
                     Err(err) => eprintln!("{:?}", err),
                 }
+                if let (true, Some(helper)) = (changes_schema, rl.helper()) {
+                    helper.invalidate_schema();
+                }
             }
             Err(ReadlineError::Interrupted) => {
                 println!("^C");
@@ -154,13 +175,32 @@ async fn exec_and_print(
         }
 
         let now = Instant::now();
-        let results = ctx.sql(tmp.to_string() + ";").await?;
-        print_options.print_batches(&results, now)?;
+        cancelable(print_options.print_sql(ctx, tmp.to_string() + ";", now)).await?;
+        ctx.apply_session_statement(tmp);
     }
 
     Ok(())
 }
 
+/// Run the statement until it completes or is canceled by Ctrl-C,
+/// the request is dropped when canceled, which cancels the flight call
+async fn cancelable<T>(fut: impl Future<Output = Result<T, String>>) -> Result<T, String> {
+    tokio::select! {
+        result = fut => result,
+        _ = tokio::signal::ctrl_c() => Err("Canceled".to_string()),
+    }
+}
+
+/// Whether the statements may create, drop or alter databases, tables or columns
+fn changes_schema(sql: &str) -> bool {
+    sql.split(';').any(|e| {
+        let keyword = e.split_whitespace().next().unwrap_or_default();
+        ["CREATE", "DROP", "ALTER"]
+            .iter()
+            .any(|k| k.eq_ignore_ascii_case(keyword))
+    })
+}
+
 fn use_database(sql: &str) -> Option<String> {
     let sql = sql.trim().trim_end_matches(';');
     if !sql[0..3].to_ascii_lowercase().eq("use") {
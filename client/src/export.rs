@@ -0,0 +1,230 @@
+//! Export of query results into files
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::parquet::arrow::ArrowWriter;
+
+use crate::ctx::SessionContext;
+use crate::import::{database_precision, describe_table};
+use crate::line_protocol::{batch_to_lines, TableColumns};
+use crate::print_format::PrintFormat;
+
+/// Csv is exported by the http api, parquet and line protocol by Flight SQL,
+/// which must be reachable at `--flight-port`.
+#[derive(Debug, PartialEq, Eq, clap::ArgEnum, Clone, Copy)]
+pub enum ExportFormat {
+    Csv,
+    Parquet,
+    /// Line protocol
+    Lp,
+}
+
+#[derive(Debug, PartialEq, Eq, clap::Args)]
+pub struct ExportArgs {
+    #[clap(long, help = "Query of the exported data")]
+    pub query: String,
+
+    #[clap(
+        long,
+        arg_enum,
+        help = "Format of the file, parquet and lp are exported by Flight SQL at --flight-port"
+    )]
+    pub format: ExportFormat,
+
+    #[clap(
+        long,
+        help = "Table the query reads, its tags and time are used to write line protocol"
+    )]
+    pub table: Option<String>,
+
+    #[clap(short, long, help = "File the data is exported into")]
+    pub output: String,
+}
+
+/// Where the record batches of the result are written
+enum BlockWriter {
+    Parquet {
+        file: Option<File>,
+        writer: Option<ArrowWriter<File>>,
+    },
+    Lp {
+        table: String,
+        columns: TableColumns,
+        precision: i64,
+        file: BufWriter<File>,
+    },
+}
+
+impl BlockWriter {
+    fn write(&mut self, batch: &RecordBatch) -> Result<(), String> {
+        match self {
+            Self::Parquet { file, writer } => {
+                // The schema is known after the first batch is received
+                if writer.is_none() {
+                    if let Some(file) = file.take() {
+                        *writer = Some(
+                            ArrowWriter::try_new(file, batch.schema(), None)
+                                .map_err(|e| e.to_string())?,
+                        );
+                    }
+                }
+                if let Some(writer) = writer {
+                    writer.write(batch).map_err(|e| e.to_string())?;
+                }
+            }
+            Self::Lp {
+                table,
+                columns,
+                precision,
+                file,
+            } => {
+                for line in batch_to_lines(table, columns, *precision, batch)? {
+                    writeln!(file, "{}", line).map_err(|e| e.to_string())?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Finish the file of the result of `schema`, a parquet file is written
+    /// with the schema even if the result is empty.
+    fn finish(self, schema: SchemaRef) -> Result<(), String> {
+        match self {
+            Self::Parquet {
+                writer: Some(writer),
+                ..
+            } => writer.close().map(|_| ()).map_err(|e| e.to_string()),
+            Self::Parquet {
+                file: Some(file), ..
+            } => ArrowWriter::try_new(file, schema, None)
+                .and_then(|writer| writer.close())
+                .map(|_| ())
+                .map_err(|e| e.to_string()),
+            Self::Parquet { .. } => Ok(()),
+            Self::Lp { mut file, .. } => file.flush().map_err(|e| e.to_string()),
+        }
+    }
+}
+
+/// Execute the query and stream the result into the output file, returns the number of records.
+///
+/// Csv is requested by the http api and written as is. The other formats fetch the record
+/// batches of the result by Flight SQL, so the types of the columns are kept, and write them one
+/// by one, the result is never held in memory entirely.
+pub async fn export(ctx: &SessionContext, args: &ExportArgs) -> Result<u64, String> {
+    let mut writer = match args.format {
+        ExportFormat::Csv => return export_csv(ctx, args).await,
+        ExportFormat::Parquet => BlockWriter::Parquet {
+            file: Some(create_file(&args.output)?),
+            writer: None,
+        },
+        ExportFormat::Lp => {
+            let table = args
+                .table
+                .clone()
+                .ok_or_else(|| "--table is required to export line protocol".to_string())?;
+            BlockWriter::Lp {
+                columns: describe_table(ctx, &table).await?,
+                precision: database_precision(ctx).await?,
+                table,
+                file: BufWriter::new(create_file(&args.output)?),
+            }
+        }
+    };
+
+    let mut num_records = 0;
+    let schema = ctx
+        .sql_for_each(args.query.clone(), |batch| {
+            num_records += batch.num_rows() as u64;
+            writer.write(&batch)
+        })
+        .await?;
+    writer.finish(schema)?;
+
+    Ok(num_records)
+}
+
+/// Csv is written as it is received
+async fn export_csv(ctx: &SessionContext, args: &ExportArgs) -> Result<u64, String> {
+    let mut resp = ctx
+        .sql_response(args.query.clone(), PrintFormat::Csv)
+        .await?;
+
+    let mut file = BufWriter::new(create_file(&args.output)?);
+    let mut counter = CsvRecordCounter::default();
+    while let Some(chunk) = resp.chunk().await.map_err(|e| e.to_string())? {
+        counter.feed(&chunk);
+        file.write_all(&chunk).map_err(|e| e.to_string())?;
+    }
+    file.flush().map_err(|e| e.to_string())?;
+
+    // Without the header
+    Ok(counter.records.saturating_sub(1))
+}
+
+fn create_file(path: &str) -> Result<File, String> {
+    File::create(path).map_err(|e| format!("{}: {}", path, e))
+}
+
+/// Count the csv records in a stream, newlines in quoted values are not boundaries
+#[derive(Default)]
+struct CsvRecordCounter {
+    in_quotes: bool,
+    records: u64,
+}
+
+impl CsvRecordCounter {
+    fn feed(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            match b {
+                b'"' => self.in_quotes = !self.in_quotes,
+                b'\n' if !self.in_quotes => self.records += 1,
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use datafusion::arrow::datatypes::{DataType, Field, Schema};
+    use datafusion::parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    use super::*;
+
+    #[test]
+    fn test_export_empty_parquet() {
+        let path = std::env::temp_dir().join("cnosdb_test_export_empty.parquet");
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("time", DataType::Int64, false),
+            Field::new("value", DataType::Float64, true),
+        ]));
+
+        let writer = BlockWriter::Parquet {
+            file: Some(File::create(&path).unwrap()),
+            writer: None,
+        };
+        writer.finish(schema.clone()).unwrap();
+
+        let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(&path).unwrap()).unwrap();
+        assert_eq!(reader.schema().fields(), schema.fields());
+        assert_eq!(reader.build().unwrap().count(), 0);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_csv_record_counter() {
+        let mut counter = CsvRecordCounter::default();
+        counter.feed(b"a,b\n1,\"x\ny\"\n2,");
+        assert_eq!(counter.records, 2);
+
+        counter.feed(b"z\n3,");
+        assert_eq!(counter.records, 3);
+    }
+}
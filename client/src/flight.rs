@@ -0,0 +1,271 @@
+//! Flight SQL transport of the session
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use arrow_flight::flight_service_client::FlightServiceClient;
+use arrow_flight::sql::{
+    ActionClosePreparedStatementRequest, ActionCreatePreparedStatementRequest,
+    ActionCreatePreparedStatementResult, Any, CommandPreparedStatementQuery, CommandStatementQuery,
+    ProstMessageExt,
+};
+use arrow_flight::utils::flight_data_to_arrow_batch;
+use arrow_flight::{Action, FlightDescriptor, FlightInfo, HandshakeRequest, IpcMessage};
+use datafusion::arrow::datatypes::{Schema, SchemaRef};
+use datafusion::arrow::ipc::{root_as_message, MessageHeader};
+use datafusion::arrow::record_batch::RecordBatch;
+use http_protocol::header::{AUTHORIZATION, BASIC_PREFIX};
+use prost::Message;
+use tonic::metadata::{Ascii, AsciiMetadataValue, MetadataKey};
+use tonic::transport::{Channel, Endpoint};
+use tonic::Request;
+
+const CREATE_PREPARED_STATEMENT: &str = "CreatePreparedStatement";
+const CLOSE_PREPARED_STATEMENT: &str = "ClosePreparedStatement";
+
+/// A statement prepared on the server
+#[derive(Debug, Clone)]
+pub struct PreparedStatement {
+    pub sql: String,
+    handle: Vec<u8>,
+}
+
+/// Client of the Flight SQL service of the server.
+///
+/// The client authenticates once by the handshake, the bearer token returned by the server
+/// is sent with the following requests.
+pub struct FlightSqlClient {
+    inner: FlightServiceClient<Channel>,
+    token: AsciiMetadataValue,
+}
+
+impl FlightSqlClient {
+    pub async fn connect(
+        host: &str,
+        port: usize,
+        user: &str,
+        password: Option<&str>,
+    ) -> Result<Self, String> {
+        let endpoint = Endpoint::from_shared(format!("http://{}:{}", host, port))
+            .map_err(|e| e.to_string())?;
+        let channel = endpoint.connect().await.map_err(|e| {
+            format!(
+                "Failed to connect to the Flight SQL service at {}:{}, check --flight-port: {}",
+                host, port, e
+            )
+        })?;
+        let mut inner = FlightServiceClient::new(channel);
+
+        let basic = format!(
+            "{}{}",
+            BASIC_PREFIX,
+            base64::encode(format!("{}:{}", user, password.unwrap_or_default()))
+        );
+        let mut req = Request::new(futures::stream::iter(vec![HandshakeRequest::default()]));
+        req.metadata_mut().insert(
+            AUTHORIZATION.as_str(),
+            AsciiMetadataValue::try_from(basic.as_str()).map_err(|e| e.to_string())?,
+        );
+        let resp = inner
+            .handshake(req)
+            .await
+            .map_err(|e| e.message().to_string())?;
+        let token = resp
+            .metadata()
+            .get(AUTHORIZATION.as_str())
+            .cloned()
+            .ok_or_else(|| "The server returned no token by the handshake".to_string())?;
+
+        Ok(Self { inner, token })
+    }
+
+    /// Execute the statement and fetch all the result
+    pub async fn execute(
+        &mut self,
+        sql: String,
+        headers: &[(String, String)],
+    ) -> Result<Vec<RecordBatch>, String> {
+        let mut batches = vec![];
+        self.execute_for_each(sql, headers, |batch| {
+            batches.push(batch);
+            Ok(())
+        })
+        .await?;
+
+        Ok(batches)
+    }
+
+    /// Execute the statement, each batch of the result is passed to `f` once it is received,
+    /// returns the schema of the result
+    pub async fn execute_for_each(
+        &mut self,
+        sql: String,
+        headers: &[(String, String)],
+        f: impl FnMut(RecordBatch) -> Result<(), String>,
+    ) -> Result<SchemaRef, String> {
+        let cmd = CommandStatementQuery { query: sql };
+        let descriptor = FlightDescriptor::new_cmd(cmd.as_any().encode_to_vec());
+        let req = self.request(descriptor, headers)?;
+        let info = self
+            .inner
+            .get_flight_info(req)
+            .await
+            .map_err(|e| e.message().to_string())?
+            .into_inner();
+
+        self.fetch(info, headers, f).await
+    }
+
+    /// Prepare the statement, which can be executed afterwards by [`Self::execute_prepared`]
+    pub async fn prepare(
+        &mut self,
+        sql: String,
+        headers: &[(String, String)],
+    ) -> Result<PreparedStatement, String> {
+        let cmd = ActionCreatePreparedStatementRequest { query: sql.clone() };
+        let action = Action {
+            r#type: CREATE_PREPARED_STATEMENT.to_string(),
+            body: cmd.as_any().encode_to_vec().into(),
+        };
+        let req = self.request(action, headers)?;
+        let mut results = self
+            .inner
+            .do_action(req)
+            .await
+            .map_err(|e| e.message().to_string())?
+            .into_inner();
+
+        let result = results
+            .message()
+            .await
+            .map_err(|e| e.message().to_string())?
+            .ok_or_else(|| "The server returned no prepared statement".to_string())?;
+        let result = Any::decode(&result.body[..])
+            .map_err(|e| e.to_string())?
+            .unpack::<ActionCreatePreparedStatementResult>()
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "Unexpected result of creating prepared statement".to_string())?;
+
+        Ok(PreparedStatement {
+            sql,
+            handle: result.prepared_statement_handle.to_vec(),
+        })
+    }
+
+    /// Execute the prepared statement and fetch all the result
+    pub async fn execute_prepared(
+        &mut self,
+        statement: &PreparedStatement,
+        headers: &[(String, String)],
+    ) -> Result<Vec<RecordBatch>, String> {
+        let mut batches = vec![];
+        self.execute_prepared_for_each(statement, headers, |batch| {
+            batches.push(batch);
+            Ok(())
+        })
+        .await?;
+
+        Ok(batches)
+    }
+
+    /// Execute the prepared statement, each batch of the result is passed to `f` once it is
+    /// received, returns the schema of the result
+    pub async fn execute_prepared_for_each(
+        &mut self,
+        statement: &PreparedStatement,
+        headers: &[(String, String)],
+        f: impl FnMut(RecordBatch) -> Result<(), String>,
+    ) -> Result<SchemaRef, String> {
+        let cmd = CommandPreparedStatementQuery {
+            prepared_statement_handle: statement.handle.clone().into(),
+        };
+        let descriptor = FlightDescriptor::new_cmd(cmd.as_any().encode_to_vec());
+        let req = self.request(descriptor, headers)?;
+        let info = self
+            .inner
+            .get_flight_info(req)
+            .await
+            .map_err(|e| e.message().to_string())?
+            .into_inner();
+
+        self.fetch(info, headers, f).await
+    }
+
+    pub async fn close_prepared(
+        &mut self,
+        statement: PreparedStatement,
+        headers: &[(String, String)],
+    ) -> Result<(), String> {
+        let cmd = ActionClosePreparedStatementRequest {
+            prepared_statement_handle: statement.handle.into(),
+        };
+        let action = Action {
+            r#type: CLOSE_PREPARED_STATEMENT.to_string(),
+            body: cmd.as_any().encode_to_vec().into(),
+        };
+        let req = self.request(action, headers)?;
+        self.inner
+            .do_action(req)
+            .await
+            .map_err(|e| e.message().to_string())?;
+
+        Ok(())
+    }
+
+    /// Fetch the result of all the endpoints of the flight, the batches are passed to `f` in order,
+    /// returns the schema of the result, which is known even if there is no batch
+    async fn fetch(
+        &mut self,
+        info: FlightInfo,
+        headers: &[(String, String)],
+        mut f: impl FnMut(RecordBatch) -> Result<(), String>,
+    ) -> Result<SchemaRef, String> {
+        let schema: SchemaRef =
+            Arc::new(Schema::try_from(IpcMessage(info.schema)).map_err(|e| e.to_string())?);
+        let dictionaries_by_id = HashMap::new();
+
+        for ticket in info.endpoint.into_iter().filter_map(|e| e.ticket) {
+            let req = self.request(ticket, headers)?;
+            let mut stream = self
+                .inner
+                .do_get(req)
+                .await
+                .map_err(|e| e.message().to_string())?
+                .into_inner();
+
+            while let Some(data) = stream
+                .message()
+                .await
+                .map_err(|e| e.message().to_string())?
+            {
+                let message = root_as_message(&data.data_header[..])
+                    .map_err(|e| format!("Invalid flight data: {}", e))?;
+                // The schema has been got from the flight info
+                if message.header_type() != MessageHeader::RecordBatch {
+                    continue;
+                }
+                let batch = flight_data_to_arrow_batch(&data, schema.clone(), &dictionaries_by_id)
+                    .map_err(|e| e.to_string())?;
+                f(batch)?;
+            }
+        }
+
+        Ok(schema)
+    }
+
+    /// Request with the token and headers, e.g. tenant, database and session variables
+    fn request<T>(&self, message: T, headers: &[(String, String)]) -> Result<Request<T>, String> {
+        let mut req = Request::new(message);
+        let metadata = req.metadata_mut();
+        metadata.insert(AUTHORIZATION.as_str(), self.token.clone());
+        for (key, value) in headers {
+            let value = AsciiMetadataValue::try_from(value.as_str())
+                .map_err(|_| format!("Invalid value of {}: {}", key, value))?;
+            let key = MetadataKey::<Ascii>::from_bytes(key.as_bytes())
+                .map_err(|_| format!("Invalid header {}", key))?;
+            metadata.insert(key, value);
+        }
+
+        Ok(req)
+    }
+}
@@ -1,5 +1,11 @@
 //! Helper that helps with interactive editing, including multi-line parsing and validation,
-//! and auto-completion for file name during creating external table.
+//! syntax highlighting, auto-completion for file name during creating external table,
+//! and auto-completion for keywords, functions and schema objects.
+
+use std::borrow::Cow;
+use std::iter::Peekable;
+use std::str::CharIndices;
+use std::sync::Mutex;
 
 use datafusion::sql::parser::{DFParser, Statement};
 use rustyline::completion::{Completer, FilenameCompleter, Pair};
@@ -9,12 +15,117 @@ use rustyline::hint::Hinter;
 use rustyline::validate::{ValidationContext, ValidationResult, Validator};
 use rustyline::{Context, Helper, Result};
 
-#[derive(Default)]
+use crate::completion::{SchemaCache, KEYWORDS};
+
+const KEYWORD_COLOR: &str = "\x1b[1;34m";
+const STRING_COLOR: &str = "\x1b[32m";
+const NUMBER_COLOR: &str = "\x1b[33m";
+const COMMENT_COLOR: &str = "\x1b[90m";
+const RESET_COLOR: &str = "\x1b[0m";
+
 pub struct CliHelper {
     completer: FilenameCompleter,
+    schema: Mutex<SchemaCache>,
+}
+
+impl CliHelper {
+    pub fn new(schema: SchemaCache) -> Self {
+        Self {
+            completer: FilenameCompleter::new(),
+            schema: Mutex::new(schema),
+        }
+    }
+
+    /// Set the database whose tables are completed
+    pub fn set_database(&self, database: &str) {
+        if let Ok(mut schema) = self.schema.lock() {
+            schema.set_database(database);
+        }
+    }
+
+    /// Fetch the names of the schema objects again on the next completion
+    pub fn invalidate_schema(&self) {
+        if let Ok(mut schema) = self.schema.lock() {
+            schema.invalidate();
+        }
+    }
+}
+
+impl Highlighter for CliHelper {
+    fn highlight<'l>(&self, line: &'l str, _pos: usize) -> Cow<'l, str> {
+        if line.starts_with('\\') {
+            return Cow::Borrowed(line);
+        }
+        Cow::Owned(highlight_sql(line))
+    }
+
+    fn highlight_char(&self, _line: &str, _pos: usize) -> bool {
+        true
+    }
+}
+
+/// Color keywords, string literals, numbers and comments of the sql
+fn highlight_sql(sql: &str) -> String {
+    let mut highlighted = String::with_capacity(sql.len() * 2);
+    let mut chars = sql.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        match c {
+            // Quotes are escaped by doubling, which are highlighted as adjacent literals
+            '\'' | '"' => {
+                skip_while(&mut chars, |e| e != c);
+                let end = chars.next().map(|(i, _)| i + 1).unwrap_or(sql.len());
+                if c == '"' {
+                    // Quoted identifiers are not highlighted
+                    highlighted.push_str(&sql[start..end]);
+                } else {
+                    push_colored(&mut highlighted, STRING_COLOR, &sql[start..end]);
+                }
+            }
+            '-' if sql[start..].starts_with("--") => {
+                let end = skip_while(&mut chars, |e| e != '\n').unwrap_or(sql.len());
+                push_colored(&mut highlighted, COMMENT_COLOR, &sql[start..end]);
+            }
+            c if c.is_ascii_digit() => {
+                let end = skip_while(&mut chars, |e| e.is_ascii_alphanumeric() || e == '.')
+                    .unwrap_or(sql.len());
+                push_colored(&mut highlighted, NUMBER_COLOR, &sql[start..end]);
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let end = skip_while(&mut chars, |e| e.is_alphanumeric() || e == '_')
+                    .unwrap_or(sql.len());
+                let word = &sql[start..end];
+                if KEYWORDS.iter().any(|k| k.eq_ignore_ascii_case(word)) {
+                    push_colored(&mut highlighted, KEYWORD_COLOR, word);
+                } else {
+                    highlighted.push_str(word);
+                }
+            }
+            c => highlighted.push(c),
+        }
+    }
+
+    highlighted
+}
+
+/// Skip the chars matching the predicate, returns the index of the next char
+fn skip_while(
+    chars: &mut Peekable<CharIndices>,
+    predicate: impl Fn(char) -> bool,
+) -> Option<usize> {
+    while let Some(&(i, c)) = chars.peek() {
+        if !predicate(c) {
+            return Some(i);
+        }
+        chars.next();
+    }
+    None
 }
 
-impl Highlighter for CliHelper {}
+fn push_colored(s: &mut String, color: &str, text: &str) {
+    s.push_str(color);
+    s.push_str(text);
+    s.push_str(RESET_COLOR);
+}
 
 impl Hinter for CliHelper {
     type Hint = String;
@@ -43,10 +154,22 @@ impl Completer for CliHelper {
         ctx: &Context<'_>,
     ) -> std::result::Result<(usize, Vec<Pair>), ReadlineError> {
         if is_open_quote_for_location(line, pos) {
-            self.completer.complete(line, pos, ctx)
-        } else {
-            Ok((0, Vec::with_capacity(0)))
+            return self.completer.complete(line, pos, ctx);
         }
+
+        let (start, candidates) = match self.schema.lock() {
+            Ok(mut schema) => schema.complete(line, pos),
+            Err(_) => return Ok((0, Vec::with_capacity(0))),
+        };
+        let candidates = candidates
+            .into_iter()
+            .map(|e| Pair {
+                display: e.clone(),
+                replacement: e,
+            })
+            .collect();
+
+        Ok((start, candidates))
     }
 }
 
@@ -71,3 +194,23 @@ impl Validator for CliHelper {
 }
 
 impl Helper for CliHelper {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highlight_sql() {
+        assert_eq!(
+            highlight_sql("select \"time\", 'it''s' from t where v > 1.5 -- c"),
+            format!(
+                "{k}select{r} \"time\", {s}'it'{r}{s}'s'{r} {k}from{r} t {k}where{r} v > {n}1.5{r} {c}-- c{r}",
+                k = KEYWORD_COLOR,
+                s = STRING_COLOR,
+                n = NUMBER_COLOR,
+                c = COMMENT_COLOR,
+                r = RESET_COLOR,
+            )
+        );
+    }
+}
@@ -0,0 +1,343 @@
+//! Bulk import of files through the write api
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::sync::Arc;
+
+use datafusion::arrow::csv;
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use futures::{stream, StreamExt};
+
+use crate::ctx::SessionContext;
+use crate::line_protocol::{batch_to_lines, precision_nanos, TableColumns};
+
+/// Number of rows read from csv and parquet files at once
+const READ_BATCH_SIZE: usize = 8192;
+
+#[derive(Debug, PartialEq, Eq, clap::ArgEnum, Clone, Copy)]
+pub enum ImportFormat {
+    Csv,
+    Parquet,
+    /// Line protocol
+    Lp,
+}
+
+#[derive(Debug, PartialEq, Eq, clap::Args)]
+pub struct ImportArgs {
+    #[clap(long, arg_enum, help = "Format of the files")]
+    pub format: ImportFormat,
+
+    #[clap(
+        long,
+        help = "Table the csv or parquet files are imported into, the measurements of line protocol are kept"
+    )]
+    pub table: Option<String>,
+
+    #[clap(
+        long,
+        help = "Number of records written by a request",
+        default_value = "10000"
+    )]
+    pub batch_size: usize,
+
+    #[clap(
+        long,
+        help = "Number of concurrent write requests",
+        default_value = "4"
+    )]
+    pub parallel: usize,
+
+    #[clap(
+        long,
+        help = "Skip the records before the offset, used to resume a failed import",
+        default_value = "0"
+    )]
+    pub offset: u64,
+
+    #[clap(required = true, multiple_values = true, help = "Files to import")]
+    pub files: Vec<String>,
+}
+
+/// Import the files into the current database.
+///
+/// Records of all the files are numbered in order, the import can be resumed from the offset
+/// reported on failure, all the records before it have been written.
+pub async fn import(ctx: &SessionContext, args: &ImportArgs, quiet: bool) -> Result<(), String> {
+    if args.batch_size == 0 || args.parallel == 0 {
+        return Err("batch size and parallel must be greater than 0".to_string());
+    }
+
+    let table = match (args.format, &args.table) {
+        (ImportFormat::Lp, _) => None,
+        (_, Some(table)) => Some((
+            table.as_str(),
+            describe_table(ctx, table).await?,
+            database_precision(ctx).await?,
+        )),
+        (_, None) => return Err("--table is required to import csv or parquet files".to_string()),
+    };
+
+    let mut offset = 0_u64;
+    for file in args.files.iter() {
+        let lines: Box<dyn Iterator<Item = Result<Vec<String>, String>> + '_> =
+            match (args.format, &table) {
+                (ImportFormat::Lp, _) => Box::new(read_line_protocol(file)?),
+                (format, Some((table, columns, precision))) => {
+                    let batches: Box<dyn Iterator<Item = Result<RecordBatch, String>>> =
+                        match format {
+                            ImportFormat::Parquet => Box::new(read_parquet(file)?),
+                            _ => Box::new(read_csv(file, columns)?),
+                        };
+                    Box::new(
+                        batches
+                            .map(move |batch| batch_to_lines(table, columns, *precision, &batch?)),
+                    )
+                }
+                _ => unreachable!(),
+            };
+
+        // The file is read while the bodies are written, the records imported before are skipped
+        let mut bodies = RequestBodies::new(lines, offset, args.offset, args.batch_size);
+
+        // Write in parallel, the results are returned in order
+        let mut results = stream::iter(&mut bodies)
+            .map(|body| async move {
+                let (end, body) = body?;
+                ctx.write_line_protocol(body).await.map(|_| end)
+            })
+            .buffered(args.parallel);
+        while let Some(result) = results.next().await {
+            match result {
+                Ok(end) => {
+                    offset = end;
+                    if !quiet {
+                        println!("{}: {} records imported", file, offset);
+                    }
+                }
+                Err(err) => {
+                    // The records before the resume offset have been imported by the previous run
+                    return Err(format!(
+                        "Failed to import {}: {}, resume with --offset {}",
+                        file,
+                        err,
+                        offset.max(args.offset)
+                    ));
+                }
+            }
+        }
+        drop(results);
+        offset = bodies.offset();
+    }
+
+    if !quiet {
+        println!("Import completed, {} records in total", offset);
+    }
+
+    Ok(())
+}
+
+/// Request bodies of the lines of a file, which are read batch by batch.
+///
+/// The lines are numbered from the offset the file starts at, the lines before `resume_offset`
+/// are skipped. Yields the bodies of `batch_size` lines with the offset after them.
+struct RequestBodies<I> {
+    batches: I,
+    /// Offset after the lines read
+    offset: u64,
+    resume_offset: u64,
+    batch_size: usize,
+    /// Lines read but not yet yielded
+    lines: Vec<String>,
+}
+
+impl<I> RequestBodies<I> {
+    fn new(batches: I, start: u64, resume_offset: u64, batch_size: usize) -> Self {
+        Self {
+            batches,
+            offset: start,
+            resume_offset,
+            batch_size,
+            lines: vec![],
+        }
+    }
+
+    /// Offset after the lines read, the offset the next file starts at once all are read
+    fn offset(&self) -> u64 {
+        self.offset
+    }
+}
+
+impl<I> Iterator for RequestBodies<I>
+where
+    I: Iterator<Item = Result<Vec<String>, String>>,
+{
+    type Item = Result<(u64, Vec<u8>), String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.lines.len() < self.batch_size {
+            match self.batches.next() {
+                Some(Ok(batch)) => {
+                    let skip = self
+                        .resume_offset
+                        .saturating_sub(self.offset)
+                        .min(batch.len() as u64);
+                    self.offset += batch.len() as u64;
+                    self.lines.extend(batch.into_iter().skip(skip as usize));
+                }
+                Some(Err(err)) => return Some(Err(err)),
+                None => break,
+            }
+        }
+        if self.lines.is_empty() {
+            return None;
+        }
+
+        let len = self.lines.len().min(self.batch_size);
+        let body = self.lines.drain(..len).collect::<Vec<_>>().join("\n");
+        let end = self.offset - self.lines.len() as u64;
+
+        Some(Ok((end, body.into_bytes())))
+    }
+}
+
+pub async fn describe_table(ctx: &SessionContext, table: &str) -> Result<TableColumns, String> {
+    let csv = ctx.sql_csv(format!("DESCRIBE TABLE {}", table)).await?;
+    TableColumns::from_describe_csv(&csv)
+}
+
+/// Number of nanoseconds of a unit of the precision of the current database
+pub async fn database_precision(ctx: &SessionContext) -> Result<i64, String> {
+    let csv = ctx
+        .sql_csv(format!("DESCRIBE DATABASE {}", ctx.get_database()))
+        .await?;
+    // TTL,SHARD,VNODE_DURATION,REPLICA,PRECISION
+    let precision = csv
+        .lines()
+        .nth(1)
+        .and_then(|e| e.rsplit(',').next())
+        .ok_or_else(|| format!("Unexpected result of DESCRIBE DATABASE: {}", csv))?;
+    precision_nanos(precision)
+}
+
+/// Read the lines of the file in batches, blank lines and comments are skipped
+fn read_line_protocol(
+    file: &str,
+) -> Result<impl Iterator<Item = Result<Vec<String>, String>>, String> {
+    let reader = BufReader::new(File::open(file).map_err(|e| format!("{}: {}", file, e))?);
+    let file = file.to_string();
+    let mut lines = reader.lines();
+
+    Ok(std::iter::from_fn(move || {
+        let mut batch = vec![];
+        while batch.len() < READ_BATCH_SIZE {
+            match lines.next() {
+                Some(Ok(line)) => {
+                    let line = line.trim();
+                    if !line.is_empty() && !line.starts_with('#') {
+                        batch.push(line.to_string());
+                    }
+                }
+                Some(Err(e)) => return Some(Err(format!("{}: {}", file, e))),
+                None => break,
+            }
+        }
+        if batch.is_empty() {
+            None
+        } else {
+            Some(Ok(batch))
+        }
+    }))
+}
+
+/// Read the file in record batches, the types of the columns are taken from the table
+fn read_csv(
+    file: &str,
+    columns: &TableColumns,
+) -> Result<impl Iterator<Item = Result<RecordBatch, String>>, String> {
+    let map_err = |e: datafusion::arrow::error::ArrowError| format!("{}: {}", file, e);
+
+    let mut reader = BufReader::new(File::open(file).map_err(|e| format!("{}: {}", file, e))?);
+    let (inferred, _) =
+        csv::reader::infer_reader_schema(&mut reader, b',', None, true).map_err(map_err)?;
+    reader
+        .seek(SeekFrom::Start(0))
+        .map_err(|e| format!("{}: {}", file, e))?;
+
+    let file = file.to_string();
+    let batches = csv::ReaderBuilder::new()
+        .with_schema(Arc::new(columns.csv_schema(&inferred)))
+        .has_header(true)
+        .with_batch_size(READ_BATCH_SIZE)
+        .build(reader)
+        .map_err(map_err)?
+        .map(move |e| e.map_err(|e| format!("{}: {}", file, e)));
+
+    Ok(batches)
+}
+
+/// Read the file in record batches
+fn read_parquet(file: &str) -> Result<impl Iterator<Item = Result<RecordBatch, String>>, String> {
+    let reader = File::open(file).map_err(|e| format!("{}: {}", file, e))?;
+    let file = file.to_string();
+    let batches = ParquetRecordBatchReaderBuilder::try_new(reader)
+        .and_then(|e| e.with_batch_size(READ_BATCH_SIZE).build())
+        .map_err(|e| format!("{}: {}", file, e))?
+        .map(move |e| e.map_err(|e| format!("{}: {}", file, e)));
+
+    Ok(batches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bodies(
+        lines: usize,
+        read_batch_size: usize,
+        start: u64,
+        resume_offset: u64,
+        batch_size: usize,
+    ) -> (Vec<(u64, Vec<u8>)>, u64) {
+        let lines = (0..lines)
+            .map(|i| format!("m f={}i", i))
+            .collect::<Vec<_>>();
+        let batches = lines
+            .chunks(read_batch_size)
+            .map(|e| Ok(e.to_vec()))
+            .collect::<Vec<Result<_, String>>>();
+
+        let mut bodies = RequestBodies::new(batches.into_iter(), start, resume_offset, batch_size);
+        let result = (&mut bodies).collect::<Result<Vec<_>, _>>().unwrap();
+        (result, bodies.offset())
+    }
+
+    #[test]
+    fn test_request_bodies() {
+        let (chunks, offset) = bodies(5, 3, 10, 0, 2);
+        assert_eq!(offset, 15);
+        assert_eq!(
+            chunks,
+            vec![
+                (12, b"m f=0i\nm f=1i".to_vec()),
+                (14, b"m f=2i\nm f=3i".to_vec()),
+                (15, b"m f=4i".to_vec()),
+            ]
+        );
+
+        // Resume from the middle of the file
+        let (chunks, offset) = bodies(5, 2, 10, 13, 2);
+        assert_eq!(offset, 15);
+        assert_eq!(chunks, vec![(15, b"m f=3i\nm f=4i".to_vec())]);
+
+        // The file is skipped entirely
+        let (chunks, offset) = bodies(5, 2, 10, 100, 2);
+        assert_eq!(offset, 15);
+        assert!(chunks.is_empty());
+
+        // Errors of reading are returned
+        let batches = vec![Ok(vec!["m f=0i".to_string()]), Err("broken".to_string())];
+        let mut bodies = RequestBodies::new(batches.into_iter(), 0, 0, 2);
+        assert_eq!(bodies.next(), Some(Err("broken".to_string())));
+    }
+}
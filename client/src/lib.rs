@@ -2,10 +2,15 @@
 pub const CNOSDB_CLI_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 pub mod command;
+pub mod completion;
 pub mod config;
 pub mod ctx;
 pub mod exec;
+pub mod export;
+pub mod flight;
 pub mod functions;
 pub mod helper;
+pub mod import;
+pub mod line_protocol;
 pub mod print_format;
 pub mod print_options;
@@ -0,0 +1,249 @@
+//! Conversion between record batches and line protocol
+
+use datafusion::arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::scalar::ScalarValue;
+
+/// Columns of a table, parsed from the csv result of `DESCRIBE TABLE`
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct TableColumns {
+    pub time: String,
+    pub tags: Vec<String>,
+    /// Name and sql type of the fields
+    pub fields: Vec<(String, String)>,
+}
+
+impl TableColumns {
+    /// Parse the csv result of `DESCRIBE TABLE` with header
+    /// `COLUMN_NAME,DATA_TYPE,COLUMN_TYPE,COMPRESSION_CODEC`
+    pub fn from_describe_csv(csv: &str) -> Result<Self, String> {
+        let mut columns = Self::default();
+        for line in csv.lines().skip(1).filter(|e| !e.is_empty()) {
+            let items = line.split(',').collect::<Vec<_>>();
+            match items.as_slice() {
+                [name, _, "TIME", ..] => columns.time = name.to_string(),
+                [name, _, "TAG", ..] => columns.tags.push(name.to_string()),
+                [name, data_type, "FIELD", ..] => columns
+                    .fields
+                    .push((name.to_string(), data_type.to_string())),
+                _ => return Err(format!("Unexpected column of table: {}", line)),
+            }
+        }
+
+        if columns.time.is_empty() {
+            return Err("Only the tables of CnosDB can be imported or exported".to_string());
+        }
+
+        Ok(columns)
+    }
+
+    /// Arrow type of a field of the table
+    pub fn field_type(&self, name: &str) -> Option<DataType> {
+        let (_, sql_type) = self.fields.iter().find(|(e, _)| e == name)?;
+        let data_type = match sql_type.as_str() {
+            "BIGINT" => DataType::Int64,
+            "BIGINT UNSIGNED" => DataType::UInt64,
+            "DOUBLE" => DataType::Float64,
+            "BOOLEAN" => DataType::Boolean,
+            _ => DataType::Utf8,
+        };
+        Some(data_type)
+    }
+
+    /// Schema of a csv file of the table, the types of the fields in the table are used instead
+    /// of the inferred ones, tags are read as strings
+    pub fn csv_schema(&self, inferred: &Schema) -> Schema {
+        let fields = inferred
+            .fields()
+            .iter()
+            .map(|f| {
+                let data_type = if f.name() == &self.time {
+                    match f.data_type() {
+                        DataType::Int64 | DataType::Timestamp(_, _) => f.data_type().clone(),
+                        _ => time_type(),
+                    }
+                } else if self.tags.contains(f.name()) {
+                    DataType::Utf8
+                } else {
+                    self.field_type(f.name())
+                        .unwrap_or_else(|| f.data_type().clone())
+                };
+                Field::new(f.name(), data_type, true)
+            })
+            .collect::<Vec<_>>();
+
+        Schema::new(fields)
+    }
+}
+
+/// Number of nanoseconds of a unit of the database precision, `MS`, `US` or `NS`
+pub fn precision_nanos(precision: &str) -> Result<i64, String> {
+    match precision.to_uppercase().as_str() {
+        "MS" => Ok(1_000_000),
+        "US" => Ok(1_000),
+        "NS" => Ok(1),
+        _ => Err(format!("Unknown precision {}", precision)),
+    }
+}
+
+/// Convert the rows of the batch into lines of the table.
+///
+/// Columns in `columns.tags` are written as tags, the time column as timestamp in the database
+/// precision, the others as fields. Rows without any non-null field are skipped.
+pub fn batch_to_lines(
+    table: &str,
+    columns: &TableColumns,
+    precision_nanos: i64,
+    batch: &RecordBatch,
+) -> Result<Vec<String>, String> {
+    let schema = batch.schema();
+    let time_idx = schema
+        .index_of(&columns.time)
+        .map_err(|_| format!("Column {} not found", columns.time))?;
+
+    let mut lines = Vec::with_capacity(batch.num_rows());
+    for row in 0..batch.num_rows() {
+        let mut tags = String::new();
+        let mut fields = String::new();
+        for (idx, field) in schema.fields().iter().enumerate() {
+            if idx == time_idx {
+                continue;
+            }
+            let value = scalar_value(batch, idx, row)?;
+            if value.is_null() {
+                continue;
+            }
+
+            if columns.tags.contains(field.name()) {
+                tags.push(',');
+                tags.push_str(&escape(field.name(), &[',', '=', ' ']));
+                tags.push('=');
+                tags.push_str(&escape(&value.to_string(), &[',', '=', ' ']));
+            } else if let Some(value) = field_value(value) {
+                if !fields.is_empty() {
+                    fields.push(',');
+                }
+                fields.push_str(&escape(field.name(), &[',', '=', ' ']));
+                fields.push('=');
+                fields.push_str(&value);
+            }
+        }
+        if fields.is_empty() {
+            continue;
+        }
+
+        let timestamp = match scalar_value(batch, time_idx, row)? {
+            ScalarValue::TimestampSecond(Some(v), _) => v * 1_000_000_000 / precision_nanos,
+            ScalarValue::TimestampMillisecond(Some(v), _) => v * 1_000_000 / precision_nanos,
+            ScalarValue::TimestampMicrosecond(Some(v), _) => v * 1_000 / precision_nanos,
+            ScalarValue::TimestampNanosecond(Some(v), _) => v / precision_nanos,
+            // Integers are taken as timestamps in the database precision
+            ScalarValue::Int64(Some(v)) => v,
+            v => return Err(format!("Invalid time {} of row {}", v, row)),
+        };
+
+        lines.push(format!(
+            "{}{} {} {}",
+            escape(table, &[',', ' ']),
+            tags,
+            fields,
+            timestamp
+        ));
+    }
+
+    Ok(lines)
+}
+
+/// Type of the time column read from files, timestamps are parsed in nanoseconds
+pub fn time_type() -> DataType {
+    DataType::Timestamp(TimeUnit::Nanosecond, None)
+}
+
+fn scalar_value(batch: &RecordBatch, column: usize, row: usize) -> Result<ScalarValue, String> {
+    ScalarValue::try_from_array(batch.column(column), row).map_err(|e| e.to_string())
+}
+
+/// `None` if the value can't be written, e.g. NaN
+fn field_value(value: ScalarValue) -> Option<String> {
+    let value = match value {
+        ScalarValue::Int8(Some(v)) => format!("{}i", v),
+        ScalarValue::Int16(Some(v)) => format!("{}i", v),
+        ScalarValue::Int32(Some(v)) => format!("{}i", v),
+        ScalarValue::Int64(Some(v)) => format!("{}i", v),
+        ScalarValue::UInt8(Some(v)) => format!("{}u", v),
+        ScalarValue::UInt16(Some(v)) => format!("{}u", v),
+        ScalarValue::UInt32(Some(v)) => format!("{}u", v),
+        ScalarValue::UInt64(Some(v)) => format!("{}u", v),
+        ScalarValue::Float32(Some(v)) if v.is_finite() => format!("{:?}", v),
+        ScalarValue::Float64(Some(v)) if v.is_finite() => format!("{:?}", v),
+        ScalarValue::Float32(_) | ScalarValue::Float64(_) => return None,
+        ScalarValue::Boolean(Some(v)) => v.to_string(),
+        v => format!("\"{}\"", escape(&v.to_string(), &['"', '\\'])),
+    };
+    Some(value)
+}
+
+fn escape(s: &str, special: &[char]) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        if special.contains(&c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use datafusion::arrow::array::{
+        Float64Array, Int64Array, StringArray, TimestampNanosecondArray,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_batch_to_lines() {
+        let columns = TableColumns::from_describe_csv(
+            "COLUMN_NAME,DATA_TYPE,COLUMN_TYPE,COMPRESSION_CODEC\n\
+             time,TIMESTAMP,TIME,DEFAULT\n\
+             host,STRING,TAG,DEFAULT\n\
+             usage,DOUBLE,FIELD,DEFAULT\n\
+             msg,STRING,FIELD,DEFAULT\n",
+        )
+        .unwrap();
+        assert_eq!(columns.field_type("usage"), Some(DataType::Float64));
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("time", time_type(), false),
+            Field::new("host", DataType::Utf8, true),
+            Field::new("usage", DataType::Float64, true),
+            Field::new("msg", DataType::Utf8, true),
+            Field::new("count", DataType::Int64, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(TimestampNanosecondArray::from(vec![
+                    1_000_000, 2_000_000, 3_000_000,
+                ])),
+                Arc::new(StringArray::from(vec![Some("a b"), None, Some("c")])),
+                Arc::new(Float64Array::from(vec![Some(1.0), Some(0.5), None])),
+                Arc::new(StringArray::from(vec![Some("say \"hi\""), None, None])),
+                Arc::new(Int64Array::from(vec![Some(1), None, None])),
+            ],
+        )
+        .unwrap();
+
+        let lines = batch_to_lines("cpu", &columns, 1_000_000, &batch).unwrap();
+        assert_eq!(
+            lines,
+            vec![
+                r#"cpu,host=a\ b usage=1.0,msg="say \"hi\"",count=1i 1"#.to_string(),
+                "cpu usage=0.5 2".to_string(),
+            ]
+        );
+    }
+}
@@ -2,8 +2,10 @@ use std::env;
 use std::net::IpAddr;
 use std::path::Path;
 
-use clap::Parser;
-use client::ctx::{SessionConfig, SessionContext};
+use clap::{Parser, Subcommand};
+use client::ctx::{Protocol, SessionConfig, SessionContext};
+use client::export::{export, ExportArgs};
+use client::import::{import, ImportArgs};
 use client::print_format::PrintFormat;
 use client::print_options::PrintOptions;
 use client::{exec, CNOSDB_CLI_VERSION};
@@ -30,6 +32,22 @@ struct Args {
     )]
     port: usize,
 
+    #[clap(
+        long,
+        help = "CnosDB server flight sql port",
+        default_value = "31004",
+        validator(is_valid_port)
+    )]
+    flight_port: usize,
+
+    #[clap(
+        long,
+        arg_enum,
+        help = "Protocol the statements are executed by",
+        default_value_t = Protocol::Http
+    )]
+    protocol: Protocol,
+
     #[clap(
         short,
         long,
@@ -104,6 +122,17 @@ struct Args {
         help = "Reduce printing other than the results and work quietly"
     )]
     quiet: bool,
+
+    #[clap(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand, PartialEq)]
+enum Command {
+    /// Import csv, parquet or line protocol files into the database
+    Import(ImportArgs),
+    /// Export the result of a query into a csv, parquet or line protocol file
+    Export(ExportArgs),
 }
 
 #[tokio::main]
@@ -124,6 +153,8 @@ pub async fn main() -> Result<()> {
     let session_config = SessionConfig::from_env()
         .with_host(args.host)
         .with_port(args.port)
+        .with_flight_port(args.flight_port)
+        .with_protocol(args.protocol)
         .with_user(args.user)
         .with_password(args.password)
         .with_tenant(args.tenant)
@@ -138,6 +169,30 @@ pub async fn main() -> Result<()> {
         quiet: args.quiet,
     };
 
+    match &args.command {
+        Some(Command::Import(import_args)) => {
+            if let Err(err) = import(&ctx, import_args, args.quiet).await {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Some(Command::Export(export_args)) => {
+            match export(&ctx, export_args).await {
+                Ok(num_records) if !args.quiet => {
+                    println!("{} records exported to {}", num_records, export_args.output)
+                }
+                Ok(_) => {}
+                Err(err) => {
+                    eprintln!("{}", err);
+                    std::process::exit(1);
+                }
+            }
+            return Ok(());
+        }
+        None => {}
+    }
+
     let files = args.file;
     let rc = match args.rc {
         Some(file) => file,
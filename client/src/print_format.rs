@@ -82,6 +82,91 @@ impl PrintFormat {
     }
 }
 
+/// Prints the record batches of a result once they are received, the output is the same as
+/// [`PrintFormat::print_batches`] of all the batches, except that each batch is printed as
+/// a table of its own in the table format.
+pub struct BatchPrinter {
+    format: PrintFormat,
+    /// Batches without rows received before any rows, printed if the result has no rows
+    empty_batches: Vec<RecordBatch>,
+    row_count: usize,
+}
+
+impl BatchPrinter {
+    pub fn new(format: PrintFormat) -> Self {
+        Self {
+            format,
+            empty_batches: vec![],
+            row_count: 0,
+        }
+    }
+
+    pub fn print(&mut self, batch: &RecordBatch) -> Result<()> {
+        print!("{}", self.format_batch(batch)?);
+        Ok(())
+    }
+
+    /// Print the end of the result, returns the number of rows printed
+    pub fn finish(self) -> Result<usize> {
+        if self.row_count == 0 {
+            self.format.print_batches(&self.empty_batches)?;
+        } else {
+            print!("{}", self.format_end());
+        }
+        Ok(self.row_count)
+    }
+
+    fn format_batch(&mut self, batch: &RecordBatch) -> Result<String> {
+        if batch.num_rows() == 0 {
+            if self.row_count == 0 {
+                self.empty_batches.push(batch.clone());
+            }
+            return Ok(String::new());
+        }
+
+        let first = self.row_count == 0;
+        self.row_count += batch.num_rows();
+        let batches = std::slice::from_ref(batch);
+        let formatted = match self.format {
+            PrintFormat::Csv => batch_to_csv(batch, b',', first)?,
+            PrintFormat::Tsv => batch_to_csv(batch, b'\t', first)?,
+            PrintFormat::Table => format!("{}\n", pretty::pretty_format_batches(batches)?),
+            PrintFormat::Json => {
+                let json = batches_to_json!(ArrayWriter, batches);
+                // Rows of the batch without the brackets of the array
+                let rows = &json[1..json.len() - 1];
+                if first {
+                    format!("[{}", rows)
+                } else {
+                    format!(",{}", rows)
+                }
+            }
+            PrintFormat::NdJson => batches_to_json!(LineDelimitedWriter, batches),
+        };
+        Ok(formatted)
+    }
+
+    fn format_end(&self) -> &'static str {
+        match self.format {
+            PrintFormat::Table => "",
+            PrintFormat::Json => "]\n",
+            PrintFormat::Csv | PrintFormat::Tsv | PrintFormat::NdJson => "\n",
+        }
+    }
+}
+
+fn batch_to_csv(batch: &RecordBatch, delimiter: u8, with_header: bool) -> Result<String> {
+    let mut bytes = vec![];
+    {
+        let builder = WriterBuilder::new()
+            .has_headers(with_header)
+            .with_delimiter(delimiter);
+        let mut writer = builder.build(&mut bytes);
+        writer.write(batch)?;
+    }
+    String::from_utf8(bytes).map_err(|e| DataFusionError::Execution(e.to_string()))
+}
+
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
@@ -157,4 +242,40 @@ mod tests {
         );
         Ok(())
     }
+
+    #[test]
+    fn test_batch_printer() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+        let batches = [vec![], vec![1, 2], vec![3]]
+            .into_iter()
+            .map(|values| {
+                RecordBatch::try_new(schema.clone(), vec![Arc::new(Int32Array::from(values))])
+            })
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        let print = |format: PrintFormat| -> Result<String> {
+            let mut printer = BatchPrinter::new(format);
+            let mut output = String::new();
+            for batch in &batches {
+                output.push_str(&printer.format_batch(batch)?);
+            }
+            output.push_str(printer.format_end());
+            assert_eq!(printer.row_count, 3);
+            Ok(output)
+        };
+
+        assert_eq!(
+            print(PrintFormat::Csv)?,
+            format!("{}\n", print_batches_with_sep(&batches, b',')?)
+        );
+        assert_eq!(
+            print(PrintFormat::Json)?,
+            format!("{}\n", batches_to_json!(ArrayWriter, &batches))
+        );
+        assert_eq!(
+            print(PrintFormat::NdJson)?,
+            format!("{}\n", batches_to_json!(LineDelimitedWriter, &batches))
+        );
+        Ok(())
+    }
 }
@@ -1,7 +1,7 @@
 use std::time::Instant;
 
-use crate::ctx::ResultSet;
-use crate::print_format::PrintFormat;
+use crate::ctx::{ResultSet, SessionContext};
+use crate::print_format::{BatchPrinter, PrintFormat};
 
 #[derive(Debug, Clone)]
 pub struct PrintOptions {
@@ -29,4 +29,49 @@ impl PrintOptions {
         }
         Ok(())
     }
+
+    /// Execute the statement and print the result, the record batches are printed once
+    /// they are received if the protocol is Flight SQL
+    pub async fn print_sql(
+        &self,
+        ctx: &SessionContext,
+        sql: String,
+        now: Instant,
+    ) -> Result<(), String> {
+        if !ctx.is_flight() {
+            let results = ctx.sql(sql).await?;
+            return self.print_batches(&results, now);
+        }
+
+        let mut printer = BatchPrinter::new(self.format);
+        ctx.sql_for_each(sql, |batch| {
+            printer.print(&batch).map_err(|e| e.to_string())
+        })
+        .await?;
+        self.finish(printer, now)
+    }
+
+    /// Execute the prepared statement and print the record batches once they are received
+    pub async fn print_prepared(
+        &self,
+        ctx: &SessionContext,
+        name: &str,
+        now: Instant,
+    ) -> Result<(), String> {
+        let mut printer = BatchPrinter::new(self.format);
+        ctx.execute_prepared_for_each(name, |batch| {
+            printer.print(&batch).map_err(|e| e.to_string())
+        })
+        .await?;
+        self.finish(printer, now)
+    }
+
+    fn finish(&self, printer: BatchPrinter, now: Instant) -> Result<(), String> {
+        let row_count = printer.finish().map_err(|e| e.to_string())?;
+
+        if !self.quiet {
+            print_timing_info(row_count, now);
+        }
+        Ok(())
+    }
 }
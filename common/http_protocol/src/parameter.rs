@@ -8,6 +8,23 @@ pub struct SqlParam {
     pub chunked: Option<String>,
     // Number of partitions for query execution. Increasing partitions can increase concurrency.
     pub target_partitions: Option<usize>,
+    // The maximum memory in bytes that the query can use.
+    pub query_memory_limit: Option<usize>,
+    // Priority class of the query, interactive or batch.
+    pub priority: Option<String>,
+    // The query is canceled if it is not finished in milliseconds.
+    pub query_timeout_ms: Option<u64>,
+    // Time zone used to interpret and display timestamps, e.g. +08:00.
+    pub time_zone: Option<String>,
+    // Precision of timestamps in the query result, ms, us or ns.
+    pub precision: Option<String>,
+    // Maximum number of rows of the record batches during execution.
+    pub batch_size: Option<usize>,
+    // Consistency level of INSERT, any, one, quorum or all.
+    pub consistency: Option<String>,
+    // The client keeps the variables changed by SET and RESET and sends them with the subsequent requests,
+    // otherwise SET and RESET are rejected because requests are stateless.
+    pub client_session: Option<bool>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
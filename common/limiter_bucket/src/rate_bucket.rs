@@ -182,7 +182,9 @@ impl RateBucket {
     fn update_critical(&self, critical: &mut MutexGuard<Critical>) {
         if let Some((tokens, deadline)) = calculate_drain(critical.deadline, self.interval) {
             critical.deadline = deadline;
-            critical.balance = critical.balance.saturating_add(tokens);
+            critical.balance = critical
+                .balance
+                .saturating_add(tokens.saturating_mul(self.refill));
 
             if critical.balance > self.max {
                 critical.balance = self.max;
@@ -339,6 +341,7 @@ fn calculate_drain(
     let millis = interval.num_milliseconds();
     let since = now.signed_duration_since(deadline).num_milliseconds();
 
+    // Number of intervals passed, `refill` tokens are added for each of them
     let tokens = usize::try_from(since / millis + 1).unwrap_or(usize::MAX);
 
     let rem = since % millis;
@@ -360,3 +363,17 @@ fn test_serialize_rate_limiter() {
     let limiter2 = bincode::deserialize(data.as_slice()).unwrap();
     assert_eq!(limiter1, limiter2)
 }
+
+#[test]
+fn test_refill() {
+    let bucket = RateBucket::builder()
+        .max(100)
+        .interval(chrono::Duration::milliseconds(50))
+        .initial(0)
+        .refill(10)
+        .build();
+    assert!(bucket.acquire(1).is_err());
+
+    std::thread::sleep(std::time::Duration::from_millis(60));
+    assert_eq!(bucket.acquire_closed(100), 10);
+}
@@ -0,0 +1,112 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use datafusion::error::{DataFusionError, Result};
+use datafusion::execution::memory_pool::{MemoryConsumer, MemoryPool, MemoryReservation};
+
+use crate::MemoryPoolRef;
+
+/// A bounded [`MemoryPool`] that charges every reservation to its parent as well.
+///
+/// Pools can be chained to build a hierarchy such as node -> tenant -> query,
+/// an allocation succeeds only if every pool on the path to the root has room for it.
+#[derive(Debug)]
+pub struct HierarchicalMemoryPool {
+    name: String,
+    limit: AtomicUsize,
+    used: AtomicUsize,
+    parent: Option<MemoryPoolRef>,
+}
+
+impl HierarchicalMemoryPool {
+    /// Create a root pool with the given limit in bytes
+    pub fn new(name: impl Into<String>, limit: usize) -> Self {
+        Self {
+            name: name.into(),
+            limit: AtomicUsize::new(limit),
+            used: AtomicUsize::new(0),
+            parent: None,
+        }
+    }
+
+    /// Create a pool whose reservations are also charged to `parent`
+    pub fn with_parent(name: impl Into<String>, limit: usize, parent: MemoryPoolRef) -> Self {
+        Self {
+            parent: Some(parent),
+            ..Self::new(name, limit)
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit.load(Ordering::Relaxed)
+    }
+
+    /// Change the limit of this pool.
+    ///
+    /// Memory already reserved is not released, only subsequent allocations are checked.
+    pub fn set_limit(&self, limit: usize) {
+        self.limit.store(limit, Ordering::Relaxed);
+    }
+}
+
+impl MemoryPool for HierarchicalMemoryPool {
+    fn register(&self, consumer: &MemoryConsumer) {
+        if let Some(parent) = &self.parent {
+            parent.register(consumer)
+        }
+    }
+
+    fn unregister(&self, consumer: &MemoryConsumer) {
+        if let Some(parent) = &self.parent {
+            parent.unregister(consumer)
+        }
+    }
+
+    fn grow(&self, reservation: &MemoryReservation, additional: usize) {
+        self.used.fetch_add(additional, Ordering::Relaxed);
+        if let Some(parent) = &self.parent {
+            parent.grow(reservation, additional)
+        }
+    }
+
+    fn shrink(&self, reservation: &MemoryReservation, shrink: usize) {
+        self.used.fetch_sub(shrink, Ordering::Relaxed);
+        if let Some(parent) = &self.parent {
+            parent.shrink(reservation, shrink)
+        }
+    }
+
+    fn try_grow(&self, reservation: &MemoryReservation, additional: usize) -> Result<()> {
+        let limit = self.limit();
+        self.used
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used| {
+                let new_used = used.checked_add(additional)?;
+                (new_used <= limit).then_some(new_used)
+            })
+            .map_err(|used| {
+                DataFusionError::ResourcesExhausted(format!(
+                    "Failed to allocate additional {} bytes in memory pool '{}' with {} bytes already allocated - maximum available is {}",
+                    additional,
+                    self.name,
+                    used,
+                    limit.saturating_sub(used)
+                ))
+            })?;
+
+        if let Some(parent) = &self.parent {
+            if let Err(err) = parent.try_grow(reservation, additional) {
+                self.used.fetch_sub(additional, Ordering::Relaxed);
+                return Err(err);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn reserved(&self) -> usize {
+        self.used.load(Ordering::Relaxed)
+    }
+}
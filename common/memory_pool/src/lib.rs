@@ -1,6 +1,9 @@
 use std::sync::Arc;
 
 pub use datafusion::execution::memory_pool::*;
+pub use hierarchical::HierarchicalMemoryPool;
+
+mod hierarchical;
 
 pub type MemoryPoolRef = Arc<dyn MemoryPool>;
 
@@ -38,4 +41,90 @@ mod tests {
         a2.try_grow(25).unwrap();
         assert_eq!(pool.reserved(), 25);
     }
+
+    #[test]
+    fn test_hierarchical_pool() {
+        let node: MemoryPoolRef = Arc::new(GreedyMemoryPool::new(100));
+        let tenant: MemoryPoolRef = Arc::new(HierarchicalMemoryPool::with_parent(
+            "tenant",
+            60,
+            node.clone(),
+        ));
+        let query: MemoryPoolRef = Arc::new(HierarchicalMemoryPool::with_parent(
+            "query",
+            40,
+            tenant.clone(),
+        ));
+
+        let mut a1 = MemoryConsumer::new("a1").register(&query);
+        a1.try_grow(30).unwrap();
+        assert_eq!(query.reserved(), 30);
+        assert_eq!(tenant.reserved(), 30);
+        assert_eq!(node.reserved(), 30);
+
+        // exceeds the query limit
+        a1.try_grow(20).unwrap_err();
+        assert_eq!(query.reserved(), 30);
+        assert_eq!(tenant.reserved(), 30);
+
+        // exceeds the tenant limit, query pool must be rolled back
+        let mut a2 = MemoryConsumer::new("a2").register(&tenant);
+        a2.try_grow(25).unwrap();
+        a1.try_grow(10).unwrap_err();
+        assert_eq!(query.reserved(), 30);
+        assert_eq!(tenant.reserved(), 55);
+        assert_eq!(node.reserved(), 55);
+
+        drop(a1);
+        assert_eq!(query.reserved(), 0);
+        assert_eq!(tenant.reserved(), 25);
+        assert_eq!(node.reserved(), 25);
+
+        drop(a2);
+        assert_eq!(node.reserved(), 0);
+    }
+
+    #[test]
+    fn test_hierarchical_pool_set_limit() {
+        let pool = HierarchicalMemoryPool::new("tenant", 10);
+        let pool: MemoryPoolRef = Arc::new(pool);
+        let mut a1 = MemoryConsumer::new("a1").register(&pool);
+        a1.try_grow(20).unwrap_err();
+
+        let pool = HierarchicalMemoryPool::new("tenant", 10);
+        pool.set_limit(20);
+        assert_eq!(pool.limit(), 20);
+        let pool: MemoryPoolRef = Arc::new(pool);
+        let mut a2 = MemoryConsumer::new("a2").register(&pool);
+        a2.try_grow(20).unwrap();
+
+        // change the limit of a pool in use
+        let pool = Arc::new(HierarchicalMemoryPool::new("tenant", 30));
+        let pool_ref: MemoryPoolRef = pool.clone();
+        let mut a3 = MemoryConsumer::new("a3").register(&pool_ref);
+        a3.try_grow(20).unwrap();
+
+        // shrink below the reserved memory, reservations are kept but can't grow
+        pool.set_limit(10);
+        assert_eq!(pool.reserved(), 20);
+        a3.try_grow(1).unwrap_err();
+        assert_eq!(pool.reserved(), 20);
+
+        // memory freed under the new limit can be reserved again
+        a3.shrink(15);
+        a3.try_grow(5).unwrap();
+        a3.try_grow(1).unwrap_err();
+        assert_eq!(pool.reserved(), 10);
+
+        // raise the limit
+        pool.set_limit(40);
+        let mut a4 = MemoryConsumer::new("a4").register(&pool_ref);
+        a4.try_grow(30).unwrap();
+        assert_eq!(pool.reserved(), 40);
+        a3.try_grow(1).unwrap_err();
+
+        drop(a3);
+        drop(a4);
+        assert_eq!(pool.reserved(), 0);
+    }
 }
@@ -18,7 +18,7 @@ pub use duration::DURATION_MAX;
 use once_cell::sync::Lazy;
 use prometheus::{
     default_registry, gather, linear_buckets, register_histogram_vec, register_int_counter_vec,
-    HistogramOpts, HistogramVec, IntCounter, IntCounterVec, Opts,
+    CounterVec, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, Opts,
 };
 use trace::error;
 
@@ -207,6 +207,19 @@ pub static COMPACTION_DURATION: Lazy<HistogramVec> = Lazy::new(|| {
     .expect("tskv metric cannot be created")
 });
 
+pub static IO_THROTTLED_DURATION: Lazy<CounterVec> = Lazy::new(|| {
+    CounterVec::new(
+        Opts::new(
+            "io_throttled_seconds_total",
+            "total time of flush and compaction writes waiting for the io rate limiter",
+        )
+        .namespace(NAMESPACE)
+        .subsystem(TSKV_SUBSYSTEM),
+        &["priority"],
+    )
+    .expect("tskv metric cannot be created")
+});
+
 pub fn init_tskv_metrics_recorder() {
     default_registry()
         .register(Box::new(COMPACTION_SUCCESS.clone()))
@@ -217,6 +230,9 @@ pub fn init_tskv_metrics_recorder() {
     default_registry()
         .register(Box::new(COMPACTION_DURATION.clone()))
         .expect("tskv metrics collector cannot be registered");
+    default_registry()
+        .register(Box::new(IO_THROTTLED_DURATION.clone()))
+        .expect("tskv metrics collector cannot be registered");
 }
 
 pub fn incr_compaction_success() {
@@ -233,6 +249,12 @@ pub fn sample_tskv_compaction_duration(db: &str, ts_family: &str, level: &str, d
         .observe(delta)
 }
 
+pub fn sample_tskv_io_throttled_duration(priority: &str, delta: f64) {
+    IO_THROTTLED_DURATION
+        .with_label_values(&[priority])
+        .inc_by(delta)
+}
+
 pub fn gather_metrics() -> Vec<u8> {
     use prometheus::Encoder;
     let encoder = prometheus::TextEncoder::new();
@@ -2,25 +2,30 @@ use std::str::FromStr;
 
 use serde::{Deserialize, Serialize};
 
-pub const BIGINT_CODEC: [Encoding; 4] = [
+pub const BIGINT_CODEC: [Encoding; 8] = [
     Encoding::Default,
     Encoding::Null,
     Encoding::Delta,
     Encoding::Quantile,
+    Encoding::Rle,
+    Encoding::DeltaOfDelta,
+    Encoding::Xor,
+    Encoding::Auto,
 ];
 // Because timestamp, bigint, and unsigned bigint are all integers,
 // so their compression algorithms are the same
-pub const TIMESTAMP_CODEC: [Encoding; 4] = BIGINT_CODEC;
-pub const UNSIGNED_BIGINT_CODEC: [Encoding; 4] = BIGINT_CODEC;
+pub const TIMESTAMP_CODEC: [Encoding; 8] = BIGINT_CODEC;
+pub const UNSIGNED_BIGINT_CODEC: [Encoding; 8] = BIGINT_CODEC;
 
-pub const DOUBLE_CODEC: [Encoding; 4] = [
+pub const DOUBLE_CODEC: [Encoding; 5] = [
     Encoding::Default,
     Encoding::Null,
     Encoding::Gorilla,
     Encoding::Quantile,
+    Encoding::Auto,
 ];
 
-pub const STRING_CODEC: [Encoding; 7] = [
+pub const STRING_CODEC: [Encoding; 8] = [
     Encoding::Default,
     Encoding::Null,
     Encoding::Gzip,
@@ -28,9 +33,15 @@ pub const STRING_CODEC: [Encoding; 7] = [
     Encoding::Zstd,
     Encoding::Snappy,
     Encoding::Zlib,
+    Encoding::Auto,
 ];
 
-pub const BOOLEAN_CODEC: [Encoding; 3] = [Encoding::Default, Encoding::Null, Encoding::BitPack];
+pub const BOOLEAN_CODEC: [Encoding; 4] = [
+    Encoding::Default,
+    Encoding::Null,
+    Encoding::BitPack,
+    Encoding::Auto,
+];
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize, Hash, Default)]
 pub enum Encoding {
@@ -46,6 +57,16 @@ pub enum Encoding {
     Zstd = 8,
     Zlib = 9,
     BitPack = 10,
+    /// Encoding of each block is selected by trying the encodings of the type
+    /// on samples of the block, the selected one is recorded in the block.
+    Auto = 11,
+    /// Runs of repeated values, or a single value and the count for constant blocks.
+    Rle = 12,
+    /// Deltas of the deltas between values, packed into bits like Gorilla timestamps.
+    DeltaOfDelta = 13,
+    /// XOR of each value with the previous one, only the meaningful bits are stored like
+    /// Gorilla floats.
+    Xor = 14,
     Unknown = 15,
 }
 
@@ -87,6 +108,10 @@ impl Encoding {
             Encoding::Zstd => "ZSTD",
             Encoding::Zlib => "ZLIB",
             Encoding::BitPack => "BITPACK",
+            Encoding::Auto => "AUTO",
+            Encoding::Rle => "RLE",
+            Encoding::DeltaOfDelta => "DELTA_OF_DELTA",
+            Encoding::Xor => "XOR",
             Encoding::Unknown => "UNKNOWN",
         }
     }
@@ -107,6 +132,10 @@ impl FromStr for Encoding {
             "ZSTD" => Ok(Self::Zstd),
             "ZLIB" => Ok(Self::Zlib),
             "BITPACK" => Ok(Self::BitPack),
+            "AUTO" => Ok(Self::Auto),
+            "RLE" => Ok(Self::Rle),
+            "DELTA_OF_DELTA" => Ok(Self::DeltaOfDelta),
+            "XOR" => Ok(Self::Xor),
             _ => Err(s.to_string()),
         }
     }
@@ -126,7 +155,53 @@ impl From<u8> for Encoding {
             8 => Encoding::Zstd,
             9 => Encoding::Zlib,
             10 => Encoding::BitPack,
+            11 => Encoding::Auto,
+            12 => Encoding::Rle,
+            13 => Encoding::DeltaOfDelta,
+            14 => Encoding::Xor,
             _ => Encoding::Unknown,
         }
     }
 }
+
+/// Index of a string field, built for every data block of the field
+/// to skip blocks when filtering values of the field.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize, Hash)]
+pub enum FieldIndexType {
+    /// Bloom filter of the 3-grams of values, used by `LIKE` and `=` filters.
+    Ngram = 1,
+    /// Bloom filter of whole values, used by `=` filters.
+    Bloom = 2,
+}
+
+impl FieldIndexType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FieldIndexType::Ngram => "NGRAM",
+            FieldIndexType::Bloom => "BLOOM",
+        }
+    }
+
+    pub fn id(&self) -> u8 {
+        *self as u8
+    }
+
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            1 => Some(FieldIndexType::Ngram),
+            2 => Some(FieldIndexType::Bloom),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for FieldIndexType {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_uppercase().as_str() {
+            "NGRAM" => Ok(Self::Ngram),
+            "BLOOM" => Ok(Self::Bloom),
+            _ => Err(s.to_string()),
+        }
+    }
+}
@@ -1,7 +1,11 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
 #[allow(dead_code)]
-#[derive(Debug)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub enum ConsistencyLevel {
     /// allows for hinted handoff, potentially no write happened yet.
+    #[default]
     Any,
     /// at least one data node acknowledged a write or read.
     One,
@@ -10,3 +14,31 @@ pub enum ConsistencyLevel {
     /// requires all data nodes to acknowledge a write or read.
     All,
 }
+
+impl Display for ConsistencyLevel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Any => f.write_str("any"),
+            Self::One => f.write_str("one"),
+            Self::Quorum => f.write_str("quorum"),
+            Self::All => f.write_str("all"),
+        }
+    }
+}
+
+impl FromStr for ConsistencyLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "any" => Ok(Self::Any),
+            "one" => Ok(Self::One),
+            "quorum" => Ok(Self::Quorum),
+            "all" => Ok(Self::All),
+            _ => Err(format!(
+                "expected consistency level [any, one, quorum, all], found {}",
+                s
+            )),
+        }
+    }
+}
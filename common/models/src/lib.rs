@@ -17,6 +17,7 @@ pub mod duration;
 pub mod object_reference;
 pub mod oid;
 pub mod predicate;
+pub mod time_zone;
 use std::sync::Arc;
 
 pub use error_code;
@@ -78,6 +78,74 @@ pub struct VnodeAllInfo {
     pub end_time: i64,
 }
 
+/// A file loaded into a table by `COPY INTO`, files already loaded are
+/// skipped by the next `COPY INTO` of the table.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct LoadedFile {
+    pub url: String,
+    pub size: u64,
+    /// Last modified time of the file in nanoseconds.
+    pub last_modified: i64,
+    pub rows_loaded: u64,
+    /// Time of the load in nanoseconds.
+    pub load_time: i64,
+}
+
+impl LoadedFile {
+    /// Returns true if the file is not modified since it's loaded.
+    pub fn is_same_file(&self, url: &str, size: u64, last_modified: i64) -> bool {
+        self.url == url && self.size == size && self.last_modified == last_modified
+    }
+}
+
+/// A pipe loads new files of a location into a table continuously,
+/// it's run periodically by the node holding its lease, another node takes it over
+/// once the lease expires.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PipeInfo {
+    pub name: String,
+    /// The user the pipe loads files as.
+    pub owner: String,
+    /// The default database of the session creating the pipe.
+    pub database: String,
+    /// The table files are loaded into.
+    pub table: String,
+    /// The node running the pipe.
+    pub node_id: NodeId,
+    /// Time in nanoseconds until which `node_id` holds the pipe, it's renewed while
+    /// the node loads files. 0 if the pipe has not been leased since its creation.
+    #[serde(default)]
+    pub lease_expire_time: i64,
+    /// Interval between two loads in milliseconds.
+    pub interval: u64,
+    /// The `CREATE PIPE` statement.
+    pub definition: String,
+    /// Time of the creation in nanoseconds.
+    pub create_time: i64,
+    pub status: PipeStatus,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct PipeStatus {
+    pub files_loaded: u64,
+    pub rows_loaded: u64,
+    pub files_failed: u64,
+    /// Time of the last load in nanoseconds.
+    pub last_load_time: Option<i64>,
+    /// Error of the last load, cleared by the next successful load.
+    pub last_error: Option<String>,
+}
+
+impl PipeStatus {
+    pub fn state(&self) -> &'static str {
+        match (&self.last_load_time, &self.last_error) {
+            (_, Some(_)) => "FAILED",
+            (None, None) => "PENDING",
+            (Some(_), None) => "RUNNING",
+        }
+    }
+}
+
 // CREATE DATABASE <database_name>
 // [WITH [TTL <duration>]
 // [SHARD <n>]
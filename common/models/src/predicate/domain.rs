@@ -111,6 +111,29 @@ impl Marker {
     fn check_type_compatibility(&self, other: &Self) -> bool {
         self.data_type == other.data_type
     }
+    /// Compare the point with a value.
+    ///
+    /// Returns None if the data types are incompatible.
+    fn cmp_value(&self, value: &ScalarValue) -> Option<Ordering> {
+        if self.is_lower_unbound() {
+            return Some(Ordering::Less);
+        }
+        if self.is_upper_unbound() {
+            return Some(Ordering::Greater);
+        }
+        if self.data_type != value.get_datatype() {
+            return None;
+        }
+
+        match self.value.as_ref()?.partial_cmp(value)? {
+            Ordering::Equal => match self.bound {
+                Bound::Below => Some(Ordering::Less),
+                Bound::Exactly => Some(Ordering::Equal),
+                Bound::Above => Some(Ordering::Greater),
+            },
+            ordering => Some(ordering),
+        }
+    }
 }
 
 impl PartialEq for Marker {
@@ -383,6 +406,15 @@ impl Range {
             err: "cannot span non-overlapping ranges".to_string(),
         })
     }
+    /// Determine if the value is in the range.
+    ///
+    /// Returns None if the data types are incompatible.
+    pub fn contains_value(&self, value: &ScalarValue) -> Option<bool> {
+        let low = self.low.cmp_value(value)?;
+        let high = self.high.cmp_value(value)?;
+
+        Some(low != Ordering::Greater && high != Ordering::Less)
+    }
     /// whether to match all lines
     pub fn is_all(&self) -> bool {
         self.low.is_lower_unbound() && self.high.is_upper_unbound()
@@ -526,6 +558,26 @@ impl Domain {
             entries,
         })
     }
+    /// Determine if the value may be in the domain.
+    ///
+    /// Returns true if the data types are incompatible, the value can not be excluded.
+    pub fn may_contain(&self, value: &ScalarValue) -> bool {
+        match self {
+            Self::Range(val_set) => val_set
+                .low_indexed_ranges
+                .values()
+                .any(|range| range.contains_value(value).unwrap_or(true)),
+            Self::Equtable(val_set) => {
+                if val_set.data_type != value.get_datatype() {
+                    return true;
+                }
+                let found = val_set.entries.iter().any(|entry| entry.value.eq(value));
+                found == val_set.white_list
+            }
+            Self::None => false,
+            Self::All => true,
+        }
+    }
     /// Calculates the intersection of two ranges, and returns None if the intersection does not exist
     ///
     /// This method returns the new value without changing the old value
@@ -1096,6 +1148,24 @@ impl QueryExpr {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum PushedAggregateFunction {
     Count(String),
+    Min(String),
+    Max(String),
+    Sum(String),
+    /// `SUM(CAST(column AS Float64))`, the sum of integers that never overflows.
+    FloatSum(String),
+}
+
+impl PushedAggregateFunction {
+    /// Name of the aggregated column
+    pub fn column(&self) -> &str {
+        match self {
+            Self::Count(column)
+            | Self::Min(column)
+            | Self::Max(column)
+            | Self::Sum(column)
+            | Self::FloatSum(column) => column,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -1122,6 +1192,31 @@ mod tests {
         assert_eq!(schema.as_ref(), &df_schema);
     }
 
+    #[test]
+    fn test_may_contain() {
+        let v = |v: f64| ScalarValue::Float64(Some(v));
+        let domain = Domain::of_ranges(&[
+            Range::lt(&DataType::Float64, &v(0.0)),
+            Range::ge(&DataType::Float64, &v(90.0)),
+        ])
+        .unwrap();
+        assert!(domain.may_contain(&v(-1.0)));
+        assert!(!domain.may_contain(&v(0.0)));
+        assert!(!domain.may_contain(&v(89.9)));
+        assert!(domain.may_contain(&v(90.0)));
+        assert!(domain.may_contain(&v(100.0)));
+        // incompatible types are not excluded
+        assert!(domain.may_contain(&ScalarValue::Int64(Some(50))));
+
+        let b = ScalarValue::Boolean(Some(true));
+        let domain = Domain::of_values(&DataType::Boolean, false, &[&b]);
+        assert!(!domain.may_contain(&b));
+        assert!(domain.may_contain(&ScalarValue::Boolean(Some(false))));
+
+        assert!(!Domain::None.may_contain(&b));
+        assert!(Domain::All.may_contain(&b));
+    }
+
     #[test]
     fn test_of_ranges() {
         let f1 = Range::lt(&DataType::Float64, &ScalarValue::Float64(Some(-1000000.1)));
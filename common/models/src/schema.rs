@@ -30,7 +30,7 @@ use datafusion::logical_expr::TableSource;
 use derive_builder::Builder;
 use serde::{Deserialize, Serialize};
 
-use crate::codec::Encoding;
+use crate::codec::{Encoding, FieldIndexType};
 use crate::oid::{Identifier, Oid};
 use crate::{ColumnId, Error, SchemaId, ValueType};
 
@@ -310,6 +310,9 @@ pub struct TableColumn {
     pub name: String,
     pub column_type: ColumnType,
     pub encoding: Encoding,
+    /// Index of the string field, see `FieldIndexType`.
+    #[serde(default)]
+    pub index: Option<FieldIndexType>,
 }
 
 impl From<&TableColumn> for ArrowField {
@@ -336,6 +339,7 @@ impl TableColumn {
             name,
             column_type,
             encoding,
+            index: None,
         }
     }
     pub fn new_with_default(name: String, column_type: ColumnType) -> Self {
@@ -344,6 +348,7 @@ impl TableColumn {
             name,
             column_type,
             encoding: Encoding::Default,
+            index: None,
         }
     }
 
@@ -353,6 +358,7 @@ impl TableColumn {
             name: TIME_FIELD_NAME.to_string(),
             column_type: ColumnType::Time,
             encoding: Encoding::Default,
+            index: None,
         }
     }
 
@@ -362,6 +368,7 @@ impl TableColumn {
             name,
             column_type: ColumnType::Tag,
             encoding: Encoding::Default,
+            index: None,
         }
     }
 
@@ -572,6 +579,16 @@ pub struct DatabaseOptions {
     replica: Option<u64>,
     // timestamp percision
     precision: Option<Precision>,
+
+    // strategy to pick files of vnodes to compact
+    #[serde(default)]
+    compaction: Option<CompactionStrategy>,
+    // time window of files compacted together by the time window strategy
+    #[serde(default)]
+    compaction_window: Option<Duration>,
+    // windows older than it are not compacted by the time window strategy
+    #[serde(default)]
+    cold_duration: Option<Duration>,
 }
 
 impl DatabaseOptions {
@@ -586,6 +603,7 @@ impl DatabaseOptions {
         unit: DurationUnit::Day,
     };
     pub const DEFAULT_PRECISION: Precision = Precision::NS;
+    pub const DEFAULT_COMPACTION: CompactionStrategy = CompactionStrategy::Level;
 
     pub fn ttl(&self) -> &Option<Duration> {
         &self.ttl
@@ -631,6 +649,31 @@ impl DatabaseOptions {
             .unwrap_or(&DatabaseOptions::DEFAULT_PRECISION)
     }
 
+    pub fn compaction(&self) -> &Option<CompactionStrategy> {
+        &self.compaction
+    }
+
+    pub fn compaction_or_default(&self) -> &CompactionStrategy {
+        self.compaction
+            .as_ref()
+            .unwrap_or(&DatabaseOptions::DEFAULT_COMPACTION)
+    }
+
+    pub fn compaction_window(&self) -> &Option<Duration> {
+        &self.compaction_window
+    }
+
+    /// Returns the compaction window, the vnode duration if it's not set.
+    pub fn compaction_window_or_default(&self) -> &Duration {
+        self.compaction_window
+            .as_ref()
+            .unwrap_or_else(|| self.vnode_duration_or_default())
+    }
+
+    pub fn cold_duration(&self) -> &Option<Duration> {
+        &self.cold_duration
+    }
+
     pub fn with_ttl(&mut self, ttl: Duration) {
         self.ttl = Some(ttl);
     }
@@ -650,6 +693,45 @@ impl DatabaseOptions {
     pub fn with_precision(&mut self, precision: Precision) {
         self.precision = Some(precision)
     }
+
+    pub fn with_compaction(&mut self, compaction: CompactionStrategy) {
+        self.compaction = Some(compaction);
+    }
+
+    pub fn with_compaction_window(&mut self, compaction_window: Duration) {
+        self.compaction_window = Some(compaction_window);
+    }
+
+    pub fn with_cold_duration(&mut self, cold_duration: Duration) {
+        self.cold_duration = Some(cold_duration);
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CompactionStrategy {
+    /// Compacts the files of the level with the highest score into the next level.
+    Level,
+    /// Compacts the files in the same time window only.
+    TimeWindow,
+}
+
+impl CompactionStrategy {
+    pub fn new(text: &str) -> Option<Self> {
+        match text.to_uppercase().as_str() {
+            "LEVEL" => Some(CompactionStrategy::Level),
+            "TIME_WINDOW" => Some(CompactionStrategy::TimeWindow),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for CompactionStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CompactionStrategy::Level => f.write_str("LEVEL"),
+            CompactionStrategy::TimeWindow => f.write_str("TIME_WINDOW"),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
@@ -792,6 +874,13 @@ impl Tenant {
 pub struct TenantOptions {
     pub comment: Option<String>,
     pub limiter_config: Option<TenantLimiterConfig>,
+    /// The maximum memory in bytes that all queries of the tenant can use
+    #[serde(default)]
+    pub query_memory_limit: Option<u64>,
+    /// The maximum number of queries of the tenant that can be executed concurrently,
+    /// the others wait in the query queue
+    #[serde(default)]
+    pub max_concurrent_queries: Option<u64>,
 }
 
 impl TenantOptions {
@@ -826,6 +915,14 @@ impl Display for TenantOptions {
             write!(f, "limiter=None,")?;
         }
 
+        if let Some(ref e) = self.query_memory_limit {
+            write!(f, "query_memory_limit={},", e)?;
+        }
+
+        if let Some(ref e) = self.max_concurrent_queries {
+            write!(f, "max_concurrent_queries={},", e)?;
+        }
+
         Ok(())
     }
 }
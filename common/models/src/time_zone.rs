@@ -0,0 +1,199 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use chrono::{Duration, FixedOffset, LocalResult, NaiveDateTime, Offset, TimeZone as _};
+use chrono_tz::Tz;
+
+const NANOS_PER_SEC: i64 = 1_000_000_000;
+
+/// Time zone of a session, a fixed offset such as '+08:00'
+/// or an IANA name such as 'Asia/Shanghai' which follows daylight saving time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeZone {
+    Fixed(FixedOffset),
+    Named(Tz),
+}
+
+impl Default for TimeZone {
+    fn default() -> Self {
+        Self::utc()
+    }
+}
+
+impl TimeZone {
+    pub fn utc() -> Self {
+        Self::Fixed(FixedOffset::east_opt(0).expect("zero offset is valid"))
+    }
+
+    pub fn is_utc(&self) -> bool {
+        match self {
+            Self::Fixed(offset) => offset.local_minus_utc() == 0,
+            Self::Named(_) => false,
+        }
+    }
+
+    /// The offset from UTC at the given UTC time
+    pub fn offset_at(&self, utc: &NaiveDateTime) -> FixedOffset {
+        match self {
+            Self::Fixed(offset) => *offset,
+            Self::Named(tz) => tz.offset_from_utc_datetime(utc).fix(),
+        }
+    }
+
+    /// Convert a UTC timestamp in nanoseconds to the wall-clock time of this time zone,
+    /// `None` on overflow
+    pub fn utc_to_local(&self, utc_ns: i64) -> Option<i64> {
+        let offset = self.offset_at(&nanos_to_datetime(utc_ns));
+        utc_ns.checked_add(offset.local_minus_utc() as i64 * NANOS_PER_SEC)
+    }
+
+    /// Convert a wall-clock time in nanoseconds of this time zone to a UTC timestamp,
+    /// `None` on overflow.
+    ///
+    /// A time that occurs twice (clocks turned back) resolves to the earlier instant,
+    /// a time that is skipped (clocks turned forward) is converted with the offset before the transition.
+    pub fn local_to_utc(&self, local_ns: i64) -> Option<i64> {
+        let offset = match self {
+            Self::Fixed(offset) => *offset,
+            Self::Named(tz) => {
+                let local = nanos_to_datetime(local_ns);
+                match tz.offset_from_local_datetime(&local) {
+                    LocalResult::Single(offset) | LocalResult::Ambiguous(offset, _) => offset.fix(),
+                    // transitions of a time zone are always more than one day apart
+                    LocalResult::None => tz
+                        .offset_from_utc_datetime(&(local - Duration::days(1)))
+                        .fix(),
+                }
+            }
+        };
+
+        local_ns.checked_sub(offset.local_minus_utc() as i64 * NANOS_PER_SEC)
+    }
+}
+
+impl Display for TimeZone {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Fixed(offset) => write!(f, "{}", offset),
+            Self::Named(tz) => f.write_str(tz.name()),
+        }
+    }
+}
+
+impl FromStr for TimeZone {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("utc") || s.eq_ignore_ascii_case("z") {
+            return Ok(Self::utc());
+        }
+
+        if s.starts_with('+') || s.starts_with('-') {
+            return parse_fixed_offset(s)
+                .map(Self::Fixed)
+                .ok_or_else(|| format!("expected time zone offset like '+08:00', found {}", s));
+        }
+
+        s.parse::<Tz>().map(Self::Named).map_err(|_| {
+            format!(
+                "expected time zone like '+08:00' or 'Asia/Shanghai', found {}",
+                s
+            )
+        })
+    }
+}
+
+/// Parse offset like '+08:00' or '-03:30'
+fn parse_fixed_offset(s: &str) -> Option<FixedOffset> {
+    let (sign, offset) = if let Some(offset) = s.strip_prefix('+') {
+        (1, offset)
+    } else {
+        (-1, s.strip_prefix('-')?)
+    };
+    let (hours, minutes) = offset.split_once(':')?;
+    if hours.len() != 2 || minutes.len() != 2 {
+        return None;
+    }
+    let hours: i32 = hours.parse().ok()?;
+    let minutes: i32 = minutes.parse().ok()?;
+    if minutes >= 60 {
+        return None;
+    }
+
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
+/// Every i64 nanosecond timestamp is in the range of [`NaiveDateTime`]
+pub fn nanos_to_datetime(ns: i64) -> NaiveDateTime {
+    NaiveDateTime::from_timestamp_opt(
+        ns.div_euclid(NANOS_PER_SEC),
+        ns.rem_euclid(NANOS_PER_SEC) as u32,
+    )
+    .expect("i64 nanoseconds are in the range of NaiveDateTime")
+}
+
+/// `None` if the datetime is out of the range of i64 nanoseconds
+pub fn datetime_to_nanos(datetime: &NaiveDateTime) -> Option<i64> {
+    datetime
+        .timestamp()
+        .checked_mul(NANOS_PER_SEC)?
+        .checked_add(datetime.timestamp_subsec_nanos() as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::{datetime_to_nanos, TimeZone};
+
+    fn nanos(y: i32, m: u32, d: u32, h: u32, min: u32) -> i64 {
+        let datetime = NaiveDate::from_ymd_opt(y, m, d)
+            .unwrap()
+            .and_hms_opt(h, min, 0)
+            .unwrap();
+        datetime_to_nanos(&datetime).unwrap()
+    }
+
+    #[test]
+    fn test_parse_time_zone() {
+        assert!("utc".parse::<TimeZone>().unwrap().is_utc());
+        assert!("+00:00".parse::<TimeZone>().unwrap().is_utc());
+        assert_eq!("+08:00".parse::<TimeZone>().unwrap().to_string(), "+08:00");
+        assert_eq!("-03:30".parse::<TimeZone>().unwrap().to_string(), "-03:30");
+        assert_eq!(
+            "Asia/Shanghai".parse::<TimeZone>().unwrap().to_string(),
+            "Asia/Shanghai"
+        );
+
+        assert!("08:00".parse::<TimeZone>().is_err());
+        assert!("+8:00".parse::<TimeZone>().is_err());
+        assert!("+08:60".parse::<TimeZone>().is_err());
+        assert!("Mars/Olympus".parse::<TimeZone>().is_err());
+    }
+
+    #[test]
+    fn test_daylight_saving_time() {
+        let berlin: TimeZone = "Europe/Berlin".parse().unwrap();
+
+        // winter, UTC+1
+        let utc = nanos(2023, 1, 15, 11, 0);
+        assert_eq!(berlin.utc_to_local(utc), Some(nanos(2023, 1, 15, 12, 0)));
+        assert_eq!(berlin.local_to_utc(nanos(2023, 1, 15, 12, 0)), Some(utc));
+
+        // summer, UTC+2
+        let utc = nanos(2023, 7, 15, 10, 0);
+        assert_eq!(berlin.utc_to_local(utc), Some(nanos(2023, 7, 15, 12, 0)));
+        assert_eq!(berlin.local_to_utc(nanos(2023, 7, 15, 12, 0)), Some(utc));
+
+        // 02:30 is skipped on 2023-03-26
+        assert_eq!(
+            berlin.local_to_utc(nanos(2023, 3, 26, 2, 30)),
+            Some(nanos(2023, 3, 26, 1, 30))
+        );
+        // 02:30 occurs twice on 2023-10-29
+        assert_eq!(
+            berlin.local_to_utc(nanos(2023, 10, 29, 2, 30)),
+            Some(nanos(2023, 10, 29, 0, 30))
+        );
+    }
+}
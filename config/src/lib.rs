@@ -69,6 +69,9 @@ pub fn get_config(path: impl AsRef<Path>) -> Config {
         ),
     };
     config.wal.introspect();
+    if let Err(err) = config.wal.check() {
+        panic!("Invalid configurtion file '{}': {}", path.display(), err);
+    }
     config
 }
 
@@ -99,6 +102,15 @@ pub struct QueryConfig {
     pub write_sql_limit: u64,
     #[serde(default = "QueryConfig::default_auth_enabled")]
     pub auth_enabled: bool,
+    #[serde(default = "QueryConfig::default_spill_path")]
+    pub spill_path: String,
+    #[serde(default = "QueryConfig::default_query_queue_size")]
+    pub query_queue_size: u32,
+    #[serde(
+        with = "duration",
+        default = "QueryConfig::default_query_queue_timeout"
+    )]
+    pub query_queue_timeout: Duration,
 }
 
 impl QueryConfig {
@@ -118,6 +130,18 @@ impl QueryConfig {
         false
     }
 
+    fn default_spill_path() -> String {
+        "data/spill".to_string()
+    }
+
+    fn default_query_queue_size() -> u32 {
+        1024
+    }
+
+    fn default_query_queue_timeout() -> Duration {
+        Duration::from_secs(60)
+    }
+
     pub fn override_by_env(&mut self) {
         if let Ok(size) = std::env::var("MAX_SERVER_CONNECTIONS") {
             self.max_server_connections = size.parse::<u32>().unwrap();
@@ -131,6 +155,15 @@ impl QueryConfig {
         if let Ok(val) = std::env::var("AUTH_ENABLED") {
             self.auth_enabled = val.parse::<bool>().unwrap();
         }
+        if let Ok(val) = std::env::var("QUERY_SPILL_PATH") {
+            self.spill_path = val;
+        }
+        if let Ok(size) = std::env::var("QUERY_QUEUE_SIZE") {
+            self.query_queue_size = size.parse::<u32>().unwrap();
+        }
+        if let Ok(dur) = std::env::var("QUERY_QUEUE_TIMEOUT") {
+            self.query_queue_timeout = duration::parse_duration(&dur).unwrap();
+        }
     }
 }
 
@@ -165,6 +198,8 @@ pub struct StorageConfig {
     pub max_concurrent_compaction: u16,
     #[serde(default = "StorageConfig::default_strict_write")]
     pub strict_write: bool,
+    #[serde(with = "bytes_num", default = "StorageConfig::default_io_rate_limit")]
+    pub io_rate_limit: u64,
 }
 
 impl StorageConfig {
@@ -208,6 +243,10 @@ impl StorageConfig {
         false
     }
 
+    fn default_io_rate_limit() -> u64 {
+        0
+    }
+
     pub fn override_by_env(&mut self) {
         if let Ok(path) = std::env::var("CNOSDB_APPLICATION_PATH") {
             self.path = path;
@@ -239,6 +278,9 @@ impl StorageConfig {
         if let Ok(size) = std::env::var("CNOSDB_STORAGE_STRICT_WRITE") {
             self.strict_write = size.parse::<bool>().unwrap();
         }
+        if let Ok(size) = std::env::var("CNOSDB_STORAGE_IO_RATE_LIMIT") {
+            self.io_rate_limit = size.parse::<u64>().unwrap();
+        }
 
         self.introspect();
     }
@@ -264,6 +306,8 @@ pub struct WalConfig {
     pub sync: bool,
     #[serde(with = "duration", default = "WalConfig::default_sync_interval")]
     pub sync_interval: Duration,
+    #[serde(default = "WalConfig::default_compression")]
+    pub compression: String,
 }
 
 impl WalConfig {
@@ -291,6 +335,10 @@ impl WalConfig {
         Duration::from_secs(0)
     }
 
+    fn default_compression() -> String {
+        "zstd".to_string()
+    }
+
     pub fn override_by_env(&mut self) {
         if let Ok(cap) = std::env::var("CNOSDB_WAL_REQ_CHANNEL_CAP") {
             self.wal_req_channel_cap = cap.parse::<usize>().unwrap();
@@ -304,6 +352,20 @@ impl WalConfig {
         if let Ok(sync) = std::env::var("CNOSDB_WAL_SYNC") {
             self.sync = sync.as_str() == sync;
         }
+        if let Ok(compression) = std::env::var("CNOSDB_WAL_COMPRESSION") {
+            self.compression = compression;
+        }
+    }
+
+    /// Checks the values that are not checked by deserialization.
+    pub fn check(&self) -> Result<(), String> {
+        match self.compression.to_ascii_lowercase().as_str() {
+            "none" | "snappy" | "zstd" => Ok(()),
+            _ => Err(format!(
+                "wal.compression must be one of none, snappy and zstd, found '{}'",
+                self.compression
+            )),
+        }
     }
 
     pub fn introspect(&mut self) {
@@ -318,6 +380,8 @@ pub struct CacheConfig {
     pub max_buffer_size: u64,
     #[serde(default = "CacheConfig::default_max_immutable_number")]
     pub max_immutable_number: u16,
+    #[serde(with = "bytes_num", default = "CacheConfig::default_block_cache_size")]
+    pub block_cache_size: u64,
 }
 
 impl CacheConfig {
@@ -329,6 +393,10 @@ impl CacheConfig {
         4
     }
 
+    fn default_block_cache_size() -> u64 {
+        64 * 1024 * 1024
+    }
+
     pub fn override_by_env(&mut self) {
         if let Ok(size) = std::env::var("CNOSDB_CACHE_MAX_BUFFER_SIZE") {
             self.max_buffer_size = size.parse::<u64>().unwrap();
@@ -336,6 +404,9 @@ impl CacheConfig {
         if let Ok(size) = std::env::var("CNOSDB_CACHE_MAX_IMMUTABLE_NUMBER") {
             self.max_immutable_number = size.parse::<u16>().unwrap();
         }
+        if let Ok(size) = std::env::var("CNOSDB_CACHE_BLOCK_CACHE_SIZE") {
+            self.block_cache_size = size.parse::<u64>().unwrap();
+        }
     }
 }
 
@@ -517,6 +588,15 @@ mod test {
         assert_eq!(cfg, cfg_2);
     }
 
+    #[test]
+    fn test_wal_compression() {
+        let mut cfg = crate::default_config();
+        cfg.wal.compression = "Snappy".to_string();
+        assert!(cfg.wal.check().is_ok());
+        cfg.wal.compression = "zstd1".to_string();
+        assert!(cfg.wal.check().is_err());
+    }
+
     #[test]
     fn test() {
         let config_str = r#"
@@ -527,6 +607,9 @@ max_server_connections = 10240
 query_sql_limit = 16777216   # 16 * 1024 * 1024
 write_sql_limit = 167772160  # 160 * 1024 * 1024
 auth_enabled = false
+spill_path = 'data/spill'
+query_queue_size = 1024
+query_queue_timeout = "60s"
 
 [storage]
 
@@ -562,6 +645,9 @@ max_concurrent_compaction = 4
 # If true, write request will not be checked in detail.
 strict_write = false
 
+# The maximum bytes per second written by flush and compaction, 0 means unlimited.
+io_rate_limit = "0"
+
 [wal]
 
 # If true, write requets on disk before writing to memory.
@@ -577,6 +663,9 @@ max_file_size = "1G" # 1073741824
 sync = false
 sync_interval = "10s" # h, m, s
 
+# Compression of wal entries: 'zstd', 'snappy' or 'none'.
+compression = 'zstd'
+
 [cache]
 max_buffer_size = "128M" # 134217728
 max_immutable_number = 4
@@ -63,6 +63,7 @@ pub struct QueryExecutor {
 
     sender: Sender<CoordinatorResult<RecordBatch>>,
     data_out: U64Counter,
+    metrics: Arc<CoordServiceMetrics>,
 }
 
 impl QueryExecutor {
@@ -80,6 +81,7 @@ impl QueryExecutor {
             meta_manager,
             sender,
             data_out,
+            metrics,
         }
     }
     pub async fn execute(&self) -> CoordinatorResult<()> {
@@ -213,12 +215,16 @@ impl QueryExecutor {
             }
             let record = record_batch_decode(&received.data)?;
 
-            self.meta_manager
+            let checked = self
+                .meta_manager
                 .tenant_manager()
                 .limiter(self.option.tenant.as_str())
                 .await
                 .check_data_out(record.get_array_memory_size())
-                .await?;
+                .await;
+            self.metrics
+                .record_throttled(self.option.tenant.as_str(), &checked);
+            checked?;
 
             self.sender.send(Ok(record)).await?;
         }
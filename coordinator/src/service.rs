@@ -5,6 +5,8 @@ use std::time::Duration;
 
 use config::{ClusterConfig, HintedOffConfig};
 use datafusion::arrow::record_batch::RecordBatch;
+use meta::error::{MetaError, MetaResult};
+use meta::limiter::RequestLimiterKind;
 use meta::{MetaClientRef, MetaRef};
 use metrics::count::U64Counter;
 use metrics::label::Labels;
@@ -48,13 +50,22 @@ pub struct CoordService {
 pub struct CoordServiceMetrics {
     data_in: Metric<U64Counter>,
     data_out: Metric<U64Counter>,
+    throttled: Metric<U64Counter>,
 }
 
 impl CoordServiceMetrics {
     pub fn new(register: &MetricsRegister) -> Self {
         let data_in = register.metric("coord_data_in", "tenant data in");
         let data_out = register.metric("coord_data_out", "tenant data out");
-        Self { data_in, data_out }
+        let throttled = register.metric(
+            "coord_throttled",
+            "the number of requests rejected by the rate limiter of the tenant",
+        );
+        Self {
+            data_in,
+            data_out,
+            throttled,
+        }
     }
 
     pub fn tenant_db_labels<'a>(tenant: &'a str, db: &'a str) -> impl Into<Labels> + 'a {
@@ -68,6 +79,19 @@ impl CoordServiceMetrics {
     pub fn data_out(&self, tenant: &str, db: &str) -> U64Counter {
         self.data_out.recorder(Self::tenant_db_labels(tenant, db))
     }
+
+    pub fn throttled(&self, tenant: &str, kind: RequestLimiterKind) -> U64Counter {
+        let kind = kind.to_string();
+        self.throttled
+            .recorder([("tenant", tenant), ("kind", kind.as_str())])
+    }
+
+    /// Count the request if it is rejected by the rate limiter
+    pub fn record_throttled(&self, tenant: &str, result: &MetaResult<()>) {
+        if let Err(MetaError::RequestLimit { kind }) = result {
+            self.throttled(tenant, *kind).inc_one();
+        }
+    }
 }
 
 impl CoordService {
@@ -204,15 +228,15 @@ impl CoordService {
     ) {
         let tenant = option.tenant.as_str();
 
-        if let Err(e) = self
+        let checked = self
             .meta
             .tenant_manager()
             .limiter(tenant)
             .await
             .check_query()
-            .await
-            .map_err(|e| CoordinatorError::Meta { source: e })
-        {
+            .await;
+        self.metrics.record_throttled(tenant, &checked);
+        if let Err(e) = checked.map_err(|e| CoordinatorError::Meta { source: e }) {
             let _ = sender.send(Err(e)).await;
             return;
         }
@@ -285,8 +309,12 @@ impl Coordinator for CoordService {
         let points = request.points.as_slice();
         let write_size = points.len();
 
-        limiter.check_write().await?;
-        limiter.check_data_in(write_size).await?;
+        let checked = match limiter.check_write().await {
+            Ok(_) => limiter.check_data_in(write_size).await,
+            Err(e) => Err(e),
+        };
+        self.metrics.record_throttled(&tenant, &checked);
+        checked?;
 
         let db = get_db_from_flatbuffers(points)?;
 
@@ -349,6 +377,15 @@ impl Coordinator for CoordService {
         Ok(())
     }
 
+    async fn exec_admin_command(
+        &self,
+        node_id: u64,
+        req: AdminCommandRequest,
+    ) -> CoordinatorResult<()> {
+        info!("exec command:{:?} on node:{:?}", req, node_id);
+        self.exec_admin_command_on_node(node_id, req).await
+    }
+
     async fn vnode_manager(
         &self,
         tenant: &str,
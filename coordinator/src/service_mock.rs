@@ -33,6 +33,12 @@ pub trait Coordinator: Send + Sync + Debug {
 
     async fn broadcast_command(&self, req: AdminCommandRequest) -> CoordinatorResult<()>;
 
+    async fn exec_admin_command(
+        &self,
+        node_id: u64,
+        req: AdminCommandRequest,
+    ) -> CoordinatorResult<()>;
+
     async fn vnode_manager(
         &self,
         tenant: &str,
@@ -79,6 +85,14 @@ impl Coordinator for MockCoordinator {
         Ok(())
     }
 
+    async fn exec_admin_command(
+        &self,
+        node_id: u64,
+        req: AdminCommandRequest,
+    ) -> CoordinatorResult<()> {
+        Ok(())
+    }
+
     async fn vnode_manager(
         &self,
         tenant: &str,
@@ -1,4 +1,6 @@
+use std::collections::HashMap;
 use std::pin::Pin;
+use std::sync::Arc;
 use std::time::Duration;
 
 use arrow_flight::flight_service_server::FlightService;
@@ -11,34 +13,57 @@ use arrow_flight::sql::{
     CommandPreparedStatementUpdate, CommandStatementQuery, CommandStatementUpdate, SqlInfo,
     TicketStatementQuery,
 };
+use arrow_flight::utils::flight_data_to_arrow_batch;
 use arrow_flight::{
     utils as flight_utils, Action, FlightData, FlightDescriptor, FlightInfo, HandshakeRequest,
     HandshakeResponse, IpcMessage, SchemaAsIpc, Ticket,
 };
-use datafusion::arrow::datatypes::{Schema, ToByteSlice};
+use datafusion::arrow::datatypes::{Schema, SchemaRef, ToByteSlice};
+use datafusion::arrow::error::ArrowError;
 use datafusion::arrow::ipc::writer::IpcWriteOptions;
+use datafusion::arrow::ipc::{root_as_message, MessageHeader};
+use datafusion::arrow::record_batch::RecordBatch;
 use futures::Stream;
-use http_protocol::header::{DB, TENANT};
-use models::auth::user::User;
+use http_protocol::header::{BEARER_PREFIX, DB, TENANT};
+use models::auth::user::{User, UserDesc};
 use models::oid::UuidGenerator;
 use moka::sync::Cache;
+use parking_lot::Mutex;
 use prost::bytes::Bytes;
 use spi::query::execution::Output;
+use spi::query::prepared_statement::{PreparedResult, PreparedStatementRef};
+use spi::query::session::SessionVariable;
 use spi::server::dbms::DBMSRef;
-use spi::service::protocol::{Context, ContextBuilder, Query, QueryHandle};
+use spi::service::protocol::{
+    Context, ContextBuilder, Query, QueryHandle, SessionVariablesRef, VariableStore,
+};
 use tonic::metadata::MetadataMap;
 use tonic::{Request, Response, Status, Streaming};
 use trace::debug;
 
 use super::auth_middleware::CallHeaderAuthenticator;
 use crate::flight_sql::auth_middleware::AuthResult;
-use crate::flight_sql::utils;
+use crate::flight_sql::{metadata, utils};
+
+/// A statement prepared by [`FlightSqlService::do_action_create_prepared_statement`]
+struct PreparedStatementEntry {
+    /// The user who prepared the statement
+    owner: UserDesc,
+    sql: String,
+    statement: PreparedStatementRef,
+    /// The parameter rows bound by [`FlightSqlService::do_put_prepared_statement_query`]
+    parameters: Mutex<Vec<RecordBatch>>,
+}
+
 pub struct FlightSqlServiceImpl<T> {
     instance: DBMSRef,
     authenticator: T,
     id_generator: UuidGenerator,
 
     result_cache: Cache<Vec<u8>, Output>,
+    prepared_statements: Cache<Vec<u8>, Arc<PreparedStatementEntry>>,
+    /// Session variables changed by `SET` and `RESET`, by the bearer token of the session
+    session_variables: Cache<String, SessionVariablesRef>,
 }
 
 impl<T> FlightSqlServiceImpl<T> {
@@ -49,12 +74,24 @@ impl<T> FlightSqlServiceImpl<T> {
             // The query results are only cached for 2 minutes and expire after 2 minutes
             .time_to_live(Duration::from_secs(2 * 60))
             .build();
+        let prepared_statements = Cache::builder()
+            .thread_pool_enabled(false)
+            // The prepared statements not used for 30 minutes are closed
+            .time_to_idle(Duration::from_secs(30 * 60))
+            .build();
+        let session_variables = Cache::builder()
+            .thread_pool_enabled(false)
+            // Expire together with the bearer token of the session, which is idle for 10 minutes at most
+            .time_to_idle(Duration::from_secs(10 * 60))
+            .build();
 
         Self {
             instance,
             authenticator,
             id_generator: Default::default(),
             result_cache,
+            prepared_statements,
+            session_variables,
         }
     }
 }
@@ -146,21 +183,41 @@ where
         // parse tenant & default database
         let tenant = utils::get_value_from_header(metadata, TENANT, "");
         let db = utils::get_value_from_header(metadata, DB, "");
-        let ctx = ContextBuilder::new(user_info)
+        let mut ctx_builder = ContextBuilder::new(user_info)
             .with_tenant(tenant)
-            .with_database(db)
-            .build();
+            .with_database(db);
+
+        // `SET` and `RESET` are kept for the session identified by the bearer token,
+        // the headers of the request take precedence
+        if let Some(session) = utils::get_value_from_auth_header(metadata, BEARER_PREFIX) {
+            let variables = self.session_variables.get_with(session, Default::default);
+            ctx_builder = ctx_builder
+                .with_variable_store(VariableStore::Server(variables))
+                .map_err(utils::query_error_to_status)?;
+        }
+
+        // session variables are passed by the headers of the same name
+        for variable in SessionVariable::ALL {
+            if variable == SessionVariable::Database {
+                continue;
+            }
+            let value = utils::get_value_from_header(metadata, variable.as_ref(), "");
+            ctx_builder = ctx_builder
+                .with_session_variable(variable, value.as_deref())
+                .map_err(utils::query_error_to_status)?;
+        }
 
-        Ok(ctx)
+        Ok(ctx_builder.build())
     }
 
     async fn execute(&self, sql: String, ctx: Context) -> Result<QueryHandle, Status> {
         // execute sql
         let query = Query::new(ctx, sql);
-        let query_result = self.instance.execute(&query).await.map_err(|e| {
-            // TODO convert error message
-            Status::internal(format!("{}", e))
-        })?;
+        let query_result = self
+            .instance
+            .execute(&query)
+            .await
+            .map_err(utils::query_error_to_status)?;
 
         Ok(query_result)
     }
@@ -189,15 +246,142 @@ where
         Ok(stream)
     }
 
-    fn fetch_affected_rows_count(&self, statement_handle: &[u8]) -> Result<i64, Status> {
-        let result_set = self.result_cache.get(statement_handle).ok_or_else(|| {
-            Status::internal(format!(
-                "The result of query({:?}) does not exist or has expired",
-                statement_handle
+    /// Query `information_schema` of the tenant of the request,
+    /// returns the tenant and the result
+    async fn query_information_schema(
+        &self,
+        sql: String,
+        metadata: &MetadataMap,
+    ) -> Result<(String, Vec<RecordBatch>), Status> {
+        let user = self.authenticator.authenticate(metadata).await?.identity();
+        let ctx = self.construct_context(user, metadata)?;
+        let tenant = ctx.tenant().to_string();
+
+        let output = self.execute(sql, ctx).await?.result();
+
+        Ok((tenant, output.chunk_result().to_vec()))
+    }
+
+    /// The tenant of the request
+    async fn auth_tenant(&self, metadata: &MetadataMap) -> Result<String, Status> {
+        let user = self.authenticator.authenticate(metadata).await?.identity();
+        let ctx = self.construct_context(user, metadata)?;
+
+        Ok(ctx.tenant().to_string())
+    }
+
+    /// Cache the result of the metadata command,
+    /// waiting to call [`FlightSqlService::do_get_statement`] to get the result
+    fn metadata_flight_info(
+        &self,
+        batch: Result<RecordBatch, ArrowError>,
+        flight_descriptor: FlightDescriptor,
+    ) -> Result<Response<FlightInfo>, Status> {
+        let batch = batch.map_err(|e| Status::internal(format!("{}", e)))?;
+        let schema = batch.schema();
+        let total_records = batch.num_rows();
+
+        let result_ident = self.id_generator.next_id().to_le_bytes().to_vec();
+        self.result_cache.insert(
+            result_ident.clone(),
+            Output::StreamData(schema.clone(), vec![batch]),
+        );
+
+        let flight_info = self.construct_flight_info(
+            result_ident,
+            schema.as_ref(),
+            total_records as i64,
+            flight_descriptor,
+        )?;
+
+        Ok(Response::new(flight_info))
+    }
+
+    /// Get the prepared statement, which is only accessible to the user who prepared it
+    async fn auth_prepared_statement(
+        &self,
+        handle: &[u8],
+        metadata: &MetadataMap,
+    ) -> Result<(User, Arc<PreparedStatementEntry>), Status> {
+        let user = self.authenticator.authenticate(metadata).await?.identity();
+
+        let entry = self.prepared_statements.get(handle).ok_or_else(|| {
+            Status::not_found(format!(
+                "The prepared statement({:?}) does not exist or has been closed",
+                handle
             ))
         })?;
+        if &entry.owner != user.desc() {
+            return Err(Status::permission_denied(format!(
+                "The prepared statement({:?}) is not prepared by the user {}",
+                handle,
+                user.desc().name()
+            )));
+        }
 
-        Ok(result_set.affected_rows())
+        Ok((user, entry))
+    }
+
+    /// Execute the prepared statement with the parameters,
+    /// or the parameters bound by [`FlightSqlService::do_put_prepared_statement_query`] if `None`
+    async fn execute_prepared(
+        &self,
+        handle: &[u8],
+        metadata: &MetadataMap,
+        parameters: Option<Vec<RecordBatch>>,
+    ) -> Result<Output, Status> {
+        let (user, entry) = self.auth_prepared_statement(handle, metadata).await?;
+
+        // The statement executed when preparing, e.g. SHOW and DDL
+        if let Some(output) = self.result_cache.get(handle) {
+            self.result_cache.invalidate(handle);
+            return Ok(output);
+        }
+
+        let ctx = self.construct_context(user, metadata)?;
+        let query = Query::new(ctx, entry.sql.clone());
+        let parameters = parameters.unwrap_or_else(|| entry.parameters.lock().clone());
+        let query_result = self
+            .instance
+            .execute_prepared(&query, &entry.statement, parameters)
+            .await
+            .map_err(utils::query_error_to_status)?;
+
+        Ok(query_result.result())
+    }
+
+    /// Decode the parameter rows of the prepared statement,
+    /// the schema message may have been taken along with the command
+    async fn decode_parameters(
+        &self,
+        mut stream: Streaming<FlightData>,
+        schema: SchemaRef,
+    ) -> Result<Vec<RecordBatch>, Status> {
+        let mut schema = schema;
+        let dictionaries_by_id = HashMap::new();
+
+        let mut batches = vec![];
+        while let Some(data) = stream.message().await? {
+            let message = root_as_message(&data.data_header[..])
+                .map_err(|e| Status::invalid_argument(format!("Invalid flight data: {}", e)))?;
+            match message.header_type() {
+                MessageHeader::Schema => {
+                    schema = Arc::new(
+                        Schema::try_from(&data)
+                            .map_err(|e| Status::invalid_argument(format!("{}", e)))?,
+                    );
+                }
+                MessageHeader::RecordBatch => {
+                    let batch =
+                        flight_data_to_arrow_batch(&data, schema.clone(), &dictionaries_by_id)
+                            .map_err(|e| Status::invalid_argument(format!("{}", e)))?;
+                    batches.push(batch);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(batches)
     }
 }
 
@@ -236,10 +420,12 @@ where
 /// .   }
 /// ```
 /// 1. do_handshake: basic auth -> baerar token
-/// 2. do_action_create_prepared_statement: sql(baerar token) -> sql
-/// 3. do_put_prepared_statement_update: not use
-/// 4. get_flight_info_prepared_statement: sql(baerar token) -> address of resut set
+/// 2. do_action_create_prepared_statement: sql(baerar token) -> handle of prepared statement
+/// 3. do_put_prepared_statement_query: bind the parameters of the prepared statement
+/// 4. get_flight_info_prepared_statement: handle(baerar token) -> address of resut set
+///    or do_put_prepared_statement_update: handle(baerar token) -> number of affected rows
 /// 5. do_get_statement: address of resut set(baerar token) -> resut set stream
+/// 6. do_action_close_prepared_statement: handle(baerar token)
 /// ```
 ///
 /// use flight sql to execute statement query:
@@ -250,6 +436,12 @@ where
 /// 4. get_flight_info_statement: sql(baerar token) -> address of resut set
 /// 5. do_get_statement: address of resut set(baerar token) -> resut set stream
 ///
+/// use flight sql to browse metadata, e.g. the schema browsers of jdbc clients:
+///
+/// 1. do_handshake: basic auth -> baerar token
+/// 2. get_flight_info_catalogs/schemas/tables/...: filters(baerar token) -> address of resut set
+/// 3. do_get_statement: address of resut set(baerar token) -> resut set stream
+///
 #[tonic::async_trait]
 impl<T> FlightSqlService for FlightSqlServiceImpl<T>
 where
@@ -299,8 +491,10 @@ where
         self.precess_statement_query_req(sql, request).await
     }
 
-    /// Fetch meta of the prepared statement.
+    /// Execute the prepared statement with the bound parameters.
     ///
+    /// Return the address of the result set,
+    /// waiting to call [`Self::do_get_statement`] to get the result set.
     /// The prepared statement can be reused after fetching results.
     async fn get_flight_info_prepared_statement(
         &self,
@@ -315,24 +509,22 @@ where
         let CommandPreparedStatementQuery {
             prepared_statement_handle,
         } = query;
-        let prepared_statement_handle = prepared_statement_handle.to_byte_slice().to_owned();
 
-        // get metadata of result from cache
         let output = self
-            .result_cache
-            .get(&prepared_statement_handle)
-            .ok_or_else(|| {
-                Status::internal(format!(
-                    "The result of query({:?}) does not exist or has expired",
-                    prepared_statement_handle
-                ))
-            })?;
+            .execute_prepared(&prepared_statement_handle, request.metadata(), None)
+            .await?;
+
+        // get result metadata
         let schema = output.schema();
         let total_records = output.num_rows();
 
+        // cache result wait cli fetching
+        let result_ident = self.id_generator.next_id().to_le_bytes().to_vec();
+        self.result_cache.insert(result_ident.clone(), output);
+
         // construct response start
         let flight_info = self.construct_flight_info(
-            prepared_statement_handle,
+            result_ident,
             schema.as_ref(),
             total_records as i64,
             request.into_inner(),
@@ -341,8 +533,7 @@ where
         Ok(Response::new(flight_info))
     }
 
-    /// TODO support
-    /// wait for <https://github.com/cnosdb/cnosdb/issues/642>
+    /// List the catalogs, i.e. the tenant of the connection
     async fn get_flight_info_catalogs(
         &self,
         query: CommandGetCatalogs,
@@ -353,13 +544,15 @@ where
             query, request
         );
 
-        Err(Status::unimplemented(
-            "get_flight_info_catalogs not implemented",
-        ))
+        let tenant = self.auth_tenant(request.metadata()).await?;
+
+        self.metadata_flight_info(
+            metadata::catalogs_batch(&[tenant.as_str()]),
+            request.into_inner(),
+        )
     }
 
-    /// TODO support
-    /// wait for <https://github.com/cnosdb/cnosdb/issues/642>
+    /// List the schemas, i.e. the databases readable by the user
     async fn get_flight_info_schemas(
         &self,
         query: CommandGetDbSchemas,
@@ -370,13 +563,31 @@ where
             query, request
         );
 
-        Err(Status::unimplemented(
-            "get_flight_info_schemas not implemented",
-        ))
+        let CommandGetDbSchemas {
+            catalog,
+            db_schema_filter_pattern,
+        } = query;
+
+        let sql = metadata::databases_sql(&db_schema_filter_pattern);
+        let (tenant, batches) = self
+            .query_information_schema(sql, request.metadata())
+            .await?;
+
+        let databases = if metadata::catalog_matches(&catalog, &tenant) {
+            metadata::databases_from_batches(&batches)
+                .map_err(|e| Status::internal(format!("{}", e)))?
+        } else {
+            vec![]
+        };
+
+        self.metadata_flight_info(
+            metadata::schemas_batch(&tenant, &databases),
+            request.into_inner(),
+        )
     }
 
-    /// TODO support
-    /// wait for <https://github.com/cnosdb/cnosdb/issues/642>
+    /// List the tables of the databases readable by the user,
+    /// the schemas of the tables are annotated with the types of the columns, i.e. TAG, FIELD or TIME
     async fn get_flight_info_tables(
         &self,
         query: CommandGetTables,
@@ -387,13 +598,46 @@ where
             query, request
         );
 
-        Err(Status::unimplemented(
-            "get_flight_info_tables not implemented",
-        ))
+        let CommandGetTables {
+            catalog,
+            db_schema_filter_pattern,
+            table_name_filter_pattern,
+            table_types,
+            include_schema,
+        } = query;
+
+        let sql = metadata::tables_sql(&db_schema_filter_pattern, &table_name_filter_pattern);
+        let (tenant, batches) = self
+            .query_information_schema(sql, request.metadata())
+            .await?;
+        let tables = if metadata::catalog_matches(&catalog, &tenant) {
+            metadata::tables_from_batches(&batches)
+                .map_err(|e| Status::internal(format!("{}", e)))?
+        } else {
+            vec![]
+        };
+
+        let columns = if include_schema && !tables.is_empty() {
+            let sql = metadata::columns_sql(&db_schema_filter_pattern, &table_name_filter_pattern);
+            let (_, batches) = self
+                .query_information_schema(sql, request.metadata())
+                .await?;
+            let columns = metadata::columns_from_batches(&batches)
+                .map_err(|e| Status::internal(format!("{}", e)))?;
+            Some(columns)
+        } else if include_schema {
+            Some(vec![])
+        } else {
+            None
+        };
+
+        self.metadata_flight_info(
+            metadata::tables_batch(&tenant, &tables, &table_types, columns.as_deref()),
+            request.into_inner(),
+        )
     }
 
-    /// TODO support
-    /// wait for <https://github.com/cnosdb/cnosdb/issues/642>
+    /// List the table types
     async fn get_flight_info_table_types(
         &self,
         query: CommandGetTableTypes,
@@ -404,12 +648,12 @@ where
             query, request
         );
 
-        Err(Status::unimplemented(
-            "get_flight_info_table_types not implemented",
-        ))
+        self.authenticator.authenticate(request.metadata()).await?;
+
+        self.metadata_flight_info(metadata::table_types_batch(), request.into_inner())
     }
 
-    /// not support
+    /// Fetch the capabilities of the server
     async fn get_flight_info_sql_info(
         &self,
         query: CommandGetSqlInfo,
@@ -420,12 +664,12 @@ where
             query, request
         );
 
-        Err(Status::unimplemented(
-            "get_flight_info_sql_info not implemented",
-        ))
+        self.authenticator.authenticate(request.metadata()).await?;
+
+        self.metadata_flight_info(metadata::sql_info_batch(&query.info), request.into_inner())
     }
 
-    /// not support
+    /// The primary key of a table is the time and the tags, which identify a row
     async fn get_flight_info_primary_keys(
         &self,
         query: CommandGetPrimaryKeys,
@@ -436,12 +680,36 @@ where
             query, request
         );
 
-        Err(Status::unimplemented(
-            "get_flight_info_primary_keys not implemented",
-        ))
+        let CommandGetPrimaryKeys {
+            catalog,
+            db_schema,
+            table,
+        } = query;
+
+        let sql = metadata::columns_sql(&db_schema, &Some(table.clone()));
+        let (tenant, batches) = self
+            .query_information_schema(sql, request.metadata())
+            .await?;
+        let columns = if metadata::catalog_matches(&catalog, &tenant) {
+            metadata::columns_from_batches(&batches)
+                .map_err(|e| Status::internal(format!("{}", e)))?
+        } else {
+            vec![]
+        };
+        // `_` and `%` of the names are wildcards of LIKE
+        let columns = columns
+            .into_iter()
+            .filter(|e| e.table == table)
+            .filter(|e| db_schema.as_ref().map(|s| s == &e.database).unwrap_or(true))
+            .collect::<Vec<_>>();
+
+        self.metadata_flight_info(
+            metadata::primary_keys_batch(&tenant, &columns),
+            request.into_inner(),
+        )
     }
 
-    /// not support
+    /// There are no foreign keys
     async fn get_flight_info_exported_keys(
         &self,
         query: CommandGetExportedKeys,
@@ -452,12 +720,12 @@ where
             query, request
         );
 
-        Err(Status::unimplemented(
-            "get_flight_info_exported_keys not implemented",
-        ))
+        self.authenticator.authenticate(request.metadata()).await?;
+
+        self.metadata_flight_info(Ok(metadata::foreign_keys_batch()), request.into_inner())
     }
 
-    /// not support
+    /// There are no foreign keys
     async fn get_flight_info_imported_keys(
         &self,
         query: CommandGetImportedKeys,
@@ -468,12 +736,12 @@ where
             query, request
         );
 
-        Err(Status::unimplemented(
-            "get_flight_info_imported_keys not implemented",
-        ))
+        self.authenticator.authenticate(request.metadata()).await?;
+
+        self.metadata_flight_info(Ok(metadata::foreign_keys_batch()), request.into_inner())
     }
 
-    /// not support
+    /// There are no foreign keys
     async fn get_flight_info_cross_reference(
         &self,
         query: CommandGetCrossReference,
@@ -484,9 +752,9 @@ where
             query, request
         );
 
-        Err(Status::unimplemented(
-            "get_flight_info_imported_keys not implemented",
-        ))
+        self.authenticator.authenticate(request.metadata()).await?;
+
+        self.metadata_flight_info(Ok(metadata::foreign_keys_batch()), request.into_inner())
     }
 
     /// Fetch the ad-hoc SQL query's result set
@@ -537,8 +805,7 @@ where
         Ok(Response::new(output))
     }
 
-    /// TODO support
-    /// wait for <https://github.com/cnosdb/cnosdb/issues/642>
+    /// Unused, the ticket of [`Self::get_flight_info_catalogs`] is fetched by [`Self::do_get_statement`]
     async fn do_get_catalogs(
         &self,
         query: CommandGetCatalogs,
@@ -552,8 +819,7 @@ where
         Err(Status::unimplemented("do_get_catalogs not implemented"))
     }
 
-    /// TODO support
-    /// wait for <https://github.com/cnosdb/cnosdb/issues/642>
+    /// Unused, the ticket of [`Self::get_flight_info_schemas`] is fetched by [`Self::do_get_statement`]
     async fn do_get_schemas(
         &self,
         query: CommandGetDbSchemas,
@@ -564,8 +830,7 @@ where
         Err(Status::unimplemented("do_get_schemas not implemented"))
     }
 
-    /// TODO support
-    /// wait for `<https://github.com/cnosdb/cnosdb/issues/642>`
+    /// Unused, the ticket of [`Self::get_flight_info_tables`] is fetched by [`Self::do_get_statement`]
     async fn do_get_tables(
         &self,
         query: CommandGetTables,
@@ -576,8 +841,7 @@ where
         Err(Status::unimplemented("do_get_tables not implemented"))
     }
 
-    /// TODO support
-    /// wait for <https://github.com/cnosdb/cnosdb/issues/642>
+    /// Unused, the ticket of [`Self::get_flight_info_table_types`] is fetched by [`Self::do_get_statement`]
     async fn do_get_table_types(
         &self,
         query: CommandGetTableTypes,
@@ -591,7 +855,7 @@ where
         Err(Status::unimplemented("do_get_table_types not implemented"))
     }
 
-    /// not support
+    /// Unused, the ticket of [`Self::get_flight_info_sql_info`] is fetched by [`Self::do_get_statement`]
     async fn do_get_sql_info(
         &self,
         query: CommandGetSqlInfo,
@@ -605,7 +869,7 @@ where
         Err(Status::unimplemented("do_get_sql_info not implemented"))
     }
 
-    /// not support
+    /// Unused, the ticket of [`Self::get_flight_info_primary_keys`] is fetched by [`Self::do_get_statement`]
     async fn do_get_primary_keys(
         &self,
         query: CommandGetPrimaryKeys,
@@ -619,7 +883,7 @@ where
         Err(Status::unimplemented("do_get_primary_keys not implemented"))
     }
 
-    /// not support
+    /// Unused, the ticket of [`Self::get_flight_info_exported_keys`] is fetched by [`Self::do_get_statement`]
     async fn do_get_exported_keys(
         &self,
         query: CommandGetExportedKeys,
@@ -635,7 +899,7 @@ where
         ))
     }
 
-    /// not support
+    /// Unused, the ticket of [`Self::get_flight_info_imported_keys`] is fetched by [`Self::do_get_statement`]
     async fn do_get_imported_keys(
         &self,
         _query: CommandGetImportedKeys,
@@ -646,7 +910,7 @@ where
         ))
     }
 
-    /// not support
+    /// Unused, the ticket of [`Self::get_flight_info_cross_reference`] is fetched by [`Self::do_get_statement`]
     async fn do_get_cross_reference(
         &self,
         _query: CommandGetCrossReference,
@@ -679,7 +943,8 @@ where
         Ok(affected_rows)
     }
 
-    /// not support
+    /// Bind the parameter rows to the prepared statement,
+    /// which are used by the following executions until bound again.
     async fn do_put_prepared_statement_query(
         &self,
         query: CommandPreparedStatementQuery,
@@ -690,16 +955,25 @@ where
             query, request
         );
 
-        Err(Status::unimplemented(
-            "do_put_prepared_statement_query not implemented",
-        ))
+        let handle = query.prepared_statement_handle.to_byte_slice();
+        let (_, entry) = self
+            .auth_prepared_statement(handle, request.metadata())
+            .await?;
+
+        let parameters = self
+            .decode_parameters(request.into_inner(), entry.statement.parameter_schema())
+            .await?;
+        *entry.parameters.lock() = parameters;
+
+        let output: <Self as FlightService>::DoPutStream = Box::pin(futures::stream::empty());
+        Ok(Response::new(output))
     }
 
-    /// Execute the query and return the number of affected rows.
+    /// Execute the prepared statement with the parameter rows of the request,
+    /// and return the number of affected rows.
     /// The prepared statement can be reused afterwards.
     ///
-    /// Prepared statement is not supported,
-    /// because ad-hoc statement of flight jdbc needs to call this interface, so it is simple to implement
+    /// All the rows are written at once by `INSERT INTO ... VALUES (?, ?, ...)`.
     async fn do_put_prepared_statement_update(
         &self,
         query: CommandPreparedStatementUpdate,
@@ -710,16 +984,32 @@ where
             query, request
         );
 
-        let prepared_statement_handle = query.prepared_statement_handle.to_byte_slice();
+        let handle = query.prepared_statement_handle.to_byte_slice();
+        let (_, entry) = self
+            .auth_prepared_statement(handle, request.metadata())
+            .await?;
+
+        let metadata = request.metadata().clone();
+        let parameters = self
+            .decode_parameters(request.into_inner(), entry.statement.parameter_schema())
+            .await?;
+        // Without rows in the request, the parameters bound before are used
+        let parameters = if parameters.is_empty() {
+            None
+        } else {
+            Some(parameters)
+        };
 
-        let rows_count = self.fetch_affected_rows_count(prepared_statement_handle)?;
+        let output = self.execute_prepared(handle, &metadata, parameters).await?;
 
-        Ok(rows_count)
+        Ok(output.affected_rows())
     }
 
-    /// Prepared statement is not supported,
-    /// because ad-hoc statement of flight jdbc needs to call this interface,
-    /// so directly return the sql as the result.
+    /// Plan the statement, `?` and `$1`, `$2`... in the statement are the parameters.
+    ///
+    /// Jdbc tells queries from updates by whether the schema of the result set is empty,
+    /// the statements whose result is only known after the execution, e.g. SHOW and DDL,
+    /// are executed at once, and the result is returned by the first execution.
     async fn do_action_create_prepared_statement(
         &self,
         query: ActionCreatePreparedStatementRequest,
@@ -734,42 +1024,70 @@ where
         let metadata = request.metadata();
 
         let user_info = self.authenticator.authenticate(metadata).await?.identity();
+        let owner = user_info.desc().clone();
 
         // construct context by user_info and headers(parse tenant & default database)
         let ctx = self.construct_context(user_info, metadata)?;
+        let query = Query::new(ctx, sql.clone());
 
-        // execute sql
-        let query_result = self.execute(sql, ctx).await?;
-
-        // generate result identifier
-        let result_ident = self.id_generator.next_id().to_le_bytes().to_vec();
-
-        // get result metadata
-        let output = query_result.result();
-        let schema = output.schema();
-        let _total_records = output.num_rows();
-
-        // cache result wait cli fetching
-        self.result_cache.insert(result_ident.clone(), output);
+        let statement = self
+            .instance
+            .prepare(&query)
+            .await
+            .map_err(utils::query_error_to_status)?;
+
+        // generate handle of the prepared statement
+        let handle = self.id_generator.next_id().to_le_bytes().to_vec();
+
+        let dataset_schema = match statement.result() {
+            PreparedResult::Rows(schema) => schema,
+            PreparedResult::AffectedRows => Arc::new(Schema::empty()),
+            PreparedResult::Unknown => {
+                let output = self
+                    .instance
+                    .execute_prepared(&query, &statement, vec![])
+                    .await
+                    .map_err(utils::query_error_to_status)?
+                    .result();
+                let schema = output.schema();
+                // cache result wait cli fetching
+                self.result_cache.insert(handle.clone(), output);
+                schema
+            }
+        };
+        let parameter_schema = statement.parameter_schema();
+
+        self.prepared_statements.insert(
+            handle.clone(),
+            Arc::new(PreparedStatementEntry {
+                owner,
+                sql,
+                statement,
+                parameters: Default::default(),
+            }),
+        );
 
         // construct response start
         let IpcMessage(dataset_schema) = IpcMessage::try_from(SchemaAsIpc::new(
-            schema.as_ref(),
+            dataset_schema.as_ref(),
+            &IpcWriteOptions::default(),
+        ))
+        .map_err(|e| Status::internal(format!("{}", e)))?;
+        let IpcMessage(parameter_schema) = IpcMessage::try_from(SchemaAsIpc::new(
+            parameter_schema.as_ref(),
             &IpcWriteOptions::default(),
         ))
         .map_err(|e| Status::internal(format!("{}", e)))?;
         let result = ActionCreatePreparedStatementResult {
-            prepared_statement_handle: result_ident.into(),
+            prepared_statement_handle: handle.into(),
             dataset_schema,
-            ..Default::default()
+            parameter_schema,
         };
 
         Ok(result)
     }
 
     /// Close a previously created prepared statement.
-    ///
-    /// Empty logic, because we not save created prepared statement.
     async fn do_action_close_prepared_statement(
         &self,
         query: ActionClosePreparedStatementRequest,
@@ -779,6 +1097,16 @@ where
             "do_action_close_prepared_statement: query: {:?}, request: {:?}",
             query, request
         );
+
+        let handle = query.prepared_statement_handle.to_byte_slice();
+        if self
+            .auth_prepared_statement(handle, request.metadata())
+            .await
+            .is_ok()
+        {
+            self.prepared_statements.invalidate(handle);
+            self.result_cache.invalidate(handle);
+        }
     }
 
     /// not support
@@ -0,0 +1,645 @@
+//! Results of the metadata commands of Flight SQL, e.g. `CommandGetTables`.
+//!
+//! Tenants, databases and tables of CnosDB are the catalogs, schemas and tables of Flight SQL,
+//! their metadata is queried from `information_schema` of the tenant of the connection.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use arrow_flight::sql::SqlInfo;
+use arrow_flight::{IpcMessage, SchemaAsIpc};
+use datafusion::arrow::array::{
+    Array, ArrayBuilder, ArrayRef, BinaryBuilder, BooleanArray, BooleanBuilder, Int32Builder,
+    Int64Builder, ListBuilder, MapBuilder, StringArray, StringBuilder, UInt32Builder, UnionArray,
+};
+use datafusion::arrow::buffer::Buffer;
+use datafusion::arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+use datafusion::arrow::error::{ArrowError, Result};
+use datafusion::arrow::ipc::writer::IpcWriteOptions;
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::sql::sqlparser::keywords::ALL_KEYWORDS;
+use models::schema::TAG;
+
+/// Metadata keys of the columns of Flight SQL
+const CATALOG_NAME: &str = "ARROW:FLIGHT:SQL:CATALOG_NAME";
+const SCHEMA_NAME: &str = "ARROW:FLIGHT:SQL:SCHEMA_NAME";
+const TABLE_NAME: &str = "ARROW:FLIGHT:SQL:TABLE_NAME";
+const TYPE_NAME: &str = "ARROW:FLIGHT:SQL:TYPE_NAME";
+/// TAG, FIELD or TIME
+pub const COLUMN_TYPE: &str = "_column_type";
+
+const TABLE_TYPE: &str = "TABLE";
+
+/// A table of `information_schema.tables`
+pub struct TableInfo {
+    pub database: String,
+    pub name: String,
+    pub table_type: String,
+}
+
+/// A column of `information_schema.columns`
+pub struct ColumnInfo {
+    pub database: String,
+    pub table: String,
+    pub name: String,
+    pub column_type: String,
+    pub data_type: String,
+    pub nullable: bool,
+}
+
+pub fn databases_sql(db_schema_filter_pattern: &Option<String>) -> String {
+    select(
+        "database_name",
+        "databases",
+        like("database_name", db_schema_filter_pattern),
+        "database_name",
+    )
+}
+
+pub fn tables_sql(
+    db_schema_filter_pattern: &Option<String>,
+    table_name_filter_pattern: &Option<String>,
+) -> String {
+    let conditions = like("table_database", db_schema_filter_pattern)
+        .into_iter()
+        .chain(like("table_name", table_name_filter_pattern))
+        .collect();
+    select(
+        "table_database, table_name, table_type",
+        "tables",
+        conditions,
+        "table_database, table_name",
+    )
+}
+
+pub fn columns_sql(
+    db_schema_filter_pattern: &Option<String>,
+    table_name_filter_pattern: &Option<String>,
+) -> String {
+    let conditions = like("database_name", db_schema_filter_pattern)
+        .into_iter()
+        .chain(like("table_name", table_name_filter_pattern))
+        .collect();
+    select(
+        "database_name, table_name, column_name, column_type, data_type, is_nullable",
+        "columns",
+        conditions,
+        "database_name, table_name, ordinal_position",
+    )
+}
+
+fn select(projection: &str, table: &str, conditions: Vec<String>, order_by: &str) -> String {
+    let selection = if conditions.is_empty() {
+        String::new()
+    } else {
+        format!(" WHERE {}", conditions.join(" AND "))
+    };
+
+    format!(
+        "SELECT {} FROM information_schema.{}{} ORDER BY {}",
+        projection, table, selection, order_by
+    )
+}
+
+/// The filter patterns of the commands are the patterns of LIKE,
+/// no filter if the pattern is not specified
+fn like(column: &str, pattern: &Option<String>) -> Option<String> {
+    pattern
+        .as_ref()
+        .map(|e| format!("{} LIKE '{}'", column, e.replace('\'', "''")))
+}
+
+/// A catalog filter matches the tenant of the connection only
+pub fn catalog_matches(catalog: &Option<String>, tenant: &str) -> bool {
+    catalog.as_deref().map(|e| e == tenant).unwrap_or(true)
+}
+
+pub fn databases_from_batches(batches: &[RecordBatch]) -> Result<Vec<String>> {
+    let mut databases = vec![];
+    for batch in batches {
+        let names = string_column(batch, 0)?;
+        databases.extend((0..batch.num_rows()).map(|row| names.value(row).to_string()));
+    }
+
+    Ok(databases)
+}
+
+pub fn tables_from_batches(batches: &[RecordBatch]) -> Result<Vec<TableInfo>> {
+    let mut tables = vec![];
+    for batch in batches {
+        let databases = string_column(batch, 0)?;
+        let names = string_column(batch, 1)?;
+        let table_types = string_column(batch, 2)?;
+        for row in 0..batch.num_rows() {
+            let table_type = match table_types.value(row) {
+                "BASE TABLE" => TABLE_TYPE,
+                table_type => table_type,
+            };
+            tables.push(TableInfo {
+                database: databases.value(row).to_string(),
+                name: names.value(row).to_string(),
+                table_type: table_type.to_string(),
+            });
+        }
+    }
+
+    Ok(tables)
+}
+
+pub fn columns_from_batches(batches: &[RecordBatch]) -> Result<Vec<ColumnInfo>> {
+    let mut columns = vec![];
+    for batch in batches {
+        let databases = string_column(batch, 0)?;
+        let tables = string_column(batch, 1)?;
+        let names = string_column(batch, 2)?;
+        let column_types = string_column(batch, 3)?;
+        let data_types = string_column(batch, 4)?;
+        let nullables = batch
+            .column(5)
+            .as_any()
+            .downcast_ref::<BooleanArray>()
+            .ok_or_else(|| unexpected_column(batch, 5))?;
+        for row in 0..batch.num_rows() {
+            columns.push(ColumnInfo {
+                database: databases.value(row).to_string(),
+                table: tables.value(row).to_string(),
+                name: names.value(row).to_string(),
+                column_type: column_types.value(row).to_string(),
+                data_type: data_types.value(row).to_string(),
+                nullable: nullables.value(row),
+            });
+        }
+    }
+
+    Ok(columns)
+}
+
+fn string_column(batch: &RecordBatch, index: usize) -> Result<&StringArray> {
+    batch
+        .column(index)
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .ok_or_else(|| unexpected_column(batch, index))
+}
+
+fn unexpected_column(batch: &RecordBatch, index: usize) -> ArrowError {
+    ArrowError::SchemaError(format!(
+        "Unexpected column {} of information_schema",
+        batch.schema().field(index)
+    ))
+}
+
+pub fn catalogs_batch(catalogs: &[&str]) -> Result<RecordBatch> {
+    let schema = Schema::new(vec![Field::new("catalog_name", DataType::Utf8, false)]);
+    let catalogs: ArrayRef = Arc::new(StringArray::from(catalogs.to_vec()));
+
+    RecordBatch::try_new(Arc::new(schema), vec![catalogs])
+}
+
+pub fn schemas_batch(catalog: &str, databases: &[String]) -> Result<RecordBatch> {
+    let schema = Schema::new(vec![
+        Field::new("catalog_name", DataType::Utf8, true),
+        Field::new("db_schema_name", DataType::Utf8, false),
+    ]);
+    let catalogs: ArrayRef = Arc::new(StringArray::from(vec![catalog; databases.len()]));
+    let databases: ArrayRef = Arc::new(StringArray::from_iter_values(databases));
+
+    RecordBatch::try_new(Arc::new(schema), vec![catalogs, databases])
+}
+
+pub fn table_types_batch() -> Result<RecordBatch> {
+    let schema = Schema::new(vec![Field::new("table_type", DataType::Utf8, false)]);
+    let table_types: ArrayRef = Arc::new(StringArray::from(vec![TABLE_TYPE]));
+
+    RecordBatch::try_new(Arc::new(schema), vec![table_types])
+}
+
+/// The tables whose types are not in `table_types` are skipped unless `table_types` is empty,
+/// `columns` are the columns of the tables if the schemas of the tables are included
+pub fn tables_batch(
+    catalog: &str,
+    tables: &[TableInfo],
+    table_types: &[String],
+    columns: Option<&[ColumnInfo]>,
+) -> Result<RecordBatch> {
+    let mut fields = vec![
+        Field::new("catalog_name", DataType::Utf8, true),
+        Field::new("db_schema_name", DataType::Utf8, true),
+        Field::new("table_name", DataType::Utf8, false),
+        Field::new("table_type", DataType::Utf8, false),
+    ];
+    let mut catalogs = StringBuilder::new();
+    let mut databases = StringBuilder::new();
+    let mut names = StringBuilder::new();
+    let mut types = StringBuilder::new();
+    let mut schemas = BinaryBuilder::new();
+
+    let mut columns_by_table: HashMap<(&str, &str), Vec<&ColumnInfo>> = HashMap::new();
+    for column in columns.unwrap_or_default() {
+        columns_by_table
+            .entry((column.database.as_str(), column.table.as_str()))
+            .or_default()
+            .push(column);
+    }
+
+    let tables = tables
+        .iter()
+        .filter(|e| table_types.is_empty() || table_types.contains(&e.table_type));
+    for table in tables {
+        catalogs.append_value(catalog);
+        databases.append_value(&table.database);
+        names.append_value(&table.name);
+        types.append_value(&table.table_type);
+
+        if columns.is_some() {
+            let columns = columns_by_table
+                .get(&(table.database.as_str(), table.name.as_str()))
+                .map(|e| e.as_slice())
+                .unwrap_or_default();
+            let schema = table_schema(catalog, columns);
+            let IpcMessage(schema) =
+                IpcMessage::try_from(SchemaAsIpc::new(&schema, &IpcWriteOptions::default()))?;
+            schemas.append_value(schema);
+        }
+    }
+
+    let mut arrays: Vec<ArrayRef> = vec![
+        Arc::new(catalogs.finish()),
+        Arc::new(databases.finish()),
+        Arc::new(names.finish()),
+        Arc::new(types.finish()),
+    ];
+    if columns.is_some() {
+        fields.push(Field::new("table_schema", DataType::Binary, false));
+        arrays.push(Arc::new(schemas.finish()));
+    }
+
+    RecordBatch::try_new(Arc::new(Schema::new(fields)), arrays)
+}
+
+/// The arrow schema of the table, the columns are annotated with their column types,
+/// i.e. TAG, FIELD or TIME
+pub fn table_schema(catalog: &str, columns: &[&ColumnInfo]) -> Schema {
+    let fields = columns
+        .iter()
+        .map(|column| {
+            let mut field = Field::new(
+                &column.name,
+                arrow_data_type(&column.column_type, &column.data_type),
+                column.nullable,
+            );
+            field.set_metadata(HashMap::from([
+                (CATALOG_NAME.to_string(), catalog.to_string()),
+                (SCHEMA_NAME.to_string(), column.database.clone()),
+                (TABLE_NAME.to_string(), column.table.clone()),
+                (TYPE_NAME.to_string(), column.data_type.clone()),
+                (COLUMN_TYPE.to_string(), column.column_type.clone()),
+                (TAG.to_string(), (column.column_type == "TAG").to_string()),
+            ]));
+            field
+        })
+        .collect();
+
+    Schema::new(fields)
+}
+
+/// The types of the columns of tskv tables are sql types,
+/// and the types of the columns of external tables are arrow types
+fn arrow_data_type(column_type: &str, data_type: &str) -> DataType {
+    match (column_type, data_type) {
+        ("TIME", _) => DataType::Timestamp(TimeUnit::Nanosecond, None),
+        (_, "STRING" | "Utf8") => DataType::Utf8,
+        (_, "BIGINT" | "Int64") => DataType::Int64,
+        (_, "BIGINT UNSIGNED" | "UInt64") => DataType::UInt64,
+        (_, "DOUBLE" | "Float64") => DataType::Float64,
+        (_, "BOOLEAN" | "Boolean") => DataType::Boolean,
+        (_, "LargeUtf8") => DataType::LargeUtf8,
+        (_, "Int8") => DataType::Int8,
+        (_, "Int16") => DataType::Int16,
+        (_, "Int32") => DataType::Int32,
+        (_, "UInt8") => DataType::UInt8,
+        (_, "UInt16") => DataType::UInt16,
+        (_, "UInt32") => DataType::UInt32,
+        (_, "Float32") => DataType::Float32,
+        (_, "Date32") => DataType::Date32,
+        (_, "Date64") => DataType::Date64,
+        (_, "Binary") => DataType::Binary,
+        (_, "Timestamp(Second, None)") => DataType::Timestamp(TimeUnit::Second, None),
+        (_, "Timestamp(Millisecond, None)") => DataType::Timestamp(TimeUnit::Millisecond, None),
+        (_, "Timestamp(Microsecond, None)") => DataType::Timestamp(TimeUnit::Microsecond, None),
+        (_, "Timestamp(Nanosecond, None)") => DataType::Timestamp(TimeUnit::Nanosecond, None),
+        // The exact type is kept in the metadata of the field
+        _ => DataType::Utf8,
+    }
+}
+
+/// The time and the tags identify a row of a tskv table
+pub fn primary_keys_batch(catalog: &str, columns: &[ColumnInfo]) -> Result<RecordBatch> {
+    let schema = Schema::new(vec![
+        Field::new("catalog_name", DataType::Utf8, true),
+        Field::new("db_schema_name", DataType::Utf8, true),
+        Field::new("table_name", DataType::Utf8, false),
+        Field::new("column_name", DataType::Utf8, false),
+        Field::new("key_name", DataType::Utf8, true),
+        Field::new("key_sequence", DataType::Int32, false),
+    ]);
+    let mut catalogs = StringBuilder::new();
+    let mut databases = StringBuilder::new();
+    let mut tables = StringBuilder::new();
+    let mut names = StringBuilder::new();
+    let mut key_names = StringBuilder::new();
+    let mut key_sequences = Int32Builder::new();
+
+    let keys = columns
+        .iter()
+        .filter(|e| e.column_type == "TIME" || e.column_type == "TAG");
+    for (index, column) in keys.enumerate() {
+        catalogs.append_value(catalog);
+        databases.append_value(&column.database);
+        tables.append_value(&column.table);
+        names.append_value(&column.name);
+        key_names.append_null();
+        key_sequences.append_value(index as i32 + 1);
+    }
+
+    RecordBatch::try_new(
+        Arc::new(schema),
+        vec![
+            Arc::new(catalogs.finish()),
+            Arc::new(databases.finish()),
+            Arc::new(tables.finish()),
+            Arc::new(names.finish()),
+            Arc::new(key_names.finish()),
+            Arc::new(key_sequences.finish()),
+        ],
+    )
+}
+
+/// There are no foreign keys, the result of exported keys, imported keys and cross reference
+/// is always empty
+pub fn foreign_keys_batch() -> RecordBatch {
+    let schema = Schema::new(vec![
+        Field::new("pk_catalog_name", DataType::Utf8, true),
+        Field::new("pk_db_schema_name", DataType::Utf8, true),
+        Field::new("pk_table_name", DataType::Utf8, false),
+        Field::new("pk_column_name", DataType::Utf8, false),
+        Field::new("fk_catalog_name", DataType::Utf8, true),
+        Field::new("fk_db_schema_name", DataType::Utf8, true),
+        Field::new("fk_table_name", DataType::Utf8, false),
+        Field::new("fk_column_name", DataType::Utf8, false),
+        Field::new("key_sequence", DataType::Int32, false),
+        Field::new("fk_key_name", DataType::Utf8, true),
+        Field::new("pk_key_name", DataType::Utf8, true),
+        Field::new("update_rule", DataType::UInt8, false),
+        Field::new("delete_rule", DataType::UInt8, false),
+    ]);
+
+    RecordBatch::new_empty(Arc::new(schema))
+}
+
+/// Value of [`SqlInfo`]
+enum SqlInfoValue {
+    String(&'static str),
+    Bool(bool),
+    /// Ordinals of enums and bitmasks
+    Int32(i32),
+    StringList(&'static [&'static str]),
+}
+
+fn sql_info_values() -> Vec<(SqlInfo, SqlInfoValue)> {
+    vec![
+        (SqlInfo::FlightSqlServerName, SqlInfoValue::String("CnosDB")),
+        (
+            SqlInfo::FlightSqlServerVersion,
+            SqlInfoValue::String(env!("CARGO_PKG_VERSION")),
+        ),
+        (SqlInfo::FlightSqlServerReadOnly, SqlInfoValue::Bool(false)),
+        // CREATE TENANT, CREATE DATABASE and CREATE TABLE
+        (SqlInfo::SqlDdlCatalog, SqlInfoValue::Bool(true)),
+        (SqlInfo::SqlDdlSchema, SqlInfoValue::Bool(true)),
+        (SqlInfo::SqlDdlTable, SqlInfoValue::Bool(true)),
+        // Unquoted identifiers are lowercase
+        (SqlInfo::SqlIdentifierCase, SqlInfoValue::Int32(3)),
+        (SqlInfo::SqlIdentifierQuoteChar, SqlInfoValue::String("\"")),
+        (SqlInfo::SqlAllTablesAreSelectable, SqlInfoValue::Bool(true)),
+        // NULLS LAST for ascending order and NULLS FIRST for descending order
+        (SqlInfo::SqlNullOrdering, SqlInfoValue::Int32(0)),
+        (SqlInfo::SqlKeywords, SqlInfoValue::StringList(ALL_KEYWORDS)),
+        (SqlInfo::SqlSearchStringEscape, SqlInfoValue::String("\\")),
+        (SqlInfo::SqlExtraNameCharacters, SqlInfoValue::String("")),
+        (SqlInfo::SqlSupportsColumnAliasing, SqlInfoValue::Bool(true)),
+        (SqlInfo::SqlNullPlusNullIsNull, SqlInfoValue::Bool(true)),
+        (
+            SqlInfo::SqlSupportsTableCorrelationNames,
+            SqlInfoValue::Bool(true),
+        ),
+        (
+            SqlInfo::SqlSupportsExpressionsInOrderBy,
+            SqlInfoValue::Bool(true),
+        ),
+        (
+            SqlInfo::SqlSupportsLikeEscapeClause,
+            SqlInfoValue::Bool(false),
+        ),
+        (
+            SqlInfo::SqlSupportsNonNullableColumns,
+            SqlInfoValue::Bool(false),
+        ),
+        (SqlInfo::SqlCatalogTerm, SqlInfoValue::String("tenant")),
+        (SqlInfo::SqlSchemaTerm, SqlInfoValue::String("database")),
+        (SqlInfo::SqlCatalogAtStart, SqlInfoValue::Bool(true)),
+        (
+            SqlInfo::SqlSelectForUpdateSupported,
+            SqlInfoValue::Bool(false),
+        ),
+        (
+            SqlInfo::SqlStoredProceduresSupported,
+            SqlInfoValue::Bool(false),
+        ),
+        (SqlInfo::SqlTransactionsSupported, SqlInfoValue::Bool(false)),
+        // The parameter rows of INSERT are written all at once
+        (SqlInfo::SqlBatchUpdatesSupported, SqlInfoValue::Bool(true)),
+        (SqlInfo::SqlSavepointsSupported, SqlInfoValue::Bool(false)),
+        (
+            SqlInfo::SqlNamedParametersSupported,
+            SqlInfoValue::Bool(false),
+        ),
+        (
+            SqlInfo::SqlStoredFunctionsUsingCallSyntaxSupported,
+            SqlInfoValue::Bool(false),
+        ),
+    ]
+}
+
+/// Values of the [`SqlInfo`]s, or of all the [`SqlInfo`]s supported if `info` is empty
+pub fn sql_info_batch(info: &[u32]) -> Result<RecordBatch> {
+    let mut names = UInt32Builder::new();
+    let mut type_ids = vec![];
+    let mut offsets = vec![];
+
+    let mut strings = StringBuilder::new();
+    let mut bools = BooleanBuilder::new();
+    let mut bigints = Int64Builder::new();
+    let mut int32s = Int32Builder::new();
+    let mut string_lists = ListBuilder::new(StringBuilder::new());
+    let mut maps = MapBuilder::new(
+        None,
+        Int32Builder::new(),
+        ListBuilder::new(Int32Builder::new()),
+    );
+
+    let values = sql_info_values()
+        .into_iter()
+        .filter(|(name, _)| info.is_empty() || info.contains(&(*name as u32)));
+    for (name, value) in values {
+        names.append_value(name as u32);
+        match value {
+            SqlInfoValue::String(value) => {
+                type_ids.push(0_i8);
+                offsets.push(strings.len() as i32);
+                strings.append_value(value);
+            }
+            SqlInfoValue::Bool(value) => {
+                type_ids.push(1);
+                offsets.push(bools.len() as i32);
+                bools.append_value(value);
+            }
+            SqlInfoValue::Int32(value) => {
+                type_ids.push(3);
+                offsets.push(int32s.len() as i32);
+                int32s.append_value(value);
+            }
+            SqlInfoValue::StringList(values) => {
+                type_ids.push(4);
+                offsets.push(string_lists.len() as i32);
+                for value in values {
+                    string_lists.values().append_value(value);
+                }
+                string_lists.append(true);
+            }
+        }
+    }
+
+    let children: Vec<(&str, ArrayRef)> = vec![
+        ("string_value", Arc::new(strings.finish())),
+        ("bool_value", Arc::new(bools.finish())),
+        ("bigint_value", Arc::new(bigints.finish())),
+        ("int32_bitmask", Arc::new(int32s.finish())),
+        ("string_list", Arc::new(string_lists.finish())),
+        ("int32_to_int32_list_map", Arc::new(maps.finish())),
+    ];
+    let children = children
+        .into_iter()
+        .map(|(name, array)| (Field::new(name, array.data_type().clone(), true), array))
+        .collect::<Vec<_>>();
+    let field_type_ids = (0..children.len() as i8).collect::<Vec<_>>();
+    let values = UnionArray::try_new(
+        &field_type_ids,
+        Buffer::from_slice_ref(&type_ids),
+        Some(Buffer::from_slice_ref(&offsets)),
+        children,
+    )?;
+
+    let schema = Schema::new(vec![
+        Field::new("info_name", DataType::UInt32, false),
+        Field::new("value", values.data_type().clone(), false),
+    ]);
+
+    RecordBatch::try_new(
+        Arc::new(schema),
+        vec![Arc::new(names.finish()), Arc::new(values)],
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn column(name: &str, column_type: &str, data_type: &str) -> ColumnInfo {
+        ColumnInfo {
+            database: "public".to_string(),
+            table: "air".to_string(),
+            name: name.to_string(),
+            column_type: column_type.to_string(),
+            data_type: data_type.to_string(),
+            nullable: column_type != "TIME",
+        }
+    }
+
+    #[test]
+    fn test_tables_sql() {
+        assert_eq!(
+            tables_sql(&Some("pub%".to_string()), &Some("it's".to_string())),
+            "SELECT table_database, table_name, table_type FROM information_schema.tables \
+            WHERE table_database LIKE 'pub%' AND table_name LIKE 'it''s' \
+            ORDER BY table_database, table_name"
+        );
+        assert_eq!(
+            databases_sql(&None),
+            "SELECT database_name FROM information_schema.databases ORDER BY database_name"
+        );
+    }
+
+    #[test]
+    fn test_table_schema() {
+        let columns = vec![
+            column("time", "TIME", "TIMESTAMP"),
+            column("station", "TAG", "STRING"),
+            column("visibility", "FIELD", "DOUBLE"),
+        ];
+        let columns = columns.iter().collect::<Vec<_>>();
+
+        let schema = table_schema("cnosdb", &columns);
+        assert_eq!(
+            schema.field(0).data_type(),
+            &DataType::Timestamp(TimeUnit::Nanosecond, None)
+        );
+        assert!(!schema.field(0).is_nullable());
+        assert_eq!(schema.field(1).data_type(), &DataType::Utf8);
+        assert_eq!(schema.field(2).data_type(), &DataType::Float64);
+
+        let metadata = schema.field(1).metadata();
+        assert_eq!(metadata.get(COLUMN_TYPE).unwrap(), "TAG");
+        assert_eq!(metadata.get(TAG).unwrap(), "true");
+        assert_eq!(metadata.get(SCHEMA_NAME).unwrap(), "public");
+        assert_eq!(
+            schema.field(2).metadata().get(COLUMN_TYPE).unwrap(),
+            "FIELD"
+        );
+    }
+
+    #[test]
+    fn test_primary_keys() {
+        let columns = vec![
+            column("time", "TIME", "TIMESTAMP"),
+            column("station", "TAG", "STRING"),
+            column("visibility", "FIELD", "DOUBLE"),
+        ];
+
+        let batch = primary_keys_batch("cnosdb", &columns).unwrap();
+        assert_eq!(batch.num_rows(), 2);
+        let names = string_column(&batch, 3).unwrap();
+        assert_eq!(names.value(0), "time");
+        assert_eq!(names.value(1), "station");
+    }
+
+    #[test]
+    fn test_sql_info() {
+        let batch = sql_info_batch(&[]).unwrap();
+        assert_eq!(batch.num_rows(), sql_info_values().len());
+
+        let batch = sql_info_batch(&[
+            SqlInfo::FlightSqlServerName as u32,
+            SqlInfo::SqlKeywords as u32,
+        ])
+        .unwrap();
+        assert_eq!(batch.num_rows(), 2);
+        let values = batch
+            .column(1)
+            .as_any()
+            .downcast_ref::<UnionArray>()
+            .unwrap();
+        assert_eq!(values.type_id(0), 0);
+        assert_eq!(values.type_id(1), 4);
+    }
+}
@@ -14,6 +14,7 @@ use crate::server::{Service, ServiceHandle};
 
 mod auth_middleware;
 pub mod flight_sql_server;
+mod metadata;
 mod utils;
 
 pub struct FlightSqlServiceAdapter {
@@ -11,6 +11,7 @@ use datafusion::arrow::record_batch::RecordBatch;
 use http_protocol::header::AUTHORIZATION;
 use models::auth::user::UserInfo;
 use prost::Message;
+use spi::QueryError;
 use tonic::metadata::{AsciiMetadataValue, MetadataMap};
 use tonic::{Request, Status};
 
@@ -125,6 +126,20 @@ pub fn endpoint(
     })
 }
 
+/// Convert the error of query execution to grpc status,
+/// so that clients can distinguish retryable resource errors from the others.
+pub fn query_error_to_status(err: QueryError) -> Status {
+    match err {
+        QueryError::ResourcesExhausted { .. }
+        | QueryError::RequestLimit
+        | QueryError::QueryQueueFull { .. }
+        | QueryError::QueryQueueTimeout { .. } => Status::resource_exhausted(err.to_string()),
+        QueryError::QueryTimeout { .. } => Status::deadline_exceeded(err.to_string()),
+        QueryError::Cancel => Status::cancelled(err.to_string()),
+        _ => Status::internal(err.to_string()),
+    }
+}
+
 pub fn record_batch_from_message(
     message: ipc::Message<'_>,
     data_body: &Buffer,
@@ -6,7 +6,7 @@ use std::fmt;
 use std::fmt::Display;
 use std::net::SocketAddr;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use chrono::Local;
 use config::TLSConfig;
@@ -27,9 +27,10 @@ use models::schema::DEFAULT_CATALOG;
 use protos::kv_service::WritePointsRequest;
 use query::prom::remote_server::PromRemoteSqlServer;
 use snafu::ResultExt;
+use spi::query::session::{QueryPriority, SessionVariable};
 use spi::server::dbms::DBMSRef;
 use spi::server::prom::PromRemoteServerRef;
-use spi::service::protocol::{Context, ContextBuilder, Query};
+use spi::service::protocol::{Context, ContextBuilder, Query, VariableStore};
 use spi::QueryError;
 use tokio::sync::oneshot;
 use trace::{debug, info};
@@ -544,11 +545,37 @@ async fn construct_query(
         .await
         .context(QuerySnafu)?;
 
-    let context = ContextBuilder::new(user)
+    let priority = param
+        .priority
+        .map(|e| e.parse::<QueryPriority>())
+        .transpose()
+        .context(QuerySnafu)?;
+
+    let mut context_builder = ContextBuilder::new(user)
         .with_tenant(tenant)
         .with_database(param.db)
         .with_target_partitions(param.target_partitions)
-        .build();
+        .with_query_memory_limit(param.query_memory_limit)
+        .with_priority(priority)
+        .with_query_timeout(param.query_timeout_ms.map(Duration::from_millis));
+    if param.client_session.unwrap_or_default() {
+        context_builder = context_builder
+            .with_variable_store(VariableStore::Client)
+            .context(QuerySnafu)?;
+    }
+
+    let batch_size = param.batch_size.map(|e| e.to_string());
+    for (variable, value) in [
+        (SessionVariable::TimeZone, param.time_zone.as_deref()),
+        (SessionVariable::Precision, param.precision.as_deref()),
+        (SessionVariable::BatchSize, batch_size.as_deref()),
+        (SessionVariable::Consistency, param.consistency.as_deref()),
+    ] {
+        context_builder = context_builder
+            .with_session_variable(variable, value)
+            .context(QuerySnafu)?;
+    }
+    let context = context_builder.build();
 
     Ok(Query::new(
         context,
@@ -181,6 +181,15 @@ impl TskvServiceImpl {
         }
     }
 
+    async fn admin_set_io_rate_limit(
+        &self,
+        _tenant: &str,
+        request: &SetIoRateLimitRequest,
+    ) -> Result<tonic::Response<StatusResponse>, tonic::Status> {
+        self.kv_inst.set_io_rate_limit(request.bytes_per_sec);
+        self.status_response(SUCCESS_RESPONSE_CODE, "".to_string())
+    }
+
     async fn query_record_batch_exec(
         args: QueryArgs,
         expr: QueryExpr,
@@ -350,6 +359,9 @@ impl TskvService for TskvServiceImpl {
                 admin_command_request::Command::AlterColumn(command) => {
                     self.admin_alter_column(&inner.tenant, command).await
                 }
+                admin_command_request::Command::SetIoRateLimit(command) => {
+                    self.admin_set_io_rate_limit(&inner.tenant, command).await
+                }
             };
 
             info!("admin command: {:?}, result: {:?}", command, resp);
@@ -133,6 +133,18 @@ pub enum MetaError {
     #[snafu(display("Connect to Meta error reason: {}", msg))]
     #[error_code(code = 26)]
     ConnectMetaError { msg: String },
+
+    #[snafu(display("The pipe {} already exists", pipe))]
+    #[error_code(code = 27)]
+    PipeAlreadyExists { pipe: String },
+
+    #[snafu(display("The pipe {} not found", pipe))]
+    #[error_code(code = 28)]
+    PipeNotFound { pipe: String },
+
+    #[snafu(display("The lease of pipe {} is held by another node", pipe))]
+    #[error_code(code = 29)]
+    PipeLeaseLost { pipe: String },
 }
 impl MetaError {
     pub fn error_code(&self) -> &dyn ErrorCode {
@@ -1,15 +1,21 @@
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use async_trait::async_trait;
-use config::RequestLimiterConfig;
-use limiter_bucket::CountBucket;
+use config::{RateBucketConfig, RequestLimiterConfig};
+use limiter_bucket::{CountBucket, RateBucket};
+use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
+use trace::warn;
 
-use crate::client::MetaHttpClient;
 use crate::error::{MetaError, MetaResult};
 use crate::limiter::limiter_kind::RequestLimiterKind;
 use crate::limiter::RequestLimiter;
+use crate::{AdminMetaRef, MetaClientRef};
+
+/// Interval of leasing tokens from meta again after a failure, doubled on each failure
+const MIN_RETRY_INTERVAL: Duration = Duration::from_secs(1);
+const MAX_RETRY_INTERVAL: Duration = Duration::from_secs(60);
 
 pub enum RequreResult {
     Fail,
@@ -35,152 +41,227 @@ pub struct LocalBucketResponse {
     pub alloc: i64,
 }
 
+/// Request limiter of a tenant on a single node.
+///
+/// Tokens are leased from the tenant's global buckets in the meta service in batches
+/// (at most `local_bucket.max` at a time), so all nodes share one quota.
+/// If the meta service is unreachable, requests are checked against `fallback_buckets`,
+/// which enforce this node's share of the rate of `remote_bucket`, and meta is not
+/// requested again until the retry interval elapses.
 #[derive(Debug)]
 pub struct LocalRequestLimiter {
-    cluster: String,
     tenant: String,
-    meta_http_client: MetaHttpClient,
-    pub(crate) buckets: HashMap<RequestLimiterKind, Arc<tokio::sync::Mutex<CountBucket>>>,
+    meta_client: MetaClientRef,
+    admin_meta: AdminMetaRef,
+    buckets: HashMap<RequestLimiterKind, LocalBucket>,
+    fallback_buckets: HashMap<RequestLimiterKind, FallbackBucket>,
+    /// Time before which meta is not requested after a failure, and the interval of it
+    retry: Mutex<Option<(Instant, Duration)>>,
 }
 
 impl LocalRequestLimiter {
     pub fn new(
-        cluster: &str,
         tenant: &str,
         value: &RequestLimiterConfig,
-        meta_http_client: MetaHttpClient,
+        meta_client: MetaClientRef,
+        admin_meta: AdminMetaRef,
     ) -> Self {
         let mut buckets = HashMap::new();
+        let mut fallback_buckets = HashMap::new();
 
-        if let Some(ref config) = value.data_in {
-            buckets.insert(
-                RequestLimiterKind::DataIn,
-                Arc::new(tokio::sync::Mutex::new(CountBucket::from(
-                    &config.local_bucket,
-                ))),
-            );
-        }
-
-        if let Some(ref config) = value.data_out {
-            buckets.insert(
-                RequestLimiterKind::DataOut,
-                Arc::new(tokio::sync::Mutex::new(CountBucket::from(
-                    &config.local_bucket,
-                ))),
-            );
-        }
-
-        if let Some(ref config) = value.queries {
-            buckets.insert(
-                RequestLimiterKind::Queries,
-                Arc::new(tokio::sync::Mutex::new(CountBucket::from(
-                    &config.local_bucket,
-                ))),
-            );
-        }
-
-        if let Some(ref config) = value.writes {
-            buckets.insert(
-                RequestLimiterKind::Writes,
-                Arc::new(tokio::sync::Mutex::new(CountBucket::from(
-                    &config.local_bucket,
-                ))),
-            );
+        let configs = [
+            (RequestLimiterKind::DataIn, &value.data_in),
+            (RequestLimiterKind::DataOut, &value.data_out),
+            (RequestLimiterKind::Queries, &value.queries),
+            (RequestLimiterKind::Writes, &value.writes),
+        ];
+        for (kind, config) in configs {
+            if let Some(config) = config {
+                buckets.insert(
+                    kind,
+                    LocalBucket::new(CountBucket::from(&config.local_bucket)),
+                );
+                fallback_buckets.insert(kind, FallbackBucket::new(config.remote_bucket));
+            }
         }
 
         Self {
-            cluster: cluster.to_string(),
             tenant: tenant.to_string(),
+            meta_client,
+            admin_meta,
             buckets,
-            meta_http_client,
+            fallback_buckets,
+            retry: Mutex::new(None),
         }
     }
 
-    fn requre(
-        &self,
-        bucket_guard: &mut tokio::sync::MutexGuard<CountBucket>,
-        requre: i64,
-    ) -> RequreResult {
+    fn requre(&self, bucket: &mut CountBucket, requre: i64) -> RequreResult {
         if requre < 0 {
             return RequreResult::Fail;
         }
 
-        let now_count = bucket_guard.fetch();
+        let now_count = bucket.fetch();
 
-        if now_count > requre {
-            bucket_guard.dec(requre);
+        if now_count >= requre {
+            bucket.dec(requre);
             return RequreResult::Success;
         }
 
+        // Local tokens are not enough, lease the missing part from the remote_bucket.
+        // Never consume tokens that have not been leased, otherwise the quota of
+        // the tenant grows with the number of nodes.
         // If the local_bucket does not specify `max`,
         // the remote_bucket must be requested every time
-        let max = match bucket_guard.max() {
-            Some(max) => max,
-            None => {
-                return RequreResult::RequestMeta {
-                    min: requre,
-                    max: requre,
-                }
-            }
-        };
+        let max = bucket.max().unwrap_or(requre).max(requre);
 
-        if now_count <= 0 && requre <= max {
-            RequreResult::RequestMeta {
-                min: now_count.abs() + requre,
-                max: now_count.abs() + max,
-            }
-        } else if now_count > 0 && now_count + max > requre {
-            bucket_guard.dec(requre);
-            RequreResult::Success
-        } else {
-            RequreResult::Fail
+        RequreResult::RequestMeta {
+            min: requre - now_count,
+            max: max - now_count,
         }
     }
 
+    /// Lease the tokens from meta, the bucket is not locked while requesting meta,
+    /// so the requests of this node are not blocked by meta.
+    ///
+    /// Only one lease of a bucket is in flight, the requests waiting for it check
+    /// the bucket again once it's done, and only lease the tokens that are still missing.
     async fn remote_requre(
         &self,
         kind: RequestLimiterKind,
-        bucket_guard: &mut tokio::sync::MutexGuard<'_, CountBucket>,
+        bucket: &LocalBucket,
         data_len: i64,
-        min: i64,
-        max: i64,
     ) -> MetaResult<()> {
+        let _lease = bucket.lease.lock().await;
+        let (min, max) = match self.requre(&mut *bucket.tokens.lock().await, data_len) {
+            RequreResult::Success => return Ok(()),
+            RequreResult::Fail => return Err(MetaError::RequestLimit { kind }),
+            RequreResult::RequestMeta { min, max } => (min, max),
+        };
+
+        if matches!(*self.retry.lock(), Some((retry_at, _)) if Instant::now() < retry_at) {
+            return self.fallback_requre(kind, data_len).await;
+        }
+
         let request = LocalBucketRequest {
             kind,
             expected: ExpectedRequest { min, max },
         };
-        let LocalBucketResponse { alloc, .. } = self
-            .meta_http_client
-            .limiter_request(&self.cluster, &self.tenant, request)
-            .await?;
-        bucket_guard.inc(alloc);
-        if bucket_guard.fetch() > data_len {
-            bucket_guard.dec(data_len);
+        let alloc = match self.meta_client.limiter_request(request).await {
+            Ok(LocalBucketResponse { alloc, .. }) => {
+                *self.retry.lock() = None;
+                alloc
+            }
+            Err(err) => {
+                let interval = {
+                    let mut retry = self.retry.lock();
+                    let interval = retry
+                        .map(|(_, interval)| (interval * 2).min(MAX_RETRY_INTERVAL))
+                        .unwrap_or(MIN_RETRY_INTERVAL);
+                    *retry = Some((Instant::now() + interval, interval));
+                    interval
+                };
+                warn!(
+                    "lease {} tokens of tenant {} from meta failed, fall back to local limiter for {:?}: {}",
+                    kind, self.tenant, interval, err
+                );
+                return self.fallback_requre(kind, data_len).await;
+            }
+        };
+
+        let mut bucket = bucket.tokens.lock().await;
+        bucket.inc(alloc);
+        if bucket.fetch() >= data_len {
+            bucket.dec(data_len);
             Ok(())
         } else {
             Err(MetaError::RequestLimit { kind })
         }
     }
 
+    async fn fallback_requre(&self, kind: RequestLimiterKind, data_len: i64) -> MetaResult<()> {
+        match self.fallback_buckets.get(&kind) {
+            Some(bucket) => {
+                let nodes = self.admin_meta.data_nodes().await.len();
+                bucket
+                    .acquire(nodes, data_len as usize)
+                    .map_err(|_| MetaError::RequestLimit { kind })
+            }
+            None => Ok(()),
+        }
+    }
+
     async fn check_bucket(&self, kind: RequestLimiterKind, data_len: usize) -> MetaResult<()> {
-        let mut bucket_guard = match self.buckets.get(&kind) {
-            Some(bucket) => bucket.lock().await,
+        let bucket = match self.buckets.get(&kind) {
+            Some(bucket) => bucket,
             None => return Ok(()),
         };
         let data_len = data_len as i64;
 
-        match self.requre(&mut bucket_guard, data_len) {
+        let result = self.requre(&mut *bucket.tokens.lock().await, data_len);
+        match result {
             RequreResult::Success => Ok(()),
 
-            RequreResult::RequestMeta { min, max } => {
-                self.remote_requre(kind, &mut bucket_guard, data_len, min, max)
-                    .await
-            }
+            RequreResult::RequestMeta { .. } => self.remote_requre(kind, bucket, data_len).await,
             RequreResult::Fail => Err(MetaError::RequestLimit { kind }),
         }
     }
 }
 
+/// Tokens leased from meta, and the lock serializing the leases of them
+#[derive(Debug)]
+struct LocalBucket {
+    tokens: tokio::sync::Mutex<CountBucket>,
+    lease: tokio::sync::Mutex<()>,
+}
+
+impl LocalBucket {
+    fn new(bucket: CountBucket) -> Self {
+        Self {
+            tokens: tokio::sync::Mutex::new(bucket),
+            lease: tokio::sync::Mutex::new(()),
+        }
+    }
+}
+
+/// Bucket enforcing the share of a node of the rate of `remote_bucket`,
+/// which is rebuilt when the number of nodes changes.
+#[derive(Debug)]
+struct FallbackBucket {
+    config: RateBucketConfig,
+    /// The bucket and the number of nodes it's built for
+    bucket: Mutex<Option<(usize, RateBucket)>>,
+}
+
+impl FallbackBucket {
+    fn new(config: RateBucketConfig) -> Self {
+        Self {
+            config,
+            bucket: Mutex::new(None),
+        }
+    }
+
+    fn acquire(&self, nodes: usize, data_len: usize) -> Result<(), String> {
+        let nodes = nodes.max(1);
+        let mut bucket = self.bucket.lock();
+        match bucket.as_ref() {
+            Some((built_for, _)) if *built_for == nodes => {}
+            _ => {
+                let share = |value: usize| (value / nodes).max(1);
+                let config = RateBucketConfig {
+                    max: self.config.max.map(share),
+                    initial: share(self.config.initial),
+                    refill: share(self.config.refill),
+                    interval: self.config.interval,
+                };
+                *bucket = Some((nodes, RateBucket::from(&config)));
+            }
+        }
+
+        let (_, bucket) = bucket.as_ref().expect("built");
+        bucket.acquire(data_len)
+    }
+}
+
 #[async_trait]
 impl RequestLimiter for LocalRequestLimiter {
     async fn check_data_in(&self, data_len: usize) -> MetaResult<()> {
@@ -198,6 +279,75 @@ impl RequestLimiter for LocalRequestLimiter {
     }
 
     async fn check_write(&self) -> MetaResult<()> {
-        self.check_bucket(RequestLimiterKind::Queries, 1).await
+        self.check_bucket(RequestLimiterKind::Writes, 1).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use config::{Bucket, CountBucketConfing, RateBucketConfig, RequestLimiterConfig};
+
+    use super::FallbackBucket;
+    use crate::error::MetaError;
+    use crate::limiter::{LocalRequestLimiter, RequestLimiter};
+    use crate::meta_client_mock::{MockAdminMeta, MockMetaClient};
+
+    fn rate_bucket(initial: usize) -> RateBucketConfig {
+        RateBucketConfig {
+            max: Some(initial),
+            initial,
+            refill: 1,
+            interval: 1000 * 60,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fallback_to_local_limiter() {
+        let bucket = Bucket {
+            remote_bucket: rate_bucket(2),
+            local_bucket: CountBucketConfing {
+                max: Some(10),
+                initial: 0,
+            },
+        };
+        let config = RequestLimiterConfig {
+            data_in: None,
+            data_out: None,
+            queries: Some(bucket),
+            writes: None,
+        };
+        // The mock meta client can't lease tokens
+        let limiter = LocalRequestLimiter::new(
+            "tenant",
+            &config,
+            Arc::new(MockMetaClient::default()),
+            Arc::new(MockAdminMeta::default()),
+        );
+
+        limiter.check_query().await.unwrap();
+        limiter.check_query().await.unwrap();
+        let err = limiter.check_query().await.unwrap_err();
+        assert!(matches!(err, MetaError::RequestLimit { .. }));
+        // Meta is not requested until the retry interval elapses
+        assert!(limiter.retry.lock().is_some());
+
+        // writes are not limited
+        limiter.check_write().await.unwrap();
+    }
+
+    #[test]
+    fn test_fallback_bucket_share() {
+        let bucket = FallbackBucket::new(rate_bucket(4));
+
+        // 2 of the 4 tokens are the share of the node
+        bucket.acquire(2, 1).unwrap();
+        bucket.acquire(2, 1).unwrap();
+        assert!(bucket.acquire(2, 1).is_err());
+
+        // The bucket is rebuilt for a single node
+        bucket.acquire(1, 4).unwrap();
+        assert!(bucket.acquire(1, 1).is_err());
     }
 }
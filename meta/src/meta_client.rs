@@ -17,10 +17,11 @@ use store::command;
 use trace::{debug, info, warn};
 
 use crate::error::{MetaError, MetaResult};
+use crate::limiter::local_request_limiter::{LocalBucketRequest, LocalBucketResponse};
 use crate::store::command::{
-    EntryLog, META_REQUEST_PRIVILEGE_EXIST, META_REQUEST_PRIVILEGE_NOT_FOUND,
-    META_REQUEST_ROLE_EXIST, META_REQUEST_ROLE_NOT_FOUND, META_REQUEST_USER_EXIST,
-    META_REQUEST_USER_NOT_FOUND,
+    EntryLog, META_REQUEST_PIPE_EXIST, META_REQUEST_PIPE_NOT_FOUND, META_REQUEST_PRIVILEGE_EXIST,
+    META_REQUEST_PRIVILEGE_NOT_FOUND, META_REQUEST_ROLE_EXIST, META_REQUEST_ROLE_NOT_FOUND,
+    META_REQUEST_USER_EXIST, META_REQUEST_USER_NOT_FOUND,
 };
 use crate::store::key_path;
 use crate::{client, store};
@@ -69,6 +70,32 @@ pub trait MetaClient: Send + Sync + Debug {
     ) -> MetaResult<()>;
     async fn drop_custom_role(&self, role_name: &str) -> MetaResult<bool>;
 
+    // tenant pipe
+    async fn create_pipe(&self, pipe: PipeInfo) -> MetaResult<()>;
+    async fn pipes(&self) -> MetaResult<Vec<PipeInfo>>;
+    async fn update_pipe_status(&self, pipe_name: &str, status: PipeStatus) -> MetaResult<()>;
+    /// Extend the lease of the pipe run by `node_id`, false if it's run by another node
+    async fn renew_pipe_lease(
+        &self,
+        pipe_name: &str,
+        node_id: NodeId,
+        lease_expire_time: i64,
+    ) -> MetaResult<bool>;
+    /// Move the pipe from node `from` to node `to` with a new lease, false if it's not run
+    /// by `from` or the lease of `from` has been renewed since `from_lease_expire_time`
+    async fn take_over_pipe(
+        &self,
+        pipe_name: &str,
+        (from, from_lease_expire_time): (NodeId, i64),
+        to: NodeId,
+        lease_expire_time: i64,
+    ) -> MetaResult<bool>;
+    async fn drop_pipe(&self, pipe_name: &str) -> MetaResult<bool>;
+
+    /// Lease tokens from the global buckets of the request limiter of the tenant
+    async fn limiter_request(&self, request: LocalBucketRequest)
+        -> MetaResult<LocalBucketResponse>;
+
     async fn create_db(&self, info: DatabaseSchema) -> MetaResult<()>;
     async fn alter_db_schema(&self, info: &DatabaseSchema) -> MetaResult<()>;
     fn get_db_schema(&self, name: &str) -> MetaResult<Option<DatabaseSchema>>;
@@ -92,6 +119,20 @@ pub trait MetaClient: Send + Sync + Debug {
     fn list_tables(&self, db: &str) -> MetaResult<Vec<String>>;
     async fn drop_table(&self, db: &str, table: &str) -> MetaResult<()>;
 
+    /// The history of the files of `urls` loaded into the table
+    async fn copy_history(
+        &self,
+        db: &str,
+        table: &str,
+        urls: Vec<String>,
+    ) -> MetaResult<Vec<LoadedFile>>;
+    async fn add_copy_history(
+        &self,
+        db: &str,
+        table: &str,
+        files: Vec<LoadedFile>,
+    ) -> MetaResult<()>;
+
     async fn create_bucket(&self, db: &str, ts: i64) -> MetaResult<BucketInfo>;
     async fn delete_bucket(&self, db: &str, id: u32) -> MetaResult<()>;
 
@@ -536,6 +577,135 @@ impl MetaClient for RemoteMetaClient {
 
     // tenant role end
 
+    // tenant pipe start
+
+    async fn create_pipe(&self, pipe: PipeInfo) -> MetaResult<()> {
+        let req = command::WriteCommand::CreatePipe(self.cluster.clone(), self.tenant_name(), pipe);
+
+        match self.client.write::<command::CommonResp<()>>(&req).await? {
+            command::CommonResp::Ok(_) => Ok(()),
+            command::CommonResp::Err(status) => {
+                if status.code == META_REQUEST_PIPE_EXIST {
+                    Err(MetaError::PipeAlreadyExists { pipe: status.msg })
+                } else {
+                    Err(MetaError::CommonError { msg: status.msg })
+                }
+            }
+        }
+    }
+
+    async fn pipes(&self) -> MetaResult<Vec<PipeInfo>> {
+        let req = command::ReadCommand::Pipes(self.cluster.clone(), self.tenant_name());
+
+        match self
+            .client
+            .read::<command::CommonResp<Vec<PipeInfo>>>(&req)
+            .await?
+        {
+            command::CommonResp::Ok(e) => Ok(e),
+            command::CommonResp::Err(status) => Err(MetaError::CommonError { msg: status.msg }),
+        }
+    }
+
+    async fn update_pipe_status(&self, pipe_name: &str, status: PipeStatus) -> MetaResult<()> {
+        let req = command::WriteCommand::UpdatePipeStatus(
+            self.cluster.clone(),
+            self.tenant_name(),
+            pipe_name.to_string(),
+            status,
+        );
+
+        match self.client.write::<command::CommonResp<()>>(&req).await? {
+            command::CommonResp::Ok(_) => Ok(()),
+            command::CommonResp::Err(status) => {
+                if status.code == META_REQUEST_PIPE_NOT_FOUND {
+                    Err(MetaError::PipeNotFound { pipe: status.msg })
+                } else {
+                    Err(MetaError::CommonError { msg: status.msg })
+                }
+            }
+        }
+    }
+
+    async fn renew_pipe_lease(
+        &self,
+        pipe_name: &str,
+        node_id: NodeId,
+        lease_expire_time: i64,
+    ) -> MetaResult<bool> {
+        let req = command::WriteCommand::RenewPipeLease(
+            self.cluster.clone(),
+            self.tenant_name(),
+            pipe_name.to_string(),
+            node_id,
+            lease_expire_time,
+        );
+
+        match self.client.write::<command::CommonResp<bool>>(&req).await? {
+            command::CommonResp::Ok(e) => Ok(e),
+            command::CommonResp::Err(status) => {
+                if status.code == META_REQUEST_PIPE_NOT_FOUND {
+                    Err(MetaError::PipeNotFound { pipe: status.msg })
+                } else {
+                    Err(MetaError::CommonError { msg: status.msg })
+                }
+            }
+        }
+    }
+
+    async fn take_over_pipe(
+        &self,
+        pipe_name: &str,
+        (from, from_lease_expire_time): (NodeId, i64),
+        to: NodeId,
+        lease_expire_time: i64,
+    ) -> MetaResult<bool> {
+        let req = command::WriteCommand::TakeOverPipe(
+            self.cluster.clone(),
+            self.tenant_name(),
+            pipe_name.to_string(),
+            from,
+            from_lease_expire_time,
+            to,
+            lease_expire_time,
+        );
+
+        match self.client.write::<command::CommonResp<bool>>(&req).await? {
+            command::CommonResp::Ok(e) => Ok(e),
+            command::CommonResp::Err(status) => {
+                if status.code == META_REQUEST_PIPE_NOT_FOUND {
+                    Err(MetaError::PipeNotFound { pipe: status.msg })
+                } else {
+                    Err(MetaError::CommonError { msg: status.msg })
+                }
+            }
+        }
+    }
+
+    async fn drop_pipe(&self, pipe_name: &str) -> MetaResult<bool> {
+        let req = command::WriteCommand::DropPipe(
+            self.cluster.clone(),
+            self.tenant_name(),
+            pipe_name.to_string(),
+        );
+
+        match self.client.write::<command::CommonResp<bool>>(&req).await? {
+            command::CommonResp::Ok(e) => Ok(e),
+            command::CommonResp::Err(status) => Err(MetaError::CommonError { msg: status.msg }),
+        }
+    }
+
+    // tenant pipe end
+
+    async fn limiter_request(
+        &self,
+        request: LocalBucketRequest,
+    ) -> MetaResult<LocalBucketResponse> {
+        self.client
+            .limiter_request(&self.cluster, &self.tenant_name(), request)
+            .await
+    }
+
     async fn create_db(&self, mut schema: DatabaseSchema) -> MetaResult<()> {
         self.check_create_db(&mut schema)?;
 
@@ -743,6 +913,50 @@ impl MetaClient for RemoteMetaClient {
         }
     }
 
+    async fn copy_history(
+        &self,
+        db: &str,
+        table: &str,
+        urls: Vec<String>,
+    ) -> MetaResult<Vec<LoadedFile>> {
+        let req = command::ReadCommand::CopyHistory(
+            self.cluster.clone(),
+            self.tenant_name(),
+            db.to_string(),
+            table.to_string(),
+            urls,
+        );
+
+        match self
+            .client
+            .read::<command::CommonResp<Vec<LoadedFile>>>(&req)
+            .await?
+        {
+            command::CommonResp::Ok(files) => Ok(files),
+            command::CommonResp::Err(status) => Err(MetaError::CommonError { msg: status.msg }),
+        }
+    }
+
+    async fn add_copy_history(
+        &self,
+        db: &str,
+        table: &str,
+        files: Vec<LoadedFile>,
+    ) -> MetaResult<()> {
+        let req = command::WriteCommand::AddCopyHistory(
+            self.cluster.clone(),
+            self.tenant_name(),
+            db.to_string(),
+            table.to_string(),
+            files,
+        );
+
+        match self.client.write::<command::CommonResp<()>>(&req).await? {
+            command::CommonResp::Ok(_) => Ok(()),
+            command::CommonResp::Err(status) => Err(MetaError::CommonError { msg: status.msg }),
+        }
+    }
+
     async fn create_bucket(&self, db: &str, ts: i64) -> MetaResult<BucketInfo> {
         let req = command::WriteCommand::CreateBucket(
             self.cluster.clone(),
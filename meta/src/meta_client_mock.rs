@@ -6,7 +6,8 @@ use std::sync::Arc;
 use models::auth::privilege::DatabasePrivilege;
 use models::auth::role::{CustomTenantRole, SystemTenantRole, TenantRoleIdentifier};
 use models::meta_data::{
-    BucketInfo, DatabaseInfo, ExpiredBucketInfo, NodeInfo, ReplicationSet, VnodeAllInfo, VnodeInfo,
+    BucketInfo, DatabaseInfo, ExpiredBucketInfo, LoadedFile, NodeId, NodeInfo, PipeInfo,
+    PipeStatus, ReplicationSet, VnodeAllInfo, VnodeInfo,
 };
 use models::oid::Oid;
 use models::schema::{
@@ -14,7 +15,8 @@ use models::schema::{
 };
 use tonic::transport::Channel;
 
-use crate::error::MetaResult;
+use crate::error::{MetaError, MetaResult};
+use crate::limiter::local_request_limiter::{LocalBucketRequest, LocalBucketResponse};
 use crate::limiter::RequestLimiter;
 use crate::meta_admin::AdminMeta;
 use crate::meta_client::MetaClient;
@@ -131,6 +133,24 @@ impl MetaClient for MockMetaClient {
         Ok(())
     }
 
+    async fn copy_history(
+        &self,
+        db: &str,
+        table: &str,
+        urls: Vec<String>,
+    ) -> MetaResult<Vec<LoadedFile>> {
+        Ok(vec![])
+    }
+
+    async fn add_copy_history(
+        &self,
+        db: &str,
+        table: &str,
+        files: Vec<LoadedFile>,
+    ) -> MetaResult<()> {
+        Ok(())
+    }
+
     async fn create_bucket(&self, db: &str, ts: i64) -> MetaResult<BucketInfo> {
         Ok(BucketInfo::default())
     }
@@ -225,6 +245,50 @@ impl MetaClient for MockMetaClient {
         todo!()
     }
 
+    async fn create_pipe(&self, pipe: PipeInfo) -> MetaResult<()> {
+        Ok(())
+    }
+
+    async fn pipes(&self) -> MetaResult<Vec<PipeInfo>> {
+        Ok(vec![])
+    }
+
+    async fn update_pipe_status(&self, pipe_name: &str, status: PipeStatus) -> MetaResult<()> {
+        Ok(())
+    }
+
+    async fn renew_pipe_lease(
+        &self,
+        pipe_name: &str,
+        node_id: NodeId,
+        lease_expire_time: i64,
+    ) -> MetaResult<bool> {
+        Ok(true)
+    }
+
+    async fn take_over_pipe(
+        &self,
+        pipe_name: &str,
+        from: (NodeId, i64),
+        to: NodeId,
+        lease_expire_time: i64,
+    ) -> MetaResult<bool> {
+        Ok(false)
+    }
+
+    async fn drop_pipe(&self, pipe_name: &str) -> MetaResult<bool> {
+        Ok(false)
+    }
+
+    async fn limiter_request(
+        &self,
+        request: LocalBucketRequest,
+    ) -> MetaResult<LocalBucketResponse> {
+        Err(MetaError::CommonError {
+            msg: "there is no meta service".to_string(),
+        })
+    }
+
     fn expired_bucket(&self) -> Vec<ExpiredBucketInfo> {
         vec![]
     }
@@ -69,6 +69,7 @@ impl RemoteMetaManager {
             config.meta_service_addr.clone(),
             config.node_id,
             tenant_change_sender.clone(),
+            admin.clone(),
         ));
 
         let manager = Arc::new(Self {
@@ -57,6 +57,8 @@ pub enum WriteCommand {
     UpdateTable(String, String, TableSchema),
     // cluster, tenant, db name, table name
     DropTable(String, String, String, String),
+    // cluster, tenant, db name, table name, loaded files
+    AddCopyHistory(String, String, String, String, Vec<LoadedFile>),
 
     // cluster, user_name, user_options, is_admin
     CreateUser(String, String, UserOptions, bool),
@@ -98,6 +100,18 @@ pub enum WriteCommand {
     // cluster, privileges, role_name, tenant_name
     RevokePrivileges(String, Vec<(DatabasePrivilege, String)>, String, String),
 
+    // cluster, tenant_name, pipe
+    CreatePipe(String, String, PipeInfo),
+    // cluster, tenant_name, pipe_name
+    DropPipe(String, String, String),
+    // cluster, tenant_name, pipe_name, status
+    UpdatePipeStatus(String, String, String, PipeStatus),
+    // cluster, tenant_name, pipe_name, node running the pipe, expire time of the lease
+    RenewPipeLease(String, String, String, NodeId, i64),
+    // cluster, tenant_name, pipe_name, node running the pipe, expire time of its lease,
+    // node taking over the pipe, expire time of the new lease
+    TakeOverPipe(String, String, String, NodeId, i64, NodeId, i64),
+
     Set {
         key: String,
         value: String,
@@ -132,6 +146,10 @@ pub enum ReadCommand {
     Tenant(String, String),
     // cluster
     Tenants(String),
+    // cluster, tenant, db name, table name, urls of the files
+    CopyHistory(String, String, String, String, Vec<String>),
+    // cluster, tenant_name
+    Pipes(String, String),
 }
 
 /******************* response  *************************/
@@ -148,6 +166,8 @@ pub const META_REQUEST_ROLE_NOT_FOUND: i32 = 8;
 pub const META_REQUEST_PRIVILEGE_EXIST: i32 = 9;
 pub const META_REQUEST_PRIVILEGE_NOT_FOUND: i32 = 10;
 pub const META_REQUEST_DB_NOT_FOUND: i32 = 11;
+pub const META_REQUEST_PIPE_EXIST: i32 = 12;
+pub const META_REQUEST_PIPE_NOT_FOUND: i32 = 13;
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct StatusResponse {
@@ -5,6 +5,7 @@ use models::oid::Oid;
 // **    /cluster_name/tenants/tenant ->
 // **    /cluster_name/tenants/tenant/roles/roles ->
 // **    /cluster_name/tenants/tenant/members/user_id ->
+// **    /cluster_name/tenants/tenant/pipes/pipe -> [PipeInfo] 持续导入文件的pipe
 // **    /cluster_name/auto_incr_id -> id
 // **    /cluster_name/data_nodes/node_id -> [NodeInfo] 集群、数据节点等信息
 
@@ -12,6 +13,7 @@ use models::oid::Oid;
 // **    /cluster_name/tenant_name/dbs/db_name -> [DatabaseInfo] db相关信息、保留策略等
 // **    /cluster_name/tenant_name/dbs/db_name/buckets/id -> [BucketInfo] bucket相关信息
 // **    /cluster_name/tenant_name/dbs/db_name/schemas/name -> [TskvTableSchema] schema相关信息
+// **    /cluster_name/tenant_name/dbs/db_name/copy_history/table/file -> [LoadedFile] 已导入的文件
 
 pub const DBS: &str = "dbs";
 pub const USERS: &str = "users";
@@ -20,6 +22,8 @@ pub const BUCKETS: &str = "buckets";
 pub const SCHEMAS: &str = "schemas";
 pub const TENANTS: &str = "tenants";
 pub const MEMBERS: &str = "members";
+pub const COPY_HISTORY: &str = "copy_history";
+pub const PIPES: &str = "pipes";
 pub const DATA_NODES: &str = "data_nodes";
 pub const AUTO_INCR_ID: &str = "auto_incr_id";
 
@@ -92,6 +96,28 @@ impl KeyPath {
         )
     }
 
+    pub fn tenant_copy_history(cluster: &str, tenant: &str, db: &str, table: &str) -> String {
+        format!(
+            "/{}/tenants/{}/dbs/{}/copy_history/{}",
+            cluster, tenant, db, table
+        )
+    }
+
+    /// Url of the file is escaped as a single key.
+    pub fn tenant_copy_history_file(
+        cluster: &str,
+        tenant: &str,
+        db: &str,
+        table: &str,
+        url: &str,
+    ) -> String {
+        format!(
+            "{}/{}",
+            Self::tenant_copy_history(cluster, tenant, db, table),
+            url.replace('%', "%25").replace('/', "%2F")
+        )
+    }
+
     pub fn tenants(cluster: &str) -> String {
         format!("/{}/tenants/", cluster)
     }
@@ -108,6 +134,14 @@ impl KeyPath {
         format!("/{}/tenants/{}/roles", cluster, tenant_name)
     }
 
+    pub fn pipe(cluster: &str, tenant_name: &str, pipe_name: &str) -> String {
+        format!("/{}/tenants/{}/pipes/{}", cluster, tenant_name, pipe_name)
+    }
+
+    pub fn pipes(cluster: &str, tenant_name: &str) -> String {
+        format!("/{}/tenants/{}/pipes", cluster, tenant_name)
+    }
+
     pub fn member(cluster: &str, tenant_name: &str, user_id: &Oid) -> String {
         format!("/{}/tenants/{}/members/{}", cluster, tenant_name, user_id)
     }
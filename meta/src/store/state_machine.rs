@@ -497,6 +497,30 @@ impl StateMachine {
 
                 CommonResp::Ok(data).to_string()
             }
+
+            ReadCommand::CopyHistory(cluster, tenant, db_name, table_name, urls) => {
+                let data: Vec<LoadedFile> = urls
+                    .iter()
+                    .filter_map(|url| {
+                        let key = KeyPath::tenant_copy_history_file(
+                            cluster, tenant, db_name, table_name, url,
+                        );
+                        get_struct::<LoadedFile>(&key, self.db.clone())
+                    })
+                    .collect();
+
+                CommonResp::Ok(data).to_string()
+            }
+
+            ReadCommand::Pipes(cluster, tenant_name) => {
+                let path = KeyPath::pipes(cluster, tenant_name);
+
+                let data: Vec<PipeInfo> = children_data::<PipeInfo>(&path, self.db.clone())
+                    .into_values()
+                    .collect();
+
+                CommonResp::Ok(data).to_string()
+            }
         }
     }
 
@@ -529,6 +553,10 @@ impl StateMachine {
                 self.process_drop_table(cluster, tenant, db_name, table_name)
             }
 
+            WriteCommand::AddCopyHistory(cluster, tenant, db_name, table_name, files) => {
+                self.process_add_copy_history(cluster, tenant, db_name, table_name, files)
+            }
+
             WriteCommand::CreateTable(cluster, tenant, schema) => {
                 self.process_create_table(cluster, tenant, schema)
             }
@@ -589,6 +617,44 @@ impl StateMachine {
             WriteCommand::RevokePrivileges(cluster, privileges, role_name, tenant_name) => {
                 self.process_revoke_privileges(cluster, privileges, role_name, tenant_name)
             }
+            WriteCommand::CreatePipe(cluster, tenant_name, pipe) => {
+                self.process_create_pipe(cluster, tenant_name, pipe)
+            }
+            WriteCommand::DropPipe(cluster, tenant_name, pipe_name) => {
+                self.process_drop_pipe(cluster, tenant_name, pipe_name)
+            }
+            WriteCommand::UpdatePipeStatus(cluster, tenant_name, pipe_name, status) => {
+                self.process_update_pipe_status(cluster, tenant_name, pipe_name, status)
+            }
+            WriteCommand::RenewPipeLease(
+                cluster,
+                tenant_name,
+                pipe_name,
+                node_id,
+                lease_expire_time,
+            ) => self.process_renew_pipe_lease(
+                cluster,
+                tenant_name,
+                pipe_name,
+                *node_id,
+                *lease_expire_time,
+            ),
+            WriteCommand::TakeOverPipe(
+                cluster,
+                tenant_name,
+                pipe_name,
+                from,
+                from_lease_expire_time,
+                to,
+                lease_expire_time,
+            ) => self.process_take_over_pipe(
+                cluster,
+                tenant_name,
+                pipe_name,
+                (*from, *from_lease_expire_time),
+                *to,
+                *lease_expire_time,
+            ),
             WriteCommand::RetainID(cluster, count) => self.process_retain_id(cluster, *count),
             WriteCommand::UpdateVnodeReplSet(args) => self.process_update_vnode_repl_set(args),
             WriteCommand::LimiterRequest {
@@ -680,6 +746,13 @@ impl StateMachine {
         let schemas_path = KeyPath::tenant_schemas(cluster, tenant, db_name);
         for it in children_fullpath(&schemas_path, self.db.clone()).iter() {
             let _ = self.remove(it);
+
+            if let Some(table_name) = it.rsplit('/').next() {
+                let path = KeyPath::tenant_copy_history(cluster, tenant, db_name, table_name);
+                for key in children_fullpath(&path, self.db.clone()) {
+                    let _ = self.remove(&key);
+                }
+            }
         }
 
         StatusResponse::new(META_REQUEST_SUCCESS, "".to_string()).to_string()
@@ -695,9 +768,35 @@ impl StateMachine {
         let key = KeyPath::tenant_schema_name(cluster, tenant, db_name, table_name);
         let _ = self.remove(&key);
 
+        // Files loaded into the dropped table should be loaded again.
+        let path = KeyPath::tenant_copy_history(cluster, tenant, db_name, table_name);
+        for key in children_fullpath(&path, self.db.clone()) {
+            let _ = self.remove(&key);
+        }
+
         StatusResponse::new(META_REQUEST_SUCCESS, "".to_string()).to_string()
     }
 
+    fn process_add_copy_history(
+        &self,
+        cluster: &str,
+        tenant: &str,
+        db_name: &str,
+        table_name: &str,
+        files: &[LoadedFile],
+    ) -> CommandResp {
+        for file in files {
+            let key =
+                KeyPath::tenant_copy_history_file(cluster, tenant, db_name, table_name, &file.url);
+            if let Err(e) = self.insert(&key, &serde_json::to_string(file).unwrap()) {
+                let status = StatusResponse::new(META_REQUEST_FAILED, e.to_string());
+                return CommonResp::<()>::Err(status).to_string();
+            }
+        }
+
+        CommonResp::Ok(()).to_string()
+    }
+
     fn process_create_db(
         &self,
         cluster: &str,
@@ -1149,6 +1248,11 @@ impl StateMachine {
 
         let success = self.remove(&key).is_ok() && self.remove(&limiter_key).is_ok();
 
+        let pipes_path = KeyPath::pipes(cluster, name);
+        for key in children_fullpath(&pipes_path, self.db.clone()) {
+            let _ = self.remove(&key);
+        }
+
         CommonResp::Ok(success).to_string()
     }
 
@@ -1269,6 +1373,134 @@ impl StateMachine {
         CommonResp::Ok(success).to_string()
     }
 
+    fn process_create_pipe(
+        &self,
+        cluster: &str,
+        tenant_name: &str,
+        pipe: &PipeInfo,
+    ) -> CommandResp {
+        let key = KeyPath::pipe(cluster, tenant_name, &pipe.name);
+
+        if self.db.contains_key(&key).unwrap() {
+            let status = StatusResponse::new(
+                META_REQUEST_PIPE_EXIST,
+                format!("{} of tenant {}", pipe.name, tenant_name),
+            );
+            return CommonResp::<()>::Err(status).to_string();
+        }
+
+        match serde_json::to_string(pipe) {
+            Ok(value) => {
+                let _ = self.insert(&key, &value);
+                CommonResp::Ok(()).to_string()
+            }
+            Err(err) => {
+                let status = StatusResponse::new(META_REQUEST_FAILED, err.to_string());
+                CommonResp::<()>::Err(status).to_string()
+            }
+        }
+    }
+
+    fn process_drop_pipe(&self, cluster: &str, tenant_name: &str, pipe_name: &str) -> CommandResp {
+        let key = KeyPath::pipe(cluster, tenant_name, pipe_name);
+
+        let success = self.db.contains_key(&key).unwrap();
+        self.remove(&key).unwrap();
+
+        CommonResp::Ok(success).to_string()
+    }
+
+    fn process_update_pipe_status(
+        &self,
+        cluster: &str,
+        tenant_name: &str,
+        pipe_name: &str,
+        status: &PipeStatus,
+    ) -> CommandResp {
+        let key = KeyPath::pipe(cluster, tenant_name, pipe_name);
+
+        // The pipe may be dropped while it's loading files
+        let mut pipe = match get_struct::<PipeInfo>(&key, self.db.clone()) {
+            Some(pipe) => pipe,
+            None => {
+                let status = StatusResponse::new(
+                    META_REQUEST_PIPE_NOT_FOUND,
+                    format!("{} of tenant {}", pipe_name, tenant_name),
+                );
+                return CommonResp::<()>::Err(status).to_string();
+            }
+        };
+        pipe.status = status.clone();
+
+        let _ = self.insert(&key, &serde_json::to_string(&pipe).unwrap());
+        CommonResp::Ok(()).to_string()
+    }
+
+    /// Extend the lease of the pipe only if it's still run by `node_id`,
+    /// false if the pipe has been taken over by another node.
+    fn process_renew_pipe_lease(
+        &self,
+        cluster: &str,
+        tenant_name: &str,
+        pipe_name: &str,
+        node_id: NodeId,
+        lease_expire_time: i64,
+    ) -> CommandResp {
+        let key = KeyPath::pipe(cluster, tenant_name, pipe_name);
+
+        let mut pipe = match get_struct::<PipeInfo>(&key, self.db.clone()) {
+            Some(pipe) => pipe,
+            None => {
+                let status = StatusResponse::new(
+                    META_REQUEST_PIPE_NOT_FOUND,
+                    format!("{} of tenant {}", pipe_name, tenant_name),
+                );
+                return CommonResp::<bool>::Err(status).to_string();
+            }
+        };
+        if pipe.node_id != node_id {
+            return CommonResp::Ok(false).to_string();
+        }
+        pipe.lease_expire_time = pipe.lease_expire_time.max(lease_expire_time);
+
+        let _ = self.insert(&key, &serde_json::to_string(&pipe).unwrap());
+        CommonResp::Ok(true).to_string()
+    }
+
+    /// Move the pipe to node `to` only if it's still run by node `from` with the lease
+    /// seen expired by `to`, i.e. the lease has not been renewed since,
+    /// so a pipe is taken over by one node at most.
+    fn process_take_over_pipe(
+        &self,
+        cluster: &str,
+        tenant_name: &str,
+        pipe_name: &str,
+        (from, from_lease_expire_time): (NodeId, i64),
+        to: NodeId,
+        lease_expire_time: i64,
+    ) -> CommandResp {
+        let key = KeyPath::pipe(cluster, tenant_name, pipe_name);
+
+        let mut pipe = match get_struct::<PipeInfo>(&key, self.db.clone()) {
+            Some(pipe) => pipe,
+            None => {
+                let status = StatusResponse::new(
+                    META_REQUEST_PIPE_NOT_FOUND,
+                    format!("{} of tenant {}", pipe_name, tenant_name),
+                );
+                return CommonResp::<bool>::Err(status).to_string();
+            }
+        };
+        if pipe.node_id != from || pipe.lease_expire_time != from_lease_expire_time {
+            return CommonResp::Ok(false).to_string();
+        }
+        pipe.node_id = to;
+        pipe.lease_expire_time = lease_expire_time;
+
+        let _ = self.insert(&key, &serde_json::to_string(&pipe).unwrap());
+        CommonResp::Ok(true).to_string()
+    }
+
     fn process_grant_privileges(
         &self,
         cluster: &str,
@@ -15,7 +15,7 @@ use crate::error::{MetaError, MetaResult};
 use crate::limiter::{LocalRequestLimiter, NoneLimiter, RequestLimiter};
 use crate::meta_client::{MetaClient, RemoteMetaClient};
 use crate::store::command::{self, META_REQUEST_TENANT_EXIST, META_REQUEST_TENANT_NOT_FOUND};
-use crate::MetaClientRef;
+use crate::{AdminMetaRef, MetaClientRef};
 
 pub const USE_TENANT_ACTION_ADD: i32 = 1;
 pub const USE_TENANT_ACTION_DEL: i32 = 2;
@@ -57,6 +57,7 @@ pub struct RemoteTenantManager {
     cluster_meta: String,
     node_id: u64,
     tenant_change_sender: Sender<UseTenantInfo>,
+    admin_meta: AdminMetaRef,
 
     tenants: RwLock<HashMap<String, MetaClientRef>>,
     limiters: RwLock<HashMap<String, Arc<dyn RequestLimiter>>>,
@@ -68,9 +69,11 @@ impl RemoteTenantManager {
         cluster_meta: String,
         id: u64,
         tenant_change_sender: Sender<UseTenantInfo>,
+        admin_meta: AdminMetaRef,
     ) -> Self {
         Self {
             tenant_change_sender,
+            admin_meta,
             client: MetaHttpClient::new(cluster_meta.clone()),
             cluster_name,
             cluster_meta,
@@ -97,7 +100,7 @@ impl RemoteTenantManager {
             .await
             .insert(tenant_name.clone(), client.clone());
 
-        let limiter = self.new_limiter(&self.cluster_name, &tenant_name, &option);
+        let limiter = self.new_limiter(&tenant_name, &option, client.clone());
         self.limiters
             .write()
             .await
@@ -115,16 +118,16 @@ impl RemoteTenantManager {
 
     pub fn new_limiter(
         &self,
-        _cluster_name: &str,
         tenant_name: &str,
         options: &TenantOptions,
+        meta_client: MetaClientRef,
     ) -> Arc<dyn RequestLimiter> {
         match options.request_config() {
             Some(config) => Arc::new(LocalRequestLimiter::new(
-                self.cluster_name.as_str(),
                 tenant_name,
                 config,
-                self.client.clone(),
+                meta_client,
+                self.admin_meta.clone(),
             )),
             None => Arc::new(NoneLimiter {}),
         }
@@ -142,7 +145,6 @@ impl TenantManager for RemoteTenantManager {
         name: String,
         options: TenantOptions,
     ) -> MetaResult<MetaClientRef> {
-        let _limiter = self.new_limiter(&self.cluster_name, &name, &options);
         let req = command::WriteCommand::CreateTenant(self.cluster_name.clone(), name, options);
 
         match self
@@ -195,8 +197,6 @@ impl TenantManager for RemoteTenantManager {
     }
 
     async fn alter_tenant(&self, name: &str, options: TenantOptions) -> MetaResult<()> {
-        let _limiter = self.new_limiter(&self.cluster_name, name, &options);
-
         let req = command::WriteCommand::AlterTenant(
             self.cluster_name.clone(),
             name.to_string(),
@@ -0,0 +1,241 @@
+use std::any::Any;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use datafusion::arrow::datatypes::{Field, Schema, SchemaRef};
+use datafusion::arrow::error::ArrowError;
+use datafusion::datasource::file_format::FileFormat;
+use datafusion::datasource::listing::ListingOptions;
+use datafusion::error::Result;
+use datafusion::execution::context::{SessionState, TaskContext};
+use datafusion::logical_expr::Expr;
+use datafusion::physical_expr::PhysicalSortExpr;
+use datafusion::physical_plan::file_format::{
+    FileMeta, FileOpenFuture, FileOpener, FileScanConfig, FileStream,
+};
+use datafusion::physical_plan::metrics::{ExecutionPlanMetricsSet, MetricsSet};
+use datafusion::physical_plan::{
+    DisplayFormatType, ExecutionPlan, Partitioning, SendableRecordBatchStream, Statistics,
+};
+use futures::StreamExt;
+use object_store::{ObjectMeta, ObjectStore};
+
+use self::reader::OrcFile;
+
+pub mod reader;
+
+/// The file type of ORC files in `STORED AS` and `FILE_FORMAT`
+pub const ORC_FILE_TYPE: &str = "ORC";
+/// The file extension of ORC files
+pub const DEFAULT_ORC_EXTENSION: &str = ".orc";
+
+/// The options of listing tables on ORC files, datafusion does not know the file type
+pub fn orc_listing_options(target_partitions: usize) -> ListingOptions {
+    ListingOptions::new(Arc::new(OrcFormat::default()))
+        .with_file_extension(DEFAULT_ORC_EXTENSION)
+        .with_target_partitions(target_partitions)
+}
+
+/// ORC files, which are read by [`OrcExec`]
+#[derive(Debug, Default)]
+pub struct OrcFormat {}
+
+#[async_trait]
+impl FileFormat for OrcFormat {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    async fn infer_schema(
+        &self,
+        _state: &SessionState,
+        store: &Arc<dyn ObjectStore>,
+        objects: &[ObjectMeta],
+    ) -> Result<SchemaRef> {
+        let mut schemas = Vec::with_capacity(objects.len());
+        for object in objects {
+            let data = store.get(&object.location).await?.bytes().await?;
+            schemas.push(OrcFile::try_new(data)?.schema()?);
+        }
+
+        Ok(Arc::new(Schema::try_merge(schemas)?))
+    }
+
+    async fn infer_stats(
+        &self,
+        _state: &SessionState,
+        _store: &Arc<dyn ObjectStore>,
+        _table_schema: SchemaRef,
+        _object: &ObjectMeta,
+    ) -> Result<Statistics> {
+        Ok(Statistics::default())
+    }
+
+    async fn create_physical_plan(
+        &self,
+        _state: &SessionState,
+        conf: FileScanConfig,
+        _filters: &[Expr],
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(Arc::new(OrcExec::new(conf)))
+    }
+}
+
+/// Execution plan for scanning ORC files, the files are read one after another
+#[derive(Debug, Clone)]
+pub struct OrcExec {
+    base_config: FileScanConfig,
+    projected_schema: SchemaRef,
+    /// Execution metrics
+    metrics: ExecutionPlanMetricsSet,
+}
+
+impl OrcExec {
+    pub fn new(base_config: FileScanConfig) -> Self {
+        let file_schema = &base_config.file_schema;
+        let num_file_columns = file_schema.fields().len();
+        let fields = base_config
+            .projection
+            .clone()
+            .unwrap_or_else(|| {
+                (0..num_file_columns + base_config.table_partition_cols.len()).collect()
+            })
+            .into_iter()
+            .map(|i| {
+                if i < num_file_columns {
+                    file_schema.field(i).clone()
+                } else {
+                    let (name, data_type) = &base_config.table_partition_cols[i - num_file_columns];
+                    Field::new(name, data_type.clone(), false)
+                }
+            })
+            .collect();
+
+        Self {
+            projected_schema: Arc::new(Schema::new(fields)),
+            base_config,
+            metrics: ExecutionPlanMetricsSet::new(),
+        }
+    }
+
+    /// Columns of the files to read, the partition columns are appended by [`FileStream`]
+    fn projected_file_schema(&self) -> SchemaRef {
+        let file_schema = &self.base_config.file_schema;
+        match &self.base_config.projection {
+            Some(projection) => {
+                let fields = projection
+                    .iter()
+                    .filter(|i| **i < file_schema.fields().len())
+                    .map(|i| file_schema.field(*i).clone())
+                    .collect();
+                Arc::new(Schema::new(fields))
+            }
+            None => file_schema.clone(),
+        }
+    }
+}
+
+impl ExecutionPlan for OrcExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.projected_schema.clone()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(self.base_config.file_groups.len())
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        None
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        _: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(self)
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let opener = OrcOpener {
+            object_store: context
+                .runtime_env()
+                .object_store(&self.base_config.object_store_url)?,
+            schema: self.projected_file_schema(),
+            batch_size: context.session_config().batch_size(),
+        };
+
+        let stream = FileStream::new(&self.base_config, partition, opener, &self.metrics)?;
+        Ok(Box::pin(stream))
+    }
+
+    fn fmt_as(&self, t: DisplayFormatType, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match t {
+            DisplayFormatType::Default => {
+                let files = self
+                    .base_config
+                    .file_groups
+                    .iter()
+                    .map(|group| group.len())
+                    .sum::<usize>();
+                write!(
+                    f,
+                    "OrcExec: files={}, limit={:?}",
+                    files, self.base_config.limit
+                )
+            }
+        }
+    }
+
+    fn statistics(&self) -> Statistics {
+        Statistics::default()
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
+}
+
+struct OrcOpener {
+    object_store: Arc<dyn ObjectStore>,
+    schema: SchemaRef,
+    batch_size: usize,
+}
+
+impl FileOpener for OrcOpener {
+    fn open(&self, file_meta: FileMeta) -> Result<FileOpenFuture> {
+        let object_store = self.object_store.clone();
+        let schema = self.schema.clone();
+        let batch_size = self.batch_size.max(1);
+
+        Ok(Box::pin(async move {
+            let data = object_store
+                .get(file_meta.location())
+                .await?
+                .bytes()
+                .await?;
+            let batches = OrcFile::try_new(data)?.read(&schema)?;
+
+            // A stripe is read as a whole, which is split into batches of the batch size
+            let batches = batches.into_iter().flat_map(move |batch| {
+                let num_rows = batch.num_rows();
+                (0..num_rows).step_by(batch_size).map(move |offset| {
+                    Ok::<_, ArrowError>(batch.slice(offset, batch_size.min(num_rows - offset)))
+                })
+            });
+
+            Ok(futures::stream::iter(batches).boxed())
+        }))
+    }
+}
@@ -0,0 +1,717 @@
+use std::borrow::Cow;
+use std::io::Read;
+use std::sync::Arc;
+
+use bytes::Bytes;
+use datafusion::arrow::array::{
+    new_null_array, ArrayRef, BinaryArray, BooleanArray, Date32Array, Decimal128Array,
+    Float32Array, Float64Array, Int16Array, Int32Array, Int64Array, Int8Array, StringArray,
+    TimestampNanosecondArray,
+};
+use datafusion::arrow::compute::cast;
+use datafusion::arrow::datatypes::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use datafusion::arrow::record_batch::{RecordBatch, RecordBatchOptions};
+use datafusion::error::{DataFusionError, Result};
+use orc_format::proto::column_encoding::Kind as EncodingKind;
+use orc_format::proto::r#type::Kind as TypeKind;
+use orc_format::proto::stream::Kind as StreamKind;
+use orc_format::proto::{Footer, PostScript, StripeFooter, StripeInformation};
+use orc_format::read::decode::{SignedRleV2Iter, UnsignedRleV2Iter};
+use prost_orc::Message;
+
+const ORC_MAGIC: &[u8] = b"ORC";
+/// Seconds of 2015-01-01 00:00:00, which the seconds of timestamps are relative to
+const TIMESTAMP_BASE_SECONDS: i64 = 1_420_070_400;
+/// Precision and scale of decimal columns written without them
+const DEFAULT_DECIMAL_PRECISION: u32 = 38;
+const DEFAULT_DECIMAL_SCALE: u32 = 10;
+
+/// An ORC file read into memory.
+///
+/// Columns of the root struct are read as columns of the record batches,
+/// nested types and the run length encodings of version 1 are not supported.
+/// Timestamps are the local date times of the writer, which are read as timestamps without time zone.
+pub struct OrcFile {
+    data: Bytes,
+    compression: Compression,
+    footer: Footer,
+}
+
+impl OrcFile {
+    pub fn try_new(data: Bytes) -> Result<Self> {
+        if !data.starts_with(ORC_MAGIC) {
+            return Err(orc_error("missing magic of orc file"));
+        }
+
+        // The last byte is the length of the postscript, which is never compressed
+        let postscript_len = *data.last().unwrap_or(&0) as usize;
+        let postscript_start = (data.len() - 1)
+            .checked_sub(postscript_len)
+            .ok_or_else(|| orc_error("postscript out of the file"))?;
+        let postscript = PostScript::decode(&data[postscript_start..data.len() - 1])
+            .map_err(|e| orc_error(e.to_string()))?;
+        let compression = Compression::try_new(postscript.compression.unwrap_or_default())?;
+
+        let footer_len = postscript.footer_length() as usize;
+        let footer_start = postscript_start
+            .checked_sub(footer_len)
+            .ok_or_else(|| orc_error("footer out of the file"))?;
+        let footer = decompress(&data[footer_start..postscript_start], compression)?;
+        let footer = Footer::decode(footer.as_ref()).map_err(|e| orc_error(e.to_string()))?;
+
+        Ok(Self {
+            data,
+            compression,
+            footer,
+        })
+    }
+
+    pub fn num_rows(&self) -> usize {
+        self.footer.number_of_rows() as usize
+    }
+
+    /// The schema of the columns of the root struct, all columns are nullable
+    pub fn schema(&self) -> Result<Schema> {
+        let fields = self
+            .columns()?
+            .into_iter()
+            .map(|(name, column)| Ok(Field::new(name, self.data_type(column)?, true)))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Schema::new(fields))
+    }
+
+    /// Read the columns of `schema` by name, one record batch for each stripe.
+    ///
+    /// Columns not in the file are filled with nulls,
+    /// the others are cast to the type in `schema` if the types are different.
+    pub fn read(&self, schema: &SchemaRef) -> Result<Vec<RecordBatch>> {
+        let columns = self.columns()?;
+        let column_ids = schema
+            .fields()
+            .iter()
+            .map(|field| {
+                columns
+                    .iter()
+                    .find(|(name, _)| name == field.name())
+                    .map(|(_, column)| *column)
+            })
+            .collect::<Vec<_>>();
+
+        self.footer
+            .stripes
+            .iter()
+            .map(|stripe| self.read_stripe(stripe, schema, &column_ids))
+            .collect()
+    }
+
+    fn read_stripe(
+        &self,
+        stripe: &StripeInformation,
+        schema: &SchemaRef,
+        column_ids: &[Option<u32>],
+    ) -> Result<RecordBatch> {
+        let footer_start = stripe.offset() + stripe.index_length() + stripe.data_length();
+        let footer = self.slice(footer_start, stripe.footer_length())?;
+        let footer = decompress(footer, self.compression)?;
+        let footer = StripeFooter::decode(footer.as_ref()).map_err(|e| orc_error(e.to_string()))?;
+        let stripe = Stripe {
+            file: self,
+            footer,
+            offset: stripe.offset(),
+            num_rows: stripe.number_of_rows() as usize,
+        };
+
+        let arrays = schema
+            .fields()
+            .iter()
+            .zip(column_ids)
+            .map(|(field, column)| {
+                let array = match column {
+                    Some(column) => stripe.read_column(*column)?,
+                    None => new_null_array(field.data_type(), stripe.num_rows),
+                };
+                if array.data_type() == field.data_type() {
+                    Ok(array)
+                } else {
+                    Ok(cast(&array, field.data_type())?)
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let options = RecordBatchOptions::new().with_row_count(Some(stripe.num_rows));
+        Ok(RecordBatch::try_new_with_options(
+            schema.clone(),
+            arrays,
+            &options,
+        )?)
+    }
+
+    /// Names and ids of the columns of the root struct
+    fn columns(&self) -> Result<Vec<(&str, u32)>> {
+        let root = self
+            .footer
+            .types
+            .first()
+            .ok_or_else(|| orc_error("missing type of root"))?;
+        if root.kind() != TypeKind::Struct {
+            return Err(orc_error(format!("root is {:?} not struct", root.kind())));
+        }
+
+        Ok(root
+            .field_names
+            .iter()
+            .map(String::as_str)
+            .zip(root.subtypes.iter().copied())
+            .collect())
+    }
+
+    fn data_type(&self, column: u32) -> Result<DataType> {
+        let column_type = self
+            .footer
+            .types
+            .get(column as usize)
+            .ok_or_else(|| orc_error(format!("missing type of column {}", column)))?;
+
+        let data_type = match column_type.kind() {
+            TypeKind::Boolean => DataType::Boolean,
+            TypeKind::Byte => DataType::Int8,
+            TypeKind::Short => DataType::Int16,
+            TypeKind::Int => DataType::Int32,
+            TypeKind::Long => DataType::Int64,
+            TypeKind::Float => DataType::Float32,
+            TypeKind::Double => DataType::Float64,
+            TypeKind::String | TypeKind::Varchar | TypeKind::Char => DataType::Utf8,
+            TypeKind::Binary => DataType::Binary,
+            TypeKind::Timestamp => DataType::Timestamp(TimeUnit::Nanosecond, None),
+            TypeKind::Date => DataType::Date32,
+            TypeKind::Decimal => DataType::Decimal128(
+                column_type.precision.unwrap_or(DEFAULT_DECIMAL_PRECISION) as u8,
+                column_type.scale.unwrap_or(DEFAULT_DECIMAL_SCALE) as i8,
+            ),
+            kind => {
+                return Err(DataFusionError::NotImplemented(format!(
+                    "read {:?} column of orc file",
+                    kind
+                )))
+            }
+        };
+
+        Ok(data_type)
+    }
+
+    fn slice(&self, offset: u64, len: u64) -> Result<&[u8]> {
+        let (offset, len) = (offset as usize, len as usize);
+        self.data
+            .get(offset..offset + len)
+            .ok_or_else(|| orc_error("stripe out of the file"))
+    }
+}
+
+/// Kinds of compression in the postscript, the generated `CompressionKind` lacks the newer ones
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Compression {
+    None,
+    Zlib,
+    Snappy,
+    Lzo,
+    Lz4,
+    Zstd,
+}
+
+impl Compression {
+    fn try_new(kind: i32) -> Result<Self> {
+        match kind {
+            0 => Ok(Self::None),
+            1 => Ok(Self::Zlib),
+            2 => Ok(Self::Snappy),
+            3 => Ok(Self::Lzo),
+            4 => Ok(Self::Lz4),
+            5 => Ok(Self::Zstd),
+            _ => Err(orc_error(format!("unknown compression {}", kind))),
+        }
+    }
+}
+
+struct Stripe<'a> {
+    file: &'a OrcFile,
+    footer: StripeFooter,
+    offset: u64,
+    num_rows: usize,
+}
+
+impl<'a> Stripe<'a> {
+    fn read_column(&self, column: u32) -> Result<ArrayRef> {
+        let present = self
+            .stream(column, StreamKind::Present)?
+            .map(|data| decode_booleans(&data, self.num_rows))
+            .transpose()?;
+        let num_values = present
+            .as_ref()
+            .map(|present| present.iter().filter(|p| **p).count())
+            .unwrap_or(self.num_rows);
+
+        let array: ArrayRef = match self.file.data_type(column)? {
+            DataType::Boolean => {
+                let values = decode_booleans(&self.data(column)?, num_values)?;
+                Arc::new(BooleanArray::from(with_present(values, &present)))
+            }
+            DataType::Int8 => {
+                let values = decode_bytes(&self.data(column)?, num_values)?;
+                let values = values.into_iter().map(|v| v as i8).collect();
+                Arc::new(Int8Array::from(with_present(values, &present)))
+            }
+            DataType::Int16 => {
+                let values = self.signed(column, StreamKind::Data, num_values)?;
+                let values = values.into_iter().map(|v| v as i16).collect();
+                Arc::new(Int16Array::from(with_present(values, &present)))
+            }
+            DataType::Int32 => {
+                let values = self.signed(column, StreamKind::Data, num_values)?;
+                let values = values.into_iter().map(|v| v as i32).collect();
+                Arc::new(Int32Array::from(with_present(values, &present)))
+            }
+            DataType::Int64 => {
+                let values = self.signed(column, StreamKind::Data, num_values)?;
+                Arc::new(Int64Array::from(with_present(values, &present)))
+            }
+            DataType::Float32 => {
+                let values = decode_floats(&self.data(column)?, num_values, f32::from_le_bytes)?;
+                Arc::new(Float32Array::from(with_present(values, &present)))
+            }
+            DataType::Float64 => {
+                let values = decode_floats(&self.data(column)?, num_values, f64::from_le_bytes)?;
+                Arc::new(Float64Array::from(with_present(values, &present)))
+            }
+            DataType::Utf8 => {
+                let values = self
+                    .binaries(column, num_values)?
+                    .into_iter()
+                    .map(|v| String::from_utf8(v).map_err(|e| orc_error(e.to_string())))
+                    .collect::<Result<Vec<_>>>()?;
+                Arc::new(StringArray::from(with_present(values, &present)))
+            }
+            DataType::Binary => {
+                let values = self.binaries(column, num_values)?;
+                Arc::new(BinaryArray::from_iter(with_present(values, &present)))
+            }
+            DataType::Timestamp(_, _) => {
+                let values = self.timestamps(column, num_values)?;
+                Arc::new(TimestampNanosecondArray::from(with_present(
+                    values, &present,
+                )))
+            }
+            DataType::Date32 => {
+                let values = self.signed(column, StreamKind::Data, num_values)?;
+                let values = values.into_iter().map(|v| v as i32).collect();
+                Arc::new(Date32Array::from(with_present(values, &present)))
+            }
+            DataType::Decimal128(precision, scale) => {
+                let values = self.decimals(column, num_values, scale)?;
+                Arc::new(
+                    Decimal128Array::from(with_present(values, &present))
+                        .with_precision_and_scale(precision, scale)?,
+                )
+            }
+            data_type => {
+                return Err(DataFusionError::NotImplemented(format!(
+                    "read {} column of orc file",
+                    data_type
+                )))
+            }
+        };
+
+        Ok(array)
+    }
+
+    /// Decompressed stream of `kind` of the column, none if the column has no such stream
+    fn stream(&self, column: u32, kind: StreamKind) -> Result<Option<Cow<'a, [u8]>>> {
+        // Streams are stored in the order of the footer, the index streams come first
+        let mut offset = self.offset;
+        for stream in &self.footer.streams {
+            if stream.column() == column && stream.kind() == kind {
+                let data = self.file.slice(offset, stream.length())?;
+                return decompress(data, self.file.compression).map(Some);
+            }
+            offset += stream.length();
+        }
+
+        Ok(None)
+    }
+
+    fn required_stream(&self, column: u32, kind: StreamKind) -> Result<Cow<'a, [u8]>> {
+        self.stream(column, kind)?
+            .ok_or_else(|| orc_error(format!("missing {:?} stream of column {}", kind, column)))
+    }
+
+    fn data(&self, column: u32) -> Result<Cow<'a, [u8]>> {
+        self.required_stream(column, StreamKind::Data)
+    }
+
+    fn encoding(&self, column: u32) -> EncodingKind {
+        self.footer
+            .columns
+            .get(column as usize)
+            .map(|encoding| encoding.kind())
+            .unwrap_or(EncodingKind::Direct)
+    }
+
+    fn check_encoding_v2(&self, column: u32) -> Result<()> {
+        match self.encoding(column) {
+            EncodingKind::DirectV2 | EncodingKind::DictionaryV2 => Ok(()),
+            encoding => Err(DataFusionError::NotImplemented(format!(
+                "read orc column {} of {:?} encoding",
+                column, encoding
+            ))),
+        }
+    }
+
+    fn signed(&self, column: u32, kind: StreamKind, num_values: usize) -> Result<Vec<i64>> {
+        self.check_encoding_v2(column)?;
+        let data = self.required_stream(column, kind)?;
+        SignedRleV2Iter::new(data.as_ref(), num_values, vec![])
+            .collect::<std::result::Result<_, _>>()
+            .map_err(|e| orc_error(format!("{:?}", e)))
+    }
+
+    fn unsigned(&self, column: u32, kind: StreamKind, num_values: usize) -> Result<Vec<u64>> {
+        self.check_encoding_v2(column)?;
+        let data = self.required_stream(column, kind)?;
+        UnsignedRleV2Iter::new(data.as_ref(), num_values, vec![])
+            .collect::<std::result::Result<_, _>>()
+            .map_err(|e| orc_error(format!("{:?}", e)))
+    }
+
+    fn binaries(&self, column: u32, num_values: usize) -> Result<Vec<Vec<u8>>> {
+        match self.encoding(column) {
+            EncodingKind::DictionaryV2 => {
+                let dictionary_size = self.footer.columns[column as usize].dictionary_size();
+                let lengths =
+                    self.unsigned(column, StreamKind::Length, dictionary_size as usize)?;
+                let dictionary = self.required_stream(column, StreamKind::DictionaryData)?;
+                let dictionary = split_values(&dictionary, &lengths)?;
+                self.unsigned(column, StreamKind::Data, num_values)?
+                    .into_iter()
+                    .map(|index| {
+                        dictionary
+                            .get(index as usize)
+                            .map(|value| value.to_vec())
+                            .ok_or_else(|| orc_error("index out of the dictionary"))
+                    })
+                    .collect()
+            }
+            _ => {
+                let lengths = self.unsigned(column, StreamKind::Length, num_values)?;
+                let data = self.data(column)?;
+                Ok(split_values(&data, &lengths)?
+                    .into_iter()
+                    .map(|value| value.to_vec())
+                    .collect())
+            }
+        }
+    }
+
+    fn timestamps(&self, column: u32, num_values: usize) -> Result<Vec<i64>> {
+        let seconds = self.signed(column, StreamKind::Data, num_values)?;
+        let nanos = self.unsigned(column, StreamKind::Secondary, num_values)?;
+
+        seconds
+            .into_iter()
+            .zip(nanos)
+            .map(|(seconds, nanos)| {
+                let nanos = decode_nanos(nanos);
+                let mut seconds = seconds + TIMESTAMP_BASE_SECONDS;
+                // Seconds before 1970 are rounded towards zero by the writers
+                if seconds < 0 && nanos > 999_999 {
+                    seconds -= 1;
+                }
+                seconds
+                    .checked_mul(1_000_000_000)
+                    .and_then(|v| v.checked_add(nanos))
+                    .ok_or_else(|| orc_error("timestamp out of the range of nanoseconds"))
+            })
+            .collect()
+    }
+
+    /// Decimals are written as unbounded varints, each of which has its own scale
+    fn decimals(&self, column: u32, num_values: usize, scale: i8) -> Result<Vec<i128>> {
+        let data = self.data(column)?;
+        let scales = self.signed(column, StreamKind::Secondary, num_values)?;
+
+        let mut data = data.as_ref();
+        scales
+            .into_iter()
+            .map(|value_scale| {
+                let value = decode_varint_i128(&mut data)?;
+                let diff = scale as i64 - value_scale;
+                let factor = 10_i128
+                    .checked_pow(diff.unsigned_abs() as u32)
+                    .ok_or_else(|| orc_error("scale of decimal out of range"))?;
+                if diff >= 0 {
+                    value
+                        .checked_mul(factor)
+                        .ok_or_else(|| orc_error("decimal out of the range of the column"))
+                } else {
+                    Ok(value / factor)
+                }
+            })
+            .collect()
+    }
+}
+
+/// Fill the nulls into the values of the present rows
+fn with_present<T>(values: Vec<T>, present: &Option<Vec<bool>>) -> Vec<Option<T>> {
+    match present {
+        Some(present) => {
+            let mut values = values.into_iter();
+            present
+                .iter()
+                .map(|p| if *p { values.next() } else { None })
+                .collect()
+        }
+        None => values.into_iter().map(Some).collect(),
+    }
+}
+
+/// Streams are split into chunks, each of which has a header of 3 bytes:
+/// the length of the chunk and whether the chunk is not compressed.
+fn decompress(data: &[u8], compression: Compression) -> Result<Cow<'_, [u8]>> {
+    if compression == Compression::None {
+        return Ok(Cow::Borrowed(data));
+    }
+
+    let mut result = vec![];
+    let mut data = data;
+    while !data.is_empty() {
+        if data.len() < 3 {
+            return Err(orc_error("truncated header of compressed chunk"));
+        }
+        let header = u32::from_le_bytes([data[0], data[1], data[2], 0]);
+        let (is_original, len) = (header & 1 == 1, (header >> 1) as usize);
+        let chunk = data
+            .get(3..3 + len)
+            .ok_or_else(|| orc_error("truncated compressed chunk"))?;
+        data = &data[3 + len..];
+
+        if is_original {
+            result.extend_from_slice(chunk);
+            continue;
+        }
+        match compression {
+            Compression::Zlib => {
+                flate2::read::DeflateDecoder::new(chunk)
+                    .read_to_end(&mut result)
+                    .map_err(|e| orc_error(e.to_string()))?;
+            }
+            Compression::Snappy => {
+                let chunk = snap::raw::Decoder::new()
+                    .decompress_vec(chunk)
+                    .map_err(|e| orc_error(e.to_string()))?;
+                result.extend(chunk);
+            }
+            Compression::Zstd => {
+                let chunk =
+                    zstd::stream::decode_all(chunk).map_err(|e| orc_error(e.to_string()))?;
+                result.extend(chunk);
+            }
+            compression => {
+                return Err(DataFusionError::NotImplemented(format!(
+                    "read orc file compressed by {:?}",
+                    compression
+                )))
+            }
+        }
+    }
+
+    Ok(Cow::Owned(result))
+}
+
+/// Runs of at least 3 repeated bytes or up to 128 literal bytes
+fn decode_bytes(data: &[u8], num_values: usize) -> Result<Vec<u8>> {
+    let mut values = Vec::with_capacity(num_values);
+    let mut data = data.iter().copied();
+    let mut next = || data.next().ok_or_else(|| orc_error("truncated byte run"));
+    while values.len() < num_values {
+        let header = next()? as i8;
+        if header >= 0 {
+            let value = next()?;
+            values.extend(std::iter::repeat(value).take(header as usize + 3));
+        } else {
+            for _ in 0..header.unsigned_abs() {
+                values.push(next()?);
+            }
+        }
+    }
+    values.truncate(num_values);
+
+    Ok(values)
+}
+
+/// Bits of the bytes, the most significant bit first
+fn decode_booleans(data: &[u8], num_values: usize) -> Result<Vec<bool>> {
+    let bytes = decode_bytes(data, (num_values + 7) / 8)?;
+    Ok(bytes
+        .iter()
+        .flat_map(|b| (0..8).map(move |i| b & (0x80 >> i) != 0))
+        .take(num_values)
+        .collect())
+}
+
+fn decode_floats<T, const N: usize>(
+    data: &[u8],
+    num_values: usize,
+    from_le_bytes: fn([u8; N]) -> T,
+) -> Result<Vec<T>> {
+    if data.len() < num_values * N {
+        return Err(orc_error("truncated floats"));
+    }
+    Ok(data
+        .chunks_exact(N)
+        .take(num_values)
+        .map(|b| from_le_bytes(b.try_into().unwrap_or([0; N])))
+        .collect())
+}
+
+/// The lower 3 bits are the number of trailing zeros of the nanoseconds minus one
+fn decode_nanos(value: u64) -> i64 {
+    let zeros = value & 0x07;
+    let nanos = (value >> 3) as i64;
+    if zeros == 0 {
+        nanos
+    } else {
+        nanos * 10_i64.pow(zeros as u32 + 1)
+    }
+}
+
+fn decode_varint_i128(data: &mut &[u8]) -> Result<i128> {
+    let mut value = 0_u128;
+    let mut shift = 0;
+    loop {
+        let (byte, rest) = data
+            .split_first()
+            .ok_or_else(|| orc_error("truncated varint"))?;
+        *data = rest;
+        if shift >= 128 {
+            return Err(orc_error("varint out of the range of decimal"));
+        }
+        value |= ((byte & 0x7f) as u128) << shift;
+        if byte & 0x80 == 0 {
+            break;
+        }
+        shift += 7;
+    }
+
+    // zigzag
+    Ok((value >> 1) as i128 ^ -((value & 1) as i128))
+}
+
+fn split_values<'b>(data: &'b [u8], lengths: &[u64]) -> Result<Vec<&'b [u8]>> {
+    let mut offset = 0;
+    lengths
+        .iter()
+        .map(|len| {
+            let end = offset + *len as usize;
+            let value = data
+                .get(offset..end)
+                .ok_or_else(|| orc_error("value out of the data stream"))?;
+            offset = end;
+            Ok(value)
+        })
+        .collect()
+}
+
+fn orc_error(reason: impl Into<String>) -> DataFusionError {
+    DataFusionError::Execution(format!("Invalid orc file: {}", reason.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use datafusion::arrow::util::pretty::pretty_format_batches;
+
+    use super::*;
+
+    fn test_file() -> OrcFile {
+        let data = std::fs::read("tests/data/orc/test.orc").unwrap();
+        OrcFile::try_new(Bytes::from(data)).unwrap()
+    }
+
+    #[test]
+    fn test_read_schema() {
+        let schema = test_file().schema().unwrap();
+        assert_eq!(
+            schema,
+            Schema::new(vec![
+                Field::new(
+                    "time",
+                    DataType::Timestamp(TimeUnit::Nanosecond, None),
+                    true
+                ),
+                Field::new("host", DataType::Utf8, true),
+                Field::new("region", DataType::Utf8, true),
+                Field::new("value", DataType::Float64, true),
+                Field::new("count", DataType::Int64, true),
+                Field::new("flag", DataType::Boolean, true),
+                Field::new("tiny", DataType::Int8, true),
+                Field::new("day", DataType::Date32, true),
+                Field::new("price", DataType::Decimal128(10, 2), true),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_read_columns() {
+        let file = test_file();
+        assert_eq!(file.num_rows(), 3);
+
+        let schema = Arc::new(file.schema().unwrap());
+        let batches = file.read(&schema).unwrap();
+        let expected = [
+            "+-------------------------------+------+--------+-------+------------+-------+------+------------+-------+",
+            "| time                          | host | region | value | count      | flag  | tiny | day        | price |",
+            "+-------------------------------+------+--------+-------+------------+-------+------+------------+-------+",
+            "| 2023-01-01T00:00:00.000000001 | a    | sh     | 1.5   | 1          | true  | 1    | 2023-01-01 | 1.23  |",
+            "| 2023-01-01T00:00:01.500       |      | bj     |       | -2         | false | -1   | 1970-01-01 | -4.50 |",
+            "| 1969-12-31T23:59:58.500       | ccc  | sh     | -2.25 | 3000000000 | true  | 127  | 1969-12-31 |       |",
+            "+-------------------------------+------+--------+-------+------------+-------+------+------------+-------+",
+        ];
+        assert_eq!(
+            pretty_format_batches(&batches).unwrap().to_string(),
+            expected.join("\n")
+        );
+    }
+
+    #[test]
+    fn test_read_projected_columns() {
+        let file = test_file();
+
+        // Missing columns are filled with nulls and the others are cast
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("count", DataType::Float64, true),
+            Field::new("missing", DataType::Utf8, true),
+        ]));
+        let batches = file.read(&schema).unwrap();
+        let expected = [
+            "+------------+---------+",
+            "| count      | missing |",
+            "+------------+---------+",
+            "| 1          |         |",
+            "| -2         |         |",
+            "| 3000000000 |         |",
+            "+------------+---------+",
+        ];
+        assert_eq!(
+            pretty_format_batches(&batches).unwrap().to_string(),
+            expected.join("\n")
+        );
+
+        let batches = file.read(&Arc::new(Schema::empty())).unwrap();
+        assert_eq!(batches[0].num_rows(), 3);
+    }
+
+    #[test]
+    fn test_decode_nanos() {
+        assert_eq!(decode_nanos(1 << 3), 1);
+        // 5 with 8 trailing zeros
+        assert_eq!(decode_nanos(5 << 3 | 7), 500_000_000);
+    }
+}
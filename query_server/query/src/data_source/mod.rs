@@ -13,9 +13,11 @@ use futures::StreamExt;
 use spi::Result;
 use trace::warn;
 
+use self::table_provider::external_location::ExternalLocationTable;
 use self::table_provider::tskv::ClusterTable;
 use crate::extension::physical::plan_node::table_writer::TableWriterExec;
 
+pub mod file_format;
 pub mod sink;
 pub mod table_provider;
 pub mod write_exec_ext;
@@ -41,6 +43,8 @@ impl WriteExecExt for dyn TableProvider {
                 table as _
             } else if let Some(table) = self.as_any().downcast_ref::<ListingTable>() {
                 table as _
+            } else if let Some(table) = self.as_any().downcast_ref::<ExternalLocationTable>() {
+                table as _
             } else {
                 warn!("Table not support write.");
                 return Err(DataFusionError::Plan(
@@ -1,3 +1,4 @@
+pub mod partitioned;
 pub mod serializer;
 
 use std::sync::Arc;
@@ -12,6 +13,7 @@ use spi::query::datasource::WriteContext;
 use spi::{QueryError, Result};
 use trace::debug;
 
+use self::partitioned::{PartitionedObjectStoreSink, SharedManifest};
 use super::DynRecordBatchSerializer;
 use crate::data_source::{RecordBatchSink, RecordBatchSinkProvider, SinkMetadata};
 
@@ -56,6 +58,9 @@ pub struct ObjectStoreSinkProvider {
     object_store: Arc<DynObjectStore>,
     serializer: Arc<DynRecordBatchSerializer>,
     file_extension: String,
+    partition_columns: Vec<String>,
+    max_file_size: Option<usize>,
+    manifest: Arc<SharedManifest>,
 }
 
 impl ObjectStoreSinkProvider {
@@ -70,8 +75,29 @@ impl ObjectStoreSinkProvider {
             object_store,
             serializer,
             file_extension,
+            partition_columns: vec![],
+            max_file_size: None,
+            manifest: Arc::new(SharedManifest::default()),
         }
     }
+
+    /// Write the rows into hive-style directories of these columns
+    pub fn with_partition_columns(mut self, partition_columns: Vec<String>) -> Self {
+        self.partition_columns = partition_columns;
+        self
+    }
+
+    /// Roll to a new file when the file reaches this size
+    pub fn with_max_file_size(mut self, max_file_size: Option<usize>) -> Self {
+        self.max_file_size = max_file_size;
+        self
+    }
+
+    /// Whether the rows are written into multiple files listed by a manifest,
+    /// the sinks of all the partitions share the manifest.
+    pub fn is_multi_files(&self) -> bool {
+        !self.partition_columns.is_empty() || self.max_file_size.is_some()
+    }
 }
 
 impl RecordBatchSinkProvider for ObjectStoreSinkProvider {
@@ -86,6 +112,17 @@ impl RecordBatchSinkProvider for ObjectStoreSinkProvider {
             self.file_extension.clone(),
         );
 
+        if self.is_multi_files() {
+            return Box::new(PartitionedObjectStoreSink::new(
+                ctx,
+                self.serializer.clone(),
+                self.object_store.clone(),
+                self.partition_columns.clone(),
+                self.max_file_size,
+                self.manifest.clone(),
+            ));
+        }
+
         Box::new(ObjectStoreSink {
             ctx,
             s: self.serializer.clone(),
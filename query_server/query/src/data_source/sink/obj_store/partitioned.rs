@@ -0,0 +1,516 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use datafusion::arrow::array::{Array, ArrayRef, UInt32Array};
+use datafusion::arrow::compute::{concat_batches, take};
+use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::arrow::util::display::array_value_to_string;
+use datafusion::physical_plan::memory::MemoryStream;
+use datafusion::physical_plan::SendableRecordBatchStream;
+use futures::TryStreamExt;
+use object_store::path::Path;
+use object_store::DynObjectStore;
+use serde::Serialize;
+use spi::query::datasource::WriteContext;
+use spi::{QueryError, Result};
+use trace::debug;
+
+use crate::data_source::sink::DynRecordBatchSerializer;
+use crate::data_source::{RecordBatchSink, SinkMetadata};
+
+/// Name of the file listing all the files written
+pub const MANIFEST_FILE_NAME: &str = "_MANIFEST";
+
+/// Directory name of the rows whose partition value is null, the same as hive
+const DEFAULT_PARTITION_VALUE: &str = "__HIVE_DEFAULT_PARTITION__";
+
+/// Buffered size of a partition to write the rows into a file if `max_file_size` is not set
+const DEFAULT_FLUSH_SIZE: usize = 64 * 1024 * 1024;
+
+/// Buffered size of all the partitions of a sink, the largest partitions are written beyond it
+const MAX_BUFFERED_SIZE: usize = 256 * 1024 * 1024;
+
+#[derive(Debug, Serialize)]
+struct ManifestFile {
+    /// Path relative to the location
+    path: String,
+    partition_values: BTreeMap<String, String>,
+    rows: usize,
+    bytes: usize,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct Manifest {
+    files: Vec<ManifestFile>,
+    rows: usize,
+    bytes: usize,
+}
+
+/// Manifest of the files written by the sinks of all the input partitions
+#[derive(Default)]
+pub struct SharedManifest {
+    manifest: tokio::sync::Mutex<Manifest>,
+}
+
+/// Rows of a partition that have not been written
+struct PartitionBuffer {
+    partition_values: Vec<String>,
+    batches: Vec<RecordBatch>,
+    buffered_size: usize,
+    next_file_id: usize,
+}
+
+/// Write the rows into hive-style directories of the partition values, e.g.
+/// `date=2023-01-01/host=a/part-0-0.csv`, files are rolled by `max_file_size`.
+/// The rows are buffered up to [`DEFAULT_FLUSH_SIZE`] per partition if `max_file_size` is not set.
+///
+/// Each input partition is written by a sink of its own, the files are merged into
+/// the [`SharedManifest`] which is rewritten when a sink finishes, so the manifest
+/// lists all the files after the sinks of all the input partitions are finished.
+pub struct PartitionedObjectStoreSink {
+    ctx: WriteContext,
+    s: Arc<DynRecordBatchSerializer>,
+    object_store: Arc<DynObjectStore>,
+    partition_columns: Vec<String>,
+    max_file_size: Option<usize>,
+    manifest: Arc<SharedManifest>,
+}
+
+impl PartitionedObjectStoreSink {
+    pub fn new(
+        ctx: WriteContext,
+        s: Arc<DynRecordBatchSerializer>,
+        object_store: Arc<DynObjectStore>,
+        partition_columns: Vec<String>,
+        max_file_size: Option<usize>,
+        manifest: Arc<SharedManifest>,
+    ) -> Self {
+        Self {
+            ctx,
+            s,
+            object_store,
+            partition_columns,
+            max_file_size,
+            manifest,
+        }
+    }
+
+    /// Split the batch by the partition values, partition columns are removed from the result
+    fn split(&self, batch: RecordBatch) -> Result<Vec<(Vec<String>, RecordBatch)>> {
+        if self.partition_columns.is_empty() {
+            return Ok(vec![(vec![], batch)]);
+        }
+
+        let schema = batch.schema();
+        let partition_indices = self
+            .partition_columns
+            .iter()
+            .map(|e| schema.index_of(e))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let data_indices = (0..schema.fields().len())
+            .filter(|i| !partition_indices.contains(i))
+            .collect::<Vec<_>>();
+        let data_schema = Arc::new(schema.project(&data_indices)?);
+
+        let mut rows_of_partitions: BTreeMap<Vec<String>, Vec<u32>> = BTreeMap::new();
+        for row in 0..batch.num_rows() {
+            let partition_values = partition_indices
+                .iter()
+                .map(|i| partition_value(batch.column(*i), row))
+                .collect::<Result<Vec<_>>>()?;
+            rows_of_partitions
+                .entry(partition_values)
+                .or_default()
+                .push(row as u32);
+        }
+
+        let mut result = Vec::with_capacity(rows_of_partitions.len());
+        for (partition_values, rows) in rows_of_partitions {
+            let indices = UInt32Array::from(rows);
+            let columns = data_indices
+                .iter()
+                .map(|i| take(batch.column(*i).as_ref(), &indices, None))
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            result.push((
+                partition_values,
+                RecordBatch::try_new(data_schema.clone(), columns)?,
+            ));
+        }
+
+        Ok(result)
+    }
+
+    fn partition_dir(&self, partition_values: &[String]) -> Path {
+        self.partition_columns
+            .iter()
+            .zip(partition_values)
+            .fold(self.ctx.location().clone(), |dir, (name, value)| {
+                dir.child(format!("{}={}", name, value))
+            })
+    }
+
+    /// Write the buffered rows of the partition into one or more files
+    async fn flush(&self, buffer: &mut PartitionBuffer, manifest: &mut Manifest) -> Result<()> {
+        if buffer.batches.is_empty() {
+            return Ok(());
+        }
+
+        let batches = std::mem::take(&mut buffer.batches);
+        buffer.buffered_size = 0;
+        let schema = batches[0].schema();
+
+        let (rows, data) = self.serialize(schema.clone(), batches.clone()).await?;
+        let files = match self.max_file_size {
+            // Serialized size differs from the in-memory size, split the rows evenly if the file is too large
+            Some(max_file_size) if data.len() > max_file_size && rows > 1 => {
+                let num_files = ((data.len() + max_file_size - 1) / max_file_size).min(rows);
+                let rows_per_file = (rows + num_files - 1) / num_files;
+                let batch = concat_batches(&schema, &batches)?;
+
+                let mut files = Vec::with_capacity(num_files);
+                let mut offset = 0;
+                while offset < rows {
+                    let len = rows_per_file.min(rows - offset);
+                    let slice = batch.slice(offset, len);
+                    files.push(self.serialize(schema.clone(), vec![slice]).await?);
+                    offset += len;
+                }
+                files
+            }
+            _ => vec![(rows, data)],
+        };
+
+        let dir = self.partition_dir(&buffer.partition_values);
+        for (rows, data) in files {
+            let path = dir.child(format!(
+                "part-{}-{}{}",
+                self.ctx.partition(),
+                buffer.next_file_id,
+                self.ctx.file_extension()
+            ));
+            buffer.next_file_id += 1;
+
+            let bytes = data.len();
+            self.object_store.put(&path, data).await?;
+            debug!("Generated file: {}, rows: {}, bytes: {}", path, rows, bytes);
+
+            manifest.rows += rows;
+            manifest.bytes += bytes;
+            manifest.files.push(ManifestFile {
+                path: relative_path(self.ctx.location(), &path),
+                partition_values: self
+                    .partition_columns
+                    .iter()
+                    .cloned()
+                    .zip(buffer.partition_values.iter().cloned())
+                    .collect(),
+                rows,
+                bytes,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Merge the files into the shared manifest and rewrite it
+    async fn write_manifest(&self, manifest: Manifest) -> Result<()> {
+        let mut shared = self.manifest.manifest.lock().await;
+        shared.rows += manifest.rows;
+        shared.bytes += manifest.bytes;
+        shared.files.extend(manifest.files);
+        shared.files.sort_by(|a, b| a.path.cmp(&b.path));
+
+        let manifest_path = self.ctx.location().child(MANIFEST_FILE_NAME);
+        self.object_store
+            .put(&manifest_path, serde_json::to_vec_pretty(&*shared)?.into())
+            .await?;
+
+        Ok(())
+    }
+
+    async fn serialize(
+        &self,
+        schema: SchemaRef,
+        batches: Vec<RecordBatch>,
+    ) -> Result<(usize, bytes::Bytes)> {
+        let stream: SendableRecordBatchStream =
+            Box::pin(MemoryStream::try_new(batches, schema, None)?);
+        self.s.to_bytes(&self.ctx, stream).await
+    }
+}
+
+#[async_trait]
+impl RecordBatchSink for PartitionedObjectStoreSink {
+    async fn append(&self, _record_batch: RecordBatch) -> Result<SinkMetadata> {
+        Err(QueryError::Unimplement {
+            msg: "PartitionedObjectStoreSink::append".to_string(),
+        })
+    }
+
+    async fn stream_write(&self, mut stream: SendableRecordBatchStream) -> Result<SinkMetadata> {
+        debug!("Process PartitionedObjectStoreSink::stream_write");
+
+        let mut buffers: BTreeMap<Vec<String>, PartitionBuffer> = BTreeMap::new();
+        let mut buffered_size = 0;
+        let mut manifest = Manifest::default();
+        let flush_size = self.max_file_size.unwrap_or(DEFAULT_FLUSH_SIZE);
+
+        while let Some(batch) = stream.try_next().await? {
+            for (partition_values, batch) in self.split(batch)? {
+                let buffer =
+                    buffers
+                        .entry(partition_values.clone())
+                        .or_insert_with(|| PartitionBuffer {
+                            partition_values,
+                            batches: vec![],
+                            buffered_size: 0,
+                            next_file_id: 0,
+                        });
+                let size = batch
+                    .columns()
+                    .iter()
+                    .map(|e| e.get_array_memory_size())
+                    .sum::<usize>();
+                buffer.buffered_size += size;
+                buffered_size += size;
+                buffer.batches.push(batch);
+
+                if buffer.buffered_size >= flush_size {
+                    buffered_size -= buffer.buffered_size;
+                    self.flush(buffer, &mut manifest).await?;
+                }
+            }
+
+            // Too many partitions are buffered
+            while buffered_size > MAX_BUFFERED_SIZE {
+                let buffer = buffers
+                    .values_mut()
+                    .max_by_key(|e| e.buffered_size)
+                    .expect("buffered partitions");
+                buffered_size -= buffer.buffered_size;
+                self.flush(buffer, &mut manifest).await?;
+            }
+        }
+
+        for buffer in buffers.values_mut() {
+            self.flush(buffer, &mut manifest).await?;
+        }
+
+        let metadata = SinkMetadata::new(manifest.rows, manifest.bytes);
+        self.write_manifest(manifest).await?;
+
+        Ok(metadata)
+    }
+}
+
+fn partition_value(array: &ArrayRef, row: usize) -> Result<String> {
+    if array.is_null(row) {
+        return Ok(DEFAULT_PARTITION_VALUE.to_string());
+    }
+
+    Ok(array_value_to_string(array, row)?)
+}
+
+fn relative_path(location: &Path, path: &Path) -> String {
+    match path.prefix_match(location) {
+        Some(parts) => parts
+            .map(|e| e.as_ref().to_string())
+            .collect::<Vec<_>>()
+            .join("/"),
+        None => path.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use datafusion::arrow::array::{Int64Array, StringArray};
+    use datafusion::arrow::datatypes::{DataType, Field, Schema};
+    use datafusion::arrow::record_batch::RecordBatch;
+    use datafusion::physical_plan::memory::MemoryStream;
+    use object_store::local::LocalFileSystem;
+    use object_store::path::Path;
+    use object_store::ObjectStore;
+    use spi::query::datasource::WriteContext;
+
+    use super::{PartitionedObjectStoreSink, SharedManifest, MANIFEST_FILE_NAME};
+    use crate::data_source::sink::obj_store::serializer::csv::CsvRecordBatchSerializer;
+    use crate::data_source::RecordBatchSink;
+
+    #[tokio::test]
+    async fn test_partitioned_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let object_store = Arc::new(LocalFileSystem::new_with_prefix(dir.path()).unwrap());
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("value", DataType::Int64, false),
+            Field::new("host", DataType::Utf8, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int64Array::from(vec![1, 2, 3, 4])),
+                Arc::new(StringArray::from(vec![
+                    Some("a"),
+                    Some("b"),
+                    Some("a"),
+                    None,
+                ])),
+            ],
+        )
+        .unwrap();
+        let stream = Box::pin(MemoryStream::try_new(vec![batch], schema, None).unwrap());
+
+        let sink = PartitionedObjectStoreSink::new(
+            WriteContext::new(Path::from("export"), 0, ".csv".to_string()),
+            Arc::new(CsvRecordBatchSerializer::new(false, b',')),
+            object_store.clone(),
+            vec!["host".to_string()],
+            None,
+            Arc::new(SharedManifest::default()),
+        );
+        let meta = sink.stream_write(stream).await.unwrap();
+        assert_eq!(meta.rows_writed(), 4);
+
+        let read = |path: &'static str| {
+            let object_store = object_store.clone();
+            async move {
+                let data = object_store
+                    .get(&Path::from(path))
+                    .await
+                    .unwrap()
+                    .bytes()
+                    .await
+                    .unwrap();
+                String::from_utf8(data.to_vec()).unwrap()
+            }
+        };
+        assert_eq!(read("export/host=a/part-0-0.csv").await, "1\n3\n");
+        assert_eq!(read("export/host=b/part-0-0.csv").await, "2\n");
+        assert_eq!(
+            read("export/host=__HIVE_DEFAULT_PARTITION__/part-0-0.csv").await,
+            "4\n"
+        );
+
+        let manifest: serde_json::Value =
+            serde_json::from_str(&read("export/_MANIFEST").await).unwrap();
+        assert_eq!(manifest["rows"], 4);
+        assert_eq!(manifest["files"].as_array().unwrap().len(), 3);
+        assert_eq!(
+            manifest["files"][0]["path"],
+            "host=__HIVE_DEFAULT_PARTITION__/part-0-0.csv"
+        );
+        assert_eq!(manifest["files"][1]["partition_values"]["host"], "a");
+    }
+
+    #[tokio::test]
+    async fn test_roll_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let object_store = Arc::new(LocalFileSystem::new_with_prefix(dir.path()).unwrap());
+
+        let schema = Arc::new(Schema::new(vec![Field::new(
+            "value",
+            DataType::Int64,
+            false,
+        )]));
+        let batches = (0..4)
+            .map(|i| {
+                RecordBatch::try_new(
+                    schema.clone(),
+                    vec![Arc::new(Int64Array::from_iter_values(
+                        i * 100..(i + 1) * 100,
+                    ))],
+                )
+                .unwrap()
+            })
+            .collect::<Vec<_>>();
+        let stream = Box::pin(MemoryStream::try_new(batches, schema, None).unwrap());
+
+        let sink = PartitionedObjectStoreSink::new(
+            WriteContext::new(Path::from("export"), 0, ".csv".to_string()),
+            Arc::new(CsvRecordBatchSerializer::new(false, b',')),
+            object_store.clone(),
+            vec![],
+            Some(256),
+            Arc::new(SharedManifest::default()),
+        );
+        let meta = sink.stream_write(stream).await.unwrap();
+        assert_eq!(meta.rows_writed(), 400);
+
+        let files = object_store
+            .list_with_delimiter(Some(&Path::from("export")))
+            .await
+            .unwrap()
+            .objects;
+        // Every batch is flushed and split into 2 files, and the manifest
+        assert_eq!(files.len(), 9);
+        for file in files {
+            if file.location.filename() != Some(MANIFEST_FILE_NAME) {
+                assert!(file.size <= 256, "{} is too large", file.location);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_manifest_of_partitions() {
+        let dir = tempfile::tempdir().unwrap();
+        let object_store = Arc::new(LocalFileSystem::new_with_prefix(dir.path()).unwrap());
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("value", DataType::Int64, false),
+            Field::new("host", DataType::Utf8, false),
+        ]));
+        let manifest = Arc::new(SharedManifest::default());
+        let writes = (0..2).map(|partition| {
+            let batch = RecordBatch::try_new(
+                schema.clone(),
+                vec![
+                    Arc::new(Int64Array::from(vec![partition, partition + 2])),
+                    Arc::new(StringArray::from(vec!["a", "b"])),
+                ],
+            )
+            .unwrap();
+            let stream =
+                Box::pin(MemoryStream::try_new(vec![batch], schema.clone(), None).unwrap());
+            let sink = PartitionedObjectStoreSink::new(
+                WriteContext::new(Path::from("export"), partition as usize, ".csv".to_string()),
+                Arc::new(CsvRecordBatchSerializer::new(false, b',')),
+                object_store.clone(),
+                vec!["host".to_string()],
+                None,
+                manifest.clone(),
+            );
+            async move { sink.stream_write(stream).await.unwrap() }
+        });
+        let metas = futures::future::join_all(writes).await;
+        assert!(metas.iter().all(|e| e.rows_writed() == 2));
+
+        let data = object_store
+            .get(&Path::from("export/_MANIFEST"))
+            .await
+            .unwrap()
+            .bytes()
+            .await
+            .unwrap();
+        let manifest: serde_json::Value = serde_json::from_slice(&data).unwrap();
+        assert_eq!(manifest["rows"], 4);
+        let paths = manifest["files"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| e["path"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            vec![
+                "host=a/part-0-0.csv",
+                "host=a/part-1-0.csv",
+                "host=b/part-0-0.csv",
+                "host=b/part-1-0.csv",
+            ]
+        );
+    }
+}
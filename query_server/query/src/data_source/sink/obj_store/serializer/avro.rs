@@ -0,0 +1,290 @@
+use apache_avro::types::Value;
+use apache_avro::{Codec, Schema as AvroSchema, Writer};
+use async_trait::async_trait;
+use bytes::Bytes;
+use datafusion::arrow::array::{
+    Array, BinaryArray, BooleanArray, Date32Array, Float32Array, Float64Array, Int16Array,
+    Int32Array, Int64Array, Int8Array, LargeBinaryArray, LargeStringArray, StringArray,
+    TimestampMicrosecondArray, TimestampMillisecondArray, TimestampNanosecondArray,
+    TimestampSecondArray, UInt16Array, UInt32Array, UInt64Array, UInt8Array,
+};
+use datafusion::arrow::datatypes::{DataType, Schema, TimeUnit};
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::physical_plan::SendableRecordBatchStream;
+use futures::{pin_mut, TryStreamExt};
+use serde_json::json;
+use spi::query::datasource::WriteContext;
+use spi::QueryError;
+
+use crate::data_source::sink::RecordBatchSerializer;
+use crate::data_source::Result;
+
+/// The key of the file metadata listing the columns of nanosecond timestamps,
+/// the column names are separated by commas
+pub const AVRO_TIMESTAMP_NANOS_KEY: &str = "cnosdb.timestamp-nanos";
+
+/// Serialize record batches into an avro object container file
+pub struct AvroRecordBatchSerializer {}
+
+#[async_trait]
+impl RecordBatchSerializer for AvroRecordBatchSerializer {
+    async fn to_bytes(
+        &self,
+        _ctx: &WriteContext,
+        stream: SendableRecordBatchStream,
+    ) -> Result<(usize, Bytes)> {
+        let schema = to_avro_schema(&stream.schema())?;
+
+        pin_mut!(stream);
+
+        let mut num_rows = 0;
+        let mut writer = Writer::with_codec(&schema, vec![], Codec::Snappy);
+        let nanos_columns = timestamp_nanos_columns(&stream.schema()).join(",");
+        if !nanos_columns.is_empty() {
+            writer
+                .add_user_metadata(AVRO_TIMESTAMP_NANOS_KEY.to_string(), nanos_columns)
+                .map_err(|e| QueryError::SerializeAvro {
+                    reason: e.to_string(),
+                })?;
+        }
+        while let Some(batch) = stream.try_next().await? {
+            num_rows += batch.num_rows();
+            for record in to_avro_records(&batch)? {
+                writer
+                    .append(record)
+                    .map_err(|e| QueryError::SerializeAvro {
+                        reason: e.to_string(),
+                    })?;
+            }
+        }
+        let bytes = writer.into_inner().map_err(|e| QueryError::SerializeAvro {
+            reason: e.to_string(),
+        })?;
+
+        Ok((num_rows, Bytes::from(bytes)))
+    }
+}
+
+fn timestamp_nanos_columns(schema: &Schema) -> Vec<&str> {
+    schema
+        .fields()
+        .iter()
+        .filter(|f| matches!(f.data_type(), DataType::Timestamp(TimeUnit::Nanosecond, _)))
+        .map(|f| f.name().as_str())
+        .collect()
+}
+
+/// Nullable columns are written as a union of null and the type of the column
+fn to_avro_schema(schema: &Schema) -> Result<AvroSchema> {
+    let fields = schema
+        .fields()
+        .iter()
+        .map(|f| {
+            let avro_type = to_avro_type(f.data_type())?;
+            let avro_type = if f.is_nullable() {
+                json!(["null", avro_type])
+            } else {
+                avro_type
+            };
+            Ok(json!({"name": f.name(), "type": avro_type}))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let schema = json!({
+        "type": "record",
+        "name": "record",
+        "fields": fields,
+    });
+
+    AvroSchema::parse(&schema).map_err(|e| QueryError::SerializeAvro {
+        reason: e.to_string(),
+    })
+}
+
+fn to_avro_type(data_type: &DataType) -> Result<serde_json::Value> {
+    let avro_type = match data_type {
+        DataType::Boolean => json!("boolean"),
+        DataType::Int8 | DataType::Int16 | DataType::Int32 | DataType::UInt8 | DataType::UInt16 => {
+            json!("int")
+        }
+        DataType::Int64 | DataType::UInt32 | DataType::UInt64 => json!("long"),
+        DataType::Float32 => json!("float"),
+        DataType::Float64 => json!("double"),
+        DataType::Utf8 | DataType::LargeUtf8 => json!("string"),
+        DataType::Binary | DataType::LargeBinary => json!("bytes"),
+        DataType::Date32 => json!({"type": "int", "logicalType": "date"}),
+        DataType::Timestamp(TimeUnit::Second | TimeUnit::Millisecond, _) => {
+            json!({"type": "long", "logicalType": "timestamp-millis"})
+        }
+        DataType::Timestamp(TimeUnit::Microsecond, _) => {
+            json!({"type": "long", "logicalType": "timestamp-micros"})
+        }
+        // Avro has no logical type of nanoseconds, the values are kept in plain longs,
+        // and the columns are listed in the file metadata of `AVRO_TIMESTAMP_NANOS_KEY`
+        DataType::Timestamp(TimeUnit::Nanosecond, _) => json!("long"),
+        _ => {
+            return Err(QueryError::NotImplemented {
+                err: format!("write {} column to avro file", data_type),
+            })
+        }
+    };
+
+    Ok(avro_type)
+}
+
+/// Convert the batch column by column, then the values are taken row by row
+fn to_avro_records(batch: &RecordBatch) -> Result<Vec<Value>> {
+    let schema = batch.schema();
+    let mut columns = schema
+        .fields()
+        .iter()
+        .zip(batch.columns())
+        .map(|(field, array)| {
+            let values = to_avro_values(array.as_ref())?;
+            Ok((field, values.into_iter()))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let records = (0..batch.num_rows())
+        .map(|_| {
+            let fields = columns
+                .iter_mut()
+                .map(|(field, values)| {
+                    let value = values.next().unwrap_or(Value::Null);
+                    let value = match (field.is_nullable(), value) {
+                        (true, Value::Null) => Value::Union(0, Box::new(Value::Null)),
+                        (true, value) => Value::Union(1, Box::new(value)),
+                        (false, value) => value,
+                    };
+                    (field.name().clone(), value)
+                })
+                .collect();
+            Value::Record(fields)
+        })
+        .collect();
+
+    Ok(records)
+}
+
+fn to_avro_values(array: &dyn Array) -> Result<Vec<Value>> {
+    macro_rules! to_values {
+        ($array_type:ty, $to_value:expr) => {
+            downcast::<$array_type>(array)?
+                .iter()
+                .map(|v| v.map($to_value).unwrap_or(Ok(Value::Null)))
+                .collect::<Result<Vec<_>>>()
+        };
+    }
+
+    match array.data_type() {
+        DataType::Boolean => to_values!(BooleanArray, |v| Ok(Value::Boolean(v))),
+        DataType::Int8 => to_values!(Int8Array, |v| Ok(Value::Int(v as i32))),
+        DataType::Int16 => to_values!(Int16Array, |v| Ok(Value::Int(v as i32))),
+        DataType::Int32 => to_values!(Int32Array, |v| Ok(Value::Int(v))),
+        DataType::Int64 => to_values!(Int64Array, |v| Ok(Value::Long(v))),
+        DataType::UInt8 => to_values!(UInt8Array, |v| Ok(Value::Int(v as i32))),
+        DataType::UInt16 => to_values!(UInt16Array, |v| Ok(Value::Int(v as i32))),
+        DataType::UInt32 => to_values!(UInt32Array, |v| Ok(Value::Long(v as i64))),
+        // avro has no unsigned types
+        DataType::UInt64 => to_values!(UInt64Array, |v| i64::try_from(v).map(Value::Long).map_err(
+            |_| QueryError::SerializeAvro {
+                reason: format!("unsigned value {} is out of the range of avro long", v),
+            }
+        )),
+        DataType::Float32 => to_values!(Float32Array, |v| Ok(Value::Float(v))),
+        DataType::Float64 => to_values!(Float64Array, |v| Ok(Value::Double(v))),
+        DataType::Utf8 => to_values!(StringArray, |v| Ok(Value::String(v.to_string()))),
+        DataType::LargeUtf8 => to_values!(LargeStringArray, |v| Ok(Value::String(v.to_string()))),
+        DataType::Binary => to_values!(BinaryArray, |v| Ok(Value::Bytes(v.to_vec()))),
+        DataType::LargeBinary => to_values!(LargeBinaryArray, |v| Ok(Value::Bytes(v.to_vec()))),
+        DataType::Date32 => to_values!(Date32Array, |v| Ok(Value::Date(v))),
+        DataType::Timestamp(TimeUnit::Second, _) => {
+            to_values!(TimestampSecondArray, |v| Ok(Value::TimestampMillis(
+                v * 1000
+            )))
+        }
+        DataType::Timestamp(TimeUnit::Millisecond, _) => {
+            to_values!(TimestampMillisecondArray, |v| Ok(Value::TimestampMillis(v)))
+        }
+        DataType::Timestamp(TimeUnit::Microsecond, _) => {
+            to_values!(TimestampMicrosecondArray, |v| Ok(Value::TimestampMicros(v)))
+        }
+        DataType::Timestamp(TimeUnit::Nanosecond, _) => {
+            to_values!(TimestampNanosecondArray, |v| Ok(Value::Long(v)))
+        }
+        data_type => Err(QueryError::NotImplemented {
+            err: format!("write {} column to avro file", data_type),
+        }),
+    }
+}
+
+fn downcast<T: Array + 'static>(array: &dyn Array) -> Result<&T> {
+    array
+        .as_any()
+        .downcast_ref::<T>()
+        .ok_or_else(|| QueryError::SerializeAvro {
+            reason: format!("unexpected array of {}", array.data_type()),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use apache_avro::Reader;
+    use datafusion::arrow::datatypes::Field;
+    use datafusion::physical_plan::memory::MemoryStream;
+    use object_store::path::Path;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_avro_serializer() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new(
+                "time",
+                DataType::Timestamp(TimeUnit::Nanosecond, None),
+                false,
+            ),
+            Field::new("host", DataType::Utf8, true),
+            Field::new("value", DataType::Int64, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(TimestampNanosecondArray::from(vec![1_000, 2_999])),
+                Arc::new(StringArray::from(vec![Some("a"), None])),
+                Arc::new(Int64Array::from(vec![None, Some(3)])),
+            ],
+        )
+        .unwrap();
+        let stream = MemoryStream::try_new(vec![batch], schema, None).unwrap();
+
+        let (num_rows, bytes) = AvroRecordBatchSerializer {}
+            .to_bytes(
+                &WriteContext::new(Path::from("test"), 0, ".avro".to_string()),
+                Box::pin(stream),
+            )
+            .await
+            .unwrap();
+        assert_eq!(num_rows, 2);
+
+        let reader = Reader::new(&bytes[..]).unwrap();
+        assert_eq!(
+            reader.user_metadata().get(AVRO_TIMESTAMP_NANOS_KEY),
+            Some(&b"time".to_vec())
+        );
+        let records = reader.collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(
+            records[1],
+            Value::Record(vec![
+                ("time".to_string(), Value::Long(2_999)),
+                ("host".to_string(), Value::Union(0, Box::new(Value::Null))),
+                (
+                    "value".to_string(),
+                    Value::Union(1, Box::new(Value::Long(3)))
+                ),
+            ])
+        );
+    }
+}
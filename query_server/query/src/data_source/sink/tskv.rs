@@ -14,6 +14,7 @@ pub struct TskvRecordBatchSink {
     coord: CoordinatorRef,
     partition: usize,
     schema: TskvTableSchemaRef,
+    consistency_level: ConsistencyLevel,
 
     metrics: TskvSinkMetrics,
 }
@@ -46,7 +47,7 @@ impl RecordBatchSink for TskvRecordBatchSink {
         };
 
         self.coord
-            .write_points(self.schema.tenant.clone(), ConsistencyLevel::Any, req)
+            .write_points(self.schema.tenant.clone(), self.consistency_level, req)
             .await?;
 
         timer.done();
@@ -58,11 +59,20 @@ impl RecordBatchSink for TskvRecordBatchSink {
 pub struct TskvRecordBatchSinkProvider {
     coord: CoordinatorRef,
     schema: TskvTableSchemaRef,
+    consistency_level: ConsistencyLevel,
 }
 
 impl TskvRecordBatchSinkProvider {
-    pub fn new(coord: CoordinatorRef, schema: TskvTableSchemaRef) -> Self {
-        Self { coord, schema }
+    pub fn new(
+        coord: CoordinatorRef,
+        schema: TskvTableSchemaRef,
+        consistency_level: ConsistencyLevel,
+    ) -> Self {
+        Self {
+            coord,
+            schema,
+            consistency_level,
+        }
     }
 }
 
@@ -76,6 +86,7 @@ impl RecordBatchSinkProvider for TskvRecordBatchSinkProvider {
             coord: self.coord.clone(),
             partition,
             schema: self.schema.clone(),
+            consistency_level: self.consistency_level,
             metrics: TskvSinkMetrics::new(metrics, partition),
         })
     }
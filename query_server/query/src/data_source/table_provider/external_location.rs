@@ -0,0 +1,71 @@
+use std::any::Any;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::datasource::listing::ListingTable;
+use datafusion::datasource::{TableProvider, TableType};
+use datafusion::error::{DataFusionError, Result};
+use datafusion::execution::context::SessionState;
+use datafusion::logical_expr::logical_plan::AggWithGrouping;
+use datafusion::logical_expr::Expr;
+use datafusion::physical_plan::ExecutionPlan;
+
+use crate::data_source::write_exec_ext::external_table::write_listing_table;
+use crate::data_source::WriteExecExt;
+use crate::extension::physical::plan_node::table_writer::TableWriterExec;
+
+/// The external location of `COPY INTO <location>`,
+/// the partition columns of the listing table are written as hive-style directories.
+pub struct ExternalLocationTable {
+    table: Arc<ListingTable>,
+    max_file_size: Option<usize>,
+}
+
+impl ExternalLocationTable {
+    pub fn new(table: Arc<ListingTable>, max_file_size: Option<usize>) -> Self {
+        Self {
+            table,
+            max_file_size,
+        }
+    }
+}
+
+#[async_trait]
+impl TableProvider for ExternalLocationTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.table.schema()
+    }
+
+    fn table_type(&self) -> TableType {
+        self.table.table_type()
+    }
+
+    async fn scan(
+        &self,
+        state: &SessionState,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+        agg_with_grouping: Option<&AggWithGrouping>,
+        limit: Option<usize>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        self.table
+            .scan(state, projection, filters, agg_with_grouping, limit)
+            .await
+    }
+}
+
+#[async_trait]
+impl WriteExecExt for ExternalLocationTable {
+    async fn write(
+        &self,
+        state: &SessionState,
+        input: Arc<dyn ExecutionPlan>,
+    ) -> Result<Arc<TableWriterExec>, DataFusionError> {
+        write_listing_table(&self.table, state, input, self.max_file_size)
+    }
+}
@@ -1 +1,3 @@
+pub mod external_location;
+pub mod parameters;
 pub mod tskv;
@@ -4,12 +4,12 @@ use std::sync::Arc;
 
 use async_trait::async_trait;
 use coordinator::service::CoordinatorRef;
-use datafusion::arrow::datatypes::SchemaRef;
-use datafusion::common::DFSchemaRef;
+use datafusion::arrow::datatypes::{DataType, SchemaRef};
+use datafusion::common::{Column, DFSchemaRef};
 use datafusion::datasource::{TableProvider, TableType};
 use datafusion::error::{DataFusionError, Result};
 use datafusion::execution::context::SessionState;
-use datafusion::logical_expr::expr::AggregateFunction;
+use datafusion::logical_expr::expr::{AggregateFunction, Cast};
 use datafusion::logical_expr::logical_plan::AggWithGrouping;
 use datafusion::logical_expr::{
     aggregate_function, Expr, TableProviderAggregationPushDown, TableProviderFilterPushDown,
@@ -17,8 +17,10 @@ use datafusion::logical_expr::{
 use datafusion::optimizer::utils::split_conjunction;
 use datafusion::physical_plan::{project_schema, ExecutionPlan};
 use meta::error::MetaError;
+use models::consistency_level::ConsistencyLevel;
 use models::predicate::domain::{Predicate, PredicateRef, PushedAggregateFunction};
-use models::schema::{TskvTableSchema, TskvTableSchemaRef};
+use models::schema::{ColumnType, TskvTableSchema, TskvTableSchemaRef};
+use models::ValueType;
 use trace::debug;
 
 use crate::data_source::sink::tskv::TskvRecordBatchSinkProvider;
@@ -55,6 +57,39 @@ impl ClusterTable {
         ClusterTable { coord, schema }
     }
 
+    /// Min, max and sum are answered from statistics of numeric fields,
+    /// count is answered from the index.
+    fn supports_pushed_aggregate(
+        &self,
+        fun: &aggregate_function::AggregateFunction,
+        column: &str,
+    ) -> bool {
+        let value_type = match self.schema.column(column).map(|c| &c.column_type) {
+            Some(ColumnType::Field(value_type)) => Some(*value_type),
+            Some(_) => None,
+            None => return false,
+        };
+
+        match fun {
+            aggregate_function::AggregateFunction::Count => true,
+            aggregate_function::AggregateFunction::Min
+            | aggregate_function::AggregateFunction::Max => matches!(
+                value_type,
+                Some(
+                    ValueType::Integer
+                        | ValueType::Unsigned
+                        | ValueType::Float
+                        | ValueType::Boolean
+                )
+            ),
+            aggregate_function::AggregateFunction::Sum => matches!(
+                value_type,
+                Some(ValueType::Integer | ValueType::Unsigned | ValueType::Float)
+            ),
+            _ => false,
+        }
+    }
+
     pub async fn tag_scan(
         &self,
         _ctx: &SessionState,
@@ -138,10 +173,12 @@ impl TableProvider for ClusterTable {
         let exprs = exprs.into_iter().cloned().collect::<Vec<_>>();
         if expr_utils::find_exprs_in_exprs(&exprs, &|nested_expr| {
             !expr_utils::is_time_filter(nested_expr)
+                && !expr_utils::is_field_filter(nested_expr, &self.schema)
         })
         .is_empty()
         {
-            // all exprs are time range filter
+            // all exprs are time range filter or field value filter,
+            // so the limit can be pushed down to the scan as well
             return Ok(TableProviderFilterPushDown::Exact);
         }
 
@@ -157,30 +194,21 @@ impl TableProvider for ClusterTable {
             return Ok(TableProviderAggregationPushDown::Unsupported);
         }
 
-        let result = if aggr_expr.iter().all(|e| {
-            match e {
-                Expr::AggregateFunction(AggregateFunction {
-                    fun,
-                    args,
-                    distinct,
-                    filter,
-                }) => {
-                    let support_agg_func = matches!(
-                        fun,
-                        aggregate_function::AggregateFunction::Count // TODO
-                                                                     // | aggregate_function::AggregateFunction::Max
-                                                                     // | aggregate_function::AggregateFunction::Min
-                                                                     // | aggregate_function::AggregateFunction::Sum
-                    );
-
-                    support_agg_func
-                        && args.len() == 1
-                        && matches!(args[0], Expr::Column(_))
+        let result = if aggr_expr.iter().all(|e| match e {
+            Expr::AggregateFunction(AggregateFunction {
+                fun,
+                args,
+                distinct,
+                filter,
+            }) => match pushed_aggregate_column(fun, args) {
+                Some(column) => {
+                    self.supports_pushed_aggregate(fun, &column.name)
                         && !distinct
                         && filter.is_none()
                 }
-                _ => false,
-            }
+                None => false,
+            },
+            _ => false,
         }) {
             TableProviderAggregationPushDown::Ungrouped
         } else {
@@ -220,39 +248,35 @@ fn create_agg_filter_scan(
         .map(|agg| {
             let AggregateFunction { fun, args, .. } = agg;
 
-            args.iter()
-                .map(|expr| {
-                    // The parameter of the aggregate function pushed down must be a column column
-                    match expr {
-                        Expr::Column(c) => Ok(c),
-                        _ => Err(DataFusionError::Internal(format!(
-                            "Pushed aggregate functions's args contains non-column: {expr:?}."
-                        ))),
-                    }
-                })
-                .collect::<Result<Vec<_>>>()
-                .and_then(|columns| {
-                    // Convert pushdown aggregate functions to intermediate structures
-                    match fun {
-                        aggregate_function::AggregateFunction::Count => {
-                            let column = columns
-                                .first()
-                                .ok_or_else(|| {
-                                    DataFusionError::Internal(
-                                        "Pushed aggregate functions's args is none.".to_string(),
-                                    )
-                                })?
-                                .deref()
-                                .clone();
-                            Ok(PushedAggregateFunction::Count(column.name))
-                        }
-                        // aggregate_function::AggregateFunction::Max => {},
-                        // aggregate_function::AggregateFunction::Min => {},
-                        _ => Err(DataFusionError::Internal(
-                            "Pushed aggregate functions's args is none.".to_string(),
-                        )),
-                    }
-                })
+            let column = pushed_aggregate_column(fun, args)
+                .ok_or_else(|| {
+                    DataFusionError::Internal(format!(
+                        "Pushed aggregate functions's args is not a column: {args:?}."
+                    ))
+                })?
+                .name
+                .clone();
+            // Convert pushdown aggregate functions to intermediate structures
+            match (fun, args.as_slice()) {
+                (aggregate_function::AggregateFunction::Count, _) => {
+                    Ok(PushedAggregateFunction::Count(column))
+                }
+                (aggregate_function::AggregateFunction::Min, _) => {
+                    Ok(PushedAggregateFunction::Min(column))
+                }
+                (aggregate_function::AggregateFunction::Max, _) => {
+                    Ok(PushedAggregateFunction::Max(column))
+                }
+                (aggregate_function::AggregateFunction::Sum, [Expr::Cast(_)]) => {
+                    Ok(PushedAggregateFunction::FloatSum(column))
+                }
+                (aggregate_function::AggregateFunction::Sum, _) => {
+                    Ok(PushedAggregateFunction::Sum(column))
+                }
+                _ => Err(DataFusionError::Internal(format!(
+                    "Unsupported pushed aggregate function: {fun}."
+                ))),
+            }
         })
         .collect::<Result<Vec<_>>>()?;
 
@@ -265,16 +289,41 @@ fn create_agg_filter_scan(
     )))
 }
 
+/// Returns the column of a pushed aggregate function, whose argument is
+/// a column or the column casted to Float64 in `SUM(CAST(column AS Float64))`.
+fn pushed_aggregate_column<'a>(
+    fun: &aggregate_function::AggregateFunction,
+    args: &'a [Expr],
+) -> Option<&'a Column> {
+    match args {
+        [Expr::Column(column)] => Some(column),
+        [Expr::Cast(Cast {
+            expr,
+            data_type: DataType::Float64,
+        })] if *fun == aggregate_function::AggregateFunction::Sum => match expr.as_ref() {
+            Expr::Column(column) => Some(column),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
 #[async_trait]
 impl WriteExecExt for ClusterTable {
     async fn write(
         &self,
-        _state: &SessionState,
+        state: &SessionState,
         input: Arc<dyn ExecutionPlan>,
     ) -> Result<Arc<TableWriterExec>> {
+        let consistency_level = state
+            .config()
+            .get_extension::<ConsistencyLevel>()
+            .map(|e| *e)
+            .unwrap_or_default();
         let record_batch_sink_privider = Arc::new(TskvRecordBatchSinkProvider::new(
             self.coord.clone(),
             self.schema.clone(),
+            consistency_level,
         ));
 
         Ok(Arc::new(TableWriterExec::new(
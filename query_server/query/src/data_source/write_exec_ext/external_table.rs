@@ -1,6 +1,7 @@
 use std::sync::Arc;
 
 use async_trait::async_trait;
+use datafusion::datasource::file_format::avro::AvroFormat;
 use datafusion::datasource::file_format::csv::CsvFormat;
 use datafusion::datasource::file_format::json::JsonFormat;
 use datafusion::datasource::file_format::parquet::ParquetFormat;
@@ -13,6 +14,7 @@ use object_store::path::Path;
 use trace::debug;
 use url::Url;
 
+use crate::data_source::sink::obj_store::serializer::avro::AvroRecordBatchSerializer;
 use crate::data_source::sink::obj_store::serializer::csv::CsvRecordBatchSerializer;
 use crate::data_source::sink::obj_store::serializer::json::NdJsonRecordBatchSerializer;
 use crate::data_source::sink::obj_store::serializer::parquet::ParquetRecordBatchSerializer;
@@ -28,43 +30,56 @@ impl WriteExecExt for ListingTable {
         state: &SessionState,
         input: Arc<dyn ExecutionPlan>,
     ) -> Result<Arc<TableWriterExec>, DataFusionError> {
-        let table_paths = self.table_paths();
-        debug!("Try get ListingTable's ExecutionPlan: {:?}", table_paths);
-
-        let listing_table_url = unsafe {
-            debug_assert_eq!(
-                1,
-                table_paths.len(),
-                "ListingTable has multiple ListingTableUrl"
-            );
-            table_paths.get_unchecked(0)
-        };
-
-        let object_store_url = listing_table_url.object_store();
-        let url_str = listing_table_url.to_string();
-        let url: &Url = listing_table_url.as_ref();
-
-        debug!("Parse external location: {:?}", url.path());
-        let location = Path::parse(url.path())?;
-
-        debug!("Get object_store by url: {:?}", object_store_url);
-        let object_store = state.runtime_env().object_store(&object_store_url)?;
-        let serializer = get_record_batch_serializer(self.options().format.clone())?;
-        let file_extension = self.options().file_extension.clone();
+        write_listing_table(self, state, input, None)
+    }
+}
 
-        let record_batch_sink_provider = Arc::new(ObjectStoreSinkProvider::new(
-            location,
-            object_store,
-            serializer,
-            file_extension,
-        ));
+pub fn write_listing_table(
+    table: &ListingTable,
+    state: &SessionState,
+    input: Arc<dyn ExecutionPlan>,
+    max_file_size: Option<usize>,
+) -> Result<Arc<TableWriterExec>, DataFusionError> {
+    let table_paths = table.table_paths();
+    debug!("Try get ListingTable's ExecutionPlan: {:?}", table_paths);
+
+    let listing_table_url = unsafe {
+        debug_assert_eq!(
+            1,
+            table_paths.len(),
+            "ListingTable has multiple ListingTableUrl"
+        );
+        table_paths.get_unchecked(0)
+    };
 
-        Ok(Arc::new(TableWriterExec::new(
-            input,
-            url_str,
-            record_batch_sink_provider,
-        )))
-    }
+    let object_store_url = listing_table_url.object_store();
+    let url_str = listing_table_url.to_string();
+    let url: &Url = listing_table_url.as_ref();
+
+    debug!("Parse external location: {:?}", url.path());
+    let location = Path::parse(url.path())?;
+
+    debug!("Get object_store by url: {:?}", object_store_url);
+    let object_store = state.runtime_env().object_store(&object_store_url)?;
+    let serializer = get_record_batch_serializer(table.options().format.clone())?;
+    let file_extension = table.options().file_extension.clone();
+    let partition_columns = table
+        .options()
+        .table_partition_cols
+        .iter()
+        .map(|(name, _)| name.clone())
+        .collect();
+
+    let record_batch_sink_provider =
+        ObjectStoreSinkProvider::new(location, object_store, serializer, file_extension)
+            .with_partition_columns(partition_columns)
+            .with_max_file_size(max_file_size);
+
+    Ok(Arc::new(TableWriterExec::new(
+        input,
+        url_str,
+        Arc::new(record_batch_sink_provider),
+    )))
 }
 
 fn get_record_batch_serializer(
@@ -80,9 +95,11 @@ fn get_record_batch_serializer(
         )) as _
     } else if any.is::<JsonFormat>() {
         Arc::new(NdJsonRecordBatchSerializer {}) as _
+    } else if any.is::<AvroFormat>() {
+        Arc::new(AvroRecordBatchSerializer {}) as _
     } else {
         return Err(DataFusionError::NotImplemented(
-            "Only support ParquetFormat | CsvFormat | JsonFormat.".to_string(),
+            "Only support ParquetFormat | CsvFormat | JsonFormat | AvroFormat.".to_string(),
         ));
     };
 
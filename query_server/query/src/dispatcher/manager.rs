@@ -1,41 +1,56 @@
 use std::sync::Arc;
+use std::time::Duration;
 
 use async_trait::async_trait;
 use coordinator::service::CoordinatorRef;
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::sql::sqlparser::parser::ParserError;
 use memory_pool::MemoryPoolRef;
 use meta::error::MetaError;
+use meta::MetaClientRef;
 use models::oid::Oid;
 use models::schema::DEFAULT_CATALOG;
 use spi::query::ast::ExtStatement;
 use spi::query::dispatcher::{QueryDispatcher, QueryInfo, QueryStatus};
 use spi::query::execution::{Output, QueryExecutionFactory, QueryStateMachine};
-use spi::query::logical_planner::LogicalPlanner;
+use spi::query::logical_planner::{LogicalPlanner, Plan};
 use spi::query::optimizer::Optimizer;
 use spi::query::parser::Parser;
+use spi::query::prepared_statement::{PreparedStatement, PreparedStatementRef};
 use spi::query::scheduler::SchedulerRef;
-use spi::query::session::SessionCtxFactory;
+use spi::query::session::{SessionCtx, SessionCtxFactory};
 use spi::service::protocol::{Query, QueryId};
 use spi::{QueryError, Result};
+use trace::debug;
 
+use super::memory_manager::QueryMemoryManager;
 use super::query_tracker::QueryTracker;
+use super::workload_manager::WorkloadManager;
 use crate::execution::factory::SqlQueryExecutionFactory;
 use crate::extension::expr::load_all_functions;
 use crate::function::simple_func_manager::SimpleFunctionMetadataManager;
 use crate::metadata::{ContextProviderExtension, MetadataProvider};
 use crate::sql::logical::planner::DefaultLogicalPlanner;
+use crate::sql::prepared_statement::{
+    bind_parameters, is_plan_valid, normalize_placeholders, optimize_prepared_plan, prepare_plan,
+};
 
 #[derive(Clone)]
 pub struct SimpleQueryDispatcher {
     coord: CoordinatorRef,
     session_factory: Arc<SessionCtxFactory>,
-    // memory pool
-    memory_pool: MemoryPoolRef,
+    // memory pool: node -> tenant -> query
+    memory_manager: Arc<QueryMemoryManager>,
     // query tracker
     query_tracker: Arc<QueryTracker>,
+    // concurrency slots and query queues of tenants
+    workload_manager: Arc<WorkloadManager>,
     // parser
     parser: Arc<dyn Parser + Send + Sync>,
     // get query execution factory
     query_execution_factory: Arc<dyn QueryExecutionFactory + Send + Sync>,
+    // optimize the plans of prepared statements once for all the executions
+    optimizer: Arc<dyn Optimizer + Send + Sync>,
 }
 
 #[async_trait]
@@ -62,12 +77,194 @@ impl QueryDispatcher for SimpleQueryDispatcher {
         query_id: QueryId,
         query: &Query,
     ) -> Result<Output> {
-        let session = self.session_factory.create_session_ctx(
+        let stmt = match self.parse_single_statement(query)? {
+            Some(stmt) => stmt,
+            None => return Ok(Output::Nil(())),
+        };
+
+        self.execute_ext_statement(tenant_id, query_id, query, stmt)
+            .await
+    }
+
+    async fn prepare_query(
+        &self,
+        tenant_id: Oid,
+        query_id: QueryId,
+        query: &Query,
+    ) -> Result<PreparedStatementRef> {
+        let query = Query::new(
             query.context().clone(),
-            tenant_id,
-            self.memory_pool.clone(),
-        )?;
+            normalize_placeholders(query.content()),
+        );
+        let stmt = self.parse_single_statement(&query)?.ok_or_else(|| {
+            QueryError::from(ParserError::ParserError(
+                "No statement to prepare".to_string(),
+            ))
+        })?;
+
+        let ctx = self.statement_context(tenant_id, query_id, &query).await?;
+        let logical_planner = DefaultLogicalPlanner::new(&ctx.scheme_provider);
+        let plan = logical_planner
+            .create_logical_plan(stmt.clone(), &ctx.session)
+            .await?;
+
+        let prepared = prepare_plan(plan, &ctx.meta_client, ctx.session.default_database())?;
+
+        Ok(Arc::new(PreparedStatement::new(stmt, prepared)))
+    }
+
+    async fn execute_prepared_query(
+        &self,
+        tenant_id: Oid,
+        query_id: QueryId,
+        query: &Query,
+        statement: &PreparedStatement,
+        parameters: Vec<RecordBatch>,
+    ) -> Result<Output> {
+        let ctx = self.statement_context(tenant_id, query_id, query).await?;
+
+        let mut prepared = statement.plan();
+        // The plan is out of date once the tables it depends on are altered or dropped
+        if !is_plan_valid(&prepared, &ctx.meta_client)? {
+            let logical_planner = DefaultLogicalPlanner::new(&ctx.scheme_provider);
+            let plan = logical_planner
+                .create_logical_plan(statement.statement().clone(), &ctx.session)
+                .await?;
+            prepared = prepare_plan(plan, &ctx.meta_client, ctx.session.default_database())?;
+            statement.replace_plan(prepared.clone());
+        }
+
+        // The parameters are bound to the plan optimized once for the time zone
+        let time_zone = *ctx.session.time_zone();
+        let prepared = match statement.optimized_plan(&time_zone) {
+            Some(optimized) => optimized,
+            None => {
+                match optimize_prepared_plan(&prepared, self.optimizer.as_ref(), &ctx.session) {
+                    Ok(Some(optimized)) => {
+                        statement.cache_optimized_plan(&prepared, time_zone, optimized.clone());
+                        optimized
+                    }
+                    // Optimized by each execution with the values of the parameters
+                    Ok(None) => prepared,
+                    Err(err) => {
+                        debug!("Failed to optimize the prepared plan: {}", err);
+                        prepared
+                    }
+                }
+            }
+        };
+
+        let mut schema = None;
+        let mut batches = vec![];
+        for (i, plan) in bind_parameters(&prepared, &parameters)?
+            .into_iter()
+            .enumerate()
+        {
+            // The plan of each parameter row is a query of its own,
+            // which is tracked, admitted and limited in memory separately
+            let (query_id, session) = if i == 0 {
+                (query_id, ctx.session.clone())
+            } else {
+                let query_id = self.create_query_id();
+                let session = self.create_session(tenant_id, query_id, query, &ctx.meta_client)?;
+                (query_id, session)
+            };
+            let query_state_machine = Arc::new(QueryStateMachine::begin(
+                query_id,
+                query.clone(),
+                session,
+                self.coord.clone(),
+            ));
+            if let Output::StreamData(s, b) = self
+                .execute_plan(plan, query_state_machine, ctx.max_concurrent_queries)
+                .await?
+            {
+                schema.get_or_insert(s);
+                batches.extend(b);
+            }
+        }
+
+        match schema {
+            Some(schema) => Ok(Output::StreamData(schema, batches)),
+            None => Ok(Output::Nil(())),
+        }
+    }
+
+    fn running_query_infos(&self) -> Vec<QueryInfo> {
+        self.query_tracker
+            .running_queries()
+            .iter()
+            .map(|e| e.info())
+            .collect()
+    }
+
+    fn running_query_status(&self) -> Vec<QueryStatus> {
+        self.query_tracker
+            .running_queries()
+            .iter()
+            .map(|e| e.status())
+            .collect()
+    }
+
+    fn cancel_query(&self, id: &QueryId) {
+        self.query_tracker.query(id).map(|e| e.cancel());
+    }
+}
+
+impl SimpleQueryDispatcher {
+    /// Execute a statement that has been parsed, e.g. the statement of a pipe,
+    /// the content of the query is only used to display the query
+    pub async fn execute_ext_statement(
+        &self,
+        tenant_id: Oid,
+        query_id: QueryId,
+        query: &Query,
+        stmt: ExtStatement,
+    ) -> Result<Output> {
+        let ctx = self.statement_context(tenant_id, query_id, query).await?;
+
+        let logical_planner = DefaultLogicalPlanner::new(&ctx.scheme_provider);
+
+        let query_state_machine = Arc::new(QueryStateMachine::begin(
+            query_id,
+            query.clone(),
+            ctx.session.clone(),
+            self.coord.clone(),
+        ));
+
+        let result = self
+            .execute_statement(
+                stmt,
+                &logical_planner,
+                query_state_machine,
+                ctx.max_concurrent_queries,
+            )
+            .await?;
+
+        Ok(result)
+    }
+
+    /// Parse the query, which has one statement at most
+    fn parse_single_statement(&self, query: &Query) -> Result<Option<ExtStatement>> {
+        let statements = self.parser.parse(query.content())?;
+
+        // not allow multi statement
+        if statements.len() > 1 {
+            return Err(QueryError::MultiStatement {
+                num: statements.len(),
+                sql: query.content().to_string(),
+            });
+        }
 
+        Ok(statements.front().cloned())
+    }
+
+    async fn statement_context(
+        &self,
+        tenant_id: Oid,
+        query_id: QueryId,
+        query: &Query,
+    ) -> Result<StatementContext> {
         let meta_client = self
             .coord
             .meta_manager()
@@ -78,6 +275,8 @@ impl QueryDispatcher for SimpleQueryDispatcher {
                 tenant: query.context().tenant().to_string(),
             })?;
 
+        let session = self.create_session(tenant_id, query_id, query, &meta_client)?;
+
         let mut func_manager = SimpleFunctionMetadataManager::default();
         load_all_functions(&mut func_manager)?;
 
@@ -91,71 +290,55 @@ impl QueryDispatcher for SimpleQueryDispatcher {
 
         let scheme_provider = MetadataProvider::new(
             self.coord.clone(),
-            meta_client,
+            meta_client.clone(),
             func_manager,
             self.query_tracker.clone(),
             session.clone(),
             default_catalog_meta_client,
         );
 
-        let logical_planner = DefaultLogicalPlanner::new(&scheme_provider);
-
-        let statements = self.parser.parse(query.content())?;
-
-        // not allow multi statement
-        if statements.len() > 1 {
-            return Err(QueryError::MultiStatement {
-                num: statements.len(),
-                sql: query.content().to_string(),
-            });
-        }
-
-        let stmt = match statements.front() {
-            Some(stmt) => stmt.clone(),
-            None => return Ok(Output::Nil(())),
-        };
-
-        let query_state_machine = Arc::new(QueryStateMachine::begin(
-            query_id,
-            query.clone(),
-            session.clone(),
-            self.coord.clone(),
-        ));
+        let max_concurrent_queries = meta_client.tenant().options().max_concurrent_queries;
 
-        let result = self
-            .execute_statement(stmt, &logical_planner, query_state_machine)
-            .await?;
-
-        Ok(result)
-    }
-
-    fn running_query_infos(&self) -> Vec<QueryInfo> {
-        self.query_tracker
-            .running_queries()
-            .iter()
-            .map(|e| e.info())
-            .collect()
+        Ok(StatementContext {
+            session,
+            meta_client,
+            scheme_provider,
+            max_concurrent_queries,
+        })
     }
 
-    fn running_query_status(&self) -> Vec<QueryStatus> {
-        self.query_tracker
-            .running_queries()
-            .iter()
-            .map(|e| e.status())
-            .collect()
-    }
+    /// Create the session of a query with the memory pool of its own
+    fn create_session(
+        &self,
+        tenant_id: Oid,
+        query_id: QueryId,
+        query: &Query,
+        meta_client: &MetaClientRef,
+    ) -> Result<SessionCtx> {
+        let tenant_memory_pool = self.memory_manager.tenant_pool(
+            tenant_id,
+            query.context().tenant(),
+            meta_client.tenant().options().query_memory_limit,
+        );
+        let query_memory_pool = self.memory_manager.create_query_pool(
+            tenant_memory_pool,
+            query_id,
+            query.context().session_config().query_memory_limit(),
+        );
 
-    fn cancel_query(&self, id: &QueryId) {
-        self.query_tracker.query(id).map(|e| e.cancel());
+        self.session_factory.create_session_ctx(
+            query.context().clone(),
+            tenant_id,
+            query_memory_pool,
+        )
     }
-}
 
-impl SimpleQueryDispatcher {
     async fn execute_statement<S: ContextProviderExtension + Send + Sync>(
         &self,
         stmt: ExtStatement,
         logical_planner: &DefaultLogicalPlanner<'_, S>,
         query_state_machine: Arc<QueryStateMachine>,
+        max_concurrent_queries: Option<u64>,
     ) -> Result<Output> {
         // begin analyze
         query_state_machine.begin_analyze();
@@ -164,18 +347,69 @@ impl SimpleQueryDispatcher {
             .await?;
         query_state_machine.end_analyze();
 
+        self.execute_plan(logical_plan, query_state_machine, max_concurrent_queries)
+            .await
+    }
+
+    async fn execute_plan(
+        &self,
+        logical_plan: Plan,
+        query_state_machine: Arc<QueryStateMachine>,
+        max_concurrent_queries: Option<u64>,
+    ) -> Result<Output> {
+        // DDL and system statements (e.g. KILL QUERY) are never queued
+        let need_admission = matches!(logical_plan, Plan::Query(_) | Plan::CopyIntoTable(_));
+
         let execution = self
             .query_execution_factory
             .create_query_execution(logical_plan, query_state_machine.clone());
 
+        let session_config = query_state_machine.query.context().session_config();
+
+        // The permit is released when the query is finished
+        let _permit = if need_admission {
+            query_state_machine.begin_queue();
+            let permit = self
+                .workload_manager
+                .acquire(
+                    *query_state_machine.session.tenant_id(),
+                    query_state_machine.session.tenant(),
+                    max_concurrent_queries,
+                    session_config.priority(),
+                )
+                .await?;
+            Some(permit)
+        } else {
+            None
+        };
+
+        // Tracked once admitted, so the queued queries do not take the slots of the tracker.
         // TrackedQuery.drop() is called implicitly when the value goes out of scope,
-        self.query_tracker
-            .try_track_query(query_state_machine.query_id, execution)?
-            .start()
-            .await
+        let tracked_query = self
+            .query_tracker
+            .try_track_query(query_state_machine.query_id, execution)?;
+
+        match session_config.query_timeout() {
+            Some(timeout) => match tokio::time::timeout(timeout, tracked_query.start()).await {
+                Ok(result) => result,
+                Err(_) => {
+                    let _ = tracked_query.cancel();
+                    Err(QueryError::QueryTimeout { timeout })
+                }
+            },
+            None => tracked_query.start().await,
+        }
     }
 }
 
+/// What the statements of a query are planned and executed with
+struct StatementContext {
+    session: SessionCtx,
+    meta_client: MetaClientRef,
+    scheme_provider: MetadataProvider,
+    max_concurrent_queries: Option<u64>,
+}
+
 #[derive(Default, Clone)]
 pub struct SimpleQueryDispatcherBuilder {
     coord: Option<CoordinatorRef>,
@@ -186,6 +420,8 @@ pub struct SimpleQueryDispatcherBuilder {
     scheduler: Option<SchedulerRef>,
 
     queries_limit: usize,
+    query_queue_size: usize,
+    query_queue_timeout: Duration,
     memory_pool: Option<MemoryPoolRef>, // memory
 }
 
@@ -220,6 +456,13 @@ impl SimpleQueryDispatcherBuilder {
         self
     }
 
+    /// Set the maximum number of queued queries per tenant and the maximum waiting time
+    pub fn with_query_queue(mut self, size: u32, timeout: Duration) -> Self {
+        self.query_queue_size = size as usize;
+        self.query_queue_timeout = timeout;
+        self
+    }
+
     pub fn with_memory_pool(mut self, memory_pool: MemoryPoolRef) -> Self {
         self.memory_pool = Some(memory_pool);
         self
@@ -256,7 +499,7 @@ impl SimpleQueryDispatcherBuilder {
         let query_tracker = Arc::new(QueryTracker::new(self.queries_limit));
 
         let query_execution_factory = Arc::new(SqlQueryExecutionFactory::new(
-            optimizer,
+            optimizer.clone(),
             scheduler,
             query_tracker.clone(),
         ));
@@ -265,13 +508,21 @@ impl SimpleQueryDispatcherBuilder {
             .ok_or_else(|| QueryError::BuildQueryDispatcher {
                 err: "lost of memory pool".to_string(),
             })?;
+        let memory_manager = Arc::new(QueryMemoryManager::new(memory_pool));
+        let workload_manager = Arc::new(WorkloadManager::new(
+            self.query_queue_size,
+            self.query_queue_timeout,
+        ));
+
         Ok(SimpleQueryDispatcher {
             coord,
             session_factory,
-            memory_pool,
+            memory_manager,
             parser,
             query_execution_factory,
             query_tracker,
+            workload_manager,
+            optimizer,
         })
     }
 }
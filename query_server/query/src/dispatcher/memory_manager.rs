@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use memory_pool::{HierarchicalMemoryPool, MemoryPool, MemoryPoolRef};
+use models::oid::Oid;
+use parking_lot::RwLock;
+use spi::service::protocol::QueryId;
+
+/// Maintain the memory pool hierarchy of queries: node -> tenant -> query.
+///
+/// All queries of a tenant share the tenant pool, so one huge query can
+/// neither exceed the tenant limit nor take over the memory of the whole node.
+pub struct QueryMemoryManager {
+    node_pool: MemoryPoolRef,
+    tenant_pools: RwLock<HashMap<Oid, Arc<HierarchicalMemoryPool>>>,
+}
+
+impl QueryMemoryManager {
+    pub fn new(node_pool: MemoryPoolRef) -> Self {
+        Self {
+            node_pool,
+            tenant_pools: Default::default(),
+        }
+    }
+
+    /// Get the memory pool of the tenant, create it if not exists.
+    ///
+    /// The limit of the pool is refreshed by `limit`, `None` means unlimited.
+    /// Pools of the tenants without running queries are dropped when a new pool is created,
+    /// so the pools of deleted tenants are not kept forever.
+    pub fn tenant_pool(
+        &self,
+        tenant_id: Oid,
+        tenant: &str,
+        limit: Option<u64>,
+    ) -> Arc<HierarchicalMemoryPool> {
+        let limit = limit.map(|e| e as usize).unwrap_or(usize::MAX);
+
+        if let Some(pool) = self.tenant_pools.read().get(&tenant_id) {
+            pool.set_limit(limit);
+            return pool.clone();
+        }
+
+        let mut tenant_pools = self.tenant_pools.write();
+        tenant_pools.retain(|id, pool| *id == tenant_id || !Self::is_idle(pool));
+        tenant_pools
+            .entry(tenant_id)
+            .or_insert_with(|| {
+                Arc::new(HierarchicalMemoryPool::with_parent(
+                    format!("tenant_{}", tenant),
+                    limit,
+                    self.node_pool.clone(),
+                ))
+            })
+            .clone()
+    }
+
+    /// The pool is only referenced by the manager and holds no memory
+    fn is_idle(pool: &Arc<HierarchicalMemoryPool>) -> bool {
+        Arc::strong_count(pool) == 1 && pool.reserved() == 0
+    }
+
+    /// Create the memory pool of a query, `None` means only limited by the tenant
+    pub fn create_query_pool(
+        &self,
+        tenant_pool: Arc<HierarchicalMemoryPool>,
+        query_id: QueryId,
+        limit: Option<usize>,
+    ) -> MemoryPoolRef {
+        Arc::new(HierarchicalMemoryPool::with_parent(
+            format!("query_{}", query_id.to_string()),
+            limit.unwrap_or(usize::MAX),
+            tenant_pool,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use memory_pool::{GreedyMemoryPool, MemoryConsumer, MemoryPool, MemoryPoolRef};
+    use spi::service::protocol::QueryId;
+
+    use super::QueryMemoryManager;
+
+    #[test]
+    fn test_query_memory_limit() {
+        let node_pool: MemoryPoolRef = Arc::new(GreedyMemoryPool::new(1000));
+        let manager = QueryMemoryManager::new(node_pool.clone());
+
+        let tenant_pool = manager.tenant_pool(1, "tenant", Some(100));
+        let query_pool = manager.create_query_pool(tenant_pool.clone(), QueryId::next_id(), None);
+        let mut r1 = MemoryConsumer::new("r1").register(&query_pool);
+        r1.try_grow(80).unwrap();
+
+        let query_pool = manager.create_query_pool(tenant_pool.clone(), QueryId::next_id(), None);
+        let mut r2 = MemoryConsumer::new("r2").register(&query_pool);
+        // tenant limit exceeded
+        r2.try_grow(30).unwrap_err();
+
+        // limit refreshed by the latest tenant options
+        let tenant_pool = manager.tenant_pool(1, "tenant", Some(200));
+        assert_eq!(tenant_pool.limit(), 200);
+        r2.try_grow(30).unwrap();
+        assert_eq!(tenant_pool.reserved(), 110);
+        assert_eq!(node_pool.reserved(), 110);
+
+        let query_pool = manager.create_query_pool(tenant_pool, QueryId::next_id(), Some(10));
+        let mut r3 = MemoryConsumer::new("r3").register(&query_pool);
+        // query limit exceeded
+        r3.try_grow(20).unwrap_err();
+    }
+
+    #[test]
+    fn test_prune_idle_tenant_pools() {
+        let node_pool: MemoryPoolRef = Arc::new(GreedyMemoryPool::new(1000));
+        let manager = QueryMemoryManager::new(node_pool);
+
+        let tenant_pool = manager.tenant_pool(1, "t1", None);
+        let query_pool = manager.create_query_pool(tenant_pool, QueryId::next_id(), None);
+        drop(manager.tenant_pool(2, "t2", None));
+
+        // pool of t2 is idle, pool of t1 is used by a running query
+        drop(manager.tenant_pool(3, "t3", None));
+        let mut tenants = manager
+            .tenant_pools
+            .read()
+            .keys()
+            .copied()
+            .collect::<Vec<_>>();
+        tenants.sort_unstable();
+        assert_eq!(tenants, vec![1, 3]);
+
+        drop(query_pool);
+        drop(manager.tenant_pool(4, "t4", None));
+        let mut tenants = manager
+            .tenant_pools
+            .read()
+            .keys()
+            .copied()
+            .collect::<Vec<_>>();
+        tenants.sort_unstable();
+        assert_eq!(tenants, vec![4]);
+    }
+}
@@ -1,2 +1,5 @@
 pub mod manager;
+pub mod memory_manager;
+pub mod pipe_manager;
 pub mod query_tracker;
+pub mod workload_manager;
@@ -0,0 +1,562 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use coordinator::service::CoordinatorRef;
+use datafusion::arrow::array::{Array, StringArray, UInt64Array};
+use meta::error::{MetaError, MetaResult};
+use meta::MetaClientRef;
+use models::meta_data::{PipeInfo, PipeStatus};
+use models::oid::Identifier;
+use spi::query::ast::{self, ExtStatement};
+use spi::query::execution::Output;
+use spi::query::parser::Parser;
+use spi::service::protocol::{ContextBuilder, Query, QueryId};
+use spi::{QueryError, Result};
+use tokio::sync::mpsc::{self, UnboundedSender};
+use trace::{debug, info, warn};
+
+use super::manager::SimpleQueryDispatcher;
+use crate::execution::copy::{LOADED, LOAD_FAILED, PARTIALLY_LOADED};
+use crate::sql::parser::DefaultParser;
+
+/// How often the pipes are checked for a due load
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+/// How often the pipes of this node are reloaded from meta
+const REFRESH_INTERVAL: Duration = Duration::from_secs(10);
+/// Duration of the lease of a pipe (or 3 intervals if longer), a pipe not loaded
+/// by its node for this long is taken over by another node
+const LEASE_DURATION: Duration = Duration::from_secs(60);
+/// Maximum number of pipes loading at the same time on this node
+const MAX_CONCURRENT_LOADS: usize = 4;
+
+type PipeKey = (String, String);
+
+struct ScheduledPipe {
+    tenant: String,
+    pipe: PipeInfo,
+    next_load: Instant,
+    loading: bool,
+}
+
+struct LoadResult {
+    key: PipeKey,
+    create_time: i64,
+    status: PipeStatus,
+    updated: MetaResult<()>,
+}
+
+/// Runs the pipes of this node.
+///
+/// Every pipe periodically executes the COPY INTO statement of its definition,
+/// files that have already been loaded are skipped by the load history of the table.
+///
+/// A pipe is loaded by the node holding its lease in meta. The lease is renewed when
+/// a load starts and periodically while it runs, the load is cancelled once the lease
+/// can't be renewed. The lease expires if the node stops loading the pipe, e.g. the node
+/// is stopped, then the first node noticing it takes the pipe over. The take over only
+/// succeeds if the lease has not been renewed since it was seen expired, so a pipe is
+/// loaded by one node at a time, as long as the clocks of the nodes are synchronized.
+///
+/// Files are loaded at least once, not exactly once: the history of a file is recorded
+/// after its points are written, the file is loaded again by the next load if the node
+/// fails or the load is cancelled in between.
+///
+/// Pipes are loaded concurrently, a pipe has at most one load running on a node.
+pub struct PipeManager {
+    runner: Arc<PipeRunner>,
+    pipes: HashMap<PipeKey, ScheduledPipe>,
+    last_refresh: Option<Instant>,
+    running: usize,
+}
+
+impl PipeManager {
+    pub fn new(coord: CoordinatorRef, dispatcher: SimpleQueryDispatcher) -> Self {
+        Self {
+            runner: Arc::new(PipeRunner {
+                coord,
+                dispatcher,
+                parser: DefaultParser::default(),
+            }),
+            pipes: HashMap::new(),
+            last_refresh: None,
+            running: 0,
+        }
+    }
+
+    pub fn start(mut self) {
+        tokio::spawn(async move {
+            let (sender, mut receiver) = mpsc::unbounded_channel();
+            let mut ticker = tokio::time::interval(CHECK_INTERVAL);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => self.load_due_pipes(&sender).await,
+                    Some(result) = receiver.recv() => self.finish_load(result),
+                }
+            }
+        });
+    }
+
+    async fn load_due_pipes(&mut self, sender: &UnboundedSender<LoadResult>) {
+        let now = Instant::now();
+        if self
+            .last_refresh
+            .map_or(true, |t| now.duration_since(t) >= REFRESH_INTERVAL)
+        {
+            if let Err(err) = self.refresh().await {
+                warn!("Failed to refresh pipes: {}", err);
+            }
+            self.last_refresh = Some(now);
+        }
+
+        let mut due = self
+            .pipes
+            .iter()
+            .filter(|(_, e)| !e.loading && e.next_load <= now)
+            .map(|(k, e)| (e.next_load, k.clone()))
+            .collect::<Vec<_>>();
+        due.sort();
+
+        let available = MAX_CONCURRENT_LOADS.saturating_sub(self.running);
+        for (_, key) in due.into_iter().take(available) {
+            let (tenant, pipe) = match self.pipes.get_mut(&key) {
+                Some(e) => {
+                    e.loading = true;
+                    (e.tenant.clone(), e.pipe.clone())
+                }
+                None => continue,
+            };
+            self.running += 1;
+
+            let runner = self.runner.clone();
+            let sender = sender.clone();
+            tokio::spawn(async move {
+                let (status, updated) = runner.load_pipe(&tenant, &pipe).await;
+                let _ = sender.send(LoadResult {
+                    key,
+                    create_time: pipe.create_time,
+                    status,
+                    updated,
+                });
+            });
+        }
+    }
+
+    fn finish_load(&mut self, result: LoadResult) {
+        self.running -= 1;
+
+        let LoadResult {
+            key,
+            create_time,
+            status,
+            updated,
+        } = result;
+        let pipe = match self.pipes.get_mut(&key) {
+            // Ignore the load of a pipe that has been recreated
+            Some(e) if e.pipe.create_time == create_time => e,
+            _ => return,
+        };
+        pipe.loading = false;
+        pipe.next_load = Instant::now() + Duration::from_millis(pipe.pipe.interval);
+
+        match updated {
+            Ok(_) => pipe.pipe.status = status,
+            Err(MetaError::PipeNotFound { .. }) | Err(MetaError::TenantNotFound { .. }) => {
+                // The pipe has been dropped
+                self.pipes.remove(&key);
+            }
+            Err(MetaError::PipeLeaseLost { .. }) => {
+                info!("Pipe {} has been taken over by another node", key.1);
+                self.pipes.remove(&key);
+            }
+            Err(err) => {
+                warn!("Failed to update status of pipe {}: {}", key.1, err);
+            }
+        }
+    }
+
+    /// Reload the pipes run by this node from meta, taking over the ones whose lease expired
+    async fn refresh(&mut self) -> Result<()> {
+        let node_id = self.runner.coord.node_id();
+        let tenant_manager = self.runner.coord.meta_manager().tenant_manager();
+        let now = chrono::Utc::now().timestamp_nanos();
+
+        let mut pipes = HashMap::new();
+        for tenant in tenant_manager.tenants().await? {
+            let tenant_name = tenant.name().to_string();
+            let meta = match tenant_manager.tenant_meta(&tenant_name).await {
+                Some(meta) => meta,
+                None => continue,
+            };
+
+            for mut pipe in meta.pipes().await? {
+                let key = (tenant_name.clone(), pipe.name.clone());
+                let scheduled = self
+                    .pipes
+                    .remove(&key)
+                    .filter(|e| e.pipe.create_time == pipe.create_time);
+
+                if pipe.node_id != node_id {
+                    // A running load of this node counts as a load of the pipe
+                    if scheduled.map_or(false, |e| e.loading) || !is_lease_expired(&pipe, now) {
+                        continue;
+                    }
+                    let lease_expire_time = now + lease_duration(&pipe).as_nanos() as i64;
+                    match meta
+                        .take_over_pipe(
+                            &pipe.name,
+                            (pipe.node_id, pipe.lease_expire_time),
+                            node_id,
+                            lease_expire_time,
+                        )
+                        .await
+                    {
+                        Ok(true) => {
+                            info!(
+                                "Take over pipe {} of tenant {} from node {}",
+                                pipe.name, tenant_name, pipe.node_id
+                            );
+                            pipe.node_id = node_id;
+                            pipe.lease_expire_time = lease_expire_time;
+                        }
+                        Ok(false) => continue,
+                        Err(err) => {
+                            warn!("Failed to take over pipe {}: {}", pipe.name, err);
+                            continue;
+                        }
+                    }
+                    pipes.insert(
+                        key,
+                        ScheduledPipe {
+                            tenant: tenant_name.clone(),
+                            pipe,
+                            next_load: Instant::now(),
+                            loading: false,
+                        },
+                    );
+                    continue;
+                }
+
+                // Keep the schedule of a pipe unless it has been recreated
+                let (next_load, loading) = match scheduled {
+                    Some(e) => (e.next_load, e.loading),
+                    None => (Instant::now(), false),
+                };
+                pipes.insert(
+                    key,
+                    ScheduledPipe {
+                        tenant: tenant_name.clone(),
+                        pipe,
+                        next_load,
+                        loading,
+                    },
+                );
+            }
+        }
+
+        self.pipes = pipes;
+        Ok(())
+    }
+}
+
+fn lease_duration(pipe: &PipeInfo) -> Duration {
+    LEASE_DURATION.max(Duration::from_millis(pipe.interval) * 3)
+}
+
+/// Returns true if the lease of the pipe expired, so its node is likely stopped.
+fn is_lease_expired(pipe: &PipeInfo, now: i64) -> bool {
+    let lease_expire_time = if pipe.lease_expire_time > 0 {
+        pipe.lease_expire_time
+    } else {
+        // Not leased yet, it's leased since the creation or the last load
+        let leased_time = pipe.status.last_load_time.unwrap_or(pipe.create_time);
+        leased_time + lease_duration(pipe).as_nanos() as i64
+    };
+    now > lease_expire_time
+}
+
+struct PipeRunner {
+    coord: CoordinatorRef,
+    dispatcher: SimpleQueryDispatcher,
+    parser: DefaultParser,
+}
+
+impl PipeRunner {
+    /// Load the pipe once while holding its lease and save its status to meta
+    async fn load_pipe(&self, tenant: &str, pipe: &PipeInfo) -> (PipeStatus, MetaResult<()>) {
+        let meta = match self
+            .coord
+            .meta_manager()
+            .tenant_manager()
+            .tenant_meta(tenant)
+            .await
+        {
+            Some(meta) => meta,
+            None => {
+                let err = MetaError::TenantNotFound {
+                    tenant: tenant.to_string(),
+                };
+                return (pipe.status.clone(), Err(err));
+            }
+        };
+        let lease_expire_time = match self.renew_lease(&meta, pipe).await {
+            Ok(lease_expire_time) => lease_expire_time,
+            Err(err) => return (pipe.status.clone(), Err(err)),
+        };
+
+        debug!("Load pipe {} of tenant {}", pipe.name, tenant);
+        let mut status = pipe.status.clone();
+        status.last_load_time = Some(chrono::Utc::now().timestamp_nanos());
+        let output = tokio::select! {
+            output = self.execute_pipe(tenant, pipe) => output,
+            // The load is cancelled, the files loaded so far are recorded in the history
+            err = self.keep_lease(&meta, pipe, lease_expire_time) => {
+                warn!("Cancel the load of pipe {} of tenant {}: {}", pipe.name, tenant, err);
+                return (pipe.status.clone(), Err(err));
+            }
+        };
+        let last_error = match output {
+            Ok(output) => accumulate_status(&mut status, &output),
+            Err(err) => Some(err.to_string()),
+        };
+        status.last_error = last_error;
+
+        if let Some(err) = &status.last_error {
+            info!("Pipe {} of tenant {} failed: {}", pipe.name, tenant, err);
+        }
+
+        let updated = meta.update_pipe_status(&pipe.name, status.clone()).await;
+        (status, updated)
+    }
+
+    /// Extend the lease of the pipe held by this node, returns the new expire time
+    async fn renew_lease(&self, meta: &MetaClientRef, pipe: &PipeInfo) -> MetaResult<i64> {
+        let now = chrono::Utc::now().timestamp_nanos();
+        let lease_expire_time = now + lease_duration(pipe).as_nanos() as i64;
+        if meta
+            .renew_pipe_lease(&pipe.name, self.coord.node_id(), lease_expire_time)
+            .await?
+        {
+            Ok(lease_expire_time)
+        } else {
+            Err(MetaError::PipeLeaseLost {
+                pipe: pipe.name.clone(),
+            })
+        }
+    }
+
+    /// Renew the lease of the pipe periodically while it's loading,
+    /// returns once the lease is lost or about to expire without being renewed.
+    async fn keep_lease(
+        &self,
+        meta: &MetaClientRef,
+        pipe: &PipeInfo,
+        mut lease_expire_time: i64,
+    ) -> MetaError {
+        let renew_interval = lease_duration(pipe) / 3;
+        loop {
+            tokio::time::sleep(renew_interval).await;
+            match self.renew_lease(meta, pipe).await {
+                Ok(expire_time) => lease_expire_time = expire_time,
+                Err(err @ MetaError::PipeLeaseLost { .. })
+                | Err(err @ MetaError::PipeNotFound { .. }) => return err,
+                Err(err) => {
+                    warn!("Failed to renew the lease of pipe {}: {}", pipe.name, err);
+                    let next_renew_time =
+                        chrono::Utc::now().timestamp_nanos() + renew_interval.as_nanos() as i64;
+                    if next_renew_time >= lease_expire_time {
+                        return err;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Execute the COPY INTO statement of the pipe as the owner of the pipe
+    async fn execute_pipe(&self, tenant: &str, pipe: &PipeInfo) -> Result<Output> {
+        let meta_manager = self.coord.meta_manager();
+        let user = meta_manager
+            .user_with_privileges(&pipe.owner, Some(tenant))
+            .await?;
+        let tenant_id = *meta_manager
+            .tenant_manager()
+            .tenant_meta(tenant)
+            .await
+            .ok_or_else(|| MetaError::TenantNotFound {
+                tenant: tenant.to_string(),
+            })?
+            .tenant()
+            .id();
+
+        let mut statements = self.parser.parse(&pipe.definition)?;
+        let stmt = match statements.pop_front() {
+            Some(ExtStatement::CreatePipe(ast::CreatePipe { copy, .. })) => {
+                ExtStatement::Copy(copy)
+            }
+            _ => {
+                return Err(QueryError::Internal {
+                    reason: format!("invalid definition of pipe {}", pipe.name),
+                })
+            }
+        };
+
+        let context = ContextBuilder::new(user)
+            .with_tenant(Some(tenant.to_string()))
+            .with_database(Some(pipe.database.clone()))
+            .build();
+        let query = Query::new(context, pipe.definition.clone());
+
+        self.dispatcher
+            .execute_ext_statement(tenant_id, QueryId::next_id(), &query, stmt)
+            .await
+    }
+}
+
+/// Add the result of a COPY INTO to the status of the pipe,
+/// returns the first error of the files failed to load
+fn accumulate_status(status: &mut PipeStatus, output: &Output) -> Option<String> {
+    let schema = output.schema();
+    let (file_idx, status_idx, rows_idx, error_idx) = match (
+        schema.index_of("file"),
+        schema.index_of("status"),
+        schema.index_of("rows_loaded"),
+        schema.index_of("first_error"),
+    ) {
+        (Ok(a), Ok(b), Ok(c), Ok(d)) => (a, b, c, d),
+        _ => return None,
+    };
+
+    let mut first_error = None;
+    for batch in output.chunk_result() {
+        let files = batch
+            .column(file_idx)
+            .as_any()
+            .downcast_ref::<StringArray>();
+        let states = batch
+            .column(status_idx)
+            .as_any()
+            .downcast_ref::<StringArray>();
+        let rows = batch
+            .column(rows_idx)
+            .as_any()
+            .downcast_ref::<UInt64Array>();
+        let errors = batch
+            .column(error_idx)
+            .as_any()
+            .downcast_ref::<StringArray>();
+        let (files, states, rows, errors) = match (files, states, rows, errors) {
+            (Some(a), Some(b), Some(c), Some(d)) => (a, b, c, d),
+            _ => continue,
+        };
+
+        for i in 0..batch.num_rows() {
+            match states.value(i) {
+                LOADED | PARTIALLY_LOADED => status.files_loaded += 1,
+                LOAD_FAILED => {
+                    status.files_failed += 1;
+                    if first_error.is_none() && !errors.is_null(i) {
+                        first_error = Some(format!("{}: {}", files.value(i), errors.value(i)));
+                    }
+                }
+                _ => {}
+            }
+            status.rows_loaded += rows.value(i);
+        }
+    }
+
+    first_error
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use datafusion::arrow::datatypes::{DataType, Field, Schema};
+    use datafusion::arrow::record_batch::RecordBatch;
+
+    use super::*;
+    use crate::execution::copy::SKIPPED;
+
+    #[test]
+    fn test_accumulate_status() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("file", DataType::Utf8, false),
+            Field::new("status", DataType::Utf8, false),
+            Field::new("rows_loaded", DataType::UInt64, false),
+            Field::new("rows_rejected", DataType::UInt64, false),
+            Field::new("rows_skipped", DataType::UInt64, false),
+            Field::new("first_error", DataType::Utf8, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(StringArray::from(vec!["a.csv", "b.csv", "c.csv", "d.csv"])),
+                Arc::new(StringArray::from(vec![
+                    LOADED,
+                    PARTIALLY_LOADED,
+                    LOAD_FAILED,
+                    SKIPPED,
+                ])),
+                Arc::new(UInt64Array::from(vec![10, 5, 0, 0])),
+                Arc::new(UInt64Array::from(vec![0, 1, 0, 0])),
+                Arc::new(UInt64Array::from(vec![0, 0, 0, 0])),
+                Arc::new(StringArray::from(vec![
+                    None,
+                    Some("bad row"),
+                    Some("bad file"),
+                    None,
+                ])),
+            ],
+        )
+        .unwrap();
+        let output = Output::StreamData(schema, vec![batch]);
+
+        let mut status = PipeStatus {
+            files_loaded: 1,
+            rows_loaded: 1,
+            ..Default::default()
+        };
+        let err = accumulate_status(&mut status, &output);
+
+        assert_eq!(err.as_deref(), Some("c.csv: bad file"));
+        assert_eq!(status.files_loaded, 3);
+        assert_eq!(status.files_failed, 1);
+        assert_eq!(status.rows_loaded, 16);
+    }
+
+    #[test]
+    fn test_is_lease_expired() {
+        let second = Duration::from_secs(1).as_nanos() as i64;
+        let mut pipe = PipeInfo {
+            name: "p".to_string(),
+            owner: "root".to_string(),
+            database: "public".to_string(),
+            table: "t".to_string(),
+            node_id: 1,
+            lease_expire_time: 0,
+            interval: 1000,
+            definition: String::new(),
+            create_time: 0,
+            status: PipeStatus::default(),
+        };
+
+        // Never leased since the creation
+        assert!(!is_lease_expired(&pipe, 60 * second));
+        assert!(is_lease_expired(&pipe, 61 * second));
+
+        pipe.status.last_load_time = Some(100 * second);
+        assert!(!is_lease_expired(&pipe, 150 * second));
+        assert!(is_lease_expired(&pipe, 161 * second));
+
+        // A long interval waits for 3 intervals
+        pipe.interval = 120_000;
+        assert!(!is_lease_expired(&pipe, 400 * second));
+        assert!(is_lease_expired(&pipe, 461 * second));
+
+        // The lease is renewed after the last load
+        pipe.lease_expire_time = 500 * second;
+        assert!(!is_lease_expired(&pipe, 500 * second));
+        assert!(is_lease_expired(&pipe, 501 * second));
+    }
+}
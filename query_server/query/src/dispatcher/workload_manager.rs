@@ -0,0 +1,352 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+
+use models::oid::Oid;
+use parking_lot::{Mutex, RwLock};
+use spi::query::session::QueryPriority;
+use spi::QueryError;
+use tokio::sync::oneshot;
+use trace::debug;
+
+/// Admission control of queries.
+///
+/// Each tenant owns a number of concurrency slots, queries that can not get a slot
+/// wait in a bounded queue of the tenant until a slot is released or the wait times out.
+/// [`QueryPriority::Interactive`] queries are always woken up before [`QueryPriority::Batch`] ones.
+pub struct WorkloadManager {
+    max_queued: usize,
+    queue_timeout: Duration,
+    tenants: RwLock<HashMap<Oid, Arc<TenantWorkload>>>,
+}
+
+impl WorkloadManager {
+    pub fn new(max_queued: usize, queue_timeout: Duration) -> Self {
+        Self {
+            max_queued,
+            queue_timeout,
+            tenants: Default::default(),
+        }
+    }
+
+    /// Wait for a concurrency slot of the tenant.
+    ///
+    /// `limit` is the maximum number of concurrent queries of the tenant, `None` means unlimited.
+    ///
+    /// [`QueryError::QueryQueueFull`] if too many queries are already waiting
+    ///
+    /// [`QueryError::QueryQueueTimeout`] if no slot is released within the queue timeout
+    pub async fn acquire(
+        &self,
+        tenant_id: Oid,
+        tenant: &str,
+        limit: Option<u64>,
+        priority: QueryPriority,
+    ) -> Result<WorkloadPermit, QueryError> {
+        let limit = limit.map(|e| e as usize).unwrap_or(usize::MAX);
+        let workload = self.tenant_workload(tenant_id, tenant, limit);
+
+        workload.acquire(priority, self.queue_timeout).await
+    }
+
+    /// Get the workload of the tenant, create it if not exists.
+    ///
+    /// Workloads of the tenants without running or queued queries are dropped
+    /// when a new workload is created, so the workloads of deleted tenants are not kept forever.
+    fn tenant_workload(&self, tenant_id: Oid, tenant: &str, limit: usize) -> Arc<TenantWorkload> {
+        if let Some(workload) = self.tenants.read().get(&tenant_id) {
+            workload.set_limit(limit);
+            return workload.clone();
+        }
+
+        let mut tenants = self.tenants.write();
+        tenants.retain(|id, workload| *id == tenant_id || !Self::is_idle(workload));
+        tenants
+            .entry(tenant_id)
+            .or_insert_with(|| {
+                Arc::new(TenantWorkload::new(
+                    tenant.to_string(),
+                    limit,
+                    self.max_queued,
+                ))
+            })
+            .clone()
+    }
+
+    /// The workload is only referenced by the manager, permits and waiters hold a reference
+    fn is_idle(workload: &Arc<TenantWorkload>) -> bool {
+        Arc::strong_count(workload) == 1
+    }
+}
+
+struct TenantWorkload {
+    tenant: String,
+    max_queued: usize,
+    state: Mutex<WorkloadState>,
+}
+
+struct WorkloadState {
+    limit: usize,
+    running: usize,
+    interactive: VecDeque<oneshot::Sender<()>>,
+    batch: VecDeque<oneshot::Sender<()>>,
+}
+
+impl WorkloadState {
+    fn queued(&self) -> usize {
+        self.interactive.len() + self.batch.len()
+    }
+
+    /// Whether there are waiters that should be served before a query with `priority`
+    fn has_waiters_before(&self, priority: QueryPriority) -> bool {
+        match priority {
+            QueryPriority::Interactive => !self.interactive.is_empty(),
+            QueryPriority::Batch => self.queued() > 0,
+        }
+    }
+
+    fn remove_expired_waiters(&mut self) {
+        self.interactive.retain(|e| !e.is_closed());
+        self.batch.retain(|e| !e.is_closed());
+    }
+
+    /// Hand the free slots over to the waiters
+    fn wake_waiters(&mut self) {
+        while self.running < self.limit {
+            let waiter = match self.interactive.pop_front() {
+                Some(waiter) => waiter,
+                None => match self.batch.pop_front() {
+                    Some(waiter) => waiter,
+                    None => break,
+                },
+            };
+
+            // the waiter may has timed out
+            if waiter.send(()).is_ok() {
+                self.running += 1;
+            }
+        }
+    }
+}
+
+impl TenantWorkload {
+    fn new(tenant: String, limit: usize, max_queued: usize) -> Self {
+        Self {
+            tenant,
+            max_queued,
+            state: Mutex::new(WorkloadState {
+                limit,
+                running: 0,
+                interactive: VecDeque::new(),
+                batch: VecDeque::new(),
+            }),
+        }
+    }
+
+    fn set_limit(&self, limit: usize) {
+        let mut state = self.state.lock();
+        if state.limit != limit {
+            state.limit = limit;
+            state.wake_waiters();
+        }
+    }
+
+    async fn acquire(
+        self: Arc<Self>,
+        priority: QueryPriority,
+        timeout: Duration,
+    ) -> Result<WorkloadPermit, QueryError> {
+        let mut rx = {
+            let mut state = self.state.lock();
+            state.remove_expired_waiters();
+
+            if state.running < state.limit && !state.has_waiters_before(priority) {
+                state.running += 1;
+                drop(state);
+                return Ok(WorkloadPermit { workload: self });
+            }
+
+            if state.queued() >= self.max_queued {
+                return Err(QueryError::QueryQueueFull {
+                    tenant: self.tenant.clone(),
+                    size: self.max_queued,
+                });
+            }
+
+            let (tx, rx) = oneshot::channel();
+            match priority {
+                QueryPriority::Interactive => state.interactive.push_back(tx),
+                QueryPriority::Batch => state.batch.push_back(tx),
+            }
+            rx
+        };
+
+        debug!(
+            "Query of tenant {} is queued, priority: {}",
+            self.tenant,
+            priority.as_ref()
+        );
+
+        match tokio::time::timeout(timeout, &mut rx).await {
+            Ok(Ok(())) => Ok(WorkloadPermit { workload: self }),
+            Ok(Err(_)) => Err(QueryError::Closed),
+            Err(_) => {
+                rx.close();
+                // the slot may be handed over just after timeout
+                if rx.try_recv().is_ok() {
+                    return Ok(WorkloadPermit { workload: self });
+                }
+
+                Err(QueryError::QueryQueueTimeout {
+                    tenant: self.tenant.clone(),
+                    timeout,
+                })
+            }
+        }
+    }
+
+    fn release(&self) {
+        let mut state = self.state.lock();
+        state.running -= 1;
+        state.wake_waiters();
+    }
+}
+
+/// A concurrency slot of the tenant, released on drop
+pub struct WorkloadPermit {
+    workload: Arc<TenantWorkload>,
+}
+
+impl Drop for WorkloadPermit {
+    fn drop(&mut self) {
+        self.workload.release();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use spi::query::session::QueryPriority;
+    use spi::QueryError;
+
+    use super::WorkloadManager;
+
+    #[tokio::test]
+    async fn test_queue_full() {
+        let manager = WorkloadManager::new(1, Duration::from_secs(10));
+        let p1 = manager
+            .acquire(1, "t", Some(1), QueryPriority::Interactive)
+            .await
+            .unwrap();
+
+        let manager = Arc::new(manager);
+        let m = manager.clone();
+        let waiter = tokio::spawn(async move {
+            m.acquire(1, "t", Some(1), QueryPriority::Interactive)
+                .await
+                .map(|_| ())
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let err = manager
+            .acquire(1, "t", Some(1), QueryPriority::Interactive)
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, QueryError::QueryQueueFull { .. }));
+
+        // other tenants are not affected
+        let _p2 = manager
+            .acquire(2, "t2", Some(1), QueryPriority::Interactive)
+            .await
+            .unwrap();
+
+        drop(p1);
+        waiter.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_queue_timeout() {
+        let manager = WorkloadManager::new(10, Duration::from_millis(20));
+        let p1 = manager
+            .acquire(1, "t", Some(1), QueryPriority::Batch)
+            .await
+            .unwrap();
+
+        let err = manager
+            .acquire(1, "t", Some(1), QueryPriority::Batch)
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, QueryError::QueryQueueTimeout { .. }));
+
+        // slot of the timed out query is not leaked
+        drop(p1);
+        let _p2 = manager
+            .acquire(1, "t", Some(1), QueryPriority::Batch)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_interactive_before_batch() {
+        let manager = Arc::new(WorkloadManager::new(10, Duration::from_secs(10)));
+        let p1 = manager
+            .acquire(1, "t", Some(1), QueryPriority::Batch)
+            .await
+            .unwrap();
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        for priority in [QueryPriority::Batch, QueryPriority::Interactive] {
+            let m = manager.clone();
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                let _permit = m.acquire(1, "t", Some(1), priority).await.unwrap();
+                tx.send(priority).unwrap();
+            });
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        drop(p1);
+        assert_eq!(rx.recv().await, Some(QueryPriority::Interactive));
+        assert_eq!(rx.recv().await, Some(QueryPriority::Batch));
+    }
+
+    #[tokio::test]
+    async fn test_prune_idle_tenant_workloads() {
+        let manager = WorkloadManager::new(10, Duration::from_secs(10));
+        let p1 = manager
+            .acquire(1, "t1", Some(1), QueryPriority::Interactive)
+            .await
+            .unwrap();
+        drop(
+            manager
+                .acquire(2, "t2", Some(1), QueryPriority::Interactive)
+                .await
+                .unwrap(),
+        );
+
+        // workload of t2 is idle, workload of t1 has a running query
+        drop(
+            manager
+                .acquire(3, "t3", Some(1), QueryPriority::Interactive)
+                .await
+                .unwrap(),
+        );
+        let mut tenants = manager.tenants.read().keys().copied().collect::<Vec<_>>();
+        tenants.sort_unstable();
+        assert_eq!(tenants, vec![1, 3]);
+
+        drop(p1);
+        drop(
+            manager
+                .acquire(4, "t4", Some(1), QueryPriority::Interactive)
+                .await
+                .unwrap(),
+        );
+        let tenants = manager.tenants.read().keys().copied().collect::<Vec<_>>();
+        assert_eq!(tenants, vec![4]);
+    }
+}
@@ -0,0 +1,1171 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use datafusion::arrow::array::{
+    as_primitive_array, StringArray, TimestampNanosecondArray, UInt64Array,
+};
+use datafusion::arrow::datatypes::{DataType, Field, Schema, SchemaRef, TimeUnit, UInt64Type};
+use datafusion::arrow::error::Result as ArrowResult;
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::arrow::{csv, json};
+use datafusion::datasource::file_format::file_type::FileType;
+use datafusion::datasource::listing::ListingTableUrl;
+use datafusion::datasource::{provider_as_source, MemTable};
+use datafusion::logical_expr::{LogicalPlan, LogicalPlanBuilder, TableSource};
+use datafusion::physical_plan::SendableRecordBatchStream;
+use futures::stream::{AbortHandle, BoxStream};
+use futures::{StreamExt, TryStreamExt};
+use line_protocol::{parse_lines_to_points, FieldValue, Line, Parser};
+use meta::error::MetaError;
+use meta::MetaClientRef;
+use models::consistency_level::ConsistencyLevel;
+use models::meta_data::LoadedFile;
+use models::schema::Precision;
+use object_store::path::Path;
+use parking_lot::Mutex;
+use protos::kv_service::WritePointsRequest;
+use spi::query::dispatcher::{QueryInfo, QueryStatus};
+use spi::query::execution::{Output, QueryExecution, QueryStateMachineRef};
+use spi::query::logical_planner::{
+    CopyFile, CopyFileSource, CopyIntoTablePlan, OnError, PointsFormat,
+};
+use spi::query::optimizer::Optimizer;
+use spi::query::scheduler::SchedulerRef;
+use spi::{QueryError, Result};
+use trace::{debug, warn};
+
+use crate::prom::{exposition, METRIC_NAME_LABEL, METRIC_SAMPLE_COLUMN_NAME};
+use crate::sql::logical::planner::TableWriteExt;
+
+pub const LOADED: &str = "LOADED";
+pub const PARTIALLY_LOADED: &str = "PARTIALLY_LOADED";
+pub const LOAD_FAILED: &str = "LOAD_FAILED";
+pub const SKIPPED: &str = "SKIPPED";
+
+/// Number of lines decoded at once when loading a file row by row
+const ROWS_BATCH_SIZE: usize = 1024;
+
+/// Number of decoded rows written at once when loading a file row by row
+const ROWS_WRITE_SIZE: usize = 64 * 1024;
+
+/// Number of points written at once when loading a file of points
+const POINTS_BATCH_SIZE: usize = 10240;
+
+/// Suffix of the file rejected rows are written to, which is next to the source file
+pub const REJECTED_FILE_SUFFIX: &str = ".rejected";
+
+/// Columns of the table rejected rows are written to
+const REJECTED_ROWS_COLUMNS: [&str; 5] = ["time", "file", "line", "error", "row"];
+
+pub struct CopyIntoTableExecution {
+    query_state_machine: QueryStateMachineRef,
+    plan: CopyIntoTablePlan,
+    optimizer: Arc<dyn Optimizer + Send + Sync>,
+    scheduler: SchedulerRef,
+
+    abort_handle: Mutex<Option<AbortHandle>>,
+}
+
+impl CopyIntoTableExecution {
+    pub fn new(
+        query_state_machine: QueryStateMachineRef,
+        plan: CopyIntoTablePlan,
+        optimizer: Arc<dyn Optimizer + Send + Sync>,
+        scheduler: SchedulerRef,
+    ) -> Self {
+        Self {
+            query_state_machine,
+            plan,
+            optimizer,
+            scheduler,
+            abort_handle: Mutex::new(None),
+        }
+    }
+}
+
+/// Load result of a file, returned as a row of the output
+struct FileLoadResult {
+    file: String,
+    status: &'static str,
+    rows_loaded: u64,
+    rows_rejected: u64,
+    /// Lines of other measurements, which are left to the COPY INTO of their tables
+    rows_skipped: u64,
+    first_error: Option<String>,
+}
+
+impl FileLoadResult {
+    fn new(file: &CopyFile, status: &'static str) -> Self {
+        Self {
+            file: file.name.clone(),
+            status,
+            rows_loaded: 0,
+            rows_rejected: 0,
+            rows_skipped: 0,
+            first_error: None,
+        }
+    }
+
+    fn loaded(file: &CopyFile, rows_loaded: u64) -> Self {
+        Self {
+            rows_loaded,
+            ..Self::new(file, LOADED)
+        }
+    }
+
+    fn failed(file: &CopyFile, err: QueryError) -> Self {
+        warn!("Failed to load file {}, error: {}", file.url, err);
+        Self {
+            first_error: Some(err.to_string()),
+            ..Self::new(file, LOAD_FAILED)
+        }
+    }
+}
+
+/// A row of the file that can't be loaded
+struct RejectedRow {
+    /// Line number in the file, starting from 1
+    line: u64,
+    error: String,
+    row: String,
+}
+
+/// Points parsed from a file of line protocol or prometheus
+#[derive(Default)]
+struct PointsLoad {
+    rows_loaded: u64,
+    rows_skipped: u64,
+    rejected_rows: Vec<RejectedRow>,
+}
+
+fn parse_points_error(file: &CopyFile, row: &RejectedRow) -> QueryError {
+    QueryError::ParsePoints {
+        file: file.url.clone(),
+        line: row.line,
+        reason: row.error.clone(),
+    }
+}
+
+impl CopyIntoTableExecution {
+    async fn start(&self) -> Result<Output> {
+        let tenant = self.query_state_machine.session.tenant();
+        let client = self
+            .query_state_machine
+            .meta
+            .tenant_manager()
+            .tenant_meta(tenant)
+            .await
+            .ok_or(MetaError::TenantNotFound {
+                tenant: tenant.to_string(),
+            })?;
+
+        let history = if self.plan.copy_options.force {
+            vec![]
+        } else {
+            let urls = self.plan.files.iter().map(|e| e.url.clone()).collect();
+            client
+                .copy_history(&self.plan.database, &self.plan.table_name, urls)
+                .await?
+        };
+        let history: HashMap<&str, &LoadedFile> =
+            history.iter().map(|e| (e.url.as_str(), e)).collect();
+
+        self.query_state_machine.begin_schedule();
+        let mut results = Vec::with_capacity(self.plan.files.len());
+        for file in self.plan.files.iter() {
+            if history
+                .get(file.url.as_str())
+                .map(|e| e.is_same_file(&file.url, file.size, file.last_modified))
+                .unwrap_or(false)
+            {
+                debug!("Skip file {} which has been loaded", file.url);
+                results.push(FileLoadResult::new(file, SKIPPED));
+                continue;
+            }
+
+            let load_time = chrono::Utc::now().timestamp_nanos();
+            let result = self.load_file(file, load_time).await?;
+            // The history is recorded after the points are written, a failure in between
+            // loads the file again next time. That's harmless as the same points are
+            // written again and overwrite the ones written before.
+            if result.status == LOADED || result.status == PARTIALLY_LOADED {
+                self.record_history(&client, file, result.rows_loaded, load_time)
+                    .await?;
+            }
+            results.push(result);
+        }
+        self.query_state_machine.end_schedule();
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("file", DataType::Utf8, false),
+            Field::new("status", DataType::Utf8, false),
+            Field::new("rows_loaded", DataType::UInt64, false),
+            Field::new("rows_rejected", DataType::UInt64, false),
+            Field::new("rows_skipped", DataType::UInt64, false),
+            Field::new("first_error", DataType::Utf8, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(StringArray::from_iter_values(
+                    results.iter().map(|e| e.file.as_str()),
+                )),
+                Arc::new(StringArray::from_iter_values(
+                    results.iter().map(|e| e.status),
+                )),
+                Arc::new(UInt64Array::from_iter_values(
+                    results.iter().map(|e| e.rows_loaded),
+                )),
+                Arc::new(UInt64Array::from_iter_values(
+                    results.iter().map(|e| e.rows_rejected),
+                )),
+                Arc::new(UInt64Array::from_iter_values(
+                    results.iter().map(|e| e.rows_skipped),
+                )),
+                Arc::new(StringArray::from_iter(
+                    results.iter().map(|e| e.first_error.as_deref()),
+                )),
+            ],
+        )?;
+
+        Ok(Output::StreamData(schema, vec![batch]))
+    }
+
+    async fn record_history(
+        &self,
+        client: &MetaClientRef,
+        file: &CopyFile,
+        rows_loaded: u64,
+        load_time: i64,
+    ) -> Result<()> {
+        let loaded_file = LoadedFile {
+            url: file.url.clone(),
+            size: file.size,
+            last_modified: file.last_modified,
+            rows_loaded,
+            load_time,
+        };
+        client
+            .add_copy_history(
+                &self.plan.database,
+                &self.plan.table_name,
+                vec![loaded_file],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn load_file(&self, file: &CopyFile, load_time: i64) -> Result<FileLoadResult> {
+        debug!(
+            "Load file {} with {:?}",
+            file.url, self.plan.copy_options.on_error
+        );
+
+        let (source, write_plan) = match &file.source {
+            CopyFileSource::Table { source, write_plan } => (source, write_plan),
+            CopyFileSource::Points(format) => {
+                return self.load_points(file, *format, load_time).await
+            }
+        };
+
+        match self.plan.copy_options.on_error {
+            OnError::Abort => {
+                let rows_loaded = self.write(write_plan).await?;
+                Ok(FileLoadResult::loaded(file, rows_loaded))
+            }
+            OnError::SkipFile => match self.validate_and_write(file, source, write_plan).await {
+                Ok(rows_loaded) => Ok(FileLoadResult::loaded(file, rows_loaded)),
+                Err(err) => Ok(FileLoadResult::failed(file, err)),
+            },
+            OnError::Continue => match self.validate_and_write(file, source, write_plan).await {
+                Ok(rows_loaded) => Ok(FileLoadResult::loaded(file, rows_loaded)),
+                Err(err) if self.support_rows_loading() => {
+                    debug!(
+                        "Failed to load file {} at once, load it row by row, error: {}",
+                        file.url, err
+                    );
+                    self.load_rows(file, load_time).await
+                }
+                Err(err) => Ok(FileLoadResult::failed(file, err)),
+            },
+        }
+    }
+
+    /// Scan the whole file before writing it, so that a file with bad rows is not partially written.
+    ///
+    /// Writes can't be rolled back, validating while writing would leave the rows before
+    /// the first bad row written, so the file is scanned twice. The scan streams the file
+    /// without holding it in memory, ON_ERROR = ABORT writes the file in a single pass.
+    async fn validate_and_write(
+        &self,
+        file: &CopyFile,
+        source: &Arc<dyn TableSource>,
+        write_plan: &LogicalPlan,
+    ) -> Result<u64> {
+        let scan_plan =
+            LogicalPlanBuilder::scan("external_location_table", source.clone(), None)?.build()?;
+        let rows = self
+            .execute(&scan_plan)
+            .await?
+            .try_fold(
+                0_usize,
+                |acc, batch| async move { Ok(acc + batch.num_rows()) },
+            )
+            .await?;
+        debug!("Validated {} rows of file {}", rows, file.url);
+
+        self.write(write_plan).await
+    }
+
+    /// Parse the file into points of the target table and write them the way the write api does,
+    /// so that the tags and fields not in the table are created.
+    ///
+    /// The file is read as a stream and split into lines by [`RowSplitter`], the points are
+    /// written every [`POINTS_BATCH_SIZE`] lines. The lines of other measurements are counted
+    /// as skipped rows.
+    ///
+    /// Writes can't be rolled back, so the file is parsed before it's written if ON_ERROR is
+    /// SKIP_FILE. ON_ERROR = ABORT writes the file in a single pass, the lines before the first
+    /// bad line are written. ON_ERROR = CONTINUE writes the bad lines to the rejected rows sink.
+    async fn load_points(
+        &self,
+        file: &CopyFile,
+        format: PointsFormat,
+        load_time: i64,
+    ) -> Result<FileLoadResult> {
+        let on_error = self.plan.copy_options.on_error;
+        if on_error == OnError::SkipFile {
+            let parsed = self.stream_points(file, format, load_time, false).await?;
+            if let Some(first) = parsed.rejected_rows.first() {
+                return Ok(FileLoadResult::failed(
+                    file,
+                    parse_points_error(file, first),
+                ));
+            }
+        }
+
+        let PointsLoad {
+            rows_loaded,
+            rows_skipped,
+            rejected_rows,
+        } = self.stream_points(file, format, load_time, true).await?;
+        if rejected_rows.is_empty() {
+            return Ok(FileLoadResult {
+                rows_skipped,
+                ..FileLoadResult::loaded(file, rows_loaded)
+            });
+        }
+
+        self.write_rejected_rows(file, &rejected_rows, load_time)
+            .await?;
+
+        let status = if rows_loaded > 0 {
+            PARTIALLY_LOADED
+        } else {
+            LOAD_FAILED
+        };
+        Ok(FileLoadResult {
+            rows_loaded,
+            rows_rejected: rejected_rows.len() as u64,
+            rows_skipped,
+            first_error: rejected_rows.first().map(|e| e.error.clone()),
+            ..FileLoadResult::new(file, status)
+        })
+    }
+
+    /// Parse the file chunk by chunk, the points of each chunk are written if `write`.
+    ///
+    /// Fails at the first bad line if ON_ERROR is ABORT, before the chunk of it is written.
+    async fn stream_points(
+        &self,
+        file: &CopyFile,
+        format: PointsFormat,
+        load_time: i64,
+        write: bool,
+    ) -> Result<PointsLoad> {
+        let database_precision = self.database_precision().await?;
+        let mut stream = self.read_file(file).await?;
+        let mut splitter = RowSplitter::lines();
+
+        let mut rows = vec![];
+        let mut load = PointsLoad::default();
+        loop {
+            let eof = match stream.try_next().await? {
+                Some(data) => {
+                    splitter.push(&data, &mut rows);
+                    false
+                }
+                None => {
+                    splitter.finish(&mut rows);
+                    true
+                }
+            };
+
+            while rows.len() >= POINTS_BATCH_SIZE || (eof && !rows.is_empty()) {
+                let chunk = rows
+                    .drain(..rows.len().min(POINTS_BATCH_SIZE))
+                    .collect::<Vec<_>>();
+                let chunk = chunk
+                    .iter()
+                    .map(|(line, row)| (*line, String::from_utf8_lossy(row)))
+                    .collect::<Vec<_>>();
+                let mut samples = vec![];
+                let lines = self.parse_points(
+                    &chunk,
+                    format,
+                    &database_precision,
+                    load_time,
+                    &mut samples,
+                    &mut load,
+                );
+
+                if self.plan.copy_options.on_error == OnError::Abort {
+                    if let Some(first) = load.rejected_rows.first() {
+                        return Err(parse_points_error(file, first));
+                    }
+                }
+                if write {
+                    load.rows_loaded += self.write_points(&lines).await?;
+                }
+            }
+
+            if eof {
+                break;
+            }
+        }
+
+        Ok(load)
+    }
+
+    /// Parse the lines into points of the target table, the bad lines and the lines
+    /// of other measurements are counted in `load`.
+    ///
+    /// The samples of prometheus are parsed into `samples` first since the labels are unescaped.
+    fn parse_points<'a>(
+        &self,
+        rows: &'a [(u64, Cow<'a, str>)],
+        format: PointsFormat,
+        database_precision: &Precision,
+        load_time: i64,
+        samples: &'a mut Vec<(Sample, i64)>,
+        load: &mut PointsLoad,
+    ) -> Vec<Line<'a>> {
+        let table = self.plan.table_name.as_str();
+        let mut reject = |line: u64, error: String, row: &str| {
+            load.rejected_rows.push(RejectedRow {
+                line,
+                error,
+                row: row.to_string(),
+            })
+        };
+
+        let mut lines = vec![];
+        match format {
+            PointsFormat::LineProtocol => {
+                let file_precision = self
+                    .plan
+                    .file_format_options
+                    .precision
+                    .as_ref()
+                    .unwrap_or(database_precision);
+                let parser = Parser::new(load_time / precision_nanos(file_precision));
+                for (line_number, row) in rows {
+                    if row.trim_start().starts_with('#') {
+                        continue;
+                    }
+                    let parsed = match parser.parse(row) {
+                        Ok(parsed) => parsed,
+                        Err(err) => {
+                            reject(*line_number, err.to_string(), row);
+                            continue;
+                        }
+                    };
+                    for mut line in parsed {
+                        if line.measurement != table {
+                            load.rows_skipped += 1;
+                            continue;
+                        }
+                        match convert_timestamp(line.timestamp, file_precision, database_precision)
+                        {
+                            Some(timestamp) => {
+                                line.timestamp = timestamp;
+                                lines.push(line);
+                            }
+                            None => reject(
+                                *line_number,
+                                format!("timestamp {} is out of range", line.timestamp),
+                                row,
+                            ),
+                        }
+                    }
+                }
+            }
+            PointsFormat::Prometheus => {
+                for (line_number, row) in rows {
+                    let sample = match exposition::parse_line(row) {
+                        Ok(Some(sample)) => sample,
+                        Ok(None) => continue,
+                        Err(err) => {
+                            reject(*line_number, err, row);
+                            continue;
+                        }
+                    };
+                    let timestamp = match sample.timestamp {
+                        Some(ms) => convert_timestamp(ms, &Precision::MS, database_precision),
+                        None => Some(load_time / precision_nanos(database_precision)),
+                    };
+                    match timestamp {
+                        Some(timestamp) => samples.push((sample, timestamp)),
+                        None => reject(
+                            *line_number,
+                            format!("timestamp {:?} is out of range", sample.timestamp),
+                            row,
+                        ),
+                    }
+                }
+                let samples: &'a Vec<_> = samples;
+                for (sample, timestamp) in samples {
+                    let mut tags = sample
+                        .labels
+                        .iter()
+                        .map(|(k, v)| (k.as_str(), v.as_str()))
+                        .collect::<Vec<_>>();
+                    if !tags.iter().any(|(k, _)| *k == METRIC_NAME_LABEL) {
+                        tags.push((METRIC_NAME_LABEL, sample.metric.as_str()));
+                    }
+                    let fields = vec![(METRIC_SAMPLE_COLUMN_NAME, FieldValue::F64(sample.value))];
+                    lines.push(Line::new(table, tags, fields, *timestamp));
+                }
+            }
+        }
+
+        lines
+    }
+
+    /// Write the points in batches through the coordinator, returns the number of points written
+    async fn write_points(&self, lines: &[Line<'_>]) -> Result<u64> {
+        let session = &self.query_state_machine.session;
+        let consistency_level = session
+            .inner()
+            .state()
+            .config()
+            .get_extension::<ConsistencyLevel>()
+            .map(|e| *e)
+            .unwrap_or_default();
+
+        let database = self.plan.database.as_str();
+        for chunk in lines.chunks(POINTS_BATCH_SIZE) {
+            let req = WritePointsRequest {
+                version: 1,
+                meta: None,
+                points: parse_lines_to_points(database, chunk),
+            };
+            self.query_state_machine
+                .coord
+                .write_points(session.tenant().to_string(), consistency_level, req)
+                .await?;
+        }
+
+        Ok(lines.len() as u64)
+    }
+
+    async fn database_precision(&self) -> Result<Precision> {
+        let tenant = self.query_state_machine.session.tenant();
+        let client = self
+            .query_state_machine
+            .meta
+            .tenant_manager()
+            .tenant_meta(tenant)
+            .await
+            .ok_or(MetaError::TenantNotFound {
+                tenant: tenant.to_string(),
+            })?;
+        let schema = client.get_db_schema(&self.plan.database)?.ok_or_else(|| {
+            MetaError::DatabaseNotFound {
+                database: self.plan.database.clone(),
+            }
+        })?;
+
+        Ok(schema.config.precision_or_default().clone())
+    }
+
+    /// Read the file as a stream of chunks
+    async fn read_file(&self, file: &CopyFile) -> Result<BoxStream<'static, Result<Bytes>>> {
+        let table_path = ListingTableUrl::parse(&file.url)?;
+        let object_store = self
+            .query_state_machine
+            .session
+            .inner()
+            .runtime_env()
+            .object_store(&table_path)?;
+
+        Ok(object_store
+            .get(&file.location)
+            .await?
+            .into_stream()
+            .map_err(QueryError::from)
+            .boxed())
+    }
+
+    /// Only uncompressed line-delimited files can be split into rows
+    fn support_rows_loading(&self) -> bool {
+        let options = &self.plan.file_format_options;
+        options.ext_file_type.is_none()
+            && matches!(options.file_type, FileType::CSV | FileType::JSON)
+            && !options.file_compression_type.is_compressed()
+    }
+
+    /// Load the rows that can be decoded and write the others to the rejected rows sink.
+    ///
+    /// The file is read as a stream and split into rows by [`RowSplitter`],
+    /// the decoded rows are written every [`ROWS_WRITE_SIZE`] rows.
+    async fn load_rows(&self, file: &CopyFile, load_time: i64) -> Result<FileLoadResult> {
+        let mut stream = self.read_file(file).await?;
+
+        let options = &self.plan.file_format_options;
+        let mut splitter = match options.file_type {
+            FileType::CSV => RowSplitter::csv(options.delimiter as u8, options.with_header),
+            _ => RowSplitter::lines(),
+        };
+
+        let mut rows = vec![];
+        let mut batches = vec![];
+        let mut buffered_rows = 0;
+        let mut rows_loaded = 0;
+        let mut rejected_rows = vec![];
+        loop {
+            let eof = match stream.try_next().await? {
+                Some(data) => {
+                    splitter.push(&data, &mut rows);
+                    false
+                }
+                None => {
+                    splitter.finish(&mut rows);
+                    true
+                }
+            };
+
+            while rows.len() >= ROWS_BATCH_SIZE || (eof && !rows.is_empty()) {
+                let chunk = rows
+                    .drain(..rows.len().min(ROWS_BATCH_SIZE))
+                    .collect::<Vec<_>>();
+                for batch in self.decode_rows(&chunk, &mut rejected_rows) {
+                    buffered_rows += batch.num_rows();
+                    batches.push(batch);
+                }
+                if buffered_rows >= ROWS_WRITE_SIZE {
+                    rows_loaded += self.write_rows(std::mem::take(&mut batches)).await?;
+                    buffered_rows = 0;
+                }
+            }
+
+            if eof {
+                break;
+            }
+        }
+        rows_loaded += self.write_rows(batches).await?;
+
+        self.write_rejected_rows(file, &rejected_rows, load_time)
+            .await?;
+
+        let status = if rows_loaded > 0 {
+            PARTIALLY_LOADED
+        } else {
+            LOAD_FAILED
+        };
+        Ok(FileLoadResult {
+            rows_loaded,
+            rows_rejected: rejected_rows.len() as u64,
+            first_error: rejected_rows.first().map(|e| e.error.clone()),
+            ..FileLoadResult::new(file, status)
+        })
+    }
+
+    /// Decode the rows at once, or one by one to find out the bad rows if it fails
+    fn decode_rows(
+        &self,
+        rows: &[(u64, Vec<u8>)],
+        rejected_rows: &mut Vec<RejectedRow>,
+    ) -> Vec<RecordBatch> {
+        let data = rows
+            .iter()
+            .map(|(_, row)| row.as_slice())
+            .collect::<Vec<_>>()
+            .join(&b'\n');
+        if let Ok(batches) = self.decode(data) {
+            return batches;
+        }
+
+        let mut batches = vec![];
+        for (line, row) in rows {
+            match self.decode(row.clone()) {
+                Ok(row_batches) => batches.extend(row_batches),
+                Err(err) => rejected_rows.push(RejectedRow {
+                    line: *line,
+                    error: err.to_string(),
+                    row: String::from_utf8_lossy(row).to_string(),
+                }),
+            }
+        }
+
+        batches
+    }
+
+    /// Write the decoded rows into the target table
+    async fn write_rows(&self, batches: Vec<RecordBatch>) -> Result<u64> {
+        if batches.is_empty() {
+            return Ok(0);
+        }
+
+        let table = MemTable::try_new(self.plan.source_schema.clone(), vec![batches])?;
+        let write_plan = LogicalPlanBuilder::scan(
+            "external_location_table",
+            provider_as_source(Arc::new(table)),
+            None,
+        )?
+        .write(
+            self.plan.target_table.clone(),
+            &self.plan.table_name,
+            &self.plan.insert_columns,
+        )?
+        .build()?;
+
+        self.write(&write_plan).await
+    }
+
+    fn decode(&self, data: Vec<u8>) -> ArrowResult<Vec<RecordBatch>> {
+        let options = &self.plan.file_format_options;
+        let schema = self.plan.source_schema.clone();
+        let reader = Cursor::new(data);
+
+        match options.file_type {
+            FileType::JSON => json::ReaderBuilder::new()
+                .with_schema(schema)
+                .with_batch_size(ROWS_BATCH_SIZE)
+                .build(reader)?
+                .collect(),
+            _ => csv::ReaderBuilder::new()
+                .with_schema(schema)
+                .has_header(false)
+                .with_delimiter(options.delimiter as u8)
+                .with_batch_size(ROWS_BATCH_SIZE)
+                .build(reader)?
+                .collect(),
+        }
+    }
+
+    /// Write the rejected rows into the table if specified, otherwise next to the file
+    async fn write_rejected_rows(
+        &self,
+        file: &CopyFile,
+        rejected_rows: &[RejectedRow],
+        load_time: i64,
+    ) -> Result<()> {
+        if rejected_rows.is_empty() {
+            return Ok(());
+        }
+
+        match &self.plan.rejected_rows_table {
+            Some((table, table_name)) => {
+                let batch = rejected_rows_to_batch(&file.url, rejected_rows, load_time)?;
+                self.write_batch(batch, table.clone(), table_name).await?;
+            }
+            None => {
+                let table_path = ListingTableUrl::parse(&file.url)?;
+                let object_store = self
+                    .query_state_machine
+                    .session
+                    .inner()
+                    .runtime_env()
+                    .object_store(&table_path)?;
+                let location = Path::from(format!("{}{}", file.location, REJECTED_FILE_SUFFIX));
+                let content = rejected_rows
+                    .iter()
+                    .map(|e| e.row.as_str())
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                object_store.put(&location, Bytes::from(content)).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn write_batch(
+        &self,
+        batch: RecordBatch,
+        table: Arc<dyn TableSource>,
+        table_name: &str,
+    ) -> Result<u64> {
+        let memory_table = MemTable::try_new(batch.schema(), vec![vec![batch]])?;
+        let insert_columns = REJECTED_ROWS_COLUMNS.map(|e| e.to_string());
+        let write_plan = LogicalPlanBuilder::scan(
+            "rejected_rows",
+            provider_as_source(Arc::new(memory_table)),
+            None,
+        )?
+        .write(table, table_name, &insert_columns)?
+        .build()?;
+
+        self.write(&write_plan).await
+    }
+
+    /// Execute the write plan, returns the number of rows written
+    async fn write(&self, plan: &LogicalPlan) -> Result<u64> {
+        let batches = self.execute(plan).await?.try_collect::<Vec<_>>().await?;
+
+        let rows = batches
+            .iter()
+            .filter(|batch| batch.num_columns() > 0)
+            .flat_map(|batch| as_primitive_array::<UInt64Type>(batch.column(0)).iter())
+            .flatten()
+            .sum();
+
+        Ok(rows)
+    }
+
+    async fn execute(&self, plan: &LogicalPlan) -> Result<SendableRecordBatchStream> {
+        let optimized_physical_plan = self
+            .optimizer
+            .optimize(plan, &self.query_state_machine.session)
+            .await?;
+        let stream = self
+            .scheduler
+            .schedule(
+                optimized_physical_plan,
+                self.query_state_machine.session.inner().task_ctx(),
+            )?
+            .stream();
+
+        Ok(stream)
+    }
+}
+
+fn precision_nanos(precision: &Precision) -> i64 {
+    match precision {
+        Precision::MS => 1_000_000,
+        Precision::US => 1_000,
+        Precision::NS => 1,
+    }
+}
+
+/// Convert the timestamp between precisions, `None` if overflow
+fn convert_timestamp(timestamp: i64, from: &Precision, to: &Precision) -> Option<i64> {
+    let (from, to) = (precision_nanos(from), precision_nanos(to));
+    if from >= to {
+        timestamp.checked_mul(from / to)
+    } else {
+        Some(timestamp / (to / from))
+    }
+}
+
+/// Splits the content of a file into rows incrementally, so that a file is loaded row by row
+/// without being read into memory at once. Each row is returned with the line number it
+/// starts at, the trailing line break is removed.
+enum RowSplitter {
+    /// Records of CSV, which are split the way the CSV reader does,
+    /// quoted fields may contain line breaks
+    Csv {
+        reader: csv_core::Reader,
+        /// Bytes of the current record
+        record: Vec<u8>,
+        /// Number of line breaks before the current record
+        lines: u64,
+        skip_header: bool,
+        /// Parsed fields are not used, the buffers are reused
+        output: Vec<u8>,
+        ends: Vec<usize>,
+    },
+    /// Lines, e.g. of newline-delimited JSON
+    Lines {
+        /// Bytes of the current line
+        line: Vec<u8>,
+        lines: u64,
+    },
+}
+
+impl RowSplitter {
+    fn csv(delimiter: u8, with_header: bool) -> Self {
+        Self::Csv {
+            reader: csv_core::ReaderBuilder::new().delimiter(delimiter).build(),
+            record: vec![],
+            lines: 0,
+            skip_header: with_header,
+            output: vec![0; 4096],
+            ends: vec![0; 128],
+        }
+    }
+
+    fn lines() -> Self {
+        Self::Lines {
+            line: vec![],
+            lines: 0,
+        }
+    }
+
+    /// Split the data following the data pushed before, the complete rows are appended to `rows`
+    fn push(&mut self, data: &[u8], rows: &mut Vec<(u64, Vec<u8>)>) {
+        self.split(data, false, rows)
+    }
+
+    /// Split the rest data that is not ended with a line break
+    fn finish(&mut self, rows: &mut Vec<(u64, Vec<u8>)>) {
+        self.split(&[], true, rows)
+    }
+
+    fn split(&mut self, data: &[u8], eof: bool, rows: &mut Vec<(u64, Vec<u8>)>) {
+        match self {
+            Self::Csv {
+                reader,
+                record,
+                lines,
+                skip_header,
+                output,
+                ends,
+            } => {
+                let mut input = data;
+                // Empty input means the end of the data to the CSV reader
+                while !input.is_empty() || eof {
+                    let (result, nin, _, _) = reader.read_record(input, output, ends);
+                    record.extend_from_slice(&input[..nin]);
+                    input = &input[nin..];
+                    match result {
+                        csv_core::ReadRecordResult::InputEmpty
+                        | csv_core::ReadRecordResult::End => break,
+                        csv_core::ReadRecordResult::OutputFull
+                        | csv_core::ReadRecordResult::OutputEndsFull => {}
+                        csv_core::ReadRecordResult::Record => {
+                            let row = std::mem::take(record);
+                            let row_lines = *lines;
+                            *lines += row.iter().filter(|b| **b == b'\n').count() as u64;
+                            if !std::mem::take(skip_header) {
+                                push_row(row_lines, &row, rows);
+                            }
+                        }
+                    }
+                }
+            }
+            Self::Lines { line, lines } => {
+                let mut input = data;
+                while let Some(pos) = input.iter().position(|b| *b == b'\n') {
+                    line.extend_from_slice(&input[..=pos]);
+                    input = &input[pos + 1..];
+                    push_row(*lines, line, rows);
+                    *lines += 1;
+                    line.clear();
+                }
+                line.extend_from_slice(input);
+                if eof {
+                    push_row(*lines, line, rows);
+                    line.clear();
+                }
+            }
+        }
+    }
+}
+
+/// Push the row without the line breaks around it, blank rows are ignored.
+/// `lines` is the number of line breaks before the row.
+fn push_row(lines: u64, row: &[u8], rows: &mut Vec<(u64, Vec<u8>)>) {
+    let is_line_break = |b: &u8| *b == b'\r' || *b == b'\n';
+    let start = match row.iter().position(|b| !is_line_break(b)) {
+        Some(start) => start,
+        None => return,
+    };
+    let end = row.iter().rposition(|b| !is_line_break(b)).unwrap_or(start) + 1;
+    if row[start..end].iter().all(u8::is_ascii_whitespace) {
+        return;
+    }
+
+    // The line break ending the previous row may be split into this row, e.g. "\r\n"
+    let leading_lines = row[..start].iter().filter(|b| **b == b'\n').count() as u64;
+    rows.push((lines + leading_lines + 1, row[start..end].to_vec()));
+}
+
+fn rejected_rows_to_batch(
+    url: &str,
+    rejected_rows: &[RejectedRow],
+    load_time: i64,
+) -> Result<RecordBatch> {
+    let schema: SchemaRef = Arc::new(Schema::new(vec![
+        Field::new(
+            REJECTED_ROWS_COLUMNS[0],
+            DataType::Timestamp(TimeUnit::Nanosecond, None),
+            false,
+        ),
+        Field::new(REJECTED_ROWS_COLUMNS[1], DataType::Utf8, false),
+        Field::new(REJECTED_ROWS_COLUMNS[2], DataType::UInt64, false),
+        Field::new(REJECTED_ROWS_COLUMNS[3], DataType::Utf8, false),
+        Field::new(REJECTED_ROWS_COLUMNS[4], DataType::Utf8, false),
+    ]));
+
+    // Rows of a load are distinguished by the line number
+    let batch = RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(TimestampNanosecondArray::from_iter_values(
+                rejected_rows.iter().map(|e| load_time + e.line as i64),
+            )),
+            Arc::new(StringArray::from_iter_values(
+                rejected_rows.iter().map(|_| url),
+            )),
+            Arc::new(UInt64Array::from_iter_values(
+                rejected_rows.iter().map(|e| e.line),
+            )),
+            Arc::new(StringArray::from_iter_values(
+                rejected_rows.iter().map(|e| e.error.as_str()),
+            )),
+            Arc::new(StringArray::from_iter_values(
+                rejected_rows.iter().map(|e| e.row.as_str()),
+            )),
+        ],
+    )?;
+
+    Ok(batch)
+}
+
+#[async_trait]
+impl QueryExecution for CopyIntoTableExecution {
+    async fn start(&self) -> Result<Output> {
+        let (task, abort_handle) = futures::future::abortable(self.start());
+
+        {
+            *self.abort_handle.lock() = Some(abort_handle);
+        }
+
+        task.await.map_err(|_| QueryError::Cancel)?
+    }
+
+    fn cancel(&self) -> Result<()> {
+        debug!(
+            "cancel copy into table execution: query_id: {:?}, sql: {}, state: {:?}",
+            &self.query_state_machine.query_id,
+            self.query_state_machine.query.content(),
+            self.query_state_machine.state()
+        );
+
+        // change state
+        self.query_state_machine.cancel();
+        // stop future task
+        if let Some(e) = self.abort_handle.lock().as_ref() {
+            e.abort()
+        };
+
+        Ok(())
+    }
+
+    fn info(&self) -> QueryInfo {
+        let qsm = &self.query_state_machine;
+        QueryInfo::new(
+            qsm.query_id,
+            qsm.query.content().to_string(),
+            *qsm.session.tenant_id(),
+            qsm.session.tenant().to_string(),
+            qsm.query.context().user_info().desc().clone(),
+        )
+        .with_priority(qsm.query.context().session_config().priority())
+    }
+
+    fn status(&self) -> QueryStatus {
+        QueryStatus::new(
+            self.query_state_machine.state().clone(),
+            self.query_state_machine.duration(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use datafusion::arrow::array::Array;
+
+    use super::*;
+
+    #[test]
+    fn test_rejected_rows_to_batch() {
+        let rejected_rows = vec![
+            RejectedRow {
+                line: 2,
+                error: "bad value".to_string(),
+                row: "a,b".to_string(),
+            },
+            RejectedRow {
+                line: 5,
+                error: "bad value".to_string(),
+                row: "c,d".to_string(),
+            },
+        ];
+
+        let batch = rejected_rows_to_batch("file:///tmp/a.csv", &rejected_rows, 100).unwrap();
+        assert_eq!(batch.num_rows(), 2);
+        assert_eq!(batch.num_columns(), REJECTED_ROWS_COLUMNS.len());
+
+        let time = as_primitive_array::<datafusion::arrow::datatypes::TimestampNanosecondType>(
+            batch.column(0),
+        );
+        assert_eq!(time.value(0), 102);
+        assert_eq!(time.value(1), 105);
+        assert_eq!(batch.column(4).len(), 2);
+    }
+
+    fn split_rows(mut splitter: RowSplitter, data: &[u8], chunk_size: usize) -> Vec<(u64, String)> {
+        let mut rows = vec![];
+        for chunk in data.chunks(chunk_size) {
+            splitter.push(chunk, &mut rows);
+        }
+        splitter.finish(&mut rows);
+
+        rows.into_iter()
+            .map(|(line, row)| (line, String::from_utf8(row).unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn test_row_splitter() {
+        let data = b"a,b\r\n1,\"x\r\ny\"\r\n\r\n2,z\r\n3,w";
+        for chunk_size in [1, 3, data.len()] {
+            let rows = split_rows(RowSplitter::csv(b',', true), data, chunk_size);
+            assert_eq!(
+                rows,
+                vec![
+                    (2, "1,\"x\r\ny\"".to_string()),
+                    (5, "2,z".to_string()),
+                    (6, "3,w".to_string()),
+                ]
+            );
+        }
+
+        let data = b"{\"a\": 1}\r\n\n{\"a\": 2}\n";
+        for chunk_size in [1, 4, data.len()] {
+            let rows = split_rows(RowSplitter::lines(), data, chunk_size);
+            assert_eq!(
+                rows,
+                vec![(1, "{\"a\": 1}".to_string()), (3, "{\"a\": 2}".to_string()),]
+            );
+        }
+    }
+
+    #[test]
+    fn test_convert_timestamp() {
+        assert_eq!(
+            convert_timestamp(1, &Precision::MS, &Precision::NS),
+            Some(1_000_000)
+        );
+        assert_eq!(
+            convert_timestamp(1_500_000, &Precision::NS, &Precision::MS),
+            Some(1)
+        );
+        assert_eq!(
+            convert_timestamp(7, &Precision::US, &Precision::US),
+            Some(7)
+        );
+        assert_eq!(
+            convert_timestamp(i64::MAX, &Precision::MS, &Precision::US),
+            None
+        );
+    }
+}
@@ -64,4 +64,13 @@ fn build_database_schema(database_options: &DatabaseOptions, config: &mut Databa
     if let Some(precision) = database_options.precision() {
         config.with_precision(precision.clone());
     }
+    if let Some(compaction) = database_options.compaction() {
+        config.with_compaction(*compaction);
+    }
+    if let Some(compaction_window) = database_options.compaction_window() {
+        config.with_compaction_window(compaction_window.clone());
+    }
+    if let Some(cold_duration) = database_options.cold_duration() {
+        config.with_cold_duration(cold_duration.clone());
+    }
 }
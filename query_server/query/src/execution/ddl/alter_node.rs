@@ -0,0 +1,43 @@
+use async_trait::async_trait;
+use protos::kv_service::admin_command_request::Command::SetIoRateLimit;
+use protos::kv_service::{AdminCommandRequest, SetIoRateLimitRequest};
+use spi::query::execution::{Output, QueryStateMachineRef};
+use spi::query::logical_planner::AlterNode;
+use spi::Result;
+
+use super::DDLDefinitionTask;
+
+pub struct AlterNodeTask {
+    stmt: AlterNode,
+}
+
+impl AlterNodeTask {
+    #[inline(always)]
+    pub fn new(stmt: AlterNode) -> Self {
+        Self { stmt }
+    }
+}
+
+#[async_trait]
+impl DDLDefinitionTask for AlterNodeTask {
+    async fn execute(&self, query_state_machine: QueryStateMachineRef) -> Result<Output> {
+        let AlterNode {
+            node_id,
+            io_rate_limit,
+        } = self.stmt;
+        let tenant = query_state_machine.session.tenant();
+
+        let req = AdminCommandRequest {
+            tenant: tenant.to_string(),
+            command: Some(SetIoRateLimit(SetIoRateLimitRequest {
+                bytes_per_sec: io_rate_limit,
+            })),
+        };
+        query_state_machine
+            .coord
+            .exec_admin_command(node_id, req)
+            .await?;
+
+        Ok(Output::Nil(()))
+    }
+}
@@ -21,6 +21,7 @@ use spi::query::execution::{Output, QueryStateMachineRef};
 use spi::{DatafusionSnafu, Result};
 
 use super::DDLDefinitionTask;
+use crate::data_source::file_format::orc::{orc_listing_options, ORC_FILE_TYPE};
 
 pub struct CreateExternalTableTask {
     stmt: CreateExternalTable,
@@ -167,6 +168,10 @@ fn build_external_table_config(
     target_partitions: usize,
 ) -> Result<ListingOptions> {
     let file_compression_type = FileCompressionType::from(stmt.file_compression_type);
+    if stmt.file_type == ORC_FILE_TYPE {
+        return Ok(orc_listing_options(target_partitions));
+    }
+
     let file_type = FileType::from_str(stmt.file_type.as_str())?;
     let file_extension = file_type.get_ext_with_compression(file_compression_type.to_owned())?;
     let file_format: Arc<dyn FileFormat> = match file_type {
@@ -0,0 +1,72 @@
+use async_trait::async_trait;
+use meta::error::MetaError;
+use models::meta_data::PipeInfo;
+use models::oid::Identifier;
+use spi::query::execution::{Output, QueryStateMachineRef};
+use spi::query::logical_planner::CreatePipe;
+use spi::{QueryError, Result};
+use trace::debug;
+
+use crate::execution::ddl::DDLDefinitionTask;
+
+pub struct CreatePipeTask {
+    stmt: CreatePipe,
+}
+
+impl CreatePipeTask {
+    pub fn new(stmt: CreatePipe) -> Self {
+        Self { stmt }
+    }
+}
+
+#[async_trait]
+impl DDLDefinitionTask for CreatePipeTask {
+    async fn execute(&self, query_state_machine: QueryStateMachineRef) -> Result<Output> {
+        let CreatePipe {
+            ref tenant_name,
+            ref name,
+            ref if_not_exists,
+            ref database,
+            ref table,
+            ref interval,
+        } = self.stmt;
+
+        let meta = query_state_machine
+            .meta
+            .tenant_manager()
+            .tenant_meta(tenant_name)
+            .await
+            .ok_or_else(|| QueryError::Meta {
+                source: MetaError::TenantNotFound {
+                    tenant: tenant_name.to_string(),
+                },
+            })?;
+
+        // The pipe is run by the node it's created on, as the user creating it,
+        // the statement is parsed again when the pipe is run.
+        let pipe = PipeInfo {
+            name: name.clone(),
+            owner: query_state_machine.session.user().desc().name().to_string(),
+            database: database.clone(),
+            table: table.clone(),
+            node_id: query_state_machine.coord.node_id(),
+            lease_expire_time: 0,
+            interval: interval.as_millis() as u64,
+            definition: query_state_machine.query.content().to_string(),
+            create_time: chrono::Utc::now().timestamp_nanos(),
+            status: Default::default(),
+        };
+
+        debug!("Create pipe {:?} of tenant {}", pipe, tenant_name);
+        match meta.create_pipe(pipe).await {
+            Err(MetaError::PipeAlreadyExists { .. }) if *if_not_exists => Ok(Output::Nil(())),
+            Err(MetaError::PipeAlreadyExists { .. }) => Err(QueryError::Meta {
+                source: MetaError::PipeAlreadyExists { pipe: name.clone() },
+            }),
+            result => {
+                result?;
+                Ok(Output::Nil(()))
+            }
+        }
+    }
+}
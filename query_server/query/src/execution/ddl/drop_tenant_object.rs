@@ -1,5 +1,6 @@
 use async_trait::async_trait;
 use meta::error::MetaError;
+use models::oid::Identifier;
 use protos::kv_service::admin_command_request::Command::DropDb;
 use protos::kv_service::{AdminCommandRequest, DropDbRequest};
 use spi::query::execution::{Output, QueryStateMachineRef};
@@ -65,6 +66,34 @@ impl DDLDefinitionTask for DropTenantObjectTask {
                 Ok(Output::Nil(()))
             }
 
+            TenantObjectType::Pipe => {
+                // A pipe can be dropped by its owner or the owner of the tenant
+                let pipe = meta.pipes().await?.into_iter().find(|e| &e.name == name);
+                let user = query_state_machine.session.user();
+                if let Some(pipe) = pipe {
+                    if pipe.owner != user.desc().name()
+                        && !user.can_access_system(*meta.tenant().id())
+                    {
+                        return Err(QueryError::InsufficientPrivileges {
+                            privilege: format!("owner of pipe {}", name),
+                        });
+                    }
+                }
+
+                debug!("Drop pipe {} of tenant {}", name, tenant_name);
+                let success = meta.drop_pipe(name).await?;
+
+                if let (false, false) = (if_exist, success) {
+                    return Err(QueryError::Meta {
+                        source: MetaError::PipeNotFound {
+                            pipe: name.to_string(),
+                        },
+                    });
+                }
+
+                Ok(Output::Nil(()))
+            }
+
             TenantObjectType::Database => {
                 // 删除租户下的database
                 // tenant_id
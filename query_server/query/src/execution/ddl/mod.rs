@@ -7,6 +7,7 @@ use spi::Result;
 use self::alter_tenant::AlterTenantTask;
 use self::alter_user::AlterUserTask;
 use self::create_external_table::CreateExternalTableTask;
+use self::create_pipe::CreatePipeTask;
 use self::create_role::CreateRoleTask;
 use self::create_table::CreateTableTask;
 use self::create_tenant::CreateTenantTask;
@@ -16,6 +17,7 @@ use self::drop_global_object::DropGlobalObjectTask;
 use self::drop_tenant_object::DropTenantObjectTask;
 use self::grant_revoke::GrantRevokeTask;
 use crate::execution::ddl::alter_database::AlterDatabaseTask;
+use crate::execution::ddl::alter_node::AlterNodeTask;
 use crate::execution::ddl::alter_table::AlterTableTask;
 use crate::execution::ddl::checksum_group::ChecksumGroupTask;
 use crate::execution::ddl::compact_vnode::CompactVnodeTask;
@@ -26,9 +28,11 @@ use crate::execution::ddl::describe_table::DescribeTableTask;
 use crate::execution::ddl::drop_vnode::DropVnodeTask;
 use crate::execution::ddl::move_node::MoveVnodeTask;
 use crate::execution::ddl::show_database::ShowDatabasesTask;
+use crate::execution::ddl::show_pipes::ShowPipesTask;
 use crate::execution::ddl::show_table::ShowTablesTask;
 
 mod alter_database;
+mod alter_node;
 mod alter_table;
 mod alter_tenant;
 mod alter_user;
@@ -37,6 +41,7 @@ mod compact_vnode;
 mod copy_vnode;
 mod create_database;
 mod create_external_table;
+mod create_pipe;
 mod create_role;
 mod create_table;
 mod create_tenant;
@@ -50,6 +55,7 @@ mod drop_vnode;
 mod grant_revoke;
 mod move_node;
 mod show_database;
+mod show_pipes;
 mod show_table;
 
 /// Traits that DDL tasks should implement
@@ -144,12 +150,14 @@ impl DDLDefinitionTaskFactory {
             DDLPlan::CreateTenant(sub_plan) => Box::new(CreateTenantTask::new(*sub_plan.clone())),
             DDLPlan::CreateUser(sub_plan) => Box::new(CreateUserTask::new(sub_plan.clone())),
             DDLPlan::CreateRole(sub_plan) => Box::new(CreateRoleTask::new(sub_plan.clone())),
+            DDLPlan::CreatePipe(sub_plan) => Box::new(CreatePipeTask::new(sub_plan.clone())),
             DDLPlan::DescribeDatabase(sub_plan) => {
                 Box::new(DescribeDatabaseTask::new(sub_plan.clone()))
             }
             DDLPlan::DescribeTable(sub_plan) => Box::new(DescribeTableTask::new(sub_plan.clone())),
             DDLPlan::ShowTables(sub_plan) => Box::new(ShowTablesTask::new(sub_plan.clone())),
             DDLPlan::ShowDatabases() => Box::new(ShowDatabasesTask::new()),
+            DDLPlan::ShowPipes => Box::new(ShowPipesTask::new()),
             DDLPlan::AlterDatabase(sub_plan) => Box::new(AlterDatabaseTask::new(sub_plan.clone())),
             DDLPlan::AlterTable(sub_plan) => Box::new(AlterTableTask::new(sub_plan.clone())),
             DDLPlan::AlterTenant(sub_plan) => Box::new(AlterTenantTask::new(sub_plan.clone())),
@@ -160,6 +168,7 @@ impl DDLDefinitionTaskFactory {
             DDLPlan::MoveVnode(sub_plan) => Box::new(MoveVnodeTask::new(sub_plan.clone())),
             DDLPlan::CompactVnode(sub_plan) => Box::new(CompactVnodeTask::new(sub_plan.clone())),
             DDLPlan::ChecksumGroup(sub_plan) => Box::new(ChecksumGroupTask::new(sub_plan.clone())),
+            DDLPlan::AlterNode(sub_plan) => Box::new(AlterNodeTask::new(sub_plan.clone())),
         }
     }
 }
@@ -0,0 +1,104 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use datafusion::arrow::array::{StringArray, TimestampNanosecondArray, UInt64Array};
+use datafusion::arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+use datafusion::arrow::record_batch::RecordBatch;
+use meta::error::MetaError;
+use models::oid::Identifier;
+use spi::query::execution::{Output, QueryStateMachineRef};
+use spi::Result;
+
+use crate::execution::ddl::DDLDefinitionTask;
+
+pub struct ShowPipesTask {}
+
+impl ShowPipesTask {
+    pub fn new() -> Self {
+        ShowPipesTask {}
+    }
+}
+
+#[async_trait]
+impl DDLDefinitionTask for ShowPipesTask {
+    async fn execute(&self, query_state_machine: QueryStateMachineRef) -> Result<Output> {
+        show_pipes(query_state_machine).await
+    }
+}
+
+async fn show_pipes(machine: QueryStateMachineRef) -> Result<Output> {
+    let tenant = machine.session.tenant();
+    let client = machine
+        .meta
+        .tenant_manager()
+        .tenant_meta(tenant)
+        .await
+        .ok_or(MetaError::TenantNotFound {
+            tenant: tenant.to_string(),
+        })?;
+
+    // Non-owner members only see their own pipes
+    let user = machine.session.user();
+    let can_access_all = user.can_access_system(*machine.session.tenant_id());
+    let mut pipes = client
+        .pipes()
+        .await?
+        .into_iter()
+        .filter(|e| can_access_all || e.owner == user.desc().name())
+        .collect::<Vec<_>>();
+    pipes.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("pipe_name", DataType::Utf8, false),
+        Field::new("table_name", DataType::Utf8, false),
+        Field::new("interval", DataType::Utf8, false),
+        Field::new("state", DataType::Utf8, false),
+        Field::new("files_loaded", DataType::UInt64, false),
+        Field::new("rows_loaded", DataType::UInt64, false),
+        Field::new("files_failed", DataType::UInt64, false),
+        Field::new(
+            "last_load_time",
+            DataType::Timestamp(TimeUnit::Nanosecond, None),
+            true,
+        ),
+        Field::new("last_error", DataType::Utf8, true),
+    ]));
+
+    let batch = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(StringArray::from_iter_values(
+                pipes.iter().map(|e| e.name.as_str()),
+            )),
+            Arc::new(StringArray::from_iter_values(
+                pipes.iter().map(|e| e.table.as_str()),
+            )),
+            Arc::new(StringArray::from_iter_values(
+                pipes
+                    .iter()
+                    .map(|e| format!("{:?}", Duration::from_millis(e.interval))),
+            )),
+            Arc::new(StringArray::from_iter_values(
+                pipes.iter().map(|e| e.status.state()),
+            )),
+            Arc::new(UInt64Array::from_iter_values(
+                pipes.iter().map(|e| e.status.files_loaded),
+            )),
+            Arc::new(UInt64Array::from_iter_values(
+                pipes.iter().map(|e| e.status.rows_loaded),
+            )),
+            Arc::new(UInt64Array::from_iter_values(
+                pipes.iter().map(|e| e.status.files_failed),
+            )),
+            Arc::new(TimestampNanosecondArray::from_iter(
+                pipes.iter().map(|e| e.status.last_load_time),
+            )),
+            Arc::new(StringArray::from_iter(
+                pipes.iter().map(|e| e.status.last_error.as_deref()),
+            )),
+        ],
+    )?;
+
+    Ok(Output::StreamData(schema, vec![batch]))
+}
@@ -5,6 +5,7 @@ use spi::query::logical_planner::Plan;
 use spi::query::optimizer::Optimizer;
 use spi::query::scheduler::SchedulerRef;
 
+use super::copy::CopyIntoTableExecution;
 use super::query::SqlQueryExecution;
 use super::sys::SystemExecution;
 use crate::dispatcher::query_tracker::QueryTracker;
@@ -44,6 +45,12 @@ impl QueryExecutionFactory for SqlQueryExecutionFactory {
                 self.optimizer.clone(),
                 self.scheduler.clone(),
             )),
+            Plan::CopyIntoTable(copy_plan) => Arc::new(CopyIntoTableExecution::new(
+                state_machine,
+                copy_plan,
+                self.optimizer.clone(),
+                self.scheduler.clone(),
+            )),
             Plan::DDL(ddl_plan) => Arc::new(DDLExecution::new(state_machine, ddl_plan)),
             Plan::SYSTEM(sys_plan) => Arc::new(SystemExecution::new(
                 state_machine,
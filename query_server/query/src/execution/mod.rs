@@ -1,3 +1,4 @@
+pub mod copy;
 mod ddl;
 pub mod factory;
 mod query;
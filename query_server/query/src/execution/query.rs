@@ -1,8 +1,15 @@
 use std::sync::Arc;
 
 use async_trait::async_trait;
+use datafusion::arrow::array::make_array;
+use datafusion::arrow::compute::cast;
+use datafusion::arrow::datatypes::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use datafusion::arrow::error::ArrowError;
+use datafusion::arrow::record_batch::RecordBatch;
 use futures::stream::AbortHandle;
 use futures::TryStreamExt;
+use models::schema::Precision;
+use models::time_zone::TimeZone;
 use parking_lot::Mutex;
 use spi::query::dispatcher::{QueryInfo, QueryStatus};
 use spi::query::execution::{Output, QueryExecution, QueryStateMachineRef};
@@ -42,10 +49,14 @@ impl SqlQueryExecution {
     async fn start(&self) -> Result<Output> {
         // begin optimize
         self.query_state_machine.begin_optimize();
-        let optimized_physical_plan = self
-            .optimizer
-            .optimize(&self.plan.df_plan, &self.query_state_machine.session)
-            .await?;
+        let session = &self.query_state_machine.session;
+        let optimized_physical_plan = if self.plan.optimized {
+            self.optimizer
+                .create_physical_plan(&self.plan.df_plan, session)
+                .await?
+        } else {
+            self.optimizer.optimize(&self.plan.df_plan, session).await?
+        };
         self.query_state_machine.end_optimize();
 
         // begin schedule
@@ -62,10 +73,121 @@ impl SqlQueryExecution {
         let execution_result = stream.try_collect::<Vec<_>>().await?;
         self.query_state_machine.end_schedule();
 
+        let session_config = self.query_state_machine.query.context().session_config();
+        let (schema_ref, execution_result) = match session_config.precision() {
+            Some(precision) => cast_timestamp_precision(schema_ref, execution_result, precision)?,
+            None => (schema_ref, execution_result),
+        };
+
+        let (schema_ref, execution_result) = match session_config.time_zone() {
+            time_zone if time_zone.is_utc() => (schema_ref, execution_result),
+            time_zone => with_time_zone(schema_ref, execution_result, time_zone)?,
+        };
+
         Ok(Output::StreamData(schema_ref, execution_result))
     }
 }
 
+/// Attach the session time zone to the timestamp columns of the result,
+/// so that the UTC timestamps are displayed as the wall-clock time of the time zone.
+///
+/// Only the data types change, timestamp columns already with a time zone are kept,
+/// e.g. the wall-clock times returned by FROM_UTC_TIMESTAMP.
+fn with_time_zone(
+    schema: SchemaRef,
+    batches: Vec<RecordBatch>,
+    time_zone: &TimeZone,
+) -> Result<(SchemaRef, Vec<RecordBatch>)> {
+    let time_zone = time_zone.to_string();
+    let fields = schema
+        .fields()
+        .iter()
+        .map(|f| match f.data_type() {
+            DataType::Timestamp(unit, None) => Field::new(
+                f.name(),
+                DataType::Timestamp(unit.clone(), Some(time_zone.clone())),
+                f.is_nullable(),
+            ),
+            _ => f.clone(),
+        })
+        .collect::<Vec<_>>();
+    let target_schema = Arc::new(Schema::new_with_metadata(fields, schema.metadata().clone()));
+    if target_schema == schema {
+        return Ok((schema, batches));
+    }
+
+    let batches = batches
+        .into_iter()
+        .map(|batch| {
+            let columns = batch
+                .columns()
+                .iter()
+                .zip(target_schema.fields())
+                .map(|(column, field)| {
+                    if column.data_type() == field.data_type() {
+                        return Ok(column.clone());
+                    }
+                    let data = column
+                        .data()
+                        .clone()
+                        .into_builder()
+                        .data_type(field.data_type().clone())
+                        .build()?;
+                    Ok(make_array(data))
+                })
+                .collect::<std::result::Result<Vec<_>, ArrowError>>()?;
+            RecordBatch::try_new(target_schema.clone(), columns)
+        })
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    Ok((target_schema, batches))
+}
+
+/// Cast all timestamp columns of the result to the precision of the session
+fn cast_timestamp_precision(
+    schema: SchemaRef,
+    batches: Vec<RecordBatch>,
+    precision: &Precision,
+) -> Result<(SchemaRef, Vec<RecordBatch>)> {
+    let unit = match precision {
+        Precision::MS => TimeUnit::Millisecond,
+        Precision::US => TimeUnit::Microsecond,
+        Precision::NS => TimeUnit::Nanosecond,
+    };
+
+    let fields = schema
+        .fields()
+        .iter()
+        .map(|f| match f.data_type() {
+            DataType::Timestamp(_, tz) => Field::new(
+                f.name(),
+                DataType::Timestamp(unit, tz.clone()),
+                f.is_nullable(),
+            ),
+            _ => f.clone(),
+        })
+        .collect::<Vec<_>>();
+    let target_schema = Arc::new(Schema::new_with_metadata(fields, schema.metadata().clone()));
+    if target_schema == schema {
+        return Ok((schema, batches));
+    }
+
+    let batches = batches
+        .into_iter()
+        .map(|batch| {
+            let columns = batch
+                .columns()
+                .iter()
+                .zip(target_schema.fields())
+                .map(|(column, field)| cast(column, field.data_type()))
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            RecordBatch::try_new(target_schema.clone(), columns)
+        })
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    Ok((target_schema, batches))
+}
+
 #[async_trait]
 impl QueryExecution for SqlQueryExecution {
     async fn start(&self) -> Result<Output> {
@@ -111,6 +233,7 @@ impl QueryExecution for SqlQueryExecution {
             qsm.session.tenant().to_string(),
             qsm.query.context().user_info().desc().clone(),
         )
+        .with_priority(qsm.query.context().session_config().priority())
     }
 
     fn status(&self) -> QueryStatus {
@@ -120,3 +243,59 @@ impl QueryExecution for SqlQueryExecution {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use datafusion::arrow::array::{Array, TimestampNanosecondArray};
+    use datafusion::arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+    use datafusion::arrow::record_batch::RecordBatch;
+    use models::time_zone::TimeZone;
+
+    use super::with_time_zone;
+
+    #[test]
+    fn test_with_time_zone() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new(
+                "time",
+                DataType::Timestamp(TimeUnit::Nanosecond, None),
+                false,
+            ),
+            Field::new(
+                "local",
+                DataType::Timestamp(TimeUnit::Nanosecond, Some("+00:00".to_string())),
+                false,
+            ),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(TimestampNanosecondArray::from(vec![1])),
+                Arc::new(
+                    TimestampNanosecondArray::from(vec![2]).with_timezone("+00:00".to_string()),
+                ),
+            ],
+        )
+        .unwrap();
+
+        let time_zone = "Asia/Shanghai".parse::<TimeZone>().unwrap();
+        let (schema, batches) = with_time_zone(schema, vec![batch], &time_zone).unwrap();
+        let expected_types = [
+            DataType::Timestamp(TimeUnit::Nanosecond, Some("Asia/Shanghai".to_string())),
+            DataType::Timestamp(TimeUnit::Nanosecond, Some("+00:00".to_string())),
+        ];
+        for (i, expected_type) in expected_types.iter().enumerate() {
+            assert_eq!(schema.field(i).data_type(), expected_type);
+            assert_eq!(batches[0].column(i).data_type(), expected_type);
+        }
+        // The values are still the UTC timestamps
+        let time = batches[0]
+            .column(0)
+            .as_any()
+            .downcast_ref::<TimestampNanosecondArray>()
+            .unwrap();
+        assert_eq!(time.value(0), 1);
+    }
+}
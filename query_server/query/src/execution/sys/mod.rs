@@ -1,4 +1,5 @@
 mod kill_query;
+mod session_variable;
 mod show_queries;
 
 use std::sync::Arc;
@@ -10,6 +11,7 @@ use spi::query::logical_planner::SYSPlan;
 use spi::Result;
 
 use self::kill_query::KillQueryTask;
+use self::session_variable::{ResetVariableTask, SetVariableTask, ShowVariableTask};
 use self::show_queries::ShowQueriesTask;
 use crate::dispatcher::query_tracker::QueryTracker;
 
@@ -94,6 +96,11 @@ impl SystemTaskFactory {
             SYSPlan::KillQuery(query_id) => {
                 Box::new(KillQueryTask::new(self.query_tracker.clone(), *query_id))
             }
+            SYSPlan::SetVariable(variable, value) => {
+                Box::new(SetVariableTask::new(*variable, value.clone()))
+            }
+            SYSPlan::ShowVariable(variable) => Box::new(ShowVariableTask::new(*variable)),
+            SYSPlan::ResetVariable(variable) => Box::new(ResetVariableTask::new(*variable)),
         }
     }
 }
@@ -0,0 +1,212 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use datafusion::arrow::array::{ArrayRef, StringBuilder};
+use datafusion::arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use datafusion::arrow::error::ArrowError;
+use datafusion::arrow::record_batch::RecordBatch;
+use spi::query::execution::{Output, QueryStateMachineRef};
+use spi::query::session::{CnosSessionConfig, SessionVariable};
+use spi::service::protocol::{Context, VariableStore};
+use spi::{QueryError, Result};
+
+use super::SystemTask;
+
+/// `SET <variable> = <value>`
+///
+/// The value is validated and returned in the normalized form, it is kept by the
+/// [`VariableStore`] of the request and is rejected if there is nowhere to keep it.
+pub struct SetVariableTask {
+    variable: SessionVariable,
+    value: String,
+}
+
+impl SetVariableTask {
+    pub fn new(variable: SessionVariable, value: String) -> Self {
+        Self { variable, value }
+    }
+}
+
+#[async_trait]
+impl SystemTask for SetVariableTask {
+    async fn execute(&self, query_state_machine: QueryStateMachineRef) -> Result<Output> {
+        let context = query_state_machine.query.context();
+        let session_config = context
+            .session_config()
+            .clone()
+            .set_variable(self.variable, &self.value)?;
+
+        let value = session_config
+            .variable(self.variable)
+            .unwrap_or_else(|| self.value.clone());
+
+        match context.variable_store() {
+            VariableStore::Unavailable => return Err(QueryError::SessionVariablesUnsupported),
+            VariableStore::Client => {}
+            VariableStore::Server(variables) => {
+                variables.write().insert(self.variable, value.clone());
+            }
+        }
+
+        let mut result_builder = VariablesResultBuilder::new(false);
+        result_builder.append(self.variable, &value);
+
+        Ok(Output::StreamData(
+            result_builder.schema(),
+            result_builder.build()?,
+        ))
+    }
+}
+
+/// `SHOW { <variable> | ALL }`
+pub struct ShowVariableTask {
+    variable: Option<SessionVariable>,
+}
+
+impl ShowVariableTask {
+    pub fn new(variable: Option<SessionVariable>) -> Self {
+        Self { variable }
+    }
+}
+
+#[async_trait]
+impl SystemTask for ShowVariableTask {
+    async fn execute(&self, query_state_machine: QueryStateMachineRef) -> Result<Output> {
+        let context = query_state_machine.query.context();
+        let variables = match self.variable {
+            Some(variable) => vec![variable],
+            None => SessionVariable::ALL.to_vec(),
+        };
+
+        let mut result_builder = VariablesResultBuilder::new(self.variable.is_none());
+        for variable in variables {
+            result_builder.append(
+                variable,
+                &variable_value(context, context.session_config(), variable),
+            );
+        }
+
+        Ok(Output::StreamData(
+            result_builder.schema(),
+            result_builder.build()?,
+        ))
+    }
+}
+
+/// `RESET { <variable> | ALL }`, returns the default values
+///
+/// Like `SET`, it is rejected if the [`VariableStore`] of the request can't keep variables.
+pub struct ResetVariableTask {
+    variable: Option<SessionVariable>,
+}
+
+impl ResetVariableTask {
+    pub fn new(variable: Option<SessionVariable>) -> Self {
+        Self { variable }
+    }
+}
+
+#[async_trait]
+impl SystemTask for ResetVariableTask {
+    async fn execute(&self, query_state_machine: QueryStateMachineRef) -> Result<Output> {
+        let context = query_state_machine.query.context();
+        let variables = match self.variable {
+            Some(variable) => vec![variable],
+            None => SessionVariable::ALL.to_vec(),
+        };
+
+        match context.variable_store() {
+            VariableStore::Unavailable => return Err(QueryError::SessionVariablesUnsupported),
+            VariableStore::Client => {}
+            VariableStore::Server(stored) => {
+                let mut stored = stored.write();
+                for variable in &variables {
+                    stored.remove(variable);
+                }
+            }
+        }
+
+        let mut result_builder = VariablesResultBuilder::new(false);
+        for variable in variables {
+            let session_config = context.session_config().clone().reset_variable(variable);
+            result_builder.append(
+                variable,
+                &variable_value(context, &session_config, variable),
+            );
+        }
+
+        Ok(Output::StreamData(
+            result_builder.schema(),
+            result_builder.build()?,
+        ))
+    }
+}
+
+fn variable_value(
+    context: &Context,
+    session_config: &CnosSessionConfig,
+    variable: SessionVariable,
+) -> String {
+    session_config
+        .variable(variable)
+        .unwrap_or_else(|| context.database().to_string())
+}
+
+struct VariablesResultBuilder {
+    schema: SchemaRef,
+    with_description: bool,
+
+    variables: StringBuilder,
+    values: StringBuilder,
+    descriptions: StringBuilder,
+}
+
+impl VariablesResultBuilder {
+    fn new(with_description: bool) -> Self {
+        let mut fields = vec![
+            Field::new("variable", DataType::Utf8, false),
+            Field::new("value", DataType::Utf8, false),
+        ];
+        if with_description {
+            fields.push(Field::new("description", DataType::Utf8, false));
+        }
+
+        Self {
+            schema: Arc::new(Schema::new(fields)),
+            with_description,
+            variables: StringBuilder::new(),
+            values: StringBuilder::new(),
+            descriptions: StringBuilder::new(),
+        }
+    }
+
+    fn append(&mut self, variable: SessionVariable, value: &str) {
+        self.variables.append_value(variable.as_ref());
+        self.values.append_value(value);
+        self.descriptions.append_value(variable.description());
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn build(self) -> std::result::Result<Vec<RecordBatch>, ArrowError> {
+        let VariablesResultBuilder {
+            schema,
+            with_description,
+            mut variables,
+            mut values,
+            mut descriptions,
+        } = self;
+
+        let mut columns: Vec<ArrayRef> =
+            vec![Arc::new(variables.finish()), Arc::new(values.finish())];
+        if with_description {
+            columns.push(Arc::new(descriptions.finish()));
+        }
+
+        let batch = RecordBatch::try_new(schema, columns)?;
+
+        Ok(vec![batch])
+    }
+}
@@ -1,8 +1,9 @@
+use datafusion::arrow::datatypes::DataType;
 use datafusion::error::Result;
 use datafusion::logical_expr::expr_visitor::{ExprVisitable, ExpressionVisitor, Recursion};
 use datafusion::logical_expr::{BinaryExpr, Operator};
 use datafusion::prelude::Expr;
-use models::schema::TIME_FIELD_NAME;
+use models::schema::{ColumnType, TskvTableSchema, TIME_FIELD_NAME};
 
 use super::selector_function::{BOTTOM, TOPK};
 
@@ -19,6 +20,36 @@ pub fn is_time_filter(expr: &Expr) -> bool {
     }
 }
 
+/// Comparison between a field column and a literal of the field type,
+/// which is evaluated by the tskv scan exactly.
+///
+/// Floats are compared by their total order both in the scan and in arrow,
+/// so NaN matches the same filters of them.
+pub fn is_field_filter(expr: &Expr, table_schema: &TskvTableSchema) -> bool {
+    match expr {
+        Expr::BinaryExpr(BinaryExpr { left, op, right }) => {
+            let (column, value) = match (left.as_ref(), right.as_ref()) {
+                (Expr::Column(column), Expr::Literal(value))
+                | (Expr::Literal(value), Expr::Column(column)) => (column, value),
+                _ => return false,
+            };
+            let column_type = match table_schema.column(&column.name) {
+                Some(column) => column.column_type,
+                None => return false,
+            };
+
+            matches!(column_type, ColumnType::Field(_))
+                && !value.is_null()
+                && value.get_datatype() == DataType::from(column_type)
+                && matches!(
+                    op,
+                    Operator::Eq | Operator::Lt | Operator::LtEq | Operator::Gt | Operator::GtEq
+                )
+        }
+        _ => false,
+    }
+}
+
 pub fn is_time_column(expr: &Expr) -> bool {
     if let Expr::Column(c) = expr {
         c.name == TIME_FIELD_NAME
@@ -51,4 +51,13 @@ impl<'a> FunctionMetadataManager for DFSessionContextFuncAdapter<'a> {
     fn udfs(&self) -> HashSet<String> {
         self.ctx.udfs()
     }
+
+    fn udafs(&self) -> HashSet<String> {
+        self.ctx
+            .state()
+            .aggregate_functions()
+            .keys()
+            .cloned()
+            .collect()
+    }
 }
@@ -9,6 +9,9 @@ mod scalar_function;
 mod selector_function;
 mod window;
 
+pub use scalar_function::{
+    from_utc_timestamp, to_utc_timestamp, FROM_UTC_TIMESTAMP, TO_UTC_TIMESTAMP,
+};
 pub use selector_function::{BOTTOM, TOPK};
 use spi::query::function::FunctionMetadataManager;
 use spi::Result;
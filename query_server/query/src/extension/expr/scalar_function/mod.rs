@@ -1,13 +1,17 @@
 #[cfg(test)]
 mod example;
+mod time_zone;
 
 use spi::query::function::FunctionMetadataManager;
 use spi::Result;
 
-pub fn register_udfs(_func_manager: &mut dyn FunctionMetadataManager) -> Result<()> {
+pub use time_zone::{from_utc_timestamp, to_utc_timestamp, FROM_UTC_TIMESTAMP, TO_UTC_TIMESTAMP};
+
+pub fn register_udfs(func_manager: &mut dyn FunctionMetadataManager) -> Result<()> {
     // extend function...
     // eg.
     //   example::register_udf(func_manager)?;
+    time_zone::register_udfs(func_manager)?;
     Ok(())
 }
 
@@ -0,0 +1,147 @@
+use std::sync::Arc;
+
+use datafusion::arrow::array::{
+    Array, ArrayRef, StringArray, TimestampNanosecondArray, TimestampNanosecondBuilder,
+};
+use datafusion::arrow::compute::cast;
+use datafusion::arrow::datatypes::{DataType, TimeUnit};
+use datafusion::error::DataFusionError;
+use datafusion::logical_expr::type_coercion::aggregates::TIMESTAMPS;
+use datafusion::logical_expr::{
+    ReturnTypeFunction, ScalarUDF, Signature, TypeSignature, Volatility,
+};
+use datafusion::physical_expr::functions::make_scalar_function;
+use models::time_zone::TimeZone;
+use spi::query::function::FunctionMetadataManager;
+use spi::Result;
+
+/// from_utc_timestamp(time, time_zone): the wall-clock time of a UTC timestamp in the time zone.
+///
+/// The result is tagged with [`WALL_CLOCK_TIME_ZONE`], so that it's displayed as it is
+/// instead of being converted to the session time zone again.
+pub const FROM_UTC_TIMESTAMP: &str = "FROM_UTC_TIMESTAMP";
+/// to_utc_timestamp(time, time_zone): the UTC timestamp of a wall-clock time in the time zone
+pub const TO_UTC_TIMESTAMP: &str = "TO_UTC_TIMESTAMP";
+
+/// Time zone of the wall-clock times, the zero offset
+pub const WALL_CLOCK_TIME_ZONE: &str = "+00:00";
+
+pub fn register_udfs(func_manager: &mut dyn FunctionMetadataManager) -> Result<()> {
+    func_manager.register_udf(from_utc_timestamp())?;
+    func_manager.register_udf(to_utc_timestamp())?;
+    Ok(())
+}
+
+pub fn from_utc_timestamp() -> ScalarUDF {
+    new(
+        FROM_UTC_TIMESTAMP,
+        TimeZone::utc_to_local,
+        Some(WALL_CLOCK_TIME_ZONE),
+    )
+}
+
+pub fn to_utc_timestamp() -> ScalarUDF {
+    new(TO_UTC_TIMESTAMP, TimeZone::local_to_utc, None)
+}
+
+fn new(
+    name: &'static str,
+    convert: fn(&TimeZone, i64) -> Option<i64>,
+    return_time_zone: Option<&'static str>,
+) -> ScalarUDF {
+    let func = move |args: &[ArrayRef]| {
+        let times = cast(&args[0], &DataType::Timestamp(TimeUnit::Nanosecond, None))?;
+        let times = times
+            .as_any()
+            .downcast_ref::<TimestampNanosecondArray>()
+            .ok_or_else(|| {
+                DataFusionError::Internal(format!("{name} expected a timestamp argument"))
+            })?;
+        let time_zones = args[1]
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .ok_or_else(|| {
+                DataFusionError::Internal(format!("{name} expected a string time zone"))
+            })?;
+
+        // the time zone is a constant in most cases, only parse it when it changes
+        let mut last_time_zone: Option<(&str, TimeZone)> = None;
+        let mut builder = TimestampNanosecondBuilder::with_capacity(times.len());
+        for (time, time_zone) in times.iter().zip(time_zones.iter()) {
+            let (time, time_zone_name) = match (time, time_zone) {
+                (Some(time), Some(time_zone_name)) => (time, time_zone_name),
+                _ => {
+                    builder.append_null();
+                    continue;
+                }
+            };
+
+            let time_zone = match last_time_zone {
+                Some((last_name, time_zone)) if last_name == time_zone_name => time_zone,
+                _ => {
+                    let time_zone = time_zone_name
+                        .parse::<TimeZone>()
+                        .map_err(DataFusionError::Execution)?;
+                    last_time_zone = Some((time_zone_name, time_zone));
+                    time_zone
+                }
+            };
+            builder.append_option(convert(&time_zone, time));
+        }
+
+        let result = builder
+            .finish()
+            .with_timezone_opt(return_time_zone.map(|e| e.to_string()));
+        Ok(Arc::new(result) as ArrayRef)
+    };
+    let func = make_scalar_function(func);
+
+    let type_signatures = TIMESTAMPS
+        .iter()
+        .map(|t| TypeSignature::Exact(vec![t.clone(), DataType::Utf8]))
+        .collect();
+    let signature = Signature::one_of(type_signatures, Volatility::Immutable);
+
+    let return_type: ReturnTypeFunction = Arc::new(move |_| {
+        Ok(Arc::new(DataType::Timestamp(
+            TimeUnit::Nanosecond,
+            return_time_zone.map(|e| e.to_string()),
+        )))
+    });
+
+    ScalarUDF::new(name, &signature, &return_type, &func)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use datafusion::arrow::array::{ArrayRef, StringArray, TimestampNanosecondArray};
+    use datafusion::physical_plan::ColumnarValue;
+
+    use super::{from_utc_timestamp, to_utc_timestamp};
+
+    const HOUR: i64 = 3_600_000_000_000;
+
+    fn invoke(udf: datafusion::logical_expr::ScalarUDF, time: i64, time_zone: &str) -> i64 {
+        let args = [
+            ColumnarValue::Array(Arc::new(TimestampNanosecondArray::from(vec![time])) as ArrayRef),
+            ColumnarValue::Array(Arc::new(StringArray::from(vec![time_zone])) as ArrayRef),
+        ];
+        match (udf.fun)(&args).unwrap() {
+            ColumnarValue::Array(array) => array
+                .as_any()
+                .downcast_ref::<TimestampNanosecondArray>()
+                .unwrap()
+                .value(0),
+            ColumnarValue::Scalar(_) => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_time_zone_conversion() {
+        assert_eq!(invoke(from_utc_timestamp(), 0, "Asia/Shanghai"), 8 * HOUR);
+        assert_eq!(invoke(to_utc_timestamp(), 8 * HOUR, "+08:00"), 0);
+        assert_eq!(invoke(from_utc_timestamp(), 0, "-03:30"), -7 * HOUR / 2);
+    }
+}
@@ -2,6 +2,7 @@ pub mod implicit_type_conversion;
 pub mod merge_limit_with_sort;
 pub mod push_down_projection;
 pub mod reject_cross_join;
+pub mod rewrite_avg_to_sum_count;
 pub mod rewrite_tag_scan;
 pub mod transform_bottom_func_to_topk_node;
 pub mod transform_time_window;
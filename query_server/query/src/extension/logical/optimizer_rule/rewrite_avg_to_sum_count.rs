@@ -0,0 +1,136 @@
+use datafusion::arrow::datatypes::DataType;
+use datafusion::common::Column;
+use datafusion::datasource::{source_as_provider, TableProvider};
+use datafusion::error::Result;
+use datafusion::logical_expr::expr::AggregateFunction;
+use datafusion::logical_expr::{
+    aggregate_function, cast, Aggregate, Expr, ExprSchemable, LogicalPlan, LogicalPlanBuilder,
+    TableProviderAggregationPushDown, TableScan,
+};
+use datafusion::optimizer::{OptimizerConfig, OptimizerRule};
+
+use crate::data_source::table_provider::tskv::ClusterTable;
+
+/// Rewrite `AVG(column)` of an aggregate without grouping on a tskv table scan
+/// to `SUM(CAST(column AS Float64)) / CAST(COUNT(column) AS Float64)`,
+/// so that the aggregate can be pushed down to the table scan.
+///
+/// The average can not be merged from the averages of each partition,
+/// but sum and count can. The column is summed as Float64 like `AVG`,
+/// so the sum of integers does not overflow.
+///
+/// Triggering conditions:
+/// 1. The input of the aggregate is a scan of `ClusterTable`
+/// 2. The table supports pushing down the rewritten aggregate functions
+pub struct RewriteAvgToSumCount {}
+
+impl OptimizerRule for RewriteAvgToSumCount {
+    fn try_optimize(
+        &self,
+        plan: &LogicalPlan,
+        optimizer_config: &dyn OptimizerConfig,
+    ) -> Result<Option<LogicalPlan>> {
+        if let LogicalPlan::Aggregate(aggregate) = plan {
+            if let LogicalPlan::TableScan(TableScan { source, .. }) = aggregate.input.as_ref() {
+                if aggregate.group_expr.is_empty()
+                    && aggregate
+                        .aggr_expr
+                        .iter()
+                        .any(|e| avg_argument(e).is_some())
+                {
+                    if let Some(cluster_table) = source_as_provider(source)?
+                        .as_any()
+                        .downcast_ref::<ClusterTable>()
+                    {
+                        let (aggr_expr, projection) = rewrite_aggr_expr(aggregate)?;
+                        // Only handle the aggregate that can be pushed down
+                        if let TableProviderAggregationPushDown::Ungrouped =
+                            cluster_table.supports_aggregate_pushdown(&[], &aggr_expr)?
+                        {
+                            return Ok(Some(
+                                LogicalPlanBuilder::from(aggregate.input.as_ref().clone())
+                                    .aggregate(Vec::<Expr>::new(), aggr_expr)?
+                                    .project(projection)?
+                                    .build()?,
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+
+        // If we didn't find the match pattern, recurse as
+        // normal and build the result.
+        datafusion::optimizer::utils::optimize_children(self, plan, optimizer_config)
+    }
+
+    fn name(&self) -> &str {
+        "rewrite_avg_to_sum_count"
+    }
+}
+
+/// Returns the argument of `AVG(column)`.
+fn avg_argument(expr: &Expr) -> Option<&Expr> {
+    match expr {
+        Expr::AggregateFunction(AggregateFunction {
+            fun: aggregate_function::AggregateFunction::Avg,
+            args,
+            distinct: false,
+            filter: None,
+        }) => match args.as_slice() {
+            [arg @ Expr::Column(_)] => Some(arg),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Returns the aggregate expressions with `AVG` replaced by `SUM` and `COUNT`,
+/// and the projection computing the original aggregate expressions from them.
+fn rewrite_aggr_expr(aggregate: &Aggregate) -> Result<(Vec<Expr>, Vec<Expr>)> {
+    let mut aggr_expr: Vec<Expr> = Vec::with_capacity(aggregate.aggr_expr.len() + 1);
+    let mut projection = Vec::with_capacity(aggregate.aggr_expr.len());
+
+    let mut add_aggr_expr = |expr: Expr| -> Result<Expr> {
+        let column = Expr::Column(Column::from_name(expr.display_name()?));
+        if !aggr_expr.contains(&expr) {
+            aggr_expr.push(expr);
+        }
+        Ok(column)
+    };
+
+    for expr in aggregate.aggr_expr.iter() {
+        let name = expr.display_name()?;
+        let new_expr = match avg_argument(expr) {
+            Some(arg) => {
+                let sum_arg = if arg.get_type(aggregate.input.schema())? == DataType::Float64 {
+                    arg.clone()
+                } else {
+                    cast(arg.clone(), DataType::Float64)
+                };
+                let sum = add_aggr_expr(aggregate_expr(
+                    aggregate_function::AggregateFunction::Sum,
+                    sum_arg,
+                ))?;
+                let count = add_aggr_expr(aggregate_expr(
+                    aggregate_function::AggregateFunction::Count,
+                    arg.clone(),
+                ))?;
+                sum / cast(count, DataType::Float64)
+            }
+            None => add_aggr_expr(expr.clone())?,
+        };
+        projection.push(new_expr.alias(name));
+    }
+
+    Ok((aggr_expr, projection))
+}
+
+fn aggregate_expr(fun: aggregate_function::AggregateFunction, arg: Expr) -> Expr {
+    Expr::AggregateFunction(AggregateFunction {
+        fun,
+        args: vec![arg],
+        distinct: false,
+        filter: None,
+    })
+}
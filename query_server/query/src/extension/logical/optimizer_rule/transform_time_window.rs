@@ -1,20 +1,30 @@
+use std::sync::Arc;
 use std::time::Duration;
 
+use chrono::{Datelike, NaiveDate};
+use datafusion::arrow::array::{Array, ArrayRef, Int64Array};
 use datafusion::arrow::datatypes::{DataType, TimeUnit};
 use datafusion::error::{DataFusionError, Result};
 use datafusion::logical_expr::utils::expand_wildcard;
-use datafusion::logical_expr::{LogicalPlan, LogicalPlanBuilder};
+use datafusion::logical_expr::{
+    BuiltinScalarFunction, Cast, LogicalPlan, LogicalPlanBuilder, ReturnTypeFunction, ScalarUDF,
+    Signature, Volatility,
+};
 use datafusion::optimizer::{OptimizerConfig, OptimizerRule};
+use datafusion::physical_expr::functions::make_scalar_function;
 use datafusion::prelude::{and, cast, col, lit, Expr};
 use datafusion::scalar::ScalarValue;
 use lazy_static::lazy_static;
 use models::duration::{DAY, HOUR, MINUTE, MS, SECOND};
+use models::time_zone::{datetime_to_nanos, nanos_to_datetime, TimeZone};
 use spi::QueryError;
 use trace::debug;
 
 use crate::extension::expr::expr_fn::{ge, is_not_null, lt, minus, modulo, multiply, plus};
 use crate::extension::expr::expr_utils::find_exprs_in_exprs_deeply_nested;
-use crate::extension::expr::{TIME_WINDOW, WINDOW_END, WINDOW_START};
+use crate::extension::expr::{
+    from_utc_timestamp, to_utc_timestamp, FROM_UTC_TIMESTAMP, TIME_WINDOW, WINDOW_END, WINDOW_START,
+};
 use crate::extension::logical::logical_plan_builder::LogicalPlanBuilderExt;
 use crate::extension::logical::plan_node::LogicalPlanExt;
 
@@ -23,7 +33,19 @@ lazy_static! {
 }
 
 /// Convert the [`TIME_WINDOW`] function to Expand or project
-pub struct TransformTimeWindowRule;
+///
+/// Windows are aligned to the wall-clock time of the session time zone,
+/// so daily windows start at the local midnight, also across daylight saving time transitions.
+/// DATE_BIN is rewritten to bin the wall-clock time the same way.
+pub struct TransformTimeWindowRule {
+    time_zone: TimeZone,
+}
+
+impl TransformTimeWindowRule {
+    pub fn new(time_zone: TimeZone) -> Self {
+        Self { time_zone }
+    }
+}
 
 impl OptimizerRule for TransformTimeWindowRule {
     fn try_optimize(
@@ -43,7 +65,7 @@ impl OptimizerRule for TransformTimeWindowRule {
 
             if window_expressions.len() == 1 {
                 let window_expr = unsafe { window_expressions.get_unchecked(0) };
-                let window = make_time_window(window_expr)
+                let window = make_time_window(window_expr, self.time_zone)
                     .map_err(|e| DataFusionError::External(Box::new(e)))?;
 
                 debug!("Construct time window: {:?}", window);
@@ -66,6 +88,16 @@ impl OptimizerRule for TransformTimeWindowRule {
             }
         }
 
+        // DATE_BIN bins the wall-clock time of the session time zone
+        if !self.time_zone.is_utc() && !find_date_bin_exprs(plan).is_empty() {
+            let plan = plan
+                .clone()
+                .transform_expressions_down(&|expr| local_date_bin(expr, self.time_zone))?;
+            let optimized_plan =
+                datafusion::optimizer::utils::optimize_children(self, &plan, optimizer_config)?;
+            return Ok(Some(optimized_plan.unwrap_or(plan)));
+        }
+
         // If we didn't find the match pattern, recurse as
         // normal and build the result.
         datafusion::optimizer::utils::optimize_children(self, plan, optimizer_config)
@@ -86,7 +118,73 @@ fn find_window_exprs(plan: &LogicalPlan) -> Vec<Expr> {
     })
 }
 
-fn make_time_window(expr: &Expr) -> Result<TimeWindow, QueryError> {
+fn find_date_bin_exprs(plan: &LogicalPlan) -> Vec<Expr> {
+    let exprs = plan.expressions();
+    find_exprs_in_exprs_deeply_nested(&exprs, &|nested_expr| {
+        matches!(
+            nested_expr,
+            Expr::ScalarFunction {
+                fun: BuiltinScalarFunction::DateBin,
+                ..
+            }
+        )
+    })
+}
+
+/// Rewrite `DATE_BIN(stride, source[, origin])` to bin the wall-clock time of the time zone,
+/// so that daily bins start at the local midnight, the default origin is the local 1970-01-01.
+///
+/// `TO_UTC_TIMESTAMP(DATE_BIN(stride, FROM_UTC_TIMESTAMP(source), FROM_UTC_TIMESTAMP(origin)))`
+fn local_date_bin(expr: &Expr, time_zone: TimeZone) -> Option<Expr> {
+    let args = match expr {
+        Expr::ScalarFunction {
+            fun: BuiltinScalarFunction::DateBin,
+            args,
+        } => args,
+        _ => return None,
+    };
+    // Already rewritten
+    if args.get(1).map(is_wall_clock_time).unwrap_or(true) {
+        return None;
+    }
+
+    let ns_type = DataType::Timestamp(TimeUnit::Nanosecond, None);
+    let time_zone = lit(time_zone.to_string());
+    let to_local = |time: &Expr| {
+        let local_time = Expr::ScalarUDF {
+            fun: Arc::new(from_utc_timestamp()),
+            args: vec![cast(time.clone(), ns_type.clone()), time_zone.clone()],
+        };
+        cast(local_time, ns_type.clone())
+    };
+
+    let origin = match args.get(2) {
+        Some(origin) => to_local(origin),
+        None => INIT_TIME.clone(),
+    };
+    let date_bin = Expr::ScalarFunction {
+        fun: BuiltinScalarFunction::DateBin,
+        args: vec![args[0].clone(), to_local(&args[1]), origin],
+    };
+    let utc_date_bin = Expr::ScalarUDF {
+        fun: Arc::new(to_utc_timestamp()),
+        args: vec![date_bin, time_zone],
+    };
+
+    // Keep the name, which is referenced by the parent plans
+    let name = expr.display_name().ok()?;
+    Some(utc_date_bin.alias(name))
+}
+
+fn is_wall_clock_time(expr: &Expr) -> bool {
+    match expr {
+        Expr::Cast(Cast { expr, .. }) => is_wall_clock_time(expr),
+        Expr::ScalarUDF { fun, .. } => fun.name == FROM_UTC_TIMESTAMP,
+        _ => false,
+    }
+}
+
+fn make_time_window(expr: &Expr, time_zone: TimeZone) -> Result<TimeWindow, QueryError> {
     let window_alias = expr.display_name()?;
     match expr {
         Expr::ScalarUDF { fun, args } if fun.name == TIME_WINDOW => {
@@ -101,17 +199,34 @@ fn make_time_window(expr: &Expr) -> Result<TimeWindow, QueryError> {
             let window_duration = unsafe { args.get_unchecked(1) };
             let window_duration = valid_duration(parse_duration_arg(window_duration)?)?;
 
-            let time_window_builder =
-                TimeWindowBuilder::new(window_alias, time_column.clone(), window_duration);
+            let mut time_window_builder =
+                TimeWindowBuilder::new(window_alias, time_column.clone(), window_duration)
+                    .with_time_zone(time_zone);
 
             // time_window(time, interval '10 seconds', interval '5 milliseconds')
             // third arg: slide_duration
             if let Some(slide_duration) = args.get(2) {
                 let slide_duration = valid_duration(parse_duration_arg(slide_duration)?)?;
-                let time_window = time_window_builder
-                    .with_slide_duration(slide_duration)
-                    .build();
-                return Ok(time_window);
+                if window_duration.is_calendar() != slide_duration.is_calendar() {
+                    return Err(QueryError::InvalidTimeWindowParam {
+                        reason: format!(
+                            "Window duration and slide duration must both be calendar durations (mo, y) or neither, but found {window_duration} and {slide_duration}"
+                        ),
+                    });
+                }
+                time_window_builder = time_window_builder.with_slide_duration(slide_duration);
+            }
+
+            // time_window(time, '7d', '7d', '4d')
+            // fourth arg: start_time, the offset of the windows from 1970-01-01 00:00:00 of the session time zone
+            if let Some(start_time) = args.get(3) {
+                let start_time =
+                    to_string(start_time).ok_or_else(|| QueryError::InvalidTimeWindowParam {
+                        reason: format!("{start_time}"),
+                    })?;
+                let start_time = parse_start_time(&start_time)
+                    .map_err(|reason| QueryError::InvalidTimeWindowParam { reason })?;
+                time_window_builder = time_window_builder.with_start_time(start_time);
             }
 
             Ok(time_window_builder.build())
@@ -122,24 +237,35 @@ fn make_time_window(expr: &Expr) -> Result<TimeWindow, QueryError> {
     }
 }
 
-fn valid_duration(dur: Duration) -> Result<Duration, QueryError> {
-    if dur.as_millis() > (365 * DAY).into() || dur.as_millis() == 0 {
-        return Err(QueryError::InvalidTimeWindowParam {
-            reason: format!("Max duration is (0s, 365d], but found {}s", dur.as_secs()),
-        });
+fn valid_duration(dur: WindowDuration) -> Result<WindowDuration, QueryError> {
+    match dur {
+        WindowDuration::Fixed(dur) => {
+            if dur.as_millis() > (365 * DAY).into() || dur.as_millis() == 0 {
+                return Err(QueryError::InvalidTimeWindowParam {
+                    reason: format!("Max duration is (0s, 365d], but found {}s", dur.as_secs()),
+                });
+            }
+        }
+        WindowDuration::Months(months) => {
+            if months > 12 || months == 0 {
+                return Err(QueryError::InvalidTimeWindowParam {
+                    reason: format!("Max duration is (0mo, 1y], but found {months}mo"),
+                });
+            }
+        }
     }
 
     Ok(dur)
 }
 
-/// Convert string time duration to [`Duration`] \
-/// Support duration unit: d | h | m | s | ms
-fn parse_duration_arg(expr: &Expr) -> Result<Duration, QueryError> {
+/// Convert string time duration to [`WindowDuration`] \
+/// Support duration unit: y | mo | d | h | m | s | ms
+fn parse_duration_arg(expr: &Expr) -> Result<WindowDuration, QueryError> {
     let duration = to_string(expr).ok_or_else(|| QueryError::InvalidTimeWindowParam {
         reason: format!("{expr}"),
     })?;
     debug!("duration str: {}", duration);
-    parse_duration(&duration).map_err(|reason| QueryError::InvalidTimeWindowParam { reason })
+    parse_window_duration(&duration).map_err(|reason| QueryError::InvalidTimeWindowParam { reason })
 }
 
 fn to_string(expr: &Expr) -> Option<String> {
@@ -149,52 +275,100 @@ fn to_string(expr: &Expr) -> Option<String> {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WindowDuration {
+    Fixed(Duration),
+    /// Calendar months, the length varies with the month
+    Months(u32),
+}
+
+impl WindowDuration {
+    fn is_calendar(&self) -> bool {
+        matches!(self, Self::Months(_))
+    }
+}
+
+impl std::fmt::Display for WindowDuration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Fixed(dur) => write!(f, "{}ms", dur.as_millis()),
+            Self::Months(months) => write!(f, "{months}mo"),
+        }
+    }
+}
+
 #[derive(Debug)]
 struct TimeWindow {
     window_alias: String,
     time_column: Expr,
     // interval, such as: '5s'
-    window_duration: Duration,
+    window_duration: WindowDuration,
     // interval
-    slide_duration: Duration,
+    slide_duration: WindowDuration,
+    // offset of the windows in nanoseconds
     start_time: i64,
+    time_zone: TimeZone,
 }
 
 impl TimeWindow {
     fn is_tumbling_window(&self) -> bool {
         self.window_duration == self.slide_duration
     }
+
+    /// The number of windows a row may belong to,
+    /// and whether the row belongs to all of them
+    fn overlapping_windows(&self) -> (u128, bool) {
+        let (window, slide) = match (self.window_duration, self.slide_duration) {
+            (WindowDuration::Fixed(window), WindowDuration::Fixed(slide)) => {
+                (window.as_nanos(), slide.as_nanos())
+            }
+            (WindowDuration::Months(window), WindowDuration::Months(slide)) => {
+                (window as u128, slide as u128)
+            }
+            _ => unreachable!("checked by make_time_window"),
+        };
+
+        // prevent window_duration + slide_duration from overflowing
+        ((window + slide - 1) / slide, window % slide == 0)
+    }
 }
 
 struct TimeWindowBuilder {
     window_alias: String,
     time_column: Expr,
-    window_duration: Duration,
-    slide_duration: Option<Duration>,
+    window_duration: WindowDuration,
+    slide_duration: Option<WindowDuration>,
     start_time: Option<i64>,
+    time_zone: TimeZone,
 }
 
 impl TimeWindowBuilder {
-    pub fn new(window_alias: String, time_column: Expr, window_duration: Duration) -> Self {
+    pub fn new(window_alias: String, time_column: Expr, window_duration: WindowDuration) -> Self {
         Self {
             window_alias,
             time_column,
             window_duration,
             slide_duration: Default::default(),
             start_time: Default::default(),
+            time_zone: Default::default(),
         }
     }
 
-    pub fn with_slide_duration(mut self, slide_duration: Duration) -> Self {
+    pub fn with_slide_duration(mut self, slide_duration: WindowDuration) -> Self {
         self.slide_duration = Some(slide_duration);
         self
     }
 
-    pub fn _with_start_time(mut self, start_time: i64) -> Self {
+    pub fn with_start_time(mut self, start_time: i64) -> Self {
         self.start_time = Some(start_time);
         self
     }
 
+    pub fn with_time_zone(mut self, time_zone: TimeZone) -> Self {
+        self.time_zone = time_zone;
+        self
+    }
+
     pub fn build(self) -> TimeWindow {
         TimeWindow {
             window_alias: self.window_alias,
@@ -202,6 +376,7 @@ impl TimeWindowBuilder {
             window_duration: self.window_duration,
             slide_duration: self.slide_duration.unwrap_or(self.window_duration),
             start_time: self.start_time.unwrap_or(0),
+            time_zone: self.time_zone,
         }
     }
 }
@@ -215,41 +390,156 @@ fn make_window_exprs(i: i64, window: &TimeWindow) -> Vec<Expr> {
         window_duration,
         slide_duration,
         start_time,
+        time_zone,
         ..
     } = window;
 
     let ns_type = DataType::Timestamp(TimeUnit::Nanosecond, None);
 
-    // Convert interval to bigint
-    let window_duration = lit(window_duration.as_nanos() as i64);
-    let slide_duration = lit(slide_duration.as_nanos() as i64);
-
     let ns_time = cast(time_column.clone(), ns_type.clone());
+    // Windows are computed on the wall-clock time of the session time zone
+    let local_time = if time_zone.is_utc() {
+        ns_time
+    } else {
+        Expr::ScalarUDF {
+            fun: Arc::new(from_utc_timestamp()),
+            args: vec![ns_time, lit(time_zone.to_string())],
+        }
+    };
     // TODO may overflow
     // i64::MAX (9223372036854775807) => 2262-04-11 23:47:16.854775807
-    let i64_time = cast(ns_time, DataType::Int64);
-
-    let last_start = minus(
-        i64_time.clone(),
-        modulo(
-            // maybe overflow 2262-04-11 23:47:16.854775807 + <slide_duration>
-            plus(minus(i64_time, lit(*start_time)), slide_duration.clone()),
-            slide_duration.clone(),
-        ),
-    );
-    let window_start = minus(last_start, multiply(lit(i), slide_duration));
-    let window_end = plus(window_start.clone(), window_duration);
+    let i64_time = cast(local_time, DataType::Int64);
+
+    let (window_start, window_end) = match (*window_duration, *slide_duration) {
+        (WindowDuration::Fixed(window_duration), WindowDuration::Fixed(slide_duration)) => {
+            // Convert interval to bigint
+            let window_duration = lit(window_duration.as_nanos() as i64);
+            let slide_duration = lit(slide_duration.as_nanos() as i64);
+
+            let last_start = minus(
+                i64_time.clone(),
+                modulo(
+                    // maybe overflow 2262-04-11 23:47:16.854775807 + <slide_duration>
+                    plus(minus(i64_time, lit(*start_time)), slide_duration.clone()),
+                    slide_duration.clone(),
+                ),
+            );
+            let window_start = minus(last_start, multiply(lit(i), slide_duration));
+            let window_end = plus(window_start.clone(), window_duration);
+            (window_start, window_end)
+        }
+        (WindowDuration::Months(window_months), WindowDuration::Months(slide_months)) => {
+            let calendar = CalendarWindow {
+                window_months: window_months as i64,
+                slide_months: slide_months as i64,
+                start_time: *start_time,
+            };
+            let window_start = Expr::ScalarUDF {
+                fun: Arc::new(calendar_window_bound_udf(
+                    "calendar_window_start",
+                    move |t| calendar.window_start(t, i),
+                )),
+                args: vec![i64_time.clone()],
+            };
+            let window_end = Expr::ScalarUDF {
+                fun: Arc::new(calendar_window_bound_udf("calendar_window_end", move |t| {
+                    calendar.window_end(t, i)
+                })),
+                args: vec![i64_time],
+            };
+            (window_start, window_end)
+        }
+        _ => unreachable!("checked by make_time_window"),
+    };
 
     // Convert bigint to timestamp
     let window_start = cast(window_start, ns_type.clone());
     let window_end = cast(window_end, ns_type);
 
+    // Convert the wall-clock time back to UTC
+    let (window_start, window_end) = if time_zone.is_utc() {
+        (window_start, window_end)
+    } else {
+        let to_utc = Arc::new(to_utc_timestamp());
+        (
+            Expr::ScalarUDF {
+                fun: to_utc.clone(),
+                args: vec![window_start, lit(time_zone.to_string())],
+            },
+            Expr::ScalarUDF {
+                fun: to_utc,
+                args: vec![window_end, lit(time_zone.to_string())],
+            },
+        )
+    };
+
     vec![
         window_start.alias(WINDOW_START),
         window_end.alias(WINDOW_END),
     ]
 }
 
+/// Windows of calendar months on the wall-clock time in nanoseconds,
+/// the windows are aligned to 1970-01-01 plus `start_time`
+#[derive(Debug, Clone, Copy)]
+struct CalendarWindow {
+    window_months: i64,
+    slide_months: i64,
+    start_time: i64,
+}
+
+impl CalendarWindow {
+    /// Start of the `i`-th latest window that contains `time`
+    fn window_start(&self, time: i64, i: i64) -> Option<i64> {
+        let month = self.last_start_month(time)? - i * self.slide_months;
+        month_start(month)?.checked_add(self.start_time)
+    }
+
+    /// End of the `i`-th latest window that contains `time`
+    fn window_end(&self, time: i64, i: i64) -> Option<i64> {
+        let month = self.last_start_month(time)? - i * self.slide_months + self.window_months;
+        month_start(month)?.checked_add(self.start_time)
+    }
+
+    /// Months since 1970-01 of the start of the latest window that contains `time`
+    fn last_start_month(&self, time: i64) -> Option<i64> {
+        let datetime = nanos_to_datetime(time.checked_sub(self.start_time)?);
+        let month = (datetime.year() as i64 - 1970) * 12 + datetime.month0() as i64;
+        Some(month - month.rem_euclid(self.slide_months))
+    }
+}
+
+/// Nanoseconds of the first day of the month, `month` is the number of months since 1970-01
+fn month_start(month: i64) -> Option<i64> {
+    let year = i32::try_from(1970 + month.div_euclid(12)).ok()?;
+    let date = NaiveDate::from_ymd_opt(year, month.rem_euclid(12) as u32 + 1, 1)?;
+    datetime_to_nanos(&date.and_hms_opt(0, 0, 0)?)
+}
+
+/// A window bound that can't be computed by arithmetic expressions, such as the start of a month
+fn calendar_window_bound_udf(
+    name: &str,
+    bound: impl Fn(i64) -> Option<i64> + Send + Sync + 'static,
+) -> ScalarUDF {
+    let func = move |args: &[ArrayRef]| {
+        let times = args[0]
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .ok_or_else(|| DataFusionError::Internal("Expected bigint time".to_string()))?;
+        let bounds = times
+            .iter()
+            .map(|t| t.and_then(&bound))
+            .collect::<Int64Array>();
+        Ok(Arc::new(bounds) as ArrayRef)
+    };
+    let func = make_scalar_function(func);
+
+    let signature = Signature::exact(vec![DataType::Int64], Volatility::Immutable);
+    let return_type: ReturnTypeFunction = Arc::new(|_| Ok(Arc::new(DataType::Int64)));
+
+    ScalarUDF::new(name, &signature, &return_type, &func)
+}
+
 /// Convert tumbling window to new plan
 fn build_tumbling_window_plan(
     window: &TimeWindow,
@@ -278,17 +568,8 @@ fn build_sliding_window_plan(
     child: LogicalPlan,
     child_project_exprs: Vec<Expr>,
 ) -> Result<LogicalPlan> {
-    let TimeWindow {
-        time_column,
-        window_duration,
-        slide_duration,
-        ..
-    } = window;
-
-    let window_ns = window_duration.as_nanos();
-    let slide_ns = slide_duration.as_nanos();
-    // prevent window_duration + slide_duration from overflowing
-    let overlapping_windows = (window_ns + slide_ns - 1) / slide_ns;
+    let time_column = &window.time_column;
+    let (overlapping_windows, exact) = window.overlapping_windows();
 
     let windows = (0..overlapping_windows).map(|i| make_window_exprs(i as i64, window));
     // Generate project exprs for each window
@@ -301,7 +582,7 @@ fn build_sliding_window_plan(
         })
         .collect::<Vec<Vec<_>>>();
 
-    let filter = if window_ns % slide_ns == 0 {
+    let filter = if exact {
         // When the condition windowDuration % slideDuration = 0 is fulfilled,
         // the estimation of the number of windows becomes exact one,
         // which means all produced windows are valid.
@@ -337,7 +618,35 @@ fn replace_window_expr(new_expr: Expr, plan: LogicalPlan) -> Result<LogicalPlan>
     })
 }
 
-fn parse_duration(s: &str) -> std::result::Result<Duration, String> {
+/// Calendar durations are in mo | y, others are parsed by [`parse_duration`]
+fn parse_window_duration(s: &str) -> std::result::Result<WindowDuration, String> {
+    let months = if let Some(digits) = s.strip_suffix("mo") {
+        digits.parse::<u32>().ok()
+    } else if let Some(digits) = s.strip_suffix('y') {
+        digits.parse::<u32>().ok().and_then(|e| e.checked_mul(12))
+    } else {
+        return parse_duration(s).map(WindowDuration::Fixed);
+    };
+
+    months
+        .map(WindowDuration::Months)
+        .ok_or_else(|| format!("Calendar duration must be an integer, but found: {s}"))
+}
+
+/// Parse the offset of windows in nanoseconds, such as '-8h', '0s'
+fn parse_start_time(s: &str) -> std::result::Result<i64, String> {
+    let (sign, dur) = match s.strip_prefix('-') {
+        Some(dur) => (-1, dur),
+        None => (1, s),
+    };
+    if dur.trim_end_matches(char::is_alphabetic).parse::<f64>() == Ok(0.0) {
+        return Ok(0);
+    }
+
+    Ok(sign * parse_duration(dur)?.as_nanos() as i64)
+}
+
+pub(crate) fn parse_duration(s: &str) -> std::result::Result<Duration, String> {
     if s.is_empty() {
         return Err("Empty string".to_string());
     }
@@ -374,7 +683,15 @@ fn parse_duration(s: &str) -> std::result::Result<Duration, String> {
 mod tests {
     use std::time::Duration;
 
-    use super::parse_duration;
+    use datafusion::logical_expr::BuiltinScalarFunction;
+    use datafusion::prelude::{col, lit, Expr};
+    use datafusion::scalar::ScalarValue;
+    use models::time_zone::TimeZone;
+
+    use super::{
+        local_date_bin, month_start, parse_duration, parse_start_time, parse_window_duration,
+        CalendarWindow, WindowDuration,
+    };
 
     #[test]
     fn test_parse_duration() {
@@ -389,4 +706,72 @@ mod tests {
             Duration::from_millis(6_000)
         );
     }
+
+    #[test]
+    fn test_parse_window_duration() {
+        assert_eq!(
+            parse_window_duration("3mo").unwrap(),
+            WindowDuration::Months(3)
+        );
+        assert_eq!(
+            parse_window_duration("1y").unwrap(),
+            WindowDuration::Months(12)
+        );
+        assert_eq!(
+            parse_window_duration("1m").unwrap(),
+            WindowDuration::Fixed(Duration::from_secs(60))
+        );
+        assert!(parse_window_duration("1.5mo").is_err());
+
+        assert_eq!(parse_start_time("0s").unwrap(), 0);
+        assert_eq!(parse_start_time("-8h").unwrap(), -8 * 3_600_000_000_000);
+    }
+
+    #[test]
+    fn test_calendar_window() {
+        // quarters
+        let window = CalendarWindow {
+            window_months: 3,
+            slide_months: 3,
+            start_time: 0,
+        };
+        // 2023-05-20
+        let time = month_start(53 * 12 + 4).unwrap() + 19 * 86_400_000_000_000;
+        // 2023-04-01
+        assert_eq!(window.window_start(time, 0), month_start(53 * 12 + 3));
+        // 2023-07-01
+        assert_eq!(window.window_end(time, 0), month_start(53 * 12 + 6));
+        // 2023-01-01
+        assert_eq!(window.window_start(time, 1), month_start(53 * 12));
+        // before 1970
+        assert_eq!(window.window_start(-1, 0), month_start(-3));
+    }
+
+    #[test]
+    fn test_local_date_bin() {
+        let time_zone = "Asia/Shanghai".parse::<TimeZone>().unwrap();
+        let date_bin = Expr::ScalarFunction {
+            fun: BuiltinScalarFunction::DateBin,
+            args: vec![
+                lit(ScalarValue::IntervalDayTime(Some(1 << 32))),
+                col("time"),
+            ],
+        };
+
+        let local = local_date_bin(&date_bin, time_zone).unwrap();
+        assert_eq!(
+            local.display_name().unwrap(),
+            date_bin.display_name().unwrap()
+        );
+
+        // The rewritten DATE_BIN is not rewritten again
+        let inner_date_bin = match &local {
+            Expr::Alias(expr, _) => match expr.as_ref() {
+                Expr::ScalarUDF { args, .. } => args[0].clone(),
+                _ => panic!("expect TO_UTC_TIMESTAMP"),
+            },
+            _ => panic!("expect alias"),
+        };
+        assert!(local_date_bin(&inner_date_bin, time_zone).is_none());
+    }
 }
@@ -1 +1,2 @@
 //! physical plan optimizer rule
+pub mod spillable_aggregate;
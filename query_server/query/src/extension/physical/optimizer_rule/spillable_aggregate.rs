@@ -0,0 +1,77 @@
+use std::sync::Arc;
+
+use datafusion::config::ConfigOptions;
+use datafusion::error::Result;
+use datafusion::physical_optimizer::PhysicalOptimizerRule;
+use datafusion::physical_plan::aggregates::{AggregateExec, AggregateMode};
+use datafusion::physical_plan::ExecutionPlan;
+
+use crate::extension::physical::plan_node::spillable_aggregate::SpillableAggregateExec;
+
+/// Replace the hash aggregations of `GROUP BY` by [`SpillableAggregateExec`],
+/// so a query with too many groups for its memory pool spills instead of failing.
+/// [`SpillableAggregateExec`] accumulates row by row, the rule is only applied
+/// to the sessions enabling `spill_aggregation`.
+///
+/// The aggregations without group columns only keep a single group and grouping sets are
+/// not supported by [`SpillableAggregateExec`], they are kept as is.
+///
+/// It runs once the distribution of the plan is enforced,
+/// the replaced aggregations require the same distribution of their inputs.
+pub struct SpillableAggregation {}
+
+impl SpillableAggregation {
+    pub const NAME: &'static str = "spillable_aggregation";
+
+    fn optimize_plan(&self, plan: Arc<dyn ExecutionPlan>) -> Result<Arc<dyn ExecutionPlan>> {
+        let children = plan
+            .children()
+            .into_iter()
+            .map(|child| self.optimize_plan(child))
+            .collect::<Result<Vec<_>>>()?;
+        let plan = if children.is_empty() {
+            plan
+        } else {
+            plan.with_new_children(children)?
+        };
+
+        let aggregate = match plan.as_any().downcast_ref::<AggregateExec>() {
+            Some(aggregate) => aggregate,
+            None => return Ok(plan),
+        };
+        let group_by = aggregate.group_expr();
+        let supported_mode = matches!(
+            aggregate.mode(),
+            AggregateMode::Partial | AggregateMode::Final | AggregateMode::FinalPartitioned
+        );
+        if !supported_mode || group_by.expr().is_empty() || !group_by.null_expr().is_empty() {
+            return Ok(plan);
+        }
+
+        Ok(Arc::new(SpillableAggregateExec::try_new(
+            *aggregate.mode(),
+            group_by.expr().to_vec(),
+            aggregate.aggr_expr().to_vec(),
+            aggregate.input().clone(),
+            aggregate.schema(),
+        )?))
+    }
+}
+
+impl PhysicalOptimizerRule for SpillableAggregation {
+    fn optimize(
+        &self,
+        plan: Arc<dyn ExecutionPlan>,
+        _config: &ConfigOptions,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        self.optimize_plan(plan)
+    }
+
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    fn schema_check(&self) -> bool {
+        true
+    }
+}
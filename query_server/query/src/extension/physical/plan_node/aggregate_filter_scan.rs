@@ -14,7 +14,7 @@ use datafusion::physical_plan::{
 };
 use models::predicate::domain::{PredicateRef, PushedAggregateFunction};
 use models::schema::TskvTableSchemaRef;
-use tskv::iterator::{QueryOption, TableScanMetrics};
+use tskv::iterator::{PushedAggregate, QueryOption, TableScanMetrics};
 
 use super::tskv_exec::TableScanStream;
 
@@ -78,15 +78,18 @@ impl ExecutionPlan for AggregateFilterTskvExec {
         partition: usize,
         context: Arc<TaskContext>,
     ) -> Result<SendableRecordBatchStream> {
-        let mut agg_columns = Vec::with_capacity(self.pushed_aggs.len());
+        let mut aggregates = Vec::with_capacity(self.pushed_aggs.len());
         for agg in self.pushed_aggs.iter() {
-            match agg {
-                PushedAggregateFunction::Count(column) => {
-                    if let Some(col) = self.table_schema.column(column) {
-                        agg_columns.push(col.clone());
-                    }
-                }
-            }
+            let column = self.table_schema.column(agg.column()).ok_or_else(|| {
+                DataFusionError::Internal(format!(
+                    "Pushed aggregate function's column not found: {}",
+                    agg.column()
+                ))
+            })?;
+            aggregates.push(PushedAggregate {
+                function: agg.clone(),
+                column: column.clone(),
+            });
         }
         let metrics = ExecutionPlanMetricsSet::new();
         let metrics = TableScanMetrics::new(&metrics, partition, Some(context.memory_pool()));
@@ -94,7 +97,7 @@ impl ExecutionPlan for AggregateFilterTskvExec {
             100_usize,
             self.table_schema.tenant.clone(),
             self.filter.clone(),
-            Some(agg_columns),
+            Some(aggregates),
             self.schema.clone(),
             (*self.table_schema).clone(),
             metrics.tskv_metrics(),
@@ -1,5 +1,6 @@
 pub mod aggregate_filter_scan;
 pub mod expand;
+pub mod spillable_aggregate;
 pub mod table_writer;
 pub mod tag_scan;
 pub mod tskv_exec;
@@ -0,0 +1,823 @@
+use std::any::Any;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, VecDeque};
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+
+use datafusion::arrow::array::{ArrayRef, UInt32Array};
+use datafusion::arrow::compute::{cast, take, SortOptions};
+use datafusion::arrow::datatypes::{Field, Schema, SchemaRef};
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::error::{DataFusionError, Result};
+use datafusion::execution::context::TaskContext;
+use datafusion::execution::memory_pool::{MemoryConsumer, MemoryReservation};
+use datafusion::logical_expr::Accumulator;
+use datafusion::physical_expr::PhysicalSortExpr;
+use datafusion::physical_plan::aggregates::AggregateMode;
+use datafusion::physical_plan::expressions::Column;
+use datafusion::physical_plan::metrics::{BaselineMetrics, ExecutionPlanMetricsSet, MetricsSet};
+use datafusion::physical_plan::sorts::sort::SortExec;
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::{
+    AggregateExpr, DisplayFormatType, Distribution, ExecutionPlan, Partitioning, PhysicalExpr,
+    SendableRecordBatchStream, Statistics,
+};
+use datafusion::scalar::ScalarValue;
+use futures::{stream, StreamExt, TryStreamExt};
+use parking_lot::Mutex;
+use trace::debug;
+
+/// Hash aggregation of `GROUP BY` that doesn't fail when the memory pool of the query is exhausted.
+///
+/// Groups are aggregated in memory until the memory reservation can't grow, then
+/// - in [`AggregateMode::Partial`], the states of the groups are emitted and the groups are cleared,
+///   the final aggregation merges the states of the same group.
+/// - in the final modes, the states of the groups and the rest of the input are sorted by the
+///   group columns, [`SortExec`] spills them to disk, then the states are merged group by group.
+#[derive(Debug)]
+pub struct SpillableAggregateExec {
+    mode: AggregateMode,
+    /// Group by expressions, a single grouping set
+    group_expr: Vec<(Arc<dyn PhysicalExpr>, String)>,
+    aggr_expr: Vec<Arc<dyn AggregateExpr>>,
+    input: Arc<dyn ExecutionPlan>,
+    schema: SchemaRef,
+    /// The group columns followed by the state fields of the aggregations,
+    /// it is the output schema of the partial mode and the input schema of the final modes
+    state_schema: SchemaRef,
+    /// Execution metrics
+    metrics: ExecutionPlanMetricsSet,
+}
+
+impl SpillableAggregateExec {
+    /// Create an aggregation with the same output `schema` as the replaced `AggregateExec`
+    pub fn try_new(
+        mode: AggregateMode,
+        group_expr: Vec<(Arc<dyn PhysicalExpr>, String)>,
+        aggr_expr: Vec<Arc<dyn AggregateExpr>>,
+        input: Arc<dyn ExecutionPlan>,
+        schema: SchemaRef,
+    ) -> Result<Self> {
+        let input_schema = input.schema();
+        let mut fields = group_expr
+            .iter()
+            .map(|(expr, name)| {
+                Ok(Field::new(
+                    name,
+                    expr.data_type(&input_schema)?,
+                    expr.nullable(&input_schema)?,
+                ))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        for expr in &aggr_expr {
+            fields.extend(expr.state_fields()?);
+        }
+
+        Ok(Self {
+            mode,
+            group_expr,
+            aggr_expr,
+            input,
+            schema,
+            state_schema: Arc::new(Schema::new(fields)),
+            metrics: ExecutionPlanMetricsSet::new(),
+        })
+    }
+
+    pub fn mode(&self) -> &AggregateMode {
+        &self.mode
+    }
+
+    /// Arguments of the accumulators evaluated on the input,
+    /// the input columns of the states in the final modes
+    fn aggr_args(&self) -> Result<Vec<Vec<Arc<dyn PhysicalExpr>>>> {
+        match self.mode {
+            AggregateMode::Partial => Ok(self.aggr_expr.iter().map(|e| e.expressions()).collect()),
+            _ => {
+                let mut index = self.group_expr.len();
+                let mut aggr_args = Vec::with_capacity(self.aggr_expr.len());
+                for expr in &self.aggr_expr {
+                    let num_states = expr.state_fields()?.len();
+                    let args = (index..index + num_states)
+                        .map(|i| {
+                            let field = self.state_schema.field(i);
+                            Arc::new(Column::new(field.name(), i)) as Arc<dyn PhysicalExpr>
+                        })
+                        .collect();
+                    aggr_args.push(args);
+                    index += num_states;
+                }
+                Ok(aggr_args)
+            }
+        }
+    }
+}
+
+impl ExecutionPlan for SpillableAggregateExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        match (self.mode, self.input.output_partitioning()) {
+            // the columns of the input are not kept by the partial aggregation
+            (AggregateMode::Partial, Partitioning::Hash(_, n)) => {
+                Partitioning::UnknownPartitioning(n)
+            }
+            (_, partitioning) => partitioning,
+        }
+    }
+
+    fn unbounded_output(&self, children: &[bool]) -> Result<bool> {
+        if children[0] {
+            Err(DataFusionError::Plan(
+                "Aggregate Error: `GROUP BY` clause is not supported for unbounded inputs."
+                    .to_string(),
+            ))
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        None
+    }
+
+    fn required_input_distribution(&self) -> Vec<Distribution> {
+        match self.mode {
+            AggregateMode::Partial => vec![Distribution::UnspecifiedDistribution],
+            AggregateMode::FinalPartitioned => vec![Distribution::HashPartitioned(
+                self.group_expr.iter().map(|(e, _)| e.clone()).collect(),
+            )],
+            _ => vec![Distribution::SinglePartition],
+        }
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.input.clone()]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(Arc::new(SpillableAggregateExec::try_new(
+            self.mode,
+            self.group_expr.clone(),
+            self.aggr_expr.clone(),
+            children[0].clone(),
+            self.schema.clone(),
+        )?))
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        debug!(
+            "Start SpillableAggregateExec::execute for partition {} of context session_id {} and task_id {:?}",
+            partition,
+            context.session_id(),
+            context.task_id()
+        );
+
+        let input = self.input.execute(partition, context.clone())?;
+        let reservation = MemoryConsumer::new(format!("SpillableAggregateExec[{partition}]"))
+            .register(context.memory_pool());
+        let exprs = AggregateExprs {
+            mode: self.mode,
+            group_expr: self.group_expr.iter().map(|(e, _)| e.clone()).collect(),
+            aggr_expr: self.aggr_expr.clone(),
+            aggr_args: self.aggr_args()?,
+        };
+        let groups = GroupedAccumulators {
+            exprs,
+            batch_size: context.session_config().batch_size(),
+            groups: HashMap::new(),
+            size: 0,
+            reservation,
+        };
+        let baseline_metrics = BaselineMetrics::new(&self.metrics, partition);
+
+        let output = match self.mode {
+            AggregateMode::Partial => aggregate_partial(groups, input, self.schema.clone()).boxed(),
+            _ => stream::once(aggregate_final(
+                groups,
+                input,
+                self.schema.clone(),
+                self.state_schema.clone(),
+                context,
+            ))
+            .try_flatten()
+            .boxed(),
+        };
+        let output = output.inspect(move |batch| {
+            if let Ok(batch) = batch {
+                baseline_metrics.record_output(batch.num_rows());
+            }
+        });
+
+        Ok(Box::pin(RecordBatchStreamAdapter::new(
+            self.schema(),
+            output,
+        )))
+    }
+
+    fn fmt_as(&self, t: DisplayFormatType, f: &mut Formatter) -> std::fmt::Result {
+        match t {
+            DisplayFormatType::Default => {
+                let group_expr = self
+                    .group_expr
+                    .iter()
+                    .map(|(e, alias)| {
+                        let e = e.to_string();
+                        if &e != alias {
+                            format!("{e} as {alias}")
+                        } else {
+                            e
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let aggr_expr = self
+                    .aggr_expr
+                    .iter()
+                    .map(|e| e.name().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                write!(
+                    f,
+                    "SpillableAggregateExec: mode={:?}, gby=[{}], aggr=[{}]",
+                    self.mode, group_expr, aggr_expr
+                )
+            }
+        }
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
+
+    fn statistics(&self) -> Statistics {
+        Statistics::default()
+    }
+}
+
+/// Emit the states of the groups whenever the memory reservation can't grow
+fn aggregate_partial(
+    groups: GroupedAccumulators,
+    input: SendableRecordBatchStream,
+    schema: SchemaRef,
+) -> impl futures::Stream<Item = Result<RecordBatch>> + Send {
+    let state = (groups, input, VecDeque::new(), false);
+    stream::try_unfold(
+        state,
+        move |(mut groups, mut input, mut pending, mut done)| {
+            let schema = schema.clone();
+            async move {
+                loop {
+                    if let Some(batch) = pending.pop_front() {
+                        return Ok(Some((batch, (groups, input, pending, done))));
+                    }
+                    if done {
+                        return Ok(None);
+                    }
+
+                    match input.next().await {
+                        Some(batch) => {
+                            groups.update_batch(&batch?)?;
+                            if !groups.try_reserve() {
+                                pending = groups.take_batches(&schema, true)?.into();
+                            }
+                        }
+                        None => {
+                            done = true;
+                            pending = groups.take_batches(&schema, true)?.into();
+                        }
+                    }
+                }
+            }
+        },
+    )
+}
+
+/// Aggregate in memory, sort and merge the states by the group columns once the memory
+/// reservation can't grow
+async fn aggregate_final(
+    mut groups: GroupedAccumulators,
+    mut input: SendableRecordBatchStream,
+    schema: SchemaRef,
+    state_schema: SchemaRef,
+    context: Arc<TaskContext>,
+) -> Result<SendableRecordBatchStream> {
+    while let Some(batch) = input.next().await {
+        groups.update_batch(&batch?)?;
+        if !groups.try_reserve() {
+            return spill_and_merge(groups, input, schema, state_schema, context);
+        }
+    }
+
+    let batches = groups.take_batches(&schema, false)?;
+    Ok(Box::pin(RecordBatchStreamAdapter::new(
+        schema,
+        stream::iter(batches.into_iter().map(Ok)),
+    )))
+}
+
+fn spill_and_merge(
+    mut groups: GroupedAccumulators,
+    input: SendableRecordBatchStream,
+    schema: SchemaRef,
+    state_schema: SchemaRef,
+    context: Arc<TaskContext>,
+) -> Result<SendableRecordBatchStream> {
+    debug!(
+        "Memory of aggregation exhausted, sort {} groups by the group columns",
+        groups.groups.len()
+    );
+
+    let states = groups.take_batches(&state_schema, true)?;
+    let rest = {
+        let exprs = groups.exprs.clone();
+        let state_schema = state_schema.clone();
+        input.map(move |batch| exprs.project_states(&batch?, &state_schema))
+    };
+    let states = stream::iter(states.into_iter().map(Ok)).chain(rest);
+    let states = Arc::new(StreamExec::new(
+        state_schema.clone(),
+        Box::pin(RecordBatchStreamAdapter::new(state_schema.clone(), states)),
+    ));
+
+    let sort_expr = (0..groups.exprs.group_expr.len())
+        .map(|i| PhysicalSortExpr {
+            expr: Arc::new(Column::new(state_schema.field(i).name(), i)),
+            options: SortOptions::default(),
+        })
+        .collect();
+    let sorted = SortExec::try_new(sort_expr, states, None)?.execute(0, context)?;
+
+    let output_schema = schema.clone();
+    let state = (groups, sorted, None, Vec::new(), false);
+    let output = stream::try_unfold(
+        state,
+        move |(mut groups, mut sorted, mut current, mut rows, mut done)| {
+            let schema = schema.clone();
+            async move {
+                loop {
+                    if rows.len() >= groups.batch_size || (done && !rows.is_empty()) {
+                        let batch = rows_to_batch(&schema, std::mem::take(&mut rows))?;
+                        return Ok(Some((batch, (groups, sorted, current, rows, done))));
+                    }
+                    if done {
+                        return Ok(None);
+                    }
+
+                    match sorted.next().await {
+                        Some(batch) => {
+                            groups
+                                .exprs
+                                .merge_sorted_batch(&batch?, &mut current, &mut rows)?;
+                        }
+                        None => {
+                            done = true;
+                            if let Some(group) = current.take() {
+                                rows.push(group.finish(false)?);
+                            }
+                        }
+                    }
+                }
+            }
+        },
+    );
+
+    Ok(Box::pin(RecordBatchStreamAdapter::new(
+        output_schema,
+        output,
+    )))
+}
+
+/// Accumulators of a group
+struct Group {
+    key: Vec<ScalarValue>,
+    accumulators: Vec<Box<dyn Accumulator>>,
+    /// Estimated memory size of the key and the accumulators
+    size: usize,
+}
+
+impl Group {
+    fn try_new(key: Vec<ScalarValue>, aggr_expr: &[Arc<dyn AggregateExpr>]) -> Result<Self> {
+        let accumulators = aggr_expr
+            .iter()
+            .map(|e| e.create_accumulator())
+            .collect::<Result<Vec<_>>>()?;
+        let mut group = Self {
+            key,
+            accumulators,
+            size: 0,
+        };
+        group.size = group.estimate_size();
+        Ok(group)
+    }
+
+    fn estimate_size(&self) -> usize {
+        self.key.iter().map(|e| e.size()).sum::<usize>()
+            + self.accumulators.iter().map(|e| e.size()).sum::<usize>()
+    }
+
+    /// The group columns followed by the states or the final values of the aggregations
+    fn finish(self, states: bool) -> Result<Vec<ScalarValue>> {
+        let mut row = self.key;
+        for accumulator in &self.accumulators {
+            if states {
+                row.extend(accumulator.state()?);
+            } else {
+                row.push(accumulator.evaluate()?);
+            }
+        }
+        Ok(row)
+    }
+}
+
+/// Expressions of the groups and the arguments of the accumulators
+#[derive(Clone)]
+struct AggregateExprs {
+    mode: AggregateMode,
+    group_expr: Vec<Arc<dyn PhysicalExpr>>,
+    aggr_expr: Vec<Arc<dyn AggregateExpr>>,
+    aggr_args: Vec<Vec<Arc<dyn PhysicalExpr>>>,
+}
+
+impl AggregateExprs {
+    fn evaluate_args(&self, batch: &RecordBatch) -> Result<Vec<Vec<ArrayRef>>> {
+        let num_rows = batch.num_rows();
+        self.aggr_args
+            .iter()
+            .map(|args| {
+                args.iter()
+                    .map(|e| e.evaluate(batch).map(|v| v.into_array(num_rows)))
+                    .collect::<Result<Vec<_>>>()
+            })
+            .collect()
+    }
+
+    fn evaluate_keys(&self, batch: &RecordBatch) -> Result<Vec<ArrayRef>> {
+        let num_rows = batch.num_rows();
+        self.group_expr
+            .iter()
+            .map(|e| e.evaluate(batch).map(|v| v.into_array(num_rows)))
+            .collect()
+    }
+
+    fn update_group(&self, group: &mut Group, args: &[Vec<ArrayRef>]) -> Result<()> {
+        for (accumulator, values) in group.accumulators.iter_mut().zip(args) {
+            match self.mode {
+                AggregateMode::Partial => accumulator.update_batch(values)?,
+                _ => accumulator.merge_batch(values)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Project the group columns and the states of an input batch of the final modes
+    fn project_states(&self, batch: &RecordBatch, state_schema: &SchemaRef) -> Result<RecordBatch> {
+        let keys = self.evaluate_keys(batch)?;
+        let args = self.evaluate_args(batch)?;
+        let columns = keys.into_iter().chain(args.into_iter().flatten()).collect();
+        Ok(RecordBatch::try_new(state_schema.clone(), columns)?)
+    }
+
+    /// Merge the states sorted by the group columns, the final values of `current` group
+    /// are pushed to `rows` once all its states are merged
+    fn merge_sorted_batch(
+        &self,
+        batch: &RecordBatch,
+        current: &mut Option<Group>,
+        rows: &mut Vec<Vec<ScalarValue>>,
+    ) -> Result<()> {
+        // the group columns are followed by the states
+        let keys = &batch.columns()[..self.group_expr.len()];
+        let args = self.evaluate_args(batch)?;
+
+        let mut start = 0;
+        while start < batch.num_rows() {
+            let key = group_key(keys, start)?;
+            let mut end = start + 1;
+            while end < batch.num_rows() && group_key(keys, end)? == key {
+                end += 1;
+            }
+
+            let group = match current.take() {
+                Some(group) if group.key == key => current.insert(group),
+                finished => {
+                    if let Some(group) = finished {
+                        rows.push(group.finish(false)?);
+                    }
+                    current.insert(Group::try_new(key, &self.aggr_expr)?)
+                }
+            };
+            let args = args
+                .iter()
+                .map(|args| args.iter().map(|e| e.slice(start, end - start)).collect())
+                .collect::<Vec<Vec<_>>>();
+            self.update_group(group, &args)?;
+
+            start = end;
+        }
+
+        Ok(())
+    }
+}
+
+/// Groups aggregated in memory, keyed by the values of the group columns
+struct GroupedAccumulators {
+    exprs: AggregateExprs,
+    batch_size: usize,
+    groups: HashMap<Vec<ScalarValue>, Group>,
+    /// Estimated memory size of the groups
+    size: usize,
+    reservation: MemoryReservation,
+}
+
+impl GroupedAccumulators {
+    fn update_batch(&mut self, batch: &RecordBatch) -> Result<()> {
+        let keys = self.exprs.evaluate_keys(batch)?;
+        let args = self.exprs.evaluate_args(batch)?;
+
+        let mut rows_of_groups: HashMap<Vec<ScalarValue>, Vec<u32>> = HashMap::new();
+        for row in 0..batch.num_rows() {
+            let key = group_key(&keys, row)?;
+            rows_of_groups.entry(key).or_default().push(row as u32);
+        }
+
+        for (key, rows) in rows_of_groups {
+            let indices = UInt32Array::from(rows);
+            let args = args
+                .iter()
+                .map(|args| {
+                    args.iter()
+                        .map(|e| take(e.as_ref(), &indices, None))
+                        .collect::<std::result::Result<Vec<_>, _>>()
+                })
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+
+            let group = match self.groups.entry(key) {
+                Entry::Occupied(e) => e.into_mut(),
+                Entry::Vacant(e) => {
+                    let group = Group::try_new(e.key().clone(), &self.exprs.aggr_expr)?;
+                    self.size += group.size;
+                    e.insert(group)
+                }
+            };
+            self.exprs.update_group(group, &args)?;
+
+            let size = group.estimate_size();
+            self.size = self.size + size - group.size;
+            group.size = size;
+        }
+
+        Ok(())
+    }
+
+    /// Resize the memory reservation to the size of the groups, false if the memory pool is exhausted
+    fn try_reserve(&mut self) -> bool {
+        self.reservation.try_resize(self.size).is_ok()
+    }
+
+    /// Take the groups as record batches, the states of the groups are output if `states`,
+    /// otherwise the final values
+    fn take_batches(&mut self, schema: &SchemaRef, states: bool) -> Result<Vec<RecordBatch>> {
+        let groups = std::mem::take(&mut self.groups);
+        self.size = 0;
+
+        let mut batches = Vec::with_capacity(groups.len() / self.batch_size + 1);
+        let mut rows = Vec::with_capacity(self.batch_size.min(groups.len()));
+        for (_, group) in groups {
+            rows.push(group.finish(states)?);
+            if rows.len() >= self.batch_size {
+                batches.push(rows_to_batch(schema, std::mem::take(&mut rows))?);
+            }
+        }
+        if !rows.is_empty() {
+            batches.push(rows_to_batch(schema, rows)?);
+        }
+        self.reservation.free();
+
+        Ok(batches)
+    }
+}
+
+fn group_key(keys: &[ArrayRef], row: usize) -> Result<Vec<ScalarValue>> {
+    keys.iter()
+        .map(|e| ScalarValue::try_from_array(e, row))
+        .collect()
+}
+
+fn rows_to_batch(schema: &SchemaRef, rows: Vec<Vec<ScalarValue>>) -> Result<RecordBatch> {
+    let mut columns = vec![Vec::with_capacity(rows.len()); schema.fields().len()];
+    for row in rows {
+        for (column, value) in columns.iter_mut().zip(row) {
+            column.push(value);
+        }
+    }
+
+    let columns = columns
+        .into_iter()
+        .zip(schema.fields())
+        .map(|(values, field)| {
+            let array = ScalarValue::iter_to_array(values)?;
+            if array.data_type() == field.data_type() {
+                Ok(array)
+            } else {
+                Ok(cast(&array, field.data_type())?)
+            }
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(RecordBatch::try_new(schema.clone(), columns)?)
+}
+
+/// Feed a stream to the operators that only accept an [`ExecutionPlan`] as input, e.g. [`SortExec`].
+///
+/// The stream can only be executed once.
+struct StreamExec {
+    schema: SchemaRef,
+    stream: Mutex<Option<SendableRecordBatchStream>>,
+}
+
+impl StreamExec {
+    fn new(schema: SchemaRef, stream: SendableRecordBatchStream) -> Self {
+        Self {
+            schema,
+            stream: Mutex::new(Some(stream)),
+        }
+    }
+}
+
+impl Debug for StreamExec {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "StreamExec")
+    }
+}
+
+impl ExecutionPlan for StreamExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(1)
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        None
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        _children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(self)
+    }
+
+    fn execute(
+        &self,
+        _partition: usize,
+        _context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        self.stream.lock().take().ok_or_else(|| {
+            DataFusionError::Internal("StreamExec can only be executed once".to_string())
+        })
+    }
+
+    fn fmt_as(&self, _t: DisplayFormatType, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "StreamExec")
+    }
+
+    fn statistics(&self) -> Statistics {
+        Statistics::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use datafusion::arrow::array::{Array, Int64Array};
+    use datafusion::arrow::datatypes::{DataType, Field, Schema};
+    use datafusion::arrow::record_batch::RecordBatch;
+    use datafusion::config::ConfigOptions;
+    use datafusion::execution::memory_pool::GreedyMemoryPool;
+    use datafusion::execution::runtime_env::{RuntimeConfig, RuntimeEnv};
+    use datafusion::physical_optimizer::PhysicalOptimizerRule;
+    use datafusion::physical_plan::aggregates::{AggregateExec, AggregateMode, PhysicalGroupBy};
+    use datafusion::physical_plan::expressions::{Column, Count, Sum};
+    use datafusion::physical_plan::memory::MemoryExec;
+    use datafusion::physical_plan::{collect, AggregateExpr, ExecutionPlan};
+    use datafusion::prelude::{SessionConfig, SessionContext};
+
+    use super::SpillableAggregateExec;
+    use crate::extension::physical::optimizer_rule::spillable_aggregate::SpillableAggregation;
+
+    #[tokio::test]
+    async fn test_spill_aggregate() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("key", DataType::Int64, false),
+            Field::new("value", DataType::Int64, false),
+        ]));
+        // 10000 groups of 5 rows
+        let batches = (0..50)
+            .map(|i| {
+                let keys = (i * 1000..(i + 1) * 1000).map(|e| e % 10000);
+                RecordBatch::try_new(
+                    schema.clone(),
+                    vec![
+                        Arc::new(Int64Array::from_iter_values(keys)),
+                        Arc::new(Int64Array::from_iter_values((0..1000).map(|_| 1))),
+                    ],
+                )
+                .unwrap()
+            })
+            .collect::<Vec<_>>();
+        let input = Arc::new(MemoryExec::try_new(&[batches], schema.clone(), None).unwrap());
+
+        let aggr_expr: Vec<Arc<dyn AggregateExpr>> = vec![
+            Arc::new(Count::new(
+                Arc::new(Column::new("value", 1)),
+                "count",
+                DataType::Int64,
+            )),
+            Arc::new(Sum::new(
+                Arc::new(Column::new("value", 1)),
+                "sum",
+                DataType::Int64,
+            )),
+        ];
+        let partial = AggregateExec::try_new(
+            AggregateMode::Partial,
+            PhysicalGroupBy::new_single(vec![(Arc::new(Column::new("key", 0)), "key".to_string())]),
+            aggr_expr.clone(),
+            input,
+            schema.clone(),
+        )
+        .unwrap();
+        let final_aggregate = AggregateExec::try_new(
+            AggregateMode::Final,
+            PhysicalGroupBy::new_single(vec![(Arc::new(Column::new("key", 0)), "key".to_string())]),
+            aggr_expr,
+            Arc::new(partial),
+            schema,
+        )
+        .unwrap();
+
+        let plan = SpillableAggregation {}
+            .optimize(Arc::new(final_aggregate), &ConfigOptions::new())
+            .unwrap();
+        assert!(plan.as_any().is::<SpillableAggregateExec>());
+        assert!(plan.children()[0].as_any().is::<SpillableAggregateExec>());
+
+        // far less than the memory of 10000 groups
+        let runtime = RuntimeEnv::new(
+            RuntimeConfig::new().with_memory_pool(Arc::new(GreedyMemoryPool::new(256 * 1024))),
+        )
+        .unwrap();
+        let ctx = SessionContext::with_config_rt(
+            SessionConfig::new().with_batch_size(1000),
+            Arc::new(runtime),
+        );
+        let batches = collect(plan, ctx.task_ctx()).await.unwrap();
+
+        let mut groups = vec![];
+        for batch in batches {
+            let column = |i: usize| {
+                batch
+                    .column(i)
+                    .as_any()
+                    .downcast_ref::<Int64Array>()
+                    .unwrap()
+                    .clone()
+            };
+            let (keys, counts, sums) = (column(0), column(1), column(2));
+            for i in 0..keys.len() {
+                groups.push((keys.value(i), counts.value(i), sums.value(i)));
+            }
+        }
+        groups.sort_unstable();
+
+        let expected = (0..10000).map(|e| (e, 5, 5)).collect::<Vec<_>>();
+        assert_eq!(groups, expected);
+    }
+}
@@ -47,4 +47,8 @@ impl FunctionMetadataManager for SimpleFunctionMetadataManager {
     fn udfs(&self) -> HashSet<String> {
         self.scalar_functions.keys().cloned().collect()
     }
+
+    fn udafs(&self) -> HashSet<String> {
+        self.aggregate_functions.keys().cloned().collect()
+    }
 }
@@ -2,21 +2,24 @@ use std::sync::Arc;
 
 use async_trait::async_trait;
 use coordinator::service::CoordinatorRef;
+use datafusion::arrow::record_batch::RecordBatch;
 use derive_builder::Builder;
 use memory_pool::MemoryPoolRef;
 use models::auth::user::{User, UserInfo};
 use snafu::ResultExt;
 use spi::query::auth::AccessControlRef;
 use spi::query::dispatcher::QueryDispatcher;
+use spi::query::prepared_statement::{PreparedStatement, PreparedStatementRef};
 use spi::query::session::SessionCtxFactory;
 use spi::server::dbms::DatabaseManagerSystem;
 use spi::service::protocol::{Query, QueryHandle, QueryId};
-use spi::{AuthSnafu, Result};
+use spi::{AuthSnafu, Result, StdIoSnafu};
 use trace::debug;
 use tskv::kv_option::Options;
 
 use crate::auth::auth_control::{AccessControlImpl, AccessControlNoCheck};
 use crate::dispatcher::manager::SimpleQueryDispatcherBuilder;
+use crate::dispatcher::pipe_manager::PipeManager;
 use crate::execution::scheduler::LocalScheduler;
 use crate::sql::optimizer::CascadeOptimizerBuilder;
 use crate::sql::parser::DefaultParser;
@@ -58,6 +61,42 @@ where
         Ok(QueryHandle::new(query_id, query.clone(), result))
     }
 
+    async fn prepare(&self, query: &Query) -> Result<PreparedStatementRef> {
+        let query_id = self.query_dispatcher.create_query_id();
+
+        let tenant_id = self
+            .access_control
+            .tenant_id(query.context().tenant())
+            .await
+            .context(AuthSnafu)?;
+
+        self.query_dispatcher
+            .prepare_query(tenant_id, query_id, query)
+            .await
+    }
+
+    async fn execute_prepared(
+        &self,
+        query: &Query,
+        statement: &PreparedStatement,
+        parameters: Vec<RecordBatch>,
+    ) -> Result<QueryHandle> {
+        let query_id = self.query_dispatcher.create_query_id();
+
+        let tenant_id = self
+            .access_control
+            .tenant_id(query.context().tenant())
+            .await
+            .context(AuthSnafu)?;
+
+        let result = self
+            .query_dispatcher
+            .execute_prepared_query(tenant_id, query_id, query, statement, parameters)
+            .await?;
+
+        Ok(QueryHandle::new(query_id, query.clone(), result))
+    }
+
     fn metrics(&self) -> String {
         let infos = self.query_dispatcher.running_query_infos();
         let status = self.query_dispatcher.running_query_status();
@@ -88,7 +127,9 @@ pub async fn make_cnosdbms(
     memory_pool: MemoryPoolRef,
 ) -> Result<impl DatabaseManagerSystem> {
     // TODO session config need load global system config
-    let session_factory = Arc::new(SessionCtxFactory::default());
+    std::fs::create_dir_all(&options.query.spill_path).context(StdIoSnafu)?;
+    let session_factory =
+        Arc::new(SessionCtxFactory::default().with_spill_path(&options.query.spill_path));
     let parser = Arc::new(DefaultParser::default());
     let optimizer = Arc::new(CascadeOptimizerBuilder::default().build());
     // TODO wrap, and num_threads configurable
@@ -99,15 +140,21 @@ pub async fn make_cnosdbms(
     let meta_manager = coord.meta_manager();
 
     let query_dispatcher = SimpleQueryDispatcherBuilder::default()
-        .with_coord(coord)
+        .with_coord(coord.clone())
         .with_session_factory(session_factory)
         .with_parser(parser)
         .with_optimizer(optimizer)
         .with_scheduler(scheduler)
         .with_queries_limit(queries_limit)
+        .with_query_queue(
+            options.query.query_queue_size,
+            options.query.query_queue_timeout,
+        )
         .with_memory_pool(memory_pool)
         .build()?;
 
+    PipeManager::new(coord, query_dispatcher.clone()).start();
+
     let mut builder = CnosdbmsBuilder::default();
 
     let access_control_no_check = AccessControlNoCheck::new(meta_manager);
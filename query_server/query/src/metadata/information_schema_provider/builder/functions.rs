@@ -0,0 +1,59 @@
+use std::sync::Arc;
+
+use datafusion::arrow::array::StringBuilder;
+use datafusion::arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::datasource::MemTable;
+use datafusion::error::DataFusionError;
+use lazy_static::lazy_static;
+
+lazy_static! {
+    static ref SCHEMA: SchemaRef = Arc::new(Schema::new(vec![
+        Field::new("function_name", DataType::Utf8, false),
+        Field::new("function_type", DataType::Utf8, false),
+    ]));
+}
+
+/// Builds the `information_schema.FUNCTIONS` table row by row
+pub struct InformationSchemaFunctionsBuilder {
+    function_names: StringBuilder,
+    function_types: StringBuilder,
+}
+
+impl Default for InformationSchemaFunctionsBuilder {
+    fn default() -> Self {
+        Self {
+            function_names: StringBuilder::new(),
+            function_types: StringBuilder::new(),
+        }
+    }
+}
+
+impl InformationSchemaFunctionsBuilder {
+    pub fn append_row(&mut self, function_name: impl AsRef<str>, function_type: impl AsRef<str>) {
+        // Note: append_value is actually infallable.
+        self.function_names.append_value(function_name.as_ref());
+        self.function_types.append_value(function_type.as_ref());
+    }
+}
+
+impl TryFrom<InformationSchemaFunctionsBuilder> for MemTable {
+    type Error = DataFusionError;
+
+    fn try_from(value: InformationSchemaFunctionsBuilder) -> Result<Self, Self::Error> {
+        let InformationSchemaFunctionsBuilder {
+            mut function_names,
+            mut function_types,
+        } = value;
+
+        let batch = RecordBatch::try_new(
+            SCHEMA.clone(),
+            vec![
+                Arc::new(function_names.finish()),
+                Arc::new(function_types.finish()),
+            ],
+        )?;
+
+        MemTable::try_new(SCHEMA.clone(), vec![vec![batch]])
+    }
+}
@@ -2,7 +2,9 @@ pub mod columns;
 pub mod database_privileges;
 pub mod databases;
 pub mod enabled_roles;
+pub mod functions;
 pub mod members;
+pub mod pipes;
 pub mod queries;
 pub mod roles;
 pub mod tables;
@@ -0,0 +1,144 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use datafusion::arrow::array::{StringBuilder, TimestampNanosecondBuilder, UInt64Builder};
+use datafusion::arrow::datatypes::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::datasource::MemTable;
+use datafusion::error::DataFusionError;
+use lazy_static::lazy_static;
+use models::meta_data::PipeInfo;
+
+lazy_static! {
+    static ref SCHEMA: SchemaRef = Arc::new(Schema::new(vec![
+        Field::new("tenant_name", DataType::Utf8, false),
+        Field::new("pipe_name", DataType::Utf8, false),
+        Field::new("owner", DataType::Utf8, false),
+        Field::new("table_name", DataType::Utf8, false),
+        Field::new("node_id", DataType::UInt64, false),
+        Field::new("interval", DataType::Utf8, false),
+        Field::new(
+            "create_time",
+            DataType::Timestamp(TimeUnit::Nanosecond, None),
+            false
+        ),
+        Field::new("state", DataType::Utf8, false),
+        Field::new("files_loaded", DataType::UInt64, false),
+        Field::new("rows_loaded", DataType::UInt64, false),
+        Field::new("files_failed", DataType::UInt64, false),
+        Field::new(
+            "last_load_time",
+            DataType::Timestamp(TimeUnit::Nanosecond, None),
+            true
+        ),
+        Field::new("last_error", DataType::Utf8, true),
+        Field::new("definition", DataType::Utf8, false),
+    ]));
+}
+
+/// Builds the `information_schema.PIPES` table row by row
+pub struct InformationSchemaPipesBuilder {
+    tenant_names: StringBuilder,
+    pipe_names: StringBuilder,
+    owners: StringBuilder,
+    table_names: StringBuilder,
+    node_ids: UInt64Builder,
+    intervals: StringBuilder,
+    create_times: TimestampNanosecondBuilder,
+    states: StringBuilder,
+    files_loaded: UInt64Builder,
+    rows_loaded: UInt64Builder,
+    files_failed: UInt64Builder,
+    last_load_times: TimestampNanosecondBuilder,
+    last_errors: StringBuilder,
+    definitions: StringBuilder,
+}
+
+impl Default for InformationSchemaPipesBuilder {
+    fn default() -> Self {
+        Self {
+            tenant_names: StringBuilder::new(),
+            pipe_names: StringBuilder::new(),
+            owners: StringBuilder::new(),
+            table_names: StringBuilder::new(),
+            node_ids: UInt64Builder::new(),
+            intervals: StringBuilder::new(),
+            create_times: TimestampNanosecondBuilder::new(),
+            states: StringBuilder::new(),
+            files_loaded: UInt64Builder::new(),
+            rows_loaded: UInt64Builder::new(),
+            files_failed: UInt64Builder::new(),
+            last_load_times: TimestampNanosecondBuilder::new(),
+            last_errors: StringBuilder::new(),
+            definitions: StringBuilder::new(),
+        }
+    }
+}
+
+impl InformationSchemaPipesBuilder {
+    pub fn append_row(&mut self, tenant_name: impl AsRef<str>, pipe: &PipeInfo) {
+        // Note: append_value is actually infallable.
+        self.tenant_names.append_value(tenant_name.as_ref());
+        self.pipe_names.append_value(&pipe.name);
+        self.owners.append_value(&pipe.owner);
+        self.table_names.append_value(&pipe.table);
+        self.node_ids.append_value(pipe.node_id);
+        self.intervals
+            .append_value(format!("{:?}", Duration::from_millis(pipe.interval)));
+        self.create_times.append_value(pipe.create_time);
+        self.states.append_value(pipe.status.state());
+        self.files_loaded.append_value(pipe.status.files_loaded);
+        self.rows_loaded.append_value(pipe.status.rows_loaded);
+        self.files_failed.append_value(pipe.status.files_failed);
+        self.last_load_times
+            .append_option(pipe.status.last_load_time);
+        self.last_errors
+            .append_option(pipe.status.last_error.as_ref());
+        self.definitions.append_value(&pipe.definition);
+    }
+}
+
+impl TryFrom<InformationSchemaPipesBuilder> for MemTable {
+    type Error = DataFusionError;
+
+    fn try_from(value: InformationSchemaPipesBuilder) -> Result<Self, Self::Error> {
+        let InformationSchemaPipesBuilder {
+            mut tenant_names,
+            mut pipe_names,
+            mut owners,
+            mut table_names,
+            mut node_ids,
+            mut intervals,
+            mut create_times,
+            mut states,
+            mut files_loaded,
+            mut rows_loaded,
+            mut files_failed,
+            mut last_load_times,
+            mut last_errors,
+            mut definitions,
+        } = value;
+
+        let batch = RecordBatch::try_new(
+            SCHEMA.clone(),
+            vec![
+                Arc::new(tenant_names.finish()),
+                Arc::new(pipe_names.finish()),
+                Arc::new(owners.finish()),
+                Arc::new(table_names.finish()),
+                Arc::new(node_ids.finish()),
+                Arc::new(intervals.finish()),
+                Arc::new(create_times.finish()),
+                Arc::new(states.finish()),
+                Arc::new(files_loaded.finish()),
+                Arc::new(rows_loaded.finish()),
+                Arc::new(files_failed.finish()),
+                Arc::new(last_load_times.finish()),
+                Arc::new(last_errors.finish()),
+                Arc::new(definitions.finish()),
+            ],
+        )?;
+
+        MemTable::try_new(SCHEMA.clone(), vec![vec![batch]])
+    }
+}
@@ -17,6 +17,7 @@ lazy_static! {
         Field::new("tenant_name", DataType::Utf8, false),
         Field::new("state", DataType::Utf8, false),
         Field::new("duration", DataType::Float64, false),
+        Field::new("priority", DataType::Utf8, false),
     ]));
 }
 
@@ -30,6 +31,7 @@ pub struct InformationSchemaQueriesBuilder {
     tenant_names: StringBuilder,
     states: StringBuilder,
     durations: Float64Builder,
+    priorities: StringBuilder,
 }
 
 impl Default for InformationSchemaQueriesBuilder {
@@ -43,6 +45,7 @@ impl Default for InformationSchemaQueriesBuilder {
             tenant_names: StringBuilder::new(),
             states: StringBuilder::new(),
             durations: Float64Builder::new(),
+            priorities: StringBuilder::new(),
         }
     }
 }
@@ -59,6 +62,7 @@ impl InformationSchemaQueriesBuilder {
         tenant_name: impl AsRef<str>,
         state: impl AsRef<str>,
         duration: f64,
+        priority: impl AsRef<str>,
     ) {
         // Note: append_value is actually infallable.
         self.query_ids.append_value(query_id.as_ref());
@@ -69,6 +73,7 @@ impl InformationSchemaQueriesBuilder {
         self.tenant_names.append_value(tenant_name.as_ref());
         self.states.append_value(state.as_ref());
         self.durations.append_value(duration);
+        self.priorities.append_value(priority.as_ref());
     }
 }
 
@@ -85,6 +90,7 @@ impl TryFrom<InformationSchemaQueriesBuilder> for MemTable {
             mut tenant_names,
             mut states,
             mut durations,
+            mut priorities,
         } = value;
 
         let batch = RecordBatch::try_new(
@@ -98,6 +104,7 @@ impl TryFrom<InformationSchemaQueriesBuilder> for MemTable {
                 Arc::new(tenant_names.finish()),
                 Arc::new(states.finish()),
                 Arc::new(durations.finish()),
+                Arc::new(priorities.finish()),
             ],
         )?;
 
@@ -0,0 +1,89 @@
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use datafusion::datasource::MemTable;
+use datafusion::logical_expr::aggregate_function::AggregateFunction;
+use datafusion::logical_expr::BuiltinScalarFunction;
+use meta::error::MetaError;
+use meta::MetaClientRef;
+use models::auth::user::User;
+use spi::query::function::FuncMetaManagerRef;
+
+use crate::dispatcher::query_tracker::QueryTracker;
+use crate::metadata::information_schema_provider::builder::functions::InformationSchemaFunctionsBuilder;
+use crate::metadata::information_schema_provider::InformationSchemaTableFactory;
+
+const INFORMATION_SCHEMA_FUNCTIONS: &str = "FUNCTIONS";
+
+const SCALAR: &str = "SCALAR";
+const AGGREGATE: &str = "AGGREGATE";
+
+/// Names of the builtin functions of datafusion, only the ones recognized
+/// by the datafusion in use are listed.
+#[rustfmt::skip]
+const BUILTIN_FUNCTIONS: &[&str] = &[
+    "abs", "acos", "approx_distinct", "approx_median", "approx_percentile_cont",
+    "approx_percentile_cont_with_weight", "array", "array_agg", "arrow_typeof", "ascii", "asin",
+    "atan", "atan2", "avg", "bit_length", "btrim", "ceil", "char_length", "character_length",
+    "chr", "coalesce", "concat", "concat_ws", "corr", "cos", "count", "covar", "covar_pop",
+    "covar_samp", "current_date", "current_time", "date_bin", "date_part", "date_trunc",
+    "digest", "exp", "floor", "from_unixtime", "grouping", "initcap", "left", "length", "ln",
+    "log", "log10", "log2", "lower", "lpad", "ltrim", "make_array", "max", "md5", "mean",
+    "median", "min", "now", "nullif", "octet_length", "pow", "power", "random", "regexp_match",
+    "regexp_replace", "repeat", "replace", "reverse", "right", "round", "rpad", "rtrim",
+    "sha224", "sha256", "sha384", "sha512", "signum", "sin", "split_part", "sqrt",
+    "starts_with", "stddev", "stddev_pop", "stddev_samp", "strpos", "struct", "substr", "sum",
+    "tan", "to_hex", "to_timestamp", "to_timestamp_micros", "to_timestamp_millis",
+    "to_timestamp_seconds", "translate", "trim", "trunc", "upper", "uuid", "var", "var_pop",
+    "var_samp",
+];
+
+/// This view shows the functions that can be called, builtin and user defined.
+pub struct FunctionsFactory {
+    func_manager: FuncMetaManagerRef,
+}
+
+impl FunctionsFactory {
+    pub fn new(func_manager: FuncMetaManagerRef) -> Self {
+        Self { func_manager }
+    }
+}
+
+#[async_trait::async_trait]
+impl InformationSchemaTableFactory for FunctionsFactory {
+    fn table_name(&self) -> &'static str {
+        INFORMATION_SCHEMA_FUNCTIONS
+    }
+
+    async fn create(
+        &self,
+        _user: &User,
+        _metadata: MetaClientRef,
+        _query_tracker: Arc<QueryTracker>,
+    ) -> std::result::Result<Arc<MemTable>, MetaError> {
+        let mut functions = BTreeMap::new();
+        for name in BUILTIN_FUNCTIONS {
+            if BuiltinScalarFunction::from_str(name).is_ok() {
+                functions.insert(name.to_string(), SCALAR);
+            } else if AggregateFunction::from_str(name).is_ok() {
+                functions.insert(name.to_string(), AGGREGATE);
+            }
+        }
+        for name in self.func_manager.udfs() {
+            functions.insert(name.to_ascii_lowercase(), SCALAR);
+        }
+        for name in self.func_manager.udafs() {
+            functions.insert(name.to_ascii_lowercase(), AGGREGATE);
+        }
+
+        let mut builder = InformationSchemaFunctionsBuilder::default();
+        for (name, function_type) in functions {
+            builder.append_row(name, function_type);
+        }
+
+        let mem_table = MemTable::try_from(builder)
+            .map_err(|e| MetaError::CommonError { msg: e.to_string() })?;
+        Ok(Arc::new(mem_table))
+    }
+}
@@ -0,0 +1,49 @@
+use std::sync::Arc;
+
+use datafusion::datasource::MemTable;
+use meta::error::MetaError;
+use meta::MetaClientRef;
+use models::auth::user::User;
+use models::oid::Identifier;
+
+use crate::dispatcher::query_tracker::QueryTracker;
+use crate::metadata::information_schema_provider::builder::pipes::InformationSchemaPipesBuilder;
+use crate::metadata::information_schema_provider::InformationSchemaTableFactory;
+
+const INFORMATION_SCHEMA_PIPES: &str = "PIPES";
+
+/// This view shows the pipes and their load status under the current tenant.
+///
+/// All records of this view are visible to the Owner of the current tenant.
+///
+/// For non-Owner members, only the pipes owned by the current member are displayed.
+pub struct PipesFactory {}
+
+#[async_trait::async_trait]
+impl InformationSchemaTableFactory for PipesFactory {
+    fn table_name(&self) -> &'static str {
+        INFORMATION_SCHEMA_PIPES
+    }
+
+    async fn create(
+        &self,
+        user: &User,
+        metadata: MetaClientRef,
+        _query_tracker: Arc<QueryTracker>,
+    ) -> std::result::Result<Arc<MemTable>, MetaError> {
+        let mut builder = InformationSchemaPipesBuilder::default();
+
+        let tenant = metadata.tenant();
+        let can_access_all = user.can_access_system(*tenant.id());
+
+        for pipe in metadata.pipes().await? {
+            if can_access_all || pipe.owner == user.desc().name() {
+                builder.append_row(tenant.name(), &pipe);
+            }
+        }
+
+        let mem_table = MemTable::try_from(builder)
+            .map_err(|e| MetaError::CommonError { msg: e.to_string() })?;
+        Ok(Arc::new(mem_table))
+    }
+}
@@ -62,6 +62,7 @@ impl InformationSchemaTableFactory for QueriesFactory {
 
             let state = status.query_state();
             let duration = status.duration().as_secs_f64();
+            let priority = info.priority();
 
             builder.append_row(
                 query_id,
@@ -72,6 +73,7 @@ impl InformationSchemaTableFactory for QueriesFactory {
                 tenant_name,
                 state,
                 duration,
+                priority,
             );
         }
 
@@ -9,12 +9,15 @@ use datafusion::datasource::MemTable;
 use meta::error::MetaError;
 use meta::MetaClientRef;
 use models::auth::user::User;
+use spi::query::function::FuncMetaManagerRef;
 
 use self::factory::columns::ColumnsFactory;
 use self::factory::database_privileges::DatabasePrivilegesFactory;
 use self::factory::databases::DatabasesFactory;
 use self::factory::enabled_roles::EnabledRolesFactory;
+use self::factory::functions::FunctionsFactory;
 use self::factory::members::MembersFactory;
+use self::factory::pipes::PipesFactory;
 use self::factory::queries::QueriesFactory;
 use self::factory::roles::RolesFactory;
 use self::factory::tables::TablesFactory;
@@ -28,7 +31,7 @@ pub struct InformationSchemaProvider {
 }
 
 impl InformationSchemaProvider {
-    pub fn new(query_tracker: Arc<QueryTracker>) -> Self {
+    pub fn new(query_tracker: Arc<QueryTracker>, func_manager: FuncMetaManagerRef) -> Self {
         let mut provider = Self {
             query_tracker,
             table_factories: Default::default(),
@@ -42,6 +45,8 @@ impl InformationSchemaProvider {
         provider.register_table_factory(Box::new(DatabasePrivilegesFactory {}));
         provider.register_table_factory(Box::new(MembersFactory {}));
         provider.register_table_factory(Box::new(QueriesFactory {}));
+        provider.register_table_factory(Box::new(PipesFactory {}));
+        provider.register_table_factory(Box::new(FunctionsFactory::new(func_manager)));
 
         provider
     }
@@ -25,6 +25,7 @@ use spi::query::session::SessionCtx;
 
 use self::cluster_schema_provider::ClusterSchemaProvider;
 use self::information_schema_provider::InformationSchemaProvider;
+use crate::data_source::file_format::orc::{orc_listing_options, ORC_FILE_TYPE};
 use crate::data_source::table_provider::tskv::ClusterTable;
 use crate::dispatcher::query_tracker::QueryTracker;
 use crate::function::simple_func_manager::SimpleFunctionMetadataManager;
@@ -70,14 +71,18 @@ impl MetadataProvider {
         session: SessionCtx,
         default_meta: MetaClientRef,
     ) -> Self {
+        let func_manager: FuncMetaManagerRef = Arc::new(func_manager);
         Self {
             coord,
             // TODO refactor
             config_options: session.inner().state().config_options().clone(),
             session,
             meta_client,
-            func_manager: Arc::new(func_manager),
-            information_schema_provider: InformationSchemaProvider::new(query_tracker),
+            information_schema_provider: InformationSchemaProvider::new(
+                query_tracker,
+                func_manager.clone(),
+            ),
+            func_manager,
             cluster_schema_provider: ClusterSchemaProvider::new(),
             usage_schema_provider: UsageSchemaProvider::new(default_meta),
             access_databases: Default::default(),
@@ -158,7 +163,11 @@ impl MetadataProvider {
                 }
                 TableSchema::ExternalTableSchema(schema) => {
                     let table_path = ListingTableUrl::parse(&schema.location)?;
-                    let options = schema.table_options()?;
+                    let options = if schema.file_type == ORC_FILE_TYPE {
+                        orc_listing_options(schema.target_partitions)
+                    } else {
+                        schema.table_options()?
+                    };
                     let config = ListingTableConfig::new(table_path)
                         .with_listing_options(options)
                         .with_schema(Arc::new(schema.schema.clone()));
@@ -16,6 +16,7 @@ pub use vnode_disk_storage::USAGE_SCHEMA_VNODE_DISK_STORAGE;
 use crate::data_source::table_provider::tskv::ClusterTable;
 use crate::metadata::usage_schema_provider::coord_data_in::CoordDataIn;
 use crate::metadata::usage_schema_provider::coord_data_out::CoordDataOut;
+use crate::metadata::usage_schema_provider::coord_throttled::CoordThrottled;
 use crate::metadata::usage_schema_provider::user_queries::UserQueries;
 use crate::metadata::usage_schema_provider::user_writes::UserWrites;
 use crate::metadata::usage_schema_provider::vnode_cache_size::VnodeCacheSize;
@@ -23,6 +24,7 @@ use crate::metadata::usage_schema_provider::vnode_disk_storage::VnodeDiskStorage
 
 mod coord_data_in;
 mod coord_data_out;
+mod coord_throttled;
 mod user_queries;
 mod user_writes;
 mod vnode_cache_size;
@@ -45,6 +47,7 @@ impl UsageSchemaProvider {
         provider.register_table_factory(Box::new(VnodeCacheSize {}));
         provider.register_table_factory(Box::new(CoordDataIn {}));
         provider.register_table_factory(Box::new(CoordDataOut {}));
+        provider.register_table_factory(Box::new(CoordThrottled {}));
         provider.register_table_factory(Box::new(UserQueries {}));
         provider.register_table_factory(Box::new(UserWrites {}));
         provider
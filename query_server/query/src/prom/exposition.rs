@@ -0,0 +1,227 @@
+//! Parser of the Prometheus text exposition format
+//!
+//! ```text
+//! # HELP http_requests_total The total number of HTTP requests.
+//! # TYPE http_requests_total counter
+//! http_requests_total{method="post",code="200"} 1027 1395066363000
+//! ```
+
+/// A sample of the exposition
+#[derive(Debug, PartialEq)]
+pub struct Sample {
+    pub metric: String,
+    pub labels: Vec<(String, String)>,
+    pub value: f64,
+    /// Milliseconds since epoch, the time of the scrape is used if not present
+    pub timestamp: Option<i64>,
+}
+
+/// Parse a line of the exposition, returns `None` for blank lines and comments
+pub fn parse_line(line: &str) -> Result<Option<Sample>, String> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+
+    let mut cursor = Cursor { buf: line, pos: 0 };
+
+    let metric = cursor
+        .take_while(|c, first| {
+            c.is_ascii_alphabetic() || c == '_' || c == ':' || (!first && c.is_ascii_digit())
+        })
+        .to_string();
+    if metric.is_empty() {
+        return Err(format!("invalid metric name at {}", cursor.pos));
+    }
+
+    let mut labels = vec![];
+    cursor.skip_whitespace();
+    if cursor.eat('{') {
+        loop {
+            cursor.skip_whitespace();
+            if cursor.eat('}') {
+                break;
+            }
+
+            let name = cursor
+                .take_while(|c, first| {
+                    c.is_ascii_alphabetic() || c == '_' || (!first && c.is_ascii_digit())
+                })
+                .to_string();
+            if name.is_empty() {
+                return Err(format!("invalid label name at {}", cursor.pos));
+            }
+            cursor.skip_whitespace();
+            cursor.expect('=')?;
+            cursor.skip_whitespace();
+            let value = cursor.label_value()?;
+            labels.push((name, value));
+
+            cursor.skip_whitespace();
+            if !cursor.eat(',') {
+                cursor.expect('}')?;
+                break;
+            }
+        }
+    }
+
+    cursor.skip_whitespace();
+    let value = cursor.take_while(|c, _| !c.is_whitespace());
+    let value = match value {
+        "NaN" => f64::NAN,
+        "+Inf" | "Inf" => f64::INFINITY,
+        "-Inf" => f64::NEG_INFINITY,
+        _ => value
+            .parse::<f64>()
+            .map_err(|e| format!("invalid sample value '{}': {}", value, e))?,
+    };
+
+    cursor.skip_whitespace();
+    let timestamp = match cursor.take_while(|c, _| !c.is_whitespace()) {
+        "" => None,
+        timestamp => Some(
+            timestamp
+                .parse::<i64>()
+                .map_err(|e| format!("invalid timestamp '{}': {}", timestamp, e))?,
+        ),
+    };
+
+    cursor.skip_whitespace();
+    if cursor.pos < line.len() {
+        return Err(format!("unexpected content at {}", cursor.pos));
+    }
+
+    Ok(Some(Sample {
+        metric,
+        labels,
+        value,
+        timestamp,
+    }))
+}
+
+struct Cursor<'a> {
+    buf: &'a str,
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn peek(&self) -> Option<char> {
+        self.buf[self.pos..].chars().next()
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        if self.peek() == Some(expected) {
+            self.pos += expected.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        if self.eat(expected) {
+            Ok(())
+        } else {
+            Err(format!("expected '{}' at {}", expected, self.pos))
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.take_while(|c, _| c.is_whitespace());
+    }
+
+    /// Take the chars matching the predicate, which is also told whether the char is the first one
+    fn take_while(&mut self, predicate: impl Fn(char, bool) -> bool) -> &'a str {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if !predicate(c, self.pos == start) {
+                break;
+            }
+            self.pos += c.len_utf8();
+        }
+        &self.buf[start..self.pos]
+    }
+
+    /// A quoted label value, `\\`, `\"` and `\n` are unescaped
+    fn label_value(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut value = String::new();
+        loop {
+            let c = self
+                .peek()
+                .ok_or_else(|| "unterminated label value".to_string())?;
+            self.pos += c.len_utf8();
+            match c {
+                '"' => return Ok(value),
+                '\\' => {
+                    let escaped = self
+                        .peek()
+                        .ok_or_else(|| "unterminated label value".to_string())?;
+                    self.pos += escaped.len_utf8();
+                    match escaped {
+                        'n' => value.push('\n'),
+                        '\\' | '"' => value.push(escaped),
+                        _ => {
+                            value.push('\\');
+                            value.push(escaped);
+                        }
+                    }
+                }
+                _ => value.push(c),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_line() {
+        assert_eq!(parse_line("").unwrap(), None);
+        assert_eq!(
+            parse_line("# TYPE http_requests_total counter").unwrap(),
+            None
+        );
+
+        assert_eq!(
+            parse_line(r#"http_requests_total{method="post",code="200",} 1027 1395066363000"#)
+                .unwrap(),
+            Some(Sample {
+                metric: "http_requests_total".to_string(),
+                labels: vec![
+                    ("method".to_string(), "post".to_string()),
+                    ("code".to_string(), "200".to_string()),
+                ],
+                value: 1027.0,
+                timestamp: Some(1395066363000),
+            })
+        );
+
+        assert_eq!(
+            parse_line(r#"msdos_file_access_time_seconds{path="C:\\DIR\\FILE.TXT",error="Cannot find file:\n\"FILE.TXT\""} 1.458255915e9"#)
+                .unwrap(),
+            Some(Sample {
+                metric: "msdos_file_access_time_seconds".to_string(),
+                labels: vec![
+                    ("path".to_string(), r"C:\DIR\FILE.TXT".to_string()),
+                    ("error".to_string(), "Cannot find file:\n\"FILE.TXT\"".to_string()),
+                ],
+                value: 1.458255915e9,
+                timestamp: None,
+            })
+        );
+
+        let sample = parse_line("go_gc_duration_seconds_count +Inf")
+            .unwrap()
+            .unwrap();
+        assert!(sample.labels.is_empty());
+        assert_eq!(sample.value, f64::INFINITY);
+
+        assert!(parse_line("1metric 1").is_err());
+        assert!(parse_line(r#"metric{label="value} 1"#).is_err());
+        assert!(parse_line("metric abc").is_err());
+        assert!(parse_line("metric 1 2 3").is_err());
+    }
+}
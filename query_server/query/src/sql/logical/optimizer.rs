@@ -28,6 +28,7 @@ use spi::Result;
 use crate::extension::logical::optimizer_rule::implicit_type_conversion::ImplicitTypeConversion;
 use crate::extension::logical::optimizer_rule::push_down_projection::PushDownProjectionAdapter;
 use crate::extension::logical::optimizer_rule::reject_cross_join::RejectCrossJoin;
+use crate::extension::logical::optimizer_rule::rewrite_avg_to_sum_count::RewriteAvgToSumCount;
 use crate::extension::logical::optimizer_rule::rewrite_tag_scan::RewriteTagScan;
 use crate::extension::logical::optimizer_rule::transform_bottom_func_to_topk_node::TransformBottomFuncToTopkNodeRule;
 use crate::extension::logical::optimizer_rule::transform_time_window::TransformTimeWindowRule;
@@ -83,6 +84,8 @@ impl Default for DefaultLogicalOptimizer {
             Arc::new(PushDownLimit::new()),
             Arc::new(PushDownFilter::new()),
             Arc::new(SingleDistinctToGroupBy::new()),
+            // cnosdb rule, makes AVG able to be pushed down
+            Arc::new(RewriteAvgToSumCount {}),
             Arc::new(PushDownAggregation::new()),
             // The previous optimizations added expressions and projections,
             // that might benefit from the following rules
@@ -95,7 +98,6 @@ impl Default for DefaultLogicalOptimizer {
             Arc::new(RewriteTagScan {}),
             Arc::new(TransformBottomFuncToTopkNodeRule {}),
             Arc::new(TransformTopkFuncToTopkNodeRule {}),
-            Arc::new(TransformTimeWindowRule),
         ];
 
         Self { rules }
@@ -104,10 +106,14 @@ impl Default for DefaultLogicalOptimizer {
 
 impl LogicalOptimizer for DefaultLogicalOptimizer {
     fn optimize(&self, plan: &LogicalPlan, session: &SessionCtx) -> Result<LogicalPlan> {
+        let mut rules = self.rules.clone();
+        // rules depending on the session
+        rules.push(Arc::new(TransformTimeWindowRule::new(*session.time_zone())));
+
         session
             .inner()
             .state()
-            .with_optimizer_rules(self.rules.clone())
+            .with_optimizer_rules(rules)
             .optimize(plan)
             .map_err(|e| e.into())
     }
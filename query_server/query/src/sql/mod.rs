@@ -3,3 +3,4 @@ pub mod optimizer;
 pub mod parser;
 pub mod physical;
 pub mod planner;
+pub mod prepared_statement;
@@ -26,6 +26,17 @@ impl Optimizer for CascadeOptimizer {
         plan: &LogicalPlan,
         session: &SessionCtx,
     ) -> Result<Arc<dyn ExecutionPlan>> {
+        let optimized_logical_plan = self.optimize_logical_plan(plan, session)?;
+
+        self.create_physical_plan(&optimized_logical_plan, session)
+            .await
+    }
+
+    fn optimize_logical_plan(
+        &self,
+        plan: &LogicalPlan,
+        session: &SessionCtx,
+    ) -> Result<LogicalPlan> {
         debug!("Original logical plan:\n{}\n", plan.display_indent_schema(),);
 
         let optimized_logical_plan = self.logical_optimizer.optimize(plan, session)?;
@@ -35,9 +46,17 @@ impl Optimizer for CascadeOptimizer {
             optimized_logical_plan.display_indent_schema(),
         );
 
+        Ok(optimized_logical_plan)
+    }
+
+    async fn create_physical_plan(
+        &self,
+        plan: &LogicalPlan,
+        session: &SessionCtx,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
         let physical_plan = self
             .physical_planner
-            .create_physical_plan(&optimized_logical_plan, session)
+            .create_physical_plan(plan, session)
             .await?;
 
         let optimized_physical_plan = self.physical_optimizer.optimize(physical_plan, session)?;
@@ -117,7 +136,7 @@ mod test {
     use datafusion::optimizer::{OptimizerContext, OptimizerRule};
     use datafusion::physical_plan::planner::DefaultPhysicalPlanner;
     use datafusion::physical_plan::{displayable, PhysicalPlanner};
-    use datafusion::prelude::{col, count, max, min, sum, Expr, SessionConfig};
+    use datafusion::prelude::{col, count, lit, max, min, sum, Expr, SessionConfig};
     use models::schema::{ColumnType, TableColumn, TskvTableSchema};
     use models::ValueType;
 
@@ -251,14 +270,16 @@ mod test {
         test_plan(
             plan,
             "\
-            Aggregate: groupBy=[[]], aggr=[[MAX(?table?.value)]]\
-            \n  TableScan: ?table? projection=[value]",
+            Projection: MAX(MAX(?table?.value)) AS MAX(?table?.value)\
+            \n  Aggregate: groupBy=[[]], aggr=[[MAX(MAX(?table?.value))]]\
+            \n    TableScan: ?table?, grouping=[], agg=[MAX(?table?.value)]",
             "\
-            AggregateExec: mode=Final, gby=[], aggr=[MAX(?table?.value)]\
-            \n  CoalescePartitionsExec\
-            \n    AggregateExec: mode=Partial, gby=[], aggr=[MAX(?table?.value)]\
-            \n      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1\
-            \n        TskvExec: limit=None, predicate=ColumnDomains { column_to_domain: Some({}) }, projection=[value]\
+            ProjectionExec: expr=[MAX(MAX(?table?.value))@0 as MAX(?table?.value)]\
+            \n  AggregateExec: mode=Final, gby=[], aggr=[MAX(MAX(?table?.value))]\
+            \n    CoalescePartitionsExec\
+            \n      AggregateExec: mode=Partial, gby=[], aggr=[MAX(MAX(?table?.value))]\
+            \n        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1\
+            \n          AggregateFilterTskvExec: agg=[[Max(\"value\")]], filter=[Predicate { exprs: [], pushed_down_domains: ColumnDomains { column_to_domain: Some({}) }, limit: None }]\
             \n",
         ).await
     }
@@ -294,14 +315,16 @@ mod test {
         test_plan(
             plan,
             "\
-            Aggregate: groupBy=[[]], aggr=[[MIN(?table?.value)]]\
-            \n  TableScan: ?table? projection=[value]",
+            Projection: MIN(MIN(?table?.value)) AS MIN(?table?.value)\
+            \n  Aggregate: groupBy=[[]], aggr=[[MIN(MIN(?table?.value))]]\
+            \n    TableScan: ?table?, grouping=[], agg=[MIN(?table?.value)]",
             "\
-            AggregateExec: mode=Final, gby=[], aggr=[MIN(?table?.value)]\
-            \n  CoalescePartitionsExec\
-            \n    AggregateExec: mode=Partial, gby=[], aggr=[MIN(?table?.value)]\
-            \n      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1\
-            \n        TskvExec: limit=None, predicate=ColumnDomains { column_to_domain: Some({}) }, projection=[value]\
+            ProjectionExec: expr=[MIN(MIN(?table?.value))@0 as MIN(?table?.value)]\
+            \n  AggregateExec: mode=Final, gby=[], aggr=[MIN(MIN(?table?.value))]\
+            \n    CoalescePartitionsExec\
+            \n      AggregateExec: mode=Partial, gby=[], aggr=[MIN(MIN(?table?.value))]\
+            \n        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1\
+            \n          AggregateFilterTskvExec: agg=[[Min(\"value\")]], filter=[Predicate { exprs: [], pushed_down_domains: ColumnDomains { column_to_domain: Some({}) }, limit: None }]\
             \n",
         ).await
     }
@@ -336,15 +359,43 @@ mod test {
         test_plan(
             plan,
             "\
-            Aggregate: groupBy=[[]], aggr=[[SUM(?table?.value)]]\
-            \n  TableScan: ?table? projection=[value]",
+            Projection: SUM(SUM(?table?.value)) AS SUM(?table?.value)\
+            \n  Aggregate: groupBy=[[]], aggr=[[SUM(SUM(?table?.value))]]\
+            \n    TableScan: ?table?, grouping=[], agg=[SUM(?table?.value)]",
             "\
-            AggregateExec: mode=Final, gby=[], aggr=[SUM(?table?.value)]\
-            \n  CoalescePartitionsExec\
-            \n    AggregateExec: mode=Partial, gby=[], aggr=[SUM(?table?.value)]\
-            \n      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1\
-            \n        TskvExec: limit=None, predicate=ColumnDomains { column_to_domain: Some({}) }, projection=[value]\
+            ProjectionExec: expr=[SUM(SUM(?table?.value))@0 as SUM(?table?.value)]\
+            \n  AggregateExec: mode=Final, gby=[], aggr=[SUM(SUM(?table?.value))]\
+            \n    CoalescePartitionsExec\
+            \n      AggregateExec: mode=Partial, gby=[], aggr=[SUM(SUM(?table?.value))]\
+            \n        RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1\
+            \n          AggregateFilterTskvExec: agg=[[Sum(\"value\")]], filter=[Predicate { exprs: [], pushed_down_domains: ColumnDomains { column_to_domain: Some({}) }, limit: None }]\
             \n",
         ).await
     }
+
+    #[tokio::test]
+    async fn test_limit_with_field_filter() -> Result<()> {
+        let plan = LogicalPlanBuilder::from(test_table_scan()?)
+            .filter(col("value").gt(lit(90_i64)))?
+            .limit(0, Some(100))?
+            .build()?;
+
+        let opt_plan = optimize_plan(&plan)?;
+        let physical_plan = DefaultPhysicalPlanner::default()
+            .create_physical_plan(
+                &opt_plan,
+                &default_session_builder(SessionConfig::default().with_target_partitions(8)),
+            )
+            .await?;
+        let result_str = format!("{}", displayable(physical_plan.as_ref()).indent());
+
+        // the field filter is evaluated by the scan exactly, so the limit reaches the scan
+        assert!(!result_str.contains("FilterExec"), "{result_str}");
+        assert!(
+            result_str.contains("TskvExec: limit=Some(100)"),
+            "{result_str}"
+        );
+
+        Ok(())
+    }
 }
@@ -12,20 +12,21 @@ use datafusion::sql::sqlparser::dialect::keywords::Keyword;
 use datafusion::sql::sqlparser::dialect::{Dialect, GenericDialect};
 use datafusion::sql::sqlparser::parser::{IsOptional, Parser, ParserError};
 use datafusion::sql::sqlparser::tokenizer::{Token, TokenWithLocation, Tokenizer};
-use models::codec::Encoding;
+use models::codec::{Encoding, FieldIndexType};
 use models::meta_data::{NodeId, ReplicationSetId, VnodeId};
 use snafu::ResultExt;
 use spi::query::ast::{
-    self, parse_string_value, Action, AlterDatabase, AlterTable, AlterTableAction, AlterTenant,
-    AlterTenantOperation, AlterUser, AlterUserOperation, ChecksumGroup, ColumnOption, CompactVnode,
-    CopyIntoLocation, CopyIntoTable, CopyTarget, CopyVnode, CreateDatabase, CreateRole,
-    CreateStream, CreateTable, CreateTenant, CreateUser, DatabaseOptions, DescribeDatabase,
-    DescribeTable, DropDatabaseObject, DropGlobalObject, DropTenantObject, DropVnode, Explain,
-    ExtStatement, GrantRevoke, MoveVnode, OutputMode, Privilege, ShowSeries, ShowTagBody,
-    ShowTagValues, Trigger, UriLocation, With,
+    self, parse_string_value, Action, AlterDatabase, AlterNode, AlterTable, AlterTableAction,
+    AlterTenant, AlterTenantOperation, AlterUser, AlterUserOperation, ChecksumGroup, ColumnOption,
+    CompactVnode, CopyIntoLocation, CopyIntoTable, CopyTarget, CopyVnode, CreateDatabase,
+    CreatePipe, CreateRole, CreateStream, CreateTable, CreateTenant, CreateUser, DatabaseOptions,
+    DescribeDatabase, DescribeTable, DropDatabaseObject, DropGlobalObject, DropTenantObject,
+    DropVnode, Explain, ExtStatement, GrantRevoke, MoveVnode, OutputMode, Privilege, SetVariable,
+    ShowSeries, ShowTagBody, ShowTagValues, Trigger, UriLocation, With,
 };
 use spi::query::logical_planner::{DatabaseObjectType, GlobalObjectType, TenantObjectType};
 use spi::query::parser::Parser as CnosdbParser;
+use spi::query::session::SessionVariable;
 use spi::ParserSnafu;
 use trace::debug;
 
@@ -56,6 +57,12 @@ enum CnosKeyWord {
     REPLICA,
     #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
     PRECISION,
+    #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+    COMPACTION,
+    #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+    COMPACTION_WINDOW,
+    #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+    COLD_DURATION,
 
     #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
     QUERIES,
@@ -95,6 +102,8 @@ enum CnosKeyWord {
     CHECKSUM,
     #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
     GROUP,
+    #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+    IO_RATE_LIMIT,
 
     #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
     STREAM,
@@ -114,6 +123,14 @@ enum CnosKeyWord {
     APPEND,
     #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
     UPDATE,
+
+    #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+    RESET,
+
+    #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+    PIPE,
+    #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+    PIPES,
 }
 
 impl FromStr for CnosKeyWord {
@@ -129,6 +146,9 @@ impl FromStr for CnosKeyWord {
             "VNODE_DURATION" => Ok(CnosKeyWord::VNODE_DURATION),
             "REPLICA" => Ok(CnosKeyWord::REPLICA),
             "PRECISION" => Ok(CnosKeyWord::PRECISION),
+            "COMPACTION" => Ok(CnosKeyWord::COMPACTION),
+            "COMPACTION_WINDOW" => Ok(CnosKeyWord::COMPACTION_WINDOW),
+            "COLD_DURATION" => Ok(CnosKeyWord::COLD_DURATION),
             "DATABASES" => Ok(CnosKeyWord::DATABASES),
             "QUERIES" => Ok(CnosKeyWord::QUERIES),
             "TENANT" => Ok(CnosKeyWord::TENANT),
@@ -148,6 +168,7 @@ impl FromStr for CnosKeyWord {
             "COMPACT" => Ok(CnosKeyWord::COMPACT),
             "CHECKSUM" => Ok(CnosKeyWord::CHECKSUM),
             "GROUP" => Ok(CnosKeyWord::GROUP),
+            "IO_RATE_LIMIT" => Ok(CnosKeyWord::IO_RATE_LIMIT),
             "STREAM" => Ok(CnosKeyWord::STREAM),
             "STREAMS" => Ok(CnosKeyWord::STREAMS),
             "TRIGGER" => Ok(CnosKeyWord::TRIGGER),
@@ -157,6 +178,9 @@ impl FromStr for CnosKeyWord {
             "COMPLETE" => Ok(CnosKeyWord::COMPLETE),
             "APPEND" => Ok(CnosKeyWord::APPEND),
             "UPDATE" => Ok(CnosKeyWord::UPDATE),
+            "RESET" => Ok(CnosKeyWord::RESET),
+            "PIPE" => Ok(CnosKeyWord::PIPE),
+            "PIPES" => Ok(CnosKeyWord::PIPES),
             _ => Err(ParserError::ParserError(format!(
                 "fail parse {} to CnosKeyWord",
                 s
@@ -280,6 +304,10 @@ impl<'a> ExtParser<'a> {
                     self.parser.next_token();
                     self.parse_explain()
                 }
+                Keyword::SET => {
+                    self.parser.next_token();
+                    self.parse_set_variable()
+                }
                 _ => {
                     if let Ok(word) = CnosKeyWord::from_str(&w.to_string()) {
                         return match word {
@@ -295,6 +323,10 @@ impl<'a> ExtParser<'a> {
                                 self.parser.next_token();
                                 self.parse_checksum()
                             }
+                            CnosKeyWord::RESET => {
+                                self.parser.next_token();
+                                self.parse_reset_variable()
+                            }
                             _ => Ok(ExtStatement::SqlStatement(Box::new(
                                 self.parser.parse_statement()?,
                             ))),
@@ -335,6 +367,8 @@ impl<'a> ExtParser<'a> {
             }
         } else if self.parse_cnos_keyword(CnosKeyWord::QUERIES) {
             self.parse_show_queries()
+        } else if self.parse_cnos_keyword(CnosKeyWord::PIPES) {
+            Ok(ExtStatement::ShowPipes)
         } else if self.parse_cnos_keyword(CnosKeyWord::STREAMS) {
             let verbose = self
                 .parser
@@ -342,14 +376,61 @@ impl<'a> ExtParser<'a> {
                 .then_some(true)
                 .unwrap_or_default();
             Ok(ExtStatement::ShowStreams(ast::ShowStreams { verbose }))
+        } else if self.parser.parse_keyword(Keyword::ALL) {
+            Ok(ExtStatement::ShowVariable(None))
+        } else if matches!(
+            &self.parser.peek_token().token,
+            Token::Word(w) if w.value.parse::<SessionVariable>().is_ok()
+        ) {
+            Ok(ExtStatement::ShowVariable(Some(
+                self.parser.parse_identifier()?,
+            )))
         } else {
             self.expected(
-                "TABLES or DATABASES or SERIES or TAG or QUERIES or STREAMS",
+                "TABLES or DATABASES or SERIES or TAG or QUERIES or PIPES or STREAMS or ALL or session variable",
                 self.parser.peek_token(),
             )
         }
     }
 
+    /// Parse a SQL SET statement
+    ///
+    /// SET [SESSION] <variable> { = | TO } { <value> | DEFAULT }
+    fn parse_set_variable(&mut self) -> Result<ExtStatement> {
+        let _ = self.parser.parse_keyword(Keyword::SESSION);
+        let variable = self.parser.parse_identifier()?;
+        if !self.consume_token(&Token::Eq) && !self.parser.parse_keyword(Keyword::TO) {
+            return self.expected("= or TO", self.parser.peek_token());
+        }
+
+        if self.parser.parse_keyword(Keyword::DEFAULT) {
+            return Ok(ExtStatement::ResetVariable(Some(variable)));
+        }
+
+        let token = self.parser.next_token();
+        let value = match token.token {
+            Token::SingleQuotedString(s) => s,
+            Token::Number(n, _) => n,
+            Token::Word(w) => w.value,
+            _ => return self.expected("variable value", token),
+        };
+
+        Ok(ExtStatement::SetVariable(SetVariable { variable, value }))
+    }
+
+    /// Parse a SQL RESET statement
+    ///
+    /// RESET { <variable> | ALL }
+    fn parse_reset_variable(&mut self) -> Result<ExtStatement> {
+        if self.parser.parse_keyword(Keyword::ALL) {
+            Ok(ExtStatement::ResetVariable(None))
+        } else {
+            Ok(ExtStatement::ResetVariable(Some(
+                self.parser.parse_identifier()?,
+            )))
+        }
+    }
+
     fn parse_on_database(&mut self) -> Result<Option<ObjectName>> {
         if self.parser.parse_keyword(Keyword::ON) {
             Ok(Some(self.parser.parse_object_name()?))
@@ -551,9 +632,26 @@ impl<'a> ExtParser<'a> {
             self.parse_alter_tenant()
         } else if self.parser.parse_keyword(Keyword::USER) {
             self.parse_alter_user()
+        } else if self.parse_cnos_keyword(CnosKeyWord::NODE) {
+            self.parse_alter_node()
         } else {
-            self.expected("TABLE/DATABASE/TENANT/USER", self.parser.peek_token())
+            self.expected("TABLE/DATABASE/TENANT/USER/NODE", self.parser.peek_token())
+        }
+    }
+
+    /// Parse `ALTER NODE <node_id> SET IO_RATE_LIMIT = <bytes per second>`
+    fn parse_alter_node(&mut self) -> Result<ExtStatement> {
+        let node_id = self.parse_number::<NodeId>()?;
+        self.parser.expect_keyword(Keyword::SET)?;
+        if self.parse_cnos_keyword(CnosKeyWord::IO_RATE_LIMIT).not() {
+            return self.expected("IO_RATE_LIMIT", self.parser.peek_token());
         }
+        self.parser.expect_token(&Token::Eq)?;
+        let io_rate_limit = self.parse_number::<u64>()?;
+        Ok(ExtStatement::AlterNode(AlterNode {
+            node_id,
+            io_rate_limit,
+        }))
     }
 
     fn parse_alter_table(&mut self) -> Result<ExtStatement> {
@@ -579,7 +677,8 @@ impl<'a> ExtParser<'a> {
             } else {
                 None
             };
-            let column = ColumnOption::new_field(field_name, data_type, encoding);
+            let index = self.parse_field_index_type()?;
+            let column = ColumnOption::new_field(field_name, data_type, encoding, index);
             Ok(ExtStatement::AlterTable(AlterTable {
                 table_name,
                 alter_action: AlterTableAction::AddColumn { column },
@@ -810,6 +909,12 @@ impl<'a> ExtParser<'a> {
             options.replica = Some(self.parse_number::<u64>()?);
         } else if self.parse_cnos_keyword(CnosKeyWord::PRECISION) {
             options.precision = Some(self.parse_string_value()?);
+        } else if self.parse_cnos_keyword(CnosKeyWord::COMPACTION) {
+            options.compaction = Some(self.parse_string_value()?);
+        } else if self.parse_cnos_keyword(CnosKeyWord::COMPACTION_WINDOW) {
+            options.compaction_window = Some(self.parse_string_value()?);
+        } else if self.parse_cnos_keyword(CnosKeyWord::COLD_DURATION) {
+            options.cold_duration = Some(self.parse_string_value()?);
         } else {
             return Ok(false);
         }
@@ -1027,6 +1132,45 @@ impl<'a> ExtParser<'a> {
         }))
     }
 
+    /// Parse a SQL CREATE PIPE statement
+    ///
+    /// CREATE PIPE [IF NOT EXISTS] <name> [INTERVAL = '<duration>'] AS COPY INTO <table> FROM <location> ...
+    fn parse_create_pipe(&mut self) -> Result<ExtStatement> {
+        let if_not_exists =
+            self.parser
+                .parse_keywords(&[Keyword::IF, Keyword::NOT, Keyword::EXISTS]);
+
+        let name = self.parser.parse_identifier()?;
+
+        let interval = if self.parser.parse_keyword(Keyword::INTERVAL) {
+            self.parser.expect_token(&Token::Eq)?;
+            Some(self.parse_string_value()?)
+        } else {
+            None
+        };
+
+        self.parser.expect_keyword(Keyword::AS)?;
+        self.parser.expect_keyword(Keyword::COPY)?;
+        self.parser.expect_keyword(Keyword::INTO)?;
+
+        let copy = match self.parse_copy_into()? {
+            ExtStatement::Copy(
+                copy @ ast::Copy {
+                    copy_target: CopyTarget::IntoTable(_),
+                    ..
+                },
+            ) => copy,
+            _ => return parser_err!("expected COPY INTO <table>, after AS"),
+        };
+
+        Ok(ExtStatement::CreatePipe(CreatePipe {
+            if_not_exists,
+            name,
+            interval,
+            copy,
+        }))
+    }
+
     /// Parse a SQL CREATE statement
     fn parse_create(&mut self) -> Result<ExtStatement> {
         // Currently only supports the creation of external tables
@@ -1044,6 +1188,8 @@ impl<'a> ExtParser<'a> {
             self.parse_create_role()
         } else if self.parse_cnos_keyword(CnosKeyWord::STREAM) {
             self.parse_create_stream()
+        } else if self.parse_cnos_keyword(CnosKeyWord::PIPE) {
+            self.parse_create_pipe()
         } else {
             self.expected("an object type after CREATE", self.parser.peek_token())
         }
@@ -1136,6 +1282,21 @@ impl<'a> ExtParser<'a> {
             }
         };
 
+        // PARTITION BY (<expr> [AS <name>] [, ...])
+        let partition_by = if self
+            .parser
+            .parse_keywords(&[Keyword::PARTITION, Keyword::BY])
+        {
+            self.parser.expect_token(&Token::LParen)?;
+            let partition_by = self
+                .parser
+                .parse_comma_separated(Parser::parse_select_item)?;
+            self.parser.expect_token(&Token::RParen)?;
+            partition_by
+        } else {
+            vec![]
+        };
+
         // external location's connection options
         let connection_options = if self.parser.parse_keyword(Keyword::CONNECTION) {
             self.parse_options()?
@@ -1145,6 +1306,7 @@ impl<'a> ExtParser<'a> {
 
         Ok(CopyTarget::IntoLocation(CopyIntoLocation {
             from,
+            partition_by,
             location: UriLocation {
                 path,
                 connection_options,
@@ -1173,7 +1335,7 @@ impl<'a> ExtParser<'a> {
             if self.parse_cnos_keyword(CnosKeyWord::FILE_FORMAT) {
                 // parse file format & format options
                 // FILE_FORMAT = ({
-                //     TYPE = { CSV | JSON | AVRO | ORC | PARQUET | XML }
+                //     TYPE = { CSV | JSON | AVRO | ORC | PARQUET | LINE_PROTOCOL | PROMETHEUS }
                 //     | DELIMITER = '<character>'
                 //     | WITH_HEADER = { true | false }
                 //     | ...
@@ -1273,9 +1435,17 @@ impl<'a> ExtParser<'a> {
             let if_exist = self.parser.parse_keywords(&[Keyword::IF, Keyword::EXISTS]);
             let name = self.parser.parse_identifier()?;
             ExtStatement::DropStream(ast::DropStream { if_exist, name })
+        } else if self.parse_cnos_keyword(CnosKeyWord::PIPE) {
+            let if_exist = self.parser.parse_keywords(&[Keyword::IF, Keyword::EXISTS]);
+            let object_name = self.parser.parse_identifier()?;
+            ExtStatement::DropTenantObject(DropTenantObject {
+                object_name,
+                if_exist,
+                obj_type: TenantObjectType::Pipe,
+            })
         } else {
             return self.expected(
-                "TABLE,DATABASE,TENANT,USER,ROLE,VNODE,STREAM after DROP",
+                "TABLE,DATABASE,TENANT,USER,ROLE,VNODE,STREAM,PIPE after DROP",
                 self.parser.peek_token(),
             );
         };
@@ -1372,17 +1542,21 @@ impl<'a> ExtParser<'a> {
             let name = self.parser.parse_identifier()?;
             let column_type = self.parse_column_type()?;
 
-            let encoding = if self.parser.peek_token().eq(&Token::Comma) {
+            let encoding = if self.parser.peek_token().eq(&Token::Comma)
+                || self.peek_keyword(Keyword::INDEX)
+            {
                 None
             } else {
                 Some(self.parse_codec_type()?)
             };
+            let index = self.parse_field_index_type()?;
 
             field_columns.push(ColumnOption {
                 name,
                 is_tag: false,
                 data_type: column_type,
                 encoding,
+                index,
             });
             let comma = self.consume_token(&Token::Comma);
             if self.consume_token(&Token::RParen) {
@@ -1418,6 +1592,7 @@ impl<'a> ExtParser<'a> {
                 is_tag: true,
                 data_type: DataType::String,
                 encoding: None,
+                index: None,
             });
             let is_comma = self.consume_token(&Token::Comma);
             if self.consume_token(&Token::RParen) {
@@ -1478,6 +1653,26 @@ impl<'a> ExtParser<'a> {
         self.parser.expect_token(&Token::RParen)?;
         Ok(encoding)
     }
+
+    fn peek_keyword(&self, keyword: Keyword) -> bool {
+        matches!(self.parser.peek_token().token, Token::Word(w) if w.keyword == keyword)
+    }
+
+    /// Parse the optional `INDEX(NGRAM | BLOOM)` of a field column.
+    fn parse_field_index_type(&mut self) -> Result<Option<FieldIndexType>> {
+        if !self.parser.parse_keyword(Keyword::INDEX) {
+            return Ok(None);
+        }
+
+        self.parser.expect_token(&Token::LParen)?;
+        let index_type = self.parser.parse_identifier()?;
+        let index_type = match index_type.value.parse() {
+            Ok(index_type) => index_type,
+            Err(str) => return parser_err!(format!("{} is not valid field index type", str)),
+        };
+        self.parser.expect_token(&Token::RParen)?;
+        Ok(Some(index_type))
+    }
 }
 
 /// This is a copy of the equivalent implementation in Datafusion.
@@ -1546,7 +1741,7 @@ mod tests {
     use std::ops::Deref;
 
     use datafusion::sql::sqlparser::ast::{
-        Ident, ObjectName, SetExpr, Statement, TableFactor, Value,
+        Ident, ObjectName, SelectItem, SetExpr, Statement, TableFactor, Value,
     };
     use spi::query::ast::{AlterTable, DropDatabaseObject, ExtStatement, ShowStreams, UriLocation};
     use spi::query::logical_planner::{DatabaseObjectType, TenantObjectType};
@@ -1632,10 +1827,11 @@ mod tests {
     fn test_create_table_statement() {
         let sql = "CREATE TABLE IF NOT EXISTS test\
             (column1 BIGINT CODEC(DELTA),\
-            column2 STRING CODEC(GZIP),\
+            column2 STRING CODEC(GZIP) INDEX(NGRAM),\
             column3 BIGINT UNSIGNED CODEC(NULL),\
             column4 BOOLEAN,\
             column5 DOUBLE CODEC(GORILLA),\
+            column8 STRING INDEX(BLOOM),\
             TAGS(column6, column7))";
         let statements = ExtParser::parse_sql(sql).unwrap();
         assert_eq!(statements.len(), 1);
@@ -1647,7 +1843,7 @@ mod tests {
             }) => {
                 assert_eq!(name.to_string(), "test".to_string());
                 assert_eq!(if_not_exists.to_string(), "true".to_string());
-                assert_eq!(columns.len(), 7);
+                assert_eq!(columns.len(), 8);
                 assert_eq!(
                     *columns,
                     vec![
@@ -1655,43 +1851,57 @@ mod tests {
                             name: Ident::from("column6"),
                             is_tag: true,
                             data_type: DataType::String,
-                            encoding: None
+                            encoding: None,
+                            index: None
                         },
                         ColumnOption {
                             name: Ident::from("column7"),
                             is_tag: true,
                             data_type: DataType::String,
-                            encoding: None
+                            encoding: None,
+                            index: None
                         },
                         ColumnOption {
                             name: Ident::from("column1"),
                             is_tag: false,
                             data_type: DataType::BigInt(None),
-                            encoding: Some(Encoding::Delta)
+                            encoding: Some(Encoding::Delta),
+                            index: None
                         },
                         ColumnOption {
                             name: Ident::from("column2"),
                             is_tag: false,
                             data_type: DataType::String,
-                            encoding: Some(Encoding::Gzip)
+                            encoding: Some(Encoding::Gzip),
+                            index: Some(FieldIndexType::Ngram)
                         },
                         ColumnOption {
                             name: Ident::from("column3"),
                             is_tag: false,
                             data_type: DataType::UnsignedBigInt(None),
-                            encoding: Some(Encoding::Null)
+                            encoding: Some(Encoding::Null),
+                            index: None
                         },
                         ColumnOption {
                             name: Ident::from("column4"),
                             is_tag: false,
                             data_type: DataType::Boolean,
-                            encoding: None
+                            encoding: None,
+                            index: None
                         },
                         ColumnOption {
                             name: Ident::from("column5"),
                             is_tag: false,
                             data_type: DataType::Double,
-                            encoding: Some(Encoding::Gorilla)
+                            encoding: Some(Encoding::Gorilla),
+                            index: None
+                        },
+                        ColumnOption {
+                            name: Ident::from("column8"),
+                            is_tag: false,
+                            data_type: DataType::String,
+                            encoding: None,
+                            index: Some(FieldIndexType::Bloom)
                         }
                     ]
                 );
@@ -1834,7 +2044,7 @@ mod tests {
             ExtStatement::CreateDatabase(ref stmt) => {
                 let ans = format!("{:?}", stmt);
                 println!("{ans}");
-                let expectd = r#"CreateDatabase { name: ObjectName([Ident { value: "test", quote_style: None }]), if_not_exists: false, options: DatabaseOptions { ttl: Some("10d"), shard_num: Some(5), vnode_duration: Some("3d"), replica: Some(10), precision: Some("us") } }"#;
+                let expectd = r#"CreateDatabase { name: ObjectName([Ident { value: "test", quote_style: None }]), if_not_exists: false, options: DatabaseOptions { ttl: Some("10d"), shard_num: Some(5), vnode_duration: Some("3d"), replica: Some(10), precision: Some("us"), compaction: None, compaction_window: None, cold_duration: None } }"#;
                 assert_eq!(ans, expectd);
             }
             _ => panic!("impossible"),
@@ -1875,7 +2085,8 @@ mod tests {
                             name: Ident::from("t"),
                             is_tag: true,
                             data_type: DataType::String,
-                            encoding: None
+                            encoding: None,
+                            index: None
                         }
                     }
                 },
@@ -1886,7 +2097,8 @@ mod tests {
                             name: Ident::from("f"),
                             is_tag: false,
                             data_type: DataType::BigInt(None),
-                            encoding: Some(Encoding::Default)
+                            encoding: Some(Encoding::Default),
+                            index: None
                         }
                     }
                 },
@@ -1994,6 +2206,15 @@ mod tests {
                 replication_set_id: 10
             })
         );
+        let sql6 = "alter node 1001 set io_rate_limit = 104857600;";
+        let statement = ExtParser::parse_sql(sql6).unwrap();
+        assert_eq!(
+            statement[0],
+            ExtStatement::AlterNode(AlterNode {
+                node_id: 1001,
+                io_rate_limit: 104857600,
+            })
+        );
     }
 
     #[test]
@@ -2027,6 +2248,7 @@ mod tests {
                 args: None,
                 with_hints: vec![],
             },
+            partition_by: vec![],
             location: UriLocation {
                 path: "s3://bucket/path".to_string(),
                 connection_options: vec![
@@ -2062,11 +2284,41 @@ mod tests {
             copy into 's3://bucket/path' from mytable COPY_OPTIONS = (ON_ERROR = 'abort') file_format = (type = 'csv');
             copy into 's3://bucket/path' from mytable CONNECTION = (xx='a', sss='ss') file_format = (type = 'csv');
             copy into 's3://bucket/path' from mytable CONNECTION = (xx='a', sss='ss') file_format = (type = 'csv') copy_options = (on_error = 'abort');
+            copy into 's3://bucket/path' from mytable partition by (host) file_format = (type = 'csv');
+            copy into 's3://bucket/path' from mytable partition by (cast(time as date) as date, host) CONNECTION = (xx='a') copy_options = (max_file_size = 1048576);
         "#;
 
         let _ = ExtParser::parse_sql(sql).unwrap();
     }
 
+    #[test]
+    fn test_parse_copy_into_location_partition_by() {
+        let sql = "copy into 's3://bucket/path' from mytable partition by (cast(time as date) as date, host);";
+        let statement = ExtParser::parse_sql(sql)
+            .unwrap()
+            .into_iter()
+            .last()
+            .unwrap();
+
+        match statement {
+            ExtStatement::Copy(ast::Copy {
+                copy_target: CopyTarget::IntoLocation(CopyIntoLocation { partition_by, .. }),
+                ..
+            }) => {
+                assert_eq!(partition_by.len(), 2);
+                assert!(matches!(
+                    &partition_by[0],
+                    SelectItem::ExprWithAlias { alias, .. } if alias.value == "date"
+                ));
+                assert!(matches!(
+                    &partition_by[1],
+                    SelectItem::UnnamedExpr(Expr::Identifier(ident)) if ident.value == "host"
+                ));
+            }
+            _ => panic!("expect copy into location"),
+        }
+    }
+
     #[test]
     fn test_create_stream() {
         let statement = parse_sql("create stream if not exists test_s trigger = once watermark = '10s' output_mode = update as insert into t_tbl select 1;");
@@ -2113,4 +2365,79 @@ mod tests {
 
         assert_eq!(expected, result);
     }
+
+    #[test]
+    fn test_create_pipe() {
+        let statement = parse_sql(
+            "create pipe if not exists test_p interval = '1m' as copy into t_tbl from 'file:///tmp/data/' file_format = (type = 'csv');",
+        );
+
+        match statement {
+            ExtStatement::CreatePipe(ast::CreatePipe {
+                if_not_exists,
+                name,
+                interval,
+                copy,
+            }) => {
+                assert!(if_not_exists);
+                assert_eq!(name, Ident::new("test_p"));
+                assert_eq!(interval.as_deref(), Some("1m"));
+                assert!(matches!(copy.copy_target, CopyTarget::IntoTable(_)));
+                assert_eq!(copy.file_format_options.len(), 1);
+            }
+            _ => panic!("expect CreatePipe"),
+        }
+
+        assert!(ExtParser::parse_sql(
+            "create pipe test_p as copy into 'file:///tmp/data/' from t_tbl;"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_drop_and_show_pipes() {
+        let result = parse_sql("drop pipe if exists test_p;");
+        let expected = ExtStatement::DropTenantObject(DropTenantObject {
+            object_name: Ident::new("test_p"),
+            if_exist: true,
+            obj_type: TenantObjectType::Pipe,
+        });
+        assert_eq!(expected, result);
+
+        let result = parse_sql("show pipes;");
+        assert_eq!(ExtStatement::ShowPipes, result);
+    }
+
+    #[test]
+    fn test_session_variable() {
+        let result = parse_sql("set session time_zone = '+08:00';");
+        let expected = ExtStatement::SetVariable(ast::SetVariable {
+            variable: Ident::new("time_zone"),
+            value: "+08:00".to_string(),
+        });
+        assert_eq!(expected, result);
+
+        let result = parse_sql("SET batch_size TO 1024");
+        let expected = ExtStatement::SetVariable(ast::SetVariable {
+            variable: Ident::new("batch_size"),
+            value: "1024".to_string(),
+        });
+        assert_eq!(expected, result);
+
+        let result = parse_sql("set priority = default");
+        let expected = ExtStatement::ResetVariable(Some(Ident::new("priority")));
+        assert_eq!(expected, result);
+
+        let result = parse_sql("show time_zone");
+        let expected = ExtStatement::ShowVariable(Some(Ident::new("time_zone")));
+        assert_eq!(expected, result);
+
+        let result = parse_sql("show all");
+        assert_eq!(ExtStatement::ShowVariable(None), result);
+
+        assert!(ExtParser::parse_sql("show not_a_variable").is_err());
+
+        let result = parse_sql("reset all");
+        assert_eq!(ExtStatement::ResetVariable(None), result);
+    }
 }
@@ -21,6 +21,7 @@ use spi::query::session::SessionCtx;
 use spi::Result;
 
 use super::optimizer::PhysicalOptimizer;
+use crate::extension::physical::optimizer_rule::spillable_aggregate::SpillableAggregation;
 use crate::extension::physical::transform_rule::expand::ExpandPlanner;
 use crate::extension::physical::transform_rule::table_writer::TableWriterPlanner;
 use crate::extension::physical::transform_rule::tag_scan::TagScanPlanner;
@@ -98,6 +99,10 @@ impl Default for DefaultPhysicalPlanner {
             // Note that one should always run this rule after running the EnforceDistribution rule
             // as the latter may break local sorting requirements.
             Arc::new(EnforceSorting::new()),
+            // The SpillableAggregation rule replaces the aggregations of `GROUP BY` by the ones
+            // spilling to disk, they require the same distribution as the replaced aggregations.
+            // It is skipped unless the session enables `spill_aggregation`.
+            Arc::new(SpillableAggregation {}),
             // The CoalesceBatches rule will not influence the distribution and ordering of the
             // whole plan tree. Therefore, to avoid influencing other rules, it should run last.
             Arc::new(CoalesceBatches::new()),
@@ -122,11 +127,16 @@ impl PhysicalPlanner for DefaultPhysicalPlanner {
         logical_plan: &LogicalPlan,
         session: &SessionCtx,
     ) -> Result<Arc<dyn ExecutionPlan>> {
+        // The spilling aggregations are slower than the hash aggregations of datafusion,
+        // they are only used by the sessions enabling `spill_aggregation`
+        let rules = self
+            .ext_physical_optimizer_rules
+            .iter()
+            .filter(|rule| session.spill_aggregation() || rule.name() != SpillableAggregation::NAME)
+            .cloned()
+            .collect();
         // 将扩展的物理计划优化规则注入df 的 session state
-        let new_state = session
-            .inner()
-            .state()
-            .with_physical_optimizer_rules(self.ext_physical_optimizer_rules.clone());
+        let new_state = session.inner().state().with_physical_optimizer_rules(rules);
 
         // 通过扩展的物理计划转换规则构造df 的 Physical Planner
         let planner = DFDefaultPhysicalPlanner::with_extension_planners(
@@ -2,10 +2,11 @@ use std::collections::{HashMap, HashSet};
 use std::iter;
 use std::option::Option;
 use std::sync::Arc;
+use std::time;
 
 use async_recursion::async_recursion;
 use async_trait::async_trait;
-use datafusion::arrow::datatypes::{DataType, SchemaRef};
+use datafusion::arrow::datatypes::{DataType, Field, Schema, SchemaRef};
 use datafusion::arrow::error::ArrowError;
 use datafusion::common::parsers::CompressionTypeVariant;
 use datafusion::common::{
@@ -29,8 +30,8 @@ use datafusion::logical_expr::logical_plan::Analyze;
 use datafusion::logical_expr::utils::expr_to_columns;
 use datafusion::logical_expr::{
     lit, BinaryExpr, BuiltinScalarFunction, Case, CreateExternalTable as PlanCreateExternalTable,
-    EmptyRelation, Explain, Expr, LogicalPlan, LogicalPlanBuilder, Operator, PlanType,
-    SubqueryAlias, TableSource, ToStringifiedPlan, Union,
+    EmptyRelation, Explain, Expr, ExprSchemable, LogicalPlan, LogicalPlanBuilder, Operator,
+    PlanType, SubqueryAlias, TableSource, ToStringifiedPlan, Union,
 };
 use datafusion::prelude::{col, SessionConfig};
 use datafusion::scalar::ScalarValue;
@@ -38,10 +39,11 @@ use datafusion::sql::parser::CreateExternalTable as AstCreateExternalTable;
 use datafusion::sql::planner::{object_name_to_table_reference, SqlToRel};
 use datafusion::sql::sqlparser::ast::{
     DataType as SQLDataType, Expr as ASTExpr, Ident, ObjectName, Offset, OrderByExpr, Query,
-    SqlOption, Statement, TableAlias, TableFactor, TimezoneInfo,
+    SelectItem, SetExpr, SqlOption, Statement, TableAlias, TableFactor, TimezoneInfo, Value,
 };
 use datafusion::sql::sqlparser::parser::ParserError;
 use datafusion::sql::TableReference;
+use futures::TryStreamExt;
 use lazy_static::__Deref;
 use meta::error::MetaError;
 use models::auth::privilege::{
@@ -52,15 +54,16 @@ use models::auth::user::User;
 use models::object_reference::{Resolve, ResolvedTable};
 use models::oid::{Identifier, Oid};
 use models::schema::{
-    ColumnType, DatabaseOptions, Duration, Precision, TableColumn, TableSourceAdapter,
-    TskvTableSchema, TskvTableSchemaRef,
+    ColumnType, CompactionStrategy, DatabaseOptions, Duration, Precision, TableColumn,
+    TableSourceAdapter, TskvTableSchema, TskvTableSchemaRef,
 };
 use models::utils::SeqIdGenerator;
 use models::{ColumnId, ValueType};
+use object_store::path::Path;
 use object_store::ObjectStore;
 use spi::query::ast;
 use spi::query::ast::{
-    AlterDatabase as ASTAlterDatabase, AlterTable as ASTAlterTable,
+    AlterDatabase as ASTAlterDatabase, AlterNode as ASTAlterNode, AlterTable as ASTAlterTable,
     AlterTableAction as ASTAlterTableAction, AlterTenantOperation, AlterUserOperation,
     ChecksumGroup as ASTChecksumGroup, ColumnOption, CompactVnode as ASTCompactVnode,
     CopyIntoTable, CopyTarget, CopyVnode as ASTCopyVnode, CreateDatabase as ASTCreateDatabase,
@@ -72,23 +75,34 @@ use spi::query::ast::{
 use spi::query::datasource::{self, UriSchema};
 use spi::query::logical_planner::{
     parse_connection_options, sql_options_to_tenant_options, sql_options_to_user_options,
-    AlterDatabase, AlterTable, AlterTableAction, AlterTenant, AlterTenantAction,
+    AlterDatabase, AlterNode, AlterTable, AlterTableAction, AlterTenant, AlterTenantAction,
     AlterTenantAddUser, AlterTenantSetUser, AlterUser, AlterUserAction, ChecksumGroup,
-    CompactVnode, CopyOptions, CopyOptionsBuilder, CopyVnode, CreateDatabase, CreateRole,
-    CreateTable, CreateTenant, CreateUser, DDLPlan, DatabaseObjectType, DescribeDatabase,
-    DescribeTable, DropDatabaseObject, DropGlobalObject, DropTenantObject, DropVnode,
-    FileFormatOptions, FileFormatOptionsBuilder, GlobalObjectType, GrantRevoke, LogicalPlanner,
-    MoveVnode, Plan, PlanWithPrivileges, QueryPlan, SYSPlan, TenantObjectType,
+    CompactVnode, CopyFile, CopyFileSource, CopyIntoTablePlan, CopyOptions, CopyOptionsBuilder,
+    CopyVnode, CreateDatabase, CreatePipe, CreateRole, CreateTable, CreateTenant, CreateUser,
+    DDLPlan, DatabaseObjectType, DescribeDatabase, DescribeTable, DropDatabaseObject,
+    DropGlobalObject, DropTenantObject, DropVnode, ExtFileType, FileFormatOptions,
+    FileFormatOptionsBuilder, GlobalObjectType, GrantRevoke, LogicalPlanner, MoveVnode, Plan,
+    PlanWithPrivileges, PointsFormat, QueryPlan, SYSPlan, TenantObjectType,
 };
 use spi::query::session::SessionCtx;
 use spi::{QueryError, Result};
 use trace::{debug, warn};
 use url::Url;
 
+use crate::data_source::file_format::orc::{OrcFormat, DEFAULT_ORC_EXTENSION};
+use crate::data_source::table_provider::external_location::ExternalLocationTable;
+use crate::data_source::table_provider::parameters::ParameterTable;
 use crate::data_source::table_provider::tskv::ClusterTable;
+use crate::execution::copy::REJECTED_FILE_SUFFIX;
+use crate::extension::logical::optimizer_rule::transform_time_window::parse_duration;
 use crate::metadata::{ContextProviderExtension, DatabaseSet, CLUSTER_SCHEMA, INFORMATION_SCHEMA};
 use crate::sql::logical::planner::TableWriteExt;
 use crate::sql::parser::{merge_object_name, normalize_ident, normalize_sql_object_name};
+use crate::sql::prepared_statement::{parameter_index, parameter_name, PARAMETERS_TABLE};
+
+/// Interval between two loads of a pipe if it's not specified
+const DEFAULT_PIPE_INTERVAL: time::Duration = time::Duration::from_secs(60);
+const MIN_PIPE_INTERVAL: time::Duration = time::Duration::from_secs(1);
 
 /// CnosDB SQL query planner
 pub struct SqlPlaner<'a, S: ContextProviderExtension> {
@@ -120,6 +134,7 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlaner<'a, S> {
             ExtStatement::CreateTenant(stmt) => self.create_tenant_to_plan(stmt),
             ExtStatement::CreateUser(stmt) => self.create_user_to_plan(stmt),
             ExtStatement::CreateRole(stmt) => self.create_role_to_plan(stmt, session),
+            ExtStatement::CreatePipe(stmt) => self.create_pipe_to_plan(stmt, session).await,
             ExtStatement::DropDatabaseObject(s) => self.drop_database_object_to_plan(s, session),
             ExtStatement::DropTenantObject(s) => self.drop_tenant_object_to_plan(s, session),
             ExtStatement::DropGlobalObject(s) => self.drop_global_object_to_plan(s),
@@ -127,6 +142,11 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlaner<'a, S> {
             ExtStatement::DescribeDatabase(stmt) => self.database_to_describe(stmt, session),
             ExtStatement::ShowDatabases() => self.database_to_show(session),
             ExtStatement::ShowTables(stmt) => self.table_to_show(stmt, session),
+            ExtStatement::ShowPipes => Ok(PlanWithPrivileges {
+                plan: Plan::DDL(DDLPlan::ShowPipes),
+                // pipes of other members are invisible to non-owner members
+                privileges: vec![],
+            }),
             ExtStatement::AlterDatabase(stmt) => self.database_to_alter(stmt, session),
             ExtStatement::ShowSeries(stmt) => self.show_series_to_plan(*stmt, session),
             ExtStatement::Explain(stmt) => {
@@ -152,6 +172,27 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlaner<'a, S> {
                     privileges: vec![],
                 })
             }
+            ExtStatement::SetVariable(stmt) => {
+                let variable = normalize_ident(&stmt.variable).parse()?;
+                Ok(PlanWithPrivileges {
+                    plan: Plan::SYSTEM(SYSPlan::SetVariable(variable, stmt.value)),
+                    privileges: vec![],
+                })
+            }
+            ExtStatement::ShowVariable(variable) => {
+                let variable = variable.map(|e| normalize_ident(&e).parse()).transpose()?;
+                Ok(PlanWithPrivileges {
+                    plan: Plan::SYSTEM(SYSPlan::ShowVariable(variable)),
+                    privileges: vec![],
+                })
+            }
+            ExtStatement::ResetVariable(variable) => {
+                let variable = variable.map(|e| normalize_ident(&e).parse()).transpose()?;
+                Ok(PlanWithPrivileges {
+                    plan: Plan::SYSTEM(SYSPlan::ResetVariable(variable)),
+                    privileges: vec![],
+                })
+            }
             ExtStatement::Copy(stmt) => self.copy_to_plan(stmt, session).await,
             // vnode statement
             ExtStatement::DropVnode(stmt) => self.drop_vnode_to_plan(stmt),
@@ -159,6 +200,8 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlaner<'a, S> {
             ExtStatement::MoveVnode(stmt) => self.move_vnode_to_plan(stmt),
             ExtStatement::CompactVnode(stmt) => self.compact_vnode_to_plan(stmt),
             ExtStatement::ChecksumGroup(stmt) => self.checksum_group_to_plan(stmt),
+            // node statement
+            ExtStatement::AlterNode(stmt) => self.alter_node_to_plan(stmt),
             ExtStatement::CreateStream(_) => Err(QueryError::NotImplemented {
                 err: "CreateStream Planner.".to_string(),
             }),
@@ -179,7 +222,10 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlaner<'a, S> {
         match stmt {
             Statement::Query(_) => {
                 let df_plan = self.df_planner.sql_statement_to_plan(stmt)?;
-                let plan = Plan::Query(QueryPlan { df_plan });
+                let plan = Plan::Query(QueryPlan {
+                    df_plan,
+                    optimized: false,
+                });
 
                 // privileges
                 let access_databases = self.schema_provider.reset_access_databases();
@@ -253,7 +299,10 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlaner<'a, S> {
             })
         };
 
-        let plan = Plan::Query(QueryPlan { df_plan });
+        let plan = Plan::Query(QueryPlan {
+            df_plan,
+            optimized: false,
+        });
 
         Ok(PlanWithPrivileges { plan, privileges })
     }
@@ -265,10 +314,18 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlaner<'a, S> {
         source: Box<Query>,
         session: &SessionCtx,
     ) -> Result<PlanWithPrivileges> {
+        // The values of `INSERT ... VALUES ($1, $2, ...)` are the parameter rows of a prepared
+        // statement, they are planned after the target table is known
+        let parameters = values_parameters(&source);
+
         // Transform subqueries
-        let source_plan = self
-            .df_planner
-            .sql_statement_to_plan(Statement::Query(source))?;
+        let source_plan = match parameters {
+            Some(_) => None,
+            None => Some(
+                self.df_planner
+                    .sql_statement_to_plan(Statement::Query(source))?,
+            ),
+        };
 
         // save database read privileges
         // This operation must be done before fetching the target table metadata
@@ -287,6 +344,15 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlaner<'a, S> {
         // Get the metadata of the target table
         let target_table = self.get_table_source(&table_name)?;
 
+        let source_plan = match (source_plan, parameters) {
+            (Some(source_plan), _) => source_plan,
+            (None, parameters) => parameters_to_plan(
+                &parameters.unwrap_or_default(),
+                target_table.clone(),
+                &columns,
+            )?,
+        };
+
         let build_plan_func = || {
             LogicalPlanBuilder::from(source_plan)
                 .write(target_table, table_name.table(), columns.as_ref())?
@@ -297,7 +363,10 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlaner<'a, S> {
 
         debug!("Insert plan:\n{}", df_plan.display_indent_schema());
 
-        let plan = Plan::Query(QueryPlan { df_plan });
+        let plan = Plan::Query(QueryPlan {
+            df_plan,
+            optimized: false,
+        });
 
         // privileges
         let mut write_privileges = databases_privileges(
@@ -376,13 +445,13 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlaner<'a, S> {
                         if_exist,
                         obj_type: TenantObjectType::Database,
                     }),
-                    Privilege::TenantObject(
+                    Some(Privilege::TenantObject(
                         TenantObjectPrivilege::Database(
                             DatabasePrivilege::Full,
                             Some(database_name),
                         ),
                         Some(tenant_id),
-                    ),
+                    )),
                 )
             }
             TenantObjectType::Role => {
@@ -394,14 +463,31 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlaner<'a, S> {
                         if_exist,
                         obj_type: TenantObjectType::Role,
                     }),
-                    Privilege::TenantObject(TenantObjectPrivilege::RoleFull, Some(tenant_id)),
+                    Some(Privilege::TenantObject(
+                        TenantObjectPrivilege::RoleFull,
+                        Some(tenant_id),
+                    )),
+                )
+            }
+            TenantObjectType::Pipe => {
+                // A pipe can be dropped by its owner or the owner of the tenant,
+                // which is checked when the pipe is dropped.
+                let pipe_name = normalize_ident(object_name);
+                (
+                    DDLPlan::DropTenantObject(DropTenantObject {
+                        tenant_name: tenant_name.to_string(),
+                        name: pipe_name,
+                        if_exist,
+                        obj_type: TenantObjectType::Pipe,
+                    }),
+                    None,
                 )
             }
         };
 
         Ok(PlanWithPrivileges {
             plan: Plan::DDL(plan),
-            privileges: vec![privilege],
+            privileges: privilege.into_iter().collect(),
         })
     }
 
@@ -578,18 +664,21 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlaner<'a, S> {
 
     fn column_opt_to_table_column(column_opt: ColumnOption, id: ColumnId) -> Result<TableColumn> {
         Self::check_column_encoding(&column_opt)?;
+        Self::check_column_index(&column_opt)?;
 
         let col = if column_opt.is_tag {
             TableColumn::new_tag_column(id, normalize_ident(&column_opt.name))
         } else {
             let name = normalize_ident(&column_opt.name);
             let column_type = Self::make_data_type(&name, &column_opt.data_type)?;
-            TableColumn::new(
+            let mut column = TableColumn::new(
                 id,
                 name,
                 column_type,
                 column_opt.encoding.unwrap_or_default(),
-            )
+            );
+            column.index = column_opt.index;
+            column
         };
         Ok(col)
     }
@@ -835,7 +924,10 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlaner<'a, S> {
         let db_name = &table_schema.db;
 
         Ok(PlanWithPrivileges {
-            plan: Plan::Query(QueryPlan { df_plan }),
+            plan: Plan::Query(QueryPlan {
+                df_plan,
+                optimized: false,
+            }),
             privileges: vec![Privilege::TenantObject(
                 TenantObjectPrivilege::Database(DatabasePrivilege::Read, Some(db_name.to_string())),
                 Some(*session.tenant_id()),
@@ -1035,6 +1127,22 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlaner<'a, S> {
                 )),
             })?);
         }
+        if let Some(compaction) = options.compaction {
+            plan_options.with_compaction(CompactionStrategy::new(&compaction).ok_or(
+                QueryError::Parser {
+                    source: ParserError::ParserError(format!(
+                        "{} is not a valid compaction strategy, use 'level' or 'time_window'",
+                        compaction
+                    )),
+                },
+            )?);
+        }
+        if let Some(compaction_window) = options.compaction_window {
+            plan_options.with_compaction_window(self.str_to_duration(&compaction_window)?);
+        }
+        if let Some(cold_duration) = options.cold_duration {
+            plan_options.with_cold_duration(self.str_to_duration(&cold_duration)?);
+        }
         Ok(plan_options)
     }
 
@@ -1088,6 +1196,18 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlaner<'a, S> {
         Ok(())
     }
 
+    fn check_column_index(column: &ColumnOption) -> Result<()> {
+        match column.index {
+            Some(index_type) if column.is_tag || column.data_type != SQLDataType::String => {
+                Err(QueryError::FieldIndexType {
+                    index_type,
+                    data_type: column.data_type.to_string(),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
     fn get_table_source(&self, table_name: &ResolvedTable) -> Result<Arc<dyn TableSource>> {
         let table_ref = table_name.into();
         Ok(self.schema_provider.get_table_source(table_ref)?.inner())
@@ -1140,6 +1260,65 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlaner<'a, S> {
         Ok(PlanWithPrivileges { plan, privileges })
     }
 
+    /// The COPY INTO statement of the pipe is planned when the pipe is created,
+    /// so that the target table, the location and the privileges are checked
+    async fn create_pipe_to_plan(
+        &self,
+        stmt: ast::CreatePipe,
+        session: &SessionCtx,
+    ) -> Result<PlanWithPrivileges> {
+        let ast::CreatePipe {
+            if_not_exists,
+            ref name,
+            interval,
+            copy,
+        } = stmt;
+        let name = normalize_ident(name);
+
+        let interval = match interval {
+            Some(interval) => parse_duration(&interval).map_err(|err| QueryError::Semantic {
+                err: format!("Invalid interval '{}' of pipe {}: {}", interval, name, err),
+            })?,
+            None => DEFAULT_PIPE_INTERVAL,
+        };
+        if interval < MIN_PIPE_INTERVAL {
+            return Err(QueryError::Semantic {
+                err: format!(
+                    "Interval of pipe {} must be at least {:?}",
+                    name, MIN_PIPE_INTERVAL
+                ),
+            });
+        }
+
+        let PlanWithPrivileges { plan, privileges } = self.copy_to_plan(copy, session).await?;
+        let table = match plan {
+            Plan::CopyIntoTable(plan) if !plan.copy_options.force => {
+                format!("{}.{}", plan.database, plan.table_name)
+            }
+            Plan::CopyIntoTable(_) => {
+                return Err(QueryError::Semantic {
+                    err: "Pipe loads new files only, FORCE is not supported".to_string(),
+                })
+            }
+            _ => {
+                return Err(QueryError::Semantic {
+                    err: "Pipe only supports COPY INTO table".to_string(),
+                })
+            }
+        };
+
+        let plan = Plan::DDL(DDLPlan::CreatePipe(CreatePipe {
+            tenant_name: session.tenant().to_string(),
+            name,
+            if_not_exists,
+            database: session.default_database().to_string(),
+            table,
+            interval,
+        }));
+
+        Ok(PlanWithPrivileges { plan, privileges })
+    }
+
     fn create_role_to_plan(
         &self,
         stmt: ast::CreateRole,
@@ -1433,6 +1612,22 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlaner<'a, S> {
         })
     }
 
+    fn alter_node_to_plan(&self, stmt: ASTAlterNode) -> Result<PlanWithPrivileges> {
+        let ASTAlterNode {
+            node_id,
+            io_rate_limit,
+        } = stmt;
+
+        let plan = Plan::DDL(DDLPlan::AlterNode(AlterNode {
+            node_id,
+            io_rate_limit,
+        }));
+        Ok(PlanWithPrivileges {
+            plan,
+            privileges: vec![Privilege::Global(GlobalPrivilege::System)],
+        })
+    }
+
     fn get_tskv_schema(&self, table_name: &ResolvedTable) -> Result<TskvTableSchemaRef> {
         Ok(self
             .get_table_provider(table_name)?
@@ -1467,34 +1662,17 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlaner<'a, S> {
 
         match copy_target {
             CopyTarget::IntoTable(stmt) => {
-                // .   TableWriter
-                //         ListingTable
-                let (external_location_table, target_table, insert_columns) = self
-                    .build_source_and_target_table(session, stmt, file_format_options, copy_options)
-                    .await?;
-
-                let plan = build_copy_into_table_plan(
-                    external_location_table,
-                    &target_table,
-                    insert_columns.as_ref(),
-                )?;
-
-                Ok(PlanWithPrivileges {
-                    plan,
-                    privileges: vec![Privilege::TenantObject(
-                        TenantObjectPrivilege::Database(
-                            DatabasePrivilege::Write,
-                            Some(target_table.database_name().into()),
-                        ),
-                        Some(tenant_id),
-                    )],
-                })
+                // .   CopyIntoTable
+                //         TableWriter (per file)
+                //             ListingTable
+                self.copy_into_table(session, stmt, file_format_options, copy_options)
+                    .await
             }
             CopyTarget::IntoLocation(stmt) => {
                 // .   TableWriterPlanNode
                 //         Plan.....
                 let plan = self
-                    .copy_into_location(session, stmt, file_format_options)
+                    .copy_into_location(session, stmt, file_format_options, copy_options)
                     .await?;
 
                 let database_set = self.schema_provider.reset_access_databases();
@@ -1505,16 +1683,16 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlaner<'a, S> {
         }
     }
 
-    /// Construct an external file as an external table
+    /// Construct every external file as an external table
     ///
     /// Get target table‘s metadata and insert columns
-    async fn build_source_and_target_table(
+    async fn copy_into_table(
         &self,
         session: &SessionCtx,
         stmt: CopyIntoTable,
         file_format_options: FileFormatOptions,
         copy_options: CopyOptions,
-    ) -> Result<(Arc<dyn TableSource>, TableSourceAdapter, Vec<String>)> {
+    ) -> Result<PlanWithPrivileges> {
         let CopyIntoTable {
             location,
             ref table_name,
@@ -1526,8 +1704,6 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlaner<'a, S> {
             connection_options,
         } = location;
 
-        let CopyOptions { auto_infer_schema } = copy_options;
-
         let table_path = ListingTableUrl::parse(path)?;
         let insert_columns = columns.iter().map(normalize_ident).collect::<Vec<_>>();
 
@@ -1538,44 +1714,153 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlaner<'a, S> {
             session.inner().runtime_env(),
         )?;
 
-        // 2. Get the metadata of the target table
+        // 2. Get the metadata of the target table and the table rejected rows are written to
         let table_name = normalize_sql_object_name(table_name);
         let target_table_source = self
             .schema_provider
             .get_table_source(TableReference::from(table_name.as_str()))?;
-
-        // 3. According to the external path, construct the external table
-        let default_schema = if auto_infer_schema {
-            None
-        } else {
-            let schema = target_table_source.inner().schema();
-            if insert_columns.is_empty() {
-                // Use the schema of the insert table directly
-                Some(schema)
-            } else {
-                // projection with specific columns
-                // e.g. COPY INTO inner_csv_v2(time, tag1, tag2, bigint_c, string_c, ubigint_c, boolean_c, double_c)
-                let indices = insert_columns
-                    .iter()
-                    .map(|e| schema.index_of(e))
-                    .collect::<std::result::Result<Vec<usize>, ArrowError>>()?;
-                Some(Arc::new(schema.project(&indices)?))
+        let rejected_rows_table_source = copy_options
+            .rejected_rows_table
+            .as_ref()
+            .map(|name| {
+                self.schema_provider
+                    .get_table_source(TableReference::from(name.as_str()))
+            })
+            .transpose()?;
+
+        // 3. Get the schema of the external files
+        let (file_extension, file_format) = match file_format_options.points_format {
+            // Points are written the way the write api does, tags and fields are created if not exist
+            Some(_) if !insert_columns.is_empty() => {
+                return Err(QueryError::Semantic {
+                    err: "Can't specify the columns when loading points".to_string(),
+                })
+            }
+            Some(PointsFormat::Prometheus) if file_format_options.precision.is_some() => {
+                return Err(QueryError::Semantic {
+                    err: "The timestamps of prometheus exposition are always in milliseconds"
+                        .to_string(),
+                })
+            }
+            Some(_) => (String::new(), None),
+            None => {
+                let (file_extension, file_format) =
+                    build_file_extension_and_format(file_format_options.clone())?;
+                (file_extension, Some(file_format))
             }
         };
-        let external_location_table_source = build_external_location_table_source(
-            &session.inner().state(),
-            table_path,
-            default_schema,
-            file_format_options,
-            session.inner().copied_config(),
-        )
-        .await?;
+        let source_schema =
+            if let (true, Some(file_format)) = (copy_options.auto_infer_schema, file_format) {
+                debug!("Not has default schema, infer schema, path: {}", table_path);
+                ListingOptions::new(file_format)
+                    .with_file_extension(file_extension.clone())
+                    .infer_schema(&session.inner().state(), &table_path)
+                    .await?
+            } else {
+                let schema = target_table_source.inner().schema();
+                if insert_columns.is_empty() {
+                    // Use the schema of the insert table directly
+                    schema
+                } else {
+                    // projection with specific columns
+                    // e.g. COPY INTO inner_csv_v2(time, tag1, tag2, bigint_c, string_c, ubigint_c, boolean_c, double_c)
+                    let indices = insert_columns
+                        .iter()
+                        .map(|e| schema.index_of(e))
+                        .collect::<std::result::Result<Vec<usize>, ArrowError>>()?;
+                    Arc::new(schema.project(&indices)?)
+                }
+            };
+
+        // 4. According to the external files, construct the external tables
+        let object_store = session.inner().runtime_env().object_store(&table_path)?;
+        let mut objects = table_path
+            .list_all_files(object_store.as_ref(), &file_extension)
+            .try_collect::<Vec<_>>()
+            .await?;
+        if file_format_options.points_format.is_some() {
+            // Files of points have no extension, skip the rejected rows written next to them
+            objects.retain(|e| !e.location.as_ref().ends_with(REJECTED_FILE_SUFFIX));
+        }
+        objects.sort_by(|a, b| a.location.cmp(&b.location));
+
+        let url: &Url = table_path.as_ref();
+        let prefix = Path::from_url_path(url.path()).map_err(object_store::Error::from)?;
+        let mut files = Vec::with_capacity(objects.len());
+        for object in objects {
+            let url = format!("{}{}", table_path.object_store().as_str(), object.location);
+            let name = object
+                .location
+                .prefix_match(&prefix)
+                .map(|parts| parts.map(|e| e.as_ref().to_string()).collect::<Vec<_>>())
+                .unwrap_or_default()
+                .join("/");
+            let name = if name.is_empty() {
+                object.location.filename().unwrap_or_default().to_string()
+            } else {
+                name
+            };
+            let source = match file_format_options.points_format {
+                Some(points_format) => CopyFileSource::Points(points_format),
+                None => {
+                    let source = build_external_location_table_source(
+                        &session.inner().state(),
+                        ListingTableUrl::parse(&url)?,
+                        Some(source_schema.clone()),
+                        file_format_options.clone(),
+                        session.inner().copied_config(),
+                    )
+                    .await?;
+                    let write_plan = build_copy_into_table_plan(
+                        source.clone(),
+                        &target_table_source,
+                        &insert_columns,
+                    )?;
+                    CopyFileSource::Table { source, write_plan }
+                }
+            };
+            files.push(CopyFile {
+                url,
+                name,
+                location: object.location,
+                size: object.size as u64,
+                last_modified: object.last_modified.timestamp_nanos(),
+                source,
+            });
+        }
+
+        let tenant_id = *session.tenant_id();
+        let mut privileges = vec![Privilege::TenantObject(
+            TenantObjectPrivilege::Database(
+                DatabasePrivilege::Write,
+                Some(target_table_source.database_name().into()),
+            ),
+            Some(tenant_id),
+        )];
+        if let Some(table) = &rejected_rows_table_source {
+            privileges.push(Privilege::TenantObject(
+                TenantObjectPrivilege::Database(
+                    DatabasePrivilege::Write,
+                    Some(table.database_name().into()),
+                ),
+                Some(tenant_id),
+            ));
+        }
 
-        Ok((
-            external_location_table_source,
-            target_table_source,
+        let plan = Plan::CopyIntoTable(CopyIntoTablePlan {
+            files,
+            source_schema,
+            target_table: target_table_source.inner(),
+            database: target_table_source.database_name().to_string(),
+            table_name: target_table_source.table_name().to_string(),
             insert_columns,
-        ))
+            file_format_options,
+            copy_options,
+            rejected_rows_table: rejected_rows_table_source
+                .map(|table| (table.inner(), table.table_name().to_string())),
+        });
+
+        Ok(PlanWithPrivileges { plan, privileges })
     }
 
     async fn copy_into_location(
@@ -1583,13 +1868,24 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlaner<'a, S> {
         session: &SessionCtx,
         stmt: ast::CopyIntoLocation,
         file_format_options: FileFormatOptions,
+        copy_options: CopyOptions,
     ) -> Result<Plan> {
-        let ast::CopyIntoLocation { from, location } = stmt;
+        let ast::CopyIntoLocation {
+            from,
+            partition_by,
+            location,
+        } = stmt;
         let UriLocation {
             path,
             connection_options,
         } = location;
 
+        if file_format_options.points_format.is_some() {
+            return Err(QueryError::Semantic {
+                err: "Points can only be loaded by COPY INTO table".to_string(),
+            });
+        }
+
         let table_path = ListingTableUrl::parse(path)?;
 
         // 1. Build and register object store
@@ -1599,26 +1895,92 @@ impl<'a, S: ContextProviderExtension + Send + Sync + 'a> SqlPlaner<'a, S> {
             session.inner().runtime_env(),
         )?;
 
-        // 2. build source plan
+        let max_file_size = match copy_options.max_file_size {
+            Some(0) => {
+                return Err(QueryError::Semantic {
+                    err: "MAX_FILE_SIZE must be greater than 0".to_string(),
+                })
+            }
+            size => size.map(|e| e as usize),
+        };
+
+        // 2. build source plan, partition columns are placed after the other columns
         let source_plan = self.create_relation(from, &mut Default::default())?;
+        let (source_plan, partition_cols) =
+            self.build_partition_columns(source_plan, partition_by)?;
         let source_schem = SchemaRef::new(source_plan.schema().deref().into());
 
         // 3. According to the external path, construct the external table
-        let target_table = build_external_location_table_source(
-            &session.inner().state(),
+        let target_table = build_export_location_table_source(
             table_path,
-            Some(source_schem),
+            source_schem,
+            partition_cols,
             file_format_options,
+            max_file_size,
             session.inner().copied_config(),
-        )
-        .await?;
+        )?;
 
         // 4. build final plan
         let df_plan = LogicalPlanBuilder::from(source_plan)
             .write(target_table, "external_location_table", Default::default())?
             .build()?;
 
-        Ok(Plan::Query(QueryPlan { df_plan }))
+        Ok(Plan::Query(QueryPlan {
+            df_plan,
+            optimized: false,
+        }))
+    }
+
+    /// Project the partition columns after the other columns,
+    /// partition columns are written as directories instead of into files
+    fn build_partition_columns(
+        &self,
+        source_plan: LogicalPlan,
+        partition_by: Vec<SelectItem>,
+    ) -> Result<(LogicalPlan, Vec<(String, DataType)>)> {
+        if partition_by.is_empty() {
+            return Ok((source_plan, vec![]));
+        }
+
+        let schema = source_plan.schema().clone();
+        let mut partition_exprs = Vec::with_capacity(partition_by.len());
+        for item in partition_by {
+            let (expr, name) = match item {
+                SelectItem::UnnamedExpr(ASTExpr::Identifier(ident)) => {
+                    let name = normalize_ident(&ident);
+                    (ASTExpr::Identifier(ident), name)
+                }
+                SelectItem::ExprWithAlias { expr, alias } => (expr, normalize_ident(&alias)),
+                item => {
+                    return Err(QueryError::Semantic {
+                        err: format!(
+                            "Partition expression {} must be a column or have an alias",
+                            item
+                        ),
+                    })
+                }
+            };
+            let expr = self
+                .df_planner
+                .sql_to_expr(expr, &schema, &mut Default::default())?;
+            partition_exprs.push(expr.alias(&name));
+        }
+
+        let partition_cols = partition_exprs
+            .iter()
+            .map(|e| Ok((e.display_name()?, e.get_type(&schema)?)))
+            .collect::<DFResult<Vec<_>>>()?;
+        let data_exprs = schema
+            .fields()
+            .iter()
+            .filter(|f| partition_cols.iter().all(|(name, _)| name != f.name()))
+            .map(|f| Expr::Column(f.qualified_column()));
+
+        let plan = LogicalPlanBuilder::from(source_plan)
+            .project(data_exprs.chain(partition_exprs))?
+            .build()?;
+
+        Ok((plan, partition_cols))
     }
 
     fn create_relation(
@@ -1705,7 +2067,7 @@ fn build_copy_into_table_plan(
     external_location_table: Arc<dyn TableSource>,
     target_table: &TableSourceAdapter,
     insert_columns: &[String],
-) -> DFResult<Plan> {
+) -> DFResult<LogicalPlan> {
     let df_plan =
         LogicalPlanBuilder::scan("external_location_table", external_location_table, None)?
             .write(
@@ -1717,7 +2079,7 @@ fn build_copy_into_table_plan(
 
     debug!("Copy into table plan:\n{}", df_plan.display_indent_schema());
 
-    Ok(Plan::Query(QueryPlan { df_plan }))
+    Ok(df_plan)
 }
 
 fn build_and_register_object_store(
@@ -1783,6 +2145,37 @@ async fn build_external_location_table_source(
     Ok(external_location_table_source)
 }
 
+fn build_export_location_table_source(
+    table_path: ListingTableUrl,
+    source_schema: SchemaRef,
+    partition_cols: Vec<(String, DataType)>,
+    file_format_options: FileFormatOptions,
+    max_file_size: Option<usize>,
+    session_config: SessionConfig,
+) -> datafusion::common::Result<Arc<dyn TableSource>> {
+    let (file_extension, file_format) = build_file_extension_and_format(file_format_options)?;
+    let file_schema = Schema::new(
+        source_schema
+            .fields()
+            .iter()
+            .filter(|f| partition_cols.iter().all(|(name, _)| name != f.name()))
+            .cloned()
+            .collect(),
+    );
+    let options = ListingOptions::new(file_format)
+        .with_collect_stat(session_config.collect_statistics())
+        .with_file_extension(file_extension)
+        .with_target_partitions(session_config.target_partitions())
+        .with_table_partition_cols(partition_cols);
+
+    let config = ListingTableConfig::new(table_path)
+        .with_listing_options(options)
+        .with_schema(Arc::new(file_schema));
+    let table = ExternalLocationTable::new(Arc::new(ListingTable::try_new(config)?), max_file_size);
+
+    Ok(provider_as_source(Arc::new(table)))
+}
+
 async fn build_listing_table(
     ctx: &SessionState,
     table_path: ListingTableUrl,
@@ -1818,10 +2211,24 @@ fn build_file_extension_and_format(
 ) -> datafusion::common::Result<(String, Arc<dyn FileFormat>)> {
     let FileFormatOptions {
         file_type,
+        ext_file_type,
         delimiter,
         with_header,
         file_compression_type,
+        ..
     } = file_format_options;
+    if let Some(ExtFileType::Orc) = ext_file_type {
+        // ORC files are compressed inside
+        if file_compression_type.is_compressed() {
+            return Err(DataFusionError::Plan(
+                "FileCompressionType can't be specified for ORC files".to_string(),
+            ));
+        }
+        return Ok((
+            DEFAULT_ORC_EXTENSION.to_string(),
+            Arc::new(OrcFormat::default()),
+        ));
+    }
     let file_extension = file_type.get_ext_with_compression(file_compression_type.to_owned())?;
     let file_format: Arc<dyn FileFormat> = match file_type {
         FileType::CSV => Arc::new(
@@ -2066,6 +2473,93 @@ fn extract_database_table_name(full_name: &str, session: &SessionCtx) -> (String
     (database_name, table_name)
 }
 
+/// Indexes of the parameters of `VALUES ($1, $2, ...)`, `None` if the values are not a row of
+/// parameters
+fn values_parameters(query: &Query) -> Option<Vec<usize>> {
+    let rows = match query.body.as_ref() {
+        SetExpr::Values(values) => &values.rows,
+        _ => return None,
+    };
+
+    match rows.as_slice() {
+        [row] if !row.is_empty() => row
+            .iter()
+            .map(|expr| match expr {
+                ASTExpr::Value(Value::Placeholder(id)) => parameter_index(id),
+                _ => None,
+            })
+            .collect(),
+        _ => None,
+    }
+}
+
+/// Plan the parameter rows of `INSERT ... VALUES ($1, $2, ...)`,
+/// the type of a parameter is the type of the column it is inserted into
+fn parameters_to_plan(
+    parameters: &[usize],
+    target_table: Arc<dyn TableSource>,
+    columns: &[String],
+) -> Result<LogicalPlan> {
+    let target_schema = target_table.schema();
+    let columns = if columns.is_empty() {
+        target_schema
+            .fields()
+            .iter()
+            .map(|e| e.name().clone())
+            .collect()
+    } else {
+        columns.to_vec()
+    };
+    if columns.len() != parameters.len() {
+        return Err(QueryError::MismatchColumns);
+    }
+
+    let num_parameters = parameters.iter().max().map(|e| e + 1).unwrap_or_default();
+    let mut fields = vec![None; num_parameters];
+    for (index, column) in parameters.iter().zip(columns.iter()) {
+        let field = target_schema
+            .fields()
+            .iter()
+            .find(|e| e.name() == column)
+            .ok_or_else(|| QueryError::MissingColumn {
+                insert_col: column.to_string(),
+                fields: target_schema
+                    .fields()
+                    .iter()
+                    .map(|e| e.name().as_str())
+                    .collect::<Vec<&str>>()
+                    .join(","),
+            })?;
+        fields[*index].get_or_insert_with(|| {
+            Field::new(&parameter_name(*index), field.data_type().clone(), true)
+        });
+    }
+    let fields = fields
+        .into_iter()
+        .enumerate()
+        .map(|(index, field)| {
+            field.ok_or_else(|| QueryError::InvalidParameters {
+                reason: format!("{} is missing", parameter_name(index)),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let table = Arc::new(ParameterTable::new(Arc::new(Schema::new(fields))));
+    let exprs = parameters
+        .iter()
+        .zip(columns.iter())
+        .map(|(index, column)| {
+            Expr::Column(Column::from_name(parameter_name(*index))).alias(column)
+        })
+        .collect::<Vec<_>>();
+
+    let plan = LogicalPlanBuilder::scan(PARAMETERS_TABLE, provider_as_source(table), None)?
+        .project(exprs)?
+        .build()?;
+
+    Ok(plan)
+}
+
 fn object_name_to_resolved_table(
     session: &SessionCtx,
     object_name: ObjectName,
@@ -2091,14 +2585,16 @@ mod tests {
     use lazy_static::__Deref;
     use meta::error::MetaError;
     use models::auth::user::{User, UserDesc, UserOptions};
-    use models::codec::Encoding;
+    use models::codec::{Encoding, FieldIndexType};
     use models::schema::{TableSourceAdapter, Tenant};
+    use spi::query::prepared_statement::PreparedResult;
     use spi::query::session::SessionCtxFactory;
     use spi::service::protocol::ContextBuilder;
 
     use super::*;
     use crate::extension::logical::plan_node::table_writer::TableWriterPlanNode;
     use crate::sql::parser::ExtParser;
+    use crate::sql::prepared_statement::prepare_plan;
 
     #[derive(Debug)]
     struct MockContext {}
@@ -2236,7 +2732,7 @@ mod tests {
     async fn test_create_table() {
         let sql = "CREATE TABLE IF NOT EXISTS default_catalog.default_schema.test\
             (column1 BIGINT CODEC(DELTA),\
-            column2 STRING CODEC(GZIP),\
+            column2 STRING CODEC(GZIP) INDEX(NGRAM),\
             column3 BIGINT UNSIGNED CODEC(NULL),\
             column4 BOOLEAN,\
             column5 DOUBLE CODEC(GORILLA),\
@@ -2259,48 +2755,56 @@ mod tests {
                             name: "time".to_string(),
                             column_type: ColumnType::Time,
                             encoding: Encoding::Default,
+                            index: None,
                         },
                         TableColumn {
                             id: 1,
                             name: "column6".to_string(),
                             column_type: ColumnType::Tag,
                             encoding: Encoding::Default,
+                            index: None,
                         },
                         TableColumn {
                             id: 2,
                             name: "column7".to_string(),
                             column_type: ColumnType::Tag,
                             encoding: Encoding::Default,
+                            index: None,
                         },
                         TableColumn {
                             id: 3,
                             name: "column1".to_string(),
                             column_type: ColumnType::Field(ValueType::Integer),
                             encoding: Encoding::Delta,
+                            index: None,
                         },
                         TableColumn {
                             id: 4,
                             name: "column2".to_string(),
                             column_type: ColumnType::Field(ValueType::String),
                             encoding: Encoding::Gzip,
+                            index: Some(FieldIndexType::Ngram),
                         },
                         TableColumn {
                             id: 5,
                             name: "column3".to_string(),
                             column_type: ColumnType::Field(ValueType::Unsigned),
                             encoding: Encoding::Null,
+                            index: None,
                         },
                         TableColumn {
                             id: 6,
                             name: "column4".to_string(),
                             column_type: ColumnType::Field(ValueType::Boolean),
                             encoding: Encoding::Default,
+                            index: None,
                         },
                         TableColumn {
                             id: 7,
                             name: "column5".to_string(),
                             column_type: ColumnType::Field(ValueType::Float),
                             encoding: Encoding::Gorilla,
+                            index: None,
                         },
                     ],
                     name: TableReference::parse_str("test")
@@ -2327,7 +2831,7 @@ mod tests {
         if let Plan::DDL(DDLPlan::CreateDatabase(create)) = plan.plan {
             let ans = format!("{:?}", create);
             println!("{ans}");
-            let expected = r#"CreateDatabase { name: "test", if_not_exists: false, options: DatabaseOptions { ttl: Some(Duration { time_num: 10, unit: Day }), shard_num: Some(5), vnode_duration: Some(Duration { time_num: 3, unit: Day }), replica: Some(10), precision: Some(US) } }"#;
+            let expected = r#"CreateDatabase { name: "test", if_not_exists: false, options: DatabaseOptions { ttl: Some(Duration { time_num: 10, unit: Day }), shard_num: Some(5), vnode_duration: Some(Duration { time_num: 3, unit: Day }), replica: Some(10), precision: Some(US), compaction: None, compaction_window: None, cold_duration: None } }"#;
             assert_eq!(ans, expected);
         } else {
             panic!("expected create table plan")
@@ -2398,6 +2902,7 @@ mod tests {
         match plan.plan {
             Plan::Query(QueryPlan {
                 df_plan: LogicalPlan::Aggregate(Aggregate { input, .. }),
+                ..
             }) => match input.as_ref() {
                 LogicalPlan::Extension(Extension { node }) => {
                     match node.as_any().downcast_ref::<TableWriterPlanNode>() {
@@ -2414,4 +2919,28 @@ mod tests {
             _ => panic!(),
         }
     }
+
+    #[tokio::test]
+    async fn test_insert_parameters() {
+        let sql = "insert test_tb(field_int, field_string) values ($2, $1);";
+        let mut statements = ExtParser::parse_sql(sql).unwrap();
+        assert_eq!(statements.len(), 1);
+        let test = MockContext {};
+        let planner = SqlPlaner::new(&test);
+        let plan = planner
+            .statement_to_plan(statements.pop_back().unwrap(), &session())
+            .await
+            .unwrap();
+
+        let prepared = prepare_plan(plan.plan).unwrap();
+        assert!(prepared.bulk_insert);
+        assert!(matches!(prepared.result, PreparedResult::AffectedRows));
+        let parameters = prepared
+            .parameter_schema
+            .fields()
+            .iter()
+            .map(|e| e.name().as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(parameters, vec!["$1", "$2"]);
+    }
 }
@@ -0,0 +1,695 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use datafusion::arrow::compute::cast;
+use datafusion::arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::common::{DFSchema, Result as DFResult};
+use datafusion::datasource::listing::ListingTable;
+use datafusion::datasource::{provider_as_source, source_as_provider, MemTable, TableProvider};
+use datafusion::logical_expr::expr_rewriter::{ExprRewritable, ExprRewriter};
+use datafusion::logical_expr::utils::from_plan;
+use datafusion::logical_expr::{
+    Between, BinaryExpr, Expr, ExprSchemable, Extension, LogicalPlan, TableScan, TableSource,
+    Volatility,
+};
+use datafusion::scalar::ScalarValue;
+use datafusion::sql::TableReference;
+use meta::MetaClientRef;
+use models::oid::Identifier;
+use spi::query::logical_planner::{Plan, QueryPlan};
+use spi::query::optimizer::Optimizer;
+use spi::query::prepared_statement::{PreparedPlan, PreparedResult, TableVersion};
+use spi::query::session::SessionCtx;
+use spi::{QueryError, Result};
+
+use crate::data_source::table_provider::parameters::ParameterTable;
+use crate::data_source::table_provider::tskv::ClusterTable;
+use crate::extension::expr::expr_rewriter::ExprReplacer;
+use crate::extension::expr::expr_utils::find_exprs_in_exprs_deeply_nested;
+use crate::extension::logical::plan_node::table_writer::TableWriterPlanNode;
+
+/// Name of the table the parameter rows of `INSERT ... VALUES ($1, $2, ...)` are scanned from
+pub const PARAMETERS_TABLE: &str = "parameters";
+
+/// Index of the parameter `$1`, `$2`...
+pub fn parameter_index(id: &str) -> Option<usize> {
+    id.strip_prefix('$')?.parse::<usize>().ok()?.checked_sub(1)
+}
+
+pub fn parameter_name(index: usize) -> String {
+    format!("${}", index + 1)
+}
+
+/// Number the `?` placeholders of the sql as `$1`, `$2`...,
+/// quoted strings, quoted identifiers and comments are kept as they are
+pub fn normalize_placeholders(sql: &str) -> String {
+    let mut result = String::with_capacity(sql.len());
+    let mut num_placeholders = 0;
+    let mut chars = sql.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '?' {
+            result.push_str(&parameter_name(num_placeholders));
+            num_placeholders += 1;
+            continue;
+        }
+
+        result.push(c);
+        match c {
+            '\'' | '"' => copy_until(&mut chars, &mut result, |_, next| next == c),
+            '-' if chars.peek() == Some(&'-') => {
+                copy_until(&mut chars, &mut result, |_, next| next == '\n')
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                result.push('*');
+                copy_until(&mut chars, &mut result, |prev, next| {
+                    prev == Some('*') && next == '/'
+                })
+            }
+            _ => {}
+        }
+    }
+
+    result
+}
+
+/// Copy the chars until the end, which is copied as well
+fn copy_until(
+    chars: &mut impl Iterator<Item = char>,
+    result: &mut String,
+    is_end: impl Fn(Option<char>, char) -> bool,
+) {
+    let mut prev = None;
+    for next in chars {
+        result.push(next);
+        if is_end(prev, next) {
+            break;
+        }
+        prev = Some(next);
+    }
+}
+
+/// Infer the types of the parameters of the plan and collect the tables the plan depends on,
+/// the names of the tables are resolved in `default_database`
+pub fn prepare_plan(
+    plan: Plan,
+    meta_client: &MetaClientRef,
+    default_database: &str,
+) -> Result<PreparedPlan> {
+    let df_plan = match plan {
+        Plan::Query(QueryPlan { df_plan, .. }) => df_plan,
+        plan => {
+            // Files of COPY INTO are listed when planning, DDL is planned with the current
+            // metadata, so they are planned again for each execution
+            let result = match plan {
+                Plan::CopyIntoTable(_) => PreparedResult::AffectedRows,
+                _ => PreparedResult::Unknown,
+            };
+            return Ok(PreparedPlan {
+                plan,
+                parameter_schema: Arc::new(Schema::empty()),
+                result,
+                bulk_insert: false,
+                table_versions: None,
+            });
+        }
+    };
+
+    let df_plan = infer_parameter_types(&df_plan)?;
+
+    let mut dependencies = PlanDependencies::new(meta_client, default_database);
+    dependencies.collect(&df_plan)?;
+    let PlanDependencies {
+        parameters,
+        parameter_table,
+        table_versions,
+        writes,
+        ..
+    } = dependencies;
+
+    let bulk_insert = parameter_table.is_some();
+    let parameter_schema = match parameter_table {
+        Some(schema) if parameters.is_empty() => schema,
+        Some(_) => {
+            return Err(QueryError::InvalidParameters {
+                reason: "the parameters of INSERT ... VALUES can't be used elsewhere".to_string(),
+            })
+        }
+        None => {
+            let num_parameters = parameters
+                .keys()
+                .next_back()
+                .map(|e| e + 1)
+                .unwrap_or_default();
+            let fields = (0..num_parameters)
+                .map(|index| match parameters.get(&index) {
+                    Some(Some(data_type)) => {
+                        Ok(Field::new(&parameter_name(index), data_type.clone(), true))
+                    }
+                    Some(None) => Err(QueryError::InvalidParameters {
+                        reason: format!("the type of {} can't be inferred", parameter_name(index)),
+                    }),
+                    None => Err(QueryError::InvalidParameters {
+                        reason: format!("{} is missing", parameter_name(index)),
+                    }),
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Arc::new(Schema::new(fields))
+        }
+    };
+
+    let result = if writes {
+        PreparedResult::AffectedRows
+    } else {
+        PreparedResult::Rows(Arc::new(Schema::from(df_plan.schema().as_ref().clone())))
+    };
+
+    Ok(PreparedPlan {
+        plan: Plan::Query(QueryPlan {
+            df_plan,
+            optimized: false,
+        }),
+        parameter_schema,
+        result,
+        bulk_insert,
+        table_versions,
+    })
+}
+
+/// Whether none of the tables the plan depends on is altered or dropped since it was planned
+pub fn is_plan_valid(prepared: &PreparedPlan, meta_client: &MetaClientRef) -> Result<bool> {
+    let table_versions = match &prepared.table_versions {
+        Some(table_versions) => table_versions,
+        None => return Ok(false),
+    };
+
+    for version in table_versions {
+        let is_valid = match version {
+            TableVersion::Tskv {
+                database,
+                table,
+                schema_id,
+            } => {
+                let schema = meta_client.get_tskv_table_schema(database, table)?;
+                schema.map(|e| e.schema_id) == Some(*schema_id)
+            }
+            TableVersion::External(schema) => {
+                let current = meta_client.get_external_table_schema(&schema.db, &schema.name)?;
+                current.as_ref() == Some(schema)
+            }
+        };
+        if !is_valid {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+/// Apply the logical optimization rules to the prepared plan once for all the executions
+/// in the same time zone, the parameters are bound to the optimized plan.
+///
+/// `None` if the optimized plan can't be reused, e.g. `now()` is evaluated when optimizing.
+pub fn optimize_prepared_plan(
+    prepared: &PreparedPlan,
+    optimizer: &dyn Optimizer,
+    session: &SessionCtx,
+) -> Result<Option<PreparedPlan>> {
+    let df_plan = match &prepared.plan {
+        Plan::Query(QueryPlan { df_plan, .. }) if prepared.table_versions.is_some() => df_plan,
+        _ => return Ok(None),
+    };
+    if has_volatile_functions(df_plan) {
+        return Ok(None);
+    }
+
+    let df_plan = optimizer.optimize_logical_plan(df_plan, session)?;
+    Ok(Some(PreparedPlan {
+        plan: Plan::Query(QueryPlan {
+            df_plan,
+            optimized: true,
+        }),
+        ..prepared.clone()
+    }))
+}
+
+fn has_volatile_functions(plan: &LogicalPlan) -> bool {
+    let exprs = find_exprs_in_exprs_deeply_nested(&plan.expressions(), &|e| match e {
+        Expr::ScalarFunction { fun, .. } => fun.volatility() != Volatility::Immutable,
+        Expr::ScalarUDF { fun, .. } => fun.signature.volatility != Volatility::Immutable,
+        _ => false,
+    });
+
+    !exprs.is_empty() || plan.inputs().into_iter().any(has_volatile_functions)
+}
+
+/// Bind the parameter rows to the plan, returns the plans to execute in order.
+///
+/// All the rows are written at once by `INSERT ... VALUES ($1, $2, ...)`,
+/// otherwise the plan is executed for each row.
+pub fn bind_parameters(prepared: &PreparedPlan, parameters: &[RecordBatch]) -> Result<Vec<Plan>> {
+    let schema = &prepared.parameter_schema;
+    let (df_plan, optimized) = match &prepared.plan {
+        Plan::Query(QueryPlan { df_plan, optimized }) if !schema.fields().is_empty() => {
+            (df_plan, *optimized)
+        }
+        plan => return Ok(vec![plan.clone()]),
+    };
+
+    let parameters = parameters
+        .iter()
+        .filter(|e| e.num_rows() > 0)
+        .map(|e| cast_parameters(e, schema))
+        .collect::<Result<Vec<_>>>()?;
+    if parameters.is_empty() {
+        return Err(QueryError::InvalidParameters {
+            reason: "the parameters are not bound".to_string(),
+        });
+    }
+
+    if prepared.bulk_insert {
+        let table = Arc::new(MemTable::try_new(schema.clone(), vec![parameters])?);
+        let df_plan = transform_plan(df_plan, &mut |plan, inputs| match plan {
+            LogicalPlan::TableScan(scan) if is_parameter_table(&scan.source) => {
+                Ok(LogicalPlan::TableScan(TableScan {
+                    source: provider_as_source(table.clone()),
+                    ..scan.clone()
+                }))
+            }
+            _ => from_plan(plan, &plan.expressions(), &inputs),
+        })?;
+        return Ok(vec![Plan::Query(QueryPlan { df_plan, optimized })]);
+    }
+
+    let mut plans = vec![];
+    for batch in parameters.iter() {
+        for row in 0..batch.num_rows() {
+            let values = batch
+                .columns()
+                .iter()
+                .map(|e| ScalarValue::try_from_array(e, row))
+                .collect::<DFResult<Vec<_>>>()?;
+            let df_plan = replace_placeholders(df_plan, &values)?;
+            plans.push(Plan::Query(QueryPlan { df_plan, optimized }));
+        }
+    }
+
+    Ok(plans)
+}
+
+fn cast_parameters(batch: &RecordBatch, schema: &SchemaRef) -> Result<RecordBatch> {
+    if batch.num_columns() != schema.fields().len() {
+        return Err(QueryError::InvalidParameters {
+            reason: format!(
+                "expected {} parameters, but got {}",
+                schema.fields().len(),
+                batch.num_columns()
+            ),
+        });
+    }
+
+    let columns = batch
+        .columns()
+        .iter()
+        .zip(schema.fields())
+        .map(|(column, field)| cast(column, field.data_type()))
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    Ok(RecordBatch::try_new(schema.clone(), columns)?)
+}
+
+fn is_parameter_table(source: &Arc<dyn TableSource>) -> bool {
+    source_as_provider(source)
+        .map(|e| e.as_any().downcast_ref::<ParameterTable>().is_some())
+        .unwrap_or_default()
+}
+
+/// Rebuild the plan bottom-up, explained plans are kept as they are
+fn transform_plan<F>(plan: &LogicalPlan, f: &mut F) -> DFResult<LogicalPlan>
+where
+    F: FnMut(&LogicalPlan, Vec<LogicalPlan>) -> DFResult<LogicalPlan>,
+{
+    if matches!(plan, LogicalPlan::Explain(_) | LogicalPlan::Analyze(_)) {
+        return Ok(plan.clone());
+    }
+
+    let inputs = plan
+        .inputs()
+        .into_iter()
+        .map(|input| transform_plan(input, f))
+        .collect::<DFResult<Vec<_>>>()?;
+
+    f(plan, inputs)
+}
+
+fn infer_parameter_types(plan: &LogicalPlan) -> DFResult<LogicalPlan> {
+    transform_plan(plan, &mut |plan, inputs| {
+        let mut schema = DFSchema::empty();
+        for input in inputs.iter() {
+            schema.merge(input.schema());
+        }
+
+        let mut rewriter = ParameterTypeRewriter { schema: &schema };
+        let exprs = plan
+            .expressions()
+            .into_iter()
+            .map(|e| e.rewrite(&mut rewriter))
+            .collect::<DFResult<Vec<_>>>()?;
+
+        from_plan(plan, &exprs, &inputs)
+    })
+}
+
+fn replace_placeholders(plan: &LogicalPlan, values: &[ScalarValue]) -> DFResult<LogicalPlan> {
+    let replacer = |expr: &Expr| match expr {
+        Expr::Placeholder { id, .. } => parameter_index(id)
+            .and_then(|index| values.get(index))
+            .map(|value| Expr::Literal(value.clone())),
+        _ => None,
+    };
+
+    transform_plan(plan, &mut |plan, inputs| {
+        let mut rewriter = ExprReplacer::new(&replacer);
+        // The filters pushed down to the scan by the optimizer are not the expressions of the plan
+        if let LogicalPlan::TableScan(scan) = plan {
+            let filters = scan
+                .filters
+                .iter()
+                .map(|e| e.clone().rewrite(&mut rewriter))
+                .collect::<DFResult<Vec<_>>>()?;
+            return Ok(LogicalPlan::TableScan(TableScan {
+                filters,
+                ..scan.clone()
+            }));
+        }
+
+        let exprs = plan
+            .expressions()
+            .into_iter()
+            .map(|e| e.rewrite(&mut rewriter))
+            .collect::<DFResult<Vec<_>>>()?;
+
+        from_plan(plan, &exprs, &inputs)
+    })
+}
+
+/// Set the types of the parameters compared with typed expressions,
+/// e.g. the type of `$1` in `a > $1` is the type of `a`
+struct ParameterTypeRewriter<'a> {
+    schema: &'a DFSchema,
+}
+
+impl<'a> ParameterTypeRewriter<'a> {
+    fn infer(&self, expr: Box<Expr>, other: &Expr) -> Box<Expr> {
+        match *expr {
+            Expr::Placeholder {
+                id,
+                data_type: None,
+            } => Box::new(Expr::Placeholder {
+                id,
+                data_type: other.get_type(self.schema).ok(),
+            }),
+            expr => Box::new(expr),
+        }
+    }
+
+    fn infer_pair(&self, left: Box<Expr>, right: Box<Expr>) -> (Box<Expr>, Box<Expr>) {
+        let left = self.infer(left, &right);
+        let right = self.infer(right, &left);
+        (left, right)
+    }
+}
+
+impl<'a> ExprRewriter for ParameterTypeRewriter<'a> {
+    fn mutate(&mut self, expr: Expr) -> DFResult<Expr> {
+        let new_expr = match expr {
+            Expr::BinaryExpr(BinaryExpr { left, op, right }) => {
+                let (left, right) = self.infer_pair(left, right);
+                Expr::BinaryExpr(BinaryExpr { left, op, right })
+            }
+            Expr::Between(Between {
+                expr,
+                negated,
+                low,
+                high,
+            }) => {
+                let (expr, low) = self.infer_pair(expr, low);
+                let (expr, high) = self.infer_pair(expr, high);
+                Expr::Between(Between {
+                    expr,
+                    negated,
+                    low,
+                    high,
+                })
+            }
+            Expr::InList {
+                expr,
+                list,
+                negated,
+            } => {
+                let list = list
+                    .into_iter()
+                    .map(|e| *self.infer(Box::new(e), &expr))
+                    .collect::<Vec<_>>();
+                let expr = match list.first() {
+                    Some(first) => self.infer(expr, first),
+                    None => expr,
+                };
+                Expr::InList {
+                    expr,
+                    list,
+                    negated,
+                }
+            }
+            expr => expr,
+        };
+
+        Ok(new_expr)
+    }
+}
+
+/// What the reuse of a plan depends on
+struct PlanDependencies<'a> {
+    meta_client: &'a MetaClientRef,
+    default_database: &'a str,
+    parameters: BTreeMap<usize, Option<DataType>>,
+    parameter_table: Option<SchemaRef>,
+    table_versions: Option<Vec<TableVersion>>,
+    writes: bool,
+}
+
+impl<'a> PlanDependencies<'a> {
+    fn new(meta_client: &'a MetaClientRef, default_database: &'a str) -> Self {
+        Self {
+            meta_client,
+            default_database,
+            parameters: BTreeMap::new(),
+            parameter_table: None,
+            table_versions: Some(vec![]),
+            writes: false,
+        }
+    }
+
+    fn collect(&mut self, plan: &LogicalPlan) -> Result<()> {
+        match plan {
+            // The explained plan is never executed
+            LogicalPlan::Explain(_) | LogicalPlan::Analyze(_) => return Ok(()),
+            LogicalPlan::TableScan(TableScan {
+                table_name, source, ..
+            }) => self.collect_table(Some(table_name), source)?,
+            LogicalPlan::Extension(Extension { node }) => {
+                if let Some(writer) = node.as_any().downcast_ref::<TableWriterPlanNode>() {
+                    self.writes = true;
+                    // The name of the target table is not qualified by the database
+                    self.collect_table(None, &writer.target_table)?;
+                }
+            }
+            _ => {}
+        }
+
+        let exprs = find_exprs_in_exprs_deeply_nested(&plan.expressions(), &|e| {
+            matches!(
+                e,
+                Expr::Placeholder { .. }
+                    | Expr::ScalarSubquery(_)
+                    | Expr::InSubquery { .. }
+                    | Expr::Exists { .. }
+            )
+        });
+        for expr in exprs {
+            match expr {
+                Expr::Placeholder { id, data_type } => {
+                    let index =
+                        parameter_index(&id).ok_or_else(|| QueryError::InvalidParameters {
+                            reason: format!("invalid parameter {}", id),
+                        })?;
+                    let parameter = self.parameters.entry(index).or_default();
+                    if parameter.is_none() {
+                        *parameter = data_type;
+                    }
+                }
+                // The tables of subqueries are not tracked
+                _ => self.table_versions = None,
+            }
+        }
+
+        for input in plan.inputs() {
+            self.collect(input)?;
+        }
+
+        Ok(())
+    }
+
+    fn collect_table(
+        &mut self,
+        table_name: Option<&str>,
+        source: &Arc<dyn TableSource>,
+    ) -> Result<()> {
+        let provider = match source_as_provider(source) {
+            Ok(provider) => provider,
+            Err(_) => {
+                self.table_versions = None;
+                return Ok(());
+            }
+        };
+
+        if let Some(table) = provider.as_any().downcast_ref::<ParameterTable>() {
+            self.parameter_table = Some(table.schema());
+            return Ok(());
+        }
+
+        let version = if let Some(table) = provider.as_any().downcast_ref::<ClusterTable>() {
+            let schema = table.table_schema();
+            Some(TableVersion::Tskv {
+                database: schema.db.clone(),
+                table: schema.name.clone(),
+                schema_id: schema.schema_id,
+            })
+        } else if provider.as_any().is::<ListingTable>() {
+            // External tables are scanned as listing tables, which don't keep the definition
+            match table_name {
+                Some(table_name) => self.external_table_version(table_name)?,
+                None => None,
+            }
+        } else {
+            // e.g. system tables, whose content is taken when planning
+            None
+        };
+
+        match (version, self.table_versions.as_mut()) {
+            (Some(version), Some(table_versions)) => table_versions.push(version),
+            (None, _) => self.table_versions = None,
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn external_table_version(&self, table_name: &str) -> Result<Option<TableVersion>> {
+        let table_ref = TableReference::from(table_name)
+            .resolve(self.meta_client.tenant().name(), self.default_database);
+        let schema = self
+            .meta_client
+            .get_external_table_schema(&table_ref.schema, &table_ref.table)?;
+
+        Ok(schema.map(TableVersion::External))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use datafusion::arrow::array::Int32Array;
+    use datafusion::logical_expr::LogicalPlanBuilder;
+    use datafusion::prelude::{col, lit};
+    use meta::meta_client_mock::MockMetaClient;
+
+    use super::*;
+
+    #[test]
+    fn test_normalize_placeholders() {
+        assert_eq!(
+            normalize_placeholders("SELECT '?', \"?\" FROM t WHERE a = ? /* ? */ AND b = ? -- ?"),
+            "SELECT '?', \"?\" FROM t WHERE a = $1 /* ? */ AND b = $2 -- ?"
+        );
+    }
+
+    #[test]
+    fn test_bind_parameters() {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int64, false)]));
+        let table = Arc::new(MemTable::try_new(schema, vec![]).unwrap());
+        let parameter = Expr::Placeholder {
+            id: "$1".to_string(),
+            data_type: None,
+        };
+        let df_plan = LogicalPlanBuilder::scan("t", provider_as_source(table), None)
+            .unwrap()
+            .filter(col("a").gt(parameter))
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let meta_client: MetaClientRef = Arc::new(MockMetaClient::default());
+        let plan = Plan::Query(QueryPlan {
+            df_plan,
+            optimized: false,
+        });
+        let prepared = prepare_plan(plan, &meta_client, "public").unwrap();
+        assert_eq!(prepared.parameter_schema.fields().len(), 1);
+        assert_eq!(
+            prepared.parameter_schema.field(0).data_type(),
+            &DataType::Int64
+        );
+        // Not a tskv table
+        assert!(prepared.table_versions.is_none());
+
+        let parameters = RecordBatch::try_new(
+            Arc::new(Schema::new(vec![Field::new("p", DataType::Int32, false)])),
+            vec![Arc::new(Int32Array::from(vec![1, 2]))],
+        )
+        .unwrap();
+        let plans = bind_parameters(&prepared, &[parameters])
+            .unwrap()
+            .into_iter()
+            .map(|plan| match plan {
+                Plan::Query(QueryPlan { df_plan, .. }) => format!("{}", df_plan.display_indent()),
+                _ => panic!("expected query plan"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            plans,
+            vec![
+                "Filter: t.a > Int64(1)\n  TableScan: t",
+                "Filter: t.a > Int64(2)\n  TableScan: t",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_replace_placeholders_in_scan() {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int64, false)]));
+        let table = Arc::new(MemTable::try_new(schema, vec![]).unwrap());
+        let parameter = Expr::Placeholder {
+            id: "$1".to_string(),
+            data_type: Some(DataType::Int64),
+        };
+        // The filter pushed down by the optimizer
+        let df_plan = LogicalPlanBuilder::scan_with_filters(
+            "t",
+            provider_as_source(table),
+            None,
+            vec![col("a").gt(parameter)],
+        )
+        .unwrap()
+        .build()
+        .unwrap();
+
+        let df_plan = replace_placeholders(&df_plan, &[ScalarValue::Int64(Some(1))]).unwrap();
+        match df_plan {
+            LogicalPlan::TableScan(scan) => {
+                assert_eq!(scan.filters, vec![col("a").gt(lit(1_i64))])
+            }
+            _ => panic!("expected table scan"),
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use std::error;
+use std::time::Duration;
 
 use coordinator::errors::CoordinatorError;
 use datafusion::arrow::error::ArrowError;
@@ -8,7 +9,7 @@ use datafusion::sql::sqlparser::parser::ParserError;
 use error_code::ErrorCoder;
 use meta::error::MetaError;
 use models::auth::AuthError;
-use models::codec::Encoding;
+use models::codec::{Encoding, FieldIndexType};
 use models::define_result;
 use models::error_code::ErrorCode;
 use models::schema::TIME_FIELD_NAME;
@@ -368,6 +369,20 @@ pub enum QueryError {
         source: ArrowError,
     },
 
+    #[error_code(code = 65)]
+    #[snafu(display("Failed to serialize data to avro bytes, error: {}", reason))]
+    SerializeAvro {
+        reason: String,
+    },
+
+    #[error_code(code = 66)]
+    #[snafu(display("Failed to parse line {} of file {}, error: {}", line, file, reason))]
+    ParsePoints {
+        file: String,
+        line: u64,
+        reason: String,
+    },
+
     #[error_code(code = 53)]
     #[snafu(display("{}", source))]
     StdIoError {
@@ -403,6 +418,65 @@ pub enum QueryError {
     InvalidTimeWindowParam {
         reason: String,
     },
+
+    #[snafu(display("Resources exhausted: {}", reason))]
+    #[error_code(code = 59)]
+    ResourcesExhausted {
+        reason: String,
+    },
+
+    #[snafu(display("The query queue of tenant {} is full, queue size: {}", tenant, size))]
+    #[error_code(code = 60)]
+    QueryQueueFull {
+        tenant: String,
+        size: usize,
+    },
+
+    #[snafu(display(
+        "The query of tenant {} has been waiting in the queue for more than {:?}",
+        tenant,
+        timeout
+    ))]
+    #[error_code(code = 61)]
+    QueryQueueTimeout {
+        tenant: String,
+        timeout: Duration,
+    },
+
+    #[snafu(display("The query has been canceled after timeout {:?}", timeout))]
+    #[error_code(code = 62)]
+    QueryTimeout {
+        timeout: Duration,
+    },
+
+    #[snafu(display("Unknown session variable: {}", name))]
+    #[error_code(code = 63)]
+    UnknownSessionVariable {
+        name: String,
+    },
+
+    #[snafu(display(
+        "Semantic error: Unsupported field index type {:?} for {}, only STRING fields can be indexed",
+        index_type,
+        data_type
+    ))]
+    #[error_code(code = 64)]
+    FieldIndexType {
+        index_type: FieldIndexType,
+        data_type: String,
+    },
+
+    #[snafu(display("Invalid parameters of the prepared statement: {}", reason))]
+    #[error_code(code = 67)]
+    InvalidParameters {
+        reason: String,
+    },
+
+    #[snafu(display(
+        "Session variables can't be kept by the connection, send them as parameters of the requests instead"
+    ))]
+    #[error_code(code = 68)]
+    SessionVariablesUnsupported,
 }
 
 impl From<ParserError> for QueryError {
@@ -436,6 +510,9 @@ impl From<DataFusionError> for QueryError {
                 }
             }
             DataFusionError::ArrowError(e) => e.into(),
+            DataFusionError::ResourcesExhausted(reason) => {
+                QueryError::ResourcesExhausted { reason }
+            }
             v => QueryError::Datafusion { source: v },
         }
     }
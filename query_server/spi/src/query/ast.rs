@@ -2,11 +2,11 @@ use std::fmt;
 
 use datafusion::sql::parser::CreateExternalTable;
 use datafusion::sql::sqlparser::ast::{
-    AnalyzeFormat, DataType, Expr, Ident, ObjectName, Offset, OrderByExpr, SqlOption, Statement,
-    TableFactor, Value,
+    AnalyzeFormat, DataType, Expr, Ident, ObjectName, Offset, OrderByExpr, SelectItem, SqlOption,
+    Statement, TableFactor, Value,
 };
 use datafusion::sql::sqlparser::parser::ParserError;
-use models::codec::Encoding;
+use models::codec::{Encoding, FieldIndexType};
 use models::meta_data::{NodeId, ReplicationSetId, VnodeId};
 
 use super::logical_planner::{DatabaseObjectType, GlobalObjectType, TenantObjectType};
@@ -26,6 +26,7 @@ pub enum ExtStatement {
     CreateTenant(CreateTenant),
     CreateUser(CreateUser),
     CreateRole(CreateRole),
+    CreatePipe(CreatePipe),
 
     CreateStream(CreateStream),
     DropStream(DropStream),
@@ -43,10 +44,16 @@ pub enum ExtStatement {
     ShowTables(Option<ObjectName>),
     ShowSeries(Box<ShowSeries>),
     ShowTagValues(Box<ShowTagValues>),
+    ShowPipes,
     Explain(Explain),
 
     // system cmd
     ShowQueries,
+    SetVariable(SetVariable),
+    // None means all variables
+    ShowVariable(Option<Ident>),
+    // None means all variables
+    ResetVariable(Option<Ident>),
     AlterDatabase(AlterDatabase),
     AlterTable(AlterTable),
     AlterTenant(AlterTenant),
@@ -58,6 +65,15 @@ pub enum ExtStatement {
     MoveVnode(MoveVnode),
     CompactVnode(CompactVnode),
     ChecksumGroup(ChecksumGroup),
+
+    // node cmd
+    AlterNode(AlterNode),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlterNode {
+    pub node_id: NodeId,
+    pub io_rate_limit: u64,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -94,6 +110,15 @@ pub struct Copy {
     pub copy_options: Vec<SqlOption>,
 }
 
+/// CREATE PIPE [IF NOT EXISTS] name [INTERVAL = '<duration>'] AS COPY INTO table FROM 'location' ...
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CreatePipe {
+    pub if_not_exists: bool,
+    pub name: Ident,
+    pub interval: Option<String>,
+    pub copy: Copy,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum CopyTarget {
     IntoTable(CopyIntoTable),
@@ -110,6 +135,8 @@ pub struct CopyIntoTable {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct CopyIntoLocation {
     pub from: TableFactor,
+    /// Hive-style partition columns, e.g. `PARTITION BY (CAST(time AS DATE) AS date, host)`
+    pub partition_by: Vec<SelectItem>,
     pub location: UriLocation,
 }
 
@@ -272,15 +299,22 @@ pub struct ColumnOption {
     pub is_tag: bool,
     pub data_type: DataType,
     pub encoding: Option<Encoding>,
+    pub index: Option<FieldIndexType>,
 }
 
 impl ColumnOption {
-    pub fn new_field(name: Ident, data_type: DataType, encoding: Option<Encoding>) -> Self {
+    pub fn new_field(
+        name: Ident,
+        data_type: DataType,
+        encoding: Option<Encoding>,
+        index: Option<FieldIndexType>,
+    ) -> Self {
         Self {
             name,
             is_tag: false,
             data_type,
             encoding,
+            index,
         }
     }
 
@@ -290,6 +324,7 @@ impl ColumnOption {
             is_tag: true,
             data_type: DataType::String,
             encoding: None,
+            index: None,
         }
     }
 }
@@ -306,6 +341,10 @@ pub struct DatabaseOptions {
     pub replica: Option<u64>,
     // timestamp percision
     pub precision: Option<String>,
+
+    pub compaction: Option<String>,
+    pub compaction_window: Option<String>,
+    pub cold_duration: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -339,6 +378,12 @@ pub struct ShowTagBody {
     pub offset: Option<Offset>,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SetVariable {
+    pub variable: Ident,
+    pub value: String,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct ShowSeries {
     pub body: ShowTagBody,
@@ -432,6 +477,18 @@ pub fn parse_string_value(value: Value) -> std::result::Result<String, ParserErr
     }
 }
 
+pub fn parse_u64_value(value: Value) -> std::result::Result<u64, ParserError> {
+    match value {
+        Value::Number(ref n, _) => n.to_string().parse::<u64>().map_err(|_| {
+            ParserError::ParserError(format!("expected unsigned number, but found : {}", value))
+        }),
+        _ => Err(ParserError::ParserError(format!(
+            "expected unsigned number, but found : {}",
+            value
+        ))),
+    }
+}
+
 pub fn parse_char_value(value: Value) -> std::result::Result<char, ParserError> {
     let token = parse_string_value(value)?;
     match token.len() {
@@ -1,10 +1,13 @@
 use std::time::Duration;
 
 use async_trait::async_trait;
+use datafusion::arrow::record_batch::RecordBatch;
 use models::auth::user::UserDesc;
 use models::oid::{Identifier, Oid};
 
 use super::execution::QueryState;
+use super::prepared_statement::{PreparedStatement, PreparedStatementRef};
+use super::session::QueryPriority;
 use crate::query::execution::Output;
 use crate::service::protocol::{Query, QueryId};
 use crate::Result;
@@ -21,6 +24,25 @@ pub trait QueryDispatcher: Send + Sync {
 
     async fn execute_query(&self, tenant_id: Oid, id: QueryId, query: &Query) -> Result<Output>;
 
+    /// Plan the statement without executing it,
+    /// `?` and `$1`, `$2`... in the statement are the parameters bound on execution
+    async fn prepare_query(
+        &self,
+        tenant_id: Oid,
+        id: QueryId,
+        query: &Query,
+    ) -> Result<PreparedStatementRef>;
+
+    /// Execute the prepared statement with the parameter rows
+    async fn execute_prepared_query(
+        &self,
+        tenant_id: Oid,
+        id: QueryId,
+        query: &Query,
+        statement: &PreparedStatement,
+        parameters: Vec<RecordBatch>,
+    ) -> Result<Output>;
+
     fn running_query_infos(&self) -> Vec<QueryInfo>;
 
     fn running_query_status(&self) -> Vec<QueryStatus>;
@@ -36,6 +58,7 @@ pub struct QueryInfo {
     tenant_id: Oid,
     tenant_name: String,
     user: UserDesc,
+    priority: QueryPriority,
 }
 
 impl QueryInfo {
@@ -52,9 +75,15 @@ impl QueryInfo {
             tenant_id,
             tenant_name,
             user,
+            priority: QueryPriority::default(),
         }
     }
 
+    pub fn with_priority(mut self, priority: QueryPriority) -> Self {
+        self.priority = priority;
+        self
+    }
+
     pub fn query_id(&self) -> QueryId {
         self.query_id
     }
@@ -78,6 +107,10 @@ impl QueryInfo {
     pub fn user_name(&self) -> &str {
         self.user.name()
     }
+
+    pub fn priority(&self) -> QueryPriority {
+        self.priority
+    }
 }
 
 #[derive(Debug)]
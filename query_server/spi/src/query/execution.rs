@@ -131,6 +131,11 @@ impl QueryStateMachine {
         // TODO record time
     }
 
+    pub fn begin_queue(&self) {
+        // TODO record time
+        self.translate_to(Box::new(QueryState::QUEUED));
+    }
+
     pub fn begin_optimize(&self) {
         // TODO record time
         self.translate_to(Box::new(QueryState::RUNNING(RUNNING::OPTMIZING)));
@@ -180,6 +185,7 @@ impl QueryStateMachine {
 #[derive(Debug, Clone)]
 pub enum QueryState {
     ACCEPTING,
+    QUEUED,
     RUNNING(RUNNING),
     DONE(DONE),
 }
@@ -188,6 +194,7 @@ impl AsRef<str> for QueryState {
     fn as_ref(&self) -> &str {
         match self {
             QueryState::ACCEPTING => "ACCEPTING",
+            QueryState::QUEUED => "QUEUED",
             QueryState::RUNNING(e) => e.as_ref(),
             QueryState::DONE(e) => e.as_ref(),
         }
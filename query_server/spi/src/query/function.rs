@@ -16,4 +16,6 @@ pub trait FunctionMetadataManager {
     fn udaf(&self, name: &str) -> Result<Arc<AggregateUDF>>;
 
     fn udfs(&self) -> HashSet<String>;
+
+    fn udafs(&self) -> HashSet<String>;
 }
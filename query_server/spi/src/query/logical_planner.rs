@@ -1,9 +1,10 @@
 use std::io::Write;
 use std::sync::Arc;
+use std::time::Duration;
 
 use async_trait::async_trait;
 use datafusion::arrow::array::ArrayRef;
-use datafusion::arrow::datatypes::DataType;
+use datafusion::arrow::datatypes::{DataType, SchemaRef};
 use datafusion::datasource::file_format::file_type::{FileCompressionType, FileType};
 use datafusion::logical_expr::expr::AggregateFunction as AggregateFunctionExpr;
 use datafusion::logical_expr::type_coercion::aggregates::{
@@ -11,7 +12,7 @@ use datafusion::logical_expr::type_coercion::aggregates::{
 };
 use datafusion::logical_expr::{
     AggregateFunction, CreateExternalTable, LogicalPlan as DFPlan, ReturnTypeFunction, ScalarUDF,
-    Signature, Volatility,
+    Signature, TableSource, Volatility,
 };
 use datafusion::physical_plan::functions::make_scalar_function;
 use datafusion::prelude::{col, Expr};
@@ -24,18 +25,23 @@ use models::auth::user::{UserOptions, UserOptionsBuilder};
 use models::meta_data::{NodeId, ReplicationSetId, VnodeId};
 use models::object_reference::ResolvedTable;
 use models::oid::Oid;
-use models::schema::{DatabaseOptions, TableColumn, TenantOptions, TenantOptionsBuilder};
+use models::schema::{
+    DatabaseOptions, Precision, TableColumn, TenantOptions, TenantOptionsBuilder,
+};
+use object_store::path::Path;
 use snafu::ResultExt;
 use tempfile::NamedTempFile;
 
-use super::ast::{parse_bool_value, parse_char_value, parse_string_value, ExtStatement};
+use super::ast::{
+    parse_bool_value, parse_char_value, parse_string_value, parse_u64_value, ExtStatement,
+};
 use super::datasource::azure::{AzblobStorageConfig, AzblobStorageConfigBuilder};
 use super::datasource::gcs::{
     GcsStorageConfig, ServiceAccountCredentials, ServiceAccountCredentialsBuilder,
 };
 use super::datasource::s3::{S3StorageConfig, S3StorageConfigBuilder};
 use super::datasource::UriSchema;
-use super::session::SessionCtx;
+use super::session::{SessionCtx, SessionVariable};
 use super::AFFECTED_ROWS;
 use crate::service::protocol::QueryId;
 use crate::{ParserSnafu, QueryError, Result};
@@ -73,11 +79,55 @@ pub enum Plan {
     DDL(DDLPlan),
     /// Query plan
     SYSTEM(SYSPlan),
+    /// Copy files into table, file by file
+    CopyIntoTable(CopyIntoTablePlan),
 }
 
 #[derive(Debug, Clone)]
 pub struct QueryPlan {
     pub df_plan: DFPlan,
+    /// The logical optimization rules have been applied, e.g. the cached plan of a prepared statement
+    pub optimized: bool,
+}
+
+#[derive(Clone)]
+pub struct CopyIntoTablePlan {
+    /// Files under the location, sorted by url
+    pub files: Vec<CopyFile>,
+    pub source_schema: SchemaRef,
+    pub target_table: Arc<dyn TableSource>,
+    pub database: String,
+    pub table_name: String,
+    pub insert_columns: Vec<String>,
+    pub file_format_options: FileFormatOptions,
+    pub copy_options: CopyOptions,
+    /// The table rejected rows are written to and its name,
+    /// rejected rows are written next to the source file if not specified
+    pub rejected_rows_table: Option<(Arc<dyn TableSource>, String)>,
+}
+
+#[derive(Clone)]
+pub struct CopyFile {
+    pub url: String,
+    /// Path of the file relative to the location of COPY INTO
+    pub name: String,
+    pub location: Path,
+    pub size: u64,
+    /// Last modified time in nanoseconds
+    pub last_modified: i64,
+    pub source: CopyFileSource,
+}
+
+#[derive(Clone)]
+pub enum CopyFileSource {
+    Table {
+        /// The file as an external table
+        source: Arc<dyn TableSource>,
+        /// Write the file into the target table
+        write_plan: DFPlan,
+    },
+    /// The file is parsed into points of the target table, see [`PointsFormat`]
+    Points(PointsFormat),
 }
 
 #[derive(Clone)]
@@ -102,6 +152,8 @@ pub enum DDLPlan {
 
     CreateRole(CreateRole),
 
+    CreatePipe(CreatePipe),
+
     DescribeTable(DescribeTable),
 
     DescribeDatabase(DescribeDatabase),
@@ -110,6 +162,8 @@ pub enum DDLPlan {
 
     ShowDatabases(),
 
+    ShowPipes,
+
     AlterDatabase(AlterDatabase),
 
     AlterTable(AlterTable),
@@ -129,6 +183,15 @@ pub enum DDLPlan {
     CompactVnode(CompactVnode),
 
     ChecksumGroup(ChecksumGroup),
+
+    AlterNode(AlterNode),
+}
+
+#[derive(Debug, Clone)]
+pub struct AlterNode {
+    pub node_id: NodeId,
+    /// Bytes per second of flush and compaction writes, 0 means unlimited.
+    pub io_rate_limit: u64,
 }
 
 #[derive(Debug, Clone)]
@@ -162,6 +225,11 @@ pub struct DropVnode {
 pub enum SYSPlan {
     ShowQueries,
     KillQuery(QueryId),
+    SetVariable(SessionVariable, String),
+    // None means all variables
+    ShowVariable(Option<SessionVariable>),
+    // None means all variables
+    ResetVariable(Option<SessionVariable>),
 }
 
 #[derive(Debug, Clone)]
@@ -192,6 +260,7 @@ pub struct DropTenantObject {
 pub enum TenantObjectType {
     Role,
     Database,
+    Pipe,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -277,10 +346,16 @@ pub fn sql_options_to_tenant_options(options: Vec<SqlOption>) -> Result<TenantOp
             "comment" => {
                 builder.comment(parse_string_value(value).context(ParserSnafu)?);
             }
+            "query_memory_limit" => {
+                builder.query_memory_limit(parse_u64_value(value).context(ParserSnafu)?);
+            }
+            "max_concurrent_queries" => {
+                builder.max_concurrent_queries(parse_u64_value(value).context(ParserSnafu)?);
+            }
             _ => {
                 return Err(QueryError::Semantic {
                     err: ParserError::ParserError(format!(
-                        "Expected option [comment], found [{}]",
+                        "Expected option [comment, query_memory_limit, max_concurrent_queries], found [{}]",
                         name
                     ))
                     .to_string(),
@@ -342,6 +417,18 @@ pub struct CreateRole {
     pub inherit_tenant_role: SystemTenantRole,
 }
 
+#[derive(Debug, Clone)]
+pub struct CreatePipe {
+    pub tenant_name: String,
+    pub name: String,
+    pub if_not_exists: bool,
+    /// The default database of the session
+    pub database: String,
+    /// The table files are loaded into, e.g. database_name.table_name
+    pub table: String,
+    pub interval: Duration,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct DescribeDatabase {
     pub database_name: String,
@@ -477,13 +564,51 @@ pub fn normalize_ident(id: &Ident) -> String {
     }
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OnError {
+    /// Abort the whole copy at the first error
+    #[default]
+    Abort,
+    /// Skip the file that contains any error
+    SkipFile,
+    /// Skip the rows that contain error and load the others
+    Continue,
+}
+
+impl OnError {
+    fn parse(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "abort" => Ok(Self::Abort),
+            "skip_file" => Ok(Self::SkipFile),
+            "continue" => Ok(Self::Continue),
+            _ => Err(QueryError::Semantic {
+                err: format!(
+                    "Unknown ON_ERROR: {}, only support ABORT | SKIP_FILE | CONTINUE",
+                    s
+                ),
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct CopyOptions {
     pub auto_infer_schema: bool,
+    pub on_error: OnError,
+    /// Load the files even if they have been loaded
+    pub force: bool,
+    pub rejected_rows_table: Option<String>,
+    /// Roll to a new file when the exported file reaches this size in bytes
+    pub max_file_size: Option<u64>,
 }
 
 #[derive(Default)]
 pub struct CopyOptionsBuilder {
     auto_infer_schema: Option<bool>,
+    on_error: Option<OnError>,
+    force: Option<bool>,
+    rejected_rows_table: Option<String>,
+    max_file_size: Option<u64>,
 }
 
 impl CopyOptionsBuilder {
@@ -498,6 +623,18 @@ impl CopyOptionsBuilder {
                 "auto_infer_schema" => {
                     self.auto_infer_schema = Some(parse_bool_value(value)?);
                 }
+                "on_error" => {
+                    self.on_error = Some(OnError::parse(&parse_string_value(value)?)?);
+                }
+                "force" => {
+                    self.force = Some(parse_bool_value(value)?);
+                }
+                "rejected_rows_table" => {
+                    self.rejected_rows_table = Some(parse_string_value(value)?);
+                }
+                "max_file_size" => {
+                    self.max_file_size = Some(parse_u64_value(value)?);
+                }
                 option => {
                     return Err(QueryError::Semantic {
                         err: format!("Unsupported option [{}]", option),
@@ -513,40 +650,88 @@ impl CopyOptionsBuilder {
     pub fn build(self) -> CopyOptions {
         CopyOptions {
             auto_infer_schema: self.auto_infer_schema.unwrap_or_default(),
+            on_error: self.on_error.unwrap_or_default(),
+            force: self.force.unwrap_or_default(),
+            rejected_rows_table: self.rejected_rows_table,
+            max_file_size: self.max_file_size,
         }
     }
 }
 
+#[derive(Clone)]
 pub struct FileFormatOptions {
     pub file_type: FileType,
+    /// Files of the types datafusion does not know, `file_type` is ignored if set
+    pub ext_file_type: Option<ExtFileType>,
+    /// Files of points which are written the way the write api does,
+    /// `file_type` is ignored if set
+    pub points_format: Option<PointsFormat>,
+    /// Precision of the timestamps in files of line protocol,
+    /// the precision of the database is used if not specified
+    pub precision: Option<Precision>,
     pub delimiter: char,
     pub with_header: bool,
     pub file_compression_type: FileCompressionType,
 }
 
+/// File types read by cnosdb itself
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExtFileType {
+    Orc,
+}
+
+/// Formats of points that can be loaded by COPY INTO table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PointsFormat {
+    /// Lines of the target table are loaded, lines of other measurements are ignored
+    LineProtocol,
+    /// Prometheus text exposition format, all the samples are loaded into the target table,
+    /// the metric name is kept as the `__name__` tag
+    Prometheus,
+}
+
 #[derive(Debug, Default)]
 pub struct FileFormatOptionsBuilder {
     file_type: Option<FileType>,
+    ext_file_type: Option<ExtFileType>,
+    points_format: Option<PointsFormat>,
+    precision: Option<Precision>,
     delimiter: Option<char>,
     with_header: Option<bool>,
     file_compression_type: Option<FileCompressionType>,
 }
 
 impl FileFormatOptionsBuilder {
-    fn parse_file_type(s: &str) -> Result<FileType> {
+    fn parse_file_type(&mut self, s: &str) -> Result<()> {
         let s = s.to_uppercase();
-        match s.as_str() {
-            "AVRO" => Ok(FileType::AVRO),
-            "PARQUET" => Ok(FileType::PARQUET),
-            "CSV" => Ok(FileType::CSV),
-            "JSON" => Ok(FileType::JSON),
-            _ => Err(QueryError::Semantic {
-                err: format!(
-                    "Unknown FileType: {}, only support AVRO | PARQUET | CSV | JSON",
-                    s
-                ),
-            }),
-        }
+        let (file_type, ext_file_type, points_format) = match s.as_str() {
+            "AVRO" => (Some(FileType::AVRO), None, None),
+            "PARQUET" => (Some(FileType::PARQUET), None, None),
+            "CSV" => (Some(FileType::CSV), None, None),
+            "JSON" => (Some(FileType::JSON), None, None),
+            "ORC" => (None, Some(ExtFileType::Orc), None),
+            "LINE_PROTOCOL" => (None, None, Some(PointsFormat::LineProtocol)),
+            "PROMETHEUS" => (None, None, Some(PointsFormat::Prometheus)),
+            _ => {
+                return Err(QueryError::Semantic {
+                    err: format!(
+                        "Unknown FileType: {}, only support AVRO | PARQUET | CSV | JSON | ORC | LINE_PROTOCOL | PROMETHEUS",
+                        s
+                    ),
+                })
+            }
+        };
+        self.file_type = file_type;
+        self.ext_file_type = ext_file_type;
+        self.points_format = points_format;
+
+        Ok(())
+    }
+
+    fn parse_precision(s: &str) -> Result<Precision> {
+        Precision::new(s).ok_or_else(|| QueryError::Semantic {
+            err: format!("Unknown precision: {}, only support MS | US | NS", s),
+        })
     }
 
     fn parse_file_compression_type(s: &str) -> Result<FileCompressionType> {
@@ -570,8 +755,10 @@ impl FileFormatOptionsBuilder {
         for SqlOption { ref name, value } in options {
             match normalize_ident(name).as_str() {
                 "type" => {
-                    let file_type = Self::parse_file_type(&parse_string_value(value)?)?;
-                    self.file_type = Some(file_type);
+                    self.parse_file_type(&parse_string_value(value)?)?;
+                }
+                "precision" => {
+                    self.precision = Some(Self::parse_precision(&parse_string_value(value)?)?);
                 }
                 "delimiter" => {
                     self.delimiter = Some(parse_char_value(value)?);
@@ -599,6 +786,9 @@ impl FileFormatOptionsBuilder {
     pub fn build(self) -> FileFormatOptions {
         FileFormatOptions {
             file_type: self.file_type.unwrap_or(FileType::CSV),
+            ext_file_type: self.ext_file_type,
+            points_format: self.points_format,
+            precision: self.precision,
             delimiter: self.delimiter.unwrap_or(','),
             with_header: self.with_header.unwrap_or(true),
             file_compression_type: self
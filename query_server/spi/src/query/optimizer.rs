@@ -14,4 +14,18 @@ pub trait Optimizer {
         plan: &LogicalPlan,
         session: &SessionCtx,
     ) -> Result<Arc<dyn ExecutionPlan>>;
+
+    /// Only apply the logical optimization rules
+    fn optimize_logical_plan(
+        &self,
+        plan: &LogicalPlan,
+        session: &SessionCtx,
+    ) -> Result<LogicalPlan>;
+
+    /// Create the optimized physical plan of a logical plan that has been optimized
+    async fn create_physical_plan(
+        &self,
+        plan: &LogicalPlan,
+        session: &SessionCtx,
+    ) -> Result<Arc<dyn ExecutionPlan>>;
 }
@@ -0,0 +1,121 @@
+use std::sync::Arc;
+
+use datafusion::arrow::datatypes::SchemaRef;
+use models::schema::ExternalTableSchema;
+use models::time_zone::TimeZone;
+use models::SchemaId;
+use parking_lot::RwLock;
+
+use super::ast::ExtStatement;
+use super::logical_planner::Plan;
+
+pub type PreparedStatementRef = Arc<PreparedStatement>;
+
+/// What the execution of a prepared statement returns
+#[derive(Debug, Clone)]
+pub enum PreparedResult {
+    /// A result set of the schema
+    Rows(SchemaRef),
+    /// The number of affected rows, e.g. INSERT
+    AffectedRows,
+    /// Only known after the execution, e.g. SHOW and DESCRIBE
+    Unknown,
+}
+
+/// The version of a table a prepared plan reads or writes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TableVersion {
+    Tskv {
+        database: String,
+        table: String,
+        schema_id: SchemaId,
+    },
+    /// External tables have no schema id, the whole definition is compared instead
+    External(Arc<ExternalTableSchema>),
+}
+
+#[derive(Clone)]
+pub struct PreparedPlan {
+    pub plan: Plan,
+    /// Types of the parameters `$1`, `$2`... in order
+    pub parameter_schema: SchemaRef,
+    pub result: PreparedResult,
+    /// The parameter rows are written all at once, e.g. `INSERT INTO t VALUES ($1, $2)`,
+    /// otherwise the plan is executed for each row
+    pub bulk_insert: bool,
+    /// Versions of the tables the plan depends on, `None` if the plan can't be reused,
+    /// e.g. the content of system tables is taken when planning
+    pub table_versions: Option<Vec<TableVersion>>,
+}
+
+/// A statement planned once and executed many times with different parameters.
+///
+/// The statement is planned again once any table the plan depends on is altered or dropped.
+pub struct PreparedStatement {
+    statement: ExtStatement,
+    plans: RwLock<PreparedPlans>,
+}
+
+struct PreparedPlans {
+    plan: PreparedPlan,
+    /// The plan optimized by the logical rules and the time zone it's optimized in,
+    /// dropped once the statement is planned again
+    optimized: Option<(TimeZone, PreparedPlan)>,
+}
+
+impl PreparedStatement {
+    pub fn new(statement: ExtStatement, plan: PreparedPlan) -> Self {
+        Self {
+            statement,
+            plans: RwLock::new(PreparedPlans {
+                plan,
+                optimized: None,
+            }),
+        }
+    }
+
+    pub fn statement(&self) -> &ExtStatement {
+        &self.statement
+    }
+
+    pub fn plan(&self) -> PreparedPlan {
+        self.plans.read().plan.clone()
+    }
+
+    pub fn replace_plan(&self, plan: PreparedPlan) {
+        *self.plans.write() = PreparedPlans {
+            plan,
+            optimized: None,
+        };
+    }
+
+    /// The cached optimized plan, `None` if it's not optimized in the time zone yet
+    pub fn optimized_plan(&self, time_zone: &TimeZone) -> Option<PreparedPlan> {
+        match &self.plans.read().optimized {
+            Some((optimized_in, plan)) if optimized_in == time_zone => Some(plan.clone()),
+            _ => None,
+        }
+    }
+
+    /// Cache the optimized plan of `source`, which is ignored if the statement has been
+    /// planned again in the meantime
+    pub fn cache_optimized_plan(
+        &self,
+        source: &PreparedPlan,
+        time_zone: TimeZone,
+        optimized: PreparedPlan,
+    ) {
+        let mut plans = self.plans.write();
+        if plans.plan.table_versions == source.table_versions {
+            plans.optimized = Some((time_zone, optimized));
+        }
+    }
+
+    pub fn parameter_schema(&self) -> SchemaRef {
+        self.plans.read().plan.parameter_schema.clone()
+    }
+
+    pub fn result(&self) -> PreparedResult {
+        self.plans.read().plan.result.clone()
+    }
+}
@@ -1,14 +1,21 @@
+use std::path::PathBuf;
+use std::str::FromStr;
 use std::sync::Arc;
+use std::time::Duration;
 
 use datafusion::execution::context::SessionState;
+use datafusion::execution::disk_manager::DiskManagerConfig;
 use datafusion::execution::memory_pool::MemoryPool;
 use datafusion::execution::runtime_env::{RuntimeConfig, RuntimeEnv};
 use datafusion::prelude::{SessionConfig, SessionContext};
 use models::auth::user::User;
+use models::consistency_level::ConsistencyLevel;
 use models::oid::Oid;
+use models::schema::Precision;
+use models::time_zone::TimeZone;
 
 use crate::service::protocol::Context;
-use crate::Result;
+use crate::{QueryError, Result};
 
 #[derive(Clone)]
 pub struct SessionCtx {
@@ -19,6 +26,8 @@ pub struct SessionCtx {
     tenant_id: Oid,
     tenant: String,
     default_database: String,
+    time_zone: TimeZone,
+    spill_aggregation: bool,
 
     inner: SessionContext,
 }
@@ -43,12 +52,29 @@ impl SessionCtx {
     pub fn user(&self) -> &User {
         &self.user
     }
+
+    pub fn time_zone(&self) -> &TimeZone {
+        &self.time_zone
+    }
+
+    pub fn spill_aggregation(&self) -> bool {
+        self.spill_aggregation
+    }
 }
 
 #[derive(Default)]
-pub struct SessionCtxFactory {}
+pub struct SessionCtxFactory {
+    spill_path: Option<PathBuf>,
+}
 
 impl SessionCtxFactory {
+    /// Operators that support spilling (e.g. sort) write temporary files under `spill_path`
+    /// once their memory pool is exhausted
+    pub fn with_spill_path(mut self, spill_path: impl Into<PathBuf>) -> Self {
+        self.spill_path = Some(spill_path.into());
+        self
+    }
+
     pub fn create_session_ctx(
         &self,
         context: Context,
@@ -58,8 +84,14 @@ impl SessionCtxFactory {
         let ctx = context.session_config().to_owned();
         let mut rt_config = RuntimeConfig::new();
         rt_config.memory_pool = Some(memory_pool);
+        if let Some(spill_path) = &self.spill_path {
+            rt_config = rt_config
+                .with_disk_manager(DiskManagerConfig::new_specified(vec![spill_path.clone()]));
+        }
         let rt = RuntimeEnv::new(rt_config)?;
-        let df_session_state = SessionState::with_config_rt(ctx.inner, Arc::new(rt));
+        // the consistency level is read by the table writers through the session state
+        let df_config = ctx.inner.with_extension(Arc::new(ctx.consistency_level));
+        let df_session_state = SessionState::with_config_rt(df_config, Arc::new(rt));
         let df_session_ctx = SessionContext::with_state(df_session_state);
 
         Ok(SessionCtx {
@@ -67,6 +99,8 @@ impl SessionCtxFactory {
             tenant_id,
             tenant: context.tenant().to_owned(),
             default_database: context.database().to_owned(),
+            time_zone: ctx.time_zone,
+            spill_aggregation: ctx.spill_aggregation,
             inner: df_session_ctx,
         })
     }
@@ -75,6 +109,13 @@ impl SessionCtxFactory {
 #[derive(Clone)]
 pub struct CnosSessionConfig {
     inner: SessionConfig,
+    query_memory_limit: Option<usize>,
+    priority: QueryPriority,
+    query_timeout: Option<Duration>,
+    precision: Option<Precision>,
+    consistency_level: ConsistencyLevel,
+    time_zone: TimeZone,
+    spill_aggregation: bool,
 }
 
 impl Default for CnosSessionConfig {
@@ -82,7 +123,16 @@ impl Default for CnosSessionConfig {
         let inner =
             SessionConfig::default().set_bool("datafusion.optimizer.skip_failed_rules", false);
 
-        Self { inner }
+        Self {
+            inner,
+            query_memory_limit: None,
+            priority: QueryPriority::default(),
+            query_timeout: None,
+            precision: None,
+            consistency_level: ConsistencyLevel::default(),
+            time_zone: TimeZone::default(),
+            spill_aggregation: false,
+        }
     }
 }
 
@@ -97,4 +147,378 @@ impl CnosSessionConfig {
         self.inner = self.inner.with_target_partitions(n);
         self
     }
+
+    /// Customize the maximum memory in bytes that a single query of the session can use
+    pub fn with_query_memory_limit(mut self, limit: usize) -> Self {
+        self.query_memory_limit = Some(limit);
+        self
+    }
+
+    pub fn query_memory_limit(&self) -> Option<usize> {
+        self.query_memory_limit
+    }
+
+    pub fn with_priority(mut self, priority: QueryPriority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn priority(&self) -> QueryPriority {
+        self.priority
+    }
+
+    /// Customize the maximum execution time of a query, the query is canceled after timeout
+    pub fn with_query_timeout(mut self, timeout: Duration) -> Self {
+        self.query_timeout = Some(timeout);
+        self
+    }
+
+    pub fn query_timeout(&self) -> Option<Duration> {
+        self.query_timeout
+    }
+
+    /// Customize the time zone used to align time windows and display timestamps,
+    /// e.g. '+08:00' or 'Asia/Shanghai'
+    pub fn with_time_zone(mut self, time_zone: TimeZone) -> Self {
+        self.time_zone = time_zone;
+        self
+    }
+
+    pub fn time_zone(&self) -> &TimeZone {
+        &self.time_zone
+    }
+
+    /// Customize the maximum number of rows of the record batches produced by operators
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.inner = self.inner.with_batch_size(batch_size);
+        self
+    }
+
+    pub fn batch_size(&self) -> usize {
+        self.inner.batch_size()
+    }
+
+    /// Customize the precision of the timestamps in the query result,
+    /// `None` means the precision of the table is kept
+    pub fn with_precision(mut self, precision: Option<Precision>) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    pub fn precision(&self) -> Option<&Precision> {
+        self.precision.as_ref()
+    }
+
+    /// Customize the consistency level of the data written by `INSERT`
+    pub fn with_consistency_level(mut self, level: ConsistencyLevel) -> Self {
+        self.consistency_level = level;
+        self
+    }
+
+    pub fn consistency_level(&self) -> ConsistencyLevel {
+        self.consistency_level
+    }
+
+    /// Customize whether the aggregations of `GROUP BY` spill to disk once the memory pool
+    /// of the query is exhausted, they are slower than the in-memory hash aggregations
+    pub fn with_spill_aggregation(mut self, spill_aggregation: bool) -> Self {
+        self.spill_aggregation = spill_aggregation;
+        self
+    }
+
+    pub fn spill_aggregation(&self) -> bool {
+        self.spill_aggregation
+    }
+
+    /// Set a session variable from its text form.
+    ///
+    /// [`SessionVariable::Database`] is a property of the [`crate::service::protocol::Context`],
+    /// it is not handled here.
+    pub fn set_variable(self, variable: SessionVariable, value: &str) -> Result<Self> {
+        let value = value.trim();
+        let config = match variable {
+            SessionVariable::TimeZone => self.with_time_zone(
+                value
+                    .parse()
+                    .map_err(|reason| QueryError::InvalidParam { reason })?,
+            ),
+            SessionVariable::QueryTimeout => match parse_number::<u64>(variable, value)? {
+                0 => Self {
+                    query_timeout: None,
+                    ..self
+                },
+                ms => self.with_query_timeout(Duration::from_millis(ms)),
+            },
+            SessionVariable::QueryMemoryLimit => match parse_number::<usize>(variable, value)? {
+                0 => Self {
+                    query_memory_limit: None,
+                    ..self
+                },
+                limit => self.with_query_memory_limit(limit),
+            },
+            SessionVariable::Precision => {
+                let precision = Precision::new(value).ok_or_else(|| QueryError::InvalidParam {
+                    reason: format!("expected precision [ms, us, ns], found {}", value),
+                })?;
+                self.with_precision(Some(precision))
+            }
+            SessionVariable::BatchSize => match parse_number::<usize>(variable, value)? {
+                0 => {
+                    return Err(QueryError::InvalidParam {
+                        reason: "batch_size must be greater than zero".to_string(),
+                    })
+                }
+                n => self.with_batch_size(n),
+            },
+            SessionVariable::Priority => self.with_priority(value.parse()?),
+            SessionVariable::Consistency => self.with_consistency_level(
+                value
+                    .parse()
+                    .map_err(|reason| QueryError::InvalidParam { reason })?,
+            ),
+            SessionVariable::SpillAggregation => {
+                let spill_aggregation =
+                    value
+                        .to_ascii_lowercase()
+                        .parse()
+                        .map_err(|_| QueryError::InvalidParam {
+                            reason: format!(
+                                "expected true or false for {}, found {}",
+                                variable.as_ref(),
+                                value
+                            ),
+                        })?;
+                self.with_spill_aggregation(spill_aggregation)
+            }
+            SessionVariable::Database => self,
+        };
+
+        Ok(config)
+    }
+
+    /// Reset a session variable to its default value
+    pub fn reset_variable(self, variable: SessionVariable) -> Self {
+        let default = Self::default();
+        match variable {
+            SessionVariable::TimeZone => self.with_time_zone(default.time_zone),
+            SessionVariable::QueryTimeout => Self {
+                query_timeout: default.query_timeout,
+                ..self
+            },
+            SessionVariable::QueryMemoryLimit => Self {
+                query_memory_limit: default.query_memory_limit,
+                ..self
+            },
+            SessionVariable::Precision => self.with_precision(default.precision),
+            SessionVariable::BatchSize => self.with_batch_size(default.batch_size()),
+            SessionVariable::Priority => self.with_priority(default.priority),
+            SessionVariable::Consistency => self.with_consistency_level(default.consistency_level),
+            SessionVariable::SpillAggregation => {
+                self.with_spill_aggregation(default.spill_aggregation)
+            }
+            SessionVariable::Database => self,
+        }
+    }
+
+    /// The text form of a session variable, `None` for [`SessionVariable::Database`]
+    pub fn variable(&self, variable: SessionVariable) -> Option<String> {
+        let value = match variable {
+            SessionVariable::TimeZone => self.time_zone.to_string(),
+            SessionVariable::QueryTimeout => self
+                .query_timeout
+                .map(|e| e.as_millis())
+                .unwrap_or_default()
+                .to_string(),
+            SessionVariable::QueryMemoryLimit => {
+                self.query_memory_limit.unwrap_or_default().to_string()
+            }
+            SessionVariable::Precision => self
+                .precision
+                .as_ref()
+                .map(|e| e.to_string().to_ascii_lowercase())
+                .unwrap_or_default(),
+            SessionVariable::BatchSize => self.batch_size().to_string(),
+            SessionVariable::Priority => self.priority.as_ref().to_string(),
+            SessionVariable::Consistency => self.consistency_level.to_string(),
+            SessionVariable::SpillAggregation => self.spill_aggregation.to_string(),
+            SessionVariable::Database => return None,
+        };
+
+        Some(value)
+    }
+}
+
+/// Priority class of queries.
+///
+/// When the concurrency slots of a tenant are exhausted,
+/// queued interactive queries are always executed before batch queries.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum QueryPriority {
+    #[default]
+    Interactive,
+    Batch,
+}
+
+impl AsRef<str> for QueryPriority {
+    fn as_ref(&self) -> &str {
+        match self {
+            Self::Interactive => "interactive",
+            Self::Batch => "batch",
+        }
+    }
+}
+
+impl FromStr for QueryPriority {
+    type Err = QueryError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "interactive" => Ok(Self::Interactive),
+            "batch" => Ok(Self::Batch),
+            _ => Err(QueryError::InvalidParam {
+                reason: format!("expected query priority [interactive, batch], found {}", s),
+            }),
+        }
+    }
+}
+
+/// Variables of a session, set by `SET <variable> = <value>`,
+/// HTTP query parameters or Flight SQL headers of the same name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SessionVariable {
+    TimeZone,
+    QueryTimeout,
+    Precision,
+    Database,
+    BatchSize,
+    Priority,
+    Consistency,
+    QueryMemoryLimit,
+    SpillAggregation,
+}
+
+impl SessionVariable {
+    pub const ALL: [SessionVariable; 9] = [
+        Self::TimeZone,
+        Self::QueryTimeout,
+        Self::Precision,
+        Self::Database,
+        Self::BatchSize,
+        Self::Priority,
+        Self::Consistency,
+        Self::QueryMemoryLimit,
+        Self::SpillAggregation,
+    ];
+
+    pub fn description(&self) -> &str {
+        match self {
+            Self::TimeZone => "time zone of time windows and displayed timestamps, e.g. '+08:00' or 'Asia/Shanghai'",
+            Self::QueryTimeout => "query is canceled after the milliseconds, 0 means no timeout",
+            Self::Precision => "precision of timestamps in the query result, ms, us or ns",
+            Self::Database => "default database of the session",
+            Self::BatchSize => "maximum number of rows of the record batches during execution",
+            Self::Priority => "priority class of queries, interactive or batch",
+            Self::Consistency => "consistency level of INSERT, any, one, quorum or all",
+            Self::QueryMemoryLimit => "maximum bytes of memory a query can use, 0 means only limited by the tenant",
+            Self::SpillAggregation => "whether GROUP BY spills to disk instead of failing once out of memory, it is slower, true or false",
+        }
+    }
+}
+
+impl AsRef<str> for SessionVariable {
+    fn as_ref(&self) -> &str {
+        match self {
+            Self::TimeZone => "time_zone",
+            Self::QueryTimeout => "query_timeout",
+            Self::Precision => "precision",
+            Self::Database => "database",
+            Self::BatchSize => "batch_size",
+            Self::Priority => "priority",
+            Self::Consistency => "consistency",
+            Self::QueryMemoryLimit => "query_memory_limit",
+            Self::SpillAggregation => "spill_aggregation",
+        }
+    }
+}
+
+impl FromStr for SessionVariable {
+    type Err = QueryError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|e| e.as_ref().eq_ignore_ascii_case(s))
+            .ok_or_else(|| QueryError::UnknownSessionVariable {
+                name: s.to_string(),
+            })
+    }
+}
+
+fn parse_number<T: FromStr>(variable: SessionVariable, value: &str) -> Result<T> {
+    value.parse().map_err(|_| QueryError::InvalidParam {
+        reason: format!(
+            "expected a non-negative integer for {}, found {}",
+            variable.as_ref(),
+            value
+        ),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use models::consistency_level::ConsistencyLevel;
+
+    use super::{CnosSessionConfig, QueryPriority, SessionVariable};
+    use crate::QueryError;
+
+    #[test]
+    fn test_set_variable() {
+        let config = CnosSessionConfig::default()
+            .set_variable(SessionVariable::TimeZone, "Asia/Shanghai")
+            .unwrap()
+            .set_variable(SessionVariable::QueryTimeout, "1500")
+            .unwrap()
+            .set_variable(SessionVariable::Priority, "BATCH")
+            .unwrap()
+            .set_variable(SessionVariable::Consistency, "quorum")
+            .unwrap()
+            .set_variable(SessionVariable::Precision, "ms")
+            .unwrap()
+            .set_variable(SessionVariable::QueryMemoryLimit, "1048576")
+            .unwrap()
+            .set_variable(SessionVariable::SpillAggregation, "TRUE")
+            .unwrap();
+
+        assert_eq!(config.time_zone().to_string(), "Asia/Shanghai");
+        assert_eq!(config.query_timeout(), Some(Duration::from_millis(1500)));
+        assert_eq!(config.priority(), QueryPriority::Batch);
+        assert_eq!(config.consistency_level(), ConsistencyLevel::Quorum);
+        assert_eq!(config.query_memory_limit(), Some(1048576));
+        assert!(config.spill_aggregation());
+        assert_eq!(
+            config.variable(SessionVariable::Precision),
+            Some("ms".to_string())
+        );
+
+        let config = config.reset_variable(SessionVariable::TimeZone);
+        assert!(config.time_zone().is_utc());
+        let config = config.reset_variable(SessionVariable::QueryMemoryLimit);
+        assert_eq!(config.query_memory_limit(), None);
+
+        for (variable, value) in [
+            (SessionVariable::TimeZone, "08:00"),
+            (SessionVariable::BatchSize, "0"),
+            (SessionVariable::QueryTimeout, "-1"),
+            (SessionVariable::Consistency, "two"),
+            (SessionVariable::SpillAggregation, "yes"),
+        ] {
+            let err = config.clone().set_variable(variable, value).err().unwrap();
+            assert!(matches!(err, QueryError::InvalidParam { .. }));
+        }
+
+        let err = "unknown".parse::<SessionVariable>().err().unwrap();
+        assert!(matches!(err, QueryError::UnknownSessionVariable { .. }));
+    }
 }
@@ -9,8 +9,9 @@ use models::auth::role::UserRole;
 use models::auth::user::{User, UserDesc, UserInfo, UserOptionsBuilder};
 
 use crate::query::execution::Output;
+use crate::query::prepared_statement::{PreparedStatement, PreparedStatementRef};
 use crate::service::protocol::{Query, QueryHandle, QueryId};
-use crate::Result;
+use crate::{QueryError, Result};
 
 pub type DBMSRef = Arc<dyn DatabaseManagerSystem + Send + Sync>;
 
@@ -18,6 +19,13 @@ pub type DBMSRef = Arc<dyn DatabaseManagerSystem + Send + Sync>;
 pub trait DatabaseManagerSystem {
     async fn authenticate(&self, user_info: &UserInfo, tenant_name: Option<&str>) -> Result<User>;
     async fn execute(&self, query: &Query) -> Result<QueryHandle>;
+    async fn prepare(&self, query: &Query) -> Result<PreparedStatementRef>;
+    async fn execute_prepared(
+        &self,
+        query: &Query,
+        statement: &PreparedStatement,
+        parameters: Vec<RecordBatch>,
+    ) -> Result<QueryHandle>;
     fn metrics(&self) -> String;
     fn cancel(&self, query_id: &QueryId);
 }
@@ -69,6 +77,23 @@ impl DatabaseManagerSystem for DatabaseManagerSystemMock {
         ))
     }
 
+    async fn prepare(&self, query: &Query) -> Result<PreparedStatementRef> {
+        println!("DatabaseManagerSystemMock::prepare({:?})", query.content());
+
+        Err(QueryError::NotImplemented {
+            err: "prepared statement of DatabaseManagerSystemMock".to_string(),
+        })
+    }
+
+    async fn execute_prepared(
+        &self,
+        query: &Query,
+        _statement: &PreparedStatement,
+        _parameters: Vec<RecordBatch>,
+    ) -> Result<QueryHandle> {
+        self.execute(query).await
+    }
+
     fn metrics(&self) -> String {
         "todo!()".to_string()
     }
@@ -1,10 +1,14 @@
+use std::collections::HashMap;
 use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
 
 use models::auth::user::User;
 use models::schema::{DEFAULT_CATALOG, DEFAULT_DATABASE};
+use parking_lot::RwLock;
 
 use crate::query::execution::Output;
-use crate::query::session::CnosSessionConfig;
+use crate::query::session::{CnosSessionConfig, QueryPriority, SessionVariable};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct QueryId(u64);
@@ -49,6 +53,21 @@ impl ToString for QueryId {
     }
 }
 
+/// Session variables changed by `SET` and `RESET`, kept by the server for a session
+pub type SessionVariablesRef = Arc<RwLock<HashMap<SessionVariable, String>>>;
+
+/// Who keeps the session variables changed by `SET` and `RESET` between requests
+#[derive(Clone, Default)]
+pub enum VariableStore {
+    /// Nothing keeps them, `SET` and `RESET` are rejected
+    #[default]
+    Unavailable,
+    /// The client keeps the returned values and sends them with the subsequent requests
+    Client,
+    /// The server keeps them for the session, e.g. the bearer token of Flight SQL
+    Server(SessionVariablesRef),
+}
+
 #[derive(Clone)]
 pub struct Context {
     // todo
@@ -59,6 +78,7 @@ pub struct Context {
     tenant: String,
     database: String,
     session_config: CnosSessionConfig,
+    variable_store: VariableStore,
 }
 
 impl Context {
@@ -77,6 +97,10 @@ impl Context {
     pub fn session_config(&self) -> &CnosSessionConfig {
         &self.session_config
     }
+
+    pub fn variable_store(&self) -> &VariableStore {
+        &self.variable_store
+    }
 }
 
 pub struct ContextBuilder {
@@ -84,6 +108,7 @@ pub struct ContextBuilder {
     tenant: String,
     database: String,
     session_config: CnosSessionConfig,
+    variable_store: VariableStore,
 }
 
 impl ContextBuilder {
@@ -93,6 +118,7 @@ impl ContextBuilder {
             tenant: DEFAULT_CATALOG.to_string(),
             database: DEFAULT_DATABASE.to_string(),
             session_config: Default::default(),
+            variable_store: Default::default(),
         }
     }
 
@@ -119,12 +145,66 @@ impl ContextBuilder {
         self
     }
 
+    pub fn with_query_memory_limit(mut self, limit: Option<usize>) -> Self {
+        if let Some(limit) = limit {
+            self.session_config = self.session_config.with_query_memory_limit(limit);
+        }
+        self
+    }
+
+    pub fn with_priority(mut self, priority: Option<QueryPriority>) -> Self {
+        if let Some(priority) = priority {
+            self.session_config = self.session_config.with_priority(priority);
+        }
+        self
+    }
+
+    pub fn with_query_timeout(mut self, timeout: Option<Duration>) -> Self {
+        if let Some(timeout) = timeout {
+            self.session_config = self.session_config.with_query_timeout(timeout);
+        }
+        self
+    }
+
+    /// Set a session variable from its text form, e.g. a HTTP query parameter or a Flight SQL header
+    pub fn with_session_variable(
+        mut self,
+        variable: SessionVariable,
+        value: Option<&str>,
+    ) -> crate::Result<Self> {
+        let value = match value {
+            Some(value) => value,
+            None => return Ok(self),
+        };
+
+        match variable {
+            SessionVariable::Database => self.database = value.to_string(),
+            _ => self.session_config = self.session_config.set_variable(variable, value)?,
+        }
+
+        Ok(self)
+    }
+
+    /// Keep the variables changed by `SET` and `RESET` in the store,
+    /// the variables already kept by the server are applied to the context
+    pub fn with_variable_store(mut self, variable_store: VariableStore) -> crate::Result<Self> {
+        if let VariableStore::Server(variables) = &variable_store {
+            for (variable, value) in variables.read().iter() {
+                self = self.with_session_variable(*variable, Some(value))?;
+            }
+        }
+        self.variable_store = variable_store;
+
+        Ok(self)
+    }
+
     pub fn build(self) -> Context {
         Context {
             user_info: self.user_info,
             tenant: self.tenant,
             database: self.database,
             session_config: self.session_config,
+            variable_store: self.variable_store,
         }
     }
 }
@@ -38,10 +38,22 @@ COPY INTO inner_csv_v2(time, tag1, tag2, bigint_c, string_c, ubigint_c, boolean_
 FROM 'query_server/test/resource/data_type/csv/full_data_type.csv'
 file_format = (type = 'csv');
 
+-- skipped, the file has been loaded
+COPY INTO inner_csv_v2(time, tag1, tag2, bigint_c, string_c, ubigint_c, boolean_c, double_c)
+FROM 'query_server/test/resource/data_type/csv/full_data_type.csv'
+file_format = (type = 'csv');
+
 -- error
 COPY INTO inner_csv_v2
 FROM 'query_server/test/resource/data_type/csv/full_data_type.csv'
-file_format = (type = 'csv');
+file_format = (type = 'csv')
+copy_options = (force = true);
+
+-- load failed
+COPY INTO inner_csv_v2
+FROM 'query_server/test/resource/data_type/csv/full_data_type.csv'
+file_format = (type = 'csv')
+copy_options = (on_error = 'skip_file', force = true);
 -- csv end
 
 -- parquet start
@@ -76,6 +88,7 @@ copy into inner_parquet
 from 'query_server/test/resource/json/part-0.json' 
 file_format = (type = 'json');
 
+-- skipped, the file has been loaded
 copy into inner_parquet
 from 'query_server/test/resource/parquet/part-0.parquet' 
 file_format = (type = 'parquet');
@@ -96,4 +109,4 @@ copy_options = (auto_infer_schema = true);
 copy into inner_parquet
 from 'query_server/test/resource/parquet/part-0.parquet' 
 file_format = (type = 'parquet')
-copy_options = (auto_infer_schema = true);
+copy_options = (auto_infer_schema = true, force = true);
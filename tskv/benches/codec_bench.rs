@@ -0,0 +1,73 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use models::codec::Encoding;
+use tskv::codec::{get_i64_codec, get_ts_codec};
+
+const VALUES: i64 = 1000;
+
+/// Timestamps of 10s interval with a jitter of up to 3ms every 7 values.
+fn timestamps() -> Vec<i64> {
+    (0..VALUES)
+        .map(|i| 1_600_000_000_000_000_000 + i * 10_000_000_000 + (i % 7) * 500_000)
+        .collect()
+}
+
+/// A metric stays unchanged for long times.
+fn values() -> Vec<i64> {
+    (0..VALUES).map(|i| i / 300).collect()
+}
+
+fn bench_timestamp_codecs(c: &mut Criterion) {
+    let src = timestamps();
+    let mut group = c.benchmark_group("timestamp");
+    for encoding in [Encoding::Delta, Encoding::DeltaOfDelta, Encoding::Quantile] {
+        let codec = get_ts_codec(encoding);
+        let mut encoded = vec![];
+        codec.encode(&src, &mut encoded).unwrap();
+        println!("timestamp {}: {} bytes", encoding.as_str(), encoded.len());
+
+        group.bench_function(BenchmarkId::new("encode", encoding.as_str()), |b| {
+            let mut dst = vec![];
+            b.iter(|| codec.encode(black_box(&src), &mut dst).unwrap())
+        });
+        group.bench_function(BenchmarkId::new("decode", encoding.as_str()), |b| {
+            b.iter(|| {
+                let mut dst = vec![];
+                codec.decode(black_box(&encoded), &mut dst).unwrap();
+                dst
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_integer_codecs(c: &mut Criterion) {
+    let src = values();
+    let mut group = c.benchmark_group("integer");
+    for encoding in [
+        Encoding::Delta,
+        Encoding::Rle,
+        Encoding::Xor,
+        Encoding::Quantile,
+    ] {
+        let codec = get_i64_codec(encoding);
+        let mut encoded = vec![];
+        codec.encode(&src, &mut encoded).unwrap();
+        println!("integer {}: {} bytes", encoding.as_str(), encoded.len());
+
+        group.bench_function(BenchmarkId::new("encode", encoding.as_str()), |b| {
+            let mut dst = vec![];
+            b.iter(|| codec.encode(black_box(&src), &mut dst).unwrap())
+        });
+        group.bench_function(BenchmarkId::new("decode", encoding.as_str()), |b| {
+            b.iter(|| {
+                let mut dst = vec![];
+                codec.decode(black_box(&encoded), &mut dst).unwrap();
+                dst
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_timestamp_codecs, bench_integer_codecs);
+criterion_main!(benches);
@@ -2,17 +2,25 @@ use std::sync::Arc;
 
 use criterion::{criterion_group, criterion_main, Criterion};
 use datafusion::execution::memory_pool::GreedyMemoryPool;
+use datafusion::physical_plan::metrics::ExecutionPlanMetricsSet;
 use meta::meta_manager::RemoteMetaManager;
 use meta::MetaRef;
 use metrics::metric_register::MetricsRegister;
+use models::predicate::domain::Predicate;
+use models::schema::DEFAULT_CATALOG;
 use parking_lot::Mutex;
-use protos::kv_service::WritePointsRequest;
+use protos::kv_service::{Meta, WritePointsRequest};
 use protos::models_helper;
 use tokio::runtime::{self, Runtime};
-use tskv::engine::Engine;
+use tskv::engine::{Engine, EngineRef};
+use tskv::iterator::{QueryOption, RowIterator, TableScanMetrics};
 use tskv::TsKv;
 
 async fn get_tskv() -> TsKv {
+    get_tskv_and_meta().await.0
+}
+
+async fn get_tskv_and_meta() -> (TsKv, MetaRef) {
     let mut global_config = config::get_config("../config/config.toml");
     global_config.wal.path = "/tmp/test_bench/wal".to_string();
     let opt = tskv::kv_option::Options::from(&global_config);
@@ -27,15 +35,17 @@ async fn get_tskv() -> TsKv {
     let meta_manager: MetaRef = RemoteMetaManager::new(global_config.cluster.clone()).await;
     meta_manager.admin_meta().add_data_node().await.unwrap();
     let memory = Arc::new(GreedyMemoryPool::new(1024 * 1024 * 1024));
-    TsKv::open(
-        meta_manager,
+    let tskv = TsKv::open(
+        meta_manager.clone(),
         opt,
         runtime,
         memory,
         Arc::new(MetricsRegister::default()),
     )
     .await
-    .unwrap()
+    .unwrap();
+
+    (tskv, meta_manager)
 }
 
 fn test_write(tskv: Arc<Mutex<TsKv>>, request: WritePointsRequest) {
@@ -81,6 +91,65 @@ fn big_write(c: &mut Criterion) {
     });
 }
 
+async fn scan(engine: EngineRef, option: QueryOption) -> usize {
+    let mut iterator = RowIterator::new(engine, option, 0).await.unwrap();
+    let mut rows = 0;
+    while let Some(batch) = iterator.next().await {
+        rows += batch.unwrap().num_rows();
+    }
+    rows
+}
+
+fn big_scan(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let (tskv, meta) = rt.block_on(get_tskv_and_meta());
+    for _i in 0..100 {
+        let mut fbb = flatbuffers::FlatBufferBuilder::new();
+        let points = models_helper::create_random_points_with_delta(&mut fbb, 1000);
+        fbb.finish(points, None);
+        let points = fbb.finished_data().to_vec();
+
+        let request = WritePointsRequest {
+            version: 1,
+            meta: Some(Meta {
+                tenant: DEFAULT_CATALOG.to_string(),
+                user: None,
+                password: None,
+            }),
+            points,
+        };
+        rt.block_on(tskv.write(0, request)).unwrap();
+    }
+
+    let table_schema = rt
+        .block_on(meta.tenant_manager().tenant_meta(DEFAULT_CATALOG))
+        .unwrap()
+        .get_tskv_table_schema("db", "table")
+        .unwrap()
+        .unwrap();
+    let df_schema = table_schema.to_arrow_schema();
+    let engine: EngineRef = Arc::new(tskv);
+    let option = |metrics: &ExecutionPlanMetricsSet| {
+        let scan_metrics = TableScanMetrics::new(metrics, 0, None);
+        QueryOption::new(
+            4096,
+            DEFAULT_CATALOG.to_string(),
+            Arc::new(Predicate::default()),
+            None,
+            df_schema.clone(),
+            table_schema.clone(),
+            scan_metrics.tskv_metrics(),
+        )
+    };
+
+    c.bench_function("big_scan", |b| {
+        b.iter(|| {
+            let metrics = ExecutionPlanMetricsSet::new();
+            rt.block_on(scan(engine.clone(), option(&metrics)))
+        })
+    });
+}
+
 #[allow(dead_code)]
 fn run(c: &mut Criterion) {
     let rt = Runtime::new().unwrap();
@@ -107,5 +176,5 @@ fn run(c: &mut Criterion) {
     // });
 }
 
-criterion_group!(benches, big_write);
+criterion_group!(benches, big_write, big_scan);
 criterion_main!(benches);
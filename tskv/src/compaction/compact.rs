@@ -1,8 +1,12 @@
+use std::collections::hash_map::Entry;
 use std::collections::{BinaryHeap, HashMap, VecDeque};
 use std::pin::Pin;
 use std::sync::Arc;
 
-use models::{FieldId, Timestamp};
+use models::codec::{Encoding, FieldIndexType};
+use models::schema::{TableColumn, TskvTableSchema};
+use models::utils::split_id;
+use models::{FieldId, SeriesId, Timestamp, ValueType};
 use snafu::ResultExt;
 use trace::{error, info, trace};
 use utils::BloomFilter;
@@ -11,8 +15,12 @@ use super::iterator::BufferedIterator;
 use crate::compaction::CompactReq;
 use crate::context::GlobalContext;
 use crate::error::{self, Result};
+use crate::file_system::IoPriority;
+use crate::index::ts_index::TSIndex;
+use crate::schema::schemas::DBschemas;
 use crate::summary::{CompactMeta, VersionEdit};
 use crate::tseries_family::TseriesFamily;
+use crate::tsm::codec::{default_encoding, get_encoding, DataBlockEncoding};
 use crate::tsm::{
     self, BlockMeta, BlockMetaIterator, DataBlock, IndexIterator, IndexMeta, TsmReader, TsmWriter,
 };
@@ -89,7 +97,7 @@ impl CompactingBlock {
                 }
                 CompactingBlock::Raw { meta, raw, .. } => {
                     let data_block = tsm::decode_data_block(
-                        &raw,
+                        &raw[..meta.size() as usize],
                         meta.field_type(),
                         meta.val_off() - meta.offset(),
                     )
@@ -171,6 +179,9 @@ pub(crate) struct CompactIterator {
     /// How many finished_idxes is set to true
     finished_reader_cnt: usize,
     curr_fid: Option<FieldId>,
+    /// Field index types of fields in the compacting files, which are kept for
+    /// the blocks of the same fields in generated files if not found in the table schema.
+    field_index_types: HashMap<FieldId, FieldIndexType>,
 
     merged_blocks: VecDeque<CompactingBlock>,
 }
@@ -188,6 +199,7 @@ impl Default for CompactIterator {
             finished_readers: Default::default(),
             finished_reader_cnt: Default::default(),
             curr_fid: Default::default(),
+            field_index_types: Default::default(),
             merged_blocks: Default::default(),
         }
     }
@@ -267,6 +279,9 @@ impl CompactIterator {
         // Get all block_meta, and check if it's tsm file has a related tombstone file.
         for (i, blk_iter) in self.tmp_tsm_blks.iter_mut().enumerate() {
             for blk_meta in blk_iter.by_ref() {
+                if let Some((index_type, _)) = blk_meta.field_index() {
+                    self.field_index_types.insert(field_id, index_type);
+                }
                 let tsm_has_tombstone =
                     self.tsm_readers[self.tmp_tsm_blk_tsm_reader_idx[i]].has_tombstone();
                 sorted_blk_metas.push(CompactingBlockMeta::new(
@@ -447,6 +462,11 @@ impl CompactIterator {
 }
 
 impl CompactIterator {
+    /// Returns the field index type of the field in compacting files.
+    pub(crate) fn field_index_type(&self, field_id: FieldId) -> Option<FieldIndexType> {
+        self.field_index_types.get(&field_id).copied()
+    }
+
     pub(crate) async fn next(&mut self) -> Option<Result<CompactingBlock>> {
         if let Some(blk) = self.merged_blocks.pop_front() {
             return Some(Ok(blk));
@@ -484,6 +504,60 @@ impl CompactIterator {
     }
 }
 
+/// Gets encodings and index types of fields from the current table schemas of a vnode.
+pub struct FieldEncodings {
+    ts_index: Arc<tokio::sync::RwLock<TSIndex>>,
+    schemas: Arc<DBschemas>,
+    /// Table schemas of the series
+    series_tables: HashMap<SeriesId, Option<Arc<TskvTableSchema>>>,
+}
+
+impl FieldEncodings {
+    pub fn new(ts_index: Arc<tokio::sync::RwLock<TSIndex>>, schemas: Arc<DBschemas>) -> Self {
+        Self {
+            ts_index,
+            schemas,
+            series_tables: HashMap::new(),
+        }
+    }
+
+    /// Returns the value encoding of the field, `None` if the table or column is not found.
+    async fn get(&mut self, field_id: FieldId) -> Option<Encoding> {
+        self.column(field_id).await.map(|c| c.encoding)
+    }
+
+    /// Returns the index type of the field, `None` if the table or column is not found.
+    async fn index_type(&mut self, field_id: FieldId) -> Option<Option<FieldIndexType>> {
+        self.column(field_id).await.map(|c| c.index)
+    }
+
+    async fn column(&mut self, field_id: FieldId) -> Option<&TableColumn> {
+        let (column_id, series_id) = split_id(field_id);
+        if let Entry::Vacant(entry) = self.series_tables.entry(series_id) {
+            let series_key = self.ts_index.read().await.get_series_key(series_id);
+            let table = match series_key {
+                Ok(Some(key)) => self.schemas.get_table_schema(key.table()).ok().flatten(),
+                _ => None,
+            };
+            entry.insert(table);
+        }
+        let table = self.series_tables.get(&series_id)?.as_ref()?;
+        table.columns().iter().find(|c| c.id == column_id)
+    }
+}
+
+/// Returns if values encoded in `encoding` needn't to be rewritten in `target` encoding.
+///
+/// The encoding selected by `Encoding::Auto` depends on the values,
+/// so it's not known before the block is decoded.
+fn is_encoded_in(encoding: Encoding, target: Encoding, value_type: ValueType) -> bool {
+    match target {
+        Encoding::Auto => false,
+        Encoding::Default => encoding == default_encoding(value_type),
+        _ => encoding == target,
+    }
+}
+
 /// Returns if r1 (min_ts, max_ts) overlaps r2 (min_ts, max_ts)
 fn overlaps_tuples(r1: (i64, i64), r2: (i64, i64)) -> bool {
     r1.0 <= r2.1 && r1.1 >= r2.0
@@ -532,8 +606,10 @@ pub async fn run_compaction_job(
     }
 
     let mut iter = CompactIterator::new(tsm_readers, max_data_block_size, false);
+    let mut field_encodings = request.field_encodings;
     let tsm_dir = storage_opt.tsm_dir(&request.database, tsf_id);
     let mut tsm_writer = tsm::new_tsm_writer(&tsm_dir, kernel.file_id_next(), false, 0).await?;
+    tsm_writer.set_io_limiter(kernel.io_limiter(), IoPriority::Compaction);
     info!("Compaction: File {} been created.", tsm_writer.sequence());
     let mut version_edit = VersionEdit::new(tsf_id);
     let mut file_metas: HashMap<ColumnFileId, Arc<BloomFilter>> = HashMap::new();
@@ -544,13 +620,64 @@ pub async fn run_compaction_job(
         let write_ret = match blk {
             CompactingBlock::DataBlock {
                 field_id: fid,
-                data_block: b,
+                data_block: mut b,
                 ..
             } => {
-                // TODO: let enc = b.encodings();
-                tsm_writer.write_block(fid, &b).await
+                let mut index_type = iter.field_index_type(fid);
+                if let Some(encoding) = field_encodings.as_mut() {
+                    if let Some(val_enc) = encoding.get(fid).await {
+                        let (ts_enc, _) = b.encodings().split();
+                        b.set_encodings(DataBlockEncoding::new(ts_enc, val_enc));
+                    }
+                    // The index in the schema may be added or dropped after the files are written.
+                    if let Some(schema_index_type) = encoding.index_type(fid).await {
+                        index_type = schema_index_type;
+                    }
+                }
+                tsm_writer.write_block_with_index(fid, &b, index_type).await
+            }
+            CompactingBlock::Raw { meta, raw, .. } => {
+                let val_off = (meta.val_off() - meta.offset()) as usize;
+                let (target_enc, target_index_type) = match field_encodings.as_mut() {
+                    Some(encodings) => (
+                        encodings.get(meta.field_id()).await,
+                        encodings.index_type(meta.field_id()).await,
+                    ),
+                    None => (None, None),
+                };
+                let encoding = get_encoding(&raw[val_off + 4..]);
+                let file_index_type = meta.field_index().map(|(index_type, _)| index_type);
+                let index_type = target_index_type.unwrap_or(file_index_type);
+                // Rewrite the block encoded in the encoding or indexed by the index before altered.
+                let reencode = matches!(
+                    target_enc,
+                    Some(val_enc) if !is_encoded_in(encoding, val_enc, meta.field_type())
+                );
+                if reencode || index_type != file_index_type {
+                    let mut b = tsm::decode_data_block(
+                        &raw[..meta.size() as usize],
+                        meta.field_type(),
+                        val_off as u64,
+                    )
+                    .context(error::ReadTsmSnafu)?;
+                    let val_enc = match target_enc {
+                        Some(Encoding::Auto) if reencode => b.select_value_encoding(),
+                        Some(val_enc) if reencode => val_enc,
+                        _ => encoding,
+                    };
+                    if val_enc == encoding && index_type == file_index_type {
+                        tsm_writer.write_raw(&meta, &raw).await
+                    } else {
+                        let (ts_enc, _) = b.encodings().split();
+                        b.set_encodings(DataBlockEncoding::new(ts_enc, val_enc));
+                        tsm_writer
+                            .write_block_with_index(meta.field_id(), &b, index_type)
+                            .await
+                    }
+                } else {
+                    tsm_writer.write_raw(&meta, &raw).await
+                }
             }
-            CompactingBlock::Raw { meta, raw, .. } => tsm_writer.write_raw(&meta, &raw).await,
         };
         if let Err(e) = write_ret {
             match e {
@@ -578,6 +705,7 @@ pub async fn run_compaction_job(
                     version_edit.add_file(cm, version.max_level_ts);
                     tsm_writer =
                         tsm::new_tsm_writer(&tsm_dir, kernel.file_id_next(), false, 0).await?;
+                    tsm_writer.set_io_limiter(kernel.io_limiter(), IoPriority::Compaction);
                     info!("Compaction: File {} been created.", tsm_writer.sequence());
                 }
                 tsm::WriteTsmError::Finished { path } => {
@@ -769,6 +897,7 @@ pub mod test {
             files,
             version,
             out_level: 2,
+            field_encodings: None,
         };
         let kernel = Arc::new(GlobalContext::new());
         kernel.set_file_id(next_file_id);
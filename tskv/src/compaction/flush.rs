@@ -3,7 +3,7 @@ use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Duration;
 
-use models::codec::Encoding;
+use models::codec::{Encoding, FieldIndexType};
 use models::schema::TskvTableSchema;
 use models::utils::split_id;
 use models::{utils as model_utils, ColumnId, FieldId, SeriesId, Timestamp, ValueType};
@@ -18,6 +18,7 @@ use utils::BloomFilter;
 use crate::compaction::{CompactTask, FlushReq};
 use crate::context::GlobalContext;
 use crate::error::{self, Result};
+use crate::file_system::IoPriority;
 use crate::memcache::{FieldVal, MemCache, SeriesData};
 use crate::summary::{CompactMeta, CompactMetaBuilder, SummaryTask, VersionEdit};
 use crate::tseries_family::Version;
@@ -132,6 +133,7 @@ impl FlushTask {
 
         for (sid, series_datas) in caches_data.iter_mut() {
             let mut field_id_code_type_map = HashMap::new();
+            let mut field_id_index_type_map = HashMap::new();
             let mut schema_columns_value_type_map: HashMap<ColumnId, ValueType> = HashMap::new();
             let mut column_values_map: HashMap<ColumnId, Vec<(Timestamp, FieldVal)>> =
                 HashMap::new();
@@ -141,6 +143,7 @@ impl FlushTask {
                 // Iterates SeriesData -> [ RowGroups{ schema_id, schema, [ RowData ] } ]
                 for (_sch_id, sch_cols, rows) in series_data.read().flat_groups() {
                     self.build_codec_map(sch_cols.clone(), &mut field_id_code_type_map);
+                    self.build_index_map(sch_cols.clone(), &mut field_id_index_type_map);
                     // Iterates [ RowData ]
                     for row in rows.iter() {
                         // Iterates RowData -> [ Option<FieldVal>, column_id ]
@@ -178,6 +181,7 @@ impl FlushTask {
                         .copied()
                         .unwrap_or_default(),
                 );
+                let index_type = field_id_index_type_map.get(&table_field_id).copied();
 
                 if !dlt_blks.is_empty() {
                     if delta_writer.is_none() {
@@ -189,7 +193,7 @@ impl FlushTask {
                     for mut data_block in dlt_blks {
                         data_block.set_encodings(encoding);
                         writer
-                            .write_block(field_id, &data_block)
+                            .write_block_with_index(field_id, &data_block, index_type)
                             .await
                             .context(error::WriteTsmSnafu)?;
                     }
@@ -204,7 +208,7 @@ impl FlushTask {
                     for mut data_block in tsm_blks {
                         data_block.set_encodings(encoding);
                         writer
-                            .write_block(field_id, &data_block)
+                            .write_block_with_index(field_id, &data_block, index_type)
                             .await
                             .context(error::WriteTsmSnafu)?;
                     }
@@ -222,6 +226,18 @@ impl FlushTask {
         }
     }
 
+    fn build_index_map(
+        &self,
+        schema: Arc<TskvTableSchema>,
+        map: &mut HashMap<ColumnId, FieldIndexType>,
+    ) {
+        for i in schema.columns().iter() {
+            if let Some(index_type) = i.index {
+                map.insert(i.id, index_type);
+            }
+        }
+    }
+
     /// For the collected data, sort and dedup by timestamp, and then split by max_level_ts.
     /// Returns [ ( FieldId, Delta_DataBlocks, Tsm_DataBlocks) ]
     fn merge_series_data(
@@ -280,7 +296,10 @@ impl FlushTask {
         } else {
             &self.path_tsm
         };
-        tsm::new_tsm_writer(dir, self.global_context.file_id_next(), is_delta, 0).await
+        let mut writer =
+            tsm::new_tsm_writer(dir, self.global_context.file_id_next(), is_delta, 0).await?;
+        writer.set_io_limiter(self.global_context.io_limiter(), IoPriority::Flush);
+        Ok(writer)
     }
 
     /// Flush writers (if they exists) and then generate (`CompactMeta`, `Arc<BloomFilter>`)s
@@ -458,6 +477,7 @@ pub mod flush_tests {
                 name: i.to_string(),
                 column_type: ColumnType::Field(ValueType::Unknown),
                 encoding: Encoding::Default,
+                index: None,
             })
             .collect();
 
@@ -608,13 +628,13 @@ pub mod flush_tests {
         let (mut tsm_reader, mut dlt_reader) = (None, None);
         for cm in ve.add_files.iter() {
             if cm.is_delta {
-                assert_eq!(cm.file_size, 377);
+                assert_eq!(cm.file_size, 577);
                 assert_eq!(cm.min_ts, 1);
                 assert_eq!(cm.max_ts, 10);
                 let file_path = file_utils::make_delta_file_name(&delta_dir, cm.file_id);
                 dlt_reader = Some(TsmReader::open(file_path).await.unwrap())
             } else {
-                assert_eq!(cm.file_size, 366);
+                assert_eq!(cm.file_size, 566);
                 assert_eq!(cm.min_ts, 11);
                 assert_eq!(cm.max_ts, 18);
                 let file_path = file_utils::make_tsm_file_name(&tsm_dir, cm.file_id);
@@ -7,7 +7,7 @@ use tokio::task::JoinHandle;
 use tokio::time::Instant;
 use trace::{error, info};
 
-use crate::compaction::{flush, CompactTask, LevelCompactionPicker, Picker};
+use crate::compaction::{compaction_picker, flush, CompactTask};
 use crate::context::GlobalContext;
 use crate::kv_option::StorageOptions;
 use crate::summary::SummaryTask;
@@ -43,10 +43,21 @@ pub fn run(
                 info!("Starting compaction on ts_family {}", vnode_id);
                 let start = Instant::now();
 
-                let picker = LevelCompactionPicker::new(storage_opt.clone());
                 let version = tsf.read().await.version();
+                let db_options = version_set
+                    .read()
+                    .await
+                    .get_database_options(&version.database)
+                    .await
+                    .unwrap_or_default();
+                let picker = compaction_picker(storage_opt.clone(), &db_options);
                 let compact_req = picker.pick_compaction(version);
-                if let Some(req) = compact_req {
+                if let Some(mut req) = compact_req {
+                    req.field_encodings = version_set
+                        .read()
+                        .await
+                        .get_field_encodings(&req.database, req.ts_family_id)
+                        .await;
                     let database = req.database.clone();
                     let compact_ts_family = req.ts_family_id;
                     let out_level = req.out_level;
@@ -30,6 +30,9 @@ pub struct CompactReq {
     files: Vec<Arc<ColumnFile>>,
     version: Arc<Version>,
     pub out_level: LevelId,
+    /// Encodings of fields in the current table schemas, blocks in other encodings
+    /// are rewritten. Blocks are written in the original encodings if it's `None`.
+    pub field_encodings: Option<FieldEncodings>,
 }
 
 #[derive(Debug)]
@@ -1,8 +1,11 @@
 use std::cmp::Ordering;
+use std::collections::BTreeSet;
 use std::fmt::Debug;
 use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 
+use models::schema::{CompactionStrategy, DatabaseOptions};
+use models::utils::now_timestamp;
 use models::Timestamp;
 use trace::{debug, error, info};
 
@@ -150,6 +153,7 @@ impl Picker for LevelCompactionPicker {
             files: picking_files,
             version: version.clone(),
             out_level,
+            field_encodings: None,
         })
     }
 }
@@ -290,6 +294,154 @@ impl LevelCompactionPicker {
     }
 }
 
+/// Returns the compaction picker of the strategy in database options.
+pub fn compaction_picker(
+    storage_opt: Arc<StorageOptions>,
+    db_options: &DatabaseOptions,
+) -> Box<dyn Picker> {
+    match db_options.compaction_or_default() {
+        CompactionStrategy::Level => Box::new(LevelCompactionPicker::new(storage_opt)),
+        CompactionStrategy::TimeWindow => Box::new(TimeWindowCompactionPicker::new(
+            storage_opt,
+            db_options.compaction_window_or_default().to_nanoseconds(),
+            db_options
+                .cold_duration()
+                .as_ref()
+                .map(|d| d.to_nanoseconds()),
+        )),
+    }
+}
+
+/// Compaction picker for picking files in the same time window.
+///
+/// Files are grouped by the windows aligned to `window` that their time ranges overlap,
+/// a file spanning several windows belongs to all of them. Only files of a window are
+/// compacted together, so data of old windows is not rewritten by out-of-order writes
+/// of the others. Windows ended before `now - cold_duration` are frozen, files starting
+/// in them are never compacted.
+#[derive(Debug)]
+pub struct TimeWindowCompactionPicker {
+    storage: Arc<StorageOptions>,
+    /// Duration of a window in nanoseconds.
+    window: i64,
+    /// Duration in nanoseconds for windows to be cold, windows are not frozen if it's `None`.
+    cold_duration: Option<i64>,
+}
+
+impl Picker for TimeWindowCompactionPicker {
+    fn pick_compaction(&self, version: Arc<Version>) -> Option<CompactReq> {
+        //! 1. Group files of all levels by the windows they overlap, ignore windows that have
+        //!    files in compaction and the files starting in frozen windows.
+        //! 2. Pick the window with the most files, newer window first if the numbers are equal,
+        //!    the number of files must reach compact_trigger_file_num.
+        //! 3. Pick files of the window sorted by min_ts until picking_files_size reaches
+        //!    max_compact_size, compact to the max level of the picked files (at least level 1).
+
+        let frozen_before = self
+            .cold_duration
+            .map(|d| now_timestamp().saturating_sub(d))
+            .unwrap_or(Timestamp::MIN);
+        let all_files: Vec<&Arc<ColumnFile>> = version
+            .levels_info()
+            .iter()
+            .flat_map(|lvl| lvl.files.iter())
+            .collect();
+        // Files of a window between the windows containing the bounds of files are also in the
+        // next one of such windows, so only these windows are checked.
+        let windows: BTreeSet<Timestamp> = all_files
+            .iter()
+            .flat_map(|f| {
+                let time_range = f.time_range();
+                [
+                    self.window_start(time_range.min_ts),
+                    self.window_start(time_range.max_ts),
+                ]
+            })
+            .filter(|start| start.saturating_add(self.window) > frozen_before)
+            .collect();
+
+        let trigger_file_num = (self.storage.compact_trigger_file_num as usize).max(2);
+        let (window_start, mut files) = windows
+            .into_iter()
+            .filter_map(|start| {
+                let window = TimeRange::new(start, start.saturating_add(self.window - 1));
+                let mut files = Vec::new();
+                for file in all_files.iter() {
+                    let time_range = file.time_range();
+                    if !time_range.overlaps(&window) {
+                        continue;
+                    }
+                    if file.is_compacting() {
+                        return None;
+                    }
+                    if self
+                        .window_start(time_range.min_ts)
+                        .saturating_add(self.window)
+                        > frozen_before
+                    {
+                        files.push(Arc::clone(file));
+                    }
+                }
+                Some((start, files))
+            })
+            .filter(|(_, files)| files.len() >= trigger_file_num)
+            // The last one of the windows with max files is the newest.
+            .max_by_key(|(_, files)| files.len())?;
+        info!(
+            "Picker: picked time window: {}-{}, files: {}",
+            window_start,
+            window_start.saturating_add(self.window),
+            files.len()
+        );
+
+        files.sort_by(LevelCompactionPicker::compare_column_file);
+        let mut picking_files: Vec<Arc<ColumnFile>> = Vec::new();
+        let mut picking_files_size = 0_u64;
+        for file in files {
+            picking_files_size += file.size();
+            if picking_files_size > self.storage.max_compact_size && picking_files.len() > 1 {
+                break;
+            }
+            picking_files.push(file);
+        }
+        if picking_files.len() <= 1 {
+            info!("Picker: picked files: None");
+            return None;
+        }
+        picking_files.iter().for_each(|f| f.mark_compacting());
+        let out_level = picking_files
+            .iter()
+            .map(|f| f.level())
+            .max()
+            .unwrap_or(0)
+            .max(1);
+
+        Some(CompactReq {
+            ts_family_id: version.ts_family_id,
+            database: version.database.clone(),
+            storage_opt: version.storage_opt.clone(),
+            files: picking_files,
+            version: version.clone(),
+            out_level,
+            field_encodings: None,
+        })
+    }
+}
+
+impl TimeWindowCompactionPicker {
+    pub fn new(storage_opt: Arc<StorageOptions>, window: i64, cold_duration: Option<i64>) -> Self {
+        Self {
+            storage: storage_opt,
+            window: window.max(1),
+            cold_duration,
+        }
+    }
+
+    fn window_start(&self, ts: Timestamp) -> Timestamp {
+        ts - ts.rem_euclid(self.window)
+    }
+}
+
 #[derive(Default)]
 struct LevelCompatContext {
     level_scores: Vec<(u32, f64)>,
@@ -408,12 +560,13 @@ mod test {
     use tokio::sync::mpsc;
 
     use crate::compaction::test::create_options;
-    use crate::compaction::{LevelCompactionPicker, Picker};
+    use crate::compaction::{LevelCompactionPicker, Picker, TimeWindowCompactionPicker};
     use crate::file_utils::make_tsm_file_name;
     use crate::kv_option::Options;
     use crate::kvcore::COMPACT_REQ_CHANNEL_CAP;
     use crate::memcache::MemCache;
     use crate::tseries_family::{ColumnFile, LevelInfo, TseriesFamily, Version};
+    use crate::tsm::BlockCache;
     use crate::TimeRange;
 
     type ColumnFilesSketch = (u64, i64, i64, u64, bool);
@@ -494,6 +647,7 @@ mod test {
             flush_task_sender,
             compactt_task_sender,
             memory_pool,
+            Arc::new(BlockCache::new(0)),
             &Arc::new(MetricsRegister::default()),
         )
     }
@@ -540,4 +694,55 @@ mod test {
         assert_eq!(compact_req.out_level, 2);
         assert_eq!(compact_req.files.len(), 2);
     }
+
+    #[test]
+    fn test_pick_time_window() {
+        //! Windows are 1000 long, window 1000-2000 has file in compaction,
+        //! window 2000-3000 has the most files including the ones spanning other windows,
+        //! and will be compacted to Level 2.
+        let dir = "/tmp/test/pick/time_window";
+        let opt = create_options(dir.to_string());
+
+        #[rustfmt::skip]
+            let levels_sketch: LevelsSketch = vec![
+            // vec![( level, Timestamp_Begin, Timestamp_end, vec![(file_id, Timestamp_Begin, Timestamp_end, size, being_compact)] )]
+            (0_u32, 1_i64, 3000_i64, vec![
+                (11_u64, 1_i64, 999_i64, 1000_u64, false),
+                (12, 1000, 1500, 1000, false),
+                (13, 2000, 2500, 1000, false),
+                (14, 2100, 2200, 1000, false),
+                (15, 2100, 3500, 1000, false),
+            ]),
+            (1, 1, 3000, vec![
+                (7, 1, 999, 1000, false),
+                (8, 1, 999, 1000, false),
+                (9, 1001, 1999, 1000, false),
+                (10, 1001, 1999, 1000, false),
+                (16, 1001, 1999, 1000, true),
+                (17, 2001, 2999, 1000, false),
+                (18, 500, 2500, 1000, false),
+            ]),
+            (2, 1, 3000, vec![
+                (5, 1, 999, 2000, false),
+                (6, 2001, 2999, 2000, false),
+            ]),
+        ];
+
+        let storage_opt = opt.storage.clone();
+        let tsf = create_tseries_family(Arc::new("dba".to_string()), opt, levels_sketch);
+
+        // All windows are frozen.
+        let picker = TimeWindowCompactionPicker::new(storage_opt.clone(), 1000, Some(0));
+        assert!(picker.pick_compaction(tsf.version()).is_none());
+
+        let picker = TimeWindowCompactionPicker::new(storage_opt, 1000, None);
+        let compact_req = picker.pick_compaction(tsf.version()).unwrap();
+        assert_eq!(compact_req.out_level, 2);
+        let mut file_ids: Vec<u64> = compact_req.files.iter().map(|f| f.file_id()).collect();
+        file_ids.sort();
+        assert_eq!(file_ids, vec![6, 13, 14, 15, 17, 18]);
+
+        // Window 0-1000 has the most files left, but file 18 spanning it is in compaction.
+        assert!(picker.pick_compaction(tsf.version()).is_none());
+    }
 }
@@ -0,0 +1,419 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use models::{utils as model_utils, ColumnId, FieldId, SeriesId, Timestamp};
+use trace::trace;
+
+use crate::tseries_family::SuperVersion;
+use crate::tsm::{BlockMeta, BlockStatistics, DataBlock, StatValue, TsmReader, VnodeBlockCache};
+use crate::{Error, Result, TimeRange};
+
+/// Aggregated values of a field.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ValueStatistics {
+    pub count: u64,
+    pub min: Option<StatValue>,
+    pub max: Option<StatValue>,
+    /// `None` if there is no value or the values are booleans.
+    pub sum: Option<StatValue>,
+    /// Sum of the values as `f64`, integers are converted before added
+    /// so that it never overflows, `None` like `sum`.
+    pub float_sum: Option<f64>,
+}
+
+impl ValueStatistics {
+    /// Returns `None` if the sum of integers in the block may have wrapped around,
+    /// then the block must be decoded to get the sum as `f64`.
+    fn from_block_statistics(block_meta: &BlockMeta, statistics: &BlockStatistics) -> Option<Self> {
+        let count = block_meta.count() as u64;
+        let float_sum = match (statistics.min, statistics.max, statistics.sum) {
+            (StatValue::I64(min), StatValue::I64(max), Some(sum)) => {
+                let bound = min.unsigned_abs().max(max.unsigned_abs()) as u128;
+                if bound * count as u128 > i64::MAX as u128 {
+                    return None;
+                }
+                sum.as_f64()
+            }
+            (_, StatValue::U64(max), Some(sum)) => {
+                if max as u128 * count as u128 > u64::MAX as u128 {
+                    return None;
+                }
+                sum.as_f64()
+            }
+            (_, _, sum) => sum.and_then(StatValue::as_f64),
+        };
+
+        Some(Self {
+            count,
+            min: Some(statistics.min),
+            max: Some(statistics.max),
+            sum: statistics.sum,
+            float_sum,
+        })
+    }
+
+    pub fn update(&mut self, value: StatValue) {
+        let sum = match value {
+            StatValue::Bool(_) => None,
+            _ => Some(value),
+        };
+        self.merge(&Self {
+            count: 1,
+            min: Some(value),
+            max: Some(value),
+            sum,
+            float_sum: value.as_f64(),
+        });
+    }
+
+    pub fn merge(&mut self, other: &Self) {
+        if other.count == 0 {
+            return;
+        }
+        if self.count == 0 {
+            *self = other.clone();
+            return;
+        }
+
+        self.count += other.count;
+        self.min = merge_option(self.min, other.min, StatValue::min);
+        self.max = merge_option(self.max, other.max, StatValue::max);
+        self.sum = match (self.sum, other.sum) {
+            (Some(a), Some(b)) => a.wrapping_add(b),
+            _ => None,
+        };
+        self.float_sum = match (self.float_sum, other.float_sum) {
+            (Some(a), Some(b)) => Some(a + b),
+            _ => None,
+        };
+    }
+}
+
+fn merge_option<T>(a: Option<T>, b: Option<T>, f: impl FnOnce(T, T) -> T) -> Option<T> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(f(a, b)),
+        (a, None) => a,
+        (None, b) => b,
+    }
+}
+
+/// Compute pushed down aggregate:
+///
+/// `SELECT min(<field>), max(<field>), sum(<field>) FROM <table> WHERE <time_range_predicates>`
+///
+/// Statistics of a block are used if it's included by the time ranges,
+/// not overlapped with other blocks or cached data and has no tombstones,
+/// otherwise the block is decoded.
+pub async fn aggregate_field_values(
+    super_version: Arc<SuperVersion>,
+    series_ids: &[SeriesId],
+    column_id: ColumnId,
+    time_ranges: Arc<Vec<TimeRange>>,
+) -> Result<ValueStatistics> {
+    trace!("Selecting statistics for column: {}", column_id);
+
+    let mut jh_vec = Vec::with_capacity(series_ids.len());
+    for series_id in series_ids {
+        let field_id = model_utils::unite_id(column_id, *series_id);
+        let sv_inner = super_version.clone();
+        let trs_inner = time_ranges.clone();
+
+        jh_vec.push(tokio::spawn(async move {
+            aggregate_field_values_inner(sv_inner, field_id, trs_inner).await
+        }));
+    }
+
+    let mut statistics = ValueStatistics::default();
+    for jh in jh_vec {
+        // JoinHandle returns JoinError if task was paniced.
+        statistics.merge(&jh.await.map_err(|e| Error::IO { source: e.into() })??);
+    }
+
+    Ok(statistics)
+}
+
+struct ReadTask {
+    tsm_reader: Arc<TsmReader>,
+    block_meta: BlockMeta,
+    time_range: TimeRange,
+    /// Is time_range included by a time range predicate.
+    time_range_included: bool,
+    /// Data of files with greater precedence overwrites the others.
+    precedence: usize,
+}
+
+/// Get aggregated values in time ranges of a field.
+async fn aggregate_field_values_inner(
+    super_version: Arc<SuperVersion>,
+    field_id: FieldId,
+    time_ranges: Arc<Vec<TimeRange>>,
+) -> Result<ValueStatistics> {
+    let time_predicate = |ts| {
+        time_ranges
+            .iter()
+            .any(|tr| tr.is_boundless() || tr.contains(ts))
+    };
+
+    // Cached data overwrites data in files, the later written data of a timestamp is kept.
+    let mut cached_values: BTreeMap<Timestamp, StatValue> = BTreeMap::new();
+    super_version.caches.read_field_data(
+        field_id,
+        time_predicate,
+        |_| true,
+        |d| {
+            if let Some((ts, value)) = StatValue::from_data(&d) {
+                cached_values.insert(ts, value);
+            }
+        },
+    );
+    let cached_time_range = match (
+        cached_values.first_key_value(),
+        cached_values.last_key_value(),
+    ) {
+        (Some((min_ts, _)), Some((max_ts, _))) => TimeRange::new(*min_ts, *max_ts),
+        _ => TimeRange::new(i64::MAX, i64::MIN),
+    };
+
+    let read_tasks = create_file_read_tasks(&super_version, field_id, &time_ranges).await?;
+
+    let mut statistics = ValueStatistics::default();
+    let mut grouped_tr = TimeRange::new(i64::MAX, i64::MIN);
+    let mut grouped_tasks: Vec<ReadTask> = Vec::new();
+    for read_task in read_tasks {
+        if grouped_tasks.is_empty() || grouped_tr.overlaps(&read_task.time_range) {
+            grouped_tr.merge(&read_task.time_range);
+        } else {
+            grouped_tr = read_task.time_range;
+            let tasks = std::mem::take(&mut grouped_tasks);
+            aggregate_grouped_blocks(
+                &super_version.block_cache,
+                &mut statistics,
+                tasks,
+                &time_ranges,
+                &cached_values,
+                &cached_time_range,
+            )
+            .await?;
+        }
+        grouped_tasks.push(read_task);
+    }
+    if !grouped_tasks.is_empty() {
+        aggregate_grouped_blocks(
+            &super_version.block_cache,
+            &mut statistics,
+            grouped_tasks,
+            &time_ranges,
+            &cached_values,
+            &cached_time_range,
+        )
+        .await?;
+    }
+
+    for value in cached_values.into_values() {
+        statistics.update(value);
+    }
+
+    Ok(statistics)
+}
+
+/// Filter block metas of a field in files by time ranges, and then create file read tasks
+/// sorted by time range.
+async fn create_file_read_tasks(
+    super_version: &SuperVersion,
+    field_id: FieldId,
+    time_ranges: &[TimeRange],
+) -> Result<Vec<ReadTask>> {
+    let mut read_tasks: Vec<ReadTask> = Vec::new();
+
+    // Files of lower levels have newer data.
+    let mut precedence = 0_usize;
+    for level in super_version.version.levels_info.iter().rev() {
+        for file in level.files.iter() {
+            if file.is_deleted()
+                || !file.contains_field_id(field_id)
+                || !time_ranges.iter().any(|tr| file.overlap(tr))
+            {
+                continue;
+            }
+            precedence += 1;
+
+            let reader = super_version
+                .version
+                .get_tsm_reader(file.file_path())
+                .await?;
+            for idx in reader.index_iterator_opt(field_id) {
+                for block_meta in idx.block_iterator() {
+                    let blk_tr = block_meta.time_range();
+                    let included = time_ranges.iter().any(|tr| tr.includes(&blk_tr));
+                    if included || time_ranges.iter().any(|tr| tr.overlaps(&blk_tr)) {
+                        read_tasks.push(ReadTask {
+                            tsm_reader: reader.clone(),
+                            block_meta,
+                            time_range: blk_tr,
+                            time_range_included: included,
+                            precedence,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    read_tasks.sort_by(|a, b| a.time_range.cmp(&b.time_range));
+    Ok(read_tasks)
+}
+
+/// Aggregate values of grouped read tasks whose time ranges are overlapped.
+async fn aggregate_grouped_blocks(
+    block_cache: &VnodeBlockCache,
+    statistics: &mut ValueStatistics,
+    mut read_tasks: Vec<ReadTask>,
+    time_ranges: &[TimeRange],
+    cached_values: &BTreeMap<Timestamp, StatValue>,
+    cached_time_range: &TimeRange,
+) -> Result<()> {
+    if let [read_task] = read_tasks.as_slice() {
+        if read_task.time_range_included
+            && !cached_time_range.overlaps(&read_task.time_range)
+            && read_task
+                .tsm_reader
+                .get_block_tombstone_time_ranges(&read_task.block_meta)
+                .is_none()
+        {
+            if let Some(block_statistics) = read_task
+                .block_meta
+                .statistics()
+                .and_then(|s| ValueStatistics::from_block_statistics(&read_task.block_meta, &s))
+            {
+                trace!("Use statistics of block: {}", &read_task.time_range);
+                statistics.merge(&block_statistics);
+                return Ok(());
+            }
+        }
+    }
+
+    trace!("Decode {} blocks", read_tasks.len());
+    read_tasks.sort_by_key(|t| t.precedence);
+    let mut blocks = Vec::with_capacity(read_tasks.len());
+    for read_task in read_tasks {
+        blocks.push(
+            read_task
+                .tsm_reader
+                .get_cached_data_block(&read_task.block_meta, block_cache)
+                .await?,
+        );
+    }
+    if let Some(block) = DataBlock::merge_sorted(&blocks)? {
+        for i in 0..block.len() {
+            let (ts, value) = match block.get(i).as_ref().and_then(StatValue::from_data) {
+                Some(data) => data,
+                None => continue,
+            };
+            if cached_values.contains_key(&ts)
+                || !time_ranges
+                    .iter()
+                    .any(|tr| tr.is_boundless() || tr.contains(ts))
+            {
+                continue;
+            }
+            statistics.update(value);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    use config::get_config;
+    use memory_pool::{GreedyMemoryPool, MemoryPoolRef};
+    use metrics::gauge::U64Gauge;
+    use models::utils as model_utils;
+    use parking_lot::RwLock;
+
+    use super::{aggregate_field_values, ValueStatistics};
+    use crate::compaction::test::write_data_blocks_to_column_file;
+    use crate::memcache::MemCache;
+    use crate::tseries_family::test_tseries_family::build_version_by_column_files;
+    use crate::tseries_family::{CacheGroup, SuperVersion};
+    use crate::tsm::codec::DataBlockEncoding;
+    use crate::tsm::{BlockCache, DataBlock, StatValue};
+    use crate::Options;
+
+    #[tokio::test]
+    async fn test_aggregate_field_values() {
+        let dir = "/tmp/test/ts_family/aggregate_field_values";
+        let mut global_config = get_config("../config/config.toml");
+        global_config.storage.path = dir.to_string();
+
+        #[rustfmt::skip]
+        let data = vec![
+            HashMap::from([
+                (model_utils::unite_id(1, 1), vec![DataBlock::I64 { ts: vec![1, 3, 5], val: vec![10, -3, 7], enc: DataBlockEncoding::default() }]),
+            ]),
+            HashMap::from([
+                (model_utils::unite_id(1, 1), vec![DataBlock::I64 { ts: vec![2, 4, 6], val: vec![4, 20, 1], enc: DataBlockEncoding::default() }]),
+            ]),
+            HashMap::from([
+                (model_utils::unite_id(1, 1), vec![DataBlock::I64 { ts: vec![7, 8, 9], val: vec![2, 9, -5], enc: DataBlockEncoding::default() }]),
+            ]),
+        ];
+
+        let opt = Arc::new(Options::from(&global_config));
+        let database = Arc::new("dba".to_string());
+        let ts_family_id = 1;
+        let dir = opt.storage.tsm_dir(&database, 1);
+
+        let (_, files) =
+            write_data_blocks_to_column_file(&dir, data, ts_family_id, opt.clone()).await;
+        let version =
+            build_version_by_column_files(opt.storage.clone(), database, ts_family_id, files);
+        let pool: MemoryPoolRef = Arc::new(GreedyMemoryPool::new(1024 * 1024 * 1024));
+        let super_version = Arc::new(SuperVersion::new(
+            ts_family_id,
+            opt.storage.clone(),
+            CacheGroup {
+                mut_cache: Arc::new(RwLock::new(MemCache::new(ts_family_id, 1, 1, &pool))),
+                immut_cache: vec![],
+            },
+            Arc::new(version),
+            Arc::new(Arc::new(BlockCache::new(1024 * 1024)).vnode_cache(
+                ts_family_id,
+                &pool,
+                U64Gauge::default(),
+            )),
+            1,
+        ));
+
+        let statistics = |count, min, max, sum| ValueStatistics {
+            count,
+            min: Some(StatValue::I64(min)),
+            max: Some(StatValue::I64(max)),
+            sum: Some(StatValue::I64(sum)),
+            float_sum: Some(sum as f64),
+        };
+
+        #[rustfmt::skip]
+        let _skip_fmt = {
+            assert_eq!(aggregate_field_values(super_version.clone(), &[1], 1, Arc::new(vec![(i64::MIN, i64::MAX).into()])).await.unwrap(), statistics(9, -5, 20, 45));
+            assert_eq!(aggregate_field_values(super_version.clone(), &[1], 1, Arc::new(vec![(2, 8).into()])).await.unwrap(), statistics(7, -3, 20, 40));
+            // The last block is included by the time range, its statistics are used.
+            assert_eq!(aggregate_field_values(super_version.clone(), &[1], 1, Arc::new(vec![(7, 9).into()])).await.unwrap(), statistics(3, -5, 9, 6));
+            assert_eq!(aggregate_field_values(super_version.clone(), &[1], 1, Arc::new(vec![(1, 1).into(), (9, 9).into()])).await.unwrap(), statistics(2, -5, 10, 5));
+            assert_eq!(aggregate_field_values(super_version, &[1], 1, Arc::new(vec![(10, 20).into()])).await.unwrap(), ValueStatistics::default());
+            "skip_fmt"
+        };
+    }
+
+    #[test]
+    fn test_float_sum_of_integers() {
+        let mut statistics = ValueStatistics::default();
+        statistics.update(StatValue::I64(i64::MAX));
+        statistics.update(StatValue::I64(i64::MAX));
+        assert_eq!(statistics.sum, Some(StatValue::I64(-2)));
+        assert_eq!(statistics.float_sum, Some(i64::MAX as f64 * 2.0));
+    }
+}
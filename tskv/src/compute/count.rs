@@ -5,7 +5,7 @@ use models::{utils as model_utils, ColumnId, FieldId, SeriesId, Timestamp};
 use trace::trace;
 
 use crate::tseries_family::{ColumnFile, SuperVersion, TimeRangeCmp};
-use crate::tsm::{BlockMeta, TsmReader};
+use crate::tsm::{BlockMeta, TsmReader, VnodeBlockCache};
 use crate::{Error, Result, TimeRange};
 
 /// Compute pushed down aggregate:
@@ -112,6 +112,7 @@ async fn count_non_null_values_inner<'a>(
                     );
                     // Block overlaps with cached data or conditions, need to decode it to remove duplicates.
                     count += count_non_null_values_in_files(
+                        &super_version.block_cache,
                         reader_blk_metas,
                         sorted_time_ranges.clone(),
                         cached_timestamps.clone(),
@@ -130,6 +131,7 @@ async fn count_non_null_values_inner<'a>(
                 trace!("Length is {} split them", grouped_reader_blk_metas.len());
                 let reader_blk_metas = std::mem::take(&mut grouped_reader_blk_metas);
                 count += count_non_null_values_in_files(
+                    &super_version.block_cache,
                     reader_blk_metas,
                     sorted_time_ranges.clone(),
                     cached_timestamps.clone(),
@@ -143,6 +145,7 @@ async fn count_non_null_values_inner<'a>(
     if !grouped_reader_blk_metas.is_empty() {
         trace!("Calculate the last {}", grouped_reader_blk_metas.len());
         count += count_non_null_values_in_files(
+            &super_version.block_cache,
             grouped_reader_blk_metas,
             sorted_time_ranges.clone(),
             cached_timestamps.clone(),
@@ -284,6 +287,7 @@ async fn create_file_read_tasks<'a>(
 
 /// Get count of non-null values in time ranges from grouped read tasks.
 async fn count_non_null_values_in_files(
+    block_cache: &VnodeBlockCache,
     reader_blk_metas: Vec<ReadTask>,
     time_ranges: Arc<Vec<TimeRange>>,
     cached_timestamps: Arc<HashSet<Timestamp>>,
@@ -294,7 +298,7 @@ async fn count_non_null_values_in_files(
     for read_task in reader_blk_metas {
         let blk = read_task
             .tsm_reader
-            .get_data_block(&read_task.block_meta)
+            .get_cached_data_block(&read_task.block_meta, block_cache)
             .await
             .unwrap();
         let timestamps = blk.ts();
@@ -366,6 +370,7 @@ mod test {
 
     use config::get_config;
     use memory_pool::{GreedyMemoryPool, MemoryPoolRef};
+    use metrics::gauge::U64Gauge;
     use models::utils as model_utils;
     use parking_lot::RwLock;
 
@@ -377,7 +382,7 @@ mod test {
     use crate::tseries_family::test_tseries_family::build_version_by_column_files;
     use crate::tseries_family::{CacheGroup, SuperVersion};
     use crate::tsm::codec::DataBlockEncoding;
-    use crate::tsm::DataBlock;
+    use crate::tsm::{BlockCache, DataBlock};
     use crate::Options;
 
     #[tokio::test]
@@ -420,6 +425,11 @@ mod test {
                 immut_cache: vec![],
             },
             Arc::new(version),
+            Arc::new(Arc::new(BlockCache::new(1024 * 1024)).vnode_cache(
+                ts_family_id,
+                &pool,
+                U64Gauge::default(),
+            )),
             1,
         ));
 
@@ -498,6 +508,11 @@ mod test {
             opt.storage.clone(),
             cache_group,
             Arc::new(version),
+            Arc::new(Arc::new(BlockCache::new(1024 * 1024)).vnode_cache(
+                ts_family_id,
+                &pool,
+                U64Gauge::default(),
+            )),
             1,
         ));
 
@@ -594,6 +609,11 @@ mod test {
             opt.storage.clone(),
             cache_group,
             Arc::new(version),
+            Arc::new(Arc::new(BlockCache::new(1024 * 1024)).vnode_cache(
+                ts_family_id,
+                &pool,
+                U64Gauge::default(),
+            )),
             1,
         ));
 
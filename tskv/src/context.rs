@@ -6,6 +6,7 @@ use parking_lot::RwLock;
 use tokio::runtime::Runtime;
 use tokio::sync::mpsc::Receiver;
 
+use crate::file_system::IoRateLimiter;
 use crate::TseriesFamilyId;
 
 #[derive(Default, Debug)]
@@ -13,6 +14,7 @@ pub struct GlobalContext {
     file_id: AtomicU64,
     mem_seq: AtomicU64,
     last_seq: AtomicU64,
+    io_limiter: Arc<IoRateLimiter>,
 }
 
 impl GlobalContext {
@@ -21,6 +23,7 @@ impl GlobalContext {
             file_id: AtomicU64::new(0),
             mem_seq: AtomicU64::new(0),
             last_seq: AtomicU64::new(0),
+            io_limiter: Arc::new(IoRateLimiter::default()),
         }
     }
 }
@@ -58,6 +61,11 @@ impl GlobalContext {
         self.file_id.store(v, Ordering::Release);
     }
 
+    /// Limiter of the writes of flush and compaction.
+    pub fn io_limiter(&self) -> Arc<IoRateLimiter> {
+        self.io_limiter.clone()
+    }
+
     pub fn mark_log_number_used(&self, v: u64) {
         let mut old = self.file_id.load(Ordering::Acquire);
         while old <= v {
@@ -75,13 +83,16 @@ impl GlobalContext {
 #[derive(Debug)]
 pub struct GlobalSequenceContext {
     min_seq: AtomicU64,
+    last_seq: AtomicU64,
     inner: Arc<RwLock<GlobalSequenceContextInner>>,
 }
 
 impl GlobalSequenceContext {
     pub fn new(min_seq: u64, tsf_seq_map: HashMap<TseriesFamilyId, u64>) -> Self {
+        let last_seq = tsf_seq_map.values().copied().max().unwrap_or(0);
         Self {
             min_seq: AtomicU64::new(min_seq),
+            last_seq: AtomicU64::new(last_seq),
             inner: Arc::new(RwLock::new(GlobalSequenceContextInner {
                 min_seq,
                 tsf_seq_map,
@@ -99,6 +110,7 @@ impl GlobalSequenceContext {
 
         for (tsf_id, min_seq) in ts_family_min_seq {
             inner.tsf_seq_map.insert(tsf_id, min_seq);
+            self.last_seq.fetch_max(min_seq, Ordering::AcqRel);
         }
         if !del_ts_family.is_empty() {
             for tsf_id in del_ts_family {
@@ -112,6 +124,17 @@ impl GlobalSequenceContext {
     pub fn min_seq(&self) -> u64 {
         self.min_seq.load(Ordering::Acquire)
     }
+
+    /// Returns the maximum sequence number flushed to disk of all `TseriesFamily`s,
+    /// including the ones deleted after the context was created.
+    pub fn last_seq(&self) -> u64 {
+        self.last_seq.load(Ordering::Acquire)
+    }
+
+    /// Returns the last sequence number flushed to disk of the `TseriesFamily`.
+    pub fn tsf_seq(&self, tsf_id: TseriesFamilyId) -> Option<u64> {
+        self.inner.read().tsf_seq_map.get(&tsf_id).copied()
+    }
 }
 
 #[cfg(test)]
@@ -119,6 +142,7 @@ impl GlobalSequenceContext {
     pub fn empty() -> Arc<Self> {
         Arc::new(Self {
             min_seq: AtomicU64::new(0),
+            last_seq: AtomicU64::new(0),
             inner: Arc::new(RwLock::new(GlobalSequenceContextInner {
                 min_seq: 0,
                 tsf_seq_map: HashMap::new(),
@@ -17,7 +17,7 @@ use tokio::sync::{oneshot, RwLock};
 use trace::{debug, error, info};
 use utils::BloomFilter;
 
-use crate::compaction::{check, CompactTask, FlushReq};
+use crate::compaction::{check, CompactTask, FieldEncodings, FlushReq};
 use crate::error::{self, Result, SchemaSnafu};
 use crate::index::{self, IndexResult};
 use crate::kv_option::Options;
@@ -25,6 +25,7 @@ use crate::memcache::{MemCache, RowData, RowGroup};
 use crate::schema::schemas::DBschemas;
 use crate::summary::{SummaryTask, VersionEdit};
 use crate::tseries_family::{LevelInfo, TseriesFamily, Version};
+use crate::tsm::BlockCache;
 use crate::version_set::VersionSet;
 use crate::Error::{self, InvalidPoint};
 use crate::{ColumnFileId, TimeRange, TseriesFamilyId};
@@ -42,6 +43,7 @@ pub struct Database {
     ts_families: HashMap<TseriesFamilyId, Arc<RwLock<TseriesFamily>>>,
     runtime: Arc<Runtime>,
     memory_pool: MemoryPoolRef,
+    block_cache: Arc<BlockCache>,
     metrics_register: Arc<MetricsRegister>,
 }
 
@@ -52,6 +54,7 @@ impl Database {
         runtime: Arc<Runtime>,
         meta: MetaRef,
         memory_pool: MemoryPoolRef,
+        block_cache: Arc<BlockCache>,
         metrics_register: Arc<MetricsRegister>,
     ) -> Result<Self> {
         let db = Self {
@@ -62,6 +65,7 @@ impl Database {
             ts_families: HashMap::new(),
             runtime,
             memory_pool,
+            block_cache,
             metrics_register,
         };
 
@@ -89,6 +93,7 @@ impl Database {
             flush_task_sender,
             compact_task_sender,
             self.memory_pool.clone(),
+            self.block_cache.clone(),
             &self.metrics_register,
         );
         tf.schedule_compaction(self.runtime.clone());
@@ -148,6 +153,7 @@ impl Database {
             flush_task_sender,
             compact_task_sender,
             self.memory_pool.clone(),
+            self.block_cache.clone(),
             &self.metrics_register,
         );
 
@@ -403,6 +409,12 @@ impl Database {
         self.schemas.clone()
     }
 
+    /// Returns the encodings of fields in vnode to rewrite blocks in compaction.
+    pub fn get_field_encodings(&self, id: TseriesFamilyId) -> Option<FieldEncodings> {
+        self.get_ts_index(id)
+            .map(|ts_index| FieldEncodings::new(ts_index, self.schemas.clone()))
+    }
+
     // todo: will delete in cluster version
     pub fn get_tsfamily_random(&self) -> Option<Arc<RwLock<TseriesFamily>>> {
         if let Some((_, v)) = self.ts_families.iter().next() {
@@ -112,5 +112,9 @@ pub trait Engine: Send + Sync + Debug {
     async fn drop_vnode(&self, id: TseriesFamilyId) -> Result<()>;
 
     async fn compact(&self, vnode_ids: Vec<TseriesFamilyId>) -> Result<()>;
+
+    /// Changes bytes per second of flush and compaction writes, 0 means unlimited.
+    fn set_io_rate_limit(&self, bytes_per_sec: u64);
+
     async fn close(&self);
 }
@@ -189,5 +189,7 @@ impl Engine for MockEngine {
         todo!()
     }
 
+    fn set_io_rate_limit(&self, bytes_per_sec: u64) {}
+
     async fn close(&self) {}
 }
@@ -1,14 +1,28 @@
 use std::io::{Error, ErrorKind, IoSlice, Result, SeekFrom};
 use std::ops::Deref;
+use std::sync::Arc;
 
 use crate::file_system::file::async_file::{AsyncFile, IFile};
+use crate::file_system::io_limiter::{IoPriority, IoRateLimiter};
 
 pub struct FileCursor {
     file: AsyncFile,
     pos: u64,
+    io_limiter: Option<(Arc<IoRateLimiter>, IoPriority)>,
 }
 
 impl FileCursor {
+    /// Limits the rate of writes by `limiter` with `priority`.
+    pub fn set_io_limiter(&mut self, limiter: Arc<IoRateLimiter>, priority: IoPriority) {
+        self.io_limiter = Some((limiter, priority));
+    }
+
+    async fn acquire_write(&self, bytes: usize) {
+        if let Some((limiter, priority)) = &self.io_limiter {
+            limiter.request(bytes, *priority).await;
+        }
+    }
+
     pub fn into_file(self) -> AsyncFile {
         self.file
     }
@@ -33,6 +47,7 @@ impl FileCursor {
     }
 
     pub async fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        self.acquire_write(buf.len()).await;
         let size = self.file.write_at(self.pos, buf).await?;
         self.seek(SeekFrom::Current(buf.len().try_into().unwrap()))
             .unwrap();
@@ -40,6 +55,7 @@ impl FileCursor {
     }
 
     pub async fn write_vec<'a>(&mut self, bufs: &'a mut [IoSlice<'a>]) -> Result<usize> {
+        self.acquire_write(bufs.iter().map(|b| b.len()).sum()).await;
         let mut p = self.pos;
         for buf in bufs {
             p += self.write_at(p, buf.deref()).await? as u64;
@@ -76,7 +92,11 @@ impl FileCursor {
 
 impl From<AsyncFile> for FileCursor {
     fn from(file: AsyncFile) -> Self {
-        FileCursor { file, pos: 0 }
+        FileCursor {
+            file,
+            pos: 0,
+            io_limiter: None,
+        }
     }
 }
 
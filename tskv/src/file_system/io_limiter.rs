@@ -0,0 +1,192 @@
+use std::fmt::Display;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use limiter_bucket::RateBucket;
+use parking_lot::RwLock;
+
+/// Interval to refill the tokens (bytes) of the limiter.
+const REFILL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Priority of writes, writes of `Flush` are served before `Compaction`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IoPriority {
+    Flush,
+    Compaction,
+}
+
+impl IoPriority {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IoPriority::Flush => "flush",
+            IoPriority::Compaction => "compaction",
+        }
+    }
+}
+
+impl Display for IoPriority {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Limits the bytes per second written by flush and compaction, shared by all vnodes.
+///
+/// Writes of compaction wait while there are writes of flush waiting for the limiter,
+/// so that flush is not slowed down by compaction on the same disk.
+#[derive(Debug)]
+pub struct IoRateLimiter {
+    /// Bytes per second, 0 means unlimited.
+    rate: AtomicU64,
+    bucket: RwLock<Option<Arc<RateBucket>>>,
+    waiting_flushes: AtomicUsize,
+}
+
+impl Default for IoRateLimiter {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl IoRateLimiter {
+    pub fn new(bytes_per_sec: u64) -> Self {
+        Self {
+            rate: AtomicU64::new(bytes_per_sec),
+            bucket: RwLock::new(Self::build_bucket(bytes_per_sec)),
+            waiting_flushes: AtomicUsize::new(0),
+        }
+    }
+
+    fn build_bucket(bytes_per_sec: u64) -> Option<Arc<RateBucket>> {
+        if bytes_per_sec == 0 {
+            return None;
+        }
+        let bytes_per_sec = usize::try_from(bytes_per_sec).unwrap_or(usize::MAX);
+        let refill = (bytes_per_sec / (1000 / REFILL_INTERVAL.as_millis() as usize)).max(1);
+        let max = bytes_per_sec.max(refill);
+        let bucket = RateBucket::builder()
+            .max(max)
+            .initial(max)
+            .interval(chrono::Duration::milliseconds(
+                REFILL_INTERVAL.as_millis() as i64
+            ))
+            .refill(refill)
+            .build();
+        Some(Arc::new(bucket))
+    }
+
+    /// Returns bytes per second of the limiter, 0 means unlimited.
+    pub fn rate(&self) -> u64 {
+        self.rate.load(Ordering::Acquire)
+    }
+
+    /// Changes bytes per second of the limiter, 0 means unlimited.
+    /// Writes waiting for the limiter are applied with the new rate.
+    pub fn set_rate(&self, bytes_per_sec: u64) {
+        let mut bucket = self.bucket.write();
+        *bucket = Self::build_bucket(bytes_per_sec);
+        self.rate.store(bytes_per_sec, Ordering::Release);
+    }
+
+    /// Waits until `bytes` can be written, returns the time throttled.
+    pub async fn request(&self, bytes: usize, priority: IoPriority) -> Duration {
+        if bytes == 0 || self.rate() == 0 {
+            return Duration::ZERO;
+        }
+
+        let _waiting = match priority {
+            IoPriority::Flush => Some(WaitingGuard::new(&self.waiting_flushes)),
+            IoPriority::Compaction => None,
+        };
+        let start = Instant::now();
+        let mut throttled = false;
+        let mut remaining = bytes;
+        loop {
+            let bucket = match self.bucket.read().clone() {
+                Some(b) => b,
+                None => break,
+            };
+            if priority == IoPriority::Flush || self.waiting_flushes.load(Ordering::Acquire) == 0 {
+                remaining -= bucket.acquire_closed(remaining);
+                if remaining == 0 {
+                    break;
+                }
+            }
+            throttled = true;
+            tokio::time::sleep(REFILL_INTERVAL).await;
+        }
+
+        if !throttled {
+            return Duration::ZERO;
+        }
+        let elapsed = start.elapsed();
+        metrics::sample_tskv_io_throttled_duration(priority.as_str(), elapsed.as_secs_f64());
+        elapsed
+    }
+}
+
+/// Counts a waiting write until it's dropped.
+struct WaitingGuard<'a> {
+    waiting: &'a AtomicUsize,
+}
+
+impl<'a> WaitingGuard<'a> {
+    fn new(waiting: &'a AtomicUsize) -> Self {
+        waiting.fetch_add(1, Ordering::AcqRel);
+        Self { waiting }
+    }
+}
+
+impl Drop for WaitingGuard<'_> {
+    fn drop(&mut self) {
+        self.waiting.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use super::{IoPriority, IoRateLimiter};
+
+    #[tokio::test]
+    async fn test_io_rate_limiter() {
+        // Unlimited.
+        let limiter = Arc::new(IoRateLimiter::default());
+        assert_eq!(
+            limiter.request(1 << 30, IoPriority::Compaction).await,
+            Duration::ZERO
+        );
+
+        // Initial tokens of a second are not throttled, the rest are.
+        limiter.set_rate(1000);
+        assert_eq!(limiter.rate(), 1000);
+        assert_eq!(
+            limiter.request(1000, IoPriority::Flush).await,
+            Duration::ZERO
+        );
+        let throttled = limiter.request(200, IoPriority::Flush).await;
+        assert!(throttled >= Duration::from_millis(100));
+
+        // Compaction waits for flush.
+        let compaction = {
+            let limiter = limiter.clone();
+            tokio::spawn(async move { limiter.request(500, IoPriority::Compaction).await })
+        };
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let flush = limiter.request(300, IoPriority::Flush).await;
+        let compaction = compaction.await.unwrap();
+        assert!(compaction > flush);
+
+        // Waiting writes are released after the limit is removed.
+        let compaction = {
+            let limiter = limiter.clone();
+            tokio::spawn(async move { limiter.request(1 << 30, IoPriority::Compaction).await })
+        };
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        limiter.set_rate(0);
+        compaction.await.unwrap();
+    }
+}
@@ -5,7 +5,9 @@
 
 mod file;
 pub mod file_manager;
+pub mod io_limiter;
 pub mod queue;
 
 pub use file::async_file::{AsyncFile, IFile};
 pub use file::cursor::FileCursor;
+pub use io_limiter::{IoPriority, IoRateLimiter};
@@ -1,13 +1,17 @@
 use std::collections::HashMap;
+use std::iter::Peekable;
 use std::ops::{Bound, RangeBounds};
 use std::sync::Arc;
 use std::task::Poll;
 
 use datafusion::arrow::array::{
-    ArrayBuilder, BooleanBuilder, Float64Builder, Int64Builder, StringBuilder,
+    as_boolean_array, as_primitive_array, make_builder, Array, ArrayBuilder, ArrayRef,
+    BooleanBuilder, Float64Builder, Int64Builder, PrimitiveBuilder, StringBuilder,
     TimestampNanosecondBuilder, UInt64Builder,
 };
-use datafusion::arrow::datatypes::{DataType as ArrowDataType, SchemaRef};
+use datafusion::arrow::datatypes::{
+    ArrowPrimitiveType, DataType as ArrowDataType, Float64Type, Int64Type, SchemaRef, UInt64Type,
+};
 use datafusion::arrow::record_batch::RecordBatch;
 use datafusion::error::DataFusionError;
 use datafusion::execution::memory_pool::{MemoryConsumer, MemoryPool, MemoryReservation};
@@ -15,10 +19,11 @@ use datafusion::physical_plan::metrics::{
     self, BaselineMetrics, ExecutionPlanMetricsSet, MetricBuilder,
 };
 use datafusion::scalar::ScalarValue;
-use minivec::MiniVec;
-use models::predicate::domain::{ColumnDomains, Domain, PredicateRef, Range, ValueEntry};
-use models::schema::{ColumnType, TableColumn, TskvTableSchema, TIME_FIELD, TIME_FIELD_NAME};
-use models::utils::{min_num, unite_id};
+use models::predicate::domain::{
+    ColumnDomains, Domain, PredicateRef, PushedAggregateFunction, Range, ValueEntry,
+};
+use models::schema::{ColumnType, TableColumn, TskvTableSchema, TIME_FIELD_NAME};
+use models::utils::unite_id;
 use models::{FieldId, SeriesId, ValueType};
 use parking_lot::RwLock;
 use snafu::ResultExt;
@@ -29,12 +34,15 @@ use super::engine::EngineRef;
 use super::error::IndexErrSnafu;
 use super::memcache::DataType;
 use super::tseries_family::{ColumnFile, SuperVersion, TimeRange};
-use super::tsm::{BlockMetaIterator, DataBlock, TsmReader};
+use super::tsm::{
+    BlockMeta, BlockMetaIterator, DataBlock, FieldIndexFilter, StatValue, TsmReader,
+    VnodeBlockCache,
+};
 use super::{error, ColumnFileId, Error};
+use crate::compute::aggregate::{aggregate_field_values, ValueStatistics};
 use crate::compute::count::count_column_non_null_values;
 use crate::tseries_family::Version;
 
-pub type CursorPtr = Box<dyn Cursor>;
 pub type ArrayBuilderPtr = Box<dyn ArrayBuilder>;
 
 /// Stores metrics about the table writer execution.
@@ -111,6 +119,8 @@ pub struct TskvSourceMetrics {
     elapsed_point_to_record_batch: metrics::Time,
     elapsed_field_scan: metrics::Time,
     elapsed_series_scan: metrics::Time,
+    decoded_data_blocks: metrics::Count,
+    skipped_data_blocks: metrics::Count,
 }
 
 impl TskvSourceMetrics {
@@ -125,10 +135,18 @@ impl TskvSourceMetrics {
         let elapsed_series_scan =
             MetricBuilder::new(metrics).subset_time("elapsed_series_scan", partition);
 
+        let decoded_data_blocks =
+            MetricBuilder::new(metrics).counter("decoded_data_blocks", partition);
+
+        let skipped_data_blocks =
+            MetricBuilder::new(metrics).counter("skipped_data_blocks", partition);
+
         Self {
             elapsed_point_to_record_batch,
             elapsed_field_scan,
             elapsed_series_scan,
+            decoded_data_blocks,
+            skipped_data_blocks,
         }
     }
 
@@ -143,6 +161,15 @@ impl TskvSourceMetrics {
     pub fn elapsed_series_scan(&self) -> &metrics::Time {
         &self.elapsed_series_scan
     }
+
+    pub fn decoded_data_blocks(&self) -> &metrics::Count {
+        &self.decoded_data_blocks
+    }
+
+    /// Data blocks skipped by their field indexes.
+    pub fn skipped_data_blocks(&self) -> &metrics::Count {
+        &self.skipped_data_blocks
+    }
 }
 
 // 1. Tsm文件遍历： KeyCursor
@@ -159,6 +186,13 @@ impl TskvSourceMetrics {
 // 5. 行数据到DataFusion的RecordBatch转换器
 //  调用Iterator.Next得到行数据，然后转换行数据为RecordBatch结构
 
+/// An aggregate function pushed down to tskv and the column it aggregates.
+#[derive(Debug, Clone)]
+pub struct PushedAggregate {
+    pub function: PushedAggregateFunction,
+    pub column: TableColumn,
+}
+
 #[derive(Debug, Clone)]
 pub struct QueryOption {
     pub batch_size: usize,
@@ -167,11 +201,13 @@ pub struct QueryOption {
     pub df_schema: SchemaRef,
     pub table_schema: TskvTableSchema,
     pub metrics: TskvSourceMetrics,
-    pub aggregates: Option<Vec<TableColumn>>, // TODO: Use PushedAggregateFunction
+    pub aggregates: Option<Vec<PushedAggregate>>,
 
     pub time_filter: ColumnDomains<String>,
     pub tags_filter: ColumnDomains<String>,
     pub fields_filter: ColumnDomains<String>,
+    /// Filters of string fields which may skip data blocks by field indexes.
+    pub field_index_filters: HashMap<String, Vec<FieldIndexFilter>>,
 }
 
 impl QueryOption {
@@ -180,7 +216,7 @@ impl QueryOption {
         batch_size: usize,
         tenant: String,
         filter: PredicateRef,
-        aggregates: Option<Vec<TableColumn>>, // TODO: Use PushedAggregateFunction
+        aggregates: Option<Vec<PushedAggregate>>,
         df_schema: SchemaRef,
         table_schema: TskvTableSchema,
         metrics: TskvSourceMetrics,
@@ -205,6 +241,17 @@ impl QueryOption {
             _ => None,
         });
 
+        let field_index_filters = table_schema
+            .columns()
+            .iter()
+            .filter(|c| c.column_type == ColumnType::Field(ValueType::String))
+            .map(|c| {
+                let filters = FieldIndexFilter::from_exprs(filter.exprs(), &c.name);
+                (c.name.clone(), filters)
+            })
+            .filter(|(_, filters)| !filters.is_empty())
+            .collect();
+
         Self {
             batch_size,
             tenant,
@@ -217,6 +264,7 @@ impl QueryOption {
             time_filter,
             tags_filter,
             fields_filter,
+            field_index_filters,
         }
     }
 
@@ -239,154 +287,187 @@ impl QueryOption {
     }
 }
 
-pub struct FieldFileLocation {
-    reader: Arc<TsmReader>,
-    block_it: BlockMetaIterator,
-
-    read_index: usize,
-    data_block: DataBlock,
+/// A decoded `DataBlock` and the index of the first (ts, val) not read yet.
+struct BlockBuffer {
+    /// The block may be shared with the block cache, so it's never modified.
+    block: Arc<DataBlock>,
+    offset: usize,
+    end: usize,
 }
 
-impl FieldFileLocation {
-    pub fn new(reader: Arc<TsmReader>, block_it: BlockMetaIterator, vtype: ValueType) -> Self {
-        Self {
-            reader,
-            block_it,
-            read_index: 0,
-            data_block: DataBlock::new(0, vtype),
-        }
+impl BlockBuffer {
+    fn new(block: impl Into<Arc<DataBlock>>) -> Self {
+        let block = block.into();
+        let end = block.len();
+        Self::with_range(block, 0, end)
     }
 
-    pub async fn peek(&mut self) -> Result<Option<DataType>, Error> {
-        if self.read_index >= self.data_block.len() {
-            if let Some(meta) = self.block_it.next() {
-                self.read_index = 0;
-                self.data_block = self.reader.get_data_block(&meta).await?;
-            } else {
-                return Ok(None);
-            }
+    /// Only the values in `[start, end)` of the block are read.
+    fn with_range(block: Arc<DataBlock>, start: usize, end: usize) -> Self {
+        Self {
+            block,
+            offset: start,
+            end,
         }
-
-        Ok(self.data_block.get(self.read_index))
-    }
-
-    pub fn next(&mut self) {
-        self.read_index += 1;
-    }
-}
-
-//-----------trait Cursor----------------
-#[async_trait::async_trait]
-pub trait Cursor: Send + Sync {
-    fn name(&self) -> &String;
-    fn is_field(&self) -> bool;
-    fn val_type(&self) -> ValueType;
-
-    async fn next(&mut self, ts: i64);
-    async fn peek(&mut self) -> Result<Option<DataType>, Error>;
-}
-
-//-----------Time Cursor----------------
-pub struct TimeCursor {
-    ts: i64,
-    name: String,
-}
-
-impl TimeCursor {
-    pub fn new(ts: i64, name: String) -> Self {
-        Self { ts, name }
     }
-}
 
-#[async_trait::async_trait]
-impl Cursor for TimeCursor {
-    fn name(&self) -> &String {
-        &self.name
+    fn is_consumed(&self) -> bool {
+        self.offset >= self.end
     }
 
-    fn is_field(&self) -> bool {
-        false
+    fn remaining_ts(&self) -> &[i64] {
+        let ts = &self.block.ts()[..self.end];
+        &ts[self.offset.min(ts.len())..]
     }
 
-    fn val_type(&self) -> ValueType {
-        ValueType::Integer
+    fn last_timestamp(&self) -> Option<i64> {
+        self.remaining_ts().last().copied()
     }
 
-    async fn next(&mut self, _ts: i64) {}
-
-    async fn peek(&mut self) -> Result<Option<DataType>, Error> {
-        let data = DataType::I64(self.ts, self.ts);
-
-        Ok(Some(data))
+    /// Takes the (ts, val) not read yet whose timestamp is less equal than `max_ts`.
+    fn take_until(&mut self, max_ts: i64) -> DataBlock {
+        let end = self.offset + self.remaining_ts().partition_point(|ts| *ts <= max_ts);
+        let block = self.block.slice(self.offset, end);
+        self.offset = end;
+        block
     }
 }
 
-//-----------Tag Cursor----------------
-pub struct TagCursor {
-    name: String,
-    value: Option<String>,
-}
+pub struct FieldFileLocation {
+    reader: Arc<TsmReader>,
+    block_cache: Arc<VnodeBlockCache>,
+    block_it: Peekable<BlockMetaIterator>,
+    time_range: TimeRange,
+    /// Blocks whose field indexes mismatch any of the filters are skipped.
+    index_filters: Arc<Vec<FieldIndexFilter>>,
 
-impl TagCursor {
-    pub fn new(name: String, value: Option<String>) -> Self {
-        Self { name, value }
-    }
+    buffer: BlockBuffer,
 }
 
-#[async_trait::async_trait]
-impl Cursor for TagCursor {
-    fn name(&self) -> &String {
-        &self.name
+impl FieldFileLocation {
+    pub fn new(
+        reader: Arc<TsmReader>,
+        block_cache: Arc<VnodeBlockCache>,
+        block_it: BlockMetaIterator,
+        time_range: TimeRange,
+        vtype: ValueType,
+    ) -> Self {
+        Self {
+            reader,
+            block_cache,
+            block_it: block_it.peekable(),
+            time_range,
+            index_filters: Arc::new(vec![]),
+            buffer: BlockBuffer::new(DataBlock::new(0, vtype)),
+        }
     }
 
-    fn is_field(&self) -> bool {
-        false
+    pub fn with_index_filters(mut self, index_filters: Arc<Vec<FieldIndexFilter>>) -> Self {
+        self.index_filters = index_filters;
+        self
     }
 
-    fn val_type(&self) -> ValueType {
-        ValueType::String
+    /// Returns false if no value in the block matches the index filters.
+    async fn may_match(&self, meta: &BlockMeta) -> Result<bool, Error> {
+        if self.index_filters.is_empty() {
+            return Ok(true);
+        }
+        Ok(match self.reader.get_field_index(meta).await? {
+            Some(index) => self.index_filters.iter().all(|f| index.may_match(f)),
+            None => true,
+        })
     }
 
-    async fn next(&mut self, _ts: i64) {}
+    /// Reads the next data block of integers or unsigned integers into the arrow array
+    /// builders if the current one is read and the block is included by the time range,
+    /// see `TsmReader::read_data_block_to_builders`. Returns the number of values,
+    /// `None` if the block must be read by `fill`.
+    async fn read_block_to_builders(
+        &mut self,
+        ts_builder: &mut TimestampNanosecondBuilder,
+        val_builder: &mut dyn ArrayBuilder,
+        capacity: usize,
+        metrics: &TskvSourceMetrics,
+    ) -> Result<Option<usize>, Error> {
+        if !self.buffer.is_consumed() || !self.index_filters.is_empty() {
+            return Ok(None);
+        }
+        let meta = match self.block_it.peek() {
+            Some(meta)
+                if matches!(meta.field_type(), ValueType::Integer | ValueType::Unsigned)
+                    && meta.count() as usize <= capacity
+                    && self.time_range.includes(&meta.time_range()) =>
+            {
+                meta.clone()
+            }
+            _ => return Ok(None),
+        };
 
-    async fn peek(&mut self) -> Result<Option<DataType>, Error> {
-        match &self.value {
-            Some(value) => Ok(Some(DataType::Str(0, MiniVec::from(value.as_bytes())))),
-            None => Ok(None),
+        let rows = self
+            .reader
+            .read_data_block_to_builders(&meta, &self.block_cache, ts_builder, val_builder)
+            .await?;
+        if rows.is_some() {
+            self.block_it.next();
+            metrics.decoded_data_blocks().add(1);
         }
+        Ok(rows)
+    }
+
+    /// Decodes the next data block if the current one is read,
+    /// returns `false` if there is no more data.
+    async fn fill(&mut self, metrics: &TskvSourceMetrics) -> Result<bool, Error> {
+        while self.buffer.is_consumed() {
+            let meta = match self.block_it.next() {
+                Some(meta) => meta,
+                None => return Ok(false),
+            };
+            if !self.may_match(&meta).await? {
+                metrics.skipped_data_blocks().add(1);
+                continue;
+            }
+            let block = self
+                .reader
+                .get_cached_data_block(&meta, &self.block_cache)
+                .await?;
+            metrics.decoded_data_blocks().add(1);
+
+            // The block may be partially out of the time range
+            let ts = block.ts();
+            let start = ts.partition_point(|ts| *ts < self.time_range.min_ts);
+            let end = ts.partition_point(|ts| *ts <= self.time_range.max_ts);
+            self.buffer = BlockBuffer::with_range(block, start, end.max(start));
+        }
+
+        Ok(true)
     }
 }
 
 //-----------Field Cursor----------------
+/// Reads a field of a series from memcache and tsm files block by block,
+/// merges them into sorted `DataBlock`s without duplicate timestamps.
 pub struct FieldCursor {
-    name: String,
-    value_type: ValueType,
-
-    cache_index: usize,
-    cache_data: Vec<DataType>,
+    cache: BlockBuffer,
     locations: Vec<FieldFileLocation>,
 }
 
 impl FieldCursor {
-    pub fn empty(value_type: ValueType, name: String) -> Self {
+    pub fn empty(value_type: ValueType) -> Self {
         Self {
-            name,
-            value_type,
-            cache_index: 0,
-            cache_data: Vec::new(),
+            cache: BlockBuffer::new(DataBlock::new(0, value_type)),
             locations: Vec::new(),
         }
     }
 
     pub async fn new(
         field_id: FieldId,
-        name: String,
         vtype: ValueType,
+        index_filters: Vec<FieldIndexFilter>,
         iterator: &mut RowIterator,
     ) -> Result<Self, Error> {
         let super_version = match iterator.version.clone() {
             Some(v) => v,
-            None => return Ok(Self::empty(vtype, name)),
+            None => return Ok(Self::empty(vtype)),
         };
 
         let time_ranges: Vec<TimeRange> = filter_to_time_ranges(&iterator.option.time_filter);
@@ -405,128 +486,144 @@ impl FieldCursor {
             |_| true,
             |d| mem_data.push(d),
         );
+        // stable sort, the later written data of the same timestamp is kept when merging
         mem_data.sort_by_key(|data| data.timestamp());
+        let mut cache = DataBlock::new(mem_data.len(), vtype);
+        for data in mem_data {
+            cache.insert(data);
+        }
 
         debug!(
             "build memcache data id: {:02X}, len: {}",
             field_id,
-            mem_data.len()
+            cache.len()
         );
 
         // get data from levelinfo
+        let files: Vec<Arc<ColumnFile>> = super_version
+            .version
+            .levels_info
+            .iter()
+            .rev()
+            .flat_map(|level| level.files.iter())
+            .filter(|file| !file.is_deleted() && file.contains_field_id(field_id))
+            .cloned()
+            .collect();
+        let index_filters = Arc::new(index_filters);
         let mut locations = vec![];
-        for level in super_version.version.levels_info.iter().rev() {
-            for file in level.files.iter() {
-                if file.is_deleted() {
-                    continue;
-                }
-                if !file.contains_field_id(field_id) {
+        for file in files.iter() {
+            // Skipping blocks of a file overlapped with others may expose
+            // the overwritten values of the same timestamps in other files.
+            let index_filters = if files
+                .iter()
+                .any(|f| f.file_id() != file.file_id() && f.overlap(file.time_range()))
+            {
+                Arc::new(vec![])
+            } else {
+                index_filters.clone()
+            };
+
+            for time_range in time_ranges.iter() {
+                if !file.overlap(time_range) {
                     continue;
                 }
 
-                for time_range in time_ranges.iter() {
-                    if !file.overlap(time_range) {
-                        continue;
-                    }
-
-                    debug!(
-                        "build file data block id: {:02X}, len: {}",
-                        field_id,
-                        file.file_path().display()
-                    );
+                debug!(
+                    "build file data block id: {:02X}, len: {}",
+                    field_id,
+                    file.file_path().display()
+                );
 
-                    let tsm_reader = iterator
-                        .get_tsm_reader(super_version.version.clone(), file.clone())
-                        .await?;
-                    for idx in tsm_reader.index_iterator_opt(field_id) {
-                        let block_it = idx.block_iterator_opt(time_range);
-                        let location = FieldFileLocation::new(tsm_reader.clone(), block_it, vtype);
-                        locations.push(location);
-                    }
+                let tsm_reader = iterator
+                    .get_tsm_reader(super_version.version.clone(), file.clone())
+                    .await?;
+                for idx in tsm_reader.index_iterator_opt(field_id) {
+                    let block_it = idx.block_iterator_opt(time_range);
+                    let location = FieldFileLocation::new(
+                        tsm_reader.clone(),
+                        super_version.block_cache.clone(),
+                        block_it,
+                        *time_range,
+                        vtype,
+                    )
+                    .with_index_filters(index_filters.clone());
+                    locations.push(location);
                 }
             }
         }
 
         Ok(Self {
-            name,
-            value_type: vtype,
-            cache_index: 0,
-            cache_data: mem_data,
+            cache: BlockBuffer::new(cache),
             locations,
         })
     }
 
-    fn peek_cache(&mut self) -> Option<&DataType> {
-        let mut opt_top = self.cache_data.get(self.cache_index);
-        let mut opt_next = self.cache_data.get(self.cache_index + 1);
-
-        while let (Some(top), Some(next)) = (opt_top, opt_next) {
-            // if timestamp is same, select next data
-            // deduplication
-            if top.timestamp() == next.timestamp() {
-                self.cache_index += 1;
-                opt_top = Some(next);
-                opt_next = self.cache_data.get(self.cache_index + 1);
-            } else {
-                break;
+    /// Reads the next block of the field into the arrow array builders without a `DataBlock`
+    /// if nothing else is merged with it: the memcache has no data of the field and
+    /// the field is read from only one file. Returns `None` if it must be read by `next_block`.
+    pub async fn read_block_to_builders(
+        &mut self,
+        ts_builder: &mut TimestampNanosecondBuilder,
+        val_builder: &mut dyn ArrayBuilder,
+        capacity: usize,
+        metrics: &TskvSourceMetrics,
+    ) -> Result<Option<usize>, Error> {
+        if !self.cache.is_consumed() {
+            return Ok(None);
+        }
+        match self.locations.as_mut_slice() {
+            [location] => {
+                location
+                    .read_block_to_builders(ts_builder, val_builder, capacity, metrics)
+                    .await
             }
+            _ => Ok(None),
         }
-
-        opt_top
-    }
-}
-
-#[async_trait::async_trait]
-impl Cursor for FieldCursor {
-    fn name(&self) -> &String {
-        &self.name
     }
 
-    async fn peek(&mut self) -> Result<Option<DataType>, Error> {
-        let mut data = DataType::new(self.value_type, i64::MAX);
+    /// Returns the next sorted `DataBlock` of the field, `None` if all data is read.
+    pub async fn next_block(
+        &mut self,
+        metrics: &TskvSourceMetrics,
+    ) -> Result<Option<DataBlock>, Error> {
+        // Data up to the smallest last timestamp of the decoded blocks
+        // can be merged without decoding the following blocks.
+        let mut max_ts = i64::MAX;
         for loc in self.locations.iter_mut() {
-            if let Some(val) = loc.peek().await? {
-                if data.timestamp() >= val.timestamp() {
-                    data = val;
+            if loc.fill(metrics).await? {
+                if let Some(ts) = loc.buffer.last_timestamp() {
+                    max_ts = max_ts.min(ts);
                 }
             }
         }
 
-        if let Some(val) = self.peek_cache() {
-            if data.timestamp() >= val.timestamp() {
-                data = val.clone();
-            }
-        }
-
-        if data.timestamp() == i64::MAX {
-            return Ok(None);
-        }
-        Ok(Some(data))
-    }
-
-    async fn next(&mut self, ts: i64) {
-        if let Some(val) = self.peek_cache() {
-            if val.timestamp() == ts {
-                self.cache_index += 1;
-            }
-        }
-
+        // Files of lower levels and then memcache have newer data,
+        // which overwrite the older data of the same timestamp.
+        let mut blocks = Vec::with_capacity(self.locations.len() + 1);
         for loc in self.locations.iter_mut() {
-            if let Some(val) = loc.peek().await.unwrap() {
-                if val.timestamp() == ts {
-                    loc.next();
-                }
+            if !loc.buffer.is_consumed() {
+                blocks.push(loc.buffer.take_until(max_ts));
             }
         }
-    }
+        if !self.cache.is_consumed() {
+            blocks.push(self.cache.take_until(max_ts));
+        }
 
-    fn val_type(&self) -> ValueType {
-        self.value_type
+        Ok(DataBlock::merge_sorted(&blocks)?.filter(|block| !block.is_empty()))
     }
+}
 
-    fn is_field(&self) -> bool {
-        true
-    }
+/// A column of the series being scanned.
+enum SeriesColumn {
+    Time,
+    Tag(Option<String>),
+    Field {
+        cursor: FieldCursor,
+        buffer: BlockBuffer,
+        finished: bool,
+        /// Pushed down field-value filter, rows without a matched value are skipped.
+        filter: Option<Domain>,
+    },
 }
 
 pub fn filter_to_time_ranges(time_domain: &ColumnDomains<String>) -> Vec<TimeRange> {
@@ -601,7 +698,7 @@ pub struct RowIterator {
     series: Vec<u32>,
     engine: EngineRef,
     option: QueryOption,
-    columns: Vec<CursorPtr>,
+    columns: Vec<SeriesColumn>,
     version: Option<Arc<SuperVersion>>,
 
     open_files: HashMap<ColumnFileId, Arc<TsmReader>>,
@@ -610,6 +707,8 @@ pub struct RowIterator {
     vnode_id: u32,
     metrics: TskvSourceMetrics,
 
+    /// Number of rows returned, the iteration stops when the pushed limit is reached.
+    returned_rows: usize,
     finished: bool,
 }
 
@@ -647,6 +746,7 @@ impl RowIterator {
 
             batch_size,
             metrics,
+            returned_rows: 0,
             finished: false,
         })
     }
@@ -683,10 +783,9 @@ impl RowIterator {
             self.columns.clear();
             let fields = self.option.table_schema.columns().to_vec();
             for item in fields {
-                let field_name = item.name.clone();
                 debug!("build series columns id:{:02X}, {:?}", id, item);
-                let column: CursorPtr = match item.column_type {
-                    ColumnType::Time => Box::new(TimeCursor::new(0, field_name)),
+                let column = match item.column_type {
+                    ColumnType::Time => SeriesColumn::Time,
 
                     ColumnType::Tag => {
                         let tag_val = match key.tag_val(&item.name) {
@@ -696,19 +795,31 @@ impl RowIterator {
                             None => None,
                         };
 
-                        Box::new(TagCursor::new(field_name, tag_val))
+                        SeriesColumn::Tag(tag_val)
                     }
 
                     ColumnType::Field(vtype) => match vtype {
                         ValueType::Unknown => {
-                            error!("Unknown field type for column {}", &field_name);
-                            todo!()
+                            return Err(Error::CommonError {
+                                reason: format!("unknown type of {}", item.name),
+                            });
                         }
                         _ => {
+                            let index_filters = self
+                                .option
+                                .field_index_filters
+                                .get(&item.name)
+                                .cloned()
+                                .unwrap_or_default();
                             let cursor =
-                                FieldCursor::new(unite_id(item.id, id), field_name, vtype, self)
+                                FieldCursor::new(unite_id(item.id, id), vtype, index_filters, self)
                                     .await?;
-                            Box::new(cursor)
+                            SeriesColumn::Field {
+                                cursor,
+                                buffer: BlockBuffer::new(DataBlock::new(0, vtype)),
+                                finished: false,
+                                filter: self.field_filter(&item.name),
+                            }
                         }
                     },
                 };
@@ -724,6 +835,25 @@ impl RowIterator {
         Ok(())
     }
 
+    /// The pushed down domain of a field, `None` if all values match.
+    fn field_filter(&self, name: &str) -> Option<Domain> {
+        self.option
+            .fields_filter
+            .domains()?
+            .get(name)
+            .filter(|domain| !matches!(domain, Domain::All))
+            .cloned()
+    }
+
+    fn has_field_filter(&self) -> bool {
+        match self.option.fields_filter.domains() {
+            Some(domains) => domains
+                .values()
+                .any(|domain| !matches!(domain, Domain::All)),
+            None => false,
+        }
+    }
+
     async fn next_series(&mut self) -> Result<Option<()>, Error> {
         if self.series_index == usize::MAX {
             self.series_index = 0;
@@ -741,131 +871,227 @@ impl RowIterator {
         Ok(Some(()))
     }
 
-    async fn collect_row_data(
+    /// Appends at most `capacity` rows of the current series to `builder`,
+    /// returns the number of appended rows, 0 if the series is finished.
+    async fn collect_series_data(
         &mut self,
         builder: &mut [ArrayBuilderPtr],
-    ) -> Result<Option<()>, Error> {
-        debug!("======collect_row_data=========");
-        let timer = self.metrics.elapsed_field_scan().timer();
-
-        let mut min_time = i64::MAX;
-        let mut values = Vec::with_capacity(self.columns.len());
-        for column in self.columns.iter_mut() {
-            let val = column.peek().await?;
-            if let Some(ref data) = val {
-                if column.is_field() {
-                    min_time = min_num(min_time, data.timestamp());
-                }
-            }
-            values.push(val)
+        capacity: usize,
+    ) -> Result<usize, Error> {
+        debug!("======collect_series_data=========");
+        if let Some(rows) = self.collect_single_field_block(builder, capacity).await? {
+            return Ok(rows);
         }
-
-        for (column, value) in self.columns.iter_mut().zip(values.iter_mut()) {
-            debug!("field: {} value {:?}", column.name(), value);
-            if !column.is_field() {
-                continue;
+        loop {
+            let timer = self.metrics.elapsed_field_scan().timer();
+
+            for column in self.columns.iter_mut() {
+                if let SeriesColumn::Field {
+                    cursor,
+                    buffer,
+                    finished,
+                    filter,
+                } = column
+                {
+                    while !*finished && buffer.is_consumed() {
+                        match cursor.next_block(&self.metrics).await? {
+                            Some(block) => {
+                                let block = match filter {
+                                    Some(domain) => filter_block(&block, domain)?,
+                                    None => block,
+                                };
+                                *buffer = BlockBuffer::new(block);
+                            }
+                            None => *finished = true,
+                        }
+                    }
+                }
             }
 
-            if let Some(data) = value {
-                let ts = data.timestamp();
-                if ts == min_time {
-                    column.next(ts).await;
-                } else {
-                    *value = None;
+            // Rows up to the smallest last timestamp of the buffered blocks are complete,
+            // the following values of every field are greater than it.
+            let mut max_ts = i64::MAX;
+            let mut has_data = false;
+            let mut filters_matched = true;
+            for column in self.columns.iter() {
+                if let SeriesColumn::Field { buffer, filter, .. } = column {
+                    match buffer.last_timestamp() {
+                        Some(ts) => {
+                            max_ts = max_ts.min(ts);
+                            has_data = true;
+                        }
+                        // No more value matches the filter of this field
+                        None => filters_matched &= filter.is_none(),
+                    }
                 }
             }
-        }
 
-        timer.done();
-
-        debug!("min time {}", min_time);
-        if min_time == i64::MAX {
-            self.columns.clear();
-            return Ok(None);
-        }
+            if !has_data || !filters_matched {
+                timer.done();
+                self.columns.clear();
+                return Ok(0);
+            }
 
-        let timer = self.metrics.elapsed_point_to_record_batch().timer();
+            let mut timestamps = self.candidate_timestamps(max_ts);
+            timestamps.truncate(capacity);
 
-        for (i, value) in values.into_iter().enumerate() {
-            if self.columns[i].name() == TIME_FIELD {
-                let field_builder = builder[i]
-                    .as_any_mut()
-                    .downcast_mut::<TimestampNanosecondBuilder>()
-                    .unwrap();
-                field_builder.append_value(min_time);
+            timer.done();
 
-                continue;
-            }
-
-            match self.columns[i].val_type() {
-                ValueType::Unknown => {
-                    return Err(Error::CommonError {
-                        reason: format!("unknown type of {}", self.columns[i].name()),
-                    });
-                }
-                ValueType::Float => {
-                    let field_builder = builder[i]
-                        .as_any_mut()
-                        .downcast_mut::<Float64Builder>()
-                        .unwrap();
-                    if let Some(DataType::F64(_, val)) = value {
-                        field_builder.append_value(val);
-                    } else {
-                        field_builder.append_null();
+            let max_ts = match timestamps.last() {
+                Some(ts) => *ts,
+                None => {
+                    // No row matches the filters up to max_ts
+                    for column in self.columns.iter_mut() {
+                        if let SeriesColumn::Field { buffer, .. } = column {
+                            buffer.take_until(max_ts);
+                        }
                     }
+                    continue;
                 }
-                ValueType::Integer => {
-                    let field_builder = builder[i]
-                        .as_any_mut()
-                        .downcast_mut::<Int64Builder>()
-                        .unwrap();
-                    if let Some(DataType::I64(_, val)) = value {
-                        field_builder.append_value(val);
-                    } else {
-                        field_builder.append_null();
+            };
+
+            let timer = self.metrics.elapsed_point_to_record_batch().timer();
+
+            for (column, builder) in self.columns.iter_mut().zip(builder.iter_mut()) {
+                match column {
+                    SeriesColumn::Time => {
+                        downcast_builder::<TimestampNanosecondBuilder>(builder)?
+                            .append_slice(&timestamps);
                     }
-                }
-                ValueType::Unsigned => {
-                    let field_builder = builder[i]
-                        .as_any_mut()
-                        .downcast_mut::<UInt64Builder>()
-                        .unwrap();
-                    if let Some(DataType::U64(_, val)) = value {
-                        field_builder.append_value(val);
-                    } else {
-                        field_builder.append_null();
+                    SeriesColumn::Tag(value) => {
+                        let builder = downcast_builder::<StringBuilder>(builder)?;
+                        for _ in 0..timestamps.len() {
+                            builder.append_option(value.as_ref());
+                        }
                     }
-                }
-                ValueType::Boolean => {
-                    let field_builder = builder[i]
-                        .as_any_mut()
-                        .downcast_mut::<BooleanBuilder>()
-                        .unwrap();
-                    if let Some(DataType::Bool(_, val)) = value {
-                        field_builder.append_value(val);
-                    } else {
-                        field_builder.append_null();
+                    SeriesColumn::Field { buffer, .. } => {
+                        let block = buffer.take_until(max_ts);
+                        append_field_values(builder, &timestamps, &block)?;
                     }
                 }
-                ValueType::String => {
-                    let field_builder = builder[i]
-                        .as_any_mut()
-                        .downcast_mut::<StringBuilder>()
-                        .unwrap();
-                    if let Some(DataType::Str(_, val)) = value {
-                        field_builder.append_value(
-                            String::from_utf8(val.to_vec()).map_err(|_| Error::ErrCharacterSet)?,
-                        );
-                    } else {
-                        field_builder.append_null();
-                    }
+            }
+
+            timer.done();
+
+            return Ok(timestamps.len());
+        }
+    }
+
+    /// Fast path of a series with only one field without filter, the next block of
+    /// the field is decoded into the arrays directly if possible,
+    /// see `FieldCursor::read_block_to_builders`.
+    async fn collect_single_field_block(
+        &mut self,
+        builder: &mut [ArrayBuilderPtr],
+        capacity: usize,
+    ) -> Result<Option<usize>, Error> {
+        let mut time_idx = None;
+        let mut field_idx = None;
+        for (i, column) in self.columns.iter().enumerate() {
+            match column {
+                SeriesColumn::Time => time_idx = Some(i),
+                SeriesColumn::Field {
+                    buffer,
+                    finished: false,
+                    filter: None,
+                    ..
+                } if field_idx.is_none() && buffer.is_consumed() => field_idx = Some(i),
+                SeriesColumn::Field { .. } => return Ok(None),
+                SeriesColumn::Tag(_) => {}
+            }
+        }
+        let field_idx = match field_idx {
+            Some(idx) => idx,
+            None => return Ok(None),
+        };
+
+        // The timestamps are decoded even if the time column is not selected,
+        // they are the rows of the block.
+        let mut unselected_time_builder;
+        let (ts_builder, val_builder) = match time_idx {
+            Some(time_idx) => {
+                let (time_builder, val_builder) = pair_mut(builder, time_idx, field_idx);
+                (
+                    downcast_builder::<TimestampNanosecondBuilder>(time_builder)?,
+                    val_builder,
+                )
+            }
+            None => {
+                unselected_time_builder = TimestampNanosecondBuilder::with_capacity(capacity);
+                (&mut unselected_time_builder, &mut builder[field_idx])
+            }
+        };
+        let rows = match &mut self.columns[field_idx] {
+            SeriesColumn::Field { cursor, .. } => {
+                cursor
+                    .read_block_to_builders(
+                        ts_builder,
+                        val_builder.as_mut(),
+                        capacity,
+                        &self.metrics,
+                    )
+                    .await?
+            }
+            _ => None,
+        };
+        let rows = match rows {
+            Some(rows) => rows,
+            None => return Ok(None),
+        };
+
+        for (column, builder) in self.columns.iter().zip(builder.iter_mut()) {
+            if let SeriesColumn::Tag(value) = column {
+                let builder = downcast_builder::<StringBuilder>(builder)?;
+                for _ in 0..rows {
+                    builder.append_option(value.as_ref());
                 }
             }
         }
 
-        timer.done();
+        Ok(Some(rows))
+    }
 
-        Ok(Some(()))
+    /// Timestamps of the rows up to `max_ts` in the buffered blocks.
+    ///
+    /// A row must have values of all filtered fields,
+    /// rows of any field are returned if there is no filter.
+    fn candidate_timestamps(&self, max_ts: i64) -> Vec<i64> {
+        let buffered_ts = |buffer: &BlockBuffer| {
+            let ts = buffer.remaining_ts();
+            &ts[..ts.partition_point(|ts| *ts <= max_ts)]
+        };
+
+        let mut filtered: Option<Vec<i64>> = None;
+        for column in self.columns.iter() {
+            if let SeriesColumn::Field {
+                buffer,
+                filter: Some(_),
+                ..
+            } = column
+            {
+                let ts = buffered_ts(buffer);
+                filtered = Some(match filtered {
+                    Some(mut timestamps) => {
+                        timestamps.retain(|t| ts.binary_search(t).is_ok());
+                        timestamps
+                    }
+                    None => ts.to_vec(),
+                });
+            }
+        }
+        if let Some(timestamps) = filtered {
+            return timestamps;
+        }
+
+        let mut timestamps = vec![];
+        for column in self.columns.iter() {
+            if let SeriesColumn::Field { buffer, .. } = column {
+                timestamps.extend_from_slice(buffered_ts(buffer));
+            }
+        }
+        timestamps.sort_unstable();
+        timestamps.dedup();
+        timestamps
     }
 
     async fn collect_aggregate_row_data(
@@ -879,84 +1105,161 @@ impl RowIterator {
         debug!("======collect_aggregate_row_data=========");
         let time_ranges: Vec<TimeRange> = filter_to_time_ranges(&self.option.time_filter);
         let time_ranges = Arc::new(time_ranges);
-        if self.version.is_none() {
-            return Ok(None);
+        let version = match self.version.clone() {
+            Some(version) => version,
+            None => return Ok(None),
+        };
+        let aggregates = match self.option.aggregates.clone() {
+            Some(aggregates) => aggregates,
+            None => return Ok(None),
+        };
+
+        let statistics = if self.has_field_filter() {
+            self.aggregate_filtered_rows(&aggregates).await?
+        } else {
+            let mut statistics = Vec::with_capacity(aggregates.len());
+            for aggregate in aggregates.iter() {
+                statistics.push(
+                    self.aggregate_column(version.clone(), aggregate, time_ranges.clone())
+                        .await?,
+                );
+            }
+            statistics
+        };
+
+        let fields = self.option.df_schema.fields();
+        for (i, (aggregate, statistics)) in aggregates.iter().zip(statistics.iter()).enumerate() {
+            append_aggregate_value(
+                &mut builder[i],
+                fields[i].data_type(),
+                &aggregate.function,
+                statistics,
+            )?;
         }
-        let version = self.version.clone().unwrap();
-        if let Some(aggregates) = self.option.aggregates.as_ref() {
-            for (i, item) in aggregates.iter().enumerate() {
-                match item.column_type {
-                    ColumnType::Tag => todo!(),
-                    ColumnType::Time => {
-                        let agg_ret = count_column_non_null_values(
-                            version.clone(),
-                            &self.series,
-                            None,
-                            time_ranges.clone(),
-                        )
+
+        Ok(Some(()))
+    }
+
+    /// Aggregates a column from the index and statistics of blocks if possible.
+    async fn aggregate_column(
+        &self,
+        version: Arc<SuperVersion>,
+        aggregate: &PushedAggregate,
+        time_ranges: Arc<Vec<TimeRange>>,
+    ) -> Result<ValueStatistics, Error> {
+        let column = &aggregate.column;
+        let column_id = match column.column_type {
+            ColumnType::Tag => {
+                return Err(Error::CommonError {
+                    reason: format!("aggregate of tag {} is not supported", column.name),
+                });
+            }
+            ColumnType::Time => None,
+            ColumnType::Field(ValueType::Unknown) => {
+                return Err(Error::CommonError {
+                    reason: format!("unknown type of {}", column.name),
+                });
+            }
+            ColumnType::Field(_) => Some(column.id),
+        };
+
+        match (&aggregate.function, column_id) {
+            (PushedAggregateFunction::Count(_), _) | (_, None) => {
+                let count =
+                    count_column_non_null_values(version, &self.series, column_id, time_ranges)
                         .await?;
-                        let field_builder = builder[i]
-                            .as_any_mut()
-                            .downcast_mut::<Int64Builder>()
-                            .unwrap();
-                        field_builder.append_value(agg_ret as i64);
-                    }
-                    ColumnType::Field(vtype) => match vtype {
-                        ValueType::Unknown => {
-                            return Err(Error::CommonError {
-                                reason: format!("unknown type of {}", self.columns[i].name()),
-                            });
-                        }
-                        _ => {
-                            let agg_ret = count_column_non_null_values(
-                                version.clone(),
-                                &self.series,
-                                Some(item.id),
-                                time_ranges.clone(),
-                            )
-                            .await?;
-                            let field_builder = builder[i]
-                                .as_any_mut()
-                                .downcast_mut::<Int64Builder>()
-                                .unwrap();
-                            field_builder.append_value(agg_ret as i64);
-                            // field_builder.append_null();
-                        }
-                    },
-                };
+                Ok(ValueStatistics {
+                    count,
+                    ..Default::default()
+                })
+            }
+            (_, Some(column_id)) => {
+                aggregate_field_values(version, &self.series, column_id, time_ranges).await
             }
+        }
+    }
 
-            Ok(Some(()))
-        } else {
-            Ok(None)
+    /// Aggregates the columns by scanning the rows,
+    /// used when the aggregates can not be got from the index for field filters.
+    async fn aggregate_filtered_rows(
+        &mut self,
+        aggregates: &[PushedAggregate],
+    ) -> Result<Vec<ValueStatistics>, Error> {
+        let schema_columns = self.option.table_schema.columns();
+        let position = |predicate: &dyn Fn(&TableColumn) -> bool, name: &str| {
+            schema_columns
+                .iter()
+                .position(predicate)
+                .ok_or_else(|| Error::CommonError {
+                    reason: format!("column {} not found", name),
+                })
+        };
+        let indices = aggregates
+            .iter()
+            .map(|aggregate| position(&|c| c.id == aggregate.column.id, &aggregate.column.name))
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let mut statistics = vec![ValueStatistics::default(); aggregates.len()];
+        while self.next_series().await?.is_some() {
+            loop {
+                let mut builder = self.series_builder();
+                if self
+                    .collect_series_data(&mut builder, self.batch_size)
+                    .await?
+                    == 0
+                {
+                    break;
+                }
+                let arrays: Vec<ArrayRef> = builder.iter_mut().map(|b| b.finish()).collect();
+                for (statistics, idx) in statistics.iter_mut().zip(indices.iter()) {
+                    update_statistics(statistics, &arrays[*idx]);
+                }
+            }
         }
+
+        Ok(statistics)
     }
 
-    async fn next_row(&mut self, builder: &mut [ArrayBuilderPtr]) -> Result<Option<()>, Error> {
+    /// Appends at most `capacity` rows to `builder`, returns the number of appended rows,
+    /// 0 if the iterator is finished.
+    async fn next_rows(
+        &mut self,
+        builder: &mut [ArrayBuilderPtr],
+        capacity: usize,
+    ) -> Result<usize, Error> {
         if self.option.aggregates.is_some() {
-            self.collect_aggregate_row_data(builder).await
+            let rows = self.collect_aggregate_row_data(builder).await?;
+            Ok(rows.map(|_| 1).unwrap_or_default())
         } else {
             loop {
                 if self.columns.is_empty() && self.next_series().await?.is_none() {
-                    return Ok(None);
+                    return Ok(0);
                 }
 
-                if self.collect_row_data(builder).await?.is_some() {
-                    return Ok(Some(()));
+                let rows = self.collect_series_data(builder, capacity).await?;
+                if rows > 0 {
+                    return Ok(rows);
                 }
             }
         }
     }
 
     fn record_builder(&self) -> Vec<ArrayBuilderPtr> {
-        if let Some(aggregates) = self.option.aggregates.as_ref() {
+        if self.option.aggregates.is_some() {
             // TODO: Correct the aggregate columns order.
-            let mut builders: Vec<ArrayBuilderPtr> = Vec::with_capacity(aggregates.len());
-            for _agg in self.option.aggregates.iter() {
-                builders.push(Box::new(Int64Builder::with_capacity(self.batch_size)));
-            }
-            return builders;
+            return self
+                .option
+                .df_schema
+                .fields()
+                .iter()
+                .map(|field| make_builder(field.data_type(), self.batch_size))
+                .collect();
         }
+
+        self.series_builder()
+    }
+
+    fn series_builder(&self) -> Vec<ArrayBuilderPtr> {
         let mut builders: Vec<ArrayBuilderPtr> =
             Vec::with_capacity(self.option.table_schema.columns().len());
         for item in self.option.table_schema.columns().iter() {
@@ -1013,17 +1316,31 @@ impl RowIterator {
             return None;
         }
 
+        // Rows are not ordered, any rows satisfy the pushed limit
+        let capacity = match (self.option.filter.limit(), &self.option.aggregates) {
+            (Some(limit), None) => self
+                .batch_size
+                .min(limit.saturating_sub(self.returned_rows)),
+            _ => self.batch_size,
+        };
+        if capacity == 0 {
+            self.finished = true;
+            return None;
+        }
+
         let timer = self.metrics.elapsed_point_to_record_batch().timer();
         let mut builder = self.record_builder();
         timer.done();
 
-        for _ in 0..self.batch_size {
-            match self.next_row(&mut builder).await {
-                Ok(Some(_)) => {}
-                Ok(None) => break,
+        let mut rows = 0;
+        while rows < capacity {
+            match self.next_rows(&mut builder, capacity - rows).await {
+                Ok(0) => break,
+                Ok(n) => rows += n,
                 Err(err) => return Some(Err(err)),
             };
         }
+        self.returned_rows += rows;
 
         let timer = self.metrics.elapsed_point_to_record_batch().timer();
         let result = {
@@ -1044,3 +1361,262 @@ impl RowIterator {
         result
     }
 }
+
+/// Returns mutable references to two different elements of `slice`.
+fn pair_mut<T>(slice: &mut [T], a: usize, b: usize) -> (&mut T, &mut T) {
+    assert_ne!(a, b);
+    if a < b {
+        let (left, right) = slice.split_at_mut(b);
+        (&mut left[a], &mut right[0])
+    } else {
+        let (left, right) = slice.split_at_mut(a);
+        (&mut right[0], &mut left[b])
+    }
+}
+
+fn downcast_builder<T: ArrayBuilder>(builder: &mut ArrayBuilderPtr) -> Result<&mut T, Error> {
+    builder
+        .as_any_mut()
+        .downcast_mut::<T>()
+        .ok_or_else(|| Error::CommonError {
+            reason: "mismatched array builder".to_string(),
+        })
+}
+
+/// Appends the result of a pushed down aggregate function.
+fn append_aggregate_value(
+    builder: &mut ArrayBuilderPtr,
+    data_type: &ArrowDataType,
+    function: &PushedAggregateFunction,
+    statistics: &ValueStatistics,
+) -> Result<(), Error> {
+    let value = match function {
+        PushedAggregateFunction::Count(_) => {
+            downcast_builder::<Int64Builder>(builder)?.append_value(statistics.count as i64);
+            return Ok(());
+        }
+        PushedAggregateFunction::Min(_) => statistics.min,
+        PushedAggregateFunction::Max(_) => statistics.max,
+        PushedAggregateFunction::Sum(_) => statistics.sum,
+        PushedAggregateFunction::FloatSum(_) => statistics.float_sum.map(StatValue::F64),
+    };
+
+    match (data_type, value) {
+        (ArrowDataType::UInt64, Some(StatValue::U64(val))) => {
+            downcast_builder::<UInt64Builder>(builder)?.append_value(val)
+        }
+        (ArrowDataType::Int64, Some(StatValue::I64(val))) => {
+            downcast_builder::<Int64Builder>(builder)?.append_value(val)
+        }
+        (ArrowDataType::Float64, Some(StatValue::F64(val))) => {
+            downcast_builder::<Float64Builder>(builder)?.append_value(val)
+        }
+        (ArrowDataType::Boolean, Some(StatValue::Bool(val))) => {
+            downcast_builder::<BooleanBuilder>(builder)?.append_value(val)
+        }
+        (ArrowDataType::UInt64, None) => downcast_builder::<UInt64Builder>(builder)?.append_null(),
+        (ArrowDataType::Int64, None) => downcast_builder::<Int64Builder>(builder)?.append_null(),
+        (ArrowDataType::Float64, None) => {
+            downcast_builder::<Float64Builder>(builder)?.append_null()
+        }
+        (ArrowDataType::Boolean, None) => {
+            downcast_builder::<BooleanBuilder>(builder)?.append_null()
+        }
+        _ => {
+            return Err(Error::CommonError {
+                reason: format!(
+                    "mismatched aggregate value {:?} of type {}",
+                    value, data_type
+                ),
+            })
+        }
+    }
+
+    Ok(())
+}
+
+/// Updates `statistics` by the non-null values of `array`,
+/// values of strings are only counted.
+fn update_statistics(statistics: &mut ValueStatistics, array: &ArrayRef) {
+    for i in 0..array.len() {
+        if array.is_null(i) {
+            continue;
+        }
+        let value = match array.data_type() {
+            ArrowDataType::UInt64 => {
+                StatValue::U64(as_primitive_array::<UInt64Type>(array).value(i))
+            }
+            ArrowDataType::Int64 => StatValue::I64(as_primitive_array::<Int64Type>(array).value(i)),
+            ArrowDataType::Float64 => {
+                StatValue::F64(as_primitive_array::<Float64Type>(array).value(i))
+            }
+            ArrowDataType::Boolean => StatValue::Bool(as_boolean_array(array).value(i)),
+            _ => {
+                statistics.count += 1;
+                continue;
+            }
+        };
+        statistics.update(value);
+    }
+}
+
+/// Keeps the values of `block` that may be in the domain.
+fn filter_block(block: &DataBlock, domain: &Domain) -> Result<DataBlock, Error> {
+    let mut filtered = DataBlock::new(block.len(), block.field_type());
+    for i in 0..block.len() {
+        let data = match block.get(i) {
+            Some(data) => data,
+            None => continue,
+        };
+        let value = match &data {
+            DataType::U64(_, val) => ScalarValue::UInt64(Some(*val)),
+            DataType::I64(_, val) => ScalarValue::Int64(Some(*val)),
+            DataType::F64(_, val) => ScalarValue::Float64(Some(*val)),
+            DataType::Bool(_, val) => ScalarValue::Boolean(Some(*val)),
+            DataType::Str(_, val) => ScalarValue::Utf8(Some(
+                String::from_utf8(val.to_vec()).map_err(|_| Error::ErrCharacterSet)?,
+            )),
+        };
+        if domain.may_contain(&value) {
+            filtered.insert(data);
+        }
+    }
+
+    Ok(filtered)
+}
+
+/// Appends the values of `block` aligned to `timestamps`,
+/// null if the field has no value at the timestamp.
+///
+/// Values at the timestamps not in `timestamps` are skipped.
+fn append_field_values(
+    builder: &mut ArrayBuilderPtr,
+    timestamps: &[i64],
+    block: &DataBlock,
+) -> Result<(), Error> {
+    let block_ts = block.ts();
+    let mut idx = 0;
+    let indices: Vec<Option<usize>> = timestamps
+        .iter()
+        .map(|ts| {
+            while idx < block_ts.len() && block_ts[idx] < *ts {
+                idx += 1;
+            }
+            if block_ts.get(idx) == Some(ts) {
+                idx += 1;
+                Some(idx - 1)
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    match block {
+        DataBlock::F64 { val, .. } => {
+            append_primitive_values(downcast_builder::<Float64Builder>(builder)?, &indices, val)
+        }
+        DataBlock::I64 { val, .. } => {
+            append_primitive_values(downcast_builder::<Int64Builder>(builder)?, &indices, val)
+        }
+        DataBlock::U64 { val, .. } => {
+            append_primitive_values(downcast_builder::<UInt64Builder>(builder)?, &indices, val)
+        }
+        DataBlock::Bool { val, .. } => {
+            let builder = downcast_builder::<BooleanBuilder>(builder)?;
+            if is_all_taken(&indices, val.len()) {
+                builder.append_slice(val);
+            } else {
+                let (values, validity) = take_values(&indices, val);
+                builder.append_values(&values, &validity);
+            }
+        }
+        DataBlock::Str { val, .. } => {
+            let builder = downcast_builder::<StringBuilder>(builder)?;
+            for idx in indices {
+                match idx {
+                    Some(idx) => builder.append_value(
+                        std::str::from_utf8(&val[idx]).map_err(|_| Error::ErrCharacterSet)?,
+                    ),
+                    None => builder.append_null(),
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn append_primitive_values<T: ArrowPrimitiveType>(
+    builder: &mut PrimitiveBuilder<T>,
+    indices: &[Option<usize>],
+    values: &[T::Native],
+) {
+    if is_all_taken(indices, values.len()) {
+        builder.append_slice(values);
+    } else {
+        let (values, validity) = take_values(indices, values);
+        builder.append_values(&values, &validity);
+    }
+}
+
+/// Whether every value is taken and every timestamp has a value.
+fn is_all_taken(indices: &[Option<usize>], len: usize) -> bool {
+    indices.len() == len && indices.iter().all(Option::is_some)
+}
+
+/// Takes the values at `indices`, the missing values are filled with default and invalid.
+fn take_values<T: Copy + Default>(indices: &[Option<usize>], values: &[T]) -> (Vec<T>, Vec<bool>) {
+    indices
+        .iter()
+        .map(|idx| match idx {
+            Some(idx) => (values[*idx], true),
+            None => (T::default(), false),
+        })
+        .unzip()
+}
+
+#[cfg(test)]
+mod test {
+    use datafusion::arrow::array::{Array, Float64Array};
+
+    use super::{append_field_values, ArrayBuilderPtr};
+    use crate::tsm::DataBlock;
+
+    #[test]
+    fn test_append_field_values() {
+        let mut builder: ArrayBuilderPtr = Box::new(super::Float64Builder::with_capacity(4));
+        let block = DataBlock::F64 {
+            ts: vec![1, 3],
+            val: vec![1.0, 3.0],
+            enc: Default::default(),
+        };
+        append_field_values(&mut builder, &[1, 2, 3, 4], &block).unwrap();
+
+        let array = builder.finish();
+        let array = array.as_any().downcast_ref::<Float64Array>().unwrap();
+        assert_eq!(array.len(), 4);
+        assert_eq!(array.null_count(), 2);
+        assert_eq!(array.value(0), 1.0);
+        assert!(array.is_null(1));
+        assert_eq!(array.value(2), 3.0);
+        assert!(array.is_null(3));
+    }
+
+    #[test]
+    fn test_append_filtered_field_values() {
+        let mut builder: ArrayBuilderPtr = Box::new(super::Float64Builder::with_capacity(2));
+        let block = DataBlock::F64 {
+            ts: vec![1, 3],
+            val: vec![1.0, 3.0],
+            enc: Default::default(),
+        };
+        // the row at timestamp 1 is filtered out
+        append_field_values(&mut builder, &[2, 3], &block).unwrap();
+
+        let array = builder.finish();
+        let array = array.as_any().downcast_ref::<Float64Array>().unwrap();
+        assert_eq!(array.len(), 2);
+        assert!(array.is_null(0));
+        assert_eq!(array.value(1), 3.0);
+    }
+}
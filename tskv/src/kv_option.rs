@@ -5,6 +5,7 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use config::Config;
+use models::codec::Encoding;
 
 use crate::TseriesFamilyId;
 
@@ -45,6 +46,7 @@ pub struct StorageOptions {
     pub max_compact_size: u64,
     pub max_concurrent_compaction: u16,
     pub strict_write: bool,
+    pub io_rate_limit: u64,
 }
 
 // database/data/ts_family_id/tsm
@@ -103,6 +105,7 @@ impl From<&Config> for StorageOptions {
             max_compact_size: config.storage.max_compact_size,
             max_concurrent_compaction: config.storage.max_concurrent_compaction,
             strict_write: config.storage.strict_write,
+            io_rate_limit: config.storage.io_rate_limit,
         }
     }
 }
@@ -111,6 +114,9 @@ impl From<&Config> for StorageOptions {
 pub struct QueryOptions {
     pub max_server_connections: u32,
     pub auth_enabled: bool,
+    pub spill_path: PathBuf,
+    pub query_queue_size: u32,
+    pub query_queue_timeout: Duration,
 }
 
 impl From<&Config> for QueryOptions {
@@ -118,6 +124,9 @@ impl From<&Config> for QueryOptions {
         Self {
             max_server_connections: config.query.max_server_connections,
             auth_enabled: config.query.auth_enabled,
+            spill_path: PathBuf::from(config.query.spill_path.clone()),
+            query_queue_size: config.query.query_queue_size,
+            query_queue_timeout: config.query.query_queue_timeout,
         }
     }
 }
@@ -130,6 +139,8 @@ pub struct WalOptions {
     pub max_file_size: u64,
     pub sync: bool,
     pub sync_interval: Duration,
+    /// Encoding to compress points of wal entries.
+    pub compression: Encoding,
 }
 
 impl From<&Config> for WalOptions {
@@ -141,6 +152,15 @@ impl From<&Config> for WalOptions {
             max_file_size: config.wal.max_file_size,
             sync: config.wal.sync,
             sync_interval: config.wal.sync_interval,
+            compression: match config.wal.compression.to_ascii_lowercase().as_str() {
+                "none" => Encoding::Null,
+                "snappy" => Encoding::Snappy,
+                "zstd" => Encoding::Zstd,
+                other => panic!(
+                    "wal.compression must be one of none, snappy and zstd, found '{}'",
+                    other
+                ),
+            },
         }
     }
 }
@@ -149,6 +169,7 @@ impl From<&Config> for WalOptions {
 pub struct CacheOptions {
     pub max_buffer_size: u64,
     pub max_immutable_number: u16,
+    pub block_cache_size: u64,
 }
 
 impl From<&Config> for CacheOptions {
@@ -156,6 +177,7 @@ impl From<&Config> for CacheOptions {
         Self {
             max_buffer_size: config.cache.max_buffer_size,
             max_immutable_number: config.cache.max_immutable_number,
+            block_cache_size: config.cache.block_cache_size,
         }
     }
 }
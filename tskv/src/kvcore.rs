@@ -6,7 +6,6 @@ use std::time::Duration;
 use memory_pool::{MemoryPool, MemoryPoolRef};
 use meta::MetaRef;
 use metrics::metric_register::MetricsRegister;
-use models::codec::Encoding;
 use models::predicate::domain::ColumnDomains;
 use models::schema::{make_owner, DatabaseSchema, TableColumn, DEFAULT_CATALOG};
 use models::utils::unite_id;
@@ -21,9 +20,7 @@ use tokio::sync::mpsc::{self, Receiver, Sender};
 use tokio::sync::{oneshot, RwLock};
 use trace::{debug, error, info, warn};
 
-use crate::compaction::{
-    self, run_flush_memtable_job, CompactTask, FlushReq, LevelCompactionPicker, Picker,
-};
+use crate::compaction::{self, compaction_picker, run_flush_memtable_job, CompactTask, FlushReq};
 use crate::context::{self, GlobalContext, GlobalSequenceContext, GlobalSequenceTask};
 use crate::database::Database;
 use crate::engine::Engine;
@@ -34,14 +31,15 @@ use crate::kv_option::{Options, StorageOptions};
 use crate::schema::error::SchemaError;
 use crate::summary::{Summary, SummaryProcessor, SummaryTask, VersionEdit};
 use crate::tseries_family::{SuperVersion, TimeRange};
-use crate::tsm::codec::get_str_codec;
 use crate::version_set::VersionSet;
-use crate::wal::{WalEntryType, WalManager, WalTask};
+use crate::wal::{WalEntryBlock, WalManager, WalTask};
 use crate::{database, file_utils, Error, TseriesFamilyId};
 
 pub const COMPACT_REQ_CHANNEL_CAP: usize = 16;
 pub const SUMMARY_REQ_CHANNEL_CAP: usize = 16;
 pub const GLOBAL_TASK_REQ_CHANNEL_CAP: usize = 16;
+/// Interval to delete WAL files whose data are flushed.
+const WAL_TRUNCATE_INTERVAL: Duration = Duration::from_secs(10);
 
 #[derive(Debug)]
 pub struct TsKv {
@@ -95,6 +93,10 @@ impl TsKv {
         .await;
         let global_seq_ctx = version_set.read().await.get_global_sequence_context().await;
         let global_seq_ctx = Arc::new(global_seq_ctx);
+        summary
+            .global_context()
+            .io_limiter()
+            .set_rate(shared_options.storage.io_rate_limit);
 
         let core = Self {
             options: shared_options.clone(),
@@ -194,22 +196,6 @@ impl TsKv {
     }
 
     pub(crate) fn run_wal_job(&self, mut wal_manager: WalManager, mut receiver: Receiver<WalTask>) {
-        async fn on_write(
-            wal_manager: &mut WalManager,
-            points: Arc<Vec<u8>>,
-            cb: oneshot::Sender<Result<(u64, usize)>>,
-            id: TseriesFamilyId,
-            tenant: Arc<Vec<u8>>,
-        ) {
-            let ret = wal_manager
-                .write(WalEntryType::Write, points, id, tenant)
-                .await;
-            let send_ret = cb.send(ret);
-            if let Err(e) = send_ret {
-                warn!("send WAL write result failed: {:?}", e);
-            }
-        }
-
         async fn on_tick(wal_manager: &WalManager) {
             if let Err(e) = wal_manager.sync().await {
                 error!("Failed flushing WAL file: {:?}", e);
@@ -225,43 +211,46 @@ impl TsKv {
         }
 
         info!("Job 'WAL' starting.");
+        let max_group_size = self.options.wal.wal_req_channel_cap.max(1);
         let mut close_receiver = self.close_sender.subscribe();
         let _ = self.runtime.spawn(async move {
             info!("Job 'WAL' started.");
 
             let sync_interval = wal_manager.sync_interval();
-            if sync_interval == Duration::ZERO {
-                loop {
-                    tokio::select! {
-                        wal_task = receiver.recv() => {
-                            match wal_task {
-                                Some(WalTask::Write { id, points, tenant, cb }) => on_write(&mut wal_manager, points, cb, id, tenant).await,
-                                _ => break
-                            }
-                        }
-                        _close_task = close_receiver.recv() => {
-                            on_cancel(wal_manager).await;
-                            break;
-                        }
-                    }
-                }
+            // The sync ticker is not polled if sync_interval is zero.
+            let mut sync_ticker = tokio::time::interval(if sync_interval.is_zero() {
+                WAL_TRUNCATE_INTERVAL
             } else {
-                let mut ticker = tokio::time::interval(sync_interval);
-                loop {
-                    tokio::select! {
-                        wal_task = receiver.recv() => {
-                            match wal_task {
-                                Some(WalTask::Write { id, points, tenant, cb }) => on_write(&mut wal_manager, points, cb, id, tenant).await,
-                                _ => break
+                sync_interval
+            });
+            let mut truncate_ticker = tokio::time::interval(WAL_TRUNCATE_INTERVAL);
+            loop {
+                tokio::select! {
+                    wal_task = receiver.recv() => {
+                        let task = match wal_task {
+                            Some(t) => t,
+                            None => break,
+                        };
+                        // Writes queued while the previous group was writing are written
+                        // as a group, and synced once.
+                        let mut tasks = vec![task];
+                        while tasks.len() < max_group_size {
+                            match receiver.try_recv() {
+                                Ok(t) => tasks.push(t),
+                                Err(_) => break,
                             }
                         }
-                        _ = ticker.tick() => {
-                            on_tick(&wal_manager).await;
-                        }
-                        _close_task = close_receiver.recv() => {
-                            on_cancel(wal_manager).await;
-                            break;
-                        }
+                        wal_manager.write_tasks(tasks).await;
+                    }
+                    _ = sync_ticker.tick(), if !sync_interval.is_zero() => {
+                        on_tick(&wal_manager).await;
+                    }
+                    _ = truncate_ticker.tick() => {
+                        wal_manager.truncate().await;
+                    }
+                    _close_task = close_receiver.recv() => {
+                        on_cancel(wal_manager).await;
+                        break;
                     }
                 }
             }
@@ -424,10 +413,7 @@ impl Engine for TsKv {
         let mut seq = 0;
         if self.options.wal.enabled {
             let (cb, rx) = oneshot::channel();
-            let mut enc_points = Vec::with_capacity(points.len() / 2);
-            get_str_codec(Encoding::Zstd)
-                .encode(&[&points], &mut enc_points)
-                .map_err(|_| Error::Send)?;
+            let enc_points = WalEntryBlock::encode_points(self.options.wal.compression, &points)?;
             self.wal_sender
                 .send(WalTask::Write {
                     id,
@@ -918,9 +904,22 @@ impl Engine for TsKv {
                     }
                 }
 
-                let picker = LevelCompactionPicker::new(self.options.storage.clone());
                 let version = ts_family.read().await.version();
-                if let Some(req) = picker.pick_compaction(version) {
+                let db_options = self
+                    .version_set
+                    .read()
+                    .await
+                    .get_database_options(&version.database)
+                    .await
+                    .unwrap_or_default();
+                let picker = compaction_picker(self.options.storage.clone(), &db_options);
+                if let Some(mut req) = picker.pick_compaction(version) {
+                    req.field_encodings = self
+                        .version_set
+                        .read()
+                        .await
+                        .get_field_encodings(&req.database, req.ts_family_id)
+                        .await;
                     match compaction::run_compaction_job(req, self.global_ctx.clone()).await {
                         Ok(Some((version_edit, file_metas))) => {
                             let (summary_tx, _summary_rx) = oneshot::channel();
@@ -948,6 +947,11 @@ impl Engine for TsKv {
         Ok(())
     }
 
+    fn set_io_rate_limit(&self, bytes_per_sec: u64) {
+        info!("Set io rate limit to {} bytes per second", bytes_per_sec);
+        self.global_ctx.io_limiter().set_rate(bytes_per_sec);
+    }
+
     async fn close(&self) {
         let (tx, mut rx) = mpsc::channel(1);
         if let Err(e) = self.close_sender.send(tx) {
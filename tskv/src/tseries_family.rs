@@ -27,7 +27,7 @@ use crate::file_utils::{make_delta_file_name, make_tsm_file_name};
 use crate::kv_option::{CacheOptions, StorageOptions};
 use crate::memcache::{DataType, FieldVal, MemCache, RowGroup};
 use crate::summary::{CompactMeta, VersionEdit};
-use crate::tsm::{DataBlock, TsmReader, TsmTombstone};
+use crate::tsm::{BlockCache, DataBlock, TsmReader, TsmTombstone, VnodeBlockCache};
 use crate::{ColumnFileId, LevelId, TseriesFamilyId};
 
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -649,6 +649,7 @@ pub struct SuperVersion {
     pub storage_opt: Arc<StorageOptions>,
     pub caches: CacheGroup,
     pub version: Arc<Version>,
+    pub block_cache: Arc<VnodeBlockCache>,
     pub version_number: u64,
 }
 
@@ -658,6 +659,7 @@ impl SuperVersion {
         storage_opt: Arc<StorageOptions>,
         caches: CacheGroup,
         version: Arc<Version>,
+        block_cache: Arc<VnodeBlockCache>,
         version_number: u64,
     ) -> Self {
         Self {
@@ -665,6 +667,7 @@ impl SuperVersion {
             storage_opt,
             caches,
             version,
+            block_cache,
             version_number,
         }
     }
@@ -699,7 +702,9 @@ impl SuperVersion {
 #[derive(Debug)]
 pub struct TsfMetrics {
     vnode_disk_storage: U64Gauge,
+    /// Size of memcaches and cached data blocks of the vnode.
     vnode_cache_size: U64Gauge,
+    memcache_size: AtomicU64,
 }
 
 impl TsfMetrics {
@@ -722,6 +727,7 @@ impl TsfMetrics {
         Self {
             vnode_disk_storage: disk_storage_gauge,
             vnode_cache_size: cache_gauge,
+            memcache_size: AtomicU64::new(0),
         }
     }
 
@@ -729,8 +735,26 @@ impl TsfMetrics {
         self.vnode_disk_storage.set(size)
     }
 
-    pub fn record_cache_size(&self, size: u64) {
-        self.vnode_cache_size.set(size)
+    /// Records the size of memcaches, the gauge of cache size is changed by the difference
+    /// since the size of cached data blocks is added to it by the `VnodeBlockCache`.
+    pub fn record_memcache_size(&self, size: u64) {
+        let prev = self.memcache_size.swap(size, Ordering::Relaxed);
+        if size >= prev {
+            self.vnode_cache_size.inc(size - prev);
+        } else {
+            self.vnode_cache_size.dec(prev - size);
+        }
+    }
+
+    pub fn cache_size_gauge(&self) -> U64Gauge {
+        self.vnode_cache_size.clone()
+    }
+}
+
+impl Drop for TsfMetrics {
+    fn drop(&mut self) {
+        // Cached data blocks are subtracted when they are evicted.
+        self.record_memcache_size(0);
     }
 }
 
@@ -753,6 +777,7 @@ pub struct TseriesFamily {
     compact_task_sender: Sender<CompactTask>,
     cancellation_token: CancellationToken,
     memory_pool: MemoryPoolRef,
+    block_cache: Arc<VnodeBlockCache>,
     tsf_metrics: TsfMetrics,
 }
 
@@ -769,11 +794,15 @@ impl TseriesFamily {
         flush_task_sender: Sender<FlushReq>,
         compact_task_sender: Sender<CompactTask>,
         memory_pool: MemoryPoolRef,
+        block_cache: Arc<BlockCache>,
         register: &Arc<MetricsRegister>,
     ) -> Self {
         let mm = Arc::new(RwLock::new(cache));
         let seq = version.last_seq;
         let max_level_ts = version.max_level_ts;
+        let tsf_metrics = TsfMetrics::new(register, database.as_str(), tf_id as u64);
+        let block_cache =
+            Arc::new(block_cache.vnode_cache(tf_id, &memory_pool, tsf_metrics.cache_size_gauge()));
 
         Self {
             tf_id,
@@ -789,6 +818,7 @@ impl TseriesFamily {
                     immut_cache: Default::default(),
                 },
                 version.clone(),
+                block_cache.clone(),
                 0,
             )),
             super_version_id: AtomicU64::new(0),
@@ -802,7 +832,8 @@ impl TseriesFamily {
             compact_task_sender,
             cancellation_token: CancellationToken::new(),
             memory_pool,
-            tsf_metrics: TsfMetrics::new(register, database.as_str(), tf_id as u64),
+            block_cache,
+            tsf_metrics,
         }
     }
 
@@ -815,7 +846,7 @@ impl TseriesFamily {
     fn new_super_version(&mut self, version: Arc<Version>) {
         self.super_version_id.fetch_add(1, Ordering::SeqCst);
         self.tsf_metrics.record_disk_storage(self.disk_storage());
-        self.tsf_metrics.record_cache_size(self.cache_size());
+        self.tsf_metrics.record_memcache_size(self.cache_size());
         self.super_version = Arc::new(SuperVersion::new(
             self.tf_id,
             self.storage_opt.clone(),
@@ -824,6 +855,7 @@ impl TseriesFamily {
                 immut_cache: self.immut_cache.clone(),
             },
             version,
+            self.block_cache.clone(),
             self.super_version_id.load(Ordering::SeqCst),
         ))
     }
@@ -1109,7 +1141,7 @@ pub mod test_tseries_family {
     use crate::memcache::{FieldVal, MemCache, RowData, RowGroup};
     use crate::summary::{CompactMeta, SummaryTask, VersionEdit};
     use crate::tseries_family::{TimeRange, TseriesFamily, Version};
-    use crate::tsm::TsmTombstone;
+    use crate::tsm::{BlockCache, TsmTombstone};
     use crate::version_set::VersionSet;
     use crate::TseriesFamilyId;
 
@@ -1362,6 +1394,7 @@ pub mod test_tseries_family {
             flush_task_sender,
             compact_task_sender,
             memory_pool,
+            Arc::new(BlockCache::new(0)),
             &Arc::new(MetricsRegister::default()),
         );
 
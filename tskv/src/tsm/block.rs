@@ -1,7 +1,10 @@
-use std::cmp::min;
+use std::borrow::Borrow;
+use std::cmp::{min, Reverse};
+use std::collections::BinaryHeap;
 use std::fmt::Display;
 
 use minivec::MiniVec;
+use models::codec::Encoding;
 use models::{Timestamp, ValueType};
 use trace::error;
 
@@ -9,8 +12,10 @@ use crate::memcache::DataType;
 use crate::tseries_family::TimeRange;
 use crate::tsm::codec::{
     get_bool_codec, get_f64_codec, get_i64_codec, get_str_codec, get_ts_codec, get_u64_codec,
-    DataBlockEncoding,
+    resolve_encoding, select_bool_encoding, select_f64_encoding, select_i64_encoding,
+    select_str_encoding, select_ts_encoding, select_u64_encoding, DataBlockEncoding,
 };
+use crate::{Error, Result};
 
 pub trait ByTimeRange {
     fn time_range(&self) -> Option<TimeRange>;
@@ -234,6 +239,23 @@ impl DataBlock {
         }
     }
 
+    /// Returns the estimated memory size in bytes of the timestamps and values.
+    pub fn size(&self) -> usize {
+        match self {
+            Self::U64 { ts, val, .. } => (ts.len() + val.len()) * 8,
+            Self::I64 { ts, val, .. } => (ts.len() + val.len()) * 8,
+            Self::F64 { ts, val, .. } => (ts.len() + val.len()) * 8,
+            Self::Str { ts, val, .. } => {
+                ts.len() * 8
+                    + val
+                        .iter()
+                        .map(|v| v.len() + std::mem::size_of::<MiniVec<u8>>())
+                        .sum::<usize>()
+            }
+            Self::Bool { ts, val, .. } => ts.len() * 8 + val.len(),
+        }
+    }
+
     /// Returns the `ValueType` by this `DataBlock` variant.
     pub fn field_type(&self) -> ValueType {
         match &self {
@@ -408,6 +430,116 @@ impl DataBlock {
         }
     }
 
+    /// Merges sorted `DataBlock`s of the same type into one sorted `DataBlock`
+    /// without duplicate timestamps, if many (timestamp, value) conflict with the same
+    /// timestamp, use the value of the last block.
+    ///
+    /// Returns `None` if `blocks` is empty, returns error if the types of `blocks` are different.
+    pub fn merge_sorted<B: Borrow<DataBlock>>(blocks: &[B]) -> Result<Option<Self>> {
+        fn inputs<'a, T>(
+            blocks: &[&'a DataBlock],
+            values: impl Fn(&'a DataBlock) -> Option<(&'a [i64], &'a [T])>,
+        ) -> Result<Vec<(&'a [i64], &'a [T])>> {
+            blocks
+                .iter()
+                .map(|&b| {
+                    values(b).ok_or_else(|| Error::CommonError {
+                        reason: format!(
+                            "failed to merge data blocks of different types {:?} and {:?}",
+                            blocks[0].field_type(),
+                            b.field_type()
+                        ),
+                    })
+                })
+                .collect()
+        }
+
+        let blocks = blocks.iter().map(|b| b.borrow()).collect::<Vec<_>>();
+        let first = match blocks.first() {
+            Some(&first) => first,
+            None => return Ok(None),
+        };
+        let enc = first.encodings();
+        let block = match first {
+            DataBlock::U64 { .. } => {
+                let inputs = inputs(&blocks, |b| match b {
+                    DataBlock::U64 { ts, val, .. } => Some((ts.as_slice(), val.as_slice())),
+                    _ => None,
+                })?;
+                let (ts, val) = merge_sorted_values(&inputs);
+                DataBlock::U64 { ts, val, enc }
+            }
+            DataBlock::I64 { .. } => {
+                let inputs = inputs(&blocks, |b| match b {
+                    DataBlock::I64 { ts, val, .. } => Some((ts.as_slice(), val.as_slice())),
+                    _ => None,
+                })?;
+                let (ts, val) = merge_sorted_values(&inputs);
+                DataBlock::I64 { ts, val, enc }
+            }
+            DataBlock::Str { .. } => {
+                let inputs = inputs(&blocks, |b| match b {
+                    DataBlock::Str { ts, val, .. } => Some((ts.as_slice(), val.as_slice())),
+                    _ => None,
+                })?;
+                let (ts, val) = merge_sorted_values(&inputs);
+                DataBlock::Str { ts, val, enc }
+            }
+            DataBlock::F64 { .. } => {
+                let inputs = inputs(&blocks, |b| match b {
+                    DataBlock::F64 { ts, val, .. } => Some((ts.as_slice(), val.as_slice())),
+                    _ => None,
+                })?;
+                let (ts, val) = merge_sorted_values(&inputs);
+                DataBlock::F64 { ts, val, enc }
+            }
+            DataBlock::Bool { .. } => {
+                let inputs = inputs(&blocks, |b| match b {
+                    DataBlock::Bool { ts, val, .. } => Some((ts.as_slice(), val.as_slice())),
+                    _ => None,
+                })?;
+                let (ts, val) = merge_sorted_values(&inputs);
+                DataBlock::Bool { ts, val, enc }
+            }
+        };
+
+        Ok(Some(block))
+    }
+
+    /// Returns a new `DataBlock` with the (ts, val) in this `DataBlock` where index is
+    /// greater equal than `start` and less than `end`.
+    ///
+    /// **Panics** if start or end is out of range of the ts or val in this `DataBlock`.
+    pub fn slice(&self, start: usize, end: usize) -> Self {
+        match self {
+            DataBlock::U64 { ts, val, enc } => DataBlock::U64 {
+                ts: ts[start..end].to_vec(),
+                val: val[start..end].to_vec(),
+                enc: *enc,
+            },
+            DataBlock::I64 { ts, val, enc } => DataBlock::I64 {
+                ts: ts[start..end].to_vec(),
+                val: val[start..end].to_vec(),
+                enc: *enc,
+            },
+            DataBlock::Str { ts, val, enc } => DataBlock::Str {
+                ts: ts[start..end].to_vec(),
+                val: val[start..end].to_vec(),
+                enc: *enc,
+            },
+            DataBlock::F64 { ts, val, enc } => DataBlock::F64 {
+                ts: ts[start..end].to_vec(),
+                val: val[start..end].to_vec(),
+                enc: *enc,
+            },
+            DataBlock::Bool { ts, val, enc } => DataBlock::Bool {
+                ts: ts[start..end].to_vec(),
+                val: val[start..end].to_vec(),
+                enc: *enc,
+            },
+        }
+    }
+
     /// Remove (ts, val) in this `DatBlock` where index is greater equal than `min`
     /// and less than `max`.
     ///
@@ -491,6 +623,20 @@ impl DataBlock {
         min_ts
     }
 
+    /// Returns the encoding `Encoding::Auto` selects for the values of this `DataBlock`.
+    pub fn select_value_encoding(&self) -> Encoding {
+        match self {
+            DataBlock::Bool { val, .. } => select_bool_encoding(val),
+            DataBlock::U64 { val, .. } => select_u64_encoding(val),
+            DataBlock::I64 { val, .. } => select_i64_encoding(val),
+            DataBlock::Str { val, .. } => {
+                let strs: Vec<&[u8]> = val.iter().map(|str| &str[..]).collect();
+                select_str_encoding(&strs)
+            }
+            DataBlock::F64 { val, .. } => select_f64_encoding(val),
+        }
+    }
+
     // todo:
     /// Encodes timestamps and values of this `DataBlock` to bytes.
     pub fn encode(
@@ -500,36 +646,42 @@ impl DataBlock {
         encodings: DataBlockEncoding,
     ) -> Result<(Vec<u8>, Vec<u8>), Box<dyn std::error::Error + Send + Sync>> {
         let (ts_enc, val_enc) = encodings.split();
-        let ts_codec = get_ts_codec(ts_enc);
+        let ts = &self.ts()[start..end];
+        let ts_codec = get_ts_codec(resolve_encoding(ts_enc, || select_ts_encoding(ts)));
 
         let mut ts_buf = vec![];
         let mut data_buf = vec![];
+        ts_codec.encode(ts, &mut ts_buf)?;
         match self {
-            DataBlock::Bool { ts, val, .. } => {
-                ts_codec.encode(&ts[start..end], &mut ts_buf)?;
-                let val_codec = get_bool_codec(val_enc);
-                val_codec.encode(&val[start..end], &mut data_buf)?
+            DataBlock::Bool { val, .. } => {
+                let val = &val[start..end];
+                let val_codec =
+                    get_bool_codec(resolve_encoding(val_enc, || select_bool_encoding(val)));
+                val_codec.encode(val, &mut data_buf)?
             }
-            DataBlock::U64 { ts, val, .. } => {
-                ts_codec.encode(&ts[start..end], &mut ts_buf)?;
-                let val_codec = get_u64_codec(val_enc);
-                val_codec.encode(&val[start..end], &mut data_buf)?
+            DataBlock::U64 { val, .. } => {
+                let val = &val[start..end];
+                let val_codec =
+                    get_u64_codec(resolve_encoding(val_enc, || select_u64_encoding(val)));
+                val_codec.encode(val, &mut data_buf)?
             }
-            DataBlock::I64 { ts, val, .. } => {
-                ts_codec.encode(&ts[start..end], &mut ts_buf)?;
-                let val_codec = get_i64_codec(val_enc);
-                val_codec.encode(&val[start..end], &mut data_buf)?
+            DataBlock::I64 { val, .. } => {
+                let val = &val[start..end];
+                let val_codec =
+                    get_i64_codec(resolve_encoding(val_enc, || select_i64_encoding(val)));
+                val_codec.encode(val, &mut data_buf)?
             }
-            DataBlock::Str { ts, val, .. } => {
-                ts_codec.encode(&ts[start..end], &mut ts_buf)?;
-                let strs: Vec<&[u8]> = val.iter().map(|str| &str[..]).collect();
-                let val_codec = get_str_codec(val_enc);
-                val_codec.encode(&strs[start..end], &mut data_buf)?
+            DataBlock::Str { val, .. } => {
+                let strs: Vec<&[u8]> = val[start..end].iter().map(|str| &str[..]).collect();
+                let val_codec =
+                    get_str_codec(resolve_encoding(val_enc, || select_str_encoding(&strs)));
+                val_codec.encode(&strs, &mut data_buf)?
             }
-            DataBlock::F64 { ts, val, .. } => {
-                ts_codec.encode(&ts[start..end], &mut ts_buf)?;
-                let val_codec = get_f64_codec(val_enc);
-                val_codec.encode(&val[start..end], &mut data_buf)?
+            DataBlock::F64 { val, .. } => {
+                let val = &val[start..end];
+                let val_codec =
+                    get_f64_codec(resolve_encoding(val_enc, || select_f64_encoding(val)));
+                val_codec.encode(val, &mut data_buf)?
             }
         }
         Ok((ts_buf, data_buf))
@@ -645,14 +797,64 @@ fn exclude_slow(v: &mut Vec<MiniVec<u8>>, min_idx: usize, max_idx: usize) {
     v.truncate(len);
 }
 
+/// Merges sorted (timestamps, values) into sorted (timestamps, values) without
+/// duplicate timestamps, the last value of the same timestamp is kept.
+///
+/// The heads of the inputs are kept in a min-heap, so that it takes O(n * log(k))
+/// to merge k inputs of n values.
+fn merge_sorted_values<T: Clone>(inputs: &[(&[i64], &[T])]) -> (Vec<i64>, Vec<T>) {
+    if let [(ts, val)] = inputs {
+        // fast path: a single input without duplicate timestamps is copied directly
+        if ts.windows(2).all(|w| w[0] < w[1]) {
+            return (ts.to_vec(), val.to_vec());
+        }
+    }
+
+    let capacity = inputs.iter().map(|(ts, _)| ts.len()).sum();
+    let mut ts_out = Vec::with_capacity(capacity);
+    let mut val_out = Vec::with_capacity(capacity);
+    let mut offsets = vec![0_usize; inputs.len()];
+    // (timestamp, input index), inputs of the same timestamp are popped in order.
+    let mut heads: BinaryHeap<Reverse<(i64, usize)>> = inputs
+        .iter()
+        .enumerate()
+        .filter_map(|(i, (ts, _))| ts.first().map(|ts| Reverse((*ts, i))))
+        .collect();
+    while let Some(Reverse((min_ts, _))) = heads.peek().copied() {
+        let mut value = None;
+        while let Some(Reverse((ts, i))) = heads.peek().copied() {
+            if ts != min_ts {
+                break;
+            }
+            heads.pop();
+            let (input_ts, input_val) = inputs[i];
+            let off = &mut offsets[i];
+            while input_ts.get(*off) == Some(&min_ts) {
+                value = Some(&input_val[*off]);
+                *off += 1;
+            }
+            if let Some(next_ts) = input_ts.get(*off) {
+                heads.push(Reverse((*next_ts, i)));
+            }
+        }
+        if let Some(value) = value {
+            ts_out.push(min_ts);
+            val_out.push(value.clone());
+        }
+    }
+
+    (ts_out, val_out)
+}
+
 #[cfg(test)]
 pub mod test {
 
     use minivec::mini_vec;
+    use models::codec::Encoding;
 
     use crate::memcache::DataType;
     use crate::tseries_family::TimeRange;
-    use crate::tsm::codec::DataBlockEncoding;
+    use crate::tsm::codec::{select_bool_encoding, DataBlockEncoding};
     use crate::tsm::DataBlock;
 
     pub(crate) fn check_data_block(block: &DataBlock, pattern: &[DataType]) {
@@ -681,6 +883,61 @@ pub mod test {
         ]);
     }
 
+    #[test]
+    fn test_merge_sorted() {
+        #[rustfmt::skip]
+        let res = DataBlock::merge_sorted(
+            &[
+                DataBlock::U64 { ts: vec![1, 3, 5], val: vec![10, 30, 50], enc: DataBlockEncoding::default() },
+                DataBlock::U64 { ts: vec![2, 3, 3, 6], val: vec![21, 31, 32, 61], enc: DataBlockEncoding::default() },
+            ],
+        );
+
+        #[rustfmt::skip]
+        assert_eq!(res.unwrap(), Some(
+            DataBlock::U64 { ts: vec![1, 2, 3, 5, 6], val: vec![10, 21, 32, 50, 61], enc: DataBlockEncoding::default() },
+        ));
+        assert_eq!(DataBlock::merge_sorted::<DataBlock>(&[]).unwrap(), None);
+
+        #[rustfmt::skip]
+        let res = DataBlock::merge_sorted(
+            &[
+                DataBlock::U64 { ts: vec![1, 3], val: vec![10, 30], enc: DataBlockEncoding::default() },
+                DataBlock::I64 { ts: vec![2], val: vec![20], enc: DataBlockEncoding::default() },
+            ],
+        );
+        assert!(res.is_err());
+
+        #[rustfmt::skip]
+        let blk = DataBlock::I64 { ts: vec![1, 2, 3, 4], val: vec![10, 20, 30, 40], enc: DataBlockEncoding::default() };
+        #[rustfmt::skip]
+        assert_eq!(
+            blk.slice(1, 3),
+            DataBlock::I64 { ts: vec![2, 3], val: vec![20, 30], enc: DataBlockEncoding::default() }
+        );
+    }
+
+    #[test]
+    fn test_select_value_encoding() {
+        let ts: Vec<i64> = (0..1000).collect();
+        let blk = DataBlock::I64 {
+            ts: ts.clone(),
+            val: ts.iter().map(|i| i * 10).collect(),
+            enc: DataBlockEncoding::default(),
+        };
+        assert_eq!(blk.select_value_encoding(), Encoding::Delta);
+
+        let blk = DataBlock::Bool {
+            ts,
+            val: vec![true; 1000],
+            enc: DataBlockEncoding::default(),
+        };
+        assert_eq!(
+            blk.select_value_encoding(),
+            select_bool_encoding(&[true; 1000])
+        );
+    }
+
     #[test]
     fn test_data_block_exclude_1() {
         #[rustfmt::skip]
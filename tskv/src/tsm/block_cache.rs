@@ -0,0 +1,207 @@
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use lru_cache::asynchronous::ShardedCache;
+use lru_cache::AfterRemovedFnMut;
+use memory_pool::{MemoryConsumer, MemoryPoolRef, MemoryReservation};
+use metrics::gauge::U64Gauge;
+use parking_lot::Mutex;
+
+use crate::tsm::DataBlock;
+use crate::TseriesFamilyId;
+
+/// Key of a cached data block: (tsm file id, offset of the block in the file),
+/// the file ids are unique in the node.
+pub type BlockCacheKey = (u64, u64);
+
+/// LRU cache of decoded data blocks of all vnodes in the node, bounded by size in bytes.
+///
+/// Blocks are cached through the `VnodeBlockCache` of each vnode,
+/// which reserves the memory of its blocks from the memory pool.
+pub struct BlockCache {
+    capacity: usize,
+    cache: ShardedCache<BlockCacheKey, Arc<DataBlock>>,
+}
+
+impl BlockCache {
+    /// Create a cache of `capacity` bytes, `0` disables the cache.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            cache: ShardedCache::with_capacity(capacity),
+        }
+    }
+
+    /// Create the cache of a vnode, its cached blocks are reserved from `memory_pool`
+    /// and added to `cache_size_gauge` (the cache size of the vnode).
+    pub fn vnode_cache(
+        self: &Arc<Self>,
+        tf_id: TseriesFamilyId,
+        memory_pool: &MemoryPoolRef,
+        cache_size_gauge: U64Gauge,
+    ) -> VnodeBlockCache {
+        VnodeBlockCache {
+            cache: self.clone(),
+            reservation: Arc::new(Mutex::new(
+                MemoryConsumer::new(format!("block-cache-{}", tf_id)).register(memory_pool),
+            )),
+            cache_size_gauge,
+        }
+    }
+}
+
+impl Debug for BlockCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BlockCache")
+            .field("capacity", &self.capacity)
+            .finish()
+    }
+}
+
+/// Blocks of a vnode in the `BlockCache`.
+pub struct VnodeBlockCache {
+    cache: Arc<BlockCache>,
+    /// Memory of the cached blocks, released when they are evicted.
+    reservation: Arc<Mutex<MemoryReservation>>,
+    cache_size_gauge: U64Gauge,
+}
+
+impl VnodeBlockCache {
+    pub fn is_enabled(&self) -> bool {
+        self.cache.capacity > 0
+    }
+
+    pub async fn get(&self, key: &BlockCacheKey) -> Option<Arc<DataBlock>> {
+        if !self.is_enabled() {
+            return None;
+        }
+        self.cache.cache.lock_shard(key).await.get(key).cloned()
+    }
+
+    /// Cache the block unless it is already cached, it is too big
+    /// or the memory pool is exhausted.
+    pub async fn insert(&self, key: BlockCacheKey, block: Arc<DataBlock>) {
+        if !self.is_enabled() {
+            return;
+        }
+        let charge = block.size();
+
+        let after_removed: AfterRemovedFnMut<BlockCacheKey, Arc<DataBlock>> = {
+            let reservation = self.reservation.clone();
+            let cache_size_gauge = self.cache_size_gauge.clone();
+            Box::new(move |_, _| {
+                reservation.lock().shrink(charge);
+                cache_size_gauge.dec(charge as u64);
+            })
+        };
+        let mut cache = self.cache.cache.lock_shard(&key).await;
+        if cache.get(&key).is_some() {
+            return;
+        }
+        // Reserve the memory before inserting, blocks evicted by the insertion are released.
+        if self.reservation.lock().try_grow(charge).is_err() {
+            return;
+        }
+        self.cache_size_gauge.inc(charge as u64);
+        if cache
+            .insert_opt(key, block, charge, Some(after_removed))
+            .is_none()
+        {
+            self.reservation.lock().shrink(charge);
+            self.cache_size_gauge.dec(charge as u64);
+        }
+    }
+
+    /// Returns the memory size of cached blocks of the vnode.
+    pub fn usage(&self) -> usize {
+        self.reservation.lock().size()
+    }
+}
+
+impl Debug for VnodeBlockCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VnodeBlockCache")
+            .field("cache", &self.cache)
+            .field("usage", &self.usage())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use memory_pool::{GreedyMemoryPool, MemoryPool, MemoryPoolRef};
+    use metrics::gauge::U64Gauge;
+
+    use super::BlockCache;
+    use crate::tsm::codec::DataBlockEncoding;
+    use crate::tsm::DataBlock;
+
+    fn i64_block(len: usize) -> Arc<DataBlock> {
+        Arc::new(DataBlock::I64 {
+            ts: (0..len as i64).collect(),
+            val: (0..len as i64).collect(),
+            enc: DataBlockEncoding::default(),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_block_cache() {
+        let pool: MemoryPoolRef = Arc::new(GreedyMemoryPool::new(1024 * 1024));
+        // 16 shards of 1024 bytes.
+        let block_cache = Arc::new(BlockCache::new(16 * 1024));
+        let gauge = U64Gauge::default();
+        let cache = block_cache.vnode_cache(1, &pool, gauge.clone());
+
+        let block = i64_block(32); // 512 bytes
+        cache.insert((1, 0), block.clone()).await;
+        assert_eq!(cache.get(&(1, 0)).await, Some(block.clone()));
+        assert_eq!(cache.get(&(1, 1)).await, None);
+        assert_eq!(cache.usage(), 512);
+        assert_eq!(pool.reserved(), 512);
+        assert_eq!(gauge.fetch(), 512);
+
+        // Inserting a cached block again changes nothing.
+        cache.insert((1, 0), block.clone()).await;
+        assert_eq!(cache.usage(), 512);
+
+        // Blocks bigger than a shard are not cached.
+        cache.insert((1, 2), i64_block(128)).await;
+        assert_eq!(cache.get(&(1, 2)).await, None);
+        assert_eq!(cache.usage(), 512);
+        assert_eq!(pool.reserved(), 512);
+
+        // Blocks are cached in the same cache, but counted by vnodes.
+        let gauge_2 = U64Gauge::default();
+        let cache_2 = block_cache.vnode_cache(2, &pool, gauge_2.clone());
+        cache_2.insert((2, 0), block.clone()).await;
+        assert_eq!(cache.get(&(2, 0)).await, Some(block.clone()));
+        assert_eq!(cache.usage(), 512);
+        assert_eq!(cache_2.usage(), 512);
+        assert_eq!(gauge_2.fetch(), 512);
+        assert_eq!(pool.reserved(), 1024);
+
+        // Evicted blocks are released.
+        for offset in 1..64 {
+            cache_2.insert((2, offset), block.clone()).await;
+        }
+        assert!(cache.usage() + cache_2.usage() <= 16 * 1024);
+        assert_eq!(pool.reserved(), cache.usage() + cache_2.usage());
+        assert_eq!(gauge.fetch(), cache.usage() as u64);
+        assert_eq!(gauge_2.fetch(), cache_2.usage() as u64);
+
+        // Not cached if the memory pool is exhausted.
+        let pool: MemoryPoolRef = Arc::new(GreedyMemoryPool::new(256));
+        let cache = block_cache.vnode_cache(3, &pool, U64Gauge::default());
+        cache.insert((3, 0), block.clone()).await;
+        assert_eq!(cache.get(&(3, 0)).await, None);
+        assert_eq!(cache.usage(), 0);
+
+        // Disabled.
+        let cache = Arc::new(BlockCache::new(0)).vnode_cache(4, &pool, U64Gauge::default());
+        cache.insert((4, 0), block).await;
+        assert_eq!(cache.get(&(4, 0)).await, None);
+        assert_eq!(cache.usage(), 0);
+    }
+}
@@ -0,0 +1,145 @@
+use std::error::Error;
+use std::time::{Duration, Instant};
+
+use models::codec::Encoding;
+
+use crate::tsm::codec::{
+    get_bool_codec, get_f64_codec, get_i64_codec, get_str_codec, get_ts_codec, get_u64_codec,
+};
+
+/// Number of values at the beginning of a block used to select the encoding.
+const SAMPLE_VALUES: usize = 256;
+
+/// Time to try encodings on the samples of a block, the remaining candidates
+/// are not tried once it's exceeded.
+const SELECT_TIME_BUDGET: Duration = Duration::from_micros(500);
+
+// Candidates of encodings of each type, cheaper ones first.
+const INTEGER_CANDIDATES: [Encoding; 6] = [
+    Encoding::Delta,
+    Encoding::Rle,
+    Encoding::DeltaOfDelta,
+    Encoding::Xor,
+    Encoding::Quantile,
+    Encoding::Null,
+];
+const FLOAT_CANDIDATES: [Encoding; 3] = [Encoding::Gorilla, Encoding::Quantile, Encoding::Null];
+const STRING_CANDIDATES: [Encoding; 6] = [
+    Encoding::Snappy,
+    Encoding::Zstd,
+    Encoding::Gzip,
+    Encoding::Zlib,
+    Encoding::Bzip,
+    Encoding::Null,
+];
+const BOOLEAN_CANDIDATES: [Encoding; 2] = [Encoding::BitPack, Encoding::Null];
+
+type EncodeResult = Result<(), Box<dyn Error + Send + Sync>>;
+
+/// Returns `encoding`, or the one returned by `select` if it's `Encoding::Auto`.
+/// The selected encoding is recorded in the encoded data like others.
+pub fn resolve_encoding(encoding: Encoding, select: impl FnOnce() -> Encoding) -> Encoding {
+    match encoding {
+        Encoding::Auto => select(),
+        _ => encoding,
+    }
+}
+
+/// Returns the candidate which encodes samples of `values` into the least bytes.
+fn select_encoding<T>(
+    candidates: &[Encoding],
+    values: &[T],
+    encode: impl Fn(Encoding, &[T], &mut Vec<u8>) -> EncodeResult,
+) -> Encoding {
+    let samples = &values[..values.len().min(SAMPLE_VALUES)];
+    let start = Instant::now();
+    let mut buf = Vec::new();
+    let mut selected: Option<(Encoding, usize)> = None;
+    for encoding in candidates.iter().copied() {
+        if selected.is_some() && start.elapsed() > SELECT_TIME_BUDGET {
+            break;
+        }
+        buf.clear();
+        if encode(encoding, samples, &mut buf).is_err() {
+            continue;
+        }
+        if selected.map(|(_, len)| buf.len() < len).unwrap_or(true) {
+            selected = Some((encoding, buf.len()));
+        }
+    }
+
+    selected.map(|(e, _)| e).unwrap_or(candidates[0])
+}
+
+pub fn select_ts_encoding(values: &[i64]) -> Encoding {
+    select_encoding(&INTEGER_CANDIDATES, values, |e, src, dst| {
+        get_ts_codec(e).encode(src, dst)
+    })
+}
+
+pub fn select_i64_encoding(values: &[i64]) -> Encoding {
+    select_encoding(&INTEGER_CANDIDATES, values, |e, src, dst| {
+        get_i64_codec(e).encode(src, dst)
+    })
+}
+
+pub fn select_u64_encoding(values: &[u64]) -> Encoding {
+    select_encoding(&INTEGER_CANDIDATES, values, |e, src, dst| {
+        get_u64_codec(e).encode(src, dst)
+    })
+}
+
+pub fn select_f64_encoding(values: &[f64]) -> Encoding {
+    select_encoding(&FLOAT_CANDIDATES, values, |e, src, dst| {
+        get_f64_codec(e).encode(src, dst)
+    })
+}
+
+pub fn select_str_encoding(values: &[&[u8]]) -> Encoding {
+    select_encoding(&STRING_CANDIDATES, values, |e, src, dst| {
+        get_str_codec(e).encode(src, dst)
+    })
+}
+
+pub fn select_bool_encoding(values: &[bool]) -> Encoding {
+    select_encoding(&BOOLEAN_CANDIDATES, values, |e, src, dst| {
+        get_bool_codec(e).encode(src, dst)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use models::codec::Encoding;
+
+    use super::{select_encoding, select_i64_encoding, SAMPLE_VALUES};
+
+    #[test]
+    fn test_select_encoding() {
+        // Regular integers are well compressed by delta encoding.
+        let values: Vec<i64> = (0..1000).map(|i| i * 10).collect();
+        assert_eq!(select_i64_encoding(&values), Encoding::Delta);
+
+        // The smallest result is selected, only the samples are encoded.
+        let encoding = select_encoding(
+            &[Encoding::Delta, Encoding::Null],
+            &values,
+            |e, src: &[i64], dst| {
+                assert_eq!(src.len(), SAMPLE_VALUES);
+                let len = if e == Encoding::Null { 1 } else { 2 };
+                dst.resize(len, 0);
+                Ok(())
+            },
+        );
+        assert_eq!(encoding, Encoding::Null);
+
+        // Failed encodings are skipped.
+        let encoding = select_encoding(&[Encoding::Delta, Encoding::Null], &values, |e, _, _| {
+            if e == Encoding::Delta {
+                Err("error".into())
+            } else {
+                Ok(())
+            }
+        });
+        assert_eq!(encoding, Encoding::Null);
+    }
+}
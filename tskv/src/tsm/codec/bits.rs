@@ -0,0 +1,62 @@
+use std::error::Error;
+
+/// Appends bits to the end of a buffer, from the most significant bit of each byte.
+pub struct BitWriter<'a> {
+    dst: &'a mut Vec<u8>,
+    /// Number of unused bits in the last byte of dst.
+    free_bits: u32,
+}
+
+impl<'a> BitWriter<'a> {
+    pub fn new(dst: &'a mut Vec<u8>) -> Self {
+        Self { dst, free_bits: 0 }
+    }
+
+    /// Writes the lowest `n` bits of `value`.
+    pub fn write_bits(&mut self, value: u64, mut n: u32) {
+        while n > 0 {
+            if self.free_bits == 0 {
+                self.dst.push(0);
+                self.free_bits = 8;
+            }
+            let len = n.min(self.free_bits);
+            let bits = ((value >> (n - len)) & ((1 << len) - 1)) as u8;
+            if let Some(last) = self.dst.last_mut() {
+                *last |= bits << (self.free_bits - len);
+            }
+            self.free_bits -= len;
+            n -= len;
+        }
+    }
+}
+
+/// Reads bits written by `BitWriter`.
+pub struct BitReader<'a> {
+    src: &'a [u8],
+    /// Position of the next bit to read.
+    pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    pub fn new(src: &'a [u8]) -> Self {
+        Self { src, pos: 0 }
+    }
+
+    /// Reads `n` bits as the lowest bits of the returned value.
+    pub fn read_bits(&mut self, mut n: u32) -> Result<u64, Box<dyn Error + Send + Sync>> {
+        let mut value = 0_u64;
+        while n > 0 {
+            let byte = *self
+                .src
+                .get(self.pos / 8)
+                .ok_or("not enough bits to decode")?;
+            let avail = 8 - (self.pos % 8) as u32;
+            let len = n.min(avail);
+            let bits = (byte >> (avail - len)) as u64 & ((1 << len) - 1);
+            value = (value << len) | bits;
+            self.pos += len as usize;
+            n -= len;
+        }
+        Ok(value)
+    }
+}
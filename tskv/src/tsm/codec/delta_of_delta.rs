@@ -0,0 +1,251 @@
+use std::error::Error;
+
+use datafusion::arrow::array::{PrimitiveBuilder, UInt64Builder};
+use datafusion::arrow::datatypes::ArrowPrimitiveType;
+use integer_encoding::*;
+
+use crate::tsm::codec::bits::{BitReader, BitWriter};
+use crate::tsm::codec::{Encoding, MAX_VAR_INT_64};
+
+/// Buckets of delta-of-deltas, tried in order: (control bits, length of control bits,
+/// bits of the zig-zag encoded value). A zero delta-of-delta is stored as a single `0` bit.
+const BUCKETS: [(u64, u32, u32); 4] = [
+    (0b10, 2, 7),
+    (0b110, 3, 9),
+    (0b1110, 4, 12),
+    (0b1111, 4, 64),
+];
+
+/// Encodes a vector of signed integers by the deltas of deltas between them,
+/// as timestamps in Gorilla, regular intervals cost one bit per value.
+///
+/// Layout: `| Encoding::DeltaOfDelta | first value (8 bytes) | count (varint) |
+/// first delta (zig-zag varint) | bits of delta-of-deltas |`.
+pub fn i64_delta_of_delta_encode(
+    src: &[i64],
+    dst: &mut Vec<u8>,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    dst.clear();
+    if src.is_empty() {
+        return Ok(());
+    }
+
+    dst.push(Encoding::DeltaOfDelta as u8);
+    dst.extend_from_slice(&src[0].to_be_bytes());
+    put_varint(dst, src.len() as u64);
+    if src.len() == 1 {
+        return Ok(());
+    }
+
+    let mut delta = src[1].wrapping_sub(src[0]);
+    put_varint(dst, delta);
+    let mut writer = BitWriter::new(dst);
+    for w in src[1..].windows(2) {
+        let cur_delta = w[1].wrapping_sub(w[0]);
+        let dod = cur_delta.wrapping_sub(delta);
+        delta = cur_delta;
+        if dod == 0 {
+            writer.write_bits(0, 1);
+            continue;
+        }
+        let zigzag = ((dod << 1) ^ (dod >> 63)) as u64;
+        for (control, control_len, value_len) in BUCKETS {
+            if value_len == 64 || zigzag < 1 << value_len {
+                writer.write_bits(control, control_len);
+                writer.write_bits(zigzag, value_len);
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Decodes a slice of bytes encoded by `i64_delta_of_delta_encode` into a vector of
+/// signed integers.
+pub fn i64_delta_of_delta_decode(
+    src: &[u8],
+    dst: &mut Vec<i64>,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if src.is_empty() {
+        return Ok(());
+    }
+    let (count, _) = decode_header(src)?;
+    dst.reserve(count);
+    decode_values(src, |v| dst.push(v))
+}
+
+pub fn u64_delta_of_delta_encode(
+    src: &[u64],
+    dst: &mut Vec<u8>,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let signed: Vec<i64> = src.iter().map(|v| *v as i64).collect();
+    i64_delta_of_delta_encode(&signed, dst)
+}
+
+pub fn u64_delta_of_delta_decode(
+    src: &[u8],
+    dst: &mut Vec<u64>,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if src.is_empty() {
+        return Ok(());
+    }
+    let (count, _) = decode_header(src)?;
+    dst.reserve(count);
+    decode_values(src, |v| dst.push(v as u64))
+}
+
+/// Decodes a slice of bytes encoded by `i64_delta_of_delta_encode` into an arrow array builder,
+/// without the intermediate vector of `i64_delta_of_delta_decode`.
+pub fn i64_delta_of_delta_decode_to_builder<T: ArrowPrimitiveType<Native = i64>>(
+    src: &[u8],
+    builder: &mut PrimitiveBuilder<T>,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if src.is_empty() {
+        return Ok(());
+    }
+    decode_values(src, |v| builder.append_value(v))
+}
+
+pub fn u64_delta_of_delta_decode_to_builder(
+    src: &[u8],
+    builder: &mut UInt64Builder,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if src.is_empty() {
+        return Ok(());
+    }
+    decode_values(src, |v| builder.append_value(v as u64))
+}
+
+/// Returns the number of values and the offset after it.
+///
+/// The count is checked against the length of `src` before it is used to allocate,
+/// every value after the first takes at least one bit.
+fn decode_header(src: &[u8]) -> Result<(usize, usize), Box<dyn Error + Send + Sync>> {
+    if src.len() < 10 {
+        return Err(From::from("not enough data to decode delta-of-delta"));
+    }
+    let (count, n) = usize::decode_var(&src[9..]).ok_or("unable to decode count")?;
+    let offset = 9 + n;
+    if count == 0 || count - 1 > (src.len() - offset) * 8 {
+        return Err(From::from("count exceeds the encoded values"));
+    }
+    Ok((count, offset))
+}
+
+/// Calls `on_value` with each value in `src`.
+fn decode_values(
+    src: &[u8],
+    mut on_value: impl FnMut(i64),
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let (count, mut i) = decode_header(src)?;
+    let mut buf = [0_u8; 8];
+    buf.copy_from_slice(&src[1..9]);
+    let mut value = i64::from_be_bytes(buf);
+    on_value(value);
+    if count < 2 {
+        return Ok(());
+    }
+
+    let (mut delta, n) = i64::decode_var(&src[i..]).ok_or("unable to decode first delta")?;
+    i += n;
+    value = value.wrapping_add(delta);
+    on_value(value);
+
+    let mut reader = BitReader::new(&src[i..]);
+    for _ in 2..count {
+        let mut control_len = 0;
+        while control_len < BUCKETS.len() && reader.read_bits(1)? == 1 {
+            control_len += 1;
+        }
+        if control_len > 0 {
+            let (_, _, value_len) = BUCKETS[control_len - 1];
+            let zigzag = reader.read_bits(value_len)?;
+            let dod = ((zigzag >> 1) as i64) ^ -((zigzag & 1) as i64);
+            delta = delta.wrapping_add(dod);
+        }
+        value = value.wrapping_add(delta);
+        on_value(value);
+    }
+    Ok(())
+}
+
+fn put_varint<V: VarInt>(dst: &mut Vec<u8>, value: V) {
+    let mut buf = [0_u8; MAX_VAR_INT_64];
+    let n = value.encode_var(&mut buf);
+    dst.extend_from_slice(&buf[..n]);
+}
+
+#[cfg(test)]
+mod tests {
+    use datafusion::arrow::array::TimestampNanosecondBuilder;
+
+    use super::*;
+    use crate::tsm::codec::get_encoding;
+
+    #[test]
+    fn test_delta_of_delta() {
+        let tests: Vec<Vec<i64>> = vec![
+            vec![1_000_000_000],
+            vec![10, 5],
+            // Regular intervals.
+            (0..1000)
+                .map(|i| 1_600_000_000_000_000_000 + i * 10_000_000_000)
+                .collect(),
+            // Jitter of each bucket.
+            vec![
+                0,
+                100,
+                200,
+                301,
+                400,
+                650,
+                900,
+                3000,
+                3100,
+                -5,
+                i64::MAX,
+                i64::MIN,
+                0,
+            ],
+        ];
+        for src in tests {
+            let mut dst = vec![];
+            i64_delta_of_delta_encode(&src, &mut dst).unwrap();
+            assert_eq!(get_encoding(&dst), Encoding::DeltaOfDelta);
+            let mut got = vec![];
+            i64_delta_of_delta_decode(&dst, &mut got).unwrap();
+            assert_eq!(got, src);
+            let mut builder = TimestampNanosecondBuilder::new();
+            i64_delta_of_delta_decode_to_builder(&dst, &mut builder).unwrap();
+            assert_eq!(builder.values_slice(), src.as_slice());
+        }
+
+        // A bit per value after the header for regular intervals.
+        let src: Vec<i64> = (0..1001).map(|i| i * 1000).collect();
+        let mut dst = vec![];
+        i64_delta_of_delta_encode(&src, &mut dst).unwrap();
+        assert_eq!(dst.len(), 1 + 8 + 2 + 2 + 125);
+
+        let src: Vec<u64> = vec![u64::MAX, 0, 1, 3];
+        u64_delta_of_delta_encode(&src, &mut dst).unwrap();
+        let mut got = vec![];
+        u64_delta_of_delta_decode(&dst, &mut got).unwrap();
+        assert_eq!(got, src);
+        let mut builder = UInt64Builder::new();
+        u64_delta_of_delta_decode_to_builder(&dst, &mut builder).unwrap();
+        assert_eq!(builder.values_slice(), src.as_slice());
+
+        // Corrupted data.
+        dst.truncate(dst.len() - 1);
+        assert!(u64_delta_of_delta_decode(&dst, &mut got).is_err());
+        assert!(u64_delta_of_delta_decode_to_builder(&dst, &mut UInt64Builder::new()).is_err());
+
+        // The count is larger than the values the data can hold.
+        let mut dst = vec![];
+        i64_delta_of_delta_encode(&[1, 2, 3], &mut dst).unwrap();
+        dst.truncate(9);
+        put_varint(&mut dst, usize::MAX as u64);
+        dst.push(0);
+        assert!(i64_delta_of_delta_decode(&dst, &mut got).is_err());
+    }
+}
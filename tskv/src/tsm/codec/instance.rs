@@ -1,5 +1,6 @@
 use std::error::Error;
 
+use datafusion::arrow::array::{Int64Builder, TimestampNanosecondBuilder, UInt64Builder};
 use minivec::MiniVec;
 use models::codec::Encoding;
 
@@ -7,6 +8,10 @@ use crate::tsm::codec::boolean::{
     bool_bitpack_decode, bool_bitpack_encode, bool_without_compress_decode,
     bool_without_compress_encode,
 };
+use crate::tsm::codec::delta_of_delta::{
+    i64_delta_of_delta_decode, i64_delta_of_delta_decode_to_builder, i64_delta_of_delta_encode,
+    u64_delta_of_delta_decode, u64_delta_of_delta_decode_to_builder, u64_delta_of_delta_encode,
+};
 use crate::tsm::codec::float::{
     f64_gorilla_decode, f64_gorilla_encode, f64_q_compress_decode, f64_q_compress_encode,
     f64_without_compress_decode, f64_without_compress_encode,
@@ -15,6 +20,10 @@ use crate::tsm::codec::integer::{
     i64_q_compress_decode, i64_q_compress_encode, i64_without_compress_decode,
     i64_without_compress_encode, i64_zigzag_simple8b_decode, i64_zigzag_simple8b_encode,
 };
+use crate::tsm::codec::run_length::{
+    i64_rle_decode, i64_rle_decode_to_builder, i64_rle_encode, u64_rle_decode,
+    u64_rle_decode_to_builder, u64_rle_encode,
+};
 use crate::tsm::codec::string::{
     str_bzip_decode, str_bzip_encode, str_gzip_decode, str_gzip_encode, str_snappy_decode,
     str_snappy_encode, str_without_compress_decode, str_without_compress_encode, str_zlib_decode,
@@ -28,10 +37,27 @@ use crate::tsm::codec::unsigned::{
     u64_q_compress_decode, u64_q_compress_encode, u64_without_compress_decode,
     u64_without_compress_encode, u64_zigzag_simple8b_decode, u64_zigzag_simple8b_encode,
 };
+use crate::tsm::codec::xor::{
+    i64_xor_decode, i64_xor_decode_to_builder, i64_xor_encode, u64_xor_decode,
+    u64_xor_decode_to_builder, u64_xor_encode,
+};
 
 pub trait TimestampCodec {
     fn encode(&self, src: &[i64], dst: &mut Vec<u8>) -> Result<(), Box<dyn Error + Send + Sync>>;
     fn decode(&self, src: &[u8], dst: &mut Vec<i64>) -> Result<(), Box<dyn Error + Send + Sync>>;
+
+    /// Decodes into an arrow array builder, codecs with a fast path append
+    /// the values without decoding them into a vector first.
+    fn decode_to_builder(
+        &self,
+        src: &[u8],
+        builder: &mut TimestampNanosecondBuilder,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mut dst = Vec::new();
+        self.decode(src, &mut dst)?;
+        builder.append_slice(&dst);
+        Ok(())
+    }
 }
 
 struct NullTimestampCodec();
@@ -70,9 +96,82 @@ impl TimestampCodec for QuantileTimestampCodec {
     }
 }
 
+struct RleTimestampCodec();
+
+impl TimestampCodec for RleTimestampCodec {
+    fn encode(&self, src: &[i64], dst: &mut Vec<u8>) -> Result<(), Box<dyn Error + Send + Sync>> {
+        i64_rle_encode(src, dst)
+    }
+
+    fn decode(&self, src: &[u8], dst: &mut Vec<i64>) -> Result<(), Box<dyn Error + Send + Sync>> {
+        i64_rle_decode(src, dst)
+    }
+
+    fn decode_to_builder(
+        &self,
+        src: &[u8],
+        builder: &mut TimestampNanosecondBuilder,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        i64_rle_decode_to_builder(src, builder)
+    }
+}
+
+struct DeltaOfDeltaTimestampCodec();
+
+impl TimestampCodec for DeltaOfDeltaTimestampCodec {
+    fn encode(&self, src: &[i64], dst: &mut Vec<u8>) -> Result<(), Box<dyn Error + Send + Sync>> {
+        i64_delta_of_delta_encode(src, dst)
+    }
+
+    fn decode(&self, src: &[u8], dst: &mut Vec<i64>) -> Result<(), Box<dyn Error + Send + Sync>> {
+        i64_delta_of_delta_decode(src, dst)
+    }
+
+    fn decode_to_builder(
+        &self,
+        src: &[u8],
+        builder: &mut TimestampNanosecondBuilder,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        i64_delta_of_delta_decode_to_builder(src, builder)
+    }
+}
+
+struct XorTimestampCodec();
+
+impl TimestampCodec for XorTimestampCodec {
+    fn encode(&self, src: &[i64], dst: &mut Vec<u8>) -> Result<(), Box<dyn Error + Send + Sync>> {
+        i64_xor_encode(src, dst)
+    }
+
+    fn decode(&self, src: &[u8], dst: &mut Vec<i64>) -> Result<(), Box<dyn Error + Send + Sync>> {
+        i64_xor_decode(src, dst)
+    }
+
+    fn decode_to_builder(
+        &self,
+        src: &[u8],
+        builder: &mut TimestampNanosecondBuilder,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        i64_xor_decode_to_builder(src, builder)
+    }
+}
+
 pub trait IntegerCodec {
     fn encode(&self, src: &[i64], dst: &mut Vec<u8>) -> Result<(), Box<dyn Error + Send + Sync>>;
     fn decode(&self, src: &[u8], dst: &mut Vec<i64>) -> Result<(), Box<dyn Error + Send + Sync>>;
+
+    /// Decodes into an arrow array builder, codecs with a fast path append
+    /// the values without decoding them into a vector first.
+    fn decode_to_builder(
+        &self,
+        src: &[u8],
+        builder: &mut Int64Builder,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mut dst = Vec::new();
+        self.decode(src, &mut dst)?;
+        builder.append_slice(&dst);
+        Ok(())
+    }
 }
 
 struct NullIntegerCodec();
@@ -111,6 +210,66 @@ impl IntegerCodec for QuantileIntegerCodec {
     }
 }
 
+struct RleIntegerCodec();
+
+impl IntegerCodec for RleIntegerCodec {
+    fn encode(&self, src: &[i64], dst: &mut Vec<u8>) -> Result<(), Box<dyn Error + Send + Sync>> {
+        i64_rle_encode(src, dst)
+    }
+
+    fn decode(&self, src: &[u8], dst: &mut Vec<i64>) -> Result<(), Box<dyn Error + Send + Sync>> {
+        i64_rle_decode(src, dst)
+    }
+
+    fn decode_to_builder(
+        &self,
+        src: &[u8],
+        builder: &mut Int64Builder,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        i64_rle_decode_to_builder(src, builder)
+    }
+}
+
+struct DeltaOfDeltaIntegerCodec();
+
+impl IntegerCodec for DeltaOfDeltaIntegerCodec {
+    fn encode(&self, src: &[i64], dst: &mut Vec<u8>) -> Result<(), Box<dyn Error + Send + Sync>> {
+        i64_delta_of_delta_encode(src, dst)
+    }
+
+    fn decode(&self, src: &[u8], dst: &mut Vec<i64>) -> Result<(), Box<dyn Error + Send + Sync>> {
+        i64_delta_of_delta_decode(src, dst)
+    }
+
+    fn decode_to_builder(
+        &self,
+        src: &[u8],
+        builder: &mut Int64Builder,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        i64_delta_of_delta_decode_to_builder(src, builder)
+    }
+}
+
+struct XorIntegerCodec();
+
+impl IntegerCodec for XorIntegerCodec {
+    fn encode(&self, src: &[i64], dst: &mut Vec<u8>) -> Result<(), Box<dyn Error + Send + Sync>> {
+        i64_xor_encode(src, dst)
+    }
+
+    fn decode(&self, src: &[u8], dst: &mut Vec<i64>) -> Result<(), Box<dyn Error + Send + Sync>> {
+        i64_xor_decode(src, dst)
+    }
+
+    fn decode_to_builder(
+        &self,
+        src: &[u8],
+        builder: &mut Int64Builder,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        i64_xor_decode_to_builder(src, builder)
+    }
+}
+
 pub trait FloatCodec {
     fn encode(&self, src: &[f64], dst: &mut Vec<u8>) -> Result<(), Box<dyn Error + Send + Sync>>;
     fn decode(&self, src: &[u8], dst: &mut Vec<f64>) -> Result<(), Box<dyn Error + Send + Sync>>;
@@ -155,6 +314,19 @@ impl FloatCodec for QuantileFloatCodec {
 pub trait UnsignedCodec {
     fn encode(&self, src: &[u64], dst: &mut Vec<u8>) -> Result<(), Box<dyn Error + Send + Sync>>;
     fn decode(&self, src: &[u8], dst: &mut Vec<u64>) -> Result<(), Box<dyn Error + Send + Sync>>;
+
+    /// Decodes into an arrow array builder, codecs with a fast path append
+    /// the values without decoding them into a vector first.
+    fn decode_to_builder(
+        &self,
+        src: &[u8],
+        builder: &mut UInt64Builder,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mut dst = Vec::new();
+        self.decode(src, &mut dst)?;
+        builder.append_slice(&dst);
+        Ok(())
+    }
 }
 
 struct NullUnsignedCodec();
@@ -193,6 +365,66 @@ impl UnsignedCodec for QuantileUnsignedCodec {
     }
 }
 
+struct RleUnsignedCodec();
+
+impl UnsignedCodec for RleUnsignedCodec {
+    fn encode(&self, src: &[u64], dst: &mut Vec<u8>) -> Result<(), Box<dyn Error + Send + Sync>> {
+        u64_rle_encode(src, dst)
+    }
+
+    fn decode(&self, src: &[u8], dst: &mut Vec<u64>) -> Result<(), Box<dyn Error + Send + Sync>> {
+        u64_rle_decode(src, dst)
+    }
+
+    fn decode_to_builder(
+        &self,
+        src: &[u8],
+        builder: &mut UInt64Builder,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        u64_rle_decode_to_builder(src, builder)
+    }
+}
+
+struct DeltaOfDeltaUnsignedCodec();
+
+impl UnsignedCodec for DeltaOfDeltaUnsignedCodec {
+    fn encode(&self, src: &[u64], dst: &mut Vec<u8>) -> Result<(), Box<dyn Error + Send + Sync>> {
+        u64_delta_of_delta_encode(src, dst)
+    }
+
+    fn decode(&self, src: &[u8], dst: &mut Vec<u64>) -> Result<(), Box<dyn Error + Send + Sync>> {
+        u64_delta_of_delta_decode(src, dst)
+    }
+
+    fn decode_to_builder(
+        &self,
+        src: &[u8],
+        builder: &mut UInt64Builder,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        u64_delta_of_delta_decode_to_builder(src, builder)
+    }
+}
+
+struct XorUnsignedCodec();
+
+impl UnsignedCodec for XorUnsignedCodec {
+    fn encode(&self, src: &[u64], dst: &mut Vec<u8>) -> Result<(), Box<dyn Error + Send + Sync>> {
+        u64_xor_encode(src, dst)
+    }
+
+    fn decode(&self, src: &[u8], dst: &mut Vec<u64>) -> Result<(), Box<dyn Error + Send + Sync>> {
+        u64_xor_decode(src, dst)
+    }
+
+    fn decode_to_builder(
+        &self,
+        src: &[u8],
+        builder: &mut UInt64Builder,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        u64_xor_decode_to_builder(src, builder)
+    }
+}
+
 pub trait BooleanCodec {
     fn encode(&self, src: &[bool], dst: &mut Vec<u8>) -> Result<(), Box<dyn Error + Send + Sync>>;
     fn decode(&self, src: &[u8], dst: &mut Vec<bool>) -> Result<(), Box<dyn Error + Send + Sync>>;
@@ -339,6 +571,9 @@ pub fn get_ts_codec(algo: Encoding) -> Box<dyn TimestampCodec + Send + Sync> {
         Encoding::Null => Box::new(NullTimestampCodec()),
         Encoding::Delta => Box::new(DeltaTimestampCodec()),
         Encoding::Quantile => Box::new(QuantileTimestampCodec()),
+        Encoding::Rle => Box::new(RleTimestampCodec()),
+        Encoding::DeltaOfDelta => Box::new(DeltaOfDeltaTimestampCodec()),
+        Encoding::Xor => Box::new(XorTimestampCodec()),
         _ => Box::new(DeltaTimestampCodec()),
     }
 }
@@ -348,6 +583,9 @@ pub fn get_i64_codec(algo: Encoding) -> Box<dyn IntegerCodec + Send + Sync> {
         Encoding::Null => Box::new(NullIntegerCodec()),
         Encoding::Delta => Box::new(DeltaIntegerCodec()),
         Encoding::Quantile => Box::new(QuantileIntegerCodec()),
+        Encoding::Rle => Box::new(RleIntegerCodec()),
+        Encoding::DeltaOfDelta => Box::new(DeltaOfDeltaIntegerCodec()),
+        Encoding::Xor => Box::new(XorIntegerCodec()),
         _ => Box::new(DeltaIntegerCodec()),
     }
 }
@@ -357,6 +595,9 @@ pub fn get_u64_codec(algo: Encoding) -> Box<dyn UnsignedCodec + Send + Sync> {
         Encoding::Null => Box::new(NullUnsignedCodec()),
         Encoding::Delta => Box::new(DeltaUnsignedCodec()),
         Encoding::Quantile => Box::new(QuantileUnsignedCodec()),
+        Encoding::Rle => Box::new(RleUnsignedCodec()),
+        Encoding::DeltaOfDelta => Box::new(DeltaOfDeltaUnsignedCodec()),
+        Encoding::Xor => Box::new(XorUnsignedCodec()),
         _ => Box::new(DeltaUnsignedCodec()),
     }
 }
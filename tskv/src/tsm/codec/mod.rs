@@ -1,14 +1,21 @@
+mod auto;
+mod bits;
 mod boolean;
+mod delta_of_delta;
 mod float;
 mod instance;
 mod integer;
+mod run_length;
 mod simple8b;
 mod string;
 mod timestamp;
 mod unsigned;
+mod xor;
 
+pub use auto::*;
 pub use instance::*;
 use models::codec::Encoding;
+use models::ValueType;
 
 /// Max number of bytes needed to store a varint-encoded 32-bit integer.
 const MAX_VAR_INT_32: usize = 5;
@@ -35,3 +42,14 @@ impl DataBlockEncoding {
         (self.ts_encoding, self.val_encoding)
     }
 }
+
+/// Returns the encoding of values of `value_type` used by `Encoding::Default`.
+pub fn default_encoding(value_type: ValueType) -> Encoding {
+    match value_type {
+        ValueType::Unsigned | ValueType::Integer => Encoding::Delta,
+        ValueType::Float => Encoding::Gorilla,
+        ValueType::Boolean => Encoding::BitPack,
+        ValueType::String => Encoding::Snappy,
+        ValueType::Unknown => Encoding::Default,
+    }
+}
@@ -0,0 +1,225 @@
+use std::error::Error;
+use std::iter;
+
+use datafusion::arrow::array::{PrimitiveBuilder, UInt64Builder};
+use datafusion::arrow::datatypes::ArrowPrimitiveType;
+use integer_encoding::*;
+
+use crate::tsm::codec::{Encoding, MAX_VAR_INT_64};
+use crate::tsm::MAX_BLOCK_VALUES;
+
+/// Layout of a run-length encoded block, stored in the byte after the encoding id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RunLengthEncoding {
+    /// Every value is the same, only the value is stored.
+    Constant = 0,
+    /// Runs of repeated values, each run is stored as the delta from the value
+    /// of the previous run and the length of the run.
+    Runs = 1,
+}
+
+/// Encodes a vector of signed integers into runs of repeated values.
+///
+/// Layout: `| Encoding::Rle | RunLengthEncoding | count (varint) | data |`,
+/// data is the value (8 bytes) if all values are the same, or else pairs of
+/// the zig-zag varint delta from the previous run and the varint run length.
+pub fn i64_rle_encode(src: &[i64], dst: &mut Vec<u8>) -> Result<(), Box<dyn Error + Send + Sync>> {
+    dst.clear();
+    if src.is_empty() {
+        return Ok(());
+    }
+
+    let first = src[0];
+    let constant = src.iter().all(|v| *v == first);
+    dst.push(Encoding::Rle as u8);
+    if constant {
+        dst.push(RunLengthEncoding::Constant as u8);
+        put_varint(dst, src.len() as u64);
+        dst.extend_from_slice(&first.to_be_bytes());
+        return Ok(());
+    }
+
+    dst.push(RunLengthEncoding::Runs as u8);
+    put_varint(dst, src.len() as u64);
+    let mut prev = 0_i64;
+    let mut start = 0;
+    while start < src.len() {
+        let value = src[start];
+        let len = src[start..].iter().take_while(|v| **v == value).count();
+        put_varint(dst, value.wrapping_sub(prev));
+        put_varint(dst, len as u64);
+        prev = value;
+        start += len;
+    }
+    Ok(())
+}
+
+/// Decodes a slice of bytes encoded by `i64_rle_encode` into a vector of signed integers.
+pub fn i64_rle_decode(src: &[u8], dst: &mut Vec<i64>) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if src.is_empty() {
+        return Ok(());
+    }
+    dst.reserve(reserved_capacity(src)?);
+    decode_runs(src, |value, len| dst.extend(iter::repeat(value).take(len)))
+}
+
+pub fn u64_rle_encode(src: &[u64], dst: &mut Vec<u8>) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let signed: Vec<i64> = src.iter().map(|v| *v as i64).collect();
+    i64_rle_encode(&signed, dst)
+}
+
+pub fn u64_rle_decode(src: &[u8], dst: &mut Vec<u64>) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if src.is_empty() {
+        return Ok(());
+    }
+    dst.reserve(reserved_capacity(src)?);
+    decode_runs(src, |value, len| {
+        dst.extend(iter::repeat(value as u64).take(len))
+    })
+}
+
+/// Decodes a slice of bytes encoded by `i64_rle_encode` into an arrow array builder,
+/// without the intermediate vector of `i64_rle_decode`.
+pub fn i64_rle_decode_to_builder<T: ArrowPrimitiveType<Native = i64>>(
+    src: &[u8],
+    builder: &mut PrimitiveBuilder<T>,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if src.is_empty() {
+        return Ok(());
+    }
+    decode_runs(src, |value, len| {
+        for _ in 0..len {
+            builder.append_value(value);
+        }
+    })
+}
+
+pub fn u64_rle_decode_to_builder(
+    src: &[u8],
+    builder: &mut UInt64Builder,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if src.is_empty() {
+        return Ok(());
+    }
+    decode_runs(src, |value, len| {
+        for _ in 0..len {
+            builder.append_value(value as u64);
+        }
+    })
+}
+
+/// Returns the layout, the number of values and the offset of data.
+fn decode_header(src: &[u8]) -> Result<(u8, usize, usize), Box<dyn Error + Send + Sync>> {
+    if src.len() < 3 {
+        return Err(From::from("not enough data to decode using RLE"));
+    }
+    let (count, n) = usize::decode_var(&src[2..]).ok_or("unable to decode count")?;
+    Ok((src[1], count, 2 + n))
+}
+
+/// Capacity to reserve for the values of `src`.
+///
+/// A run may be longer than its bytes, the count can't be checked against the length of `src`,
+/// so at most the values of a full block are reserved and the rest grows on demand.
+fn reserved_capacity(src: &[u8]) -> Result<usize, Box<dyn Error + Send + Sync>> {
+    let (_, count, _) = decode_header(src)?;
+    Ok(count.min(MAX_BLOCK_VALUES as usize))
+}
+
+/// Calls `on_run` with each run of (value, length) in `src`.
+fn decode_runs(
+    src: &[u8],
+    mut on_run: impl FnMut(i64, usize),
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let (layout, count, mut i) = decode_header(src)?;
+    match layout {
+        layout if layout == RunLengthEncoding::Constant as u8 => {
+            if src.len() < i + 8 {
+                return Err(From::from("not enough data to decode constant value"));
+            }
+            let mut buf = [0_u8; 8];
+            buf.copy_from_slice(&src[i..i + 8]);
+            on_run(i64::from_be_bytes(buf), count);
+        }
+        layout if layout == RunLengthEncoding::Runs as u8 => {
+            let mut value = 0_i64;
+            let mut decoded = 0;
+            while decoded < count {
+                let (delta, n) = i64::decode_var(&src[i..]).ok_or("unable to decode value")?;
+                i += n;
+                let (len, n) = usize::decode_var(&src[i..]).ok_or("unable to decode length")?;
+                i += n;
+                if len > count - decoded {
+                    return Err(From::from("run length exceeds the count of values"));
+                }
+                value = value.wrapping_add(delta);
+                on_run(value, len);
+                decoded += len;
+            }
+        }
+        _ => return Err(From::from("invalid block encoding")),
+    }
+    Ok(())
+}
+
+fn put_varint<V: VarInt>(dst: &mut Vec<u8>, value: V) {
+    let mut buf = [0_u8; MAX_VAR_INT_64];
+    let n = value.encode_var(&mut buf);
+    dst.extend_from_slice(&buf[..n]);
+}
+
+#[cfg(test)]
+mod tests {
+    use datafusion::arrow::array::Int64Builder;
+
+    use super::*;
+    use crate::tsm::codec::get_encoding;
+
+    #[test]
+    fn test_rle_constant() {
+        let src = vec![-42_i64; 1000];
+        let mut dst = vec![];
+        i64_rle_encode(&src, &mut dst).unwrap();
+        assert_eq!(get_encoding(&dst), Encoding::Rle);
+        assert_eq!(dst[1], RunLengthEncoding::Constant as u8);
+        // encoding id, layout, varint count and the value.
+        assert_eq!(dst.len(), 1 + 1 + 2 + 8);
+
+        let mut got = vec![];
+        i64_rle_decode(&dst, &mut got).unwrap();
+        assert_eq!(got, src);
+    }
+
+    #[test]
+    fn test_rle_runs() {
+        let src: Vec<i64> = vec![1, 1, 1, i64::MIN, i64::MAX, i64::MAX, 0, 5, 5, 5, 5, 1];
+        let mut dst = vec![];
+        i64_rle_encode(&src, &mut dst).unwrap();
+        assert_eq!(dst[1], RunLengthEncoding::Runs as u8);
+        let mut got = vec![];
+        i64_rle_decode(&dst, &mut got).unwrap();
+        assert_eq!(got, src);
+        let mut builder = Int64Builder::new();
+        i64_rle_decode_to_builder(&dst, &mut builder).unwrap();
+        assert_eq!(builder.values_slice(), src.as_slice());
+
+        let src: Vec<u64> = vec![u64::MAX, u64::MAX, 0, 7, 7];
+        u64_rle_encode(&src, &mut dst).unwrap();
+        let mut got = vec![];
+        u64_rle_decode(&dst, &mut got).unwrap();
+        assert_eq!(got, src);
+        let mut builder = UInt64Builder::new();
+        u64_rle_decode_to_builder(&dst, &mut builder).unwrap();
+        assert_eq!(builder.values_slice(), src.as_slice());
+
+        // Encoded no values.
+        i64_rle_encode(&[], &mut dst).unwrap();
+        assert!(dst.is_empty());
+
+        // Corrupted data.
+        i64_rle_encode(&[1, 1, 2], &mut dst).unwrap();
+        dst.truncate(dst.len() - 2);
+        assert!(i64_rle_decode(&dst, &mut got).is_err());
+        assert!(i64_rle_decode_to_builder(&dst, &mut Int64Builder::new()).is_err());
+    }
+}
@@ -0,0 +1,229 @@
+use std::error::Error;
+
+use datafusion::arrow::array::{PrimitiveBuilder, UInt64Builder};
+use datafusion::arrow::datatypes::ArrowPrimitiveType;
+use integer_encoding::*;
+
+use crate::tsm::codec::bits::{BitReader, BitWriter};
+use crate::tsm::codec::{Encoding, MAX_VAR_INT_64};
+
+/// Bits to store the number of leading zeros and the length of the meaningful bits.
+const WINDOW_BITS: u32 = 6;
+
+/// Encodes a vector of signed integers by the XOR of each value with the previous one,
+/// as floats in Gorilla, only the meaningful bits between the leading and trailing zeros
+/// are stored. Unchanged values cost one bit, values changing in few bits cost few bits.
+///
+/// Layout: `| Encoding::Xor | count (varint) | first value (8 bytes) | bits of XORs |`,
+/// each XOR is stored as
+/// - `0` if the value is unchanged,
+/// - `10` and the meaningful bits if they are in the window of the previous XOR,
+/// - `11`, the leading zeros (6 bits), the length of the meaningful bits minus 1 (6 bits)
+///   and the meaningful bits, which make the new window.
+pub fn i64_xor_encode(src: &[i64], dst: &mut Vec<u8>) -> Result<(), Box<dyn Error + Send + Sync>> {
+    dst.clear();
+    if src.is_empty() {
+        return Ok(());
+    }
+
+    dst.push(Encoding::Xor as u8);
+    put_varint(dst, src.len() as u64);
+    dst.extend_from_slice(&src[0].to_be_bytes());
+
+    let mut writer = BitWriter::new(dst);
+    // (leading zeros, length) of the meaningful bits of the last stored window.
+    let mut window: Option<(u32, u32)> = None;
+    for w in src.windows(2) {
+        let xor = (w[0] ^ w[1]) as u64;
+        if xor == 0 {
+            writer.write_bits(0, 1);
+            continue;
+        }
+
+        let leading = xor.leading_zeros();
+        let trailing = xor.trailing_zeros();
+        match window {
+            Some((win_leading, win_len))
+                if leading >= win_leading && trailing >= 64 - win_leading - win_len =>
+            {
+                writer.write_bits(0b10, 2);
+                writer.write_bits(xor >> (64 - win_leading - win_len), win_len);
+            }
+            _ => {
+                let len = 64 - leading - trailing;
+                writer.write_bits(0b11, 2);
+                writer.write_bits(leading as u64, WINDOW_BITS);
+                writer.write_bits((len - 1) as u64, WINDOW_BITS);
+                writer.write_bits(xor >> trailing, len);
+                window = Some((leading, len));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Decodes a slice of bytes encoded by `i64_xor_encode` into a vector of signed integers.
+pub fn i64_xor_decode(src: &[u8], dst: &mut Vec<i64>) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if src.is_empty() {
+        return Ok(());
+    }
+    let (count, _) = decode_header(src)?;
+    dst.reserve(count);
+    decode_values(src, |v| dst.push(v))
+}
+
+pub fn u64_xor_encode(src: &[u64], dst: &mut Vec<u8>) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let signed: Vec<i64> = src.iter().map(|v| *v as i64).collect();
+    i64_xor_encode(&signed, dst)
+}
+
+pub fn u64_xor_decode(src: &[u8], dst: &mut Vec<u64>) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if src.is_empty() {
+        return Ok(());
+    }
+    let (count, _) = decode_header(src)?;
+    dst.reserve(count);
+    decode_values(src, |v| dst.push(v as u64))
+}
+
+/// Decodes a slice of bytes encoded by `i64_xor_encode` into an arrow array builder,
+/// without the intermediate vector of `i64_xor_decode`.
+pub fn i64_xor_decode_to_builder<T: ArrowPrimitiveType<Native = i64>>(
+    src: &[u8],
+    builder: &mut PrimitiveBuilder<T>,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if src.is_empty() {
+        return Ok(());
+    }
+    decode_values(src, |v| builder.append_value(v))
+}
+
+pub fn u64_xor_decode_to_builder(
+    src: &[u8],
+    builder: &mut UInt64Builder,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if src.is_empty() {
+        return Ok(());
+    }
+    decode_values(src, |v| builder.append_value(v as u64))
+}
+
+/// Returns the number of values and the offset of the first value.
+///
+/// The count is checked against the length of `src` before it is used to allocate,
+/// every value after the first takes at least one bit.
+fn decode_header(src: &[u8]) -> Result<(usize, usize), Box<dyn Error + Send + Sync>> {
+    if src.len() < 2 {
+        return Err(From::from("not enough data to decode XOR"));
+    }
+    let (count, n) = usize::decode_var(&src[1..]).ok_or("unable to decode count")?;
+    let offset = 1 + n;
+    if src.len() < offset + 8 {
+        return Err(From::from("not enough data to decode the first value"));
+    }
+    if count == 0 || count - 1 > (src.len() - offset - 8) * 8 {
+        return Err(From::from("count exceeds the encoded values"));
+    }
+    Ok((count, offset))
+}
+
+/// Calls `on_value` with each value in `src`.
+fn decode_values(
+    src: &[u8],
+    mut on_value: impl FnMut(i64),
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let (count, i) = decode_header(src)?;
+    let mut buf = [0_u8; 8];
+    buf.copy_from_slice(&src[i..i + 8]);
+    let mut value = i64::from_be_bytes(buf);
+    on_value(value);
+
+    let mut reader = BitReader::new(&src[i + 8..]);
+    let mut window: Option<(u32, u32)> = None;
+    for _ in 1..count {
+        if reader.read_bits(1)? == 1 {
+            if reader.read_bits(1)? == 1 {
+                let leading = reader.read_bits(WINDOW_BITS)? as u32;
+                let len = reader.read_bits(WINDOW_BITS)? as u32 + 1;
+                if leading + len > 64 {
+                    return Err(From::from("invalid window of meaningful bits"));
+                }
+                window = Some((leading, len));
+            }
+            let (leading, len) = window.ok_or("no window of meaningful bits")?;
+            let xor = reader.read_bits(len)? << (64 - leading - len);
+            value ^= xor as i64;
+        }
+        on_value(value);
+    }
+    Ok(())
+}
+
+fn put_varint<V: VarInt>(dst: &mut Vec<u8>, value: V) {
+    let mut buf = [0_u8; MAX_VAR_INT_64];
+    let n = value.encode_var(&mut buf);
+    dst.extend_from_slice(&buf[..n]);
+}
+
+#[cfg(test)]
+mod tests {
+    use datafusion::arrow::array::Int64Builder;
+
+    use super::*;
+    use crate::tsm::codec::get_encoding;
+
+    #[test]
+    fn test_xor() {
+        let tests: Vec<Vec<i64>> = vec![
+            vec![42],
+            vec![-1, 1],
+            // Unchanged values.
+            vec![7; 1000],
+            // A counter changing in the lowest bits.
+            (0..1000).map(|i| 1_000_000 + i / 3).collect(),
+            // Changes in the window of the previous XOR and out of it.
+            vec![0, 0b1100, 0b0100, i64::MIN, i64::MAX, -1, 0, 1, 1 << 62],
+        ];
+        for src in tests {
+            let mut dst = vec![];
+            i64_xor_encode(&src, &mut dst).unwrap();
+            assert_eq!(get_encoding(&dst), Encoding::Xor);
+            let mut got = vec![];
+            i64_xor_decode(&dst, &mut got).unwrap();
+            assert_eq!(got, src);
+            let mut builder = Int64Builder::new();
+            i64_xor_decode_to_builder(&dst, &mut builder).unwrap();
+            assert_eq!(builder.values_slice(), src.as_slice());
+        }
+
+        // A bit per value after the header for unchanged values.
+        let mut dst = vec![];
+        i64_xor_encode(&[5; 801], &mut dst).unwrap();
+        assert_eq!(dst.len(), 1 + 2 + 8 + 100);
+
+        let src: Vec<u64> = vec![u64::MAX, 0, 1, 3, u64::MAX];
+        u64_xor_encode(&src, &mut dst).unwrap();
+        let mut got = vec![];
+        u64_xor_decode(&dst, &mut got).unwrap();
+        assert_eq!(got, src);
+        let mut builder = UInt64Builder::new();
+        u64_xor_decode_to_builder(&dst, &mut builder).unwrap();
+        assert_eq!(builder.values_slice(), src.as_slice());
+
+        // Encoded no values.
+        i64_xor_encode(&[], &mut dst).unwrap();
+        assert!(dst.is_empty());
+
+        // Corrupted data.
+        i64_xor_encode(&[1, 1 << 40, 3], &mut dst).unwrap();
+        dst.truncate(dst.len() - 2);
+        assert!(i64_xor_decode(&dst, &mut got).is_err());
+        assert!(i64_xor_decode_to_builder(&dst, &mut Int64Builder::new()).is_err());
+
+        // The count is larger than the values the data can hold.
+        let mut dst = vec![Encoding::Xor as u8];
+        put_varint(&mut dst, usize::MAX as u64);
+        dst.extend_from_slice(&[0; 9]);
+        assert!(i64_xor_decode(&dst, &mut got).is_err());
+    }
+}
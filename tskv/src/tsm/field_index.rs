@@ -0,0 +1,230 @@
+use datafusion::logical_expr::{BinaryExpr, Expr, Like, Operator};
+use datafusion::optimizer::utils::split_conjunction;
+use datafusion::scalar::ScalarValue;
+use models::codec::FieldIndexType;
+use utils::BloomFilter;
+
+use crate::tsm::DataBlock;
+
+/// Length of grams in index of `FieldIndexType::Ngram`.
+const NGRAM_SIZE: usize = 3;
+/// Bits of the filter for each inserted token.
+const FILTER_BITS_PER_TOKEN: u64 = 8;
+const MIN_FILTER_BITS: u64 = 512;
+const MAX_FILTER_BITS: u64 = 1 << 20;
+
+/// A filter of string field values that may be checked by a `FieldIndex`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldIndexFilter {
+    /// `field = 'value'`
+    Eq(String),
+    /// `field LIKE 'pattern'`
+    Like(String),
+}
+
+impl FieldIndexFilter {
+    /// Extracts the filters of `column` from the conjunctive `exprs`,
+    /// a data block can be skipped if any of them mismatches.
+    pub fn from_exprs(exprs: &[Expr], column: &str) -> Vec<Self> {
+        let is_column = |expr: &Expr| matches!(expr, Expr::Column(c) if c.name == column);
+        let as_str = |expr: &Expr| match expr {
+            Expr::Literal(ScalarValue::Utf8(Some(s))) => Some(s.clone()),
+            _ => None,
+        };
+
+        let mut filters = vec![];
+        for expr in exprs.iter().flat_map(split_conjunction) {
+            match expr {
+                Expr::Like(Like {
+                    negated: false,
+                    expr,
+                    pattern,
+                    escape_char: None,
+                }) if is_column(expr.as_ref()) => {
+                    if let Some(pattern) = as_str(pattern.as_ref()) {
+                        filters.push(Self::Like(pattern));
+                    }
+                }
+                Expr::BinaryExpr(BinaryExpr {
+                    left,
+                    op: Operator::Eq,
+                    right,
+                }) => {
+                    let value = if is_column(left.as_ref()) {
+                        as_str(right.as_ref())
+                    } else if is_column(right.as_ref()) {
+                        as_str(left.as_ref())
+                    } else {
+                        None
+                    };
+                    if let Some(value) = value {
+                        filters.push(Self::Eq(value));
+                    }
+                }
+                _ => {}
+            }
+        }
+        filters
+    }
+}
+
+/// Index of the values in a data block of strings, written after the block in TSM file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldIndex {
+    index_type: FieldIndexType,
+    filter: BloomFilter,
+}
+
+impl FieldIndex {
+    /// Builds the index of the block, `None` if the block is not of strings.
+    pub fn from_block(index_type: FieldIndexType, block: &DataBlock) -> Option<Self> {
+        let values = match block {
+            DataBlock::Str { val, .. } => val,
+            _ => return None,
+        };
+        let tokens: usize = match index_type {
+            FieldIndexType::Ngram => values
+                .iter()
+                .map(|v| v.len().saturating_sub(NGRAM_SIZE - 1).max(1))
+                .sum(),
+            FieldIndexType::Bloom => values.len(),
+        };
+        let bits = (tokens as u64 * FILTER_BITS_PER_TOKEN).clamp(MIN_FILTER_BITS, MAX_FILTER_BITS);
+        let mut filter = BloomFilter::new(bits);
+        for value in values.iter() {
+            match index_type {
+                FieldIndexType::Ngram => ngrams(value).for_each(|gram| filter.insert(gram)),
+                FieldIndexType::Bloom => filter.insert(value),
+            }
+        }
+
+        Some(Self { index_type, filter })
+    }
+
+    pub fn decode(index_type: FieldIndexType, data: &[u8]) -> Self {
+        Self {
+            index_type,
+            filter: BloomFilter::with_data(data),
+        }
+    }
+
+    pub fn index_type(&self) -> FieldIndexType {
+        self.index_type
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        self.filter.bytes()
+    }
+
+    /// Returns false if there is definitely no value in the block matches the filter.
+    pub fn may_match(&self, filter: &FieldIndexFilter) -> bool {
+        match filter {
+            FieldIndexFilter::Eq(value) => self.may_contain(value.as_bytes()),
+            FieldIndexFilter::Like(pattern) => {
+                // Escaped wildcards are not parsed.
+                if pattern.contains('\\') {
+                    return true;
+                }
+                if !pattern.contains(['%', '_']) {
+                    return self.may_contain(pattern.as_bytes());
+                }
+                match self.index_type {
+                    FieldIndexType::Ngram => pattern
+                        .split(['%', '_'])
+                        .filter(|s| s.len() >= NGRAM_SIZE)
+                        .all(|s| ngrams(s.as_bytes()).all(|gram| self.filter.contains(gram))),
+                    FieldIndexType::Bloom => true,
+                }
+            }
+        }
+    }
+
+    fn may_contain(&self, value: &[u8]) -> bool {
+        match self.index_type {
+            FieldIndexType::Ngram => ngrams(value).all(|gram| self.filter.contains(gram)),
+            FieldIndexType::Bloom => self.filter.contains(value),
+        }
+    }
+}
+
+/// Returns the n-grams of value, or the value itself if it's shorter than `NGRAM_SIZE`.
+fn ngrams(value: &[u8]) -> impl Iterator<Item = &[u8]> {
+    let short = if value.len() < NGRAM_SIZE {
+        Some(value)
+    } else {
+        None
+    };
+    value.windows(NGRAM_SIZE).chain(short)
+}
+
+#[cfg(test)]
+mod test {
+    use datafusion::logical_expr::Like;
+    use datafusion::prelude::{col, lit, Expr};
+    use minivec::MiniVec;
+    use models::codec::FieldIndexType;
+
+    use super::{FieldIndex, FieldIndexFilter};
+    use crate::tsm::codec::DataBlockEncoding;
+    use crate::tsm::DataBlock;
+
+    fn str_block(values: &[&str]) -> DataBlock {
+        DataBlock::Str {
+            ts: (0..values.len() as i64).collect(),
+            val: values.iter().map(|v| MiniVec::from(v.as_bytes())).collect(),
+            enc: DataBlockEncoding::default(),
+        }
+    }
+
+    #[test]
+    fn test_field_index() {
+        let block = str_block(&["connect timeout", "ok", "request done"]);
+        let like = |s: &str| FieldIndexFilter::Like(s.to_string());
+        let eq = |s: &str| FieldIndexFilter::Eq(s.to_string());
+
+        let index = FieldIndex::from_block(FieldIndexType::Ngram, &block).unwrap();
+        let index = FieldIndex::decode(index.index_type(), index.bytes());
+        assert!(index.may_match(&like("%timeout%")));
+        assert!(index.may_match(&like("req%done")));
+        assert!(index.may_match(&like("%a%")));
+        assert!(index.may_match(&eq("ok")));
+        assert!(!index.may_match(&like("%refused%")));
+        assert!(!index.may_match(&eq("no")));
+
+        let index = FieldIndex::from_block(FieldIndexType::Bloom, &block).unwrap();
+        assert!(index.may_match(&eq("request done")));
+        assert!(index.may_match(&like("%refused%")));
+        assert!(!index.may_match(&eq("request")));
+
+        assert!(FieldIndex::from_block(
+            FieldIndexType::Bloom,
+            &DataBlock::Bool {
+                ts: vec![1],
+                val: vec![true],
+                enc: DataBlockEncoding::default(),
+            }
+        )
+        .is_none());
+
+        let exprs = vec![
+            Expr::Like(Like::new(
+                false,
+                Box::new(col("msg")),
+                Box::new(lit("%timeout%")),
+                None,
+            ))
+            .and(lit("ok").eq(col("msg"))),
+            col("other").eq(lit("a")),
+            Expr::Like(Like::new(
+                true,
+                Box::new(col("msg")),
+                Box::new(lit("%a%")),
+                None,
+            )),
+        ];
+        assert_eq!(
+            FieldIndexFilter::from_exprs(&exprs, "msg"),
+            vec![like("%timeout%"), eq("ok")]
+        );
+    }
+}
@@ -2,19 +2,30 @@ use std::cmp;
 use std::fmt::Display;
 use std::sync::Arc;
 
+use models::codec::FieldIndexType;
 use models::{FieldId, Timestamp, ValueType};
 use utils::BloomFilter;
 
 use crate::byte_utils::{decode_be_i64, decode_be_u16, decode_be_u32, decode_be_u64};
 use crate::tseries_family::TimeRange;
+use crate::tsm::statistics::BLOCK_STATISTICS_SIZE;
 use crate::tsm::{
-    BlockMetaIterator, WriteTsmError, WriteTsmResult, BLOCK_META_SIZE, INDEX_META_SIZE,
+    BlockMetaIterator, BlockStatistics, WriteTsmError, WriteTsmResult, BLOCK_META_SIZE,
+    BLOCK_META_SIZE_V1, INDEX_META_SIZE,
 };
 
+/// Flag of `BlockMeta` which has `BlockStatistics`.
+const BLOCK_FLAG_STATISTICS: u8 = 0b0000_0001;
+/// Flag of `BlockMeta` which has a `FieldIndex` after the block,
+/// the type and length of the index are stored in place of `BlockStatistics`.
+const BLOCK_FLAG_FIELD_INDEX: u8 = 0b0000_0010;
+
 #[derive(Debug, Clone)]
 pub struct Index {
     tsm_id: u64,
     bloom_filter: Arc<BloomFilter>,
+    /// Size of `BlockMeta`, depends on the version of TSM file.
+    block_meta_size: usize,
 
     /// In-memory index-block data
     ///
@@ -36,12 +47,14 @@ impl Index {
     pub fn new(
         tsm_id: u64,
         bloom_filter: Arc<BloomFilter>,
+        block_meta_size: usize,
         data: Vec<u8>,
         field_id_offs: Vec<(FieldId, usize)>,
     ) -> Self {
         Self {
             tsm_id,
             bloom_filter,
+            block_meta_size,
             data,
             field_id_offs,
         }
@@ -51,6 +64,10 @@ impl Index {
         self.bloom_filter.clone()
     }
 
+    pub fn block_meta_size(&self) -> usize {
+        self.block_meta_size
+    }
+
     pub fn data(&self) -> &[u8] {
         self.data.as_slice()
     }
@@ -116,7 +133,8 @@ impl IndexMeta {
         }
         let first_blk_beg = self.index_ref.field_id_offs()[self.index_idx].1 + INDEX_META_SIZE;
         let min_ts = decode_be_i64(&self.index_ref.data[first_blk_beg..first_blk_beg + 8]);
-        let last_blk_beg = first_blk_beg + BLOCK_META_SIZE * (self.block_count as usize - 1);
+        let last_blk_beg =
+            first_blk_beg + self.index_ref.block_meta_size() * (self.block_count as usize - 1);
         let max_ts = decode_be_i64(&self.index_ref.data[last_blk_beg + 8..last_blk_beg + 16]);
         (min_ts, max_ts)
     }
@@ -235,6 +253,36 @@ impl BlockMeta {
     pub fn val_off(&self) -> u64 {
         decode_be_u64(&self.index_ref.data()[self.block_offset + 36..self.block_offset + 44])
     }
+
+    /// Statistics of values in the block, `None` if the block is of strings
+    /// or the TSM file is written before statistics were supported.
+    pub fn statistics(&self) -> Option<BlockStatistics> {
+        if self.index_ref.block_meta_size() < BLOCK_META_SIZE {
+            return None;
+        }
+        let data = &self.index_ref.data()[self.block_offset + BLOCK_META_SIZE_V1..];
+        if data[0] & BLOCK_FLAG_STATISTICS == 0 {
+            return None;
+        }
+        BlockStatistics::decode(self.field_type, &data[1..])
+    }
+
+    /// Type and length of the `FieldIndex` written after the block, `None` if the block
+    /// has no field index.
+    pub fn field_index(&self) -> Option<(FieldIndexType, u32)> {
+        if self.index_ref.block_meta_size() < BLOCK_META_SIZE {
+            return None;
+        }
+        decode_field_index(&self.index_ref.data()[self.block_offset + BLOCK_META_SIZE_V1..])
+    }
+}
+
+fn decode_field_index(data: &[u8]) -> Option<(FieldIndexType, u32)> {
+    if data[0] & BLOCK_FLAG_FIELD_INDEX == 0 {
+        return None;
+    }
+    let index_type = FieldIndexType::from_id(data[1])?;
+    Some((index_type, decode_be_u32(&data[2..6])))
 }
 
 impl Display for BlockMeta {
@@ -273,7 +321,7 @@ pub(crate) fn get_data_block_meta_unchecked(
     field_id: FieldId,
     field_type: ValueType,
 ) -> BlockMeta {
-    let base = index_offset + INDEX_META_SIZE + block_idx * BLOCK_META_SIZE;
+    let base = index_offset + INDEX_META_SIZE + block_idx * index.block_meta_size();
     BlockMeta::new(index, field_id, field_type, base)
 }
 
@@ -320,21 +368,49 @@ pub(crate) struct BlockEntry {
     pub offset: u64,
     pub size: u64,
     pub val_offset: u64,
+    pub statistics: Option<BlockStatistics>,
+    /// Type and length of the `FieldIndex` written after the block.
+    pub field_index: Option<(FieldIndexType, u32)>,
 }
 
 impl BlockEntry {
     pub(crate) fn encode(&self, buf: &mut [u8]) {
         assert!(buf.len() >= BLOCK_META_SIZE);
+        buf[..BLOCK_META_SIZE].fill(0);
         buf[0..8].copy_from_slice(&self.min_ts.to_be_bytes()[..]);
         buf[8..16].copy_from_slice(&self.max_ts.to_be_bytes()[..]);
         buf[16..20].copy_from_slice(&self.count.to_be_bytes()[..]);
         buf[20..28].copy_from_slice(&self.offset.to_be_bytes()[..]);
         buf[28..36].copy_from_slice(&self.size.to_be_bytes()[..]);
         buf[36..44].copy_from_slice(&self.val_offset.to_be_bytes()[..]);
+        if let Some(statistics) = self.statistics.as_ref() {
+            buf[BLOCK_META_SIZE_V1] = BLOCK_FLAG_STATISTICS;
+            statistics.encode(&mut buf[BLOCK_META_SIZE_V1 + 1..BLOCK_META_SIZE]);
+        } else if let Some((index_type, len)) = self.field_index {
+            buf[BLOCK_META_SIZE_V1] = BLOCK_FLAG_FIELD_INDEX;
+            buf[BLOCK_META_SIZE_V1 + 1] = index_type.id();
+            buf[BLOCK_META_SIZE_V1 + 2..BLOCK_META_SIZE_V1 + 6].copy_from_slice(&len.to_be_bytes());
+        }
     }
 
-    pub(crate) fn decode(data: &[u8]) -> Self {
-        assert!(data.len() >= BLOCK_META_SIZE);
+    /// Decodes a `BlockEntry` of `block_meta_size` bytes.
+    pub(crate) fn decode(data: &[u8], field_type: ValueType, block_meta_size: usize) -> Self {
+        assert!(data.len() >= block_meta_size);
+        let statistics = if block_meta_size >= BLOCK_META_SIZE
+            && data[BLOCK_META_SIZE_V1] & BLOCK_FLAG_STATISTICS != 0
+        {
+            BlockStatistics::decode(
+                field_type,
+                &data[BLOCK_META_SIZE_V1 + 1..BLOCK_META_SIZE_V1 + 1 + BLOCK_STATISTICS_SIZE],
+            )
+        } else {
+            None
+        };
+        let field_index = if block_meta_size >= BLOCK_META_SIZE {
+            decode_field_index(&data[BLOCK_META_SIZE_V1..])
+        } else {
+            None
+        };
         Self {
             min_ts: decode_be_i64(&data[0..8]),
             max_ts: decode_be_i64(&data[8..16]),
@@ -342,6 +418,8 @@ impl BlockEntry {
             offset: decode_be_u64(&data[20..28]),
             size: decode_be_u64(&data[28..36]),
             val_offset: decode_be_u64(&data[36..44]),
+            statistics,
+            field_index,
         }
     }
 }
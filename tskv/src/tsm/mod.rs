@@ -1,13 +1,19 @@
 mod block;
+mod block_cache;
 pub mod codec;
+mod field_index;
 mod index;
 mod reader;
+mod statistics;
 mod tombstone;
 mod writer;
 
 pub use block::*;
+pub use block_cache::{BlockCache, BlockCacheKey, VnodeBlockCache};
+pub use field_index::{FieldIndex, FieldIndexFilter};
 pub use index::*;
 pub use reader::*;
+pub use statistics::{BlockStatistics, StatValue};
 pub use tombstone::{Tombstone, TsmTombstone};
 pub use writer::*;
 
@@ -16,11 +22,24 @@ pub(crate) const MAX_BLOCK_VALUES: u32 = 1000;
 
 const HEADER_SIZE: usize = 5;
 const INDEX_META_SIZE: usize = 11;
-const BLOCK_META_SIZE: usize = 44;
+/// Size of `BlockMeta` in TSM files of version 1, without statistics.
+const BLOCK_META_SIZE_V1: usize = 44;
+/// Size of `BlockMeta` in TSM files of the current version:
+/// `BLOCK_META_SIZE_V1`, 1 byte of flags and `BlockStatistics`.
+const BLOCK_META_SIZE: usize = BLOCK_META_SIZE_V1 + 1 + statistics::BLOCK_STATISTICS_SIZE; // 69
 const BLOOM_FILTER_SIZE: usize = 64;
 const BLOOM_FILTER_BITS: u64 = 512; // 64 * 8
 const FOOTER_SIZE: usize = BLOOM_FILTER_SIZE + 8; // 72
 
+/// Returns size of `BlockMeta` in TSM files of `version`, `None` if the version is unknown.
+fn block_meta_size(version: u8) -> Option<usize> {
+    match version {
+        1 => Some(BLOCK_META_SIZE_V1),
+        2 => Some(BLOCK_META_SIZE),
+        _ => None,
+    }
+}
+
 pub trait BlockReader {
     fn decode(&mut self, block: &BlockMeta) -> crate::error::Result<DataBlock>;
 }
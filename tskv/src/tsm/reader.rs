@@ -2,6 +2,9 @@ use std::fmt::Debug;
 use std::path::Path;
 use std::sync::Arc;
 
+use datafusion::arrow::array::{
+    ArrayBuilder, Int64Builder, TimestampNanosecondBuilder, UInt64Builder,
+};
 use models::{FieldId, ValueType};
 use parking_lot::RwLock;
 use snafu::{ResultExt, Snafu};
@@ -18,9 +21,9 @@ use crate::tsm::codec::{
 };
 use crate::tsm::tombstone::TsmTombstone;
 use crate::tsm::{
-    get_data_block_meta_unchecked, get_index_meta_unchecked, BlockEntry, BlockMeta, DataBlock,
-    Index, IndexEntry, IndexMeta, BLOCK_META_SIZE, BLOOM_FILTER_SIZE, FOOTER_SIZE, INDEX_META_SIZE,
-    MAX_BLOCK_VALUES,
+    block_meta_size, get_data_block_meta_unchecked, get_index_meta_unchecked, BlockEntry,
+    BlockMeta, DataBlock, FieldIndex, Index, IndexEntry, IndexMeta, VnodeBlockCache,
+    BLOOM_FILTER_SIZE, FOOTER_SIZE, HEADER_SIZE, INDEX_META_SIZE, MAX_BLOCK_VALUES,
 };
 
 pub type ReadTsmResult<T, E = ReadTsmError> = std::result::Result<T, E>;
@@ -51,7 +54,8 @@ pub struct IndexFile {
     reader: Arc<AsyncFile>,
     bloom_filter: BloomFilter,
     idx_meta_buf: [u8; INDEX_META_SIZE],
-    blk_meta_buf: [u8; BLOCK_META_SIZE],
+    blk_meta_buf: Vec<u8>,
+    field_type: ValueType,
 
     index_offset: u64,
     pos: u64,
@@ -70,11 +74,13 @@ impl IndexFile {
             .context(IOSnafu)?;
         let bloom_filter = BloomFilter::with_data(&footer[..BLOOM_FILTER_SIZE]);
         let index_offset = decode_be_u64(&footer[BLOOM_FILTER_SIZE..]);
+        let block_meta_size = read_block_meta_size(&reader).await?;
         Ok(Self {
             reader,
             bloom_filter,
             idx_meta_buf: [0_u8; INDEX_META_SIZE],
-            blk_meta_buf: [0_u8; BLOCK_META_SIZE],
+            blk_meta_buf: vec![0_u8; block_meta_size],
+            field_type: ValueType::Unknown,
             index_offset,
             pos: index_offset,
             end_pos: file_len - FOOTER_SIZE as u64,
@@ -94,6 +100,7 @@ impl IndexFile {
             .context(IOSnafu)?;
         self.pos += INDEX_META_SIZE as u64;
         let (entry, blk_count) = IndexEntry::decode(&self.idx_meta_buf);
+        self.field_type = entry.field_type;
         self.index_block_idx = 0;
         self.index_block_count = blk_count as usize;
 
@@ -109,8 +116,9 @@ impl IndexFile {
             .read_at(self.pos, &mut self.blk_meta_buf[..])
             .await
             .context(IOSnafu)?;
-        self.pos += BLOCK_META_SIZE as u64;
-        let entry = BlockEntry::decode(&self.blk_meta_buf);
+        self.pos += self.blk_meta_buf.len() as u64;
+        let entry =
+            BlockEntry::decode(&self.blk_meta_buf, self.field_type, self.blk_meta_buf.len());
         self.index_block_idx += 1;
 
         Ok(Some(entry))
@@ -174,13 +182,27 @@ pub async fn print_tsm_statistics(path: impl AsRef<Path>, show_tombstone: bool)
     println!("PointsCount: {}", points_cnt);
 }
 
+/// Reads the version in header of a TSM file and returns the size of `BlockMeta`.
+async fn read_block_meta_size(reader: &AsyncFile) -> ReadTsmResult<usize> {
+    let mut header = [0_u8; HEADER_SIZE];
+    reader.read_at(0, &mut header).await.context(IOSnafu)?;
+    let version = header[HEADER_SIZE - 1];
+    block_meta_size(version).ok_or_else(|| ReadTsmError::Invalid {
+        reason: format!("unknown TSM file version({})", version),
+    })
+}
+
 pub async fn load_index(tsm_id: u64, reader: Arc<AsyncFile>) -> ReadTsmResult<Index> {
     let len = reader.len();
-    if len < FOOTER_SIZE as u64 {
+    if len < (HEADER_SIZE + FOOTER_SIZE) as u64 {
         return Err(ReadTsmError::Invalid {
-            reason: format!("TSM file size less than FOOTER_SIZE({})", FOOTER_SIZE),
+            reason: format!(
+                "TSM file size less than HEADER_SIZE({}) + FOOTER_SIZE({})",
+                HEADER_SIZE, FOOTER_SIZE
+            ),
         });
     }
+    let block_meta_size = read_block_meta_size(&reader).await?;
     let mut buf = [0u8; FOOTER_SIZE];
 
     // Read index data offset
@@ -202,12 +224,12 @@ pub async fn load_index(tsm_id: u64, reader: Arc<AsyncFile>) -> ReadTsmResult<In
     reader.read_at(offset, &mut data).await.context(IOSnafu)?;
 
     // Decode index data
-    let assumed_field_count = (data_len / (INDEX_META_SIZE + BLOCK_META_SIZE)) + 1;
+    let assumed_field_count = (data_len / (INDEX_META_SIZE + block_meta_size)) + 1;
     let mut field_id_offs: Vec<(FieldId, usize)> = Vec::with_capacity(assumed_field_count);
     let mut pos = 0_usize;
     while pos < data_len {
         field_id_offs.push((decode_be_u64(&data[pos..pos + 8]), pos));
-        pos += INDEX_META_SIZE + BLOCK_META_SIZE * decode_be_u16(&data[pos + 9..pos + 11]) as usize;
+        pos += INDEX_META_SIZE + block_meta_size * decode_be_u16(&data[pos + 9..pos + 11]) as usize;
     }
 
     // Sort by field id
@@ -217,6 +239,7 @@ pub async fn load_index(tsm_id: u64, reader: Arc<AsyncFile>) -> ReadTsmResult<In
     Ok(Index::new(
         tsm_id,
         Arc::new(bloom_filter),
+        block_meta_size,
         data,
         field_id_offs,
     ))
@@ -352,15 +375,16 @@ impl BlockMetaIterator {
             // iterator that always returns none.
             return;
         }
+        let block_meta_size = self.index_ref.block_meta_size();
         let base = self.index_offset + INDEX_META_SIZE;
-        let sli = &self.index_ref.data()[base..base + self.block_count as usize * BLOCK_META_SIZE];
+        let sli = &self.index_ref.data()[base..base + self.block_count as usize * block_meta_size];
         let mut pos = 0_usize;
         let mut idx = 0_usize;
         // Find `idx` of index blocks that time_range.min_ts <= block.max_ts .
         while pos < sli.len() {
             if min_ts > decode_be_i64(&sli[pos + 8..pos + 16]) {
                 // If time_range.min_ts > block.max_ts, go on to check next block.
-                pos += BLOCK_META_SIZE;
+                pos += block_meta_size;
                 idx += 1;
             } else {
                 // If time_range.min_ts <= block.max_ts, This block may be the start block.
@@ -387,7 +411,7 @@ impl BlockMetaIterator {
             } else {
                 // If time_range.max_ts >= block.max_ts, go on to check next block.
                 self.block_meta_idx_end += 1;
-                pos += BLOCK_META_SIZE;
+                pos += block_meta_size;
             }
         }
     }
@@ -408,7 +432,7 @@ impl Iterator for BlockMetaIterator {
             self.field_type,
         ));
         self.block_meta_idx += 1;
-        self.block_offset += BLOCK_META_SIZE;
+        self.block_offset += self.index_ref.block_meta_size();
         ret
     }
 }
@@ -463,13 +487,117 @@ impl TsmReader {
         Ok(blk)
     }
 
-    // Reads raw data from file and returns the read data size.
+    /// Returns a DataBlock without tombstone, the decoded DataBlock is cached in `block_cache`.
+    ///
+    /// The cached block is shared, it's only copied if some of its values are deleted.
+    pub async fn get_cached_data_block(
+        &self,
+        block_meta: &BlockMeta,
+        block_cache: &VnodeBlockCache,
+    ) -> ReadTsmResult<Arc<DataBlock>> {
+        let key = (self.file_id, block_meta.offset());
+        let blk = match block_cache.get(&key).await {
+            Some(blk) => blk,
+            None => {
+                let mut buf = vec![0_u8; block_meta.size() as usize];
+                let blk = read_data_block(
+                    self.reader.clone(),
+                    &mut buf,
+                    block_meta.field_type(),
+                    block_meta.offset(),
+                    block_meta.val_off(),
+                )
+                .await?;
+                let blk = Arc::new(blk);
+                block_cache.insert(key, blk.clone()).await;
+                blk
+            }
+        };
+
+        let tombstone = self.tombstone.read();
+        let time_range = TimeRange::new(block_meta.min_ts(), block_meta.max_ts());
+        if !tombstone.overlaps(block_meta.field_id(), &time_range) {
+            return Ok(blk);
+        }
+        let mut blk = blk.as_ref().clone();
+        tombstone.data_block_exclude_tombstones(block_meta.field_id(), &mut blk);
+        Ok(Arc::new(blk))
+    }
+
+    /// Reads a data block of integers or unsigned integers into the arrow array builders
+    /// without decoding it into a `DataBlock`, returns the number of values.
+    ///
+    /// Returns `None` without reading if the block is cached or has tombstones,
+    /// it should be read by `get_cached_data_block`. The read block is copied into
+    /// `block_cache` if the cache is enabled.
+    pub async fn read_data_block_to_builders(
+        &self,
+        block_meta: &BlockMeta,
+        block_cache: &VnodeBlockCache,
+        ts_builder: &mut TimestampNanosecondBuilder,
+        val_builder: &mut dyn ArrayBuilder,
+    ) -> ReadTsmResult<Option<usize>> {
+        let key = (self.file_id, block_meta.offset());
+        let time_range = TimeRange::new(block_meta.min_ts(), block_meta.max_ts());
+        let has_tombstones = self
+            .tombstone
+            .read()
+            .overlaps(block_meta.field_id(), &time_range);
+        if has_tombstones || block_cache.get(&key).await.is_some() {
+            return Ok(None);
+        }
+
+        let mut buf = vec![0_u8; block_meta.size() as usize];
+        self.reader
+            .read_at(block_meta.offset(), &mut buf)
+            .await
+            .context(IOSnafu)?;
+        let val_off = block_meta.val_off() - block_meta.offset();
+        let ts_start = ts_builder.len();
+        let val_start = val_builder.len();
+        let count = decode_data_block_to_builders(
+            &buf,
+            block_meta.field_type(),
+            val_off,
+            ts_builder,
+            val_builder,
+        )?;
+
+        if block_cache.is_enabled() {
+            let ts = ts_builder.values_slice()[ts_start..].to_vec();
+            let enc = DataBlockEncoding::new(
+                get_encoding(&buf[4..val_off as usize]),
+                get_encoding(&buf[(val_off + 4) as usize..]),
+            );
+            let blk = match block_meta.field_type() {
+                ValueType::Unsigned => DataBlock::U64 {
+                    ts,
+                    val: downcast_builder::<UInt64Builder>(val_builder)?.values_slice()
+                        [val_start..]
+                        .to_vec(),
+                    enc,
+                },
+                _ => DataBlock::I64 {
+                    ts,
+                    val: downcast_builder::<Int64Builder>(val_builder)?.values_slice()[val_start..]
+                        .to_vec(),
+                    enc,
+                },
+            };
+            block_cache.insert(key, Arc::new(blk)).await;
+        }
+
+        Ok(Some(count))
+    }
+
+    // Reads raw data (with the field index) from file and returns the read data size.
     pub async fn get_raw_data(
         &self,
         block_meta: &BlockMeta,
         dst: &mut Vec<u8>,
     ) -> ReadTsmResult<usize> {
-        let data_len = block_meta.size() as usize;
+        let index_len = block_meta.field_index().map(|(_, len)| len).unwrap_or(0);
+        let data_len = block_meta.size() as usize + index_len as usize;
         if dst.len() < data_len {
             dst.resize(data_len, 0);
         }
@@ -480,6 +608,23 @@ impl TsmReader {
         Ok(data_len)
     }
 
+    /// Returns the `FieldIndex` of the block, `None` if the block has no field index.
+    pub async fn get_field_index(
+        &self,
+        block_meta: &BlockMeta,
+    ) -> ReadTsmResult<Option<FieldIndex>> {
+        let (index_type, len) = match block_meta.field_index() {
+            Some(field_index) => field_index,
+            None => return Ok(None),
+        };
+        let mut buf = vec![0_u8; len as usize];
+        self.reader
+            .read_at(block_meta.offset() + block_meta.size(), &mut buf)
+            .await
+            .context(IOSnafu)?;
+        Ok(Some(FieldIndex::decode(index_type, &buf)))
+    }
+
     pub fn has_tombstone(&self) -> bool {
         !self.tombstone.read().is_empty()
     }
@@ -569,6 +714,64 @@ async fn read_data_block(
     decode_data_block(buf, field_type, val_off - offset)
 }
 
+/// Decodes the timestamps and the integers or unsigned integers of a data block
+/// into the arrow array builders, the values are appended without decoding them
+/// into vectors first by codecs with a fast path. Returns the number of values.
+pub fn decode_data_block_to_builders(
+    buf: &[u8],
+    field_type: ValueType,
+    val_off: u64,
+    ts_builder: &mut TimestampNanosecondBuilder,
+    val_builder: &mut dyn ArrayBuilder,
+) -> ReadTsmResult<usize> {
+    if buf.len() < 8 {
+        return Err(ReadTsmError::Decode {
+            source: "buffer too short".into(),
+        });
+    }
+
+    let ts_start = ts_builder.len();
+    let ts_data = &buf[4..val_off as usize];
+    get_ts_codec(get_encoding(ts_data))
+        .decode_to_builder(ts_data, ts_builder)
+        .context(DecodeSnafu)?;
+
+    let val_start = val_builder.len();
+    let data = &buf[(val_off + 4) as usize..];
+    match field_type {
+        ValueType::Integer => get_i64_codec(get_encoding(data))
+            .decode_to_builder(data, downcast_builder::<Int64Builder>(val_builder)?),
+        ValueType::Unsigned => get_u64_codec(get_encoding(data))
+            .decode_to_builder(data, downcast_builder::<UInt64Builder>(val_builder)?),
+        _ => {
+            return Err(ReadTsmError::Decode {
+                source: From::from(format!(
+                    "cannot decode block {:?} into arrow arrays",
+                    field_type
+                )),
+            })
+        }
+    }
+    .context(DecodeSnafu)?;
+
+    let count = ts_builder.len() - ts_start;
+    if val_builder.len() - val_start != count {
+        return Err(ReadTsmError::Decode {
+            source: "mismatched number of timestamps and values".into(),
+        });
+    }
+    Ok(count)
+}
+
+fn downcast_builder<T: ArrayBuilder>(builder: &mut dyn ArrayBuilder) -> ReadTsmResult<&mut T> {
+    builder
+        .as_any_mut()
+        .downcast_mut::<T>()
+        .ok_or_else(|| ReadTsmError::Decode {
+            source: "mismatched array builder".into(),
+        })
+}
+
 pub fn decode_data_block(
     buf: &[u8],
     field_type: ValueType,
@@ -668,6 +871,10 @@ pub mod tsm_reader_tests {
     use std::path::{Path, PathBuf};
     use std::sync::Arc;
 
+    use datafusion::arrow::array::{
+        ArrayBuilder, Int64Builder, TimestampNanosecondBuilder, UInt64Builder,
+    };
+    use models::codec::Encoding;
     use models::{FieldId, Timestamp};
     use snafu::ResultExt;
 
@@ -677,7 +884,10 @@ pub mod tsm_reader_tests {
     use crate::tseries_family::TimeRange;
     use crate::tsm::codec::DataBlockEncoding;
     use crate::tsm::tsm_writer_tests::write_to_tsm;
-    use crate::tsm::{BlockEntry, DataBlock, IndexEntry, IndexFile, TsmReader, TsmTombstone};
+    use crate::tsm::{
+        decode_data_block, decode_data_block_to_builders, BlockEntry, BlockStatistics, DataBlock,
+        IndexEntry, IndexFile, StatValue, TsmReader, TsmTombstone,
+    };
 
     async fn prepare(dir: impl AsRef<Path>) -> Result<(PathBuf, PathBuf)> {
         if file_manager::try_exists(&dir) {
@@ -933,5 +1143,96 @@ pub mod tsm_reader_tests {
         assert_eq!(blk_metas[3].min_ts, 9);
         assert_eq!(blk_metas[3].max_ts, 12);
         assert_eq!(blk_metas[3].count, 4);
+
+        assert_eq!(
+            blk_metas[0].statistics,
+            Some(BlockStatistics {
+                min: StatValue::U64(11),
+                max: StatValue::U64(15),
+                sum: Some(StatValue::U64(51)),
+            })
+        );
+        assert_eq!(
+            blk_metas[3].statistics,
+            Some(BlockStatistics {
+                min: StatValue::U64(109),
+                max: StatValue::U64(112),
+                sum: Some(StatValue::U64(442)),
+            })
+        );
+    }
+
+    #[test]
+    fn test_decode_data_block_encodings() {
+        let ts: Vec<i64> = (0..1000).map(|i| 1_000_000 + i * 10).collect();
+        let val: Vec<i64> = (0..1000).map(|i| i / 100).collect();
+        for (ts_enc, val_enc) in [
+            (Encoding::DeltaOfDelta, Encoding::Rle),
+            (Encoding::Rle, Encoding::Xor),
+            (Encoding::Xor, Encoding::DeltaOfDelta),
+        ] {
+            let encoding = DataBlockEncoding::new(ts_enc, val_enc);
+            let blocks = [
+                DataBlock::I64 {
+                    ts: ts.clone(),
+                    val: val.clone(),
+                    enc: encoding,
+                },
+                DataBlock::U64 {
+                    ts: ts.clone(),
+                    val: val.iter().map(|v| *v as u64).collect(),
+                    enc: encoding,
+                },
+            ];
+            for block in blocks {
+                let (ts_buf, val_buf) = block.encode(0, block.len(), encoding).unwrap();
+                // | crc | timestamps | crc | values |
+                let mut buf = vec![0_u8; 4];
+                buf.extend_from_slice(&ts_buf);
+                let val_off = buf.len() as u64;
+                buf.extend_from_slice(&[0_u8; 4]);
+                buf.extend_from_slice(&val_buf);
+
+                let decoded = decode_data_block(&buf, block.field_type(), val_off).unwrap();
+                assert_eq!(decoded, block);
+                assert_eq!(decoded.encodings(), encoding);
+
+                // Appended to the values already in the builders.
+                let mut ts_builder = TimestampNanosecondBuilder::new();
+                ts_builder.append_value(0);
+                let mut val_builder: Box<dyn ArrayBuilder> = match block {
+                    DataBlock::U64 { .. } => Box::new(UInt64Builder::new()),
+                    _ => Box::new(Int64Builder::new()),
+                };
+                let count = decode_data_block_to_builders(
+                    &buf,
+                    block.field_type(),
+                    val_off,
+                    &mut ts_builder,
+                    val_builder.as_mut(),
+                )
+                .unwrap();
+                assert_eq!(count, block.len());
+                assert_eq!(&ts_builder.values_slice()[1..], ts.as_slice());
+                let val_any = val_builder.as_any();
+                match &block {
+                    DataBlock::U64 { val, .. } => assert_eq!(
+                        val_any
+                            .downcast_ref::<UInt64Builder>()
+                            .unwrap()
+                            .values_slice(),
+                        val.as_slice()
+                    ),
+                    DataBlock::I64 { val, .. } => assert_eq!(
+                        val_any
+                            .downcast_ref::<Int64Builder>()
+                            .unwrap()
+                            .values_slice(),
+                        val.as_slice()
+                    ),
+                    _ => unreachable!(),
+                }
+            }
+        }
     }
 }
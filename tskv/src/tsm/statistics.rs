@@ -0,0 +1,217 @@
+use std::cmp::Ordering;
+
+use models::ValueType;
+
+use crate::byte_utils::{decode_be_f64, decode_be_i64, decode_be_u64};
+use crate::memcache::DataType;
+use crate::tsm::DataBlock;
+
+/// Size of encoded `BlockStatistics`: min, max and sum value.
+pub(crate) const BLOCK_STATISTICS_SIZE: usize = 24;
+
+/// A non-string value of a field, used in statistics.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StatValue {
+    U64(u64),
+    I64(i64),
+    F64(f64),
+    Bool(bool),
+}
+
+impl StatValue {
+    /// Returns the (timestamp, value), `None` for strings.
+    pub fn from_data(data: &DataType) -> Option<(i64, Self)> {
+        match data {
+            DataType::U64(ts, val) => Some((*ts, Self::U64(*val))),
+            DataType::I64(ts, val) => Some((*ts, Self::I64(*val))),
+            DataType::F64(ts, val) => Some((*ts, Self::F64(*val))),
+            DataType::Bool(ts, val) => Some((*ts, Self::Bool(*val))),
+            DataType::Str(_, _) => None,
+        }
+    }
+
+    fn to_be_bytes(self) -> [u8; 8] {
+        match self {
+            Self::U64(val) => val.to_be_bytes(),
+            Self::I64(val) => val.to_be_bytes(),
+            Self::F64(val) => val.to_be_bytes(),
+            Self::Bool(val) => (val as u64).to_be_bytes(),
+        }
+    }
+
+    fn from_be_bytes(value_type: ValueType, bytes: &[u8]) -> Option<Self> {
+        match value_type {
+            ValueType::Unsigned => Some(Self::U64(decode_be_u64(bytes))),
+            ValueType::Integer => Some(Self::I64(decode_be_i64(bytes))),
+            ValueType::Float => Some(Self::F64(decode_be_f64(bytes))),
+            ValueType::Boolean => Some(Self::Bool(decode_be_u64(bytes) != 0)),
+            ValueType::String | ValueType::Unknown => None,
+        }
+    }
+
+    pub fn min(self, other: Self) -> Self {
+        match (self, other) {
+            (Self::F64(a), Self::F64(b)) => Self::F64(a.min(b)),
+            _ => match self.partial_cmp(&other) {
+                Some(Ordering::Greater) => other,
+                _ => self,
+            },
+        }
+    }
+
+    pub fn max(self, other: Self) -> Self {
+        match (self, other) {
+            (Self::F64(a), Self::F64(b)) => Self::F64(a.max(b)),
+            _ => match self.partial_cmp(&other) {
+                Some(Ordering::Less) => other,
+                _ => self,
+            },
+        }
+    }
+
+    /// Returns the number as `f64`, `None` for booleans.
+    pub fn as_f64(self) -> Option<f64> {
+        match self {
+            Self::U64(val) => Some(val as f64),
+            Self::I64(val) => Some(val as f64),
+            Self::F64(val) => Some(val),
+            Self::Bool(_) => None,
+        }
+    }
+
+    /// Adds values of the same number type, integers wrap around on overflow.
+    /// `None` for booleans or values of different types.
+    pub fn wrapping_add(self, other: Self) -> Option<Self> {
+        match (self, other) {
+            (Self::U64(a), Self::U64(b)) => Some(Self::U64(a.wrapping_add(b))),
+            (Self::I64(a), Self::I64(b)) => Some(Self::I64(a.wrapping_add(b))),
+            (Self::F64(a), Self::F64(b)) => Some(Self::F64(a + b)),
+            _ => None,
+        }
+    }
+}
+
+/// Values of different types are not comparable.
+impl PartialOrd for StatValue {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Self::U64(a), Self::U64(b)) => Some(a.cmp(b)),
+            (Self::I64(a), Self::I64(b)) => Some(a.cmp(b)),
+            (Self::F64(a), Self::F64(b)) => a.partial_cmp(b),
+            (Self::Bool(a), Self::Bool(b)) => Some(a.cmp(b)),
+            _ => None,
+        }
+    }
+}
+
+/// Statistics of the values in a data block, stored in `BlockMeta` since TSM version 2.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BlockStatistics {
+    pub min: StatValue,
+    pub max: StatValue,
+    /// `None` for booleans.
+    pub sum: Option<StatValue>,
+}
+
+impl BlockStatistics {
+    /// Returns `None` if the block is empty or of strings.
+    pub fn from_block(block: &DataBlock) -> Option<Self> {
+        let mut values = (0..block.len())
+            .filter_map(|i| block.get(i))
+            .filter_map(|data| StatValue::from_data(&data).map(|(_, val)| val));
+        let first = values.next()?;
+        let sum = match first {
+            StatValue::Bool(_) => None,
+            _ => Some(first),
+        };
+        let mut stats = Self {
+            min: first,
+            max: first,
+            sum,
+        };
+        for val in values {
+            stats.min = stats.min.min(val);
+            stats.max = stats.max.max(val);
+            stats.sum = stats.sum.and_then(|sum| sum.wrapping_add(val));
+        }
+
+        Some(stats)
+    }
+
+    pub(crate) fn encode(&self, buf: &mut [u8]) {
+        assert!(buf.len() >= BLOCK_STATISTICS_SIZE);
+        buf[0..8].copy_from_slice(&self.min.to_be_bytes()[..]);
+        buf[8..16].copy_from_slice(&self.max.to_be_bytes()[..]);
+        buf[16..24].copy_from_slice(&self.sum.map(StatValue::to_be_bytes).unwrap_or_default()[..]);
+    }
+
+    /// Decodes statistics of `value_type`, `None` if the type has no statistics.
+    pub(crate) fn decode(value_type: ValueType, data: &[u8]) -> Option<Self> {
+        assert!(data.len() >= BLOCK_STATISTICS_SIZE);
+        let sum = match value_type {
+            ValueType::Boolean => None,
+            _ => StatValue::from_be_bytes(value_type, &data[16..24]),
+        };
+        Some(Self {
+            min: StatValue::from_be_bytes(value_type, &data[0..8])?,
+            max: StatValue::from_be_bytes(value_type, &data[8..16])?,
+            sum,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use minivec::MiniVec;
+
+    use super::{BlockStatistics, StatValue, BLOCK_STATISTICS_SIZE};
+    use crate::tsm::codec::DataBlockEncoding;
+    use crate::tsm::DataBlock;
+
+    #[test]
+    fn test_block_statistics() {
+        let block = DataBlock::I64 {
+            ts: vec![1, 2, 3, 4],
+            val: vec![3, -1, 7, 2],
+            enc: DataBlockEncoding::default(),
+        };
+        let stats = BlockStatistics::from_block(&block).unwrap();
+        assert_eq!(
+            stats,
+            BlockStatistics {
+                min: StatValue::I64(-1),
+                max: StatValue::I64(7),
+                sum: Some(StatValue::I64(11)),
+            }
+        );
+
+        let mut buf = [0_u8; BLOCK_STATISTICS_SIZE];
+        stats.encode(&mut buf);
+        assert_eq!(
+            BlockStatistics::decode(block.field_type(), &buf),
+            Some(stats)
+        );
+
+        let block = DataBlock::Bool {
+            ts: vec![1, 2],
+            val: vec![true, false],
+            enc: DataBlockEncoding::default(),
+        };
+        let stats = BlockStatistics::from_block(&block).unwrap();
+        assert_eq!(stats.min, StatValue::Bool(false));
+        assert_eq!(stats.max, StatValue::Bool(true));
+        assert_eq!(stats.sum, None);
+        stats.encode(&mut buf);
+        assert_eq!(
+            BlockStatistics::decode(block.field_type(), &buf),
+            Some(stats)
+        );
+
+        let block = DataBlock::Str {
+            ts: vec![1],
+            val: vec![MiniVec::from("a")],
+            enc: DataBlockEncoding::default(),
+        };
+        assert_eq!(BlockStatistics::from_block(&block), None);
+    }
+}
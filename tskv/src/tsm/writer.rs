@@ -1,17 +1,19 @@
 use std::collections::BTreeMap;
 use std::io::IoSlice;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
+use models::codec::FieldIndexType;
 use models::{FieldId, Timestamp};
 use snafu::{ResultExt, Snafu};
 use utils::BloomFilter;
 
 use crate::error::{self, Error, Result};
-use crate::file_system::{file_manager, FileCursor, IFile};
+use crate::file_system::{file_manager, FileCursor, IFile, IoPriority, IoRateLimiter};
 use crate::file_utils;
 use crate::tsm::{
-    BlockEntry, BlockMeta, DataBlock, IndexEntry, BLOCK_META_SIZE, BLOOM_FILTER_BITS,
-    INDEX_META_SIZE,
+    BlockEntry, BlockMeta, BlockStatistics, DataBlock, FieldIndex, IndexEntry, BLOCK_META_SIZE,
+    BLOOM_FILTER_BITS, INDEX_META_SIZE,
 };
 
 // A TSM file is composed for four sections: header, blocks, index and the footer.
@@ -37,6 +39,9 @@ use crate::tsm::{
 // │ 4 bytes │ N bytes │ 4 bytes │ N bytes │
 // └─────────┴─────────┴─────────┴─────────┴
 //
+// Since version 2, a block of strings may be followed by its field index,
+// which is not counted in the size of the block.
+//
 // ┌───────────────────────────────────────────────────────────────────────────────┐
 // │                               Index                                           │
 // ├─────────┬──────┬───────┬─────────┬─────────┬────────┬────────┬────────┬───────┤
@@ -44,6 +49,25 @@ use crate::tsm::{
 // │ 8 bytes │1 byte│2 bytes│ 8 bytes │ 8 bytes │4 bytes │8 bytes │8 bytes │8 bytes│
 // └─────────┴──────┴───────┴─────────┴─────────┴────────┴────────┴────────┴───────┘
 //
+// Since version 2, each block in index is followed by its statistics,
+// which are zero if the flag of statistics is not set (blocks of strings).
+// For blocks of strings with field index, the flag of field index is set
+// and the statistics are replaced by the type and length of the index.
+//
+// ┌────────────────────────────────────┐
+// │          Block Statistics          │
+// ├──────┬─────────┬─────────┬─────────┤
+// │ Flag │   Min   │   Max   │   Sum   │
+// │1 byte│ 8 bytes │ 8 bytes │ 8 bytes │
+// └──────┴─────────┴─────────┴─────────┘
+//
+// ┌──────────────────────────────────────────┐
+// │           Field Index of Block           │
+// ├────────┬────────────┬─────────┬──────────┤
+// │  Flag  │ Index Type │  Length │  Zeros   │
+// │ 1 byte │   1 byte   │ 4 bytes │ 19 bytes │
+// └────────┴────────────┴─────────┴──────────┘
+//
 // ┌─────────────────────────┐
 // │ Footer                  │
 // ├───────────────┬─────────┤
@@ -53,7 +77,7 @@ use crate::tsm::{
 
 const HEADER_LEN: u64 = 5;
 const TSM_MAGIC: [u8; 4] = 0x01346613_u32.to_be_bytes();
-const VERSION: [u8; 1] = [1];
+const VERSION: [u8; 1] = [2];
 
 pub type WriteTsmResult<T, E = WriteTsmError> = std::result::Result<T, E>;
 
@@ -246,10 +270,25 @@ impl TsmWriter {
         self.size
     }
 
+    /// Limits the rate of writes by `limiter` with `priority`.
+    pub fn set_io_limiter(&mut self, limiter: Arc<IoRateLimiter>, priority: IoPriority) {
+        self.writer.set_io_limiter(limiter, priority);
+    }
+
     pub async fn write_block(
         &mut self,
         field_id: FieldId,
         block: &DataBlock,
+    ) -> WriteTsmResult<usize> {
+        self.write_block_with_index(field_id, block, None).await
+    }
+
+    /// Write the block and its `FieldIndex` of `index_type` if the block is of strings.
+    pub async fn write_block_with_index(
+        &mut self,
+        field_id: FieldId,
+        block: &DataBlock,
+        index_type: Option<FieldIndexType>,
     ) -> WriteTsmResult<usize> {
         if self.finished {
             return Err(WriteTsmError::Finished {
@@ -267,6 +306,7 @@ impl TsmWriter {
             &mut self.index_buf,
             field_id,
             block,
+            index_type,
         )
         .await;
         if let Ok(s) = ret {
@@ -397,8 +437,10 @@ async fn write_raw_data_to(
             max_ts: block_meta.max_ts(),
             count: block_meta.count(),
             offset,
-            size: block.len() as u64,
+            size: block_meta.size(),
             val_offset: offset + ts_block_len,
+            statistics: block_meta.statistics(),
+            field_index: block_meta.field_index(),
         },
     );
 
@@ -411,6 +453,7 @@ async fn write_block_to(
     index_buf: &mut IndexBuf,
     field_id: FieldId,
     block: &DataBlock,
+    index_type: Option<FieldIndexType>,
 ) -> WriteTsmResult<usize> {
     if block.is_empty() {
         return Ok(0);
@@ -423,7 +466,7 @@ async fn write_block_to(
         .encode(0, block.len(), block.encodings())
         .context(EncodeSnafu)?;
 
-    let size = writer
+    let mut size = writer
         .write_vec(
             [
                 IoSlice::new(crc32fast::hash(&ts_buf).to_be_bytes().as_slice()),
@@ -435,6 +478,12 @@ async fn write_block_to(
         )
         .await
         .context(IOSnafu)?;
+    let block_size = size;
+
+    let field_index = index_type.and_then(|t| FieldIndex::from_block(t, block));
+    if let Some(field_index) = field_index.as_ref() {
+        size += writer.write(field_index.bytes()).await.context(IOSnafu)?;
+    }
 
     index_buf.insert_block_meta(
         IndexEntry {
@@ -447,8 +496,10 @@ async fn write_block_to(
             max_ts: block.ts()[block.len() - 1],
             count: block.len() as u32,
             offset,
-            size: size as u64,
+            size: block_size as u64,
             val_offset: offset + ts_buf.len() as u64 + 4, // CRC32 is 4 bytes
+            statistics: BlockStatistics::from_block(block),
+            field_index: field_index.map(|i| (i.index_type(), i.bytes().len() as u32)),
         },
     );
 
@@ -4,19 +4,20 @@ use std::sync::Arc;
 use memory_pool::MemoryPoolRef;
 use meta::MetaRef;
 use metrics::metric_register::MetricsRegister;
-use models::schema::{make_owner, split_owner, DatabaseSchema};
+use models::schema::{make_owner, split_owner, DatabaseOptions, DatabaseSchema};
 use snafu::ResultExt;
 use tokio::runtime::Runtime;
 use tokio::sync::mpsc::Sender;
 use tokio::sync::RwLock;
 use utils::BloomFilter;
 
-use crate::compaction::{CompactTask, FlushReq};
+use crate::compaction::{CompactTask, FieldEncodings, FlushReq};
 use crate::context::GlobalSequenceContext;
 use crate::database::Database;
 use crate::error::{MetaSnafu, Result};
 use crate::summary::VersionEdit;
 use crate::tseries_family::{TseriesFamily, Version};
+use crate::tsm::BlockCache;
 use crate::{ColumnFileId, Options, TseriesFamilyId};
 
 #[derive(Debug)]
@@ -26,6 +27,8 @@ pub struct VersionSet {
     dbs: HashMap<String, Arc<RwLock<Database>>>,
     runtime: Arc<Runtime>,
     memory_pool: MemoryPoolRef,
+    /// Decoded data blocks of all vnodes
+    block_cache: Arc<BlockCache>,
     metrics_register: Arc<MetricsRegister>,
 }
 
@@ -37,6 +40,7 @@ impl VersionSet {
         metrics_register: Arc<MetricsRegister>,
     ) -> Self {
         Self {
+            block_cache: Arc::new(BlockCache::new(opt.cache.block_cache_size as usize)),
             opt,
             dbs: HashMap::new(),
             runtime,
@@ -56,6 +60,7 @@ impl VersionSet {
         compact_task_sender: Sender<CompactTask>,
         metrics_register: Arc<MetricsRegister>,
     ) -> Result<Self> {
+        let block_cache = Arc::new(BlockCache::new(opt.cache.block_cache_size as usize));
         let mut dbs = HashMap::new();
         for (_id, ver) in ver_set {
             let owner = ver.database().to_string();
@@ -76,6 +81,7 @@ impl VersionSet {
                     runtime.clone(),
                     meta.clone(),
                     memory_pool.clone(),
+                    block_cache.clone(),
                     metrics_register.clone(),
                 )
                 .await?,
@@ -95,6 +101,7 @@ impl VersionSet {
             opt,
             runtime,
             memory_pool,
+            block_cache,
             metrics_register,
         })
     }
@@ -123,6 +130,7 @@ impl VersionSet {
                     self.runtime.clone(),
                     meta.clone(),
                     memory_pool,
+                    self.block_cache.clone(),
                     sub_register,
                 )
                 .await?,
@@ -167,6 +175,29 @@ impl VersionSet {
         None
     }
 
+    pub async fn get_database_options(&self, owner: &str) -> Option<DatabaseOptions> {
+        match self.dbs.get(owner) {
+            Some(db) => db
+                .read()
+                .await
+                .get_schema()
+                .ok()
+                .map(|schema| schema.config),
+            None => None,
+        }
+    }
+
+    pub async fn get_field_encodings(
+        &self,
+        owner: &str,
+        tf_id: TseriesFamilyId,
+    ) -> Option<FieldEncodings> {
+        match self.dbs.get(owner) {
+            Some(db) => db.read().await.get_field_encodings(tf_id),
+            None => None,
+        }
+    }
+
     pub async fn tsf_num(&self) -> usize {
         let mut size = 0;
         for db in self.dbs.values() {
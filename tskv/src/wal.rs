@@ -2,15 +2,22 @@
 //!
 //! A WAL file is a [`record_file`].
 //!
+//! WAL files of each vnode are stored in `$wal_path/$vnode_id/`, so that they can be
+//! deleted as soon as the vnode is flushed. WAL files shared by all vnodes, which were
+//! written by older versions in `$wal_path/`, are still read in recovery.
+//!
 //! ## Record Data
 //! ```text
-//! +------------+------------+------------+
-//! | 0: 1 byte  | 1: 8 bytes | 9: n bytes |
-//! +------------+------------+------------+
-//! |    type    |  sequence  |    data    |
-//! +------------+------------+------------+
+//! +------------+------------+------------+------------+------------+------------+
+//! | 0: 1 byte  | 1: 8 bytes | 9: 4 bytes | 13: 8 bytes| 21: n bytes| n bytes    |
+//! +------------+------------+------------+------------+------------+------------+
+//! |    type    |  sequence  |  vnode_id  | tenant_len |   tenant   |    data    |
+//! +------------+------------+------------+------------+------------+------------+
 //! ```
 //!
+//! Data of a `Write` entry is a block of points compressed by a string codec,
+//! the first byte of the block is the `Encoding` of it.
+//!
 //! ## Footer
 //! ```text
 //! +------------+---------------+--------------+--------------+
@@ -20,7 +27,8 @@
 //! +------------+---------------+--------------+--------------+
 //! ```
 
-use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::string::String;
 use std::sync::Arc;
@@ -37,8 +45,8 @@ use crate::error::{Error, Result};
 use crate::file_system::file_manager::{self};
 use crate::kv_option::WalOptions;
 use crate::record_file::{self, RecordDataType, RecordDataVersion};
-use crate::tsm::codec::get_str_codec;
-use crate::tsm::DecodeSnafu;
+use crate::tsm::codec::{get_encoding, get_str_codec};
+use crate::tsm::{DecodeSnafu, EncodeSnafu};
 use crate::{engine, file_utils, TseriesFamilyId};
 
 const ENTRY_TYPE_LEN: usize = 1;
@@ -94,6 +102,16 @@ impl WalEntryBlock {
         Self { typ, buf }
     }
 
+    /// Compresses points into the data of a `Write` entry by `compression`,
+    /// which is one of the string encodings.
+    pub fn encode_points(compression: Encoding, points: &[u8]) -> Result<Vec<u8>> {
+        let mut data = Vec::with_capacity(points.len() / 2);
+        get_str_codec(compression)
+            .encode(&[points], &mut data)
+            .context(EncodeSnafu)?;
+        Ok(data)
+    }
+
     pub fn seq(&self) -> u64 {
         decode_be_u64(&self.buf[1..9])
     }
@@ -111,6 +129,18 @@ impl WalEntryBlock {
         let tenant_len = decode_be_u64(&self.buf[13..21]) as usize;
         &self.buf[(ENTRY_HEADER_LEN + tenant_len)..]
     }
+
+    /// Decompresses points from the data of a `Write` entry, by the encoding
+    /// stored in the first byte of data.
+    pub fn decode_points(&self) -> Result<Vec<u8>> {
+        let data = self.data();
+        let mut dst = Vec::new();
+        get_str_codec(get_encoding(data))
+            .decode(data, &mut dst)
+            .context(DecodeSnafu)?;
+        debug_assert_eq!(dst.len(), 1);
+        Ok(dst.pop().map(|p| p.to_vec()).unwrap_or_default())
+    }
 }
 
 fn build_footer(min_sequence: u64, max_sequence: u64) -> [u8; record_file::FILE_FOOTER_LEN] {
@@ -121,14 +151,40 @@ fn build_footer(min_sequence: u64, max_sequence: u64) -> [u8; record_file::FILE_
     footer
 }
 
-/// Reads a wal file and parse footer, returns sequence range
-async fn read_footer(path: impl AsRef<Path>) -> Result<Option<(u64, u64)>> {
-    if file_manager::try_exists(&path) {
-        let reader = WalReader::open(path).await?;
-        Ok(Some((reader.min_sequence, reader.max_sequence)))
-    } else {
-        Ok(None)
+/// Reads a wal file and returns the max sequence in it, reads all entries
+/// if the footer is missing.
+async fn read_max_sequence(path: impl AsRef<Path>) -> Result<u64> {
+    let mut reader = WalReader::open(path).await?;
+    if reader.max_sequence > 0 {
+        return Ok(reader.max_sequence);
     }
+    let mut max_sequence = 0;
+    while let Ok(Some(entry)) = reader.next_wal_entry().await {
+        max_sequence = max_sequence.max(entry.seq());
+    }
+    Ok(max_sequence)
+}
+
+/// Reads max sequence of wal files in the directory, returns map of file id to it.
+async fn read_wal_files(dir: impl AsRef<Path>) -> HashMap<u64, u64> {
+    let dir = dir.as_ref();
+    let mut files = HashMap::new();
+    for f in file_manager::list_file_names(dir) {
+        let file_id = match file_utils::get_wal_file_id(&f) {
+            Ok(id) => id,
+            Err(e) => {
+                warn!("Failed to parse WAL file name for '{}': {:?}", &f, e);
+                continue;
+            }
+        };
+        match read_max_sequence(dir.join(&f)).await {
+            Ok(max_seq) => {
+                files.insert(file_id, max_seq);
+            }
+            Err(e) => warn!("Failed to read WAL file '{}': {:?}", &f, e),
+        }
+    }
+    files
 }
 
 struct WalWriter {
@@ -136,37 +192,23 @@ struct WalWriter {
     inner: record_file::Writer,
     size: u64,
     path: PathBuf,
-    config: Arc<WalOptions>,
 
-    buf: Vec<u8>,
+    /// Min sequence written in the file, 0 if nothing written.
     min_sequence: u64,
+    /// Max sequence written in the file, 0 if nothing written.
     max_sequence: u64,
 }
 
 impl WalWriter {
-    pub async fn open(
-        config: Arc<WalOptions>,
-        id: u64,
-        path: impl AsRef<Path>,
-        min_seq: u64,
-    ) -> Result<Self> {
+    pub async fn open(id: u64, path: impl AsRef<Path>) -> Result<Self> {
         let path = path.as_ref();
 
-        // Use min_sequence existing in file, otherwise in parameter
-        let (writer, min_sequence, max_sequence) = if file_manager::try_exists(path) {
-            let writer = record_file::Writer::open(path, RecordDataType::Wal).await?;
-            let (min_sequence, max_sequence) = match writer.footer() {
-                Some(footer) => WalReader::parse_footer(footer).unwrap_or((min_seq, min_seq)),
-                None => (min_seq, min_seq),
-            };
-            (writer, min_sequence, max_sequence)
-        } else {
-            (
-                record_file::Writer::open(path, RecordDataType::Wal).await?,
-                min_seq,
-                min_seq,
-            )
-        };
+        // Use sequence range in footer if the file exists.
+        let writer = record_file::Writer::open(path, RecordDataType::Wal).await?;
+        let (min_sequence, max_sequence) = writer
+            .footer()
+            .and_then(WalReader::parse_footer)
+            .unwrap_or((0, 0));
 
         let size = writer.file_size();
 
@@ -175,22 +217,20 @@ impl WalWriter {
             inner: writer,
             size,
             path: PathBuf::from(path),
-            config,
-            buf: Vec::new(),
             min_sequence,
             max_sequence,
         })
     }
 
-    /// Writes data, returns data sequence and data size.
+    /// Writes data with the sequence, returns data size.
     pub async fn write(
         &mut self,
         typ: WalEntryType,
+        seq: u64,
         data: Arc<Vec<u8>>,
         id: TseriesFamilyId,
         tenant: Arc<Vec<u8>>,
-    ) -> Result<(u64, usize)> {
-        let seq = self.max_sequence;
+    ) -> Result<usize> {
         let tenant_len = tenant.len() as u64;
 
         let written_size = self
@@ -210,13 +250,12 @@ impl WalWriter {
             )
             .await?;
 
-        if self.config.sync {
-            self.inner.sync().await?;
+        if self.min_sequence == 0 {
+            self.min_sequence = seq;
         }
-        // write & fsync succeed
-        self.max_sequence += 1;
+        self.max_sequence = seq;
         self.size += written_size as u64;
-        Ok((seq, written_size))
+        Ok(written_size)
     }
 
     pub async fn sync(&self) -> Result<()> {
@@ -225,7 +264,7 @@ impl WalWriter {
 
     pub async fn close(mut self) -> Result<()> {
         info!(
-            "Closing wal with sequence: [{}, {})",
+            "Closing wal with sequence: [{}, {}]",
             self.min_sequence, self.max_sequence
         );
         let footer = build_footer(self.min_sequence, self.max_sequence);
@@ -237,9 +276,16 @@ impl WalWriter {
 pub struct WalManager {
     config: Arc<WalOptions>,
     global_seq_ctx: Arc<GlobalSequenceContext>,
-    current_dir: PathBuf,
-    current_file: WalWriter,
-    old_file_max_sequence: HashMap<u64, u64>,
+    /// Sequence of the next write, shared by all vnodes.
+    next_sequence: u64,
+    /// Writing wal file of each vnode.
+    writers: HashMap<TseriesFamilyId, WalWriter>,
+    /// Id of the next wal file of each vnode.
+    next_file_id: HashMap<TseriesFamilyId, u64>,
+    /// Maps vnode id to the file ids and max sequences of it's closed wal files.
+    old_file_max_sequence: HashMap<TseriesFamilyId, HashMap<u64, u64>>,
+    /// Maps file id to max sequence of wal files shared by all vnodes.
+    shared_file_max_sequence: HashMap<u64, u64>,
 }
 
 unsafe impl Send for WalManager {}
@@ -254,109 +300,194 @@ impl WalManager {
         if !file_manager::try_exists(&config.path) {
             std::fs::create_dir_all(&config.path).unwrap();
         }
-        let base_path = config.path.to_path_buf();
-
-        let mut old_file_max_sequence: HashMap<u64, u64> = HashMap::new();
-        let file_names = file_manager::list_file_names(&config.path);
-        for f in file_names {
-            match read_footer(base_path.join(&f)).await {
-                Ok(Some((_, max_seq))) => match file_utils::get_wal_file_id(&f) {
-                    Ok(file_id) => {
-                        old_file_max_sequence.insert(file_id, max_seq);
-                    }
-                    Err(e) => warn!("Failed to parse WAL file name for '{}': {:?}", &f, e),
-                },
-                Ok(None) => warn!("Failed to parse WAL file footer for '{}'", &f),
-                Err(e) => warn!("Failed to parse WAL file footer for '{}': {:?}", &f, e),
+
+        let shared_file_max_sequence = read_wal_files(&config.path).await;
+        let mut old_file_max_sequence = HashMap::new();
+        for dir_name in file_manager::list_dir_names(&config.path) {
+            if let Ok(vnode_id) = dir_name.parse::<TseriesFamilyId>() {
+                let files = read_wal_files(config.path.join(&dir_name)).await;
+                old_file_max_sequence.insert(vnode_id, files);
             }
         }
 
-        // Create a new wal file every time it starts.
-        let (pre_max_seq, next_file_id) =
-            match file_utils::get_max_sequence_file_name(&config.path, file_utils::get_wal_file_id)
-            {
-                Some((_, id)) => {
-                    let path = file_utils::make_wal_file(&config.path, id);
-                    let (_, max_seq) = read_footer(&path).await?.unwrap_or((1_u64, 1_u64));
-                    (max_seq + 1, id + 1)
-                }
-                None => (1_u64, 1_u64),
-            };
+        // New wal files are created for each vnode every time it starts.
+        // Wal files may be all deleted after flushed, sequences must not restart
+        // below the flushed sequence, or the entries would be skipped by recovery.
+        let max_sequence = shared_file_max_sequence
+            .values()
+            .chain(old_file_max_sequence.values().flat_map(|f| f.values()))
+            .copied()
+            .max()
+            .unwrap_or(0)
+            .max(global_seq_ctx.last_seq());
+        info!("WAL starts write at seq {}", max_sequence + 1);
 
-        let new_wal = file_utils::make_wal_file(&config.path, next_file_id);
-        let current_file =
-            WalWriter::open(config.clone(), next_file_id, new_wal, pre_max_seq).await?;
-        info!("WAL '{}' starts write", current_file.id);
-        let current_dir = config.path.clone();
         Ok(WalManager {
             config,
             global_seq_ctx,
-            current_dir,
-            current_file,
+            next_sequence: max_sequence + 1,
+            writers: HashMap::new(),
+            next_file_id: HashMap::new(),
             old_file_max_sequence,
+            shared_file_max_sequence,
         })
     }
 
     pub fn current_seq_no(&self) -> u64 {
-        self.current_file.max_sequence
+        self.next_sequence
     }
 
-    async fn roll_wal_file(&mut self, max_file_size: u64) -> Result<()> {
-        if self.current_file.size > max_file_size {
-            info!(
-                "WAL '{}' is full at seq '{}', begin rolling.",
-                self.current_file.id, self.current_file.max_sequence
-            );
-
-            let new_file_id = self.current_file.id + 1;
-            let new_file_name = file_utils::make_wal_file(&self.config.path, new_file_id);
+    fn vnode_dir(&self, id: TseriesFamilyId) -> PathBuf {
+        self.config.path.join(id.to_string())
+    }
 
-            let new_file = WalWriter::open(
-                self.config.clone(),
-                new_file_id,
-                new_file_name,
-                self.current_file.max_sequence,
-            )
-            .await?;
-            info!(
-                "WAL '{}' starts write at seq {}",
-                self.current_file.id, self.current_file.max_sequence
-            );
-
-            let mut old_file = std::mem::replace(&mut self.current_file, new_file);
-            if old_file.max_sequence <= old_file.min_sequence {
-                old_file.max_sequence = old_file.min_sequence;
-            } else {
-                old_file.max_sequence -= 1;
+    /// Returns the writer of the vnode, opens a new wal file if the vnode
+    /// has no writer or the wal file is full.
+    async fn get_writer(&mut self, id: TseriesFamilyId) -> Result<&mut WalWriter> {
+        let is_full = self
+            .writers
+            .get(&id)
+            .map(|w| w.size > self.config.max_file_size)
+            .unwrap_or(false);
+        if is_full {
+            if let Some(old_file) = self.writers.remove(&id) {
+                info!(
+                    "WAL '{}' of vnode {} is full at seq '{}', begin rolling.",
+                    old_file.id, id, old_file.max_sequence
+                );
+                self.old_file_max_sequence
+                    .entry(id)
+                    .or_default()
+                    .insert(old_file.id, old_file.max_sequence);
+                old_file.close().await?;
+                self.check_to_delete(id).await;
             }
-            self.old_file_max_sequence
-                .insert(old_file.id, old_file.max_sequence);
-            old_file.close().await?;
-
-            self.check_to_delete().await;
         }
-        Ok(())
+
+        let dir = self.vnode_dir(id);
+        let writer = match self.writers.entry(id) {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(e) => {
+                if !file_manager::try_exists(&dir) {
+                    std::fs::create_dir_all(&dir)?;
+                }
+                // File ids are not reused even if the files were deleted.
+                let file_id = match self.next_file_id.get(&id) {
+                    Some(file_id) => *file_id,
+                    None => match file_utils::get_max_sequence_file_name(
+                        &dir,
+                        file_utils::get_wal_file_id,
+                    ) {
+                        Some((_, file_id)) => file_id + 1,
+                        None => 1,
+                    },
+                };
+                self.next_file_id.insert(id, file_id + 1);
+                let writer =
+                    WalWriter::open(file_id, file_utils::make_wal_file(&dir, file_id)).await?;
+                info!("WAL '{}' of vnode {} starts write", file_id, id);
+                e.insert(writer)
+            }
+        };
+        Ok(writer)
     }
 
-    async fn check_to_delete(&mut self) {
-        let min_seq = self.global_seq_ctx.min_seq();
-        let mut old_files_to_delete: Vec<u64> = Vec::new();
-        for (old_file_id, old_file_max_seq) in self.old_file_max_sequence.iter() {
-            if *old_file_max_seq < min_seq {
-                old_files_to_delete.push(*old_file_id);
+    /// Deletes closed wal files of the vnode whose data are all flushed. If the data of
+    /// the writing wal file are all flushed too, it's closed and deleted, the next write
+    /// rolls to a new file, so that wal is truncated without waiting for other vnodes.
+    async fn check_to_delete(&mut self, id: TseriesFamilyId) {
+        let flushed_seq = match self.global_seq_ctx.tsf_seq(id) {
+            Some(seq) => seq,
+            None => return,
+        };
+
+        let writer_flushed = self
+            .writers
+            .get(&id)
+            .map(|w| w.max_sequence > 0 && w.max_sequence < flushed_seq)
+            .unwrap_or(false);
+        if writer_flushed {
+            if let Some(writer) = self.writers.remove(&id) {
+                info!(
+                    "WAL '{}' of vnode {} is flushed at seq '{}', begin rolling.",
+                    writer.id, id, writer.max_sequence
+                );
+                let (file_id, max_sequence) = (writer.id, writer.max_sequence);
+                match writer.close().await {
+                    Ok(_) => {
+                        self.old_file_max_sequence
+                            .entry(id)
+                            .or_default()
+                            .insert(file_id, max_sequence);
+                    }
+                    Err(e) => error!("failed to close wal file '{}': {:?}", file_id, e),
+                }
             }
         }
 
-        if !old_files_to_delete.is_empty() {
-            for file_id in old_files_to_delete {
-                let file_path = file_utils::make_wal_file(&self.config.path, file_id);
+        let dir = self.vnode_dir(id);
+        if let Some(files) = self.old_file_max_sequence.get_mut(&id) {
+            files.retain(|file_id, max_seq| {
+                if *max_seq >= flushed_seq {
+                    return true;
+                }
+                let file_path = file_utils::make_wal_file(&dir, *file_id);
                 debug!("Removing wal file '{}'", file_path.display());
                 if let Err(e) = std::fs::remove_file(&file_path) {
                     error!("failed to remove file '{}': {:?}", file_path.display(), e);
                 }
-                self.old_file_max_sequence.remove(&file_id);
+                false
+            });
+        }
+    }
+
+    /// Deletes wal files shared by all vnodes whose data are flushed by all vnodes.
+    fn check_to_delete_shared(&mut self) {
+        let min_seq = self.global_seq_ctx.min_seq();
+        let path = self.config.path.clone();
+        self.shared_file_max_sequence.retain(|file_id, max_seq| {
+            if *max_seq >= min_seq {
+                return true;
+            }
+            let file_path = file_utils::make_wal_file(&path, *file_id);
+            debug!("Removing wal file '{}'", file_path.display());
+            if let Err(e) = std::fs::remove_file(&file_path) {
+                error!("failed to remove file '{}': {:?}", file_path.display(), e);
             }
+            false
+        });
+    }
+
+    /// Deletes wal files whose data are flushed.
+    pub async fn truncate(&mut self) {
+        let vnode_ids: HashSet<TseriesFamilyId> = self
+            .writers
+            .keys()
+            .chain(self.old_file_max_sequence.keys())
+            .copied()
+            .collect();
+        for id in vnode_ids {
+            self.check_to_delete(id).await;
         }
+        self.check_to_delete_shared();
+    }
+
+    /// Writes data to the wal file of the vnode without sync. Return data sequence and data size.
+    async fn write_entry(
+        &mut self,
+        typ: WalEntryType,
+        data: Arc<Vec<u8>>,
+        id: TseriesFamilyId,
+        tenant: Arc<Vec<u8>>,
+    ) -> Result<(u64, usize)> {
+        let seq = self.next_sequence;
+        let written_size = self
+            .get_writer(id)
+            .await?
+            .write(typ, seq, data, id, tenant)
+            .await?;
+        self.next_sequence += 1;
+        Ok((seq, written_size))
     }
 
     /// Checks if wal file is full then writes data. Return data sequence and data size.
@@ -367,8 +498,63 @@ impl WalManager {
         id: TseriesFamilyId,
         tenant: Arc<Vec<u8>>,
     ) -> Result<(u64, usize)> {
-        self.roll_wal_file(self.config.max_file_size).await?;
-        self.current_file.write(typ, data, id, tenant).await
+        let ret = self.write_entry(typ, data, id, tenant).await?;
+        if self.config.sync {
+            if let Some(writer) = self.writers.get(&id) {
+                writer.sync().await?;
+            }
+        }
+        Ok(ret)
+    }
+
+    /// Writes a group of tasks, then syncs the written wal files once if `sync` is set,
+    /// so that concurrent writes share the cost of fsync. Results of the tasks are sent
+    /// after that.
+    pub async fn write_tasks(&mut self, tasks: Vec<WalTask>) {
+        let mut written_vnodes = HashSet::new();
+        let mut results = Vec::with_capacity(tasks.len());
+        for task in tasks {
+            match task {
+                WalTask::Write {
+                    id,
+                    points,
+                    tenant,
+                    cb,
+                } => {
+                    let ret = self
+                        .write_entry(WalEntryType::Write, points, id, tenant)
+                        .await;
+                    if ret.is_ok() {
+                        written_vnodes.insert(id);
+                    }
+                    results.push((cb, ret));
+                }
+            }
+        }
+
+        let mut sync_error = None;
+        if self.config.sync {
+            for id in written_vnodes {
+                if let Some(writer) = self.writers.get(&id) {
+                    if let Err(e) = writer.sync().await {
+                        error!("Failed to sync WAL of vnode {}: {:?}", id, e);
+                        sync_error = Some(e.to_string());
+                    }
+                }
+            }
+        }
+
+        for (cb, ret) in results {
+            let ret = match &sync_error {
+                Some(reason) => ret.and(Err(Error::CommonError {
+                    reason: reason.clone(),
+                })),
+                None => ret,
+            };
+            if let Err(e) = cb.send(ret) {
+                warn!("send WAL write result failed: {:?}", e);
+            }
+        }
     }
 
     pub async fn recover(
@@ -379,11 +565,23 @@ impl WalManager {
         let min_log_seq = global_context.last_seq();
         warn!("recovering version set from seq '{}'", &min_log_seq);
 
-        let wal_files = file_manager::list_file_names(&self.current_dir);
+        // Wal files shared by all vnodes are older than wal files of each vnode.
+        Self::recover_dir(&self.config.path, engine, min_log_seq).await?;
+        for dir_name in file_manager::list_dir_names(&self.config.path) {
+            if dir_name.parse::<TseriesFamilyId>().is_ok() {
+                let dir = self.config.path.join(dir_name);
+                Self::recover_dir(&dir, engine, min_log_seq).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn recover_dir(dir: &Path, engine: &impl engine::Engine, min_log_seq: u64) -> Result<()> {
+        let wal_files = file_manager::list_file_names(dir);
         // TODO: Parallel get min_sequence at first.
         for file_name in wal_files {
             let _id = file_utils::get_wal_file_id(&file_name)?;
-            let path = self.current_dir.join(file_name);
+            let path = dir.join(file_name);
             if !file_manager::try_exists(&path) {
                 continue;
             }
@@ -406,7 +604,6 @@ impl WalManager {
         min_log_seq: u64,
     ) -> Result<bool> {
         let mut seq_gt_min_seq = false;
-        let decoder = get_str_codec(Encoding::Zstd);
         loop {
             match reader.next_wal_entry().await {
                 Ok(Some(e)) => {
@@ -417,9 +614,7 @@ impl WalManager {
                     seq_gt_min_seq = true;
                     match e.typ {
                         WalEntryType::Write => {
-                            let mut dst = Vec::new();
-                            decoder.decode(e.data(), &mut dst).context(DecodeSnafu)?;
-                            debug_assert_eq!(dst.len(), 1);
+                            let points = e.decode_points()?;
                             let id = e.vnode_id();
                             let tenant =
                                 unsafe { String::from_utf8_unchecked(e.tenant().to_vec()) };
@@ -430,7 +625,7 @@ impl WalManager {
                                     user: None,
                                     password: None,
                                 }),
-                                points: dst[0].to_vec(),
+                                points,
                             };
                             engine.write_from_wal(id, req, seq).await.unwrap();
                         }
@@ -459,11 +654,17 @@ impl WalManager {
     }
 
     pub async fn sync(&self) -> Result<()> {
-        self.current_file.sync().await
+        for writer in self.writers.values() {
+            writer.sync().await?;
+        }
+        Ok(())
     }
 
     pub async fn close(self) -> Result<()> {
-        self.current_file.close().await
+        for (_, writer) in self.writers {
+            writer.close().await?;
+        }
+        Ok(())
     }
 
     pub fn sync_interval(&self) -> std::time::Duration {
@@ -551,17 +752,14 @@ pub async fn print_wal_statistics(path: impl AsRef<Path>) {
     use protos::models as fb_models;
 
     let mut reader = WalReader::open(path).await.unwrap();
-    let decoder = get_str_codec(Encoding::Zstd);
     let mut i = 0_usize;
     loop {
         match reader.next_wal_entry().await {
             Ok(Some(entry)) => {
                 i += 1;
                 println!("============================================================");
-                let ety_data = entry.data();
-                let mut data_buf = Vec::new();
-                decoder.decode(ety_data, &mut data_buf).unwrap();
-                match flatbuffers::root::<fb_models::Points>(&data_buf[0]) {
+                let data_buf = entry.decode_points().unwrap();
+                match flatbuffers::root::<fb_models::Points>(&data_buf) {
                     Ok(points) => {
                         if let Some(db) = points.db() {
                             let database = String::from_utf8(db.bytes().into()).unwrap();
@@ -672,7 +870,7 @@ pub async fn print_wal_statistics(path: impl AsRef<Path>) {
 #[cfg(test)]
 mod test {
     use core::panic;
-    use std::collections::HashMap;
+    use std::collections::{HashMap, HashSet};
     use std::path::Path;
     use std::sync::Arc;
 
@@ -698,9 +896,8 @@ mod test {
     use crate::kv_option::WalOptions;
     use crate::memcache::test::get_one_series_cache_data;
     use crate::memcache::FieldVal;
-    use crate::tsm::codec::get_str_codec;
-    use crate::wal::{WalEntryType, WalManager, WalReader};
-    use crate::{kv_option, Error, Result, TsKv};
+    use crate::wal::{WalEntryBlock, WalEntryType, WalManager, WalReader, WalWriter};
+    use crate::{file_utils, kv_option, Error, Result, TsKv};
 
     fn random_write_data() -> Vec<u8> {
         let mut fbb = flatbuffers::FlatBufferBuilder::new();
@@ -752,7 +949,6 @@ mod test {
             let path = wal_dir.join(wal_file);
 
             let mut reader = WalReader::open(&path).await.unwrap();
-            let decoder = get_str_codec(Encoding::Zstd);
             println!("Reading data from wal file '{}'", path.display());
             loop {
                 match reader.next_wal_entry().await {
@@ -766,10 +962,9 @@ mod test {
                             }
                         };
                         if is_flatbuffers {
-                            let mut data_buf = Vec::new();
-                            decoder.decode(ety_data, &mut data_buf).unwrap();
-                            assert_eq!(data_buf[0].as_slice(), ori_data.as_ref().as_slice());
-                            if let Err(e) = flatbuffers::root::<fb_models::Points>(&data_buf[0]) {
+                            let data_buf = entry.decode_points().unwrap();
+                            assert_eq!(data_buf.as_slice(), ori_data.as_ref().as_slice());
+                            if let Err(e) = flatbuffers::root::<fb_models::Points>(&data_buf) {
                                 panic!(
                                     "unexpected data in wal file, ignored file '{}' because '{}'",
                                     wal_dir.display(),
@@ -821,7 +1016,9 @@ mod test {
         }
         mgr.close().await.unwrap();
 
-        check_wal_files(&dir, data_vec, false).await.unwrap();
+        check_wal_files(Path::new(&dir).join("0"), data_vec, false)
+            .await
+            .unwrap();
     }
 
     #[tokio::test]
@@ -842,14 +1039,14 @@ mod test {
         let min_seq_no = 6;
 
         let gcs = GlobalSequenceContext::empty();
-        gcs.set_min_seq(min_seq_no);
+        gcs.next_stage(HashSet::new(), HashMap::from([(0, min_seq_no)]));
 
         let mut mgr = WalManager::open(Arc::new(wal_config), gcs).await.unwrap();
         let mut data_vec: Vec<Arc<Vec<u8>>> = Vec::new();
         for seq in 1..=10 {
             let data = Arc::new(format!("{}", seq).as_bytes().to_vec());
             if seq >= min_seq_no {
-                // Data in file_id taat less than version_set_min_seq_no will be deleted.
+                // Data in files of vnode 0 that less than it's flushed seq will be deleted.
                 data_vec.push(data.clone());
             }
 
@@ -861,7 +1058,9 @@ mod test {
         }
         mgr.close().await.unwrap();
 
-        check_wal_files(dir, data_vec, false).await.unwrap();
+        check_wal_files(Path::new(&dir).join("0"), data_vec, false)
+            .await
+            .unwrap();
     }
 
     #[tokio::test]
@@ -876,18 +1075,13 @@ mod test {
         let mut mgr = WalManager::open(Arc::new(wal_config), GlobalSequenceContext::empty())
             .await
             .unwrap();
-        let coder = get_str_codec(Encoding::Zstd);
         let mut data_vec: Vec<Arc<Vec<u8>>> = Vec::new();
 
         for _i in 0..10 {
             let data = Arc::new(random_write_data());
             data_vec.push(data.clone());
 
-            let mut enc_points = Vec::new();
-            coder
-                .encode(&[&data], &mut enc_points)
-                .map_err(|_| Error::Send)
-                .unwrap();
+            let enc_points = WalEntryBlock::encode_points(Encoding::Zstd, &data).unwrap();
             mgr.write(
                 WalEntryType::Write,
                 Arc::new(enc_points),
@@ -899,7 +1093,125 @@ mod test {
         }
         // Do not close wal manager, so footer won't write.
 
-        check_wal_files(dir, data_vec, true).await.unwrap();
+        check_wal_files(Path::new(&dir).join("0"), data_vec, true)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_read_shared_and_compressed() {
+        let dir = "/tmp/test/wal/5".to_string();
+        let _ = std::fs::remove_dir_all(dir.clone()); // Ignore errors
+        let mut global_config = get_config("../config/config.toml");
+        global_config.wal.path = dir.clone();
+        global_config.wal.compression = "snappy".to_string();
+        let wal_config = WalOptions::from(&global_config);
+        assert_eq!(wal_config.compression, Encoding::Snappy);
+
+        // A wal file shared by all vnodes, written by older versions.
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut shared_data: Vec<Arc<Vec<u8>>> = Vec::new();
+        let mut writer = WalWriter::open(1, file_utils::make_wal_file(&dir, 1))
+            .await
+            .unwrap();
+        for seq in 1..=5 {
+            let data = Arc::new(random_write_data());
+            shared_data.push(data.clone());
+            let enc_points = WalEntryBlock::encode_points(Encoding::Zstd, &data).unwrap();
+            writer
+                .write(
+                    WalEntryType::Write,
+                    seq,
+                    Arc::new(enc_points),
+                    seq as u32,
+                    Arc::new(b"cnosdb".to_vec()),
+                )
+                .await
+                .unwrap();
+        }
+        writer.close().await.unwrap();
+
+        let gcs = GlobalSequenceContext::empty();
+        let mut mgr = WalManager::open(Arc::new(wal_config.clone()), gcs.clone())
+            .await
+            .unwrap();
+        assert_eq!(mgr.current_seq_no(), 6);
+        let mut data_vec: Vec<Arc<Vec<u8>>> = Vec::new();
+        for _ in 0..5 {
+            let data = Arc::new(random_write_data());
+            data_vec.push(data.clone());
+            let enc_points = WalEntryBlock::encode_points(wal_config.compression, &data).unwrap();
+            mgr.write(
+                WalEntryType::Write,
+                Arc::new(enc_points),
+                0,
+                Arc::new(b"cnosdb".to_vec()),
+            )
+            .await
+            .unwrap();
+        }
+        mgr.sync().await.unwrap();
+        check_wal_files(&dir, shared_data, true).await.unwrap();
+        check_wal_files(Path::new(&dir).join("0"), data_vec, true)
+            .await
+            .unwrap();
+
+        // Shared wal files are deleted after all vnodes flushed,
+        // wal files of a vnode are deleted after the vnode flushed.
+        gcs.set_min_seq(6);
+        mgr.truncate().await;
+        assert!(list_file_names(&dir).is_empty());
+        assert_eq!(list_file_names(Path::new(&dir).join("0")).len(), 1);
+        gcs.next_stage(HashSet::new(), HashMap::from([(0, 11)]));
+        mgr.truncate().await;
+        assert!(list_file_names(Path::new(&dir).join("0")).is_empty());
+        mgr.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_restart_after_truncate() {
+        let dir = "/tmp/test/wal/6".to_string();
+        let _ = std::fs::remove_dir_all(dir.clone()); // Ignore errors
+        let mut global_config = get_config("../config/config.toml");
+        global_config.wal.path = dir.clone();
+        let wal_config = Arc::new(WalOptions::from(&global_config));
+        let tenant = Arc::new(b"cnosdb".to_vec());
+
+        let gcs = GlobalSequenceContext::empty();
+        let mut mgr = WalManager::open(wal_config.clone(), gcs.clone())
+            .await
+            .unwrap();
+        for seq in 1..=5 {
+            let data = Arc::new(format!("{}", seq).as_bytes().to_vec());
+            let (write_seq, _) = mgr
+                .write(WalEntryType::Write, data, 0, tenant.clone())
+                .await
+                .unwrap();
+            assert_eq!(seq, write_seq);
+        }
+        // All data of vnode 0 are flushed, the writing file is rolled and deleted.
+        gcs.next_stage(HashSet::new(), HashMap::from([(0, 6)]));
+        mgr.truncate().await;
+        assert!(list_file_names(Path::new(&dir).join("0")).is_empty());
+        mgr.close().await.unwrap();
+
+        // Sequences go on after the flushed sequence though no wal file is left.
+        let gcs = Arc::new(GlobalSequenceContext::new(6, HashMap::from([(0, 6)])));
+        let mut mgr = WalManager::open(wal_config, gcs).await.unwrap();
+        assert_eq!(mgr.current_seq_no(), 7);
+        let data = Arc::new(b"7".to_vec());
+        let (seq, _) = mgr
+            .write(WalEntryType::Write, data.clone(), 0, tenant)
+            .await
+            .unwrap();
+        assert_eq!(seq, 7);
+        mgr.close().await.unwrap();
+
+        check_wal_files(Path::new(&dir).join("0"), vec![data], false)
+            .await
+            .unwrap();
     }
 
     #[test]
@@ -919,7 +1231,6 @@ mod test {
             let mut mgr = WalManager::open(Arc::new(wal_config), GlobalSequenceContext::empty())
                 .await
                 .unwrap();
-            let coder = get_str_codec(Encoding::Zstd);
             let mut data_vec: Vec<Arc<Vec<u8>>> = Vec::new();
 
             for i in 1..=10 {
@@ -934,11 +1245,7 @@ mod test {
                         .extend(values.into_iter());
                 }
 
-                let mut enc_points = Vec::new();
-                coder
-                    .encode(&[&data], &mut enc_points)
-                    .map_err(|_| Error::Send)
-                    .unwrap();
+                let enc_points = WalEntryBlock::encode_points(Encoding::Zstd, &data).unwrap();
                 mgr.write(
                     WalEntryType::Write,
                     Arc::new(enc_points),
@@ -949,7 +1256,9 @@ mod test {
                 .expect("write succeed");
             }
             mgr.close().await.unwrap();
-            check_wal_files(dir, data_vec, true).await.unwrap();
+            check_wal_files(Path::new(dir).join("10"), data_vec, true)
+                .await
+                .unwrap();
         });
         let rt_2 = rt.clone();
         rt.block_on(async {